@@ -0,0 +1,12 @@
+// `sqlx::migrate!` embeds the migration files at compile time; rebuild when they change.
+// The gRPC service is generated from `proto/` with a bundled `protoc`, so building needs
+// no system packages.
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    println!("cargo:rerun-if-changed=migrations");
+    std::env::set_var("PROTOC", protoc_bin_vendored::protoc_bin_path()?);
+    tonic_prost_build::configure()
+        .build_client(false)
+        .boxed(".uran.agent.v1.RunUpdate.event.status_changed")
+        .compile_protos(&["proto/agent.proto"], &["proto"])?;
+    Ok(())
+}
@@ -0,0 +1,7 @@
+BEGIN;
+
+DROP INDEX IF EXISTS idx_test_suites_parent_id;
+ALTER TABLE test_suites DROP CONSTRAINT IF EXISTS chk_test_suites_parent_not_self;
+ALTER TABLE test_suites DROP COLUMN IF EXISTS parent_id;
+
+COMMIT;
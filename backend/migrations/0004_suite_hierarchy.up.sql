@@ -0,0 +1,16 @@
+BEGIN;
+
+ALTER TABLE test_suites
+  ADD COLUMN IF NOT EXISTS parent_id UUID REFERENCES test_suites(id) ON DELETE CASCADE;
+
+DO $$
+BEGIN
+  IF NOT EXISTS (SELECT 1 FROM pg_constraint WHERE conname = 'chk_test_suites_parent_not_self') THEN
+    ALTER TABLE test_suites
+      ADD CONSTRAINT chk_test_suites_parent_not_self CHECK (parent_id IS NULL OR parent_id <> id);
+  END IF;
+END$$;
+
+CREATE INDEX IF NOT EXISTS idx_test_suites_parent_id ON test_suites(parent_id);
+
+COMMIT;
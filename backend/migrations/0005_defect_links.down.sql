@@ -0,0 +1,7 @@
+BEGIN;
+
+DROP TRIGGER IF EXISTS trg_project_issue_trackers_set_updated_at ON project_issue_trackers;
+DROP TABLE IF EXISTS defects;
+DROP TABLE IF EXISTS project_issue_trackers;
+
+COMMIT;
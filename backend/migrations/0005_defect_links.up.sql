@@ -0,0 +1,29 @@
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS project_issue_trackers (
+  project_id UUID PRIMARY KEY REFERENCES projects(id) ON DELETE CASCADE,
+  tracker_type TEXT NOT NULL CHECK (tracker_type IN ('jira', 'github', 'gitlab')),
+  base_url TEXT NOT NULL CHECK (base_url ~ '^https?://'),
+  updated_by_user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS defects (
+  id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  run_result_id UUID NOT NULL REFERENCES run_results(id) ON DELETE CASCADE,
+  issue_key TEXT NOT NULL CHECK (length(trim(issue_key)) BETWEEN 1 AND 200),
+  url TEXT,
+  created_by_user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  UNIQUE (run_result_id, issue_key)
+);
+
+CREATE INDEX IF NOT EXISTS idx_defects_issue_key ON defects(issue_key);
+
+DROP TRIGGER IF EXISTS trg_project_issue_trackers_set_updated_at ON project_issue_trackers;
+CREATE TRIGGER trg_project_issue_trackers_set_updated_at
+BEFORE UPDATE ON project_issue_trackers
+FOR EACH ROW EXECUTE FUNCTION set_updated_at();
+
+COMMIT;
@@ -0,0 +1,13 @@
+BEGIN;
+
+DROP INDEX IF EXISTS idx_run_results_search_vector;
+DROP INDEX IF EXISTS idx_runs_search_vector;
+DROP INDEX IF EXISTS idx_testcase_versions_search_vector;
+DROP INDEX IF EXISTS idx_testcases_search_vector;
+
+ALTER TABLE run_results DROP COLUMN IF EXISTS search_vector;
+ALTER TABLE runs DROP COLUMN IF EXISTS search_vector;
+ALTER TABLE testcase_versions DROP COLUMN IF EXISTS search_vector;
+ALTER TABLE testcases DROP COLUMN IF EXISTS search_vector;
+
+COMMIT;
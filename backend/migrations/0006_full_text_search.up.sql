@@ -0,0 +1,29 @@
+BEGIN;
+
+-- 'simple' config: content mixes Russian and English, stemming for one language would hurt the other.
+ALTER TABLE testcases
+  ADD COLUMN IF NOT EXISTS search_vector TSVECTOR
+  GENERATED ALWAYS AS (to_tsvector('simple', key || ' ' || title)) STORED;
+
+ALTER TABLE testcase_versions
+  ADD COLUMN IF NOT EXISTS search_vector TSVECTOR
+  GENERATED ALWAYS AS (
+    to_tsvector('simple', summary || ' ' || preconditions)
+    || jsonb_to_tsvector('simple', steps_json, '["string"]')
+    || jsonb_to_tsvector('simple', expected_json, '["string"]')
+  ) STORED;
+
+ALTER TABLE runs
+  ADD COLUMN IF NOT EXISTS search_vector TSVECTOR
+  GENERATED ALWAYS AS (to_tsvector('simple', title || ' ' || fail_summary)) STORED;
+
+ALTER TABLE run_results
+  ADD COLUMN IF NOT EXISTS search_vector TSVECTOR
+  GENERATED ALWAYS AS (to_tsvector('simple', comment)) STORED;
+
+CREATE INDEX IF NOT EXISTS idx_testcases_search_vector ON testcases USING GIN (search_vector);
+CREATE INDEX IF NOT EXISTS idx_testcase_versions_search_vector ON testcase_versions USING GIN (search_vector);
+CREATE INDEX IF NOT EXISTS idx_runs_search_vector ON runs USING GIN (search_vector);
+CREATE INDEX IF NOT EXISTS idx_run_results_search_vector ON run_results USING GIN (search_vector);
+
+COMMIT;
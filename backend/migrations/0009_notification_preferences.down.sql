@@ -0,0 +1,6 @@
+BEGIN;
+
+DROP TRIGGER IF EXISTS trg_notification_preferences_set_updated_at ON notification_preferences;
+DROP TABLE IF EXISTS notification_preferences;
+
+COMMIT;
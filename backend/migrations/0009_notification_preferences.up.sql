@@ -0,0 +1,19 @@
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS notification_preferences (
+  user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+  member_added BOOLEAN NOT NULL DEFAULT TRUE,
+  run_assigned BOOLEAN NOT NULL DEFAULT TRUE,
+  run_done BOOLEAN NOT NULL DEFAULT TRUE,
+  required_failed BOOLEAN NOT NULL DEFAULT TRUE,
+  unsubscribe_token UUID NOT NULL UNIQUE DEFAULT gen_random_uuid(),
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+DROP TRIGGER IF EXISTS trg_notification_preferences_set_updated_at ON notification_preferences;
+CREATE TRIGGER trg_notification_preferences_set_updated_at
+BEFORE UPDATE ON notification_preferences
+FOR EACH ROW EXECUTE FUNCTION set_updated_at();
+
+COMMIT;
@@ -0,0 +1,16 @@
+BEGIN;
+
+DROP TRIGGER IF EXISTS trg_project_fail_reasons_set_updated_at ON project_fail_reasons;
+DROP TABLE IF EXISTS project_fail_reasons;
+
+UPDATE run_results
+SET fail_reason_code = NULL
+WHERE fail_reason_code IS NOT NULL
+  AND fail_reason_code NOT IN (SELECT code FROM fail_reasons);
+
+ALTER TABLE run_results DROP CONSTRAINT IF EXISTS run_results_fail_reason_code_fkey;
+ALTER TABLE run_results
+  ADD CONSTRAINT run_results_fail_reason_code_fkey
+  FOREIGN KEY (fail_reason_code) REFERENCES fail_reasons(code) ON DELETE SET NULL;
+
+COMMIT;
@@ -0,0 +1,24 @@
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS project_fail_reasons (
+  id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  project_id UUID NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+  code TEXT NOT NULL CHECK (code ~ '^[a-z0-9_]{2,64}$'),
+  title TEXT NOT NULL CHECK (length(trim(title)) BETWEEN 1 AND 200),
+  description TEXT NOT NULL DEFAULT '',
+  color TEXT NOT NULL DEFAULT '#9e9e9e' CHECK (color ~ '^#[0-9a-f]{6}$'),
+  is_active BOOLEAN NOT NULL DEFAULT TRUE,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  UNIQUE (project_id, code)
+);
+
+-- Codes may now come from a project dictionary, so the API validates them instead of the FK.
+ALTER TABLE run_results DROP CONSTRAINT IF EXISTS run_results_fail_reason_code_fkey;
+
+DROP TRIGGER IF EXISTS trg_project_fail_reasons_set_updated_at ON project_fail_reasons;
+CREATE TRIGGER trg_project_fail_reasons_set_updated_at
+BEFORE UPDATE ON project_fail_reasons
+FOR EACH ROW EXECUTE FUNCTION set_updated_at();
+
+COMMIT;
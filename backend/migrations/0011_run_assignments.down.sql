@@ -0,0 +1,8 @@
+BEGIN;
+
+DROP INDEX IF EXISTS idx_runs_default_assignee;
+DROP INDEX IF EXISTS idx_run_items_assignee;
+ALTER TABLE run_items DROP COLUMN IF EXISTS assignee_user_id;
+ALTER TABLE runs DROP COLUMN IF EXISTS default_assignee_user_id;
+
+COMMIT;
@@ -0,0 +1,15 @@
+BEGIN;
+
+ALTER TABLE runs
+  ADD COLUMN IF NOT EXISTS default_assignee_user_id UUID REFERENCES users(id) ON DELETE SET NULL;
+
+-- NULL means the item follows runs.default_assignee_user_id.
+ALTER TABLE run_items
+  ADD COLUMN IF NOT EXISTS assignee_user_id UUID REFERENCES users(id) ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS idx_run_items_assignee
+  ON run_items(assignee_user_id) WHERE assignee_user_id IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_runs_default_assignee
+  ON runs(default_assignee_user_id) WHERE default_assignee_user_id IS NOT NULL;
+
+COMMIT;
@@ -0,0 +1,7 @@
+BEGIN;
+
+DROP TRIGGER IF EXISTS trg_requirements_set_updated_at ON requirements;
+DROP TABLE IF EXISTS requirement_testcases;
+DROP TABLE IF EXISTS requirements;
+
+COMMIT;
@@ -0,0 +1,31 @@
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS requirements (
+  id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  project_id UUID NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+  key TEXT NOT NULL CHECK (length(trim(key)) BETWEEN 1 AND 64),
+  title TEXT NOT NULL CHECK (length(trim(title)) BETWEEN 2 AND 240),
+  description TEXT NOT NULL DEFAULT '',
+  created_by_user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+  updated_by_user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  UNIQUE (project_id, key)
+);
+
+CREATE TABLE IF NOT EXISTS requirement_testcases (
+  requirement_id UUID NOT NULL REFERENCES requirements(id) ON DELETE CASCADE,
+  testcase_id UUID NOT NULL REFERENCES testcases(id) ON DELETE CASCADE,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  PRIMARY KEY (requirement_id, testcase_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_requirement_testcases_testcase_id
+  ON requirement_testcases(testcase_id);
+
+DROP TRIGGER IF EXISTS trg_requirements_set_updated_at ON requirements;
+CREATE TRIGGER trg_requirements_set_updated_at
+BEFORE UPDATE ON requirements
+FOR EACH ROW EXECUTE FUNCTION set_updated_at();
+
+COMMIT;
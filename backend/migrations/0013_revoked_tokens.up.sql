@@ -0,0 +1,15 @@
+BEGIN;
+
+-- JWTs revoked before expiry (logout, refresh rotation). Rows are useless once
+-- expires_at has passed and are pruned by the API.
+CREATE TABLE IF NOT EXISTS revoked_tokens (
+  jti UUID PRIMARY KEY,
+  user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+  token_kind TEXT NOT NULL CHECK (token_kind IN ('access', 'refresh')),
+  expires_at TIMESTAMPTZ NOT NULL,
+  revoked_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_revoked_tokens_expires_at ON revoked_tokens(expires_at);
+
+COMMIT;
@@ -0,0 +1,7 @@
+BEGIN;
+
+DROP TRIGGER IF EXISTS trg_run_results_history ON run_results;
+DROP FUNCTION IF EXISTS record_run_result_history();
+DROP TABLE IF EXISTS run_result_history;
+
+COMMIT;
@@ -0,0 +1,70 @@
+BEGIN;
+
+-- Every change of a run item result. run_results keeps only the current state and is
+-- upserted from several places, so the history is filled by a trigger.
+CREATE TABLE IF NOT EXISTS run_result_history (
+  id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  run_item_id UUID NOT NULL REFERENCES run_items(id) ON DELETE CASCADE,
+  status result_status NOT NULL,
+  previous_status result_status,
+  fail_reason_code TEXT,
+  comment TEXT NOT NULL DEFAULT '',
+  measured_value TEXT,
+  changed_by_user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+  changed_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_run_result_history_item
+  ON run_result_history(run_item_id, changed_at);
+
+-- Existing results become the first entry of their history.
+INSERT INTO run_result_history (
+  run_item_id, status, fail_reason_code, comment, measured_value, changed_by_user_id, changed_at
+)
+SELECT rr.run_item_id, rr.status, rr.fail_reason_code, rr.comment, rr.measured_value,
+       rr.updated_by_user_id, rr.updated_at
+FROM run_results rr
+WHERE NOT (rr.status = 'na' AND rr.comment = '')
+  AND NOT EXISTS (SELECT 1 FROM run_result_history h WHERE h.run_item_id = rr.run_item_id);
+
+CREATE OR REPLACE FUNCTION record_run_result_history()
+RETURNS TRIGGER
+LANGUAGE plpgsql
+AS $$
+BEGIN
+  -- Placeholder rows created together with run items are not a result yet.
+  IF TG_OP = 'INSERT' AND NEW.status = 'na' AND NEW.comment = '' THEN
+    RETURN NEW;
+  END IF;
+  IF TG_OP = 'UPDATE'
+     AND NEW.status IS NOT DISTINCT FROM OLD.status
+     AND NEW.fail_reason_code IS NOT DISTINCT FROM OLD.fail_reason_code
+     AND NEW.comment IS NOT DISTINCT FROM OLD.comment
+     AND NEW.measured_value IS NOT DISTINCT FROM OLD.measured_value THEN
+    RETURN NEW;
+  END IF;
+
+  INSERT INTO run_result_history (
+    run_item_id, status, previous_status, fail_reason_code, comment, measured_value,
+    changed_by_user_id, changed_at
+  )
+  VALUES (
+    NEW.run_item_id,
+    NEW.status,
+    CASE WHEN TG_OP = 'UPDATE' THEN OLD.status END,
+    NEW.fail_reason_code,
+    NEW.comment,
+    NEW.measured_value,
+    NEW.updated_by_user_id,
+    NEW.updated_at
+  );
+  RETURN NEW;
+END;
+$$;
+
+DROP TRIGGER IF EXISTS trg_run_results_history ON run_results;
+CREATE TRIGGER trg_run_results_history
+AFTER INSERT OR UPDATE ON run_results
+FOR EACH ROW EXECUTE FUNCTION record_run_result_history();
+
+COMMIT;
@@ -0,0 +1,7 @@
+BEGIN;
+
+ALTER TABLE notification_preferences DROP COLUMN IF EXISTS mentioned;
+DROP TRIGGER IF EXISTS trg_comments_set_updated_at ON comments;
+DROP TABLE IF EXISTS comments;
+
+COMMIT;
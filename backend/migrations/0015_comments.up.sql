@@ -0,0 +1,29 @@
+BEGIN;
+
+-- Discussion threads on runs (run_item_id IS NULL) and on individual run items.
+-- Deleted comments keep their row so replies stay attached to the thread.
+CREATE TABLE IF NOT EXISTS comments (
+  id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  run_id UUID NOT NULL REFERENCES runs(id) ON DELETE CASCADE,
+  run_item_id UUID REFERENCES run_items(id) ON DELETE CASCADE,
+  parent_id UUID REFERENCES comments(id) ON DELETE CASCADE,
+  author_user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+  body TEXT NOT NULL DEFAULT '',
+  mentioned_user_ids UUID[] NOT NULL DEFAULT '{}',
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  deleted_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_comments_run_id ON comments(run_id, created_at);
+CREATE INDEX IF NOT EXISTS idx_comments_run_item_id ON comments(run_item_id);
+
+DROP TRIGGER IF EXISTS trg_comments_set_updated_at ON comments;
+CREATE TRIGGER trg_comments_set_updated_at
+BEFORE UPDATE ON comments
+FOR EACH ROW EXECUTE FUNCTION set_updated_at();
+
+ALTER TABLE notification_preferences
+  ADD COLUMN IF NOT EXISTS mentioned BOOLEAN NOT NULL DEFAULT TRUE;
+
+COMMIT;
@@ -0,0 +1,5 @@
+BEGIN;
+
+ALTER TABLE run_results DROP COLUMN IF EXISTS version;
+
+COMMIT;
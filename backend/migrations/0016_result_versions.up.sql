@@ -0,0 +1,7 @@
+BEGIN;
+
+-- Optimistic concurrency for run results: every write bumps the version, clients send it
+-- back in If-Match.
+ALTER TABLE run_results ADD COLUMN IF NOT EXISTS version INTEGER NOT NULL DEFAULT 1;
+
+COMMIT;
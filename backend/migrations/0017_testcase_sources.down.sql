@@ -0,0 +1,7 @@
+BEGIN;
+
+DROP INDEX IF EXISTS idx_testcases_source_path;
+ALTER TABLE testcases DROP COLUMN IF EXISTS source_name;
+ALTER TABLE testcases DROP COLUMN IF EXISTS source_path;
+
+COMMIT;
@@ -0,0 +1,11 @@
+BEGIN;
+
+-- Where an imported testcase came from (e.g. a Gherkin feature file and the scenario name),
+-- so importing the same file again updates the testcase instead of creating a duplicate.
+ALTER TABLE testcases ADD COLUMN IF NOT EXISTS source_path TEXT;
+ALTER TABLE testcases ADD COLUMN IF NOT EXISTS source_name TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_testcases_source_path ON testcases(source_path)
+  WHERE source_path IS NOT NULL;
+
+COMMIT;
@@ -0,0 +1,9 @@
+BEGIN;
+
+DROP TRIGGER IF EXISTS trg_testcase_versions_steps ON testcase_versions;
+DROP FUNCTION IF EXISTS derive_testcase_steps();
+DROP FUNCTION IF EXISTS fill_testcase_steps(UUID, JSONB, JSONB);
+DROP TABLE IF EXISTS run_result_steps;
+DROP TABLE IF EXISTS testcase_steps;
+
+COMMIT;
@@ -0,0 +1,102 @@
+BEGIN;
+
+-- Ordered steps of a testcase version, each an action with its expected result. Versions
+-- are written from several places (import, bundles, the versions API) as steps_json and
+-- expected_json, so the rows are derived from them by a trigger.
+CREATE TABLE IF NOT EXISTS testcase_steps (
+  id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  testcase_version_id UUID NOT NULL REFERENCES testcase_versions(id) ON DELETE CASCADE,
+  position INTEGER NOT NULL CHECK (position > 0),
+  action TEXT NOT NULL DEFAULT '',
+  expected_result TEXT NOT NULL DEFAULT '',
+  UNIQUE (testcase_version_id, position)
+);
+
+-- Per-step outcome of a run item; the item result in run_results stays the overall verdict.
+CREATE TABLE IF NOT EXISTS run_result_steps (
+  run_item_id UUID NOT NULL REFERENCES run_items(id) ON DELETE CASCADE,
+  step_id UUID NOT NULL REFERENCES testcase_steps(id) ON DELETE CASCADE,
+  status result_status NOT NULL,
+  comment TEXT NOT NULL DEFAULT '',
+  updated_by_user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+  updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  PRIMARY KEY (run_item_id, step_id)
+);
+
+CREATE OR REPLACE FUNCTION fill_testcase_steps(version_id UUID, steps JSONB, expected JSONB)
+RETURNS VOID
+LANGUAGE plpgsql
+AS $$
+DECLARE
+  step JSONB;
+  pos INTEGER := 0;
+BEGIN
+  IF jsonb_typeof(steps) <> 'array' THEN
+    RETURN;
+  END IF;
+
+  -- Gherkin scenarios: a Then step is the expected result of the step before it.
+  IF EXISTS (
+    SELECT 1 FROM jsonb_array_elements(steps) AS e(v)
+    WHERE jsonb_typeof(e.v) = 'object' AND e.v ? 'kind'
+  ) THEN
+    FOR step IN SELECT e.v FROM jsonb_array_elements(steps) AS e(v) LOOP
+      CONTINUE WHEN step->>'kind' = 'examples';
+      IF step->>'kind' = 'then' AND pos > 0 THEN
+        UPDATE testcase_steps
+        SET expected_result = concat_ws(E'\n', NULLIF(expected_result, ''), step->>'text')
+        WHERE testcase_version_id = version_id AND position = pos;
+      ELSE
+        pos := pos + 1;
+        INSERT INTO testcase_steps (testcase_version_id, position, action)
+        VALUES (version_id, pos, concat_ws(' ', step->>'keyword', step->>'text'));
+      END IF;
+    END LOOP;
+    RETURN;
+  END IF;
+
+  -- Otherwise the n-th expected result belongs to the n-th step.
+  INSERT INTO testcase_steps (testcase_version_id, position, action, expected_result)
+  SELECT version_id, row_number() OVER (ORDER BY COALESCE(s.n, e.n)), COALESCE(s.text, ''),
+         COALESCE(e.text, '')
+  FROM (
+    SELECT n, CASE jsonb_typeof(v)
+                WHEN 'string' THEN v #>> '{}'
+                WHEN 'object' THEN COALESCE(v->>'action', v->>'text')
+              END AS text
+    FROM jsonb_array_elements(steps) WITH ORDINALITY AS a(v, n)
+  ) s
+  FULL JOIN (
+    SELECT n, CASE jsonb_typeof(v)
+                WHEN 'string' THEN v #>> '{}'
+                WHEN 'object' THEN COALESCE(v->>'expected', v->>'text')
+              END AS text
+    FROM jsonb_array_elements(
+      CASE WHEN jsonb_typeof(expected) = 'array' THEN expected ELSE '[]'::jsonb END
+    ) WITH ORDINALITY AS a(v, n)
+  ) e ON e.n = s.n
+  WHERE COALESCE(s.text, '') <> '' OR COALESCE(e.text, '') <> '';
+END;
+$$;
+
+CREATE OR REPLACE FUNCTION derive_testcase_steps()
+RETURNS TRIGGER
+LANGUAGE plpgsql
+AS $$
+BEGIN
+  PERFORM fill_testcase_steps(NEW.id, NEW.steps_json, NEW.expected_json);
+  RETURN NEW;
+END;
+$$;
+
+DROP TRIGGER IF EXISTS trg_testcase_versions_steps ON testcase_versions;
+CREATE TRIGGER trg_testcase_versions_steps
+AFTER INSERT ON testcase_versions
+FOR EACH ROW EXECUTE FUNCTION derive_testcase_steps();
+
+-- Existing versions get their steps once.
+SELECT fill_testcase_steps(tv.id, tv.steps_json, tv.expected_json)
+FROM testcase_versions tv
+WHERE NOT EXISTS (SELECT 1 FROM testcase_steps ts WHERE ts.testcase_version_id = tv.id);
+
+COMMIT;
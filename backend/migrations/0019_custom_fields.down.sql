@@ -0,0 +1,9 @@
+BEGIN;
+
+DROP INDEX IF EXISTS idx_runs_custom_fields;
+DROP INDEX IF EXISTS idx_testcases_custom_fields;
+ALTER TABLE runs DROP COLUMN IF EXISTS custom_fields;
+ALTER TABLE testcases DROP COLUMN IF EXISTS custom_fields;
+DROP TABLE IF EXISTS custom_fields;
+
+COMMIT;
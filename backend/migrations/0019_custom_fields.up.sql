@@ -0,0 +1,30 @@
+BEGIN;
+
+-- Per-project custom fields of testcases and runs. Values live in a JSONB object keyed by
+-- the field key on the entity itself and are validated by the API against the definitions.
+CREATE TABLE IF NOT EXISTS custom_fields (
+  id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  project_id UUID NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+  entity TEXT NOT NULL CHECK (entity IN ('testcase', 'run')),
+  key TEXT NOT NULL CHECK (key ~ '^[a-z][a-z0-9_]{1,63}$'),
+  name TEXT NOT NULL CHECK (char_length(name) BETWEEN 1 AND 200),
+  field_type TEXT NOT NULL
+    CHECK (field_type IN ('text', 'number', 'boolean', 'date', 'select', 'multiselect')),
+  options JSONB NOT NULL DEFAULT '[]'::jsonb,
+  is_required BOOLEAN NOT NULL DEFAULT FALSE,
+  position INTEGER NOT NULL DEFAULT 0,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  UNIQUE (project_id, entity, key)
+);
+
+ALTER TABLE testcases ADD COLUMN IF NOT EXISTS custom_fields JSONB NOT NULL DEFAULT '{}'::jsonb;
+ALTER TABLE runs ADD COLUMN IF NOT EXISTS custom_fields JSONB NOT NULL DEFAULT '{}'::jsonb;
+
+-- List filters use containment (custom_fields @> '{"priority": "high"}').
+CREATE INDEX IF NOT EXISTS idx_testcases_custom_fields
+  ON testcases USING GIN (custom_fields jsonb_path_ops);
+CREATE INDEX IF NOT EXISTS idx_runs_custom_fields
+  ON runs USING GIN (custom_fields jsonb_path_ops);
+
+COMMIT;
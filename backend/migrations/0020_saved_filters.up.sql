@@ -0,0 +1,21 @@
+BEGIN;
+
+-- Named list filters: the query parameters of the runs or testcases list, private to the
+-- owner or shared with the project. List endpoints expand them via `?filterId=`.
+CREATE TABLE IF NOT EXISTS saved_filters (
+  id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  project_id UUID NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+  owner_user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+  target TEXT NOT NULL CHECK (target IN ('runs', 'testcases')),
+  name TEXT NOT NULL CHECK (char_length(name) BETWEEN 1 AND 200),
+  params JSONB NOT NULL DEFAULT '{}'::jsonb,
+  is_shared BOOLEAN NOT NULL DEFAULT FALSE,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  UNIQUE (project_id, owner_user_id, target, name)
+);
+
+CREATE INDEX IF NOT EXISTS idx_saved_filters_project_target
+  ON saved_filters(project_id, target);
+
+COMMIT;
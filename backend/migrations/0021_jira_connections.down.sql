@@ -0,0 +1,5 @@
+BEGIN;
+
+DROP TABLE IF EXISTS project_jira_connections;
+
+COMMIT;
@@ -0,0 +1,19 @@
+BEGIN;
+
+-- Jira connection of a project for creating issues from failed results. The API token is
+-- encrypted by the API with `SECRETS_KEY` (AES-256-GCM, nonce + ciphertext).
+CREATE TABLE IF NOT EXISTS project_jira_connections (
+  project_id UUID PRIMARY KEY REFERENCES projects(id) ON DELETE CASCADE,
+  base_url TEXT NOT NULL,
+  -- Jira Cloud authenticates with email + API token; without an email the token is sent as
+  -- a personal access token (Jira Server / Data Center).
+  email TEXT,
+  api_token_encrypted BYTEA NOT NULL,
+  jira_project_key TEXT NOT NULL CHECK (jira_project_key ~ '^[A-Z][A-Z0-9_]{0,49}$'),
+  issue_type TEXT NOT NULL DEFAULT 'Bug',
+  updated_by_user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+COMMIT;
@@ -0,0 +1,29 @@
+BEGIN;
+
+-- Slack/Mattermost incoming webhooks of a project. `templates` maps an event to the message
+-- text in the provider's markup with `{placeholders}`; events without one use the built-in text.
+CREATE TABLE IF NOT EXISTS chat_webhooks (
+  id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  project_id UUID NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+  provider TEXT NOT NULL CHECK (provider IN ('slack', 'mattermost')),
+  name TEXT NOT NULL CHECK (char_length(name) BETWEEN 1 AND 200),
+  url TEXT NOT NULL CHECK (url ~ '^https?://'),
+  events TEXT[] NOT NULL CHECK (
+    cardinality(events) > 0
+    AND events <@ ARRAY['run_done', 'required_failed', 'run_assigned']::TEXT[]
+  ),
+  templates JSONB NOT NULL DEFAULT '{}'::jsonb,
+  is_active BOOLEAN NOT NULL DEFAULT TRUE,
+  created_by_user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_chat_webhooks_project_id ON chat_webhooks(project_id);
+
+DROP TRIGGER IF EXISTS trg_chat_webhooks_set_updated_at ON chat_webhooks;
+CREATE TRIGGER trg_chat_webhooks_set_updated_at
+BEFORE UPDATE ON chat_webhooks
+FOR EACH ROW EXECUTE FUNCTION set_updated_at();
+
+COMMIT;
@@ -0,0 +1,6 @@
+BEGIN;
+
+ALTER TABLE runs DROP COLUMN IF EXISTS commit_sha;
+DROP TABLE IF EXISTS project_ci_connections;
+
+COMMIT;
@@ -0,0 +1,24 @@
+BEGIN;
+
+-- GitHub/GitLab repository a project reports run outcomes to as commit statuses. The token
+-- (personal access token or OAuth app token) is encrypted by the API with `SECRETS_KEY`.
+CREATE TABLE IF NOT EXISTS project_ci_connections (
+  project_id UUID PRIMARY KEY REFERENCES projects(id) ON DELETE CASCADE,
+  provider TEXT NOT NULL CHECK (provider IN ('github', 'gitlab')),
+  api_url TEXT NOT NULL CHECK (api_url ~ '^https?://'),
+  -- `owner/repo` on GitHub, the full project path on GitLab.
+  repository TEXT NOT NULL,
+  token_type TEXT NOT NULL DEFAULT 'personal' CHECK (token_type IN ('personal', 'oauth')),
+  token_encrypted BYTEA NOT NULL,
+  status_context TEXT NOT NULL DEFAULT 'uran',
+  updated_by_user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Commit the run verifies; its status is set to pending on creation and to the outcome
+-- when the run is done.
+ALTER TABLE runs
+  ADD COLUMN IF NOT EXISTS commit_sha TEXT CHECK (commit_sha ~ '^[0-9a-f]{7,64}$');
+
+COMMIT;
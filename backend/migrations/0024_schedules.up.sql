@@ -0,0 +1,32 @@
+BEGIN;
+
+-- Recurring runs: on every `cron` match (5-field expression in `timezone`) the scheduler
+-- creates a draft run from the template on behalf of `created_by_user_id`.
+CREATE TABLE IF NOT EXISTS schedules (
+  id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  project_id UUID NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+  name TEXT NOT NULL CHECK (char_length(name) BETWEEN 1 AND 200),
+  cron TEXT NOT NULL,
+  timezone TEXT NOT NULL DEFAULT 'UTC',
+  template_id UUID NOT NULL REFERENCES run_templates(id) ON DELETE CASCADE,
+  run_title TEXT NOT NULL DEFAULT '',
+  assignee_user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+  is_active BOOLEAN NOT NULL DEFAULT TRUE,
+  next_run_at TIMESTAMPTZ,
+  last_run_at TIMESTAMPTZ,
+  last_run_id UUID REFERENCES runs(id) ON DELETE SET NULL,
+  last_error TEXT,
+  created_by_user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_schedules_project_id ON schedules(project_id);
+CREATE INDEX IF NOT EXISTS idx_schedules_due ON schedules(next_run_at) WHERE is_active;
+
+DROP TRIGGER IF EXISTS trg_schedules_set_updated_at ON schedules;
+CREATE TRIGGER trg_schedules_set_updated_at
+BEFORE UPDATE ON schedules
+FOR EACH ROW EXECUTE FUNCTION set_updated_at();
+
+COMMIT;
@@ -0,0 +1,43 @@
+BEGIN;
+
+-- Background work queue. A worker claims a due row (`run_after <= NOW()`) with
+-- `FOR UPDATE SKIP LOCKED` and moves `run_after` forward by the visibility timeout, so a job
+-- whose worker died is picked up again; failures are retried with backoff until
+-- `max_attempts`.
+CREATE TABLE IF NOT EXISTS jobs (
+  id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  kind TEXT NOT NULL CHECK (kind IN (
+    'email', 'notification', 'webhook_delivery', 'chat_message', 'ci_status', 'run_report',
+    'testcase_import'
+  )),
+  project_id UUID REFERENCES projects(id) ON DELETE CASCADE,
+  payload JSONB NOT NULL DEFAULT '{}'::jsonb,
+  status TEXT NOT NULL DEFAULT 'queued'
+    CHECK (status IN ('queued', 'running', 'succeeded', 'failed')),
+  attempts INTEGER NOT NULL DEFAULT 0 CHECK (attempts >= 0),
+  max_attempts INTEGER NOT NULL DEFAULT 5 CHECK (max_attempts > 0),
+  run_after TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  last_error TEXT,
+  result JSONB,
+  created_by_user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  started_at TIMESTAMPTZ,
+  finished_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_jobs_due
+  ON jobs(run_after)
+  WHERE status IN ('queued', 'running');
+CREATE INDEX IF NOT EXISTS idx_jobs_finished_at
+  ON jobs(finished_at)
+  WHERE finished_at IS NOT NULL;
+
+-- Deliveries queued by the former webhook worker continue as jobs.
+INSERT INTO jobs (kind, project_id, payload, attempts, max_attempts, run_after)
+SELECT 'webhook_delivery', w.project_id, jsonb_build_object('deliveryId', d.id), d.attempts,
+       GREATEST(d.attempts + 1, 6), d.next_attempt_at
+FROM webhook_deliveries d
+JOIN webhooks w ON w.id = d.webhook_id
+WHERE d.status = 'pending';
+
+COMMIT;
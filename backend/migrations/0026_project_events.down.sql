@@ -0,0 +1,6 @@
+BEGIN;
+
+DROP TABLE IF EXISTS project_events;
+DROP FUNCTION IF EXISTS notify_project_event();
+
+COMMIT;
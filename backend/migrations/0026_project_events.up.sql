@@ -0,0 +1,32 @@
+BEGIN;
+
+-- Project activity feed for `GET /api/v2/projects/{project_id}/events` (SSE). Every event
+-- emitted to webhooks is also stored here; the identity column is the SSE event id that a
+-- reconnecting client sends back in `Last-Event-ID`.
+CREATE TABLE IF NOT EXISTS project_events (
+  id BIGINT GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+  project_id UUID NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+  event TEXT NOT NULL,
+  payload JSONB NOT NULL,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_project_events_project ON project_events(project_id, id);
+CREATE INDEX IF NOT EXISTS idx_project_events_created_at ON project_events(created_at);
+
+-- Wakes the streams of every API instance; the payload is only the project id, the events
+-- themselves are read from the table.
+CREATE OR REPLACE FUNCTION notify_project_event()
+RETURNS TRIGGER AS $$
+BEGIN
+  PERFORM pg_notify('project_events', NEW.project_id::text);
+  RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trg_project_events_notify ON project_events;
+CREATE TRIGGER trg_project_events_notify
+AFTER INSERT ON project_events
+FOR EACH ROW EXECUTE FUNCTION notify_project_event();
+
+COMMIT;
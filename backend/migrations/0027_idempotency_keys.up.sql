@@ -0,0 +1,21 @@
+BEGIN;
+
+-- Responses of POST requests sent with an `Idempotency-Key` header, replayed when the same
+-- user repeats the key within 24 hours. A row is `in_progress` while the first request runs.
+CREATE TABLE IF NOT EXISTS idempotency_keys (
+  user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+  key TEXT NOT NULL CHECK (char_length(key) BETWEEN 1 AND 255),
+  -- Path with query string; the same key on another endpoint is rejected.
+  path TEXT NOT NULL,
+  status TEXT NOT NULL DEFAULT 'in_progress' CHECK (status IN ('in_progress', 'completed')),
+  response_status INTEGER,
+  response_headers JSONB,
+  response_body BYTEA,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  expires_at TIMESTAMPTZ NOT NULL DEFAULT NOW() + INTERVAL '24 hours',
+  PRIMARY KEY (user_id, key)
+);
+
+CREATE INDEX IF NOT EXISTS idx_idempotency_keys_expires_at ON idempotency_keys(expires_at);
+
+COMMIT;
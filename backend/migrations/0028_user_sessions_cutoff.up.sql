@@ -0,0 +1,7 @@
+BEGIN;
+
+-- Tokens of the user issued at or before this moment are rejected: set when an admin
+-- deactivates the account or forces a password reset.
+ALTER TABLE users ADD COLUMN IF NOT EXISTS tokens_valid_after TIMESTAMPTZ;
+
+COMMIT;
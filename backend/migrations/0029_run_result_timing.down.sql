@@ -0,0 +1,9 @@
+BEGIN;
+
+DROP INDEX IF EXISTS idx_run_results_executed_at;
+DROP TRIGGER IF EXISTS trg_run_results_executed_at ON run_results;
+DROP FUNCTION IF EXISTS set_run_result_executed_at();
+ALTER TABLE run_results DROP COLUMN IF EXISTS executed_at;
+ALTER TABLE run_results DROP COLUMN IF EXISTS elapsed_seconds;
+
+COMMIT;
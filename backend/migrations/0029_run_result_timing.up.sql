@@ -0,0 +1,31 @@
+BEGIN;
+
+-- Time spent on an item as reported by the tester.
+ALTER TABLE run_results
+  ADD COLUMN IF NOT EXISTS elapsed_seconds INTEGER CHECK (elapsed_seconds >= 0);
+
+-- When the item first got a status other than `na`, whoever wrote it (API, imports).
+ALTER TABLE run_results ADD COLUMN IF NOT EXISTS executed_at TIMESTAMPTZ;
+
+UPDATE run_results SET executed_at = updated_at WHERE executed_at IS NULL AND status <> 'na';
+
+CREATE OR REPLACE FUNCTION set_run_result_executed_at()
+RETURNS TRIGGER
+LANGUAGE plpgsql
+AS $$
+BEGIN
+  IF NEW.status <> 'na' AND NEW.executed_at IS NULL THEN
+    NEW.executed_at := NOW();
+  END IF;
+  RETURN NEW;
+END;
+$$;
+
+DROP TRIGGER IF EXISTS trg_run_results_executed_at ON run_results;
+CREATE TRIGGER trg_run_results_executed_at
+BEFORE INSERT OR UPDATE ON run_results
+FOR EACH ROW EXECUTE FUNCTION set_run_result_executed_at();
+
+CREATE INDEX IF NOT EXISTS idx_run_results_executed_at ON run_results(executed_at);
+
+COMMIT;
@@ -0,0 +1,31 @@
+BEGIN;
+
+-- Enum values cannot be dropped: the type is recreated and the new statuses become `na`.
+ALTER TYPE result_status RENAME TO result_status_extended;
+CREATE TYPE result_status AS ENUM ('ok', 'fail', 'na');
+
+ALTER TABLE run_results ALTER COLUMN status DROP DEFAULT;
+ALTER TABLE run_results
+  ALTER COLUMN status TYPE result_status
+  USING (CASE WHEN status::text IN ('ok', 'fail') THEN status::text ELSE 'na' END)::result_status;
+ALTER TABLE run_results ALTER COLUMN status SET DEFAULT 'na';
+
+ALTER TABLE run_result_history
+  ALTER COLUMN status TYPE result_status
+  USING (CASE WHEN status::text IN ('ok', 'fail') THEN status::text ELSE 'na' END)::result_status,
+  ALTER COLUMN previous_status TYPE result_status
+  USING (
+    CASE
+      WHEN previous_status IS NULL THEN NULL
+      WHEN previous_status::text IN ('ok', 'fail') THEN previous_status::text
+      ELSE 'na'
+    END
+  )::result_status;
+
+ALTER TABLE run_result_steps
+  ALTER COLUMN status TYPE result_status
+  USING (CASE WHEN status::text IN ('ok', 'fail') THEN status::text ELSE 'na' END)::result_status;
+
+DROP TYPE result_status_extended;
+
+COMMIT;
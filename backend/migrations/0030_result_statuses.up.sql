@@ -0,0 +1,10 @@
+BEGIN;
+
+-- blocked: could not be executed because of an impediment;
+-- skipped: deliberately not executed in this run;
+-- retest: has to be executed again (e.g. after a fix).
+ALTER TYPE result_status ADD VALUE IF NOT EXISTS 'blocked';
+ALTER TYPE result_status ADD VALUE IF NOT EXISTS 'skipped';
+ALTER TYPE result_status ADD VALUE IF NOT EXISTS 'retest';
+
+COMMIT;
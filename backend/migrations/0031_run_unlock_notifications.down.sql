@@ -0,0 +1,5 @@
+BEGIN;
+
+ALTER TABLE notification_preferences DROP COLUMN IF EXISTS run_unlocked;
+
+COMMIT;
@@ -0,0 +1,7 @@
+BEGIN;
+
+-- Emails to project members when an owner unlocks a locked run.
+ALTER TABLE notification_preferences
+  ADD COLUMN IF NOT EXISTS run_unlocked BOOLEAN NOT NULL DEFAULT TRUE;
+
+COMMIT;
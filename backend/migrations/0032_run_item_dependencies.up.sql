@@ -0,0 +1,19 @@
+BEGIN;
+
+-- run_item_id can only be executed after depends_on_run_item_id passed (result `ok`).
+-- Both items belong to run_id; the application keeps the graph acyclic.
+CREATE TABLE IF NOT EXISTS run_item_dependencies (
+  run_id UUID NOT NULL REFERENCES runs(id) ON DELETE CASCADE,
+  run_item_id UUID NOT NULL REFERENCES run_items(id) ON DELETE CASCADE,
+  depends_on_run_item_id UUID NOT NULL REFERENCES run_items(id) ON DELETE CASCADE,
+  created_by_user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  PRIMARY KEY (run_item_id, depends_on_run_item_id),
+  CONSTRAINT chk_run_item_dependencies_not_self CHECK (run_item_id <> depends_on_run_item_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_run_item_dependencies_run ON run_item_dependencies(run_id);
+CREATE INDEX IF NOT EXISTS idx_run_item_dependencies_depends_on
+  ON run_item_dependencies(depends_on_run_item_id);
+
+COMMIT;
@@ -0,0 +1,10 @@
+BEGIN;
+
+DROP INDEX IF EXISTS idx_runs_asset_id;
+DROP TRIGGER IF EXISTS trg_runs_asset_version ON runs;
+DROP FUNCTION IF EXISTS set_run_asset_version();
+ALTER TABLE runs DROP COLUMN IF EXISTS asset_version;
+DROP TABLE IF EXISTS asset_versions;
+ALTER TABLE assets DROP COLUMN IF EXISTS name;
+
+COMMIT;
@@ -0,0 +1,53 @@
+BEGIN;
+
+-- Display name of an asset; existing assets are named after their model or type.
+ALTER TABLE assets ADD COLUMN IF NOT EXISTS name TEXT NOT NULL DEFAULT '';
+UPDATE assets SET name = COALESCE(NULLIF(model, ''), asset_type) WHERE name = '';
+
+-- Every version an asset had (`assets.firmware_version` is the current one).
+CREATE TABLE IF NOT EXISTS asset_versions (
+  id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  asset_id UUID NOT NULL REFERENCES assets(id) ON DELETE CASCADE,
+  version TEXT NOT NULL,
+  note TEXT NOT NULL DEFAULT '',
+  created_by_user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_asset_versions_asset_created
+  ON asset_versions(asset_id, created_at DESC);
+
+INSERT INTO asset_versions (asset_id, version, created_by_user_id, created_at)
+SELECT id, firmware_version, created_by_user_id, created_at
+FROM assets
+WHERE firmware_version <> ''
+  AND NOT EXISTS (SELECT 1 FROM asset_versions v WHERE v.asset_id = assets.id);
+
+-- Version of the asset a run was executed against, taken when the asset is set.
+ALTER TABLE runs ADD COLUMN IF NOT EXISTS asset_version TEXT;
+
+CREATE OR REPLACE FUNCTION set_run_asset_version()
+RETURNS TRIGGER
+LANGUAGE plpgsql
+AS $$
+BEGIN
+  IF NEW.asset_id IS NULL THEN
+    NEW.asset_version := NULL;
+  ELSIF (TG_OP = 'INSERT' AND NEW.asset_version IS NULL)
+     OR (TG_OP = 'UPDATE' AND NEW.asset_id IS DISTINCT FROM OLD.asset_id) THEN
+    SELECT NULLIF(firmware_version, '') INTO NEW.asset_version
+    FROM assets
+    WHERE id = NEW.asset_id;
+  END IF;
+  RETURN NEW;
+END;
+$$;
+
+DROP TRIGGER IF EXISTS trg_runs_asset_version ON runs;
+CREATE TRIGGER trg_runs_asset_version
+BEFORE INSERT OR UPDATE OF asset_id ON runs
+FOR EACH ROW EXECUTE FUNCTION set_run_asset_version();
+
+CREATE INDEX IF NOT EXISTS idx_runs_asset_id ON runs(asset_id);
+
+COMMIT;
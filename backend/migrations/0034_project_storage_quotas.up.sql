@@ -0,0 +1,12 @@
+BEGIN;
+
+-- Per-project storage quota set by an administrator; overrides PROJECT_STORAGE_QUOTA_BYTES.
+-- quota_bytes NULL means unlimited.
+CREATE TABLE IF NOT EXISTS project_storage_quotas (
+  project_id UUID PRIMARY KEY REFERENCES projects(id) ON DELETE CASCADE,
+  quota_bytes BIGINT CHECK (quota_bytes IS NULL OR quota_bytes >= 0),
+  set_by_user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+  updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+COMMIT;
@@ -0,0 +1,5 @@
+BEGIN;
+
+DROP INDEX IF EXISTS idx_comments_mentioned_user_ids;
+
+COMMIT;
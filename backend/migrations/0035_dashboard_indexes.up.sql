@@ -0,0 +1,7 @@
+BEGIN;
+
+-- `GET /api/v2/me/dashboard` lists the comments that mention a user.
+CREATE INDEX IF NOT EXISTS idx_comments_mentioned_user_ids
+  ON comments USING GIN (mentioned_user_ids);
+
+COMMIT;
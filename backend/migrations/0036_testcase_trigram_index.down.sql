@@ -0,0 +1,5 @@
+BEGIN;
+
+DROP INDEX IF EXISTS idx_testcases_title_trgm;
+
+COMMIT;
@@ -0,0 +1,10 @@
+BEGIN;
+
+-- Near-duplicate detection compares test case titles by trigram similarity.
+-- pg_trgm is a trusted extension: the database owner can create it.
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+CREATE INDEX IF NOT EXISTS idx_testcases_title_trgm
+  ON testcases USING GIN (title gin_trgm_ops);
+
+COMMIT;
@@ -0,0 +1,6 @@
+BEGIN;
+
+DROP INDEX IF EXISTS idx_run_template_items_testcase_version_id;
+DROP INDEX IF EXISTS idx_run_items_testcase_version_id;
+
+COMMIT;
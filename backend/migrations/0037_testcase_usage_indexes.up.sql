@@ -0,0 +1,10 @@
+BEGIN;
+
+-- Where a test case is still used (`GET /api/v2/testcases/{testcase_id}/usage`, the delete
+-- guard and the `ON DELETE RESTRICT` checks) is looked up by version.
+CREATE INDEX IF NOT EXISTS idx_run_items_testcase_version_id
+  ON run_items(testcase_version_id);
+CREATE INDEX IF NOT EXISTS idx_run_template_items_testcase_version_id
+  ON run_template_items(testcase_version_id);
+
+COMMIT;
@@ -0,0 +1,13 @@
+BEGIN;
+
+DELETE FROM jobs WHERE kind = 'email_reply';
+ALTER TABLE jobs DROP CONSTRAINT IF EXISTS jobs_kind_check;
+ALTER TABLE jobs ADD CONSTRAINT jobs_kind_check CHECK (kind IN (
+  'email', 'notification', 'webhook_delivery', 'chat_message', 'ci_status', 'run_report',
+  'testcase_import'
+));
+
+DROP TABLE IF EXISTS email_replies;
+DROP TABLE IF EXISTS email_reply_tokens;
+
+COMMIT;
@@ -0,0 +1,41 @@
+BEGIN;
+
+-- Reply address of an assignment email: `reply+<token>@INBOUND_EMAIL_DOMAIN` identifies the
+-- recipient and the run item. One token per user and item; resending extends it.
+CREATE TABLE IF NOT EXISTS email_reply_tokens (
+  token UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+  run_item_id UUID NOT NULL REFERENCES run_items(id) ON DELETE CASCADE,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  expires_at TIMESTAMPTZ NOT NULL,
+  UNIQUE (user_id, run_item_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_email_reply_tokens_expires_at ON email_reply_tokens(expires_at);
+
+-- Replies received through the inbound email webhook; `message_id` makes provider retries
+-- no-ops. Processed by `email_reply` jobs.
+CREATE TABLE IF NOT EXISTS email_replies (
+  id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  message_id TEXT NOT NULL UNIQUE,
+  token UUID NOT NULL,
+  sender TEXT NOT NULL,
+  body TEXT NOT NULL,
+  status TEXT NOT NULL DEFAULT 'received'
+    CHECK (status IN ('received', 'applied', 'rejected')),
+  error_code TEXT,
+  run_item_id UUID REFERENCES run_items(id) ON DELETE SET NULL,
+  user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  processed_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_email_replies_created_at ON email_replies(created_at);
+
+ALTER TABLE jobs DROP CONSTRAINT IF EXISTS jobs_kind_check;
+ALTER TABLE jobs ADD CONSTRAINT jobs_kind_check CHECK (kind IN (
+  'email', 'notification', 'webhook_delivery', 'chat_message', 'ci_status', 'run_report',
+  'testcase_import', 'email_reply'
+));
+
+COMMIT;
@@ -0,0 +1,13 @@
+BEGIN;
+
+DELETE FROM jobs WHERE kind = 'telegram_message';
+ALTER TABLE jobs DROP CONSTRAINT IF EXISTS jobs_kind_check;
+ALTER TABLE jobs ADD CONSTRAINT jobs_kind_check CHECK (kind IN (
+  'email', 'notification', 'webhook_delivery', 'chat_message', 'ci_status', 'run_report',
+  'testcase_import', 'email_reply'
+));
+
+DROP TABLE IF EXISTS telegram_link_codes;
+DROP TABLE IF EXISTS telegram_links;
+
+COMMIT;
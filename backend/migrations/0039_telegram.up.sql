@@ -0,0 +1,25 @@
+BEGIN;
+
+-- Private Telegram chat of a user, linked through `/start <code>` in the bot. Assignment and
+-- failure notifications are also sent there.
+CREATE TABLE IF NOT EXISTS telegram_links (
+  user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+  chat_id BIGINT NOT NULL UNIQUE,
+  username TEXT,
+  linked_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- One-time codes issued by `POST /api/v2/me/telegram/link`; one pending code per user.
+CREATE TABLE IF NOT EXISTS telegram_link_codes (
+  code TEXT PRIMARY KEY,
+  user_id UUID NOT NULL UNIQUE REFERENCES users(id) ON DELETE CASCADE,
+  expires_at TIMESTAMPTZ NOT NULL
+);
+
+ALTER TABLE jobs DROP CONSTRAINT IF EXISTS jobs_kind_check;
+ALTER TABLE jobs ADD CONSTRAINT jobs_kind_check CHECK (kind IN (
+  'email', 'notification', 'webhook_delivery', 'chat_message', 'ci_status', 'run_report',
+  'testcase_import', 'email_reply', 'telegram_message'
+));
+
+COMMIT;
@@ -0,0 +1,20 @@
+BEGIN;
+
+-- Which channels deliver which notifications. Rows with `project_id` override the user-wide
+-- rows for that project; user-wide email settings stay in `notification_preferences` (with
+-- the unsubscribe token), so a user-wide row is never stored for the email channel.
+CREATE TABLE IF NOT EXISTS notification_settings (
+  id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+  project_id UUID REFERENCES projects(id) ON DELETE CASCADE,
+  event TEXT NOT NULL CHECK (event IN (
+    'member_added', 'run_assigned', 'run_done', 'required_failed', 'mentioned', 'run_unlocked'
+  )),
+  channel TEXT NOT NULL CHECK (channel IN ('email', 'chat', 'in_app')),
+  enabled BOOLEAN NOT NULL,
+  updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  CHECK (project_id IS NOT NULL OR channel <> 'email'),
+  UNIQUE NULLS NOT DISTINCT (user_id, project_id, event, channel)
+);
+
+COMMIT;
@@ -0,0 +1,36 @@
+BEGIN;
+
+-- Saved custom reports: an entity, filters, grouping and columns kept as JSON and executed
+-- by the server-side query builder. A report with `cron` is also emailed as CSV to
+-- `recipient_user_ids` on that schedule.
+CREATE TABLE IF NOT EXISTS report_definitions (
+  id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  project_id UUID NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+  owner_user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+  name TEXT NOT NULL CHECK (char_length(name) BETWEEN 1 AND 200),
+  definition JSONB NOT NULL,
+  is_shared BOOLEAN NOT NULL DEFAULT FALSE,
+  cron TEXT,
+  timezone TEXT,
+  recipient_user_ids UUID[] NOT NULL DEFAULT '{}',
+  next_run_at TIMESTAMPTZ,
+  last_run_at TIMESTAMPTZ,
+  last_job_id UUID,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  UNIQUE (project_id, owner_user_id, name),
+  CHECK ((cron IS NULL) = (timezone IS NULL))
+);
+
+CREATE INDEX IF NOT EXISTS idx_report_definitions_project
+  ON report_definitions(project_id);
+CREATE INDEX IF NOT EXISTS idx_report_definitions_due
+  ON report_definitions(next_run_at) WHERE next_run_at IS NOT NULL;
+
+ALTER TABLE jobs DROP CONSTRAINT IF EXISTS jobs_kind_check;
+ALTER TABLE jobs ADD CONSTRAINT jobs_kind_check CHECK (kind IN (
+  'email', 'notification', 'webhook_delivery', 'chat_message', 'ci_status', 'run_report',
+  'testcase_import', 'email_reply', 'telegram_message', 'report_delivery'
+));
+
+COMMIT;
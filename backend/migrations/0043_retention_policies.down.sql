@@ -0,0 +1,12 @@
+BEGIN;
+
+DELETE FROM jobs WHERE kind = 'retention_purge';
+ALTER TABLE jobs DROP CONSTRAINT IF EXISTS jobs_kind_check;
+ALTER TABLE jobs ADD CONSTRAINT jobs_kind_check CHECK (kind IN (
+  'email', 'notification', 'webhook_delivery', 'chat_message', 'ci_status', 'run_report',
+  'testcase_import', 'email_reply', 'telegram_message', 'report_delivery'
+));
+
+DROP TABLE IF EXISTS project_retention_policies;
+
+COMMIT;
@@ -0,0 +1,22 @@
+BEGIN;
+
+-- Per-project retention: finished runs older than `run_retention_months` are deleted and
+-- attachments older than `attachment_retention_days` are stripped by the `retention_purge`
+-- job. NULL keeps the data forever; locked runs are never touched.
+CREATE TABLE IF NOT EXISTS project_retention_policies (
+  project_id UUID PRIMARY KEY REFERENCES projects(id) ON DELETE CASCADE,
+  run_retention_months INTEGER CHECK (run_retention_months BETWEEN 1 AND 1200),
+  attachment_retention_days INTEGER CHECK (attachment_retention_days BETWEEN 1 AND 36500),
+  set_by_user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+  updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  last_purged_at TIMESTAMPTZ,
+  last_job_id UUID
+);
+
+ALTER TABLE jobs DROP CONSTRAINT IF EXISTS jobs_kind_check;
+ALTER TABLE jobs ADD CONSTRAINT jobs_kind_check CHECK (kind IN (
+  'email', 'notification', 'webhook_delivery', 'chat_message', 'ci_status', 'run_report',
+  'testcase_import', 'email_reply', 'telegram_message', 'report_delivery', 'retention_purge'
+));
+
+COMMIT;
@@ -0,0 +1,15 @@
+BEGIN;
+
+-- Sealed values cannot be decrypted in SQL: webhooks that no longer have a plaintext copy
+-- are dropped and have to be created again.
+DELETE FROM webhooks WHERE secret IS NULL;
+ALTER TABLE webhooks DROP CONSTRAINT IF EXISTS webhooks_secret_stored_check;
+ALTER TABLE webhooks ALTER COLUMN secret SET NOT NULL;
+ALTER TABLE webhooks DROP COLUMN IF EXISTS secret_encrypted;
+
+DELETE FROM chat_webhooks WHERE url IS NULL;
+ALTER TABLE chat_webhooks DROP CONSTRAINT IF EXISTS chat_webhooks_url_stored_check;
+ALTER TABLE chat_webhooks ALTER COLUMN url SET NOT NULL;
+ALTER TABLE chat_webhooks DROP COLUMN IF EXISTS url_encrypted;
+
+COMMIT;
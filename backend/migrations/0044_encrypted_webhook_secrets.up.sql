@@ -0,0 +1,20 @@
+BEGIN;
+
+-- Webhook signing secrets and chat webhook URLs (the URL is the Slack/Mattermost token) are
+-- sealed with SECRETS_KEY like the Jira and CI tokens. Existing rows keep the plaintext
+-- column until `uran-api --reencrypt-secrets` moves it; new rows only have the sealed one.
+ALTER TABLE webhooks
+  ADD COLUMN IF NOT EXISTS secret_encrypted BYTEA,
+  ALTER COLUMN secret DROP NOT NULL;
+ALTER TABLE webhooks
+  ADD CONSTRAINT webhooks_secret_stored_check
+  CHECK ((secret IS NULL) <> (secret_encrypted IS NULL));
+
+ALTER TABLE chat_webhooks
+  ADD COLUMN IF NOT EXISTS url_encrypted BYTEA,
+  ALTER COLUMN url DROP NOT NULL;
+ALTER TABLE chat_webhooks
+  ADD CONSTRAINT chat_webhooks_url_stored_check
+  CHECK ((url IS NULL) <> (url_encrypted IS NULL));
+
+COMMIT;
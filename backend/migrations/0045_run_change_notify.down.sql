@@ -0,0 +1,12 @@
+BEGIN;
+
+DROP TRIGGER IF EXISTS trg_defects_notify_changed ON defects;
+DROP TRIGGER IF EXISTS trg_comments_notify_changed ON comments;
+DROP TRIGGER IF EXISTS trg_run_item_dependencies_notify_changed ON run_item_dependencies;
+DROP TRIGGER IF EXISTS trg_run_result_steps_notify_changed ON run_result_steps;
+DROP TRIGGER IF EXISTS trg_run_results_notify_changed ON run_results;
+DROP TRIGGER IF EXISTS trg_run_items_notify_changed ON run_items;
+DROP TRIGGER IF EXISTS trg_runs_notify_changed ON runs;
+DROP FUNCTION IF EXISTS notify_run_changed();
+
+COMMIT;
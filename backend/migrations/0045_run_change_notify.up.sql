@@ -0,0 +1,65 @@
+BEGIN;
+
+-- Tells every API instance that a run's details changed, so cached copies are dropped. The
+-- payload is the run id; PostgreSQL sends duplicates within one transaction only once.
+CREATE OR REPLACE FUNCTION notify_run_changed()
+RETURNS TRIGGER AS $$
+DECLARE
+  changed JSONB := to_jsonb(CASE WHEN TG_OP = 'DELETE' THEN OLD ELSE NEW END);
+  changed_run_id UUID;
+BEGIN
+  IF TG_TABLE_NAME = 'runs' THEN
+    changed_run_id := (changed->>'id')::uuid;
+  ELSIF changed ? 'run_id' THEN
+    changed_run_id := (changed->>'run_id')::uuid;
+  ELSIF changed ? 'run_item_id' THEN
+    SELECT run_id INTO changed_run_id
+    FROM run_items WHERE id = (changed->>'run_item_id')::uuid;
+  ELSIF changed ? 'run_result_id' THEN
+    SELECT ri.run_id INTO changed_run_id
+    FROM run_results rr JOIN run_items ri ON ri.id = rr.run_item_id
+    WHERE rr.id = (changed->>'run_result_id')::uuid;
+  END IF;
+  IF changed_run_id IS NOT NULL THEN
+    PERFORM pg_notify('run_changed', changed_run_id::text);
+  END IF;
+  RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trg_runs_notify_changed ON runs;
+CREATE TRIGGER trg_runs_notify_changed
+AFTER INSERT OR UPDATE OR DELETE ON runs
+FOR EACH ROW EXECUTE FUNCTION notify_run_changed();
+
+DROP TRIGGER IF EXISTS trg_run_items_notify_changed ON run_items;
+CREATE TRIGGER trg_run_items_notify_changed
+AFTER INSERT OR UPDATE OR DELETE ON run_items
+FOR EACH ROW EXECUTE FUNCTION notify_run_changed();
+
+DROP TRIGGER IF EXISTS trg_run_results_notify_changed ON run_results;
+CREATE TRIGGER trg_run_results_notify_changed
+AFTER INSERT OR UPDATE OR DELETE ON run_results
+FOR EACH ROW EXECUTE FUNCTION notify_run_changed();
+
+DROP TRIGGER IF EXISTS trg_run_result_steps_notify_changed ON run_result_steps;
+CREATE TRIGGER trg_run_result_steps_notify_changed
+AFTER INSERT OR UPDATE OR DELETE ON run_result_steps
+FOR EACH ROW EXECUTE FUNCTION notify_run_changed();
+
+DROP TRIGGER IF EXISTS trg_run_item_dependencies_notify_changed ON run_item_dependencies;
+CREATE TRIGGER trg_run_item_dependencies_notify_changed
+AFTER INSERT OR UPDATE OR DELETE ON run_item_dependencies
+FOR EACH ROW EXECUTE FUNCTION notify_run_changed();
+
+DROP TRIGGER IF EXISTS trg_comments_notify_changed ON comments;
+CREATE TRIGGER trg_comments_notify_changed
+AFTER INSERT OR UPDATE OR DELETE ON comments
+FOR EACH ROW EXECUTE FUNCTION notify_run_changed();
+
+DROP TRIGGER IF EXISTS trg_defects_notify_changed ON defects;
+CREATE TRIGGER trg_defects_notify_changed
+AFTER INSERT OR UPDATE OR DELETE ON defects
+FOR EACH ROW EXECUTE FUNCTION notify_run_changed();
+
+COMMIT;
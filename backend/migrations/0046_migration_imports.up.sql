@@ -0,0 +1,14 @@
+BEGIN;
+
+-- What `uran-api import-testrail` created, by the id it has in the source instance. Mapped
+-- rows are skipped on the next run, so an interrupted import resumes where it stopped.
+CREATE TABLE IF NOT EXISTS migration_imports (
+  source TEXT NOT NULL,
+  entity TEXT NOT NULL CHECK (entity IN ('project', 'suite', 'testcase', 'run')),
+  source_id TEXT NOT NULL,
+  local_id UUID NOT NULL,
+  imported_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  PRIMARY KEY (source, entity, source_id)
+);
+
+COMMIT;
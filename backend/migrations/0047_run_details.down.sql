@@ -0,0 +1,7 @@
+BEGIN;
+
+DROP INDEX IF EXISTS idx_runs_project_milestone;
+ALTER TABLE runs DROP COLUMN IF EXISTS milestone;
+ALTER TABLE runs DROP COLUMN IF EXISTS description;
+
+COMMIT;
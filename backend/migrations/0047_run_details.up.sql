@@ -0,0 +1,12 @@
+BEGIN;
+
+-- Free-form run details edited while the run is a draft. The milestone is a release or
+-- sprint label shared by several runs of the project.
+ALTER TABLE runs ADD COLUMN IF NOT EXISTS description TEXT NOT NULL DEFAULT '';
+ALTER TABLE runs ADD COLUMN IF NOT EXISTS milestone TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_runs_project_milestone
+  ON runs (project_id, milestone)
+  WHERE milestone IS NOT NULL;
+
+COMMIT;
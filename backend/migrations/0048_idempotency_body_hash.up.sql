@@ -0,0 +1,7 @@
+BEGIN;
+
+-- SHA-256 of the first request's body; a repeat with another body is rejected. NULL when the
+-- body was not read to the end (and for keys stored before this column).
+ALTER TABLE idempotency_keys ADD COLUMN IF NOT EXISTS body_hash TEXT;
+
+COMMIT;
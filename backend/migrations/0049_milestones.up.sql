@@ -0,0 +1,23 @@
+BEGIN;
+
+-- Due dates of run milestones. A milestone covers the project's runs whose `runs.milestone`
+-- equals its name; runs may use a milestone name before it is defined here.
+CREATE TABLE IF NOT EXISTS milestones (
+  id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  project_id UUID NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+  name TEXT NOT NULL CHECK (char_length(name) BETWEEN 1 AND 200),
+  due_date DATE,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  UNIQUE (project_id, name)
+);
+
+CREATE INDEX IF NOT EXISTS idx_milestones_due_date
+  ON milestones(due_date) WHERE due_date IS NOT NULL;
+
+DROP TRIGGER IF EXISTS trg_milestones_set_updated_at ON milestones;
+CREATE TRIGGER trg_milestones_set_updated_at
+BEFORE UPDATE ON milestones
+FOR EACH ROW EXECUTE FUNCTION set_updated_at();
+
+COMMIT;
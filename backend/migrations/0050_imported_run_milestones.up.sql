@@ -0,0 +1,9 @@
+BEGIN;
+
+-- Runs imported from TestRail/Xray before 0047 kept the milestone in the `milestone` run
+-- field; copy it into `runs.milestone`.
+UPDATE runs
+SET milestone = NULLIF(LEFT(BTRIM(custom_fields->>'milestone'), 200), '')
+WHERE milestone IS NULL AND custom_fields ? 'milestone';
+
+COMMIT;
@@ -0,0 +1,552 @@
+use axum::{
+    extract::State,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::warn;
+use utoipa::ToSchema;
+
+use crate::{
+    audit, authz::AuthUser, db_errors, ensure_db_user_exists, error::ApiError, now_iso,
+    organizations, parse_uuid, password, read_projects, read_users, report, revocation,
+    write_projects, write_users, AppState,
+};
+
+const EXPORT_FORMAT: &str = "uran.account";
+const EXPORT_VERSION: i32 = 1;
+/// What results, comments and audit entries of a deleted account show as their author.
+const DELETED_USER_NAME: &str = "Удалённый пользователь";
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteAccountRequest {
+    /// Required unless the account signs in only through single sign-on.
+    current_password: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedAccount {
+    id: String,
+    name: String,
+    email: String,
+    pending_email: Option<String>,
+    email_verified: bool,
+    is_admin: bool,
+    created_at: String,
+    deactivated_at: Option<String>,
+    /// Issuer and subject of the linked single sign-on account.
+    oidc: Option<Value>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedAvatar {
+    mime_type: String,
+    updated_at: String,
+    /// The image, base64.
+    data: String,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedMembership {
+    id: String,
+    name: String,
+    role: String,
+}
+
+/// Everything stored about the caller. Records of other people (e.g. the runs the caller
+/// took part in) appear only as ids and titles; secrets such as password and key hashes are
+/// left out.
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountExport {
+    /// Always `uran.account`.
+    format: &'static str,
+    version: i32,
+    exported_at: String,
+    account: ExportedAccount,
+    avatar: Option<ExportedAvatar>,
+    projects: Vec<ExportedMembership>,
+    organizations: Vec<ExportedMembership>,
+    /// Notification preferences, per-project channels, the in-app feed and the linked
+    /// Telegram chat.
+    notifications: Value,
+    api_keys: Value,
+    saved_filters: Value,
+    report_definitions: Value,
+    /// Runs the caller executed, led, locked or is the default assignee of.
+    runs: Value,
+    /// Run items assigned to the caller.
+    assigned_items: Value,
+    /// Current results last recorded by the caller.
+    results: Value,
+    /// Every result change made by the caller.
+    result_history: Value,
+    comments: Value,
+    /// Metadata of uploaded attachments; the files are downloaded separately.
+    attachments: Value,
+    email_replies: Value,
+    audit_log: Value,
+}
+
+/// Downloads all data tied to the caller's account as one JSON document.
+#[utoipa::path(
+    get,
+    path = "/api/auth/me/export",
+    tag = "auth",
+    responses((status = 200, body = AccountExport))
+)]
+pub async fn export_account(
+    State(state): State<AppState>,
+    AuthUser(user_id): AuthUser,
+) -> Result<Response, ApiError> {
+    let user_uuid = parse_uuid(&user_id, ApiError::InvalidUserId)?;
+    let (user, projects, organizations) = {
+        let _guard = state.file_lock.lock().await;
+        let user = read_users(&state.users_file)
+            .await
+            .map_err(|_| ApiError::AccountExportFailed)?
+            .into_iter()
+            .find(|u| u.id == user_id)
+            .ok_or(ApiError::UserNotFound)?;
+        let projects = read_projects(&state.projects_file)
+            .await
+            .map_err(|_| ApiError::AccountExportFailed)?;
+        let organizations = organizations::read_organizations(&state.organizations_file)
+            .await
+            .map_err(|_| ApiError::AccountExportFailed)?;
+        (user, projects, organizations)
+    };
+
+    let avatar = match &user.avatar {
+        Some(avatar) => {
+            let data = state
+                .storage
+                .get(&avatar.storage_key)
+                .await
+                .map_err(|_| ApiError::AccountExportFailed)?;
+            Some(ExportedAvatar {
+                mime_type: avatar.mime_type.clone(),
+                updated_at: avatar.updated_at.clone(),
+                data: base64::engine::general_purpose::STANDARD.encode(data),
+            })
+        }
+        None => None,
+    };
+    let projects = projects
+        .iter()
+        .filter_map(|p| {
+            let role = p
+                .members
+                .iter()
+                .find(|m| m.user_id == user_id)
+                .map(|m| m.role.clone())
+                .or_else(|| (p.owner_id == user_id).then(|| "owner".to_string()))?;
+            Some(ExportedMembership {
+                id: p.id.clone(),
+                name: p.name.clone(),
+                role,
+            })
+        })
+        .collect();
+    let organizations = organizations
+        .iter()
+        .filter_map(|o| {
+            Some(ExportedMembership {
+                id: o.id.clone(),
+                name: o.name.clone(),
+                role: o.role_of(&user_id)?.to_string(),
+            })
+        })
+        .collect();
+
+    // One snapshot for all tables.
+    let export_failed = |_| ApiError::AccountExportFailed;
+    let mut tx = state.db.begin().await.map_err(export_failed)?;
+    sqlx::query!("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ, READ ONLY")
+        .execute(&mut *tx)
+        .await
+        .map_err(export_failed)?;
+    let data = sqlx::query!(
+        r#"
+        SELECT
+          jsonb_build_object(
+            'preferences', (
+              SELECT jsonb_build_object(
+                'memberAdded', member_added, 'runAssigned', run_assigned, 'runDone', run_done,
+                'requiredFailed', required_failed, 'mentioned', mentioned,
+                'runUnlocked', run_unlocked, 'updatedAt', updated_at)
+              FROM notification_preferences WHERE user_id = $1),
+            'settings', COALESCE((
+              SELECT jsonb_agg(jsonb_build_object(
+                'projectId', project_id, 'event', event, 'channel', channel,
+                'enabled', enabled, 'updatedAt', updated_at) ORDER BY updated_at)
+              FROM notification_settings WHERE user_id = $1), '[]'::jsonb),
+            'feed', COALESCE((
+              SELECT jsonb_agg(jsonb_build_object(
+                'id', id, 'kind', kind, 'projectId', project_id, 'runId', run_id,
+                'runItemId', run_item_id, 'title', title, 'body', body, 'readAt', read_at,
+                'createdAt', created_at) ORDER BY created_at)
+              FROM notifications WHERE user_id = $1), '[]'::jsonb),
+            'telegram', (
+              SELECT jsonb_build_object('chatId', chat_id, 'username', username, 'linkedAt', linked_at)
+              FROM telegram_links WHERE user_id = $1)
+          ) AS "notifications!",
+          COALESCE((
+            SELECT jsonb_agg(jsonb_build_object(
+              'id', id, 'projectId', project_id, 'name', name, 'keyPrefix', key_prefix,
+              'scopes', scopes, 'expiresAt', expires_at, 'lastUsedAt', last_used_at,
+              'revokedAt', revoked_at, 'createdAt', created_at) ORDER BY created_at)
+            FROM api_keys WHERE user_id = $1), '[]'::jsonb) AS "api_keys!",
+          COALESCE((
+            SELECT jsonb_agg(jsonb_build_object(
+              'id', id, 'projectId', project_id, 'target', target, 'name', name,
+              'params', params, 'isShared', is_shared, 'createdAt', created_at) ORDER BY created_at)
+            FROM saved_filters WHERE owner_user_id = $1), '[]'::jsonb) AS "saved_filters!",
+          COALESCE((
+            SELECT jsonb_agg(jsonb_build_object(
+              'id', id, 'projectId', project_id, 'name', name, 'definition', definition,
+              'isShared', is_shared, 'cron', cron, 'timezone', timezone,
+              'recipientUserIds', recipient_user_ids, 'createdAt', created_at) ORDER BY created_at)
+            FROM report_definitions WHERE owner_user_id = $1), '[]'::jsonb) AS "report_definitions!",
+          COALESCE((
+            SELECT jsonb_agg(jsonb_build_object(
+              'id', id, 'projectId', project_id, 'title', title, 'status', status,
+              'executor', executed_by_user_id = $1, 'lead', lead_user_id IS NOT DISTINCT FROM $1,
+              'lockedBy', locked_by_user_id IS NOT DISTINCT FROM $1,
+              'defaultAssignee', default_assignee_user_id IS NOT DISTINCT FROM $1,
+              'createdAt', created_at, 'finishedAt', finished_at) ORDER BY created_at)
+            FROM runs
+            WHERE $1 IN (executed_by_user_id, lead_user_id, locked_by_user_id,
+                         default_assignee_user_id)), '[]'::jsonb) AS "runs!",
+          COALESCE((
+            SELECT jsonb_agg(jsonb_build_object(
+              'runItemId', ri.id, 'runId', ri.run_id, 'testcaseKey', tc.key,
+              'testcaseTitle', tc.title) ORDER BY ri.run_id, tc.key)
+            FROM run_items ri
+            JOIN testcase_versions tv ON tv.id = ri.testcase_version_id
+            JOIN testcases tc ON tc.id = tv.testcase_id
+            WHERE ri.assignee_user_id = $1), '[]'::jsonb) AS "assigned_items!",
+          COALESCE((
+            SELECT jsonb_agg(jsonb_build_object(
+              'runItemId', rr.run_item_id, 'runId', ri.run_id, 'testcaseKey', tc.key,
+              'status', rr.status, 'failReasonCode', rr.fail_reason_code,
+              'comment', rr.comment, 'measuredValue', rr.measured_value,
+              'elapsedSeconds', rr.elapsed_seconds, 'executedAt', rr.executed_at,
+              'updatedAt', rr.updated_at) ORDER BY rr.updated_at)
+            FROM run_results rr
+            JOIN run_items ri ON ri.id = rr.run_item_id
+            JOIN testcase_versions tv ON tv.id = ri.testcase_version_id
+            JOIN testcases tc ON tc.id = tv.testcase_id
+            WHERE rr.updated_by_user_id = $1), '[]'::jsonb) AS "results!",
+          COALESCE((
+            SELECT jsonb_agg(jsonb_build_object(
+              'runItemId', run_item_id, 'status', status, 'previousStatus', previous_status,
+              'failReasonCode', fail_reason_code, 'comment', comment,
+              'measuredValue', measured_value, 'changedAt', changed_at) ORDER BY changed_at)
+            FROM run_result_history WHERE changed_by_user_id = $1), '[]'::jsonb)
+            AS "result_history!",
+          COALESCE((
+            SELECT jsonb_agg(jsonb_build_object(
+              'id', id, 'runId', run_id, 'runItemId', run_item_id, 'parentId', parent_id,
+              'body', body, 'createdAt', created_at, 'updatedAt', updated_at,
+              'deletedAt', deleted_at) ORDER BY created_at)
+            FROM comments WHERE author_user_id = $1), '[]'::jsonb) AS "comments!",
+          COALESCE((
+            SELECT jsonb_agg(jsonb_build_object(
+              'id', id, 'runId', run_id, 'runResultId', run_result_id, 'fileName', file_name,
+              'mimeType', mime_type, 'sizeBytes', size_bytes, 'createdAt', created_at)
+              ORDER BY created_at)
+            FROM attachments WHERE uploaded_by_user_id = $1), '[]'::jsonb) AS "attachments!",
+          COALESCE((
+            SELECT jsonb_agg(jsonb_build_object(
+              'id', id, 'runItemId', run_item_id, 'sender', sender, 'body', body,
+              'status', status, 'errorCode', error_code, 'createdAt', created_at,
+              'processedAt', processed_at) ORDER BY created_at)
+            FROM email_replies WHERE user_id = $1), '[]'::jsonb) AS "email_replies!",
+          COALESCE((
+            SELECT jsonb_agg(jsonb_build_object(
+              'id', id, 'action', action, 'entityType', entity_type, 'entityId', entity_id,
+              'projectId', context_project_id, 'runId', context_run_id,
+              'before', before_json, 'after', after_json, 'createdAt', created_at)
+              ORDER BY created_at)
+            FROM audit_log WHERE actor_user_id = $1), '[]'::jsonb) AS "audit_log!"
+        "#,
+        user_uuid,
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(export_failed)?;
+    tx.commit().await.map_err(export_failed)?;
+
+    let export = AccountExport {
+        format: EXPORT_FORMAT,
+        version: EXPORT_VERSION,
+        exported_at: now_iso(),
+        account: ExportedAccount {
+            id: user.id.clone(),
+            name: user.name.clone(),
+            email: user.email.clone(),
+            pending_email: user.pending_email.as_ref().map(|p| p.email.clone()),
+            email_verified: user.email_verified,
+            is_admin: user.is_admin,
+            created_at: user.created_at.clone(),
+            deactivated_at: user.deactivated_at.clone(),
+            oidc: user
+                .oidc
+                .as_ref()
+                .map(|o| serde_json::json!({ "issuer": o.issuer, "subject": o.subject })),
+        },
+        avatar,
+        projects,
+        organizations,
+        notifications: data.notifications,
+        api_keys: data.api_keys,
+        saved_filters: data.saved_filters,
+        report_definitions: data.report_definitions,
+        runs: data.runs,
+        assigned_items: data.assigned_items,
+        results: data.results,
+        result_history: data.result_history,
+        comments: data.comments,
+        attachments: data.attachments,
+        email_replies: data.email_replies,
+        audit_log: data.audit_log,
+    };
+    let body = serde_json::to_vec(&export).map_err(|_| ApiError::AccountExportFailed)?;
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/json".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"account-{user_id}.json\""),
+            ),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+/// Deletes the caller's account. The sign-in record, memberships, avatar, API keys, sessions,
+/// notification settings and other personal records are removed; results, comments and
+/// audit entries stay and show a deleted user as their author. The last owner of an
+/// organization has to hand it over first.
+#[utoipa::path(
+    delete,
+    path = "/api/auth/me",
+    tag = "auth",
+    request_body = DeleteAccountRequest,
+    responses((status = 204, description = "Учётная запись удалена."))
+)]
+pub async fn delete_account(
+    State(state): State<AppState>,
+    AuthUser(user_id): AuthUser,
+    Json(payload): Json<DeleteAccountRequest>,
+) -> Result<StatusCode, ApiError> {
+    let user_uuid = parse_uuid(&user_id, ApiError::InvalidUserId)?;
+    ensure_db_user_exists(&state, &user_id).await?;
+
+    let _guard = state.file_lock.lock().await;
+    let mut users = read_users(&state.users_file)
+        .await
+        .map_err(|_| ApiError::AccountDeleteFailed)?;
+    let index = users
+        .iter()
+        .position(|u| u.id == user_id)
+        .ok_or(ApiError::UserNotFound)?;
+    let user = users[index].clone();
+    let sso_only = user.password_hash.is_empty() && user.oidc.is_some();
+    let confirmed = payload
+        .current_password
+        .as_deref()
+        .is_some_and(|p| password::verify_password(p, &user.password_hash));
+    if !sso_only && !confirmed {
+        return Err(ApiError::InvalidCurrentPassword);
+    }
+    let mut organizations = organizations::read_organizations(&state.organizations_file)
+        .await
+        .map_err(|_| ApiError::AccountDeleteFailed)?;
+    if organizations
+        .iter()
+        .any(|o| o.role_of(&user_id) == Some("owner") && o.owner_count() == 1)
+    {
+        return Err(ApiError::OrganizationLastOwner);
+    }
+    let mut projects = read_projects(&state.projects_file)
+        .await
+        .map_err(|_| ApiError::AccountDeleteFailed)?;
+
+    let delete_failed = |err| db_errors::map(err, ApiError::AccountDeleteFailed);
+    let mut tx = state.db.begin().await.map_err(delete_failed)?;
+    sqlx::query!(
+        r#"
+        UPDATE users
+        SET email = $2, display_name = $3, password_hash = 'deleted', is_active = FALSE,
+            updated_at = NOW()
+        WHERE id = $1
+        "#,
+        user_uuid,
+        format!("{user_uuid}@deleted.invalid"),
+        DELETED_USER_NAME,
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(delete_failed)?;
+    sqlx::query!("DELETE FROM api_keys WHERE user_id = $1", user_uuid)
+        .execute(&mut *tx)
+        .await
+        .map_err(delete_failed)?;
+    sqlx::query!(
+        "DELETE FROM auth_refresh_tokens WHERE user_id = $1",
+        user_uuid
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(delete_failed)?;
+    sqlx::query!("DELETE FROM idempotency_keys WHERE user_id = $1", user_uuid)
+        .execute(&mut *tx)
+        .await
+        .map_err(delete_failed)?;
+    sqlx::query!(
+        "DELETE FROM notification_preferences WHERE user_id = $1",
+        user_uuid
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(delete_failed)?;
+    sqlx::query!(
+        "DELETE FROM notification_settings WHERE user_id = $1",
+        user_uuid
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(delete_failed)?;
+    sqlx::query!("DELETE FROM notifications WHERE user_id = $1", user_uuid)
+        .execute(&mut *tx)
+        .await
+        .map_err(delete_failed)?;
+    sqlx::query!("DELETE FROM telegram_links WHERE user_id = $1", user_uuid)
+        .execute(&mut *tx)
+        .await
+        .map_err(delete_failed)?;
+    sqlx::query!(
+        "DELETE FROM telegram_link_codes WHERE user_id = $1",
+        user_uuid
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(delete_failed)?;
+    sqlx::query!(
+        "DELETE FROM email_reply_tokens WHERE user_id = $1",
+        user_uuid
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(delete_failed)?;
+    sqlx::query!("DELETE FROM email_replies WHERE user_id = $1", user_uuid)
+        .execute(&mut *tx)
+        .await
+        .map_err(delete_failed)?;
+    sqlx::query!(
+        "DELETE FROM saved_filters WHERE owner_user_id = $1",
+        user_uuid
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(delete_failed)?;
+    sqlx::query!(
+        "DELETE FROM report_definitions WHERE owner_user_id = $1",
+        user_uuid
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(delete_failed)?;
+    sqlx::query!(
+        r#"
+        UPDATE report_definitions SET recipient_user_ids = array_remove(recipient_user_ids, $1)
+        WHERE $1 = ANY(recipient_user_ids)
+        "#,
+        user_uuid
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(delete_failed)?;
+    audit::record(
+        &mut *tx,
+        audit::AuditEntry {
+            actor_user_id: Some(user_uuid),
+            action: "delete",
+            entity_type: "user",
+            entity_id: Some(user_uuid),
+            project_id: None,
+            run_id: None,
+            before: None,
+            after: None,
+        },
+    )
+    .await
+    .map_err(delete_failed)?;
+    // Cached reports of locked runs print the names of the executors and of who locked them.
+    let reported_runs = sqlx::query_scalar!(
+        r#"
+        SELECT r.id
+        FROM runs r
+        WHERE r.status = 'locked'
+          AND (r.executed_by_user_id = $1
+               OR r.locked_by_user_id = $1
+               OR EXISTS (
+                 SELECT 1
+                 FROM run_items ri
+                 JOIN run_results rr ON rr.run_item_id = ri.id
+                 WHERE ri.run_id = r.id AND rr.updated_by_user_id = $1
+               ))
+        "#,
+        user_uuid
+    )
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(delete_failed)?;
+    tx.commit().await.map_err(delete_failed)?;
+    revocation::end_sessions(&state.db, &state.jwt, user_uuid)
+        .await
+        .map_err(delete_failed)?;
+
+    // Owned projects keep the id as their owner and show up as orphaned to administrators.
+    for project in projects.iter_mut() {
+        project.members.retain(|m| m.user_id != user_id);
+        project
+            .invitations
+            .retain(|i| !i.email.eq_ignore_ascii_case(&user.email));
+    }
+    write_projects(&state, &projects)
+        .await
+        .map_err(|_| ApiError::AccountDeleteFailed)?;
+    for organization in organizations.iter_mut() {
+        organization.members.retain(|m| m.user_id != user_id);
+    }
+    organizations::write_organizations(&state, &organizations)
+        .await
+        .map_err(|_| ApiError::AccountDeleteFailed)?;
+    users.remove(index);
+    write_users(&state.users_file, &users)
+        .await
+        .map_err(|_| ApiError::AccountDeleteFailed)?;
+
+    if let Some(avatar) = &user.avatar {
+        if let Err(err) = state.storage.delete(&avatar.storage_key).await {
+            warn!("avatar of deleted user {user_id} not removed from storage: {err:#}");
+        }
+    }
+    for run_id in reported_runs {
+        if let Err(err) = report::drop_cached(&state, run_id).await {
+            warn!("report of run {run_id} still names deleted user {user_id}: {err:#}");
+        }
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
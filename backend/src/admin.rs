@@ -0,0 +1,604 @@
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::warn;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::{
+    audit, authz::AdminUser, db_errors, ensure_db_user_exists, error::ApiError, notifications,
+    now_iso, pagination, parse_uuid, profile, read_projects, read_users, revocation, webhooks,
+    write_projects, write_users, AppState, Project, ProjectMember, User,
+};
+
+const PASSWORD_RESET_TTL_HOURS: i64 = 72;
+
+/// Password reset forced by an admin, stored in `users.json` until the emailed link is used.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PasswordReset {
+    pub token_hash: String,
+    pub expires_at: String,
+    pub requested_by_user_id: String,
+}
+
+impl PasswordReset {
+    pub fn is_expired(&self) -> bool {
+        chrono::DateTime::parse_from_rfc3339(&self.expires_at)
+            .map(|t| t <= chrono::Utc::now())
+            .unwrap_or(true)
+    }
+}
+
+/// Admins are the users granted the role through this API plus everyone in `ADMIN_EMAILS`;
+/// a deactivated account is never one.
+pub fn is_admin(state: &AppState, user: &User) -> bool {
+    user.deactivated_at.is_none() && (user.is_admin || state.admin_emails.contains(&user.email))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct ListUsersQuery {
+    /// Substring of the name or email, case-insensitive.
+    q: Option<String>,
+    /// `active` or `deactivated`.
+    status: Option<String>,
+    limit: Option<i64>,
+    cursor: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AdminUserView {
+    id: String,
+    name: String,
+    email: String,
+    email_verified: bool,
+    is_admin: bool,
+    /// The role comes from `ADMIN_EMAILS` and cannot be changed through the API.
+    is_config_admin: bool,
+    /// `active` or `deactivated`.
+    status: &'static str,
+    deactivated_at: Option<String>,
+    password_reset_pending: bool,
+    /// Signs in through single sign-on.
+    has_sso: bool,
+    project_count: usize,
+    created_at: String,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ListAdminUsersResponse {
+    users: Vec<AdminUserView>,
+    next_cursor: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct AdminUserResponse {
+    user: AdminUserView,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateAdminUserRequest {
+    is_admin: bool,
+}
+
+fn map_user(state: &AppState, user: &User, projects: &[Project]) -> AdminUserView {
+    AdminUserView {
+        id: user.id.clone(),
+        name: user.name.clone(),
+        email: user.email.clone(),
+        email_verified: user.email_verified,
+        is_admin: is_admin(state, user),
+        is_config_admin: state.admin_emails.contains(&user.email),
+        status: if user.deactivated_at.is_some() {
+            "deactivated"
+        } else {
+            "active"
+        },
+        deactivated_at: user.deactivated_at.clone(),
+        password_reset_pending: user.password_reset.is_some(),
+        has_sso: user.oidc.is_some(),
+        project_count: projects
+            .iter()
+            .filter(|p| p.members.iter().any(|m| m.user_id == user.id))
+            .count(),
+        created_at: user.created_at.clone(),
+    }
+}
+
+/// All accounts, oldest first.
+#[utoipa::path(
+    get,
+    path = "/api/admin/users",
+    tag = "admin",
+    params(ListUsersQuery),
+    responses((status = 200, body = ListAdminUsersResponse))
+)]
+pub async fn list_users(
+    State(state): State<AppState>,
+    AdminUser(_admin_id): AdminUser,
+    Query(query): Query<ListUsersQuery>,
+) -> Result<Json<ListAdminUsersResponse>, ApiError> {
+    let deactivated = match query.status.as_deref().map(str::trim) {
+        None | Some("") => None,
+        Some("active") => Some(false),
+        Some("deactivated") => Some(true),
+        Some(_) => return Err(ApiError::InvalidUserStatus),
+    };
+    let needle = query
+        .q
+        .as_deref()
+        .map(|q| q.trim().to_lowercase())
+        .filter(|q| !q.is_empty());
+    let limit = pagination::page_limit(query.limit);
+    let cursor = pagination::parse_cursor(query.cursor.as_deref())?;
+
+    let _guard = state.file_lock.lock().await;
+    let users = read_users(&state.users_file)
+        .await
+        .map_err(|_| ApiError::AdminUsersReadFailed)?;
+    let projects = read_projects(&state.projects_file)
+        .await
+        .map_err(|_| ApiError::AdminUsersReadFailed)?;
+
+    let mut matching: Vec<&User> = users
+        .iter()
+        .filter(|u| deactivated.is_none_or(|d| u.deactivated_at.is_some() == d))
+        .filter(|u| {
+            needle.as_ref().is_none_or(|n| {
+                u.email.contains(n.as_str()) || u.name.to_lowercase().contains(n.as_str())
+            })
+        })
+        .filter(|u| match &cursor {
+            Some(c) => (&u.created_at, &u.id) > (&c.created_at, &c.id),
+            None => true,
+        })
+        .collect();
+    matching.sort_by(|a, b| (&a.created_at, &a.id).cmp(&(&b.created_at, &b.id)));
+    matching.truncate(limit as usize + 1);
+    let (page, next_cursor) = pagination::finish_page(matching, limit, |u| pagination::Cursor {
+        created_at: u.created_at.clone(),
+        id: u.id.clone(),
+    });
+    Ok(Json(ListAdminUsersResponse {
+        users: page
+            .into_iter()
+            .map(|u| map_user(&state, u, &projects))
+            .collect(),
+        next_cursor,
+    }))
+}
+
+/// Grants or removes the admin role. Admins cannot remove their own.
+#[utoipa::path(
+    patch,
+    path = "/api/admin/users/{user_id}",
+    tag = "admin",
+    params(("user_id" = String, Path)),
+    request_body = UpdateAdminUserRequest,
+    responses((status = 200, body = AdminUserResponse))
+)]
+pub async fn update_user(
+    State(state): State<AppState>,
+    AdminUser(actor_id): AdminUser,
+    Path(user_id): Path<String>,
+    Json(payload): Json<UpdateAdminUserRequest>,
+) -> Result<Json<AdminUserResponse>, ApiError> {
+    if user_id == actor_id && !payload.is_admin {
+        return Err(ApiError::AdminSelfAction);
+    }
+    change_user(&state, &actor_id, &user_id, |state, user| {
+        if !payload.is_admin && state.admin_emails.contains(&user.email) {
+            return Err(ApiError::AdminFromConfig);
+        }
+        user.is_admin = payload.is_admin;
+        Ok(())
+    })
+    .await
+    .map(|(user, _)| Json(AdminUserResponse { user }))
+}
+
+/// Blocks the account: sign-in is refused, its tokens and API keys stop working at once.
+/// Memberships and authored data stay as they are.
+#[utoipa::path(
+    post,
+    path = "/api/admin/users/{user_id}/deactivate",
+    tag = "admin",
+    params(("user_id" = String, Path)),
+    responses((status = 200, body = AdminUserResponse))
+)]
+pub async fn deactivate_user(
+    State(state): State<AppState>,
+    AdminUser(actor_id): AdminUser,
+    Path(user_id): Path<String>,
+) -> Result<Json<AdminUserResponse>, ApiError> {
+    if user_id == actor_id {
+        return Err(ApiError::AdminSelfAction);
+    }
+    let (user, _) = change_user(&state, &actor_id, &user_id, |_, user| {
+        if user.deactivated_at.is_none() {
+            user.deactivated_at = Some(now_iso());
+        }
+        Ok(())
+    })
+    .await?;
+    set_db_active(&state, &user_id, false).await?;
+    Ok(Json(AdminUserResponse { user }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/users/{user_id}/reactivate",
+    tag = "admin",
+    params(("user_id" = String, Path)),
+    responses((status = 200, body = AdminUserResponse))
+)]
+pub async fn reactivate_user(
+    State(state): State<AppState>,
+    AdminUser(actor_id): AdminUser,
+    Path(user_id): Path<String>,
+) -> Result<Json<AdminUserResponse>, ApiError> {
+    let (user, _) = change_user(&state, &actor_id, &user_id, |_, user| {
+        user.deactivated_at = None;
+        Ok(())
+    })
+    .await?;
+    set_db_active(&state, &user_id, true).await?;
+    Ok(Json(AdminUserResponse { user }))
+}
+
+/// Ends all sessions of the user and emails a link to set a new password; until then
+/// sign-in (including single sign-on) is refused with `password_reset_required`.
+#[utoipa::path(
+    post,
+    path = "/api/admin/users/{user_id}/password-reset",
+    tag = "admin",
+    params(("user_id" = String, Path)),
+    responses((status = 200, body = AdminUserResponse))
+)]
+pub async fn force_password_reset(
+    State(state): State<AppState>,
+    AdminUser(actor_id): AdminUser,
+    Path(user_id): Path<String>,
+) -> Result<Json<AdminUserResponse>, ApiError> {
+    let token = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+    let (user, stored) = change_user(&state, &actor_id, &user_id, |_, user| {
+        user.password_reset = Some(PasswordReset {
+            token_hash: profile::hash_token(&token),
+            expires_at: (chrono::Utc::now() + chrono::Duration::hours(PASSWORD_RESET_TTL_HOURS))
+                .to_rfc3339(),
+            requested_by_user_id: actor_id.clone(),
+        });
+        Ok(())
+    })
+    .await?;
+    let user_uuid = parse_uuid(&user_id, ApiError::InvalidUserId)?;
+    revocation::end_sessions(&state.db, &state.jwt, user_uuid)
+        .await
+        .map_err(|_| ApiError::AdminUserUpdateFailed)?;
+
+    let link = format!(
+        "{}/reset-password?token={token}",
+        state.public_url.trim_end_matches('/')
+    );
+    notifications::send_transactional(
+        &state,
+        notifications::OutgoingEmail {
+            to: stored.email.clone(),
+            subject: "Uran: задайте новый пароль".to_string(),
+            body: format!(
+                "{}, администратор Uran потребовал сменить пароль вашей учётной записи. \
+                 Откройте ссылку и задайте новый пароль (действует {PASSWORD_RESET_TTL_HOURS} ч):\n\
+                 {link}\n\nДо этого войти в Uran не получится.",
+                stored.name
+            ),
+            reply_to: None,
+            attachment: None,
+        },
+    );
+    Ok(Json(AdminUserResponse { user }))
+}
+
+/// Applies `change` to the user under the file lock and records the before/after state in
+/// the audit log. Returns the admin view and the stored user.
+async fn change_user(
+    state: &AppState,
+    actor_id: &str,
+    user_id: &str,
+    change: impl FnOnce(&AppState, &mut User) -> Result<(), ApiError>,
+) -> Result<(AdminUserView, User), ApiError> {
+    let user_uuid = parse_uuid(user_id, ApiError::InvalidUserId)?;
+    let (before, after, user) = {
+        let _guard = state.file_lock.lock().await;
+        let mut users = read_users(&state.users_file)
+            .await
+            .map_err(|_| ApiError::AdminUserUpdateFailed)?;
+        let projects = read_projects(&state.projects_file)
+            .await
+            .map_err(|_| ApiError::AdminUserUpdateFailed)?;
+        let user = users
+            .iter_mut()
+            .find(|u| u.id == user_id)
+            .ok_or(ApiError::UserNotFound)?;
+        let before = map_user(state, user, &projects);
+        change(state, user)?;
+        let stored = user.clone();
+        let after = map_user(state, &stored, &projects);
+        write_users(&state.users_file, &users)
+            .await
+            .map_err(|_| ApiError::AdminUserUpdateFailed)?;
+        (before, after, stored)
+    };
+
+    ensure_db_user_exists(state, actor_id).await?;
+    ensure_db_user_exists(state, user_id).await?;
+    let recorded = audit::record(
+        &state.db,
+        audit::AuditEntry {
+            actor_user_id: Uuid::parse_str(actor_id).ok(),
+            action: "update",
+            entity_type: "user",
+            entity_id: Some(user_uuid),
+            project_id: None,
+            run_id: None,
+            before: serde_json::to_value(&before).ok(),
+            after: serde_json::to_value(&after).ok(),
+        },
+    )
+    .await;
+    if let Err(err) = recorded {
+        warn!("failed to audit the change of user {user_id}: {err}");
+    }
+    Ok((after, user))
+}
+
+/// Mirrors the state into the `users` table (API keys check it) and, on deactivation,
+/// ends the user's sessions, unlinks their Telegram chat and drops their email reply tokens.
+async fn set_db_active(state: &AppState, user_id: &str, active: bool) -> Result<(), ApiError> {
+    let user_uuid = parse_uuid(user_id, ApiError::InvalidUserId)?;
+    sqlx::query!(
+        "UPDATE users SET is_active = $2 WHERE id = $1",
+        user_uuid,
+        active,
+    )
+    .execute(&state.db)
+    .await
+    .map_err(|err| db_errors::map(err, ApiError::AdminUserUpdateFailed))?;
+    if !active {
+        revocation::end_sessions(&state.db, &state.jwt, user_uuid)
+            .await
+            .map_err(|err| db_errors::map(err, ApiError::AdminUserUpdateFailed))?;
+        sqlx::query!("DELETE FROM telegram_links WHERE user_id = $1", user_uuid)
+            .execute(&state.db)
+            .await
+            .map_err(|err| db_errors::map(err, ApiError::AdminUserUpdateFailed))?;
+        sqlx::query!(
+            "DELETE FROM email_reply_tokens WHERE user_id = $1",
+            user_uuid
+        )
+        .execute(&state.db)
+        .await
+        .map_err(|err| db_errors::map(err, ApiError::AdminUserUpdateFailed))?;
+    }
+    Ok(())
+}
+
+#[derive(Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct ListProjectsQuery {
+    /// Only projects without an active owner.
+    orphaned: Option<bool>,
+    limit: Option<i64>,
+    cursor: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AdminProjectView {
+    id: String,
+    name: String,
+    organization_id: Option<String>,
+    owner_id: String,
+    /// `active`, `deactivated` or `missing` (the account was removed).
+    owner_status: &'static str,
+    member_count: usize,
+    /// No active user is the owner or has the `owner` role.
+    orphaned: bool,
+    created_at: String,
+    updated_at: String,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ListAdminProjectsResponse {
+    projects: Vec<AdminProjectView>,
+    next_cursor: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct AdminProjectResponse {
+    project: AdminProjectView,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ReassignOwnerRequest {
+    user_id: String,
+}
+
+fn is_active_user(users: &[User], user_id: &str) -> bool {
+    users
+        .iter()
+        .any(|u| u.id == user_id && u.deactivated_at.is_none())
+}
+
+fn map_project(project: &Project, users: &[User]) -> AdminProjectView {
+    let owner_status = match users.iter().find(|u| u.id == project.owner_id) {
+        Some(u) if u.deactivated_at.is_none() => "active",
+        Some(_) => "deactivated",
+        None => "missing",
+    };
+    let has_active_owner = owner_status == "active"
+        || project
+            .members
+            .iter()
+            .any(|m| m.role == "owner" && is_active_user(users, &m.user_id));
+    AdminProjectView {
+        id: project.id.clone(),
+        name: project.name.clone(),
+        organization_id: project.organization_id.clone(),
+        owner_id: project.owner_id.clone(),
+        owner_status,
+        member_count: project.members.len(),
+        orphaned: !has_active_owner,
+        created_at: project.created_at.clone(),
+        updated_at: project.updated_at.clone(),
+    }
+}
+
+/// All projects, oldest first; `orphaned=true` keeps those left without an active owner.
+#[utoipa::path(
+    get,
+    path = "/api/admin/projects",
+    tag = "admin",
+    params(ListProjectsQuery),
+    responses((status = 200, body = ListAdminProjectsResponse))
+)]
+pub async fn list_projects(
+    State(state): State<AppState>,
+    AdminUser(_admin_id): AdminUser,
+    Query(query): Query<ListProjectsQuery>,
+) -> Result<Json<ListAdminProjectsResponse>, ApiError> {
+    let limit = pagination::page_limit(query.limit);
+    let cursor = pagination::parse_cursor(query.cursor.as_deref())?;
+
+    let _guard = state.file_lock.lock().await;
+    let users = read_users(&state.users_file)
+        .await
+        .map_err(|_| ApiError::AdminProjectsReadFailed)?;
+    let projects = read_projects(&state.projects_file)
+        .await
+        .map_err(|_| ApiError::AdminProjectsReadFailed)?;
+
+    let mut views: Vec<AdminProjectView> = projects
+        .iter()
+        .map(|p| map_project(p, &users))
+        .filter(|p| query.orphaned.is_none_or(|o| p.orphaned == o))
+        .filter(|p| match &cursor {
+            Some(c) => (&p.created_at, &p.id) > (&c.created_at, &c.id),
+            None => true,
+        })
+        .collect();
+    views.sort_by(|a, b| (&a.created_at, &a.id).cmp(&(&b.created_at, &b.id)));
+    views.truncate(limit as usize + 1);
+    let (projects, next_cursor) = pagination::finish_page(views, limit, |p| pagination::Cursor {
+        created_at: p.created_at.clone(),
+        id: p.id.clone(),
+    });
+    Ok(Json(ListAdminProjectsResponse {
+        projects,
+        next_cursor,
+    }))
+}
+
+/// Makes an active user the project owner (added as a member with the `owner` role if
+/// needed). Other members keep their roles.
+#[utoipa::path(
+    put,
+    path = "/api/admin/projects/{project_id}/owner",
+    tag = "admin",
+    params(("project_id" = String, Path)),
+    request_body = ReassignOwnerRequest,
+    responses((status = 200, body = AdminProjectResponse))
+)]
+pub async fn reassign_owner(
+    State(state): State<AppState>,
+    AdminUser(actor_id): AdminUser,
+    Path(project_id): Path<String>,
+    Json(payload): Json<ReassignOwnerRequest>,
+) -> Result<Json<AdminProjectResponse>, ApiError> {
+    let project_uuid = parse_uuid(&project_id, ApiError::InvalidProjectId)?;
+    let new_owner_id = payload.user_id.trim().to_string();
+    parse_uuid(&new_owner_id, ApiError::InvalidUserId)?;
+
+    let (before, after, new_owner, added) = {
+        let _guard = state.file_lock.lock().await;
+        let users = read_users(&state.users_file)
+            .await
+            .map_err(|_| ApiError::ProjectOwnerUpdateFailed)?;
+        let new_owner = users
+            .iter()
+            .find(|u| u.id == new_owner_id && u.deactivated_at.is_none())
+            .cloned()
+            .ok_or(ApiError::NewOwnerNotFound)?;
+        let mut projects = read_projects(&state.projects_file)
+            .await
+            .map_err(|_| ApiError::ProjectOwnerUpdateFailed)?;
+        let project = projects
+            .iter_mut()
+            .find(|p| p.id == project_id)
+            .ok_or(ApiError::ProjectNotFound)?;
+        let before = map_project(project, &users);
+        project.owner_id = new_owner_id.clone();
+        let added = match project
+            .members
+            .iter_mut()
+            .find(|m| m.user_id == new_owner_id)
+        {
+            Some(member) => {
+                member.role = "owner".to_string();
+                false
+            }
+            None => {
+                project.members.push(ProjectMember {
+                    user_id: new_owner_id.clone(),
+                    role: "owner".to_string(),
+                });
+                true
+            }
+        };
+        project.updated_at = now_iso();
+        let after = map_project(project, &users);
+        write_projects(&state, &projects)
+            .await
+            .map_err(|_| ApiError::ProjectOwnerUpdateFailed)?;
+        (before, after, new_owner, added)
+    };
+
+    ensure_db_user_exists(&state, &actor_id).await?;
+    let recorded = audit::record(
+        &state.db,
+        audit::AuditEntry {
+            actor_user_id: Uuid::parse_str(&actor_id).ok(),
+            action: "assign_role",
+            entity_type: "project",
+            entity_id: Some(project_uuid),
+            project_id: Some(project_uuid),
+            run_id: None,
+            before: serde_json::to_value(&before).ok(),
+            after: serde_json::to_value(&after).ok(),
+        },
+    )
+    .await;
+    if let Err(err) = recorded {
+        warn!("failed to audit the owner change of project {project_id}: {err}");
+    }
+    if added {
+        webhooks::emit(
+            &state,
+            project_uuid,
+            "member.added",
+            json!({ "userId": &new_owner.id, "email": &new_owner.email, "name": &new_owner.name, "role": "owner" }),
+        )
+        .await;
+    }
+    Ok(Json(AdminProjectResponse { project: after }))
+}
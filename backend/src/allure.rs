@@ -0,0 +1,320 @@
+use std::{
+    collections::{HashMap, HashSet},
+    io::{Cursor, Read},
+};
+
+use axum::body::Bytes;
+use serde::Deserialize;
+
+use crate::{
+    attachments::{sanitize_file_name, AttachmentLimits},
+    error::ApiError,
+    result_import::{
+        case_title, clip, ImportedAttachment, ImportedCase, ImportedStep, ParsedReport,
+        ReportParser, ReportSource, MAX_COMMENT_CHARS,
+    },
+};
+
+/// Caps what the archive may unpack to, whatever its compression ratio.
+const MAX_UNPACKED_BYTES: u64 = 256 * 1024 * 1024;
+const MAX_RESULT_FILE_BYTES: u64 = 16 * 1024 * 1024;
+const MAX_STEP_CHARS: usize = 1000;
+/// Nested step names are joined into one action.
+const STEP_SEPARATOR: &str = " › ";
+
+static SOURCE: ReportSource = ReportSource {
+    format: "allure",
+    name: "Allure",
+    // A zipped `allure-results` directory, attachments included.
+    max_bytes: 100 * 1024 * 1024,
+    failed: ApiError::AllureImportFailed,
+    rejected: ApiError::AllureRunCreateRejected,
+};
+
+#[derive(Deserialize, Default)]
+struct StatusDetails {
+    message: Option<String>,
+    trace: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct AllureAttachment {
+    name: Option<String>,
+    source: String,
+    #[serde(rename = "type")]
+    mime_type: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AllureStep {
+    name: Option<String>,
+    status: Option<String>,
+    #[serde(default)]
+    status_details: Option<StatusDetails>,
+    #[serde(default)]
+    steps: Vec<AllureStep>,
+    #[serde(default)]
+    attachments: Vec<AllureAttachment>,
+}
+
+/// One `*-result.json` file.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AllureResult {
+    name: Option<String>,
+    full_name: Option<String>,
+    status: Option<String>,
+    #[serde(default)]
+    status_details: Option<StatusDetails>,
+    #[serde(default)]
+    steps: Vec<AllureStep>,
+    #[serde(default)]
+    attachments: Vec<AllureAttachment>,
+    start: Option<i64>,
+    stop: Option<i64>,
+}
+
+fn map_status(status: Option<&str>) -> &'static str {
+    match status.unwrap_or_default() {
+        "passed" => "ok",
+        "failed" | "broken" => "fail",
+        "skipped" => "skipped",
+        _ => "na",
+    }
+}
+
+fn details_comment(details: Option<&StatusDetails>, max_chars: usize) -> String {
+    let Some(details) = details else {
+        return String::new();
+    };
+    let message = details.message.as_deref().unwrap_or_default().trim();
+    let trace = details.trace.as_deref().unwrap_or_default().trim();
+    let comment = match (message.is_empty(), trace.is_empty()) {
+        (false, false) => format!("{message}\n\n{trace}"),
+        (false, true) => message.to_string(),
+        _ => trace.to_string(),
+    };
+    clip(&comment, max_chars)
+}
+
+fn basename(path: &str) -> &str {
+    path.rsplit(['/', '\\']).next().unwrap_or(path)
+}
+
+struct Archive {
+    zip: zip::ZipArchive<Cursor<Bytes>>,
+    /// Entry index by file name without directories; attachments are referenced that way.
+    by_name: HashMap<String, usize>,
+    unpacked: u64,
+}
+
+impl Archive {
+    fn open(body: Bytes) -> Result<Self, ApiError> {
+        let mut zip =
+            zip::ZipArchive::new(Cursor::new(body)).map_err(|_| ApiError::InvalidAllureArchive)?;
+        let mut by_name = HashMap::new();
+        for idx in 0..zip.len() {
+            let entry = zip
+                .by_index_raw(idx)
+                .map_err(|_| ApiError::InvalidAllureArchive)?;
+            if entry.is_file() {
+                by_name.insert(basename(entry.name()).to_string(), idx);
+            }
+        }
+        Ok(Self {
+            zip,
+            by_name,
+            unpacked: 0,
+        })
+    }
+
+    /// Entry contents, or `None` when it is larger than `max_bytes`.
+    fn read(&mut self, idx: usize, max_bytes: u64) -> Result<Option<Vec<u8>>, ApiError> {
+        let entry = self
+            .zip
+            .by_index(idx)
+            .map_err(|_| ApiError::InvalidAllureArchive)?;
+        let mut data = Vec::new();
+        entry
+            .take(max_bytes + 1)
+            .read_to_end(&mut data)
+            .map_err(|_| ApiError::InvalidAllureArchive)?;
+        self.unpacked += data.len() as u64;
+        if self.unpacked > MAX_UNPACKED_BYTES {
+            return Err(ApiError::AllureArchiveTooLarge);
+        }
+        Ok((data.len() as u64 <= max_bytes).then_some(data))
+    }
+}
+
+struct CaseBuilder<'a> {
+    archive: &'a mut Archive,
+    limits: &'a AttachmentLimits,
+    steps: Vec<ImportedStep>,
+    attachments: Vec<ImportedAttachment>,
+    sources: HashSet<String>,
+    skipped_attachments: usize,
+}
+
+impl CaseBuilder<'_> {
+    /// Flattens nested steps depth first; a nested step's action carries its parents' names.
+    fn add_steps(&mut self, steps: &[AllureStep], prefix: &str) -> Result<(), ApiError> {
+        for step in steps {
+            let name = step.name.as_deref().unwrap_or_default().trim();
+            let action = match (prefix.is_empty(), name.is_empty()) {
+                (_, true) => prefix.to_string(),
+                (true, false) => name.to_string(),
+                (false, false) => format!("{prefix}{STEP_SEPARATOR}{name}"),
+            };
+            if !name.is_empty() {
+                self.steps.push(ImportedStep {
+                    action: clip(&action, MAX_STEP_CHARS),
+                    expected: String::new(),
+                    status: map_status(step.status.as_deref()),
+                    comment: details_comment(step.status_details.as_ref(), MAX_STEP_CHARS),
+                });
+            }
+            self.add_attachments(&step.attachments)?;
+            self.add_steps(&step.steps, &action)?;
+        }
+        Ok(())
+    }
+
+    /// Reads the referenced files; missing, disallowed or oversized ones are counted as
+    /// skipped.
+    fn add_attachments(&mut self, attachments: &[AllureAttachment]) -> Result<(), ApiError> {
+        for attachment in attachments {
+            let source = basename(&attachment.source);
+            if !self.sources.insert(source.to_string()) {
+                continue;
+            }
+            let mime_type = attachment
+                .mime_type
+                .as_deref()
+                .map(str::trim)
+                .filter(|t| !t.is_empty())
+                .unwrap_or("application/octet-stream");
+            let idx = self.archive.by_name.get(source).copied();
+            let data = match idx {
+                Some(idx) if self.limits.allows(mime_type) => self
+                    .archive
+                    .read(idx, self.limits.max_bytes as u64)?
+                    .filter(|data| !data.is_empty()),
+                _ => None,
+            };
+            let Some(data) = data else {
+                self.skipped_attachments += 1;
+                continue;
+            };
+            let name = attachment.name.as_deref().map(str::trim).unwrap_or("");
+            let file_name = match source.rsplit_once('.') {
+                _ if name.is_empty() => source.to_string(),
+                Some((_, ext)) if !name.contains('.') => format!("{name}.{ext}"),
+                _ => name.to_string(),
+            };
+            self.attachments.push(ImportedAttachment {
+                file_name: sanitize_file_name(&file_name),
+                mime_type: mime_type.to_string(),
+                data: Bytes::from(data),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Reads every `*-result.json` of the archive. Retries of a test share its `fullName`; the
+/// one that finished last is kept. Cases come out in the order the tests started.
+fn parse_archive(body: Bytes, limits: &AttachmentLimits) -> Result<ParsedReport, ApiError> {
+    let mut archive = Archive::open(body)?;
+    let mut result_files: Vec<(String, usize)> = archive
+        .by_name
+        .iter()
+        .filter(|(name, _)| name.ends_with("-result.json"))
+        .map(|(name, idx)| (name.clone(), *idx))
+        .collect();
+    result_files.sort();
+
+    let mut latest: HashMap<String, AllureResult> = HashMap::new();
+    for (_, idx) in result_files {
+        let data = archive
+            .read(idx, MAX_RESULT_FILE_BYTES)?
+            .ok_or(ApiError::InvalidAllureArchive)?;
+        let result: AllureResult =
+            serde_json::from_slice(&data).map_err(|_| ApiError::InvalidAllureArchive)?;
+        let name = result.name.as_deref().unwrap_or_default().trim();
+        let key = match result.full_name.as_deref().map(str::trim) {
+            Some(full_name) if !full_name.is_empty() => full_name.to_string(),
+            _ if !name.is_empty() => name.to_string(),
+            _ => continue,
+        };
+        match latest.get(&key) {
+            Some(kept) if kept.stop.unwrap_or(0) >= result.stop.unwrap_or(0) => {}
+            _ => {
+                latest.insert(key, result);
+            }
+        }
+    }
+    if latest.is_empty() {
+        return Err(ApiError::AllureArchiveEmpty);
+    }
+
+    let mut results: Vec<(String, AllureResult)> = latest.into_iter().collect();
+    results.sort_by(|(a_key, a), (b_key, b)| (a.start, a_key).cmp(&(b.start, b_key)));
+    let mut cases = Vec::with_capacity(results.len());
+    let mut skipped_attachments = 0;
+    for (key, result) in results {
+        let mut builder = CaseBuilder {
+            archive: &mut archive,
+            limits,
+            steps: Vec::new(),
+            attachments: Vec::new(),
+            sources: HashSet::new(),
+            skipped_attachments: 0,
+        };
+        builder.add_steps(&result.steps, "")?;
+        builder.add_attachments(&result.attachments)?;
+        let CaseBuilder {
+            steps,
+            attachments,
+            skipped_attachments: skipped,
+            ..
+        } = builder;
+        skipped_attachments += skipped;
+        let elapsed_seconds = match (result.start, result.stop) {
+            (Some(start), Some(stop)) if stop >= start => {
+                Some(((stop - start + 500) / 1000).min(i32::MAX as i64) as i32)
+            }
+            _ => None,
+        };
+        cases.push(ImportedCase {
+            title: case_title(result.name.as_deref().unwrap_or_default(), &key),
+            key,
+            source_path: None,
+            status: map_status(result.status.as_deref()),
+            comment: details_comment(result.status_details.as_ref(), MAX_COMMENT_CHARS),
+            elapsed_seconds,
+            steps,
+            attachments,
+        });
+    }
+    Ok(ParsedReport {
+        cases,
+        skipped_attachments,
+    })
+}
+
+/// Zipped `allure-results` directory. Tests are matched to testcases by `fullName`; steps
+/// become step results of the testcase version, and the attachments of a test and its steps
+/// are attached to its result when their type and size are allowed for uploads.
+pub struct AllureParser;
+
+impl ReportParser for AllureParser {
+    fn source(&self) -> &'static ReportSource {
+        &SOURCE
+    }
+
+    fn parse(&self, body: Bytes, limits: &AttachmentLimits) -> Result<ParsedReport, ApiError> {
+        parse_archive(body, limits)
+    }
+}
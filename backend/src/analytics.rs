@@ -0,0 +1,319 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::Row;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::{authz::ProjectRole, cache::CacheStats, error::ApiError, now_iso, AppState};
+
+const DEFAULT_DAYS: i64 = 30;
+const MAX_DAYS: i64 = 365;
+const ROLLING_WINDOW_DAYS: i64 = 7;
+const MOST_FAILING_LIMIT: i64 = 10;
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AnalyticsQuery {
+    /// Period in days ending today, 1-365 (default 30).
+    days: Option<i64>,
+}
+
+#[derive(Serialize, Clone, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PassRatePoint {
+    date: String,
+    ok: i64,
+    fail: i64,
+    /// `ok / (ok + fail)` in percent; `null` on days without results.
+    pass_rate: Option<f64>,
+    /// The same over the last 7 days including this one.
+    rolling_pass_rate: Option<f64>,
+}
+
+#[derive(Serialize, Clone, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct FailingTestcase {
+    testcase_id: String,
+    key: String,
+    title: String,
+    fail_count: i64,
+    executed_count: i64,
+    /// Dense rank by `failCount`; ties share a rank.
+    rank: i64,
+}
+
+#[derive(Serialize, Clone, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RunsByStatus {
+    draft: i64,
+    in_progress: i64,
+    done: i64,
+    locked: i64,
+}
+
+#[derive(Serialize, Clone, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AnalyticsResponse {
+    project_id: String,
+    days: i64,
+    pass_rate_trend: Vec<PassRatePoint>,
+    most_failing: Vec<FailingTestcase>,
+    /// Mean `finishedAt - startedAt` of runs finished in the period.
+    average_run_duration_seconds: Option<f64>,
+    /// All runs of the project regardless of the period.
+    runs_by_status: RunsByStatus,
+    generated_at: String,
+}
+
+/// Computed dashboards per `(project, days)`. The queries scan every result of the period,
+/// so repeated dashboard loads within the TTL are served from memory.
+pub struct AnalyticsCache {
+    ttl: Duration,
+    entries: Mutex<HashMap<(Uuid, i64), (Instant, AnalyticsResponse)>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl AnalyticsCache {
+    /// A zero `ttl` disables caching.
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    fn get(&self, key: (Uuid, i64)) -> Option<AnalyticsResponse> {
+        if self.ttl.is_zero() {
+            return None;
+        }
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let response = entries
+            .get(&key)
+            .filter(|(stored_at, _)| stored_at.elapsed() < self.ttl)
+            .map(|(_, response)| response.clone());
+        let counter = if response.is_some() {
+            &self.hits
+        } else {
+            &self.misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        response
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        CacheStats::new(
+            "analytics",
+            self.ttl,
+            entries.len() as u64,
+            self.hits.load(Ordering::Relaxed),
+            self.misses.load(Ordering::Relaxed),
+        )
+    }
+
+    fn put(&self, key: (Uuid, i64), response: AnalyticsResponse) {
+        if self.ttl.is_zero() {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.retain(|_, (stored_at, _)| stored_at.elapsed() < self.ttl);
+        entries.insert(key, (Instant::now(), response));
+    }
+}
+
+fn percent(ok: i64, total: i64) -> Option<f64> {
+    (total > 0).then(|| (ok as f64 * 1000.0 / total as f64).round() / 10.0)
+}
+
+async fn compute(
+    state: &AppState,
+    project_id: Uuid,
+    days: i64,
+) -> Result<AnalyticsResponse, ApiError> {
+    let failed = |_| ApiError::AnalyticsFailed;
+
+    // The window needs the days before the period too, so the series starts earlier and
+    // is cut to the period afterwards.
+    let trend_sql = format!(
+        r#"
+        WITH daily AS (
+          SELECT
+            rr.updated_at::date AS day,
+            COUNT(*) FILTER (WHERE rr.status = 'ok') AS ok,
+            COUNT(*) FILTER (WHERE rr.status = 'fail') AS fail
+          FROM run_results rr
+          JOIN run_items ri ON ri.id = rr.run_item_id
+          JOIN runs r ON r.id = ri.run_id
+          WHERE r.project_id = $1 AND rr.updated_at >= CURRENT_DATE - ($2::int + {preceding} - 1)
+          GROUP BY 1
+        ),
+        series AS (
+          SELECT
+            d::date AS day,
+            COALESCE(daily.ok, 0) AS ok,
+            COALESCE(daily.fail, 0) AS fail,
+            SUM(COALESCE(daily.ok, 0)) OVER w AS rolling_ok,
+            SUM(COALESCE(daily.ok, 0) + COALESCE(daily.fail, 0)) OVER w AS rolling_total
+          FROM generate_series(
+            CURRENT_DATE - ($2::int + {preceding} - 1), CURRENT_DATE, INTERVAL '1 day'
+          ) d
+          LEFT JOIN daily ON daily.day = d::date
+          WINDOW w AS (ORDER BY d ROWS BETWEEN {preceding} PRECEDING AND CURRENT ROW)
+        )
+        SELECT
+          day::text AS day,
+          ok,
+          fail,
+          rolling_ok::bigint AS rolling_ok,
+          rolling_total::bigint AS rolling_total
+        FROM series
+        WHERE day > CURRENT_DATE - $2::int
+        ORDER BY day ASC
+        "#,
+        preceding = ROLLING_WINDOW_DAYS - 1
+    );
+    let trend_rows = sqlx::query(&trend_sql)
+        .bind(project_id)
+        .bind(days as i32)
+        .fetch_all(state.read_db())
+        .await
+        .map_err(failed)?;
+
+    let failing_rows = sqlx::query(
+        r#"
+        SELECT
+          tc.id::text AS testcase_id,
+          tc.key,
+          tc.title,
+          COUNT(*) FILTER (WHERE rr.status = 'fail') AS fail_count,
+          COUNT(*) AS executed_count,
+          DENSE_RANK() OVER (ORDER BY COUNT(*) FILTER (WHERE rr.status = 'fail') DESC) AS rank
+        FROM run_results rr
+        JOIN run_items ri ON ri.id = rr.run_item_id
+        JOIN runs r ON r.id = ri.run_id
+        JOIN testcase_versions tv ON tv.id = ri.testcase_version_id
+        JOIN testcases tc ON tc.id = tv.testcase_id
+        WHERE r.project_id = $1
+          AND rr.status IN ('ok', 'fail')
+          AND rr.updated_at >= CURRENT_DATE - ($2::int - 1)
+        GROUP BY tc.id, tc.key, tc.title
+        HAVING COUNT(*) FILTER (WHERE rr.status = 'fail') > 0
+        ORDER BY fail_count DESC, tc.key ASC
+        LIMIT $3
+        "#,
+    )
+    .bind(project_id)
+    .bind(days as i32)
+    .bind(MOST_FAILING_LIMIT)
+    .fetch_all(state.read_db())
+    .await
+    .map_err(failed)?;
+
+    let runs_row = sqlx::query(
+        r#"
+        SELECT
+          COUNT(*) FILTER (WHERE status = 'draft') AS draft,
+          COUNT(*) FILTER (WHERE status = 'in_progress') AS in_progress,
+          COUNT(*) FILTER (WHERE status = 'done') AS done,
+          COUNT(*) FILTER (WHERE status = 'locked') AS locked,
+          AVG(EXTRACT(EPOCH FROM finished_at - started_at)) FILTER (
+            WHERE finished_at IS NOT NULL
+              AND started_at IS NOT NULL
+              AND finished_at >= CURRENT_DATE - ($2::int - 1)
+          )::float8 AS average_duration
+        FROM runs
+        WHERE project_id = $1
+        "#,
+    )
+    .bind(project_id)
+    .bind(days as i32)
+    .fetch_one(state.read_db())
+    .await
+    .map_err(failed)?;
+
+    Ok(AnalyticsResponse {
+        project_id: project_id.to_string(),
+        days,
+        pass_rate_trend: trend_rows
+            .iter()
+            .map(|row| {
+                let ok = row.get::<i64, _>("ok");
+                let fail = row.get::<i64, _>("fail");
+                PassRatePoint {
+                    date: row.get("day"),
+                    ok,
+                    fail,
+                    pass_rate: percent(ok, ok + fail),
+                    rolling_pass_rate: percent(
+                        row.get::<i64, _>("rolling_ok"),
+                        row.get::<i64, _>("rolling_total"),
+                    ),
+                }
+            })
+            .collect(),
+        most_failing: failing_rows
+            .iter()
+            .map(|row| FailingTestcase {
+                testcase_id: row.get("testcase_id"),
+                key: row.get("key"),
+                title: row.get("title"),
+                fail_count: row.get("fail_count"),
+                executed_count: row.get("executed_count"),
+                rank: row.get("rank"),
+            })
+            .collect(),
+        average_run_duration_seconds: runs_row
+            .get::<Option<f64>, _>("average_duration")
+            .map(f64::round),
+        runs_by_status: RunsByStatus {
+            draft: runs_row.get("draft"),
+            in_progress: runs_row.get("in_progress"),
+            done: runs_row.get("done"),
+            locked: runs_row.get("locked"),
+        },
+        generated_at: now_iso(),
+    })
+}
+
+/// Project dashboard: daily pass rate with a 7-day rolling average, the test cases failing
+/// most often, mean run duration and run counts by status. Cached for
+/// `ANALYTICS_CACHE_TTL_SECONDS`, so fresh results may appear with that delay.
+#[utoipa::path(
+    get,
+    path = "/api/v2/projects/{project_id}/analytics",
+    tag = "analytics",
+    params(("project_id" = String, Path), AnalyticsQuery),
+    responses((status = 200, body = AnalyticsResponse))
+)]
+pub async fn project_analytics(
+    State(state): State<AppState>,
+    access: ProjectRole,
+    Query(query): Query<AnalyticsQuery>,
+) -> Result<Json<AnalyticsResponse>, ApiError> {
+    let days = query.days.unwrap_or(DEFAULT_DAYS);
+    if !(1..=MAX_DAYS).contains(&days) {
+        return Err(ApiError::InvalidAnalyticsPeriod);
+    }
+    let key = (access.project_id, days);
+    if let Some(cached) = state.analytics.get(key) {
+        return Ok(Json(cached));
+    }
+    let response = compute(&state, access.project_id, days).await?;
+    state.analytics.put(key, response.clone());
+    Ok(Json(response))
+}
@@ -0,0 +1,456 @@
+use std::future::Future;
+
+use axum::{
+    extract::{Path, Request, State},
+    http::{header::AUTHORIZATION, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use sqlx::Row;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{
+    audit,
+    authz::{self, AuthUser},
+    ensure_db_user_exists,
+    error::ApiError,
+    parse_uuid,
+    permissions::Capability,
+    AppState,
+};
+
+/// Distinguishes API keys from JWT access tokens in the `Authorization` header.
+pub const KEY_PREFIX: &str = "uran_";
+pub const SCOPES: [&str; 2] = ["read", "write"];
+
+/// Characters of the key kept in plain text so users can tell their keys apart.
+const DISPLAY_PREFIX_LEN: usize = 12;
+
+/// What a request authenticated with an API key is allowed to touch.
+#[derive(Clone)]
+pub struct ApiKeyGrant {
+    pub user_id: String,
+    pub project_id: Uuid,
+    pub scopes: Vec<String>,
+}
+
+tokio::task_local! {
+    static GRANT: ApiKeyGrant;
+}
+
+/// The API key grant of the current request, if it was authenticated with a key.
+pub fn current() -> Option<ApiKeyGrant> {
+    GRANT.try_with(Clone::clone).ok()
+}
+
+impl ApiKeyGrant {
+    /// Narrows a capability check: the key works only in its project and within its scopes;
+    /// `write` covers everything except locking runs and managing the project.
+    pub fn check(&self, project_id: &str, capability: Capability) -> Result<(), ApiError> {
+        if Uuid::parse_str(project_id).ok() != Some(self.project_id) {
+            return Err(ApiError::ApiKeyWrongProject);
+        }
+        let has = |scope: &str| self.scopes.iter().any(|s| s == scope);
+        let allowed = match capability {
+            Capability::ProjectRead => has("read") || has("write"),
+            Capability::RunLock | Capability::ProjectManage => false,
+            _ => has("write"),
+        };
+        if !allowed {
+            return Err(ApiError::ApiKeyScopeDenied);
+        }
+        Ok(())
+    }
+}
+
+fn hash_key(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
+
+/// Runs `task` as a request authenticated with `grant`, for callers that do not go through
+/// [`authenticate`], such as the gRPC service.
+pub async fn scope<F: Future>(grant: Option<ApiKeyGrant>, task: F) -> F::Output {
+    match grant {
+        Some(grant) => GRANT.scope(grant, task).await,
+        None => task.await,
+    }
+}
+
+/// Resolves `Authorization: Bearer uran_...` before the handlers run. Requests with a JWT or
+/// without credentials pass through untouched; API keys are only accepted on `/api/v2`.
+pub async fn authenticate(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let key = request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(str::trim)
+        .filter(|v| v.starts_with(KEY_PREFIX))
+        .map(str::to_string);
+    let Some(key) = key else {
+        return next.run(request).await;
+    };
+    if !request.uri().path().starts_with("/api/v2/") {
+        return ApiError::ApiKeyOutsideV2.into_response();
+    }
+    match resolve_key(&state, &key).await {
+        Ok(grant) => GRANT.scope(grant, next.run(request)).await,
+        Err(err) => err.into_response(),
+    }
+}
+
+pub async fn resolve_key(state: &AppState, key: &str) -> Result<ApiKeyGrant, ApiError> {
+    sqlx::query_as!(
+        ApiKeyGrant,
+        r#"
+        UPDATE api_keys
+        SET last_used_at = NOW()
+        WHERE key_hash = $1
+          AND revoked_at IS NULL
+          AND (expires_at IS NULL OR expires_at > NOW())
+          AND NOT EXISTS (SELECT 1 FROM users u WHERE u.id = api_keys.user_id AND NOT u.is_active)
+        RETURNING user_id::text AS "user_id!", project_id, scopes
+        "#,
+        hash_key(key),
+    )
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|_| ApiError::ApiKeyCheckFailed)?
+    .ok_or(ApiError::InvalidApiKey)
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateApiKeyRequest {
+    name: String,
+    project_id: String,
+    scopes: Vec<String>,
+    expires_at: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiKeyView {
+    id: String,
+    project_id: String,
+    name: String,
+    key_prefix: String,
+    scopes: Vec<String>,
+    expires_at: Option<String>,
+    last_used_at: Option<String>,
+    revoked_at: Option<String>,
+    created_at: String,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateApiKeyResponse {
+    api_key: ApiKeyView,
+    /// Returned only once; only its hash is stored.
+    key: String,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ListApiKeysResponse {
+    api_keys: Vec<ApiKeyView>,
+}
+
+const API_KEY_COLUMNS: &str = r#"
+  id::text AS id,
+  project_id::text AS project_id,
+  name,
+  key_prefix,
+  scopes,
+  expires_at::text AS expires_at,
+  last_used_at::text AS last_used_at,
+  revoked_at::text AS revoked_at,
+  created_at::text AS created_at
+"#;
+
+fn map_api_key_row(r: &sqlx::postgres::PgRow) -> ApiKeyView {
+    ApiKeyView {
+        id: r.get::<String, _>("id"),
+        project_id: r.get::<String, _>("project_id"),
+        name: r.get::<String, _>("name"),
+        key_prefix: r.get::<String, _>("key_prefix"),
+        scopes: r.get::<Vec<String>, _>("scopes"),
+        expires_at: r.get::<Option<String>, _>("expires_at"),
+        last_used_at: r.get::<Option<String>, _>("last_used_at"),
+        revoked_at: r.get::<Option<String>, _>("revoked_at"),
+        created_at: r.get::<String, _>("created_at"),
+    }
+}
+
+/// Name, scopes and expiry of a new key, checked.
+pub struct KeySpec {
+    name: String,
+    scopes: Vec<String>,
+    expires_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl KeySpec {
+    pub fn parse(
+        name: &str,
+        scopes: &[String],
+        expires_at: Option<&str>,
+    ) -> Result<Self, ApiError> {
+        let name = name.trim().to_string();
+        if name.is_empty() || name.chars().count() > 200 {
+            return Err(ApiError::InvalidApiKeyName);
+        }
+        let mut checked: Vec<String> = Vec::new();
+        for scope in scopes.iter().map(|s| s.trim()) {
+            if !SCOPES.contains(&scope) {
+                return Err(ApiError::InvalidApiKeyScope);
+            }
+            if !checked.iter().any(|s| s == scope) {
+                checked.push(scope.to_string());
+            }
+        }
+        if checked.is_empty() {
+            return Err(ApiError::ApiKeyScopesEmpty);
+        }
+        let expires_at = match expires_at.map(str::trim) {
+            Some(v) if !v.is_empty() => {
+                let parsed = chrono::DateTime::parse_from_rfc3339(v)
+                    .map_err(|_| ApiError::InvalidApiKeyExpiry)?;
+                if parsed <= chrono::Utc::now() {
+                    return Err(ApiError::ApiKeyExpiryInPast);
+                }
+                Some(parsed.with_timezone(&chrono::Utc))
+            }
+            _ => None,
+        };
+        Ok(Self {
+            name,
+            scopes: checked,
+            expires_at,
+        })
+    }
+}
+
+/// Mints a key for the caller. The key acts as its owner, so every request is checked against
+/// both the key scopes and the owner's current role in the project.
+#[utoipa::path(
+    post,
+    path = "/api/auth/api-keys",
+    tag = "api-keys",
+    request_body = CreateApiKeyRequest,
+    responses((status = 201, body = CreateApiKeyResponse))
+)]
+pub async fn create_api_key(
+    State(state): State<AppState>,
+    AuthUser(actor_id): AuthUser,
+    Json(payload): Json<CreateApiKeyRequest>,
+) -> Result<(StatusCode, Json<CreateApiKeyResponse>), ApiError> {
+    let project_uuid = parse_uuid(&payload.project_id, ApiError::InvalidProjectIdParam)?;
+    let spec = KeySpec::parse(
+        &payload.name,
+        &payload.scopes,
+        payload.expires_at.as_deref(),
+    )?;
+    authz::require_capability(
+        &state,
+        &project_uuid.to_string(),
+        &actor_id,
+        Capability::ProjectRead,
+    )
+    .await?;
+    ensure_db_user_exists(&state, &actor_id).await?;
+    let actor_uuid = parse_uuid(&actor_id, ApiError::InvalidUserId)?;
+    let created = insert_key(&state, actor_uuid, actor_uuid, project_uuid, spec).await?;
+    Ok((StatusCode::CREATED, Json(created)))
+}
+
+/// Stores a new key of `owner_uuid` in the project; `actor_uuid` is who minted it (an admin
+/// for service accounts).
+pub async fn insert_key(
+    state: &AppState,
+    actor_uuid: Uuid,
+    owner_uuid: Uuid,
+    project_uuid: Uuid,
+    spec: KeySpec,
+) -> Result<CreateApiKeyResponse, ApiError> {
+    let KeySpec {
+        name,
+        scopes,
+        expires_at,
+    } = spec;
+    let key = format!(
+        "{KEY_PREFIX}{}{}",
+        Uuid::new_v4().simple(),
+        Uuid::new_v4().simple()
+    );
+    let mut tx = state
+        .db
+        .begin()
+        .await
+        .map_err(|_| ApiError::ApiKeyCreateFailed)?;
+    let sql = format!(
+        r#"
+        INSERT INTO api_keys (user_id, project_id, name, key_prefix, key_hash, scopes, expires_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        RETURNING {API_KEY_COLUMNS}
+        "#
+    );
+    let row = sqlx::query(&sql)
+        .bind(owner_uuid)
+        .bind(project_uuid)
+        .bind(&name)
+        .bind(&key[..DISPLAY_PREFIX_LEN])
+        .bind(hash_key(&key))
+        .bind(&scopes)
+        .bind(expires_at)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|_| ApiError::ApiKeyRejected)?;
+    let api_key = map_api_key_row(&row);
+    let mut after = json!({ "name": &name, "scopes": &scopes, "keyPrefix": &api_key.key_prefix });
+    if owner_uuid != actor_uuid {
+        after["userId"] = json!(owner_uuid);
+    }
+    audit::record(
+        &mut *tx,
+        audit::AuditEntry {
+            actor_user_id: Some(actor_uuid),
+            action: "create",
+            entity_type: "api_key",
+            entity_id: Uuid::parse_str(&api_key.id).ok(),
+            project_id: Some(project_uuid),
+            run_id: None,
+            before: None,
+            after: Some(after),
+        },
+    )
+    .await
+    .map_err(|_| ApiError::ApiKeyCreateFailed)?;
+    tx.commit()
+        .await
+        .map_err(|_| ApiError::ApiKeyCreateFailed)?;
+
+    Ok(CreateApiKeyResponse { api_key, key })
+}
+
+/// The caller's own keys, including revoked and expired ones.
+#[utoipa::path(
+    get,
+    path = "/api/auth/api-keys",
+    tag = "api-keys",
+    responses((status = 200, body = ListApiKeysResponse))
+)]
+pub async fn list_api_keys(
+    State(state): State<AppState>,
+    AuthUser(actor_id): AuthUser,
+) -> Result<Json<ListApiKeysResponse>, ApiError> {
+    let actor_uuid = parse_uuid(&actor_id, ApiError::InvalidUserId)?;
+    list_keys(&state, actor_uuid).await.map(Json)
+}
+
+/// All keys of `owner_uuid`, newest first.
+pub async fn list_keys(
+    state: &AppState,
+    owner_uuid: Uuid,
+) -> Result<ListApiKeysResponse, ApiError> {
+    let sql = format!(
+        r#"
+        SELECT {API_KEY_COLUMNS}
+        FROM api_keys
+        WHERE user_id = $1
+        ORDER BY created_at DESC, id DESC
+        "#
+    );
+    let rows = sqlx::query(&sql)
+        .bind(owner_uuid)
+        .fetch_all(&state.db)
+        .await
+        .map_err(|_| ApiError::ApiKeysReadFailed)?;
+
+    Ok(ListApiKeysResponse {
+        api_keys: rows.iter().map(map_api_key_row).collect(),
+    })
+}
+
+/// Revokes one of the caller's keys; revoking an already revoked key is a no-op.
+#[utoipa::path(
+    delete,
+    path = "/api/auth/api-keys/{key_id}",
+    tag = "api-keys",
+    params(("key_id" = String, Path)),
+    responses((status = 200, body = ApiKeyView))
+)]
+pub async fn revoke_api_key(
+    State(state): State<AppState>,
+    Path(key_id): Path<String>,
+    AuthUser(actor_id): AuthUser,
+) -> Result<Json<ApiKeyView>, ApiError> {
+    let actor_uuid = parse_uuid(&actor_id, ApiError::InvalidUserId)?;
+    let key_uuid = parse_uuid(&key_id, ApiError::InvalidApiKeyId)?;
+    revoke_key(&state, actor_uuid, actor_uuid, key_uuid)
+        .await
+        .map(Json)
+}
+
+/// Revokes a key of `owner_uuid` on behalf of `actor_uuid`; revoking twice is a no-op.
+pub async fn revoke_key(
+    state: &AppState,
+    actor_uuid: Uuid,
+    owner_uuid: Uuid,
+    key_uuid: Uuid,
+) -> Result<ApiKeyView, ApiError> {
+    let mut tx = state
+        .db
+        .begin()
+        .await
+        .map_err(|_| ApiError::ApiKeyRevokeFailed)?;
+    let was_revoked: Option<bool> = sqlx::query_scalar(
+        r#"SELECT revoked_at IS NOT NULL FROM api_keys WHERE id = $1 AND user_id = $2 FOR UPDATE"#,
+    )
+    .bind(key_uuid)
+    .bind(owner_uuid)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|_| ApiError::ApiKeyReadFailed)?;
+    let was_revoked = was_revoked.ok_or(ApiError::ApiKeyNotFound)?;
+    let sql = format!(
+        r#"
+        UPDATE api_keys
+        SET revoked_at = COALESCE(revoked_at, NOW())
+        WHERE id = $1
+        RETURNING {API_KEY_COLUMNS}
+        "#
+    );
+    let row = sqlx::query(&sql)
+        .bind(key_uuid)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|_| ApiError::ApiKeyRevokeFailed)?;
+    let api_key = map_api_key_row(&row);
+    if !was_revoked {
+        audit::record(
+            &mut *tx,
+            audit::AuditEntry {
+                actor_user_id: Some(actor_uuid),
+                action: "update",
+                entity_type: "api_key",
+                entity_id: Some(key_uuid),
+                project_id: Uuid::parse_str(&api_key.project_id).ok(),
+                run_id: None,
+                before: Some(json!({ "revokedAt": null })),
+                after: Some(json!({ "revokedAt": &api_key.revoked_at })),
+            },
+        )
+        .await
+        .map_err(|_| ApiError::ApiKeyRevokeFailed)?;
+    }
+    tx.commit()
+        .await
+        .map_err(|_| ApiError::ApiKeyRevokeFailed)?;
+
+    Ok(api_key)
+}
@@ -0,0 +1,640 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use sqlx::Row;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::{
+    audit, authz::ProjectRole, db_errors, ensure_db_user_exists, error::ApiError, parse_uuid,
+    permissions::Capability, AppState,
+};
+
+const MAX_NAME_CHARS: usize = 200;
+const MAX_TYPE_CHARS: usize = 60;
+const MAX_FIELD_CHARS: usize = 200;
+
+#[derive(Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct ListAssetsQuery {
+    asset_type: Option<String>,
+    /// Also list deactivated assets (default `false`).
+    include_inactive: Option<bool>,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateAssetRequest {
+    name: String,
+    /// Free-form kind, e.g. `camera`, `firmware`, `stand`.
+    asset_type: String,
+    version: Option<String>,
+    model: Option<String>,
+    serial_number: Option<String>,
+    location_name: Option<String>,
+    stand_name: Option<String>,
+    #[schema(value_type = Option<Object>)]
+    metadata: Option<Value>,
+    /// Kept with the first entry of the version history.
+    version_note: Option<String>,
+}
+
+/// Partial update; a changed `version` adds an entry to the version history.
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateAssetRequest {
+    name: Option<String>,
+    asset_type: Option<String>,
+    version: Option<String>,
+    model: Option<String>,
+    serial_number: Option<String>,
+    location_name: Option<String>,
+    stand_name: Option<String>,
+    /// Replaces the metadata object.
+    #[schema(value_type = Option<Object>)]
+    metadata: Option<Value>,
+    is_active: Option<bool>,
+    version_note: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AssetView {
+    id: String,
+    project_id: String,
+    name: String,
+    asset_type: String,
+    /// Current version; runs keep the version they were created with.
+    version: String,
+    model: String,
+    serial_number: Option<String>,
+    location_name: String,
+    stand_name: String,
+    #[schema(value_type = Object)]
+    metadata: Value,
+    is_active: bool,
+    run_count: i64,
+    created_at: String,
+    updated_at: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ListAssetsResponse {
+    assets: Vec<AssetView>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AssetVersionView {
+    id: String,
+    version: String,
+    note: String,
+    created_by_user_id: Option<String>,
+    created_at: String,
+    /// Runs executed against this version of the asset.
+    run_count: i64,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ListAssetVersionsResponse {
+    versions: Vec<AssetVersionView>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct DeleteAssetResponse {
+    ok: bool,
+}
+
+const ASSET_SELECT: &str = r#"
+  SELECT
+    a.id::text AS id,
+    a.project_id::text AS project_id,
+    a.name,
+    a.asset_type,
+    a.firmware_version AS version,
+    a.model,
+    a.serial_number,
+    a.location_name,
+    a.stand_name,
+    a.metadata_json,
+    a.is_active,
+    (SELECT COUNT(*) FROM runs r WHERE r.asset_id = a.id) AS run_count,
+    a.created_at::text AS created_at,
+    a.updated_at::text AS updated_at
+  FROM assets a
+"#;
+
+fn map_asset_row(r: &sqlx::postgres::PgRow) -> AssetView {
+    AssetView {
+        id: r.get("id"),
+        project_id: r.get("project_id"),
+        name: r.get("name"),
+        asset_type: r.get("asset_type"),
+        version: r.get("version"),
+        model: r.get("model"),
+        serial_number: r.get("serial_number"),
+        location_name: r.get("location_name"),
+        stand_name: r.get("stand_name"),
+        metadata: r.get("metadata_json"),
+        is_active: r.get("is_active"),
+        run_count: r.get("run_count"),
+        created_at: r.get("created_at"),
+        updated_at: r.get("updated_at"),
+    }
+}
+
+async fn fetch_asset(
+    state: &AppState,
+    project_id: Uuid,
+    asset_id: Uuid,
+) -> Result<Option<AssetView>, ApiError> {
+    let sql = format!("{ASSET_SELECT} WHERE a.project_id = $1 AND a.id = $2");
+    let row = sqlx::query(&sql)
+        .bind(project_id)
+        .bind(asset_id)
+        .fetch_optional(&state.db)
+        .await
+        .map_err(|_| ApiError::AssetsReadFailed)?;
+    Ok(row.as_ref().map(map_asset_row))
+}
+
+/// The asset exists in the project (active or not); used to validate filters.
+pub async fn ensure_asset_in_project(
+    state: &AppState,
+    asset_id: Uuid,
+    project_id: Uuid,
+) -> Result<(), ApiError> {
+    fetch_is_active(state, asset_id, project_id)
+        .await?
+        .ok_or(ApiError::AssetNotFound)
+        .map(|_| ())
+}
+
+/// The asset exists in the project and is active; new runs can only use such assets.
+pub async fn ensure_active_asset_in_project(
+    state: &AppState,
+    asset_id: Uuid,
+    project_id: Uuid,
+) -> Result<(), ApiError> {
+    match fetch_is_active(state, asset_id, project_id).await? {
+        Some(true) => Ok(()),
+        Some(false) => Err(ApiError::AssetInactive),
+        None => Err(ApiError::AssetNotFound),
+    }
+}
+
+async fn fetch_is_active(
+    state: &AppState,
+    asset_id: Uuid,
+    project_id: Uuid,
+) -> Result<Option<bool>, ApiError> {
+    sqlx::query_scalar(r#"SELECT is_active FROM assets WHERE id = $1 AND project_id = $2"#)
+        .bind(asset_id)
+        .bind(project_id)
+        .fetch_optional(&state.db)
+        .await
+        .map_err(|_| ApiError::AssetsReadFailed)
+}
+
+fn normalize_name(name: &str) -> Result<String, ApiError> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().count() > MAX_NAME_CHARS {
+        return Err(ApiError::InvalidAssetName);
+    }
+    Ok(name.to_string())
+}
+
+fn normalize_type(asset_type: &str) -> Result<String, ApiError> {
+    let asset_type = asset_type.trim();
+    if asset_type.is_empty() || asset_type.chars().count() > MAX_TYPE_CHARS {
+        return Err(ApiError::InvalidAssetType);
+    }
+    Ok(asset_type.to_string())
+}
+
+/// Optional text attribute; empty is allowed and clears it.
+fn normalize_field(value: Option<&str>) -> Result<Option<String>, ApiError> {
+    value
+        .map(|v| {
+            let v = v.trim();
+            if v.chars().count() > MAX_FIELD_CHARS {
+                return Err(ApiError::InvalidAssetField);
+            }
+            Ok(v.to_string())
+        })
+        .transpose()
+}
+
+fn normalize_metadata(metadata: Option<Value>) -> Result<Option<Value>, ApiError> {
+    match metadata {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::Object(map)) => Ok(Some(Value::Object(map))),
+        Some(_) => Err(ApiError::InvalidAssetMetadata),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v2/projects/{project_id}/assets",
+    tag = "assets",
+    params(("project_id" = String, Path), ListAssetsQuery),
+    responses((status = 200, body = ListAssetsResponse))
+)]
+pub async fn list_assets(
+    State(state): State<AppState>,
+    access: ProjectRole,
+    Query(query): Query<ListAssetsQuery>,
+) -> Result<Json<ListAssetsResponse>, ApiError> {
+    let asset_type = query
+        .asset_type
+        .as_deref()
+        .map(str::trim)
+        .filter(|t| !t.is_empty());
+    let sql = format!(
+        "{ASSET_SELECT} WHERE a.project_id = $1
+           AND ($2::text IS NULL OR a.asset_type = $2)
+           AND ($3 OR a.is_active)
+         ORDER BY a.is_active DESC, a.name ASC, a.created_at ASC"
+    );
+    let rows = sqlx::query(&sql)
+        .bind(access.project_id)
+        .bind(asset_type)
+        .bind(query.include_inactive.unwrap_or(false))
+        .fetch_all(&state.db)
+        .await
+        .map_err(|_| ApiError::AssetsReadFailed)?;
+    Ok(Json(ListAssetsResponse {
+        assets: rows.iter().map(map_asset_row).collect(),
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v2/projects/{project_id}/assets/{asset_id}",
+    tag = "assets",
+    params(("project_id" = String, Path), ("asset_id" = String, Path)),
+    responses((status = 200, body = AssetView))
+)]
+pub async fn get_asset(
+    State(state): State<AppState>,
+    Path((_project_id, asset_id)): Path<(String, String)>,
+    access: ProjectRole,
+) -> Result<Json<AssetView>, ApiError> {
+    let asset_uuid = parse_uuid(&asset_id, ApiError::InvalidAssetId)?;
+    let asset = fetch_asset(&state, access.project_id, asset_uuid)
+        .await?
+        .ok_or(ApiError::AssetNotFound)?;
+    Ok(Json(asset))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v2/projects/{project_id}/assets",
+    tag = "assets",
+    params(("project_id" = String, Path)),
+    request_body = CreateAssetRequest,
+    responses((status = 201, body = AssetView))
+)]
+pub async fn create_asset(
+    State(state): State<AppState>,
+    access: ProjectRole,
+    Json(payload): Json<CreateAssetRequest>,
+) -> Result<(StatusCode, Json<AssetView>), ApiError> {
+    access.require(Capability::LibraryEdit)?;
+    let ProjectRole {
+        user_id: actor_id,
+        project_id,
+        ..
+    } = access;
+    let name = normalize_name(&payload.name)?;
+    let asset_type = normalize_type(&payload.asset_type)?;
+    let version = normalize_field(payload.version.as_deref())?.unwrap_or_default();
+    let model = normalize_field(payload.model.as_deref())?.unwrap_or_default();
+    let serial_number =
+        normalize_field(payload.serial_number.as_deref())?.filter(|s| !s.is_empty());
+    let location_name = normalize_field(payload.location_name.as_deref())?.unwrap_or_default();
+    let stand_name = normalize_field(payload.stand_name.as_deref())?.unwrap_or_default();
+    let metadata = normalize_metadata(payload.metadata)?.unwrap_or(Value::Object(Map::new()));
+    let note = normalize_field(payload.version_note.as_deref())?.unwrap_or_default();
+    ensure_db_user_exists(&state, &actor_id).await?;
+    let actor_uuid = parse_uuid(&actor_id, ApiError::InvalidUserId)?;
+
+    let save_failed = |err| db_errors::map(err, ApiError::AssetSaveFailed);
+    let mut tx = state.db.begin().await.map_err(save_failed)?;
+    let asset_id: Uuid = sqlx::query_scalar(
+        r#"
+        INSERT INTO assets (
+          project_id, name, asset_type, firmware_version, model, serial_number, location_name,
+          stand_name, metadata_json, created_by_user_id, updated_by_user_id
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $10)
+        RETURNING id
+        "#,
+    )
+    .bind(project_id)
+    .bind(&name)
+    .bind(&asset_type)
+    .bind(&version)
+    .bind(&model)
+    .bind(&serial_number)
+    .bind(&location_name)
+    .bind(&stand_name)
+    .bind(&metadata)
+    .bind(actor_uuid)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(save_failed)?;
+    if !version.is_empty() {
+        record_version(&mut tx, asset_id, &version, &note, actor_uuid)
+            .await
+            .map_err(save_failed)?;
+    }
+    audit::record(
+        &mut *tx,
+        audit::AuditEntry {
+            actor_user_id: Some(actor_uuid),
+            action: "create",
+            entity_type: "asset",
+            entity_id: Some(asset_id),
+            project_id: Some(project_id),
+            run_id: None,
+            before: None,
+            after: Some(json!({
+                "name": &name,
+                "assetType": &asset_type,
+                "version": &version,
+            })),
+        },
+    )
+    .await
+    .map_err(save_failed)?;
+    tx.commit().await.map_err(save_failed)?;
+
+    let asset = fetch_asset(&state, project_id, asset_id)
+        .await?
+        .ok_or(ApiError::AssetNotFound)?;
+    Ok((StatusCode::CREATED, Json(asset)))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/api/v2/projects/{project_id}/assets/{asset_id}",
+    tag = "assets",
+    params(("project_id" = String, Path), ("asset_id" = String, Path)),
+    request_body = UpdateAssetRequest,
+    responses((status = 200, body = AssetView))
+)]
+pub async fn update_asset(
+    State(state): State<AppState>,
+    Path((_project_id, asset_id)): Path<(String, String)>,
+    access: ProjectRole,
+    Json(payload): Json<UpdateAssetRequest>,
+) -> Result<Json<AssetView>, ApiError> {
+    access.require(Capability::LibraryEdit)?;
+    let ProjectRole {
+        user_id: actor_id,
+        project_id,
+        ..
+    } = access;
+    let asset_uuid = parse_uuid(&asset_id, ApiError::InvalidAssetId)?;
+    let name = payload.name.as_deref().map(normalize_name).transpose()?;
+    let asset_type = payload
+        .asset_type
+        .as_deref()
+        .map(normalize_type)
+        .transpose()?;
+    let version = normalize_field(payload.version.as_deref())?;
+    let model = normalize_field(payload.model.as_deref())?;
+    let serial_number = normalize_field(payload.serial_number.as_deref())?;
+    let location_name = normalize_field(payload.location_name.as_deref())?;
+    let stand_name = normalize_field(payload.stand_name.as_deref())?;
+    let metadata = normalize_metadata(payload.metadata)?;
+    let note = normalize_field(payload.version_note.as_deref())?.unwrap_or_default();
+    ensure_db_user_exists(&state, &actor_id).await?;
+    let actor_uuid = parse_uuid(&actor_id, ApiError::InvalidUserId)?;
+    let before = fetch_asset(&state, project_id, asset_uuid)
+        .await?
+        .ok_or(ApiError::AssetNotFound)?;
+    let version_changed = version
+        .as_deref()
+        .is_some_and(|v| !v.is_empty() && v != before.version);
+
+    let save_failed = |err| db_errors::map(err, ApiError::AssetSaveFailed);
+    let mut tx = state.db.begin().await.map_err(save_failed)?;
+    sqlx::query(
+        r#"
+        UPDATE assets
+        SET name = COALESCE($3, name),
+            asset_type = COALESCE($4, asset_type),
+            firmware_version = COALESCE($5, firmware_version),
+            model = COALESCE($6, model),
+            serial_number = CASE WHEN $7::text IS NULL THEN serial_number
+                                 ELSE NULLIF($7, '') END,
+            location_name = COALESCE($8, location_name),
+            stand_name = COALESCE($9, stand_name),
+            metadata_json = COALESCE($10, metadata_json),
+            is_active = COALESCE($11, is_active),
+            updated_by_user_id = $12
+        WHERE project_id = $1 AND id = $2
+        "#,
+    )
+    .bind(project_id)
+    .bind(asset_uuid)
+    .bind(&name)
+    .bind(&asset_type)
+    .bind(&version)
+    .bind(&model)
+    .bind(&serial_number)
+    .bind(&location_name)
+    .bind(&stand_name)
+    .bind(&metadata)
+    .bind(payload.is_active)
+    .bind(actor_uuid)
+    .execute(&mut *tx)
+    .await
+    .map_err(save_failed)?;
+    if version_changed {
+        if let Some(version) = version.as_deref() {
+            record_version(&mut tx, asset_uuid, version, &note, actor_uuid)
+                .await
+                .map_err(save_failed)?;
+        }
+    }
+    audit::record(
+        &mut *tx,
+        audit::AuditEntry {
+            actor_user_id: Some(actor_uuid),
+            action: "update",
+            entity_type: "asset",
+            entity_id: Some(asset_uuid),
+            project_id: Some(project_id),
+            run_id: None,
+            before: Some(json!({
+                "name": &before.name,
+                "assetType": &before.asset_type,
+                "version": &before.version,
+                "isActive": before.is_active,
+            })),
+            after: Some(json!({
+                "name": name.as_ref().unwrap_or(&before.name),
+                "assetType": asset_type.as_ref().unwrap_or(&before.asset_type),
+                "version": version.as_ref().unwrap_or(&before.version),
+                "isActive": payload.is_active.unwrap_or(before.is_active),
+            })),
+        },
+    )
+    .await
+    .map_err(save_failed)?;
+    tx.commit().await.map_err(save_failed)?;
+
+    let asset = fetch_asset(&state, project_id, asset_uuid)
+        .await?
+        .ok_or(ApiError::AssetNotFound)?;
+    Ok(Json(asset))
+}
+
+/// Deletes an asset no run refers to; used assets are deactivated instead.
+#[utoipa::path(
+    delete,
+    path = "/api/v2/projects/{project_id}/assets/{asset_id}",
+    tag = "assets",
+    params(("project_id" = String, Path), ("asset_id" = String, Path)),
+    responses((status = 200, body = DeleteAssetResponse))
+)]
+pub async fn delete_asset(
+    State(state): State<AppState>,
+    Path((_project_id, asset_id)): Path<(String, String)>,
+    access: ProjectRole,
+) -> Result<Json<DeleteAssetResponse>, ApiError> {
+    access.require(Capability::LibraryEdit)?;
+    let ProjectRole {
+        user_id: actor_id,
+        project_id,
+        ..
+    } = access;
+    let asset_uuid = parse_uuid(&asset_id, ApiError::InvalidAssetId)?;
+    ensure_db_user_exists(&state, &actor_id).await?;
+    let actor_uuid = parse_uuid(&actor_id, ApiError::InvalidUserId)?;
+
+    let delete_failed = |_| ApiError::AssetDeleteFailed;
+    let mut tx = state.db.begin().await.map_err(delete_failed)?;
+    let deleted = sqlx::query(
+        r#"
+        DELETE FROM assets a
+        WHERE a.project_id = $1 AND a.id = $2
+          AND NOT EXISTS (SELECT 1 FROM runs r WHERE r.asset_id = a.id)
+        RETURNING a.name, a.asset_type, a.firmware_version
+        "#,
+    )
+    .bind(project_id)
+    .bind(asset_uuid)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(delete_failed)?;
+    let Some(deleted) = deleted else {
+        return Err(match fetch_asset(&state, project_id, asset_uuid).await? {
+            Some(_) => ApiError::AssetInUse,
+            None => ApiError::AssetNotFound,
+        });
+    };
+    audit::record(
+        &mut *tx,
+        audit::AuditEntry {
+            actor_user_id: Some(actor_uuid),
+            action: "delete",
+            entity_type: "asset",
+            entity_id: Some(asset_uuid),
+            project_id: Some(project_id),
+            run_id: None,
+            before: Some(json!({
+                "name": deleted.get::<String, _>("name"),
+                "assetType": deleted.get::<String, _>("asset_type"),
+                "version": deleted.get::<String, _>("firmware_version"),
+            })),
+            after: None,
+        },
+    )
+    .await
+    .map_err(delete_failed)?;
+    tx.commit().await.map_err(delete_failed)?;
+
+    Ok(Json(DeleteAssetResponse { ok: true }))
+}
+
+/// Versions the asset had, newest first.
+#[utoipa::path(
+    get,
+    path = "/api/v2/projects/{project_id}/assets/{asset_id}/versions",
+    tag = "assets",
+    params(("project_id" = String, Path), ("asset_id" = String, Path)),
+    responses((status = 200, body = ListAssetVersionsResponse))
+)]
+pub async fn list_asset_versions(
+    State(state): State<AppState>,
+    Path((_project_id, asset_id)): Path<(String, String)>,
+    access: ProjectRole,
+) -> Result<Json<ListAssetVersionsResponse>, ApiError> {
+    let asset_uuid = parse_uuid(&asset_id, ApiError::InvalidAssetId)?;
+    ensure_asset_in_project(&state, asset_uuid, access.project_id).await?;
+    let rows = sqlx::query(
+        r#"
+        SELECT
+          v.id::text AS id,
+          v.version,
+          v.note,
+          v.created_by_user_id::text AS created_by_user_id,
+          v.created_at::text AS created_at,
+          (
+            SELECT COUNT(*) FROM runs r
+            WHERE r.asset_id = v.asset_id AND r.asset_version = v.version
+          ) AS run_count
+        FROM asset_versions v
+        WHERE v.asset_id = $1
+        ORDER BY v.created_at DESC, v.id DESC
+        "#,
+    )
+    .bind(asset_uuid)
+    .fetch_all(&state.db)
+    .await
+    .map_err(|_| ApiError::AssetsReadFailed)?;
+    Ok(Json(ListAssetVersionsResponse {
+        versions: rows
+            .iter()
+            .map(|r| AssetVersionView {
+                id: r.get("id"),
+                version: r.get("version"),
+                note: r.get("note"),
+                created_by_user_id: r.get("created_by_user_id"),
+                created_at: r.get("created_at"),
+                run_count: r.get("run_count"),
+            })
+            .collect(),
+    }))
+}
+
+async fn record_version(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    asset_id: Uuid,
+    version: &str,
+    note: &str,
+    actor_uuid: Uuid,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO asset_versions (asset_id, version, note, created_by_user_id)
+        VALUES ($1, $2, $3, $4)
+        "#,
+    )
+    .bind(asset_id)
+    .bind(version)
+    .bind(note)
+    .bind(actor_uuid)
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
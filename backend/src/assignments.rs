@@ -0,0 +1,541 @@
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::Row;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::{
+    audit,
+    authz::{self, AuthUser},
+    chat, db_errors, ensure_db_user_exists,
+    error::ApiError,
+    fetch_run_view, live, membership_role, notifications, pagination, parse_uuid, permissions,
+    permissions::Capability,
+    read_projects, AppState, RunView,
+};
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SetAssigneeRequest {
+    /// `null` clears the assignee; for an item this falls back to the run default.
+    assignee_user_id: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RunItemAssigneeResponse {
+    run_item_id: String,
+    assignee_user_id: Option<String>,
+    assignee_inherited: bool,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct RunAssigneeResponse {
+    run: RunView,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SetExecutorRequest {
+    executor_user_id: String,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct MyAssignmentsQuery {
+    project_id: Option<String>,
+    limit: Option<i64>,
+    cursor: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AssignmentView {
+    run_item_id: String,
+    run_id: String,
+    project_id: String,
+    run_title: String,
+    run_status: String,
+    testcase_key: String,
+    testcase_title: String,
+    position: i32,
+    is_required: bool,
+    created_at: String,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct MyAssignmentsResponse {
+    assignments: Vec<AssignmentView>,
+    next_cursor: Option<String>,
+}
+
+/// Only members who may fill in results can be assigned as executors; `denied` otherwise.
+async fn ensure_assignable(
+    state: &AppState,
+    project_id: &str,
+    user_id: &str,
+    denied: ApiError,
+) -> Result<(), ApiError> {
+    let _guard = state.file_lock.lock().await;
+    let projects = read_projects(&state.projects_file)
+        .await
+        .map_err(|_| ApiError::ProjectsReadFailed)?;
+    let can_execute = projects
+        .iter()
+        .find(|p| p.id == project_id)
+        .and_then(|project| {
+            membership_role(project, user_id).map(|role| {
+                permissions::role_capabilities(project, &role).contains(&Capability::ResultEdit)
+            })
+        })
+        .unwrap_or(false);
+    if !can_execute {
+        return Err(denied);
+    }
+    Ok(())
+}
+
+/// Validates the requested assignee and returns it normalized.
+pub async fn resolve_assignee(
+    state: &AppState,
+    project_id: &str,
+    assignee_user_id: Option<&str>,
+) -> Result<Option<Uuid>, ApiError> {
+    match assignee_user_id.map(str::trim) {
+        Some(v) if !v.is_empty() => {
+            let assignee_uuid = parse_uuid(v, ApiError::InvalidAssigneeUserId)?;
+            let assignee_id = assignee_uuid.to_string();
+            ensure_assignable(state, project_id, &assignee_id, ApiError::InvalidAssignee).await?;
+            ensure_db_user_exists(state, &assignee_id).await?;
+            Ok(Some(assignee_uuid))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Sets or clears the executor of a single run item.
+#[utoipa::path(
+    patch,
+    path = "/api/v2/runs/{run_id}/items/{run_item_id}/assignee",
+    tag = "assignments",
+    params(("run_id" = String, Path), ("run_item_id" = String, Path)),
+    request_body = SetAssigneeRequest,
+    responses((status = 200, body = RunItemAssigneeResponse))
+)]
+pub async fn set_item_assignee(
+    State(state): State<AppState>,
+    Path((run_id, run_item_id)): Path<(String, String)>,
+    AuthUser(actor_id): AuthUser,
+    Json(payload): Json<SetAssigneeRequest>,
+) -> Result<Json<RunItemAssigneeResponse>, ApiError> {
+    let run_uuid = parse_uuid(&run_id, ApiError::InvalidRunId)?;
+    let run_item_uuid = parse_uuid(&run_item_id, ApiError::InvalidRunItemId)?;
+    authz::require_run_capability(&state, run_uuid, &actor_id, Capability::RunCompose).await?;
+    ensure_db_user_exists(&state, &actor_id).await?;
+    let actor_uuid = parse_uuid(&actor_id, ApiError::InvalidUserId)?;
+
+    let context = sqlx::query(
+        r#"
+        SELECT
+          r.project_id AS project_id,
+          r.status::text AS status,
+          r.title AS run_title,
+          r.default_assignee_user_id AS default_assignee_user_id,
+          ri.assignee_user_id AS assignee_user_id,
+          tc.title AS testcase_title
+        FROM runs r
+        JOIN run_items ri ON ri.run_id = r.id
+        JOIN testcase_versions tv ON tv.id = ri.testcase_version_id
+        JOIN testcases tc ON tc.id = tv.testcase_id
+        WHERE r.id = $1 AND ri.id = $2
+        "#,
+    )
+    .bind(run_uuid)
+    .bind(run_item_uuid)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|_| ApiError::RunItemReadFailed)?
+    .ok_or(ApiError::RunOrItemNotFound)?;
+    if context.get::<String, _>("status") == "locked" {
+        return Err(ApiError::RunLockedAssignees);
+    }
+    let project_uuid = context.get::<Uuid, _>("project_id");
+    let default_assignee = context.get::<Option<Uuid>, _>("default_assignee_user_id");
+    let previous = context.get::<Option<Uuid>, _>("assignee_user_id");
+    let assignee = resolve_assignee(
+        &state,
+        &project_uuid.to_string(),
+        payload.assignee_user_id.as_deref(),
+    )
+    .await?;
+
+    let assign_failed = |_| ApiError::AssigneeUpdateFailed;
+    let mut tx = state.db.begin().await.map_err(assign_failed)?;
+    sqlx::query(r#"UPDATE run_items SET assignee_user_id = $2 WHERE id = $1"#)
+        .bind(run_item_uuid)
+        .bind(assignee)
+        .execute(&mut *tx)
+        .await
+        .map_err(assign_failed)?;
+    audit::record(
+        &mut *tx,
+        audit::AuditEntry {
+            actor_user_id: Some(actor_uuid),
+            action: "update",
+            entity_type: "run_item",
+            entity_id: Some(run_item_uuid),
+            project_id: Some(project_uuid),
+            run_id: Some(run_uuid),
+            before: Some(json!({ "assigneeUserId": previous })),
+            after: Some(json!({ "assigneeUserId": assignee })),
+        },
+    )
+    .await
+    .map_err(assign_failed)?;
+    tx.commit().await.map_err(assign_failed)?;
+
+    let effective = assignee.or(default_assignee);
+    let effective_id = effective.map(|id| id.to_string());
+    state.cache.invalidate_run(run_uuid);
+    state.live.publish(
+        run_uuid,
+        &live::RunEvent::AssigneeChanged(live::RunAssigneeEvent {
+            run_item_id: Some(run_item_uuid),
+            assignee_user_id: effective_id.as_deref(),
+        }),
+    );
+    if let Some(assignee_id) = effective_id
+        .as_ref()
+        .filter(|id| *id != &actor_id && effective != previous.or(default_assignee))
+    {
+        let run_title = context.get::<String, _>("run_title");
+        let testcase_title = context.get::<String, _>("testcase_title");
+        chat::run_assigned(
+            &state,
+            project_uuid,
+            run_uuid,
+            run_title.clone(),
+            Some(testcase_title.clone()),
+            assignee_id.clone(),
+        );
+        notifications::notify_about_item(
+            &state,
+            notifications::NotificationKind::RunAssigned,
+            project_uuid,
+            assignee_id.clone(),
+            run_item_uuid,
+            format!("Вам назначен тест в прогоне «{run_title}»"),
+            format!("Вы назначены исполнителем теста «{testcase_title}» в прогоне «{run_title}»."),
+        );
+    }
+
+    Ok(Json(RunItemAssigneeResponse {
+        run_item_id: run_item_uuid.to_string(),
+        assignee_user_id: effective_id,
+        assignee_inherited: assignee.is_none() && default_assignee.is_some(),
+    }))
+}
+
+/// Sets or clears the run-wide default executor, used by items without their own assignee.
+#[utoipa::path(
+    patch,
+    path = "/api/v2/runs/{run_id}/assignee",
+    tag = "assignments",
+    params(("run_id" = String, Path)),
+    request_body = SetAssigneeRequest,
+    responses((status = 200, body = RunAssigneeResponse))
+)]
+pub async fn set_run_default_assignee(
+    State(state): State<AppState>,
+    Path(run_id): Path<String>,
+    AuthUser(actor_id): AuthUser,
+    Json(payload): Json<SetAssigneeRequest>,
+) -> Result<Json<RunAssigneeResponse>, ApiError> {
+    let run_uuid = parse_uuid(&run_id, ApiError::InvalidRunId)?;
+    authz::require_run_capability(&state, run_uuid, &actor_id, Capability::RunCompose).await?;
+    ensure_db_user_exists(&state, &actor_id).await?;
+    let actor_uuid = parse_uuid(&actor_id, ApiError::InvalidUserId)?;
+
+    let before = fetch_run_view(&state.db, run_uuid)
+        .await?
+        .ok_or(ApiError::RunNotFound)?;
+    if before.status == "locked" {
+        return Err(ApiError::RunLockedAssignees);
+    }
+    let project_uuid = parse_uuid(&before.project_id, ApiError::InvalidProjectId)?;
+    let assignee = resolve_assignee(
+        &state,
+        &before.project_id,
+        payload.assignee_user_id.as_deref(),
+    )
+    .await?;
+
+    let assign_failed = |_| ApiError::AssigneeUpdateFailed;
+    let mut tx = state.db.begin().await.map_err(assign_failed)?;
+    let inherited_items: i64 = sqlx::query_scalar(
+        r#"
+        WITH updated AS (
+          UPDATE runs SET default_assignee_user_id = $2 WHERE id = $1 RETURNING id
+        )
+        SELECT COUNT(ri.id)
+        FROM updated u
+        JOIN run_items ri ON ri.run_id = u.id AND ri.assignee_user_id IS NULL
+        "#,
+    )
+    .bind(run_uuid)
+    .bind(assignee)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(assign_failed)?;
+    audit::record(
+        &mut *tx,
+        audit::AuditEntry {
+            actor_user_id: Some(actor_uuid),
+            action: "update",
+            entity_type: "run",
+            entity_id: Some(run_uuid),
+            project_id: Some(project_uuid),
+            run_id: Some(run_uuid),
+            before: Some(json!({ "defaultAssigneeUserId": &before.default_assignee_user_id })),
+            after: Some(json!({ "defaultAssigneeUserId": assignee })),
+        },
+    )
+    .await
+    .map_err(assign_failed)?;
+    tx.commit().await.map_err(assign_failed)?;
+
+    let run = fetch_run_view(&state.db, run_uuid)
+        .await?
+        .ok_or(ApiError::RunNotFound)?;
+    state.cache.invalidate_run(run_uuid);
+    state.live.publish(
+        run_uuid,
+        &live::RunEvent::AssigneeChanged(live::RunAssigneeEvent {
+            run_item_id: None,
+            assignee_user_id: run.default_assignee_user_id.as_deref(),
+        }),
+    );
+    if let Some(assignee_id) = run
+        .default_assignee_user_id
+        .as_ref()
+        .filter(|id| **id != actor_id && before.default_assignee_user_id.as_ref() != Some(*id))
+    {
+        chat::run_assigned(
+            &state,
+            project_uuid,
+            run_uuid,
+            run.title.clone(),
+            None,
+            assignee_id.clone(),
+        );
+        notifications::notify(
+            &state,
+            notifications::NotificationKind::RunAssigned,
+            Some(project_uuid),
+            Some(run_uuid),
+            vec![assignee_id.clone()],
+            format!("Вам назначен прогон «{}»", run.title),
+            format!(
+                "Вы назначены исполнителем прогона «{}» по умолчанию. Тестов без отдельного исполнителя: {inherited_items}.",
+                run.title
+            ),
+        );
+    }
+
+    Ok(Json(RunAssigneeResponse { run }))
+}
+
+/// Hands the run over to another member: they become `executedByUserId`, the one listings,
+/// dashboards and reports show as the run's executor. Item assignees are left as they are.
+#[utoipa::path(
+    patch,
+    path = "/api/v2/runs/{run_id}/executor",
+    tag = "assignments",
+    params(("run_id" = String, Path)),
+    request_body = SetExecutorRequest,
+    responses((status = 200, body = RunAssigneeResponse))
+)]
+pub async fn set_run_executor(
+    State(state): State<AppState>,
+    Path(run_id): Path<String>,
+    AuthUser(actor_id): AuthUser,
+    Json(payload): Json<SetExecutorRequest>,
+) -> Result<Json<RunAssigneeResponse>, ApiError> {
+    let run_uuid = parse_uuid(&run_id, ApiError::InvalidRunId)?;
+    authz::require_run_capability(&state, run_uuid, &actor_id, Capability::RunCompose).await?;
+    ensure_db_user_exists(&state, &actor_id).await?;
+    let actor_uuid = parse_uuid(&actor_id, ApiError::InvalidUserId)?;
+    let executor_uuid = parse_uuid(
+        payload.executor_user_id.trim(),
+        ApiError::InvalidExecutorUserId,
+    )?;
+    let executor_id = executor_uuid.to_string();
+
+    let before = fetch_run_view(&state.db, run_uuid)
+        .await?
+        .ok_or(ApiError::RunNotFound)?;
+    if before.status == "locked" {
+        return Err(ApiError::RunLockedAssignees);
+    }
+    if before.executed_by_user_id == executor_id {
+        return Ok(Json(RunAssigneeResponse { run: before }));
+    }
+    let project_uuid = parse_uuid(&before.project_id, ApiError::InvalidProjectId)?;
+    ensure_assignable(
+        &state,
+        &before.project_id,
+        &executor_id,
+        ApiError::InvalidExecutor,
+    )
+    .await?;
+    ensure_db_user_exists(&state, &executor_id).await?;
+
+    let update_failed = |err| db_errors::map(err, ApiError::ExecutorUpdateFailed);
+    let mut tx = state.db.begin().await.map_err(update_failed)?;
+    sqlx::query!(
+        "UPDATE runs SET executed_by_user_id = $2 WHERE id = $1",
+        run_uuid,
+        executor_uuid,
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(update_failed)?;
+    audit::record(
+        &mut *tx,
+        audit::AuditEntry {
+            actor_user_id: Some(actor_uuid),
+            action: "update",
+            entity_type: "run",
+            entity_id: Some(run_uuid),
+            project_id: Some(project_uuid),
+            run_id: Some(run_uuid),
+            before: Some(json!({ "executedByUserId": &before.executed_by_user_id })),
+            after: Some(json!({ "executedByUserId": &executor_id })),
+        },
+    )
+    .await
+    .map_err(update_failed)?;
+    tx.commit().await.map_err(update_failed)?;
+
+    let run = fetch_run_view(&state.db, run_uuid)
+        .await?
+        .ok_or(ApiError::RunNotFound)?;
+    state.cache.invalidate_run(run_uuid);
+    state
+        .live
+        .publish(run_uuid, &live::RunEvent::ExecutorChanged { run: &run });
+    if executor_id != actor_id {
+        chat::run_assigned(
+            &state,
+            project_uuid,
+            run_uuid,
+            run.title.clone(),
+            None,
+            executor_id.clone(),
+        );
+        notifications::notify(
+            &state,
+            notifications::NotificationKind::RunAssigned,
+            Some(project_uuid),
+            Some(run_uuid),
+            vec![executor_id],
+            format!("Вам передан прогон «{}»", run.title),
+            format!("Вы назначены исполнителем прогона «{}».", run.title),
+        );
+    }
+
+    Ok(Json(RunAssigneeResponse { run }))
+}
+
+/// Open items assigned to the caller across their projects: the run is not finished and the
+/// item has no result yet (items are created with a placeholder `na` result) or waits for a
+/// `retest`.
+#[utoipa::path(
+    get,
+    path = "/api/v2/my/assignments",
+    tag = "assignments",
+    params(MyAssignmentsQuery),
+    responses((status = 200, body = MyAssignmentsResponse))
+)]
+pub async fn list_my_assignments(
+    State(state): State<AppState>,
+    AuthUser(actor_id): AuthUser,
+    Query(query): Query<MyAssignmentsQuery>,
+) -> Result<Json<MyAssignmentsResponse>, ApiError> {
+    let actor_uuid = parse_uuid(&actor_id, ApiError::InvalidUserId)?;
+    let limit = pagination::page_limit(query.limit);
+    let cursor = pagination::parse_cursor(query.cursor.as_deref())?;
+    let mut project_ids = authz::member_project_ids(&state, &actor_id).await?;
+    if let Some(v) = query.project_id.as_deref().filter(|v| !v.trim().is_empty()) {
+        let project_uuid = parse_uuid(v, ApiError::InvalidProjectId)?;
+        project_ids.retain(|id| *id == project_uuid);
+    }
+
+    let rows = sqlx::query(
+        r#"
+        SELECT
+          ri.id::text AS run_item_id,
+          r.id::text AS run_id,
+          r.project_id::text AS project_id,
+          r.title AS run_title,
+          r.status::text AS run_status,
+          tc.key AS testcase_key,
+          tc.title AS testcase_title,
+          ri.position AS position,
+          ri.is_required AS is_required,
+          ri.created_at::text AS created_at
+        FROM run_items ri
+        JOIN runs r ON r.id = ri.run_id
+        JOIN testcase_versions tv ON tv.id = ri.testcase_version_id
+        JOIN testcases tc ON tc.id = tv.testcase_id
+        LEFT JOIN run_results rr ON rr.run_item_id = ri.id
+        WHERE COALESCE(ri.assignee_user_id, r.default_assignee_user_id) = $1
+          AND r.project_id = ANY($2)
+          AND r.status IN ('draft', 'in_progress')
+          AND (rr.id IS NULL OR rr.status IN ('na', 'retest'))
+          AND ($3::timestamptz IS NULL OR (ri.created_at, ri.id) < ($3::timestamptz, $4::uuid))
+        ORDER BY ri.created_at DESC, ri.id DESC
+        LIMIT $5
+        "#,
+    )
+    .bind(actor_uuid)
+    .bind(&project_ids)
+    .bind(cursor.as_ref().map(|c| c.created_at.clone()))
+    .bind(cursor.as_ref().map(|c| c.id.clone()))
+    .bind(limit + 1)
+    .fetch_all(&state.db)
+    .await
+    .map_err(|_| ApiError::AssignmentsReadFailed)?;
+
+    let assignments: Vec<AssignmentView> = rows
+        .into_iter()
+        .map(|r| AssignmentView {
+            run_item_id: r.get::<String, _>("run_item_id"),
+            run_id: r.get::<String, _>("run_id"),
+            project_id: r.get::<String, _>("project_id"),
+            run_title: r.get::<String, _>("run_title"),
+            run_status: r.get::<String, _>("run_status"),
+            testcase_key: r.get::<String, _>("testcase_key"),
+            testcase_title: r.get::<String, _>("testcase_title"),
+            position: r.get::<i32, _>("position"),
+            is_required: r.get::<bool, _>("is_required"),
+            created_at: r.get::<String, _>("created_at"),
+        })
+        .collect();
+    let (assignments, next_cursor) =
+        pagination::finish_page(assignments, limit, |a| pagination::Cursor {
+            created_at: a.created_at.clone(),
+            id: a.run_item_id.clone(),
+        });
+
+    Ok(Json(MyAssignmentsResponse {
+        assignments,
+        next_cursor,
+    }))
+}
@@ -0,0 +1,477 @@
+use axum::{
+    body::Bytes,
+    extract::{Multipart, Path, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+use serde_json::json;
+use sqlx::Row;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{
+    audit,
+    authz::{self, AuthUser},
+    ensure_db_user_exists,
+    error::ApiError,
+    parse_uuid,
+    permissions::Capability,
+    quotas, AppState,
+};
+
+#[derive(Clone)]
+pub struct AttachmentLimits {
+    pub max_bytes: usize,
+    allowed_types: Vec<String>,
+    /// Default quota of a project without an override; `None` is unlimited.
+    pub project_quota_bytes: Option<i64>,
+}
+
+impl AttachmentLimits {
+    /// `allowed_types` are MIME types, `type/*` matching a whole top-level type.
+    pub fn new(
+        max_bytes: usize,
+        allowed_types: Vec<String>,
+        project_quota_bytes: Option<i64>,
+    ) -> Self {
+        Self {
+            max_bytes,
+            allowed_types,
+            project_quota_bytes,
+        }
+    }
+
+    pub fn allows(&self, mime_type: &str) -> bool {
+        self.allowed_types
+            .iter()
+            .any(|allowed| match allowed.strip_suffix("/*") {
+                Some(prefix) => mime_type.split('/').next().is_some_and(|top| top == prefix),
+                None => allowed == mime_type,
+            })
+    }
+}
+
+/// Multipart form of `upload_attachment`; only describes the request in the OpenAPI spec.
+#[derive(ToSchema)]
+#[allow(dead_code)]
+pub struct AttachmentUpload {
+    #[schema(value_type = String, format = Binary)]
+    file: Vec<u8>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AttachmentView {
+    id: String,
+    run_id: String,
+    run_item_id: Option<String>,
+    file_name: String,
+    mime_type: String,
+    size_bytes: i64,
+    storage_provider: String,
+    uploaded_by_user_id: Option<String>,
+    created_at: String,
+    download_url: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct AttachmentResponse {
+    attachment: AttachmentView,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct AttachmentsResponse {
+    attachments: Vec<AttachmentView>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct DeleteAttachmentResponse {
+    ok: bool,
+}
+
+const ATTACHMENT_SELECT: &str = r#"
+  SELECT
+    a.id::text AS id,
+    COALESCE(a.run_id, ri.run_id)::text AS run_id,
+    ri.id::text AS run_item_id,
+    a.file_name,
+    a.mime_type,
+    a.size_bytes,
+    a.storage_provider,
+    a.storage_key,
+    a.uploaded_by_user_id::text AS uploaded_by_user_id,
+    a.created_at::text AS created_at
+  FROM attachments a
+  LEFT JOIN run_results rr ON rr.id = a.run_result_id
+  LEFT JOIN run_items ri ON ri.id = rr.run_item_id
+"#;
+
+fn map_attachment_row(r: &sqlx::postgres::PgRow) -> AttachmentView {
+    let id = r.get::<String, _>("id");
+    AttachmentView {
+        download_url: format!("/api/v2/attachments/{id}/download"),
+        id,
+        run_id: r.get::<String, _>("run_id"),
+        run_item_id: r.get::<Option<String>, _>("run_item_id"),
+        file_name: r.get::<String, _>("file_name"),
+        mime_type: r.get::<String, _>("mime_type"),
+        size_bytes: r.get::<i64, _>("size_bytes"),
+        storage_provider: r.get::<String, _>("storage_provider"),
+        uploaded_by_user_id: r.get::<Option<String>, _>("uploaded_by_user_id"),
+        created_at: r.get::<String, _>("created_at"),
+    }
+}
+
+pub fn sanitize_file_name(raw: &str) -> String {
+    let cleaned: String = raw
+        .chars()
+        .filter(|c| !matches!(c, '/' | '\\' | '"' | '\r' | '\n') && !c.is_control())
+        .take(200)
+        .collect();
+    let cleaned = cleaned.trim().to_string();
+    if cleaned.is_empty() {
+        "file".to_string()
+    } else {
+        cleaned
+    }
+}
+
+struct AttachmentRecord {
+    view: AttachmentView,
+    storage_key: String,
+    project_id: Uuid,
+    run_status: String,
+}
+
+async fn load_attachment(
+    state: &AppState,
+    attachment_id: &str,
+) -> Result<AttachmentRecord, ApiError> {
+    let attachment_uuid = parse_uuid(attachment_id, ApiError::InvalidAttachmentId)?;
+    let sql = format!(
+        r#"
+        SELECT q.*, r.project_id AS project_id, r.status::text AS run_status
+        FROM ({ATTACHMENT_SELECT} WHERE a.id = $1) q
+        JOIN runs r ON r.id = q.run_id::uuid
+        "#
+    );
+    let row = sqlx::query(&sql)
+        .bind(attachment_uuid)
+        .fetch_optional(&state.db)
+        .await
+        .map_err(|_| ApiError::AttachmentReadFailed)?
+        .ok_or(ApiError::AttachmentNotFound)?;
+    Ok(AttachmentRecord {
+        view: map_attachment_row(&row),
+        storage_key: row.get::<String, _>("storage_key"),
+        project_id: row.get::<Uuid, _>("project_id"),
+        run_status: row.get::<String, _>("run_status"),
+    })
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v2/runs/{run_id}/items/{run_item_id}/attachments",
+    tag = "attachments",
+    params(("run_id" = String, Path), ("run_item_id" = String, Path)),
+    request_body(content = AttachmentUpload, content_type = "multipart/form-data"),
+    responses((status = 201, body = AttachmentResponse))
+)]
+pub async fn upload_attachment(
+    State(state): State<AppState>,
+    Path((run_id, run_item_id)): Path<(String, String)>,
+    AuthUser(actor_id): AuthUser,
+    mut multipart: Multipart,
+) -> Result<(StatusCode, Json<AttachmentResponse>), ApiError> {
+    let run_uuid = parse_uuid(&run_id, ApiError::InvalidRunId)?;
+    let run_item_uuid = parse_uuid(&run_item_id, ApiError::InvalidRunItemId)?;
+    authz::require_run_capability(&state, run_uuid, &actor_id, Capability::ResultEdit).await?;
+    ensure_db_user_exists(&state, &actor_id).await?;
+    let actor_uuid = parse_uuid(&actor_id, ApiError::InvalidUserId)?;
+
+    let context = sqlx::query(
+        r#"
+        SELECT r.status::text AS status, r.project_id AS project_id, rr.id AS run_result_id
+        FROM run_items ri
+        JOIN runs r ON r.id = ri.run_id
+        LEFT JOIN run_results rr ON rr.run_item_id = ri.id
+        WHERE r.id = $1 AND ri.id = $2
+        "#,
+    )
+    .bind(run_uuid)
+    .bind(run_item_uuid)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|_| ApiError::RunItemLookupFailed)?
+    .ok_or(ApiError::RunOrItemNotFound)?;
+    if context.get::<String, _>("status") == "locked" {
+        return Err(ApiError::RunLockedAttachments);
+    }
+    let project_uuid = context.get::<Uuid, _>("project_id");
+
+    let limits = &state.attachment_limits;
+    let mut upload: Option<(String, String, Bytes)> = None;
+    while let Some(mut field) = multipart
+        .next_field()
+        .await
+        .map_err(|_| ApiError::InvalidMultipart)?
+    {
+        if field.name() != Some("file") {
+            continue;
+        }
+        let file_name = sanitize_file_name(field.file_name().unwrap_or("file"));
+        let mime_type = field
+            .content_type()
+            .unwrap_or("application/octet-stream")
+            .trim()
+            .to_lowercase();
+        if !limits.allows(&mime_type) {
+            return Err(ApiError::AttachmentTypeNotAllowed);
+        }
+        let mut data: Vec<u8> = Vec::new();
+        while let Some(chunk) = field
+            .chunk()
+            .await
+            .map_err(|_| ApiError::AttachmentUploadReadFailed)?
+        {
+            if data.len() + chunk.len() > limits.max_bytes {
+                return Err(ApiError::AttachmentTooLarge);
+            }
+            data.extend_from_slice(&chunk);
+        }
+        upload = Some((file_name, mime_type, Bytes::from(data)));
+        break;
+    }
+    let (file_name, mime_type, data) = upload.ok_or(ApiError::AttachmentFileRequired)?;
+    if data.is_empty() {
+        return Err(ApiError::AttachmentEmpty);
+    }
+    let size_bytes = data.len() as i64;
+    quotas::check(&state, project_uuid, size_bytes).await?;
+
+    let attachment_uuid = Uuid::new_v4();
+    let storage_key = format!("runs/{run_uuid}/{run_item_uuid}/{attachment_uuid}");
+    state
+        .storage
+        .put(&storage_key, data)
+        .await
+        .map_err(|_| ApiError::AttachmentStoreFailed)?;
+
+    let persisted: Result<(), ApiError> = async {
+        let save_failed = |_| ApiError::AttachmentSaveFailed;
+        let mut tx = state.db.begin().await.map_err(save_failed)?;
+        quotas::reserve(&mut tx, &state, project_uuid, size_bytes).await?;
+        let run_result_id: Uuid = match context.get::<Option<Uuid>, _>("run_result_id") {
+            Some(id) => id,
+            None => sqlx::query_scalar(
+                r#"
+                    INSERT INTO run_results (run_item_id, status, comment, updated_by_user_id)
+                    VALUES ($1, 'na', '', $2)
+                    ON CONFLICT (run_item_id) DO UPDATE SET run_item_id = EXCLUDED.run_item_id
+                    RETURNING id
+                    "#,
+            )
+            .bind(run_item_uuid)
+            .bind(actor_uuid)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(save_failed)?,
+        };
+        sqlx::query(
+            r#"
+            INSERT INTO attachments (
+              id, run_id, run_result_id, storage_provider, storage_key,
+              file_name, mime_type, size_bytes, uploaded_by_user_id
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            "#,
+        )
+        .bind(attachment_uuid)
+        .bind(run_uuid)
+        .bind(run_result_id)
+        .bind(state.storage.provider())
+        .bind(&storage_key)
+        .bind(&file_name)
+        .bind(&mime_type)
+        .bind(size_bytes)
+        .bind(actor_uuid)
+        .execute(&mut *tx)
+        .await
+        .map_err(save_failed)?;
+        audit::record(
+            &mut *tx,
+            audit::AuditEntry {
+                actor_user_id: Some(actor_uuid),
+                action: "attach",
+                entity_type: "attachment",
+                entity_id: Some(attachment_uuid),
+                project_id: Some(project_uuid),
+                run_id: Some(run_uuid),
+                before: None,
+                after: Some(json!({
+                    "runItemId": run_item_uuid,
+                    "fileName": file_name,
+                    "mimeType": mime_type,
+                    "sizeBytes": size_bytes,
+                })),
+            },
+        )
+        .await
+        .map_err(save_failed)?;
+        tx.commit().await.map_err(save_failed)
+    }
+    .await;
+    if let Err(err) = persisted {
+        let _ = state.storage.delete(&storage_key).await;
+        return Err(err);
+    }
+
+    let record = load_attachment(&state, &attachment_uuid.to_string()).await?;
+    Ok((
+        StatusCode::CREATED,
+        Json(AttachmentResponse {
+            attachment: record.view,
+        }),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v2/runs/{run_id}/items/{run_item_id}/attachments",
+    tag = "attachments",
+    params(("run_id" = String, Path), ("run_item_id" = String, Path)),
+    responses((status = 200, body = AttachmentsResponse))
+)]
+pub async fn list_item_attachments(
+    State(state): State<AppState>,
+    Path((run_id, run_item_id)): Path<(String, String)>,
+    AuthUser(actor_id): AuthUser,
+) -> Result<Json<AttachmentsResponse>, ApiError> {
+    let run_uuid = parse_uuid(&run_id, ApiError::InvalidRunId)?;
+    let run_item_uuid = parse_uuid(&run_item_id, ApiError::InvalidRunItemId)?;
+    authz::require_run_capability(&state, run_uuid, &actor_id, Capability::ProjectRead).await?;
+
+    let sql = format!(
+        "{ATTACHMENT_SELECT} WHERE ri.run_id = $1 AND ri.id = $2 ORDER BY a.created_at ASC"
+    );
+    let rows = sqlx::query(&sql)
+        .bind(run_uuid)
+        .bind(run_item_uuid)
+        .fetch_all(&state.db)
+        .await
+        .map_err(|_| ApiError::AttachmentsReadFailed)?;
+
+    Ok(Json(AttachmentsResponse {
+        attachments: rows.iter().map(map_attachment_row).collect(),
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v2/attachments/{attachment_id}/download",
+    tag = "attachments",
+    params(("attachment_id" = String, Path)),
+    responses((status = 200, description = "Содержимое файла.", content_type = "application/octet-stream"))
+)]
+pub async fn download_attachment(
+    State(state): State<AppState>,
+    Path(attachment_id): Path<String>,
+    AuthUser(actor_id): AuthUser,
+) -> Result<Response, ApiError> {
+    let record = load_attachment(&state, &attachment_id).await?;
+    authz::require_capability(
+        &state,
+        &record.project_id.to_string(),
+        &actor_id,
+        Capability::ProjectRead,
+    )
+    .await?;
+
+    let data = state
+        .storage
+        .get(&record.storage_key)
+        .await
+        .map_err(|_| ApiError::AttachmentFileMissing)?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, record.view.mime_type),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", record.view.file_name),
+            ),
+        ],
+        data,
+    )
+        .into_response())
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v2/attachments/{attachment_id}",
+    tag = "attachments",
+    params(("attachment_id" = String, Path)),
+    responses((status = 200, body = DeleteAttachmentResponse))
+)]
+pub async fn delete_attachment(
+    State(state): State<AppState>,
+    Path(attachment_id): Path<String>,
+    AuthUser(actor_id): AuthUser,
+) -> Result<Json<DeleteAttachmentResponse>, ApiError> {
+    let record = load_attachment(&state, &attachment_id).await?;
+    authz::require_capability(
+        &state,
+        &record.project_id.to_string(),
+        &actor_id,
+        Capability::ResultEdit,
+    )
+    .await?;
+    if record.run_status == "locked" {
+        return Err(ApiError::RunLockedAttachments);
+    }
+    ensure_db_user_exists(&state, &actor_id).await?;
+    let actor_uuid = parse_uuid(&actor_id, ApiError::InvalidUserId)?;
+    let attachment_uuid = parse_uuid(&record.view.id, ApiError::InvalidAttachmentId)?;
+    let run_uuid = parse_uuid(&record.view.run_id, ApiError::InvalidRunId)?;
+
+    let deleted: Result<(), sqlx::Error> = async {
+        let mut tx = state.db.begin().await?;
+        sqlx::query(r#"DELETE FROM attachments WHERE id = $1"#)
+            .bind(attachment_uuid)
+            .execute(&mut *tx)
+            .await?;
+        audit::record(
+            &mut *tx,
+            audit::AuditEntry {
+                actor_user_id: Some(actor_uuid),
+                action: "detach",
+                entity_type: "attachment",
+                entity_id: Some(attachment_uuid),
+                project_id: Some(record.project_id),
+                run_id: Some(run_uuid),
+                before: Some(json!({
+                    "runItemId": record.view.run_item_id,
+                    "fileName": record.view.file_name,
+                    "mimeType": record.view.mime_type,
+                    "sizeBytes": record.view.size_bytes,
+                })),
+                after: None,
+            },
+        )
+        .await?;
+        tx.commit().await
+    }
+    .await;
+    deleted.map_err(|_| ApiError::AttachmentDeleteFailed)?;
+
+    if state.storage.delete(&record.storage_key).await.is_err() {
+        tracing::warn!(
+            "attachment {} removed from DB but not from storage",
+            record.view.id
+        );
+    }
+
+    Ok(Json(DeleteAttachmentResponse { ok: true }))
+}
@@ -0,0 +1,252 @@
+use axum::{
+    extract::{Query, State},
+    response::Response,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::{postgres::PgArguments, Arguments, FromRow, PgExecutor};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::{
+    authz::ProjectRole,
+    error::ApiError,
+    export::{self, StreamFormat, StreamRow},
+    pagination, parse_uuid,
+    permissions::Capability,
+    AppState,
+};
+
+/// One `audit_log` row; `action` must be a value of the `audit_action` enum.
+pub struct AuditEntry<'a> {
+    pub actor_user_id: Option<Uuid>,
+    pub action: &'a str,
+    pub entity_type: &'a str,
+    pub entity_id: Option<Uuid>,
+    pub project_id: Option<Uuid>,
+    pub run_id: Option<Uuid>,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+}
+
+pub async fn record<'e, E: PgExecutor<'e>>(
+    executor: E,
+    entry: AuditEntry<'_>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        INSERT INTO audit_log (
+          actor_user_id, action, entity_type, entity_id,
+          context_project_id, context_run_id, before_json, after_json
+        )
+        VALUES ($1, $2::text::audit_action, $3, $4, $5, $6, $7, $8)
+        "#,
+        entry.actor_user_id,
+        entry.action,
+        entry.entity_type,
+        entry.entity_id,
+        entry.project_id,
+        entry.run_id,
+        entry.before,
+        entry.after,
+    )
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+#[derive(Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct ListAuditQuery {
+    run_id: Option<String>,
+    entity_type: Option<String>,
+    limit: Option<i64>,
+    cursor: Option<String>,
+}
+
+#[derive(Serialize, ToSchema, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEntryView {
+    id: String,
+    actor_user_id: Option<String>,
+    action: String,
+    entity_type: String,
+    entity_id: Option<String>,
+    run_id: Option<String>,
+    before: Option<Value>,
+    after: Option<Value>,
+    created_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ListAuditResponse {
+    entries: Vec<AuditEntryView>,
+    next_cursor: Option<String>,
+}
+
+/// Project audit trail, newest first. Restricted to the project owner.
+#[utoipa::path(
+    get,
+    path = "/api/v2/projects/{project_id}/audit-log",
+    tag = "audit",
+    params(("project_id" = String, Path), ListAuditQuery),
+    responses((status = 200, body = ListAuditResponse))
+)]
+pub async fn list_project_audit(
+    State(state): State<AppState>,
+    access: ProjectRole,
+    Query(query): Query<ListAuditQuery>,
+) -> Result<Json<ListAuditResponse>, ApiError> {
+    access.require(Capability::ProjectManage)?;
+    let project_uuid = access.project_id;
+    let (run_uuid, entity_type) = filters(query.run_id.as_deref(), query.entity_type.as_deref())?;
+    let limit = pagination::page_limit(query.limit);
+    let cursor = pagination::parse_cursor(query.cursor.as_deref())?;
+
+    let entries = sqlx::query_as!(
+        AuditEntryView,
+        r#"
+        SELECT
+          id::text AS "id!",
+          actor_user_id::text AS actor_user_id,
+          action::text AS "action!",
+          entity_type,
+          entity_id::text AS entity_id,
+          context_run_id::text AS run_id,
+          before_json AS before,
+          after_json AS after,
+          created_at
+        FROM audit_log
+        WHERE context_project_id = $1
+          AND ($2::uuid IS NULL OR context_run_id = $2)
+          AND ($3::text IS NULL OR entity_type = $3)
+          AND ($4::timestamptz IS NULL OR (created_at, id) < ($4, $5))
+        ORDER BY created_at DESC, id DESC
+        LIMIT $6
+        "#,
+        project_uuid,
+        run_uuid,
+        entity_type,
+        cursor.as_ref().map(pagination::Cursor::timestamp),
+        cursor.as_ref().map(pagination::Cursor::uuid),
+        limit + 1,
+    )
+    .fetch_all(state.read_db())
+    .await
+    .map_err(|_| ApiError::AuditReadFailed)?;
+    let (entries, next_cursor) = pagination::finish_page(entries, limit, |e| pagination::Cursor {
+        created_at: e.created_at.to_rfc3339(),
+        id: e.id.clone(),
+    });
+
+    Ok(Json(ListAuditResponse {
+        entries,
+        next_cursor,
+    }))
+}
+
+fn filters<'a>(
+    run_id: Option<&str>,
+    entity_type: Option<&'a str>,
+) -> Result<(Option<Uuid>, Option<&'a str>), ApiError> {
+    let run_uuid = match run_id {
+        Some(v) if !v.trim().is_empty() => Some(parse_uuid(v, ApiError::InvalidRunIdParam)?),
+        _ => None,
+    };
+    let entity_type = entity_type.map(str::trim).filter(|v| !v.is_empty());
+    Ok((run_uuid, entity_type))
+}
+
+const AUDIT_COLUMNS: [&str; 9] = [
+    "ID",
+    "Время",
+    "Пользователь",
+    "Действие",
+    "Тип объекта",
+    "ID объекта",
+    "Run",
+    "До",
+    "После",
+];
+
+impl StreamRow for AuditEntryView {
+    const CSV_COLUMNS: &'static [&'static str] = &AUDIT_COLUMNS;
+
+    fn cells(&self) -> Vec<String> {
+        let json = |v: &Option<Value>| v.as_ref().map(Value::to_string).unwrap_or_default();
+        vec![
+            self.id.clone(),
+            self.created_at.to_rfc3339(),
+            self.actor_user_id.clone().unwrap_or_default(),
+            self.action.clone(),
+            self.entity_type.clone(),
+            self.entity_id.clone().unwrap_or_default(),
+            self.run_id.clone().unwrap_or_default(),
+            json(&self.before),
+            json(&self.after),
+        ]
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct ExportAuditQuery {
+    /// `csv` (default) or `ndjson`.
+    format: Option<String>,
+    run_id: Option<String>,
+    entity_type: Option<String>,
+}
+
+/// The whole project audit trail, newest first, streamed; same filters and access as the
+/// list. In CSV `before`/`after` are JSON text.
+#[utoipa::path(
+    get,
+    path = "/api/v2/projects/{project_id}/audit-log/export",
+    tag = "audit",
+    params(("project_id" = String, Path), ExportAuditQuery),
+    responses((status = 200, description = "Файл выгрузки (`format=csv|ndjson`).", content_type = "application/octet-stream"))
+)]
+pub async fn export_project_audit(
+    State(state): State<AppState>,
+    access: ProjectRole,
+    Query(query): Query<ExportAuditQuery>,
+) -> Result<Response, ApiError> {
+    access.require(Capability::ProjectManage)?;
+    let format = StreamFormat::parse(query.format.as_deref())?;
+    let (run_uuid, entity_type) = filters(query.run_id.as_deref(), query.entity_type.as_deref())?;
+
+    let mut args = PgArguments::default();
+    args.add(access.project_id)
+        .and_then(|()| args.add(run_uuid))
+        .and_then(|()| args.add(entity_type))
+        .map_err(|_| ApiError::ExportFailed)?;
+    export::stream::<AuditEntryView>(
+        state.read_db(),
+        r#"
+        SELECT
+          id::text AS id,
+          actor_user_id::text AS actor_user_id,
+          action::text AS action,
+          entity_type,
+          entity_id::text AS entity_id,
+          context_run_id::text AS run_id,
+          before_json AS before,
+          after_json AS after,
+          created_at
+        FROM audit_log
+        WHERE context_project_id = $1
+          AND ($2::uuid IS NULL OR context_run_id = $2)
+          AND ($3::text IS NULL OR entity_type = $3)
+        ORDER BY created_at DESC, id DESC
+        "#,
+        args,
+        format,
+        &format!("audit-{}", access.project_id),
+    )
+    .await
+}
@@ -0,0 +1,169 @@
+use axum::{
+    extract::{FromRequestParts, RawPathParams},
+    http::request::Parts,
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    admin, api_keys, error::ApiError, membership_role, parse_bearer_user_id, parse_uuid,
+    permissions, permissions::Capability, read_projects, read_users, AppState,
+};
+
+/// The authenticated caller: the owner of the API key or the subject of the bearer access
+/// token. Taking it as a handler argument makes the route reject anonymous requests.
+pub struct AuthUser(pub String);
+
+impl FromRequestParts<AppState> for AuthUser {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, ApiError> {
+        parse_bearer_user_id(&state.jwt, &parts.headers).map(AuthUser)
+    }
+}
+
+/// An operator allowed to use `/api/admin` (see [`admin::is_admin`]). API keys never are.
+pub struct AdminUser(pub String);
+
+impl FromRequestParts<AppState> for AdminUser {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, ApiError> {
+        let AuthUser(user_id) = AuthUser::from_request_parts(parts, state).await?;
+        if api_keys::current().is_some() {
+            return Err(ApiError::AdminRequired);
+        }
+        let _guard = state.file_lock.lock().await;
+        let users = read_users(&state.users_file)
+            .await
+            .map_err(|_| ApiError::AccessCheckFailed)?;
+        users
+            .iter()
+            .find(|u| u.id == user_id)
+            .filter(|u| admin::is_admin(state, u))
+            .ok_or(ApiError::AdminRequired)?;
+        Ok(AdminUser(user_id))
+    }
+}
+
+/// The caller's membership in the project named by the `{project_id}` path segment.
+/// Extraction fails unless the caller may at least read the project, so handlers only
+/// check the capabilities beyond that with [`ProjectRole::require`].
+pub struct ProjectRole {
+    pub user_id: String,
+    pub project_id: Uuid,
+    capabilities: Vec<Capability>,
+}
+
+impl ProjectRole {
+    /// Fails with the capability's denial error unless the role grants it; API-key requests
+    /// are also limited to the key's scopes.
+    pub fn require(&self, capability: Capability) -> Result<(), ApiError> {
+        if let Some(grant) = api_keys::current() {
+            grant.check(&self.project_id.to_string(), capability)?;
+        }
+        if !self.capabilities.contains(&capability) {
+            return Err(capability.denied_error());
+        }
+        Ok(())
+    }
+}
+
+impl FromRequestParts<AppState> for ProjectRole {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, ApiError> {
+        let AuthUser(user_id) = AuthUser::from_request_parts(parts, state).await?;
+        let params = RawPathParams::from_request_parts(parts, state)
+            .await
+            .map_err(|_| ApiError::InvalidProjectId)?;
+        let project_id = params
+            .iter()
+            .find(|(name, _)| *name == "project_id")
+            .map(|(_, value)| parse_uuid(value, ApiError::InvalidProjectId))
+            .ok_or(ApiError::InvalidProjectId)??;
+
+        let _guard = state.file_lock.lock().await;
+        let projects = read_projects(&state.projects_file)
+            .await
+            .map_err(|_| ApiError::AccessCheckFailed)?;
+        let project = projects
+            .iter()
+            .find(|p| p.id == project_id.to_string())
+            .ok_or(ApiError::ProjectNotFound)?;
+        let role = permissions::check(project, &user_id, Capability::ProjectRead)?;
+        Ok(ProjectRole {
+            user_id,
+            project_id,
+            capabilities: permissions::role_capabilities(project, &role),
+        })
+    }
+}
+
+/// Resolves the actor's role in the project and checks that it grants `capability`.
+/// Requests made with an API key are additionally limited to the key's project and scopes.
+pub async fn require_capability(
+    state: &AppState,
+    project_id: &str,
+    user_id: &str,
+    capability: Capability,
+) -> Result<String, ApiError> {
+    let _guard = state.file_lock.lock().await;
+    let projects = read_projects(&state.projects_file)
+        .await
+        .map_err(|_| ApiError::AccessCheckFailed)?;
+    let project = projects
+        .iter()
+        .find(|p| p.id == project_id)
+        .ok_or(ApiError::ProjectNotFound)?;
+    permissions::check(project, user_id, capability)
+}
+
+/// Same as [`require_capability`], resolving the project through the run.
+pub async fn require_run_capability(
+    state: &AppState,
+    run_id: Uuid,
+    user_id: &str,
+    capability: Capability,
+) -> Result<String, ApiError> {
+    let project_id: Option<String> =
+        sqlx::query_scalar(r#"SELECT project_id::text FROM runs WHERE id = $1"#)
+            .bind(run_id)
+            .fetch_optional(&state.db)
+            .await
+            .map_err(|_| ApiError::RunReadFailed)?;
+    let project_id = project_id.ok_or(ApiError::RunNotFound)?;
+    require_capability(state, &project_id, user_id, capability).await
+}
+
+/// Project ids the user is a member of, used to scope cross-project listings.
+pub async fn member_project_ids(state: &AppState, user_id: &str) -> Result<Vec<Uuid>, ApiError> {
+    let _guard = state.file_lock.lock().await;
+    let projects = read_projects(&state.projects_file)
+        .await
+        .map_err(|_| ApiError::AccessCheckFailed)?;
+    let grant = api_keys::current();
+    Ok(projects
+        .iter()
+        .filter(|p| membership_role(p, user_id).is_some())
+        .filter_map(|p| Uuid::parse_str(&p.id).ok())
+        .filter(|id| grant.as_ref().is_none_or(|g| g.project_id == *id))
+        .collect())
+}
+
+/// Whether the user may still record results through a channel without a sign-in (Telegram
+/// buttons, email replies): the `users` row mirrors deactivation (see `admin.rs`) and marks
+/// service accounts, which act only through their API keys.
+pub async fn is_active_person(db: &PgPool, user_id: Uuid) -> Result<bool, sqlx::Error> {
+    let active = sqlx::query_scalar!(
+        r#"
+        SELECT is_active AND password_hash <> 'service-account' AS "active!"
+        FROM users
+        WHERE id = $1
+        "#,
+        user_id,
+    )
+    .fetch_optional(db)
+    .await?;
+    Ok(active.unwrap_or(false))
+}
@@ -0,0 +1,251 @@
+use std::{path::PathBuf, time::Duration};
+
+use anyhow::{anyhow, bail, Context};
+use reqwest::{Method, RequestBuilder, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// What `uran login` saves: the server and the session tokens.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Credentials {
+    pub url: String,
+    pub email: String,
+    pub token: String,
+    pub refresh_token: String,
+}
+
+/// `$URAN_CONFIG`, or `credentials.json` in the `uran` config directory.
+fn credentials_path() -> anyhow::Result<PathBuf> {
+    if let Some(path) = std::env::var_os("URAN_CONFIG") {
+        return Ok(PathBuf::from(path));
+    }
+    let dir = match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(std::env::var_os("HOME").context("HOME is not set")?).join(".config"),
+    };
+    Ok(dir.join("uran").join("credentials.json"))
+}
+
+impl Credentials {
+    pub fn load() -> anyhow::Result<Option<Self>> {
+        let path = credentials_path()?;
+        match std::fs::read_to_string(&path) {
+            Ok(raw) => Ok(Some(serde_json::from_str(&raw).with_context(|| {
+                format!("{} is damaged; run `uran login` again", path.display())
+            })?)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err).with_context(|| format!("cannot read {}", path.display())),
+        }
+    }
+
+    /// Written readable by the user only: the refresh token opens the account.
+    pub fn save(&self) -> anyhow::Result<()> {
+        let path = credentials_path()?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let file = options
+            .open(&path)
+            .with_context(|| format!("cannot write {}", path.display()))?;
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(())
+    }
+
+    pub fn remove() -> anyhow::Result<bool> {
+        match std::fs::remove_file(credentials_path()?) {
+            Ok(()) => Ok(true),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AuthResponse {
+    token: String,
+    refresh_token: String,
+}
+
+enum Auth {
+    /// `URAN_TOKEN`: an API key or an access token, used as is.
+    Token(String),
+    /// The saved login; an expired access token is refreshed once per request.
+    Saved(Credentials),
+}
+
+pub struct Client {
+    http: reqwest::Client,
+    pub base_url: String,
+    auth: Auth,
+}
+
+fn http_client() -> anyhow::Result<reqwest::Client> {
+    Ok(reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()?)
+}
+
+/// Server messages follow the terminal language; the API knows Russian and English.
+fn accept_language() -> &'static str {
+    let lang = std::env::var("LC_ALL")
+        .or_else(|_| std::env::var("LANG"))
+        .unwrap_or_default();
+    if lang.starts_with("ru") {
+        "ru"
+    } else {
+        "en"
+    }
+}
+
+/// The API's `{"error": {"code", "message"}}` as the command's error.
+async fn api_error(response: Response) -> anyhow::Error {
+    let status = response.status();
+    let body: Value = response.json().await.unwrap_or_default();
+    match (
+        body["error"]["message"].as_str(),
+        body["error"]["code"].as_str(),
+    ) {
+        (Some(message), Some(code)) => anyhow!("{message} ({code}, HTTP {})", status.as_u16()),
+        _ => anyhow!("HTTP {status}"),
+    }
+}
+
+impl Client {
+    /// Signs in and saves the session.
+    pub async fn login(url: &str, email: &str, password: &str) -> anyhow::Result<Credentials> {
+        let response = http_client()?
+            .post(format!("{url}/api/auth/login"))
+            .header("accept-language", accept_language())
+            .json(&json!({ "email": email, "password": password }))
+            .send()
+            .await
+            .with_context(|| format!("cannot reach {url}"))?;
+        if !response.status().is_success() {
+            return Err(api_error(response).await);
+        }
+        let auth: AuthResponse = response.json().await?;
+        let credentials = Credentials {
+            url: url.to_string(),
+            email: email.to_string(),
+            token: auth.token,
+            refresh_token: auth.refresh_token,
+        };
+        credentials.save()?;
+        Ok(credentials)
+    }
+
+    /// `URAN_URL` and `URAN_TOKEN` when set (for CI), otherwise the saved login.
+    pub fn from_env() -> anyhow::Result<Self> {
+        let saved = Credentials::load()?;
+        let url = std::env::var("URAN_URL")
+            .ok()
+            .filter(|v| !v.trim().is_empty())
+            .map(|v| v.trim().trim_end_matches('/').to_string());
+        let auth = match (std::env::var("URAN_TOKEN"), saved) {
+            (Ok(token), _) if !token.trim().is_empty() => Auth::Token(token.trim().to_string()),
+            (_, Some(saved)) => Auth::Saved(saved),
+            _ => bail!("not logged in; run `uran login` or set URAN_URL and URAN_TOKEN"),
+        };
+        let base_url = match (url, &auth) {
+            (Some(url), _) => url,
+            (None, Auth::Saved(saved)) => saved.url.clone(),
+            (None, Auth::Token(_)) => bail!("URAN_TOKEN needs URAN_URL"),
+        };
+        Ok(Self {
+            http: http_client()?,
+            base_url,
+            auth,
+        })
+    }
+
+    pub fn saved(&self) -> Option<&Credentials> {
+        match &self.auth {
+            Auth::Saved(saved) => Some(saved),
+            Auth::Token(_) => None,
+        }
+    }
+
+    fn token(&self) -> &str {
+        match &self.auth {
+            Auth::Token(token) => token,
+            Auth::Saved(saved) => &saved.token,
+        }
+    }
+
+    async fn refresh(&mut self) -> anyhow::Result<bool> {
+        let Auth::Saved(saved) = &mut self.auth else {
+            return Ok(false);
+        };
+        let response = self
+            .http
+            .post(format!("{}/api/auth/refresh", self.base_url))
+            .json(&json!({ "refreshToken": saved.refresh_token }))
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Ok(false);
+        }
+        let auth: AuthResponse = response.json().await?;
+        saved.token = auth.token;
+        saved.refresh_token = auth.refresh_token;
+        saved.save()?;
+        Ok(true)
+    }
+
+    /// Sends the request built by `build`, again after refreshing the session when the
+    /// access token has expired. Error responses become errors.
+    pub async fn send(
+        &mut self,
+        method: Method,
+        path: &str,
+        build: impl Fn(RequestBuilder) -> RequestBuilder,
+    ) -> anyhow::Result<Response> {
+        let url = format!("{}{path}", self.base_url);
+        let mut refreshed = false;
+        loop {
+            let request = self
+                .http
+                .request(method.clone(), &url)
+                .bearer_auth(self.token())
+                .header("accept-language", accept_language());
+            let response = build(request)
+                .send()
+                .await
+                .with_context(|| format!("cannot reach {}", self.base_url))?;
+            if response.status() == StatusCode::UNAUTHORIZED && !refreshed && self.refresh().await?
+            {
+                refreshed = true;
+                continue;
+            }
+            let status = response.status();
+            if !status.is_success() {
+                let err = api_error(response).await;
+                if status == StatusCode::UNAUTHORIZED && self.saved().is_some() {
+                    return Err(err.context("the session has ended; run `uran login` again"));
+                }
+                return Err(err);
+            }
+            return Ok(response);
+        }
+    }
+
+    pub async fn get_json(&mut self, path: &str) -> anyhow::Result<Value> {
+        Ok(self.send(Method::GET, path, |r| r).await?.json().await?)
+    }
+
+    pub async fn post_json(&mut self, path: &str, body: &Value) -> anyhow::Result<Value> {
+        Ok(self
+            .send(Method::POST, path, |r| r.json(body))
+            .await?
+            .json()
+            .await?)
+    }
+}
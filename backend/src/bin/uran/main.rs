@@ -0,0 +1,375 @@
+//! `uran`: command line client of the API for CI scripts and terminals.
+
+mod client;
+
+use std::io::{Read, Write};
+
+use anyhow::{anyhow, bail, Context};
+use reqwest::{header::CONTENT_DISPOSITION, Method};
+use serde_json::{json, Map, Value};
+
+use client::{Client, Credentials};
+
+const USAGE: &str = "\
+usage: uran [--json] COMMAND
+
+commands:
+  login --url URL --email EMAIL [--password-stdin]
+                        sign in and save the session (password from stdin or URAN_PASSWORD)
+  logout                end the saved session
+  projects              list the projects you are a member of
+  run create --project ID [--template ID] [--title TITLE] [--suite ID] [--tags QUERY]
+             [--commit SHA] [--field KEY=VALUE]...
+                        create a run, from the project's default template without --template;
+                        prints the run id
+  results submit RUN_ID [FILE]
+                        record NDJSON results (one record per line, stdin without FILE)
+  results import FORMAT FILE --project ID [--title TITLE] [--suite ID]
+                        create a run from a junit, allure, playwright or cucumber report
+  export RUN_ID [--format csv|xlsx|ndjson|pdf] [--output FILE]
+                        download a run export, `--output -` writes to stdout
+
+--json prints the API responses instead of a summary. In CI, set URAN_URL and URAN_TOKEN
+(an API key) instead of logging in.";
+
+/// Arguments of one command: positionals in order and `--name value` options.
+struct Args {
+    positional: Vec<String>,
+    options: Vec<(String, String)>,
+    flags: Vec<String>,
+}
+
+/// Options that take no value.
+const FLAGS: &[&str] = &["--json", "--password-stdin", "--help"];
+
+impl Args {
+    fn parse(raw: impl Iterator<Item = String>) -> anyhow::Result<Self> {
+        let mut args = Args {
+            positional: Vec::new(),
+            options: Vec::new(),
+            flags: Vec::new(),
+        };
+        let mut raw = raw.peekable();
+        while let Some(arg) = raw.next() {
+            if FLAGS.contains(&arg.as_str()) || arg == "-h" {
+                args.flags.push(arg);
+            } else if let Some((name, value)) =
+                arg.split_once('=').filter(|_| arg.starts_with("--"))
+            {
+                args.options.push((name.to_string(), value.to_string()));
+            } else if arg.starts_with("--") {
+                let value = raw.next().ok_or_else(|| anyhow!("{arg} needs a value"))?;
+                args.options.push((arg, value));
+            } else {
+                args.positional.push(arg);
+            }
+        }
+        Ok(args)
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        self.flags.iter().any(|f| f == name)
+    }
+
+    fn option(&self, name: &str) -> Option<&str> {
+        self.options
+            .iter()
+            .rev()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    fn required(&self, name: &str) -> anyhow::Result<&str> {
+        self.option(name)
+            .ok_or_else(|| anyhow!("{name} is required; see `uran --help`"))
+    }
+
+    fn all<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> {
+        self.options
+            .iter()
+            .filter(move |(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Fails on options the command does not know, so a typo is not silently ignored.
+    fn only(&self, known: &[&str]) -> anyhow::Result<()> {
+        match self
+            .options
+            .iter()
+            .find(|(n, _)| !known.contains(&n.as_str()))
+        {
+            Some((name, _)) => bail!("unknown option {name}; see `uran --help`"),
+            None => Ok(()),
+        }
+    }
+
+    fn positional(&self, idx: usize, what: &str) -> anyhow::Result<&str> {
+        self.positional
+            .get(idx)
+            .map(String::as_str)
+            .ok_or_else(|| anyhow!("{what} is required; see `uran --help`"))
+    }
+}
+
+fn print_json(value: &Value) -> anyhow::Result<()> {
+    writeln!(
+        std::io::stdout(),
+        "{}",
+        serde_json::to_string_pretty(value)?
+    )?;
+    Ok(())
+}
+
+fn read_input(path: Option<&str>) -> anyhow::Result<Vec<u8>> {
+    match path {
+        None | Some("-") => {
+            let mut data = Vec::new();
+            std::io::stdin().read_to_end(&mut data)?;
+            Ok(data)
+        }
+        Some(path) => std::fs::read(path).with_context(|| format!("cannot read {path}")),
+    }
+}
+
+async fn login(args: &Args) -> anyhow::Result<()> {
+    args.only(&["--url", "--email"])?;
+    let url = args.required("--url")?.trim().trim_end_matches('/');
+    let email = args.required("--email")?.trim();
+    let password = if args.flag("--password-stdin") {
+        let mut line = String::new();
+        std::io::stdin().read_line(&mut line)?;
+        line.trim_end_matches(['\r', '\n']).to_string()
+    } else {
+        std::env::var("URAN_PASSWORD")
+            .map_err(|_| anyhow!("pass the password with --password-stdin or URAN_PASSWORD"))?
+    };
+    let credentials = Client::login(url, email, &password).await?;
+    eprintln!("logged in to {} as {}", credentials.url, credentials.email);
+    Ok(())
+}
+
+async fn logout() -> anyhow::Result<()> {
+    if let Some(saved) = Credentials::load()? {
+        // Revoking fails when the session has already expired, which is fine.
+        let revoked = reqwest::Client::new()
+            .post(format!("{}/api/auth/logout", saved.url))
+            .bearer_auth(&saved.token)
+            .json(&json!({ "refreshToken": saved.refresh_token }))
+            .send()
+            .await;
+        if revoked.is_err() {
+            eprintln!(
+                "{} is not reachable, the session stays valid until it expires",
+                saved.url
+            );
+        }
+    }
+    if Credentials::remove()? {
+        eprintln!("logged out");
+    } else {
+        eprintln!("not logged in");
+    }
+    Ok(())
+}
+
+async fn projects(client: &mut Client, json: bool) -> anyhow::Result<()> {
+    let response = client.get_json("/api/projects").await?;
+    if json {
+        return print_json(&response);
+    }
+    let projects = response["projects"].as_array().cloned().unwrap_or_default();
+    if projects.is_empty() {
+        eprintln!("no projects");
+    }
+    let mut out = std::io::stdout().lock();
+    for project in projects {
+        writeln!(
+            out,
+            "{}\t{}\t{}",
+            project["id"].as_str().unwrap_or_default(),
+            project["role"].as_str().unwrap_or_default(),
+            project["name"].as_str().unwrap_or_default()
+        )?;
+    }
+    Ok(())
+}
+
+async fn create_run(client: &mut Client, args: &Args, json: bool) -> anyhow::Result<()> {
+    args.only(&[
+        "--project",
+        "--template",
+        "--title",
+        "--suite",
+        "--tags",
+        "--commit",
+        "--field",
+    ])?;
+    let mut fields = Map::new();
+    for field in args.all("--field") {
+        let (key, value) = field
+            .split_once('=')
+            .ok_or_else(|| anyhow!("--field takes KEY=VALUE, got {field}"))?;
+        // Numbers and booleans are sent as such; anything else is text.
+        let value = serde_json::from_str::<Value>(value)
+            .ok()
+            .filter(|v| v.is_number() || v.is_boolean())
+            .unwrap_or_else(|| json!(value));
+        fields.insert(key.trim().to_string(), value);
+    }
+    let body = json!({
+        "projectId": args.required("--project")?,
+        "templateId": args.option("--template"),
+        "title": args.option("--title"),
+        "suiteId": args.option("--suite"),
+        "tagQuery": args.option("--tags"),
+        "commitSha": args.option("--commit"),
+        "customFields": if fields.is_empty() { Value::Null } else { Value::Object(fields) },
+    });
+    let response = client.post_json("/api/v2/runs", &body).await?;
+    if json {
+        return print_json(&response);
+    }
+    println!("{}", response["run"]["id"].as_str().unwrap_or_default());
+    Ok(())
+}
+
+async fn submit_results(client: &mut Client, args: &Args, json: bool) -> anyhow::Result<()> {
+    args.only(&[])?;
+    let run_id = args.positional(2, "RUN_ID")?;
+    let data = read_input(args.positional.get(3).map(String::as_str))?;
+    let response: Value = client
+        .send(
+            Method::POST,
+            &format!("/api/v2/runs/{run_id}/results/ingest"),
+            |r| {
+                r.header("content-type", "application/x-ndjson")
+                    .body(data.clone())
+            },
+        )
+        .await?
+        .json()
+        .await?;
+    if json {
+        print_json(&response)?;
+    } else {
+        for error in response["errors"].as_array().into_iter().flatten() {
+            eprintln!(
+                "line {}: {}",
+                error["line"],
+                error["error"]["message"].as_str().unwrap_or_default()
+            );
+        }
+        eprintln!(
+            "received {}, recorded {}, rejected {}",
+            response["received"], response["succeeded"], response["failed"]
+        );
+    }
+    if response["failed"].as_u64().unwrap_or(0) > 0 {
+        bail!("some results were rejected");
+    }
+    Ok(())
+}
+
+async fn import_report(client: &mut Client, args: &Args, json: bool) -> anyhow::Result<()> {
+    args.only(&["--project", "--title", "--suite"])?;
+    let format = args.positional(2, "FORMAT")?;
+    let content_type = match format {
+        "junit" => "application/xml",
+        "allure" => "application/zip",
+        "playwright" => "application/json",
+        "cucumber" => "application/x-ndjson",
+        other => bail!("unknown report format {other}; use junit, allure, playwright or cucumber"),
+    };
+    let data = read_input(Some(args.positional(3, "FILE")?))?;
+    let mut query = vec![("projectId", args.required("--project")?)];
+    query.extend(args.option("--title").map(|v| ("title", v)));
+    query.extend(args.option("--suite").map(|v| ("suiteId", v)));
+    let response: Value = client
+        .send(
+            Method::POST,
+            &format!("/api/v2/runs/import/{format}"),
+            |r| {
+                r.query(&query)
+                    .header("content-type", content_type)
+                    .body(data.clone())
+            },
+        )
+        .await?
+        .json()
+        .await?;
+    if json {
+        return print_json(&response);
+    }
+    println!("{}", response["run"]["id"].as_str().unwrap_or_default());
+    eprintln!(
+        "{} results: {} passed, {} failed, {} skipped; {} new testcases",
+        response["items"],
+        response["passed"],
+        response["failed"],
+        response["skipped"],
+        response["createdTestcases"]
+    );
+    Ok(())
+}
+
+async fn export(client: &mut Client, args: &Args) -> anyhow::Result<()> {
+    args.only(&["--format", "--output"])?;
+    let run_id = args.positional(1, "RUN_ID")?;
+    let format = args.option("--format").unwrap_or("csv");
+    let path = match format {
+        "csv" | "xlsx" | "ndjson" => format!("/api/v2/runs/{run_id}/export?format={format}"),
+        "pdf" => format!("/api/v2/runs/{run_id}/report.pdf"),
+        other => bail!("unknown export format {other}; use csv, xlsx, ndjson or pdf"),
+    };
+    let response = client.send(Method::GET, &path, |r| r).await?;
+    // The server's file name, or one made of the run id.
+    let suggested = response
+        .headers()
+        .get(CONTENT_DISPOSITION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.split("filename=").nth(1))
+        .map(|name| name.trim_matches(['"', ' ', ';']).replace(['/', '\\'], "_"))
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| format!("run-{run_id}.{format}"));
+    let data = response.bytes().await?;
+    match args.option("--output").unwrap_or(&suggested) {
+        "-" => std::io::stdout().write_all(&data)?,
+        output => {
+            std::fs::write(output, &data).with_context(|| format!("cannot write {output}"))?;
+            eprintln!("saved {output} ({} bytes)", data.len());
+        }
+    }
+    Ok(())
+}
+
+async fn run() -> anyhow::Result<()> {
+    let args = Args::parse(std::env::args().skip(1))?;
+    let json = args.flag("--json");
+    let command: Vec<&str> = args.positional.iter().map(String::as_str).collect();
+    if args.flag("--help") || args.flag("-h") || command.is_empty() {
+        println!("{USAGE}");
+        return Ok(());
+    }
+    match command.as_slice() {
+        ["login", ..] => return login(&args).await,
+        ["logout", ..] => return logout().await,
+        _ => {}
+    }
+    let mut client = Client::from_env()?;
+    match command.as_slice() {
+        ["projects"] => projects(&mut client, json).await,
+        ["run", "create"] => create_run(&mut client, &args, json).await,
+        ["results", "submit", ..] => submit_results(&mut client, &args, json).await,
+        ["results", "import", ..] => import_report(&mut client, &args, json).await,
+        ["export", _] => export(&mut client, &args).await,
+        _ => bail!("unknown command {}; see `uran --help`", command.join(" ")),
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    if let Err(err) = run().await {
+        eprintln!("uran: {err:#}");
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,1321 @@
+use std::collections::{HashMap, HashSet};
+
+use axum::{
+    extract::State,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sqlx::{PgConnection, Row};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{
+    audit::{self, AuditEntry},
+    authz::{AuthUser, ProjectRole},
+    ensure_db_user_exists,
+    error::ApiError,
+    map_project_for_user, now_iso, parse_uuid, permissions,
+    permissions::Capability,
+    read_projects, read_users, write_projects, AppState, Project, ProjectForUser, ProjectMember,
+    ProjectSettings,
+};
+
+/// Project bundles are whole projects with their run history.
+pub const MAX_BUNDLE_BYTES: usize = 100 * 1024 * 1024;
+
+const BUNDLE_FORMAT: &str = "uran.project";
+const BUNDLE_VERSION: i32 = 1;
+
+/// A project with everything needed to recreate it on another instance. Ids are those of
+/// the source instance; import assigns new ones and keeps the references between entities.
+/// Attachments, comments, webhooks, API keys, invitations and the audit log are not part of
+/// the bundle.
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectBundle {
+    /// Always `uran.project`.
+    format: String,
+    version: i32,
+    exported_at: String,
+    project: BundleProject,
+    /// Members and everyone referenced by runs and results; matched by email on import.
+    #[serde(default)]
+    users: Vec<BundleUser>,
+    #[serde(default)]
+    suites: Vec<BundleSuite>,
+    #[serde(default)]
+    testcases: Vec<BundleTestcase>,
+    #[serde(default)]
+    requirements: Vec<BundleRequirement>,
+    #[serde(default)]
+    fail_reasons: Vec<BundleFailReason>,
+    issue_tracker: Option<BundleIssueTracker>,
+    #[serde(default)]
+    assets: Vec<BundleAsset>,
+    #[serde(default)]
+    run_templates: Vec<BundleRunTemplate>,
+    #[serde(default)]
+    runs: Vec<BundleRun>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleProject {
+    id: String,
+    name: String,
+    settings: ProjectSettings,
+    #[serde(default)]
+    roles: Vec<permissions::ProjectRole>,
+    #[serde(default)]
+    members: Vec<BundleMember>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleMember {
+    user_id: String,
+    role: String,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct BundleUser {
+    id: String,
+    email: String,
+    name: String,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleSuite {
+    id: String,
+    parent_id: Option<String>,
+    key: String,
+    name: String,
+    description: String,
+    position: i32,
+    is_archived: bool,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleTestcase {
+    id: String,
+    suite_id: String,
+    key: String,
+    title: String,
+    is_required: bool,
+    estimated_minutes: Option<i32>,
+    complexity: Option<i16>,
+    is_archived: bool,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    versions: Vec<BundleTestcaseVersion>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleTestcaseVersion {
+    id: String,
+    version_number: i32,
+    summary: String,
+    preconditions: String,
+    steps: Value,
+    expected: Value,
+    typical_artifacts: Value,
+    common_mistakes: Value,
+    is_mandatory: bool,
+    estimated_minutes: Option<i32>,
+    complexity: Option<i16>,
+    change_note: String,
+    created_at: String,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleRequirement {
+    key: String,
+    title: String,
+    description: String,
+    #[serde(default)]
+    testcase_ids: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleFailReason {
+    code: String,
+    title: String,
+    description: String,
+    color: String,
+    is_active: bool,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleIssueTracker {
+    tracker_type: String,
+    base_url: String,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleAsset {
+    id: String,
+    #[serde(default)]
+    name: String,
+    asset_type: String,
+    model: String,
+    firmware_version: String,
+    location_name: String,
+    stand_name: String,
+    serial_number: Option<String>,
+    metadata: Value,
+    is_active: bool,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleRunTemplate {
+    id: String,
+    key: String,
+    name: String,
+    description: String,
+    is_active: bool,
+    #[serde(default)]
+    items: Vec<BundleTemplateItem>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleTemplateItem {
+    testcase_version_id: String,
+    position: i32,
+    is_required: bool,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleRun {
+    id: String,
+    asset_id: Option<String>,
+    #[serde(default)]
+    asset_version: Option<String>,
+    template_id: Option<String>,
+    correction_of_run_id: Option<String>,
+    title: String,
+    status: String,
+    executed_by_user_id: String,
+    lead_user_id: Option<String>,
+    default_assignee_user_id: Option<String>,
+    locked_by_user_id: Option<String>,
+    fail_reason_code: Option<String>,
+    fail_summary: String,
+    report: Value,
+    started_at: Option<String>,
+    finished_at: Option<String>,
+    locked_at: Option<String>,
+    created_at: String,
+    #[serde(default)]
+    items: Vec<BundleRunItem>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleRunItem {
+    testcase_version_id: String,
+    position: i32,
+    is_required: bool,
+    assignee_user_id: Option<String>,
+    result: Option<BundleRunResult>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleRunResult {
+    status: String,
+    fail_reason_code: Option<String>,
+    comment: String,
+    measured_value: Option<String>,
+    updated_by_user_id: Option<String>,
+    updated_at: String,
+    #[serde(default)]
+    defects: Vec<BundleDefect>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleDefect {
+    issue_key: String,
+    url: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportProjectResponse {
+    project: ProjectForUser,
+    /// Emails of bundle users without an account here: their memberships are skipped and
+    /// their references in runs and results are cleared.
+    unmatched_users: Vec<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/projects/{project_id}/export",
+    tag = "projects",
+    params(("project_id" = String, Path)),
+    responses((status = 200, body = ProjectBundle))
+)]
+pub async fn export_project(
+    State(state): State<AppState>,
+    access: ProjectRole,
+) -> Result<Response, ApiError> {
+    access.require(Capability::ProjectManage)?;
+    let (project, users) = {
+        let _guard = state.file_lock.lock().await;
+        let project = read_projects(&state.projects_file)
+            .await
+            .map_err(|_| ApiError::ExportFailed)?
+            .into_iter()
+            .find(|p| p.id == access.project_id.to_string())
+            .ok_or(ApiError::ProjectNotFound)?;
+        let users = read_users(&state.users_file)
+            .await
+            .map_err(|_| ApiError::ExportFailed)?;
+        (project, users)
+    };
+
+    // One snapshot for all tables, so runs never reference versions that are not exported.
+    let mut tx = state.db.begin().await.map_err(|_| ApiError::ExportFailed)?;
+    sqlx::query("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ, READ ONLY")
+        .execute(&mut *tx)
+        .await
+        .map_err(|_| ApiError::ExportFailed)?;
+    let mut bundle = load_bundle(&mut tx, &project)
+        .await
+        .map_err(|_| ApiError::ExportFailed)?;
+    tx.commit().await.map_err(|_| ApiError::ExportFailed)?;
+
+    let referenced = bundle.referenced_user_ids();
+    bundle.users = users
+        .iter()
+        .filter(|u| referenced.contains(u.id.as_str()))
+        .map(|u| BundleUser {
+            id: u.id.clone(),
+            email: u.email.clone(),
+            name: u.name.clone(),
+        })
+        .collect();
+
+    let body = serde_json::to_vec(&bundle).map_err(|_| ApiError::ExportFailed)?;
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/json".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"project-{}.json\"", project.id),
+            ),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+/// Recreates a bundle from `GET /api/projects/{project_id}/export` as a new project owned by
+/// the caller. Everything is inserted in one transaction; the project becomes visible only
+/// when all of it is in place.
+#[utoipa::path(
+    post,
+    path = "/api/projects/import",
+    tag = "projects",
+    request_body = ProjectBundle,
+    responses((status = 201, body = ImportProjectResponse))
+)]
+pub async fn import_project(
+    State(state): State<AppState>,
+    AuthUser(actor_id): AuthUser,
+    Json(bundle): Json<ProjectBundle>,
+) -> Result<(StatusCode, Json<ImportProjectResponse>), ApiError> {
+    if bundle.format != BUNDLE_FORMAT || bundle.version != BUNDLE_VERSION {
+        return Err(ApiError::UnsupportedProjectBundle);
+    }
+    let name = bundle.project.name.trim().to_string();
+    if name.chars().count() < 3 {
+        return Err(ApiError::ProjectNameTooShort);
+    }
+    let actor_uuid = parse_uuid(&actor_id, ApiError::InvalidUserId)?;
+    ensure_db_user_exists(&state, &actor_id).await?;
+
+    let local_users = {
+        let _guard = state.file_lock.lock().await;
+        read_users(&state.users_file)
+            .await
+            .map_err(|_| ApiError::ProjectImportFailed)?
+    };
+    let mut users = HashMap::new();
+    let mut unmatched_users = Vec::new();
+    for user in &bundle.users {
+        match local_users
+            .iter()
+            .find(|u| u.email.eq_ignore_ascii_case(user.email.trim()))
+        {
+            Some(local) => {
+                ensure_db_user_exists(&state, &local.id).await?;
+                users.insert(user.id.clone(), local.id.clone());
+            }
+            None => unmatched_users.push(user.email.clone()),
+        }
+    }
+
+    let project_id = Uuid::new_v4();
+    let mut ids = IdMap::default();
+    let mut tx = state
+        .db
+        .begin()
+        .await
+        .map_err(|_| ApiError::ProjectImportFailed)?;
+    insert_bundle(
+        &mut tx, &bundle, project_id, &name, actor_uuid, &users, &mut ids,
+    )
+    .await?;
+    audit::record(
+        &mut *tx,
+        AuditEntry {
+            actor_user_id: Some(actor_uuid),
+            action: "create",
+            entity_type: "project",
+            entity_id: Some(project_id),
+            project_id: Some(project_id),
+            run_id: None,
+            before: None,
+            after: Some(json!({
+                "name": &name,
+                "importedFromProjectId": &bundle.project.id,
+                "runs": bundle.runs.len(),
+                "testcases": bundle.testcases.len(),
+            })),
+        },
+    )
+    .await
+    .map_err(|_| ApiError::ProjectImportFailed)?;
+
+    let mut members = vec![ProjectMember {
+        user_id: actor_id.clone(),
+        role: "owner".to_string(),
+    }];
+    for member in &bundle.project.members {
+        if let Some(user_id) = users.get(&member.user_id) {
+            if !members.iter().any(|m| &m.user_id == user_id) {
+                members.push(ProjectMember {
+                    user_id: user_id.clone(),
+                    role: member.role.clone(),
+                });
+            }
+        }
+    }
+    let mut settings = bundle.project.settings.clone();
+    settings.default_run_template_id = settings
+        .default_run_template_id
+        .as_deref()
+        .and_then(|id| ids.0.get(id))
+        .map(Uuid::to_string);
+    let now = now_iso();
+    let project = Project {
+        id: project_id.to_string(),
+        name,
+        owner_id: actor_id.clone(),
+        created_at: now.clone(),
+        updated_at: now,
+        members,
+        roles: bundle.project.roles.clone(),
+        settings,
+        invitations: Vec::new(),
+        session: None,
+        session_version: 0,
+        organization_id: None,
+        organization_admins: Vec::new(),
+    };
+    let mapped = map_project_for_user(&project, &actor_id).ok_or(ApiError::ProjectImportFailed)?;
+
+    // The file is written before the commit: a failed write leaves no rows behind, a failed
+    // commit leaves a project entry whose tables are empty.
+    let _guard = state.file_lock.lock().await;
+    let mut projects = read_projects(&state.projects_file)
+        .await
+        .map_err(|_| ApiError::ProjectImportFailed)?;
+    projects.push(project);
+    write_projects(&state, &projects)
+        .await
+        .map_err(|_| ApiError::ProjectImportFailed)?;
+    tx.commit()
+        .await
+        .map_err(|_| ApiError::ProjectImportFailed)?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(ImportProjectResponse {
+            project: mapped,
+            unmatched_users,
+        }),
+    ))
+}
+
+impl ProjectBundle {
+    fn referenced_user_ids(&self) -> HashSet<&str> {
+        let mut ids: HashSet<&str> = self
+            .project
+            .members
+            .iter()
+            .map(|m| m.user_id.as_str())
+            .collect();
+        for run in &self.runs {
+            ids.insert(&run.executed_by_user_id);
+            ids.extend(run.lead_user_id.as_deref());
+            ids.extend(run.default_assignee_user_id.as_deref());
+            ids.extend(run.locked_by_user_id.as_deref());
+            for item in &run.items {
+                ids.extend(item.assignee_user_id.as_deref());
+                ids.extend(
+                    item.result
+                        .as_ref()
+                        .and_then(|r| r.updated_by_user_id.as_deref()),
+                );
+            }
+        }
+        ids
+    }
+}
+
+/// Source ids to the ids assigned on import. All entities share one map: their ids are
+/// UUIDs, so they cannot collide.
+#[derive(Default)]
+struct IdMap(HashMap<String, Uuid>);
+
+impl IdMap {
+    fn assign(&mut self, source: &str) -> Result<Uuid, ApiError> {
+        let id = Uuid::new_v4();
+        if self.0.insert(source.to_string(), id).is_some() {
+            return Err(ApiError::InvalidProjectBundle);
+        }
+        Ok(id)
+    }
+
+    fn get(&self, source: &str) -> Result<Uuid, ApiError> {
+        self.0
+            .get(source)
+            .copied()
+            .ok_or(ApiError::InvalidProjectBundle)
+    }
+
+    fn optional(&self, source: Option<&str>) -> Result<Option<Uuid>, ApiError> {
+        source.map(|id| self.get(id)).transpose()
+    }
+}
+
+/// Bundle user ids to local user ids; users without a local account map to `None`.
+fn local_user(users: &HashMap<String, String>, source: Option<&str>) -> Option<Uuid> {
+    source
+        .and_then(|id| users.get(id))
+        .and_then(|id| Uuid::parse_str(id).ok())
+}
+
+async fn load_bundle(conn: &mut PgConnection, project: &Project) -> sqlx::Result<ProjectBundle> {
+    let project_id = Uuid::parse_str(&project.id).unwrap_or_default();
+
+    let suites = sqlx::query(
+        r#"
+        SELECT id::text AS id, parent_id::text AS parent_id, key, name, description, position, is_archived
+        FROM test_suites
+        WHERE project_id = $1
+        ORDER BY position ASC, created_at ASC
+        "#,
+    )
+    .bind(project_id)
+    .fetch_all(&mut *conn)
+    .await?
+    .into_iter()
+    .map(|r| BundleSuite {
+        id: r.get("id"),
+        parent_id: r.get("parent_id"),
+        key: r.get("key"),
+        name: r.get("name"),
+        description: r.get("description"),
+        position: r.get("position"),
+        is_archived: r.get("is_archived"),
+    })
+    .collect();
+
+    let mut versions: HashMap<String, Vec<BundleTestcaseVersion>> = HashMap::new();
+    for r in sqlx::query(
+        r#"
+        SELECT
+          tv.id::text AS id,
+          tv.testcase_id::text AS testcase_id,
+          tv.version_number,
+          tv.summary,
+          tv.preconditions,
+          tv.steps_json,
+          tv.expected_json,
+          tv.typical_artifacts_json,
+          tv.common_mistakes_json,
+          tv.is_mandatory,
+          tv.estimated_minutes,
+          tv.complexity,
+          tv.change_note,
+          tv.created_at::text AS created_at
+        FROM testcase_versions tv
+        JOIN testcases tc ON tc.id = tv.testcase_id
+        JOIN test_suites s ON s.id = tc.suite_id
+        WHERE s.project_id = $1
+        ORDER BY tv.version_number ASC
+        "#,
+    )
+    .bind(project_id)
+    .fetch_all(&mut *conn)
+    .await?
+    {
+        versions
+            .entry(r.get("testcase_id"))
+            .or_default()
+            .push(BundleTestcaseVersion {
+                id: r.get("id"),
+                version_number: r.get("version_number"),
+                summary: r.get("summary"),
+                preconditions: r.get("preconditions"),
+                steps: r.get("steps_json"),
+                expected: r.get("expected_json"),
+                typical_artifacts: r.get("typical_artifacts_json"),
+                common_mistakes: r.get("common_mistakes_json"),
+                is_mandatory: r.get("is_mandatory"),
+                estimated_minutes: r.get("estimated_minutes"),
+                complexity: r.get("complexity"),
+                change_note: r.get("change_note"),
+                created_at: r.get("created_at"),
+            });
+    }
+
+    let testcases = sqlx::query(
+        r#"
+        SELECT
+          tc.id::text AS id,
+          tc.suite_id::text AS suite_id,
+          tc.key,
+          tc.title,
+          tc.is_required,
+          tc.estimated_minutes,
+          tc.complexity,
+          tc.is_archived,
+          COALESCE(
+            ARRAY_AGG(t.name::text ORDER BY t.name) FILTER (WHERE t.id IS NOT NULL),
+            ARRAY[]::text[]
+          ) AS tags
+        FROM testcases tc
+        JOIN test_suites s ON s.id = tc.suite_id
+        LEFT JOIN testcase_tags tt ON tt.testcase_id = tc.id
+        LEFT JOIN tags t ON t.id = tt.tag_id
+        WHERE s.project_id = $1
+        GROUP BY tc.id
+        ORDER BY tc.key ASC
+        "#,
+    )
+    .bind(project_id)
+    .fetch_all(&mut *conn)
+    .await?
+    .into_iter()
+    .map(|r| {
+        let id: String = r.get("id");
+        BundleTestcase {
+            versions: versions.remove(&id).unwrap_or_default(),
+            id,
+            suite_id: r.get("suite_id"),
+            key: r.get("key"),
+            title: r.get("title"),
+            is_required: r.get("is_required"),
+            estimated_minutes: r.get("estimated_minutes"),
+            complexity: r.get("complexity"),
+            is_archived: r.get("is_archived"),
+            tags: r.get("tags"),
+        }
+    })
+    .collect();
+
+    let requirements = sqlx::query(
+        r#"
+        SELECT
+          r.key,
+          r.title,
+          r.description,
+          COALESCE(
+            ARRAY_AGG(rt.testcase_id::text ORDER BY rt.created_at) FILTER (WHERE rt.testcase_id IS NOT NULL),
+            ARRAY[]::text[]
+          ) AS testcase_ids
+        FROM requirements r
+        LEFT JOIN requirement_testcases rt ON rt.requirement_id = r.id
+        WHERE r.project_id = $1
+        GROUP BY r.id
+        ORDER BY r.key ASC
+        "#,
+    )
+    .bind(project_id)
+    .fetch_all(&mut *conn)
+    .await?
+    .into_iter()
+    .map(|r| BundleRequirement {
+        key: r.get("key"),
+        title: r.get("title"),
+        description: r.get("description"),
+        testcase_ids: r.get("testcase_ids"),
+    })
+    .collect();
+
+    let fail_reasons = sqlx::query(
+        r#"
+        SELECT code, title, description, color, is_active
+        FROM project_fail_reasons
+        WHERE project_id = $1
+        ORDER BY code ASC
+        "#,
+    )
+    .bind(project_id)
+    .fetch_all(&mut *conn)
+    .await?
+    .into_iter()
+    .map(|r| BundleFailReason {
+        code: r.get("code"),
+        title: r.get("title"),
+        description: r.get("description"),
+        color: r.get("color"),
+        is_active: r.get("is_active"),
+    })
+    .collect();
+
+    let issue_tracker = sqlx::query(
+        r#"SELECT tracker_type, base_url FROM project_issue_trackers WHERE project_id = $1"#,
+    )
+    .bind(project_id)
+    .fetch_optional(&mut *conn)
+    .await?
+    .map(|r| BundleIssueTracker {
+        tracker_type: r.get("tracker_type"),
+        base_url: r.get("base_url"),
+    });
+
+    let assets = sqlx::query(
+        r#"
+        SELECT
+          id::text AS id, name, asset_type, model, firmware_version, location_name,
+          stand_name, serial_number, metadata_json, is_active
+        FROM assets
+        WHERE project_id = $1
+        ORDER BY created_at ASC
+        "#,
+    )
+    .bind(project_id)
+    .fetch_all(&mut *conn)
+    .await?
+    .into_iter()
+    .map(|r| BundleAsset {
+        id: r.get("id"),
+        name: r.get("name"),
+        asset_type: r.get("asset_type"),
+        model: r.get("model"),
+        firmware_version: r.get("firmware_version"),
+        location_name: r.get("location_name"),
+        stand_name: r.get("stand_name"),
+        serial_number: r.get("serial_number"),
+        metadata: r.get("metadata_json"),
+        is_active: r.get("is_active"),
+    })
+    .collect();
+
+    let mut template_items: HashMap<String, Vec<BundleTemplateItem>> = HashMap::new();
+    for r in sqlx::query(
+        r#"
+        SELECT rti.template_id::text AS template_id, rti.testcase_version_id::text AS testcase_version_id,
+               rti.position, rti.is_required
+        FROM run_template_items rti
+        JOIN run_templates rt ON rt.id = rti.template_id
+        WHERE rt.project_id = $1
+        ORDER BY rti.position ASC
+        "#,
+    )
+    .bind(project_id)
+    .fetch_all(&mut *conn)
+    .await?
+    {
+        template_items
+            .entry(r.get("template_id"))
+            .or_default()
+            .push(BundleTemplateItem {
+                testcase_version_id: r.get("testcase_version_id"),
+                position: r.get("position"),
+                is_required: r.get("is_required"),
+            });
+    }
+    let run_templates = sqlx::query(
+        r#"
+        SELECT id::text AS id, key, name, description, is_active
+        FROM run_templates
+        WHERE project_id = $1
+        ORDER BY key ASC
+        "#,
+    )
+    .bind(project_id)
+    .fetch_all(&mut *conn)
+    .await?
+    .into_iter()
+    .map(|r| {
+        let id: String = r.get("id");
+        BundleRunTemplate {
+            items: template_items.remove(&id).unwrap_or_default(),
+            id,
+            key: r.get("key"),
+            name: r.get("name"),
+            description: r.get("description"),
+            is_active: r.get("is_active"),
+        }
+    })
+    .collect();
+
+    let mut defects: HashMap<String, Vec<BundleDefect>> = HashMap::new();
+    for r in sqlx::query(
+        r#"
+        SELECT d.run_result_id::text AS run_result_id, d.issue_key, d.url
+        FROM defects d
+        JOIN run_results rr ON rr.id = d.run_result_id
+        JOIN run_items ri ON ri.id = rr.run_item_id
+        JOIN runs r ON r.id = ri.run_id
+        WHERE r.project_id = $1
+        ORDER BY d.created_at ASC
+        "#,
+    )
+    .bind(project_id)
+    .fetch_all(&mut *conn)
+    .await?
+    {
+        defects
+            .entry(r.get("run_result_id"))
+            .or_default()
+            .push(BundleDefect {
+                issue_key: r.get("issue_key"),
+                url: r.get("url"),
+            });
+    }
+    let mut run_items: HashMap<String, Vec<BundleRunItem>> = HashMap::new();
+    for r in sqlx::query(
+        r#"
+        SELECT
+          ri.run_id::text AS run_id,
+          ri.testcase_version_id::text AS testcase_version_id,
+          ri.position,
+          ri.is_required,
+          ri.assignee_user_id::text AS assignee_user_id,
+          rr.id::text AS result_id,
+          rr.status::text AS status,
+          rr.fail_reason_code,
+          rr.comment,
+          rr.measured_value,
+          rr.updated_by_user_id::text AS updated_by_user_id,
+          rr.updated_at::text AS updated_at
+        FROM run_items ri
+        JOIN runs r ON r.id = ri.run_id
+        LEFT JOIN run_results rr ON rr.run_item_id = ri.id
+        WHERE r.project_id = $1
+        ORDER BY ri.position ASC, ri.created_at ASC
+        "#,
+    )
+    .bind(project_id)
+    .fetch_all(&mut *conn)
+    .await?
+    {
+        let result = r
+            .get::<Option<String>, _>("result_id")
+            .map(|result_id| BundleRunResult {
+                status: r.get("status"),
+                fail_reason_code: r.get("fail_reason_code"),
+                comment: r.get("comment"),
+                measured_value: r.get("measured_value"),
+                updated_by_user_id: r.get("updated_by_user_id"),
+                updated_at: r.get("updated_at"),
+                defects: defects.remove(&result_id).unwrap_or_default(),
+            });
+        run_items
+            .entry(r.get("run_id"))
+            .or_default()
+            .push(BundleRunItem {
+                testcase_version_id: r.get("testcase_version_id"),
+                position: r.get("position"),
+                is_required: r.get("is_required"),
+                assignee_user_id: r.get("assignee_user_id"),
+                result,
+            });
+    }
+    let runs = sqlx::query(
+        r#"
+        SELECT
+          id::text AS id,
+          asset_id::text AS asset_id,
+          asset_version,
+          template_id::text AS template_id,
+          correction_of_run_id::text AS correction_of_run_id,
+          title,
+          status::text AS status,
+          executed_by_user_id::text AS executed_by_user_id,
+          lead_user_id::text AS lead_user_id,
+          default_assignee_user_id::text AS default_assignee_user_id,
+          locked_by_user_id::text AS locked_by_user_id,
+          fail_reason_code,
+          fail_summary,
+          report_json,
+          started_at::text AS started_at,
+          finished_at::text AS finished_at,
+          locked_at::text AS locked_at,
+          created_at::text AS created_at
+        FROM runs
+        WHERE project_id = $1
+        ORDER BY created_at ASC
+        "#,
+    )
+    .bind(project_id)
+    .fetch_all(&mut *conn)
+    .await?
+    .into_iter()
+    .map(|r| {
+        let id: String = r.get("id");
+        BundleRun {
+            items: run_items.remove(&id).unwrap_or_default(),
+            id,
+            asset_id: r.get("asset_id"),
+            asset_version: r.get("asset_version"),
+            template_id: r.get("template_id"),
+            correction_of_run_id: r.get("correction_of_run_id"),
+            title: r.get("title"),
+            status: r.get("status"),
+            executed_by_user_id: r.get("executed_by_user_id"),
+            lead_user_id: r.get("lead_user_id"),
+            default_assignee_user_id: r.get("default_assignee_user_id"),
+            locked_by_user_id: r.get("locked_by_user_id"),
+            fail_reason_code: r.get("fail_reason_code"),
+            fail_summary: r.get("fail_summary"),
+            report: r.get("report_json"),
+            started_at: r.get("started_at"),
+            finished_at: r.get("finished_at"),
+            locked_at: r.get("locked_at"),
+            created_at: r.get("created_at"),
+        }
+    })
+    .collect();
+
+    Ok(ProjectBundle {
+        format: BUNDLE_FORMAT.to_string(),
+        version: BUNDLE_VERSION,
+        exported_at: now_iso(),
+        project: BundleProject {
+            id: project.id.clone(),
+            name: project.name.clone(),
+            settings: project.settings.clone(),
+            roles: project.roles.clone(),
+            members: project
+                .members
+                .iter()
+                .map(|m| BundleMember {
+                    user_id: m.user_id.clone(),
+                    role: m.role.clone(),
+                })
+                .collect(),
+        },
+        users: Vec::new(),
+        suites,
+        testcases,
+        requirements,
+        fail_reasons,
+        issue_tracker,
+        assets,
+        run_templates,
+        runs,
+    })
+}
+
+/// Inserts the bundle's rows under fresh ids. A row the schema refuses (duplicate keys,
+/// failed checks, references outside the bundle) rejects the whole bundle.
+async fn insert_bundle(
+    conn: &mut PgConnection,
+    bundle: &ProjectBundle,
+    project_id: Uuid,
+    name: &str,
+    actor_uuid: Uuid,
+    users: &HashMap<String, String>,
+    ids: &mut IdMap,
+) -> Result<(), ApiError> {
+    let rejected = |_| ApiError::ProjectBundleRejected;
+
+    sqlx::query(r#"INSERT INTO projects (id, name, owner_user_id) VALUES ($1, $2, $3)"#)
+        .bind(project_id)
+        .bind(name)
+        .bind(actor_uuid)
+        .execute(&mut *conn)
+        .await
+        .map_err(|_| ApiError::ProjectImportFailed)?;
+
+    if let Some(tracker) = &bundle.issue_tracker {
+        sqlx::query(
+            r#"
+            INSERT INTO project_issue_trackers (project_id, tracker_type, base_url, updated_by_user_id)
+            VALUES ($1, $2, $3, $4)
+            "#,
+        )
+        .bind(project_id)
+        .bind(&tracker.tracker_type)
+        .bind(&tracker.base_url)
+        .bind(actor_uuid)
+        .execute(&mut *conn)
+        .await
+        .map_err(rejected)?;
+    }
+
+    for reason in &bundle.fail_reasons {
+        sqlx::query(
+            r#"
+            INSERT INTO project_fail_reasons (project_id, code, title, description, color, is_active)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            "#,
+        )
+        .bind(project_id)
+        .bind(&reason.code)
+        .bind(&reason.title)
+        .bind(&reason.description)
+        .bind(&reason.color)
+        .bind(reason.is_active)
+        .execute(&mut *conn)
+        .await
+        .map_err(rejected)?;
+    }
+
+    // Parents are linked in a second pass, so suites may come in any order.
+    for suite in &bundle.suites {
+        sqlx::query(
+            r#"
+            INSERT INTO test_suites (
+              id, project_id, key, name, description, position, is_archived,
+              created_by_user_id, updated_by_user_id
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $8)
+            "#,
+        )
+        .bind(ids.assign(&suite.id)?)
+        .bind(project_id)
+        .bind(&suite.key)
+        .bind(&suite.name)
+        .bind(&suite.description)
+        .bind(suite.position)
+        .bind(suite.is_archived)
+        .bind(actor_uuid)
+        .execute(&mut *conn)
+        .await
+        .map_err(rejected)?;
+    }
+    for suite in &bundle.suites {
+        let Some(parent_id) = ids.optional(suite.parent_id.as_deref())? else {
+            continue;
+        };
+        sqlx::query(r#"UPDATE test_suites SET parent_id = $2 WHERE id = $1"#)
+            .bind(ids.get(&suite.id)?)
+            .bind(parent_id)
+            .execute(&mut *conn)
+            .await
+            .map_err(rejected)?;
+    }
+
+    for testcase in &bundle.testcases {
+        let testcase_id = ids.assign(&testcase.id)?;
+        sqlx::query(
+            r#"
+            INSERT INTO testcases (
+              id, suite_id, key, title, is_required, estimated_minutes, complexity, is_archived,
+              created_by_user_id, updated_by_user_id
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $9)
+            "#,
+        )
+        .bind(testcase_id)
+        .bind(ids.get(&testcase.suite_id)?)
+        .bind(&testcase.key)
+        .bind(&testcase.title)
+        .bind(testcase.is_required)
+        .bind(testcase.estimated_minutes)
+        .bind(testcase.complexity)
+        .bind(testcase.is_archived)
+        .bind(actor_uuid)
+        .execute(&mut *conn)
+        .await
+        .map_err(rejected)?;
+
+        if !testcase.tags.is_empty() {
+            sqlx::query(
+                r#"INSERT INTO tags (name) SELECT UNNEST($1::text[]) ON CONFLICT (name) DO NOTHING"#,
+            )
+            .bind(&testcase.tags)
+            .execute(&mut *conn)
+            .await
+            .map_err(rejected)?;
+            sqlx::query(
+                r#"
+                INSERT INTO testcase_tags (testcase_id, tag_id)
+                SELECT $1, id FROM tags WHERE name = ANY($2::citext[])
+                ON CONFLICT DO NOTHING
+                "#,
+            )
+            .bind(testcase_id)
+            .bind(&testcase.tags)
+            .execute(&mut *conn)
+            .await
+            .map_err(rejected)?;
+        }
+
+        for version in &testcase.versions {
+            sqlx::query(
+                r#"
+                INSERT INTO testcase_versions (
+                  id, testcase_id, version_number, summary, preconditions, steps_json, expected_json,
+                  typical_artifacts_json, common_mistakes_json, is_mandatory, estimated_minutes,
+                  complexity, change_note, created_by_user_id, created_at
+                )
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15::timestamptz)
+                "#,
+            )
+            .bind(ids.assign(&version.id)?)
+            .bind(testcase_id)
+            .bind(version.version_number)
+            .bind(&version.summary)
+            .bind(&version.preconditions)
+            .bind(&version.steps)
+            .bind(&version.expected)
+            .bind(&version.typical_artifacts)
+            .bind(&version.common_mistakes)
+            .bind(version.is_mandatory)
+            .bind(version.estimated_minutes)
+            .bind(version.complexity)
+            .bind(&version.change_note)
+            .bind(actor_uuid)
+            .bind(&version.created_at)
+            .execute(&mut *conn)
+            .await
+            .map_err(rejected)?;
+        }
+    }
+
+    for requirement in &bundle.requirements {
+        let testcase_ids = requirement
+            .testcase_ids
+            .iter()
+            .map(|id| ids.get(id))
+            .collect::<Result<Vec<_>, _>>()?;
+        let requirement_id: Uuid = sqlx::query_scalar(
+            r#"
+            INSERT INTO requirements (project_id, key, title, description, created_by_user_id, updated_by_user_id)
+            VALUES ($1, $2, $3, $4, $5, $5)
+            RETURNING id
+            "#,
+        )
+        .bind(project_id)
+        .bind(&requirement.key)
+        .bind(&requirement.title)
+        .bind(&requirement.description)
+        .bind(actor_uuid)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(rejected)?;
+        sqlx::query(
+            r#"
+            INSERT INTO requirement_testcases (requirement_id, testcase_id)
+            SELECT $1, UNNEST($2::uuid[])
+            ON CONFLICT DO NOTHING
+            "#,
+        )
+        .bind(requirement_id)
+        .bind(&testcase_ids)
+        .execute(&mut *conn)
+        .await
+        .map_err(rejected)?;
+    }
+
+    for asset in &bundle.assets {
+        sqlx::query(
+            r#"
+            INSERT INTO assets (
+              id, project_id, asset_type, model, firmware_version, location_name, stand_name,
+              serial_number, metadata_json, is_active, created_by_user_id, updated_by_user_id,
+              name
+            )
+            VALUES (
+              $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $11,
+              COALESCE(NULLIF($12, ''), NULLIF($4, ''), $3)
+            )
+            "#,
+        )
+        .bind(ids.assign(&asset.id)?)
+        .bind(project_id)
+        .bind(&asset.asset_type)
+        .bind(&asset.model)
+        .bind(&asset.firmware_version)
+        .bind(&asset.location_name)
+        .bind(&asset.stand_name)
+        .bind(&asset.serial_number)
+        .bind(&asset.metadata)
+        .bind(asset.is_active)
+        .bind(actor_uuid)
+        .bind(&asset.name)
+        .execute(&mut *conn)
+        .await
+        .map_err(rejected)?;
+    }
+
+    for template in &bundle.run_templates {
+        let template_id = ids.assign(&template.id)?;
+        sqlx::query(
+            r#"
+            INSERT INTO run_templates (
+              id, project_id, key, name, description, is_active, created_by_user_id, updated_by_user_id
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $7)
+            "#,
+        )
+        .bind(template_id)
+        .bind(project_id)
+        .bind(&template.key)
+        .bind(&template.name)
+        .bind(&template.description)
+        .bind(template.is_active)
+        .bind(actor_uuid)
+        .execute(&mut *conn)
+        .await
+        .map_err(rejected)?;
+        for item in &template.items {
+            sqlx::query(
+                r#"
+                INSERT INTO run_template_items (template_id, testcase_version_id, position, is_required)
+                VALUES ($1, $2, $3, $4)
+                "#,
+            )
+            .bind(template_id)
+            .bind(ids.get(&item.testcase_version_id)?)
+            .bind(item.position)
+            .bind(item.is_required)
+            .execute(&mut *conn)
+            .await
+            .map_err(rejected)?;
+        }
+    }
+
+    for run in &bundle.runs {
+        let run_id = ids.assign(&run.id)?;
+        sqlx::query(
+            r#"
+            INSERT INTO runs (
+              id, project_id, asset_id, template_id, title, status, executed_by_user_id,
+              lead_user_id, default_assignee_user_id, locked_by_user_id, fail_reason_code,
+              fail_summary, report_json, started_at, finished_at, locked_at, created_at,
+              asset_version
+            )
+            VALUES (
+              $1, $2, $3, $4, $5, $6::run_status, $7, $8, $9, $10, $11, $12, $13,
+              $14::timestamptz, $15::timestamptz, $16::timestamptz, $17::timestamptz, $18
+            )
+            "#,
+        )
+        .bind(run_id)
+        .bind(project_id)
+        .bind(ids.optional(run.asset_id.as_deref())?)
+        .bind(ids.optional(run.template_id.as_deref())?)
+        .bind(&run.title)
+        .bind(&run.status)
+        .bind(local_user(users, Some(&run.executed_by_user_id)).unwrap_or(actor_uuid))
+        .bind(local_user(users, run.lead_user_id.as_deref()))
+        .bind(local_user(users, run.default_assignee_user_id.as_deref()))
+        .bind(local_user(users, run.locked_by_user_id.as_deref()))
+        .bind(&run.fail_reason_code)
+        .bind(&run.fail_summary)
+        .bind(&run.report)
+        .bind(&run.started_at)
+        .bind(&run.finished_at)
+        .bind(&run.locked_at)
+        .bind(&run.created_at)
+        .bind(&run.asset_version)
+        .execute(&mut *conn)
+        .await
+        .map_err(rejected)?;
+
+        for item in &run.items {
+            let run_item_id: Uuid = sqlx::query_scalar(
+                r#"
+                INSERT INTO run_items (run_id, testcase_version_id, position, is_required, assignee_user_id)
+                VALUES ($1, $2, $3, $4, $5)
+                RETURNING id
+                "#,
+            )
+            .bind(run_id)
+            .bind(ids.get(&item.testcase_version_id)?)
+            .bind(item.position)
+            .bind(item.is_required)
+            .bind(local_user(users, item.assignee_user_id.as_deref()))
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(rejected)?;
+
+            let Some(result) = &item.result else {
+                continue;
+            };
+            let run_result_id: Uuid = sqlx::query_scalar(
+                r#"
+                INSERT INTO run_results (
+                  run_item_id, status, fail_reason_code, comment, measured_value,
+                  updated_by_user_id, updated_at
+                )
+                VALUES ($1, $2::result_status, $3, $4, $5, $6, $7::timestamptz)
+                RETURNING id
+                "#,
+            )
+            .bind(run_item_id)
+            .bind(&result.status)
+            .bind(&result.fail_reason_code)
+            .bind(&result.comment)
+            .bind(&result.measured_value)
+            .bind(local_user(users, result.updated_by_user_id.as_deref()))
+            .bind(&result.updated_at)
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(rejected)?;
+            for defect in &result.defects {
+                sqlx::query(
+                    r#"
+                    INSERT INTO defects (run_result_id, issue_key, url, created_by_user_id)
+                    VALUES ($1, $2, $3, $4)
+                    "#,
+                )
+                .bind(run_result_id)
+                .bind(&defect.issue_key)
+                .bind(&defect.url)
+                .bind(actor_uuid)
+                .execute(&mut *conn)
+                .await
+                .map_err(rejected)?;
+            }
+        }
+    }
+    for run in &bundle.runs {
+        let Some(source_id) = ids.optional(run.correction_of_run_id.as_deref())? else {
+            continue;
+        };
+        sqlx::query(r#"UPDATE runs SET correction_of_run_id = $2 WHERE id = $1"#)
+            .bind(ids.get(&run.id)?)
+            .bind(source_id)
+            .execute(&mut *conn)
+            .await
+            .map_err(rejected)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,253 @@
+use std::{
+    hash::{BuildHasher, Hash, RandomState},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use axum::{body::Bytes, extract::State, Json};
+use moka::sync::Cache;
+use serde::Serialize;
+use sqlx::postgres::PgListener;
+use tracing::warn;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{authz::AdminUser, config::CacheConfig, run_groups::GroupBy, AppState, Project};
+
+const LISTENER_RETRY_DELAY: Duration = Duration::from_secs(5);
+/// Invalidation counters are shared by runs that hash to the same slot.
+const EPOCH_SLOTS: usize = 64;
+
+/// A moka cache with hit and miss counters; a zero TTL disables it.
+pub struct CountedCache<K, V> {
+    cache: Option<Cache<K, V>>,
+    ttl: Duration,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl<K, V> CountedCache<K, V>
+where
+    K: Hash + Eq + Send + Sync + 'static,
+    V: Clone + Send + Sync + 'static,
+{
+    fn new(ttl: Duration, max_entries: u64) -> Self {
+        Self {
+            cache: (!ttl.is_zero()).then(|| {
+                Cache::builder()
+                    .max_capacity(max_entries)
+                    .time_to_live(ttl)
+                    .build()
+            }),
+            ttl,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    pub fn get(&self, key: &K) -> Option<V> {
+        let cache = self.cache.as_ref()?;
+        let value = cache.get(key);
+        let counter = if value.is_some() {
+            &self.hits
+        } else {
+            &self.misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        value
+    }
+
+    pub fn insert(&self, key: K, value: V) {
+        if let Some(cache) = &self.cache {
+            cache.insert(key, value);
+        }
+    }
+
+    pub fn invalidate(&self, key: &K) {
+        if let Some(cache) = &self.cache {
+            cache.invalidate(key);
+        }
+    }
+
+    pub fn invalidate_all(&self) {
+        if let Some(cache) = &self.cache {
+            cache.invalidate_all();
+        }
+    }
+
+    fn stats(&self, name: &'static str) -> CacheStats {
+        CacheStats::new(
+            name,
+            self.ttl,
+            self.cache.as_ref().map_or(0, Cache::entry_count),
+            self.hits.load(Ordering::Relaxed),
+            self.misses.load(Ordering::Relaxed),
+        )
+    }
+}
+
+/// In-process caches of the reads that clients poll. Run details are dropped when the
+/// `run_changed` notification of migration 0045 arrives from any instance (and right away
+/// for writes that publish a live event here); project sessions when `projects.json` is
+/// written. Data the notification does not cover (test case titles, tags, user names) may
+/// lag by up to the TTL.
+pub struct HotCache {
+    /// Serialized `RunDetailsResponse` per run and `groupBy`.
+    pub run_details: CountedCache<(Uuid, Option<GroupBy>), Bytes>,
+    /// Projects by id, for the session endpoint.
+    pub projects: CountedCache<String, Arc<Project>>,
+    run_epochs: [AtomicU64; EPOCH_SLOTS],
+    hasher: RandomState,
+}
+
+impl HotCache {
+    pub fn new(config: &CacheConfig) -> Self {
+        Self {
+            run_details: CountedCache::new(config.run_details_ttl, config.max_entries),
+            projects: CountedCache::new(config.project_session_ttl, config.max_entries),
+            run_epochs: std::array::from_fn(|_| AtomicU64::new(0)),
+            hasher: RandomState::new(),
+        }
+    }
+
+    fn run_epoch(&self, run_id: Uuid) -> &AtomicU64 {
+        &self.run_epochs[self.hasher.hash_one(run_id) as usize % EPOCH_SLOTS]
+    }
+
+    /// Taken before the details are read; [`HotCache::store_run_details`] drops the result
+    /// if the run was invalidated in the meantime, so a read racing a write is not cached.
+    pub fn run_details_epoch(&self, run_id: Uuid) -> u64 {
+        self.run_epoch(run_id).load(Ordering::Acquire)
+    }
+
+    pub fn store_run_details(
+        &self,
+        run_id: Uuid,
+        group_by: Option<GroupBy>,
+        epoch: u64,
+        body: Bytes,
+    ) {
+        if self.run_details_epoch(run_id) == epoch {
+            self.run_details.insert((run_id, group_by), body);
+        }
+    }
+
+    pub fn invalidate_run(&self, run_id: Uuid) {
+        self.run_epoch(run_id).fetch_add(1, Ordering::AcqRel);
+        for group_by in [
+            None,
+            Some(GroupBy::Suite),
+            Some(GroupBy::Tag),
+            Some(GroupBy::Assignee),
+        ] {
+            self.run_details.invalidate(&(run_id, group_by));
+        }
+    }
+
+    fn invalidate_all_runs(&self) {
+        for epoch in &self.run_epochs {
+            epoch.fetch_add(1, Ordering::AcqRel);
+        }
+        self.run_details.invalidate_all();
+    }
+}
+
+/// Drops cached run details on `run_changed`. While the listener is disconnected
+/// notifications are lost, so every run is dropped on reconnect.
+pub fn spawn_invalidation_listener(state: AppState) {
+    if state.cache.run_details.cache.is_none() {
+        return;
+    }
+    tokio::spawn(async move {
+        let mut listener: Option<PgListener> = None;
+        loop {
+            let Some(active) = listener.as_mut() else {
+                match connect_listener(&state).await {
+                    Ok(connected) => {
+                        listener = Some(connected);
+                        state.cache.invalidate_all_runs();
+                    }
+                    Err(err) => {
+                        warn!("failed to listen for run changes: {err}");
+                        tokio::time::sleep(LISTENER_RETRY_DELAY).await;
+                    }
+                }
+                continue;
+            };
+            match active.try_recv().await {
+                Ok(Some(notification)) => {
+                    if let Ok(run_id) = Uuid::parse_str(notification.payload()) {
+                        state.cache.invalidate_run(run_id);
+                    }
+                }
+                Ok(None) => state.cache.invalidate_all_runs(),
+                Err(err) => {
+                    warn!("run changes listener failed: {err}");
+                    listener = None;
+                }
+            }
+        }
+    });
+}
+
+async fn connect_listener(state: &AppState) -> Result<PgListener, sqlx::Error> {
+    let mut listener = PgListener::connect_with(&state.db).await?;
+    listener.listen("run_changed").await?;
+    Ok(listener)
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheStats {
+    /// `run_details`, `project_sessions` or `analytics`.
+    name: &'static str,
+    enabled: bool,
+    ttl_seconds: u64,
+    entries: u64,
+    hits: u64,
+    misses: u64,
+    /// `null` before the first lookup.
+    hit_rate: Option<f64>,
+}
+
+impl CacheStats {
+    pub fn new(name: &'static str, ttl: Duration, entries: u64, hits: u64, misses: u64) -> Self {
+        Self {
+            name,
+            enabled: !ttl.is_zero(),
+            ttl_seconds: ttl.as_secs(),
+            entries,
+            hits,
+            misses,
+            hit_rate: (hits + misses > 0).then(|| hits as f64 / (hits + misses) as f64),
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct CacheMetricsResponse {
+    caches: Vec<CacheStats>,
+}
+
+/// Hit rates of the in-process caches of this instance since it started.
+#[utoipa::path(
+    get,
+    path = "/api/admin/cache",
+    tag = "admin",
+    responses((status = 200, body = CacheMetricsResponse))
+)]
+pub async fn cache_metrics(
+    State(state): State<AppState>,
+    AdminUser(_admin_id): AdminUser,
+) -> Json<CacheMetricsResponse> {
+    Json(CacheMetricsResponse {
+        caches: vec![
+            state.cache.run_details.stats("run_details"),
+            state.cache.projects.stats("project_sessions"),
+            state.analytics.stats(),
+        ],
+    })
+}
@@ -0,0 +1,711 @@
+use std::{collections::BTreeMap, time::Duration};
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sqlx::Row;
+use tracing::warn;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{
+    audit,
+    authz::ProjectRole,
+    chat_message::{self, ChatEvent, ChatMessage, Provider},
+    ensure_db_user_exists,
+    error::ApiError,
+    jobs, parse_uuid,
+    permissions::Capability,
+    read_users, secrets, AppState,
+};
+
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// HTTP client shared by event deliveries and test messages.
+pub struct ChatClient {
+    http: reqwest::Client,
+}
+
+impl ChatClient {
+    pub fn new() -> anyhow::Result<Self> {
+        Ok(Self {
+            http: reqwest::Client::builder()
+                .timeout(DELIVERY_TIMEOUT)
+                .build()?,
+        })
+    }
+
+    /// Posts a rendered message; `Err` carries the HTTP status (if any) and a description.
+    async fn post(&self, url: &str, payload: &Value) -> Result<i32, (Option<i32>, String)> {
+        match self.http.post(url).json(payload).send().await {
+            Ok(res) if res.status().is_success() => Ok(res.status().as_u16() as i32),
+            Ok(res) => Err((
+                Some(res.status().as_u16() as i32),
+                format!("HTTP {}", res.status()),
+            )),
+            Err(err) => Err((None, err.to_string())),
+        }
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateChatWebhookRequest {
+    /// `slack` or `mattermost`.
+    provider: String,
+    name: String,
+    /// Incoming webhook URL of the channel.
+    url: String,
+    /// Any of `run_done`, `required_failed`, `run_assigned`.
+    events: Vec<String>,
+    /// Message text per event in the provider's markup with `{placeholders}`; the built-in
+    /// text is used for events without one.
+    #[schema(value_type = Option<Object>)]
+    templates: Option<BTreeMap<String, String>>,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateChatWebhookRequest {
+    name: Option<String>,
+    url: Option<String>,
+    events: Option<Vec<String>>,
+    /// Replaces all templates; `{}` restores the built-in texts.
+    #[schema(value_type = Option<Object>)]
+    templates: Option<BTreeMap<String, String>>,
+    is_active: Option<bool>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct TestChatWebhookRequest {
+    /// Event whose template is previewed with sample values; the first subscribed by default.
+    event: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ChatWebhookView {
+    id: String,
+    provider: String,
+    name: String,
+    /// `null` when the stored URL cannot be decrypted (`SECRETS_KEY` missing or changed);
+    /// set it again with `PATCH`.
+    url: Option<String>,
+    events: Vec<String>,
+    #[schema(value_type = Object)]
+    templates: Value,
+    is_active: bool,
+    created_at: String,
+    updated_at: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ListChatWebhooksResponse {
+    webhooks: Vec<ChatWebhookView>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct DeleteChatWebhookResponse {
+    ok: bool,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TestChatWebhookResponse {
+    delivered: bool,
+    event: String,
+    status_code: Option<i32>,
+    error: Option<String>,
+}
+
+const CHAT_WEBHOOK_SELECT: &str = r#"
+    SELECT id::text AS id, provider, name, url, url_encrypted, events, templates, is_active,
+           created_at::text AS created_at, updated_at::text AS updated_at
+    FROM chat_webhooks
+"#;
+
+fn map_chat_webhook_row(state: &AppState, r: &sqlx::postgres::PgRow) -> ChatWebhookView {
+    ChatWebhookView {
+        id: r.get("id"),
+        provider: r.get("provider"),
+        name: r.get("name"),
+        url: secrets::reveal(state, r.get("url"), r.get("url_encrypted"))
+            .ok()
+            .flatten(),
+        events: r.get("events"),
+        templates: r.get("templates"),
+        is_active: r.get("is_active"),
+        created_at: r.get("created_at"),
+        updated_at: r.get("updated_at"),
+    }
+}
+
+fn normalize_name(name: &str) -> Result<String, ApiError> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().count() > 200 {
+        return Err(ApiError::InvalidChatWebhookName);
+    }
+    Ok(name.to_string())
+}
+
+fn normalize_url(url: &str) -> Result<String, ApiError> {
+    let url = url.trim();
+    if !(url.starts_with("https://") || url.starts_with("http://")) {
+        return Err(ApiError::InvalidWebhookUrl);
+    }
+    Ok(url.to_string())
+}
+
+fn normalize_events(events: &[String]) -> Result<Vec<String>, ApiError> {
+    let mut normalized: Vec<String> = Vec::new();
+    for event in events {
+        let event = ChatEvent::parse(event)?.as_str().to_string();
+        if !normalized.contains(&event) {
+            normalized.push(event);
+        }
+    }
+    if normalized.is_empty() {
+        return Err(ApiError::WebhookEventsEmpty);
+    }
+    Ok(normalized)
+}
+
+fn normalize_templates(templates: BTreeMap<String, String>) -> Result<Value, ApiError> {
+    let mut normalized = serde_json::Map::new();
+    for (event, template) in templates {
+        let event = ChatEvent::parse(&event)?;
+        normalized.insert(
+            event.as_str().to_string(),
+            Value::String(chat_message::validate_template(event, &template)?),
+        );
+    }
+    Ok(Value::Object(normalized))
+}
+
+async fn fetch_chat_webhook(
+    state: &AppState,
+    project_id: Uuid,
+    webhook_id: Uuid,
+) -> Result<ChatWebhookView, ApiError> {
+    let sql = format!("{CHAT_WEBHOOK_SELECT} WHERE project_id = $1 AND id = $2");
+    let row = sqlx::query(&sql)
+        .bind(project_id)
+        .bind(webhook_id)
+        .fetch_optional(&state.db)
+        .await
+        .map_err(|_| ApiError::ChatWebhooksReadFailed)?
+        .ok_or(ApiError::ChatWebhookNotFound)?;
+    Ok(map_chat_webhook_row(state, &row))
+}
+
+fn run_link(state: &AppState, run_id: Uuid) -> String {
+    format!("{}/?run={run_id}", state.public_url.trim_end_matches('/'))
+}
+
+/// Renders `message` for every active chat webhook of the project subscribed to its event
+/// and queues a `chat_message` job per webhook. Never fails the request that produced the
+/// event: problems are only logged, failed posts are retried by the job queue.
+fn emit(state: &AppState, project_id: Uuid, message: ChatMessage) {
+    let state = state.clone();
+    tokio::spawn(async move {
+        if let Err(err) = queue(&state, project_id, &message).await {
+            warn!(
+                "failed to queue {:?} chat notifications: {err}",
+                message.event
+            );
+        }
+    });
+}
+
+async fn queue(
+    state: &AppState,
+    project_id: Uuid,
+    message: &ChatMessage,
+) -> Result<(), sqlx::Error> {
+    let event = message.event.as_str();
+    let webhooks = sqlx::query(
+        r#"
+        SELECT id, provider, templates ->> $2 AS template
+        FROM chat_webhooks
+        WHERE project_id = $1 AND is_active = TRUE AND $2 = ANY(events)
+        "#,
+    )
+    .bind(project_id)
+    .bind(event)
+    .fetch_all(&state.db)
+    .await?;
+    for webhook in webhooks {
+        let Ok(provider) = Provider::parse(webhook.get("provider")) else {
+            continue;
+        };
+        let payload = chat_message::render(
+            provider,
+            webhook.get::<Option<&str>, _>("template"),
+            message,
+        );
+        jobs::submit(
+            state,
+            jobs::NewJob {
+                kind: jobs::JobKind::ChatMessage,
+                project_id: Some(project_id),
+                created_by_user_id: None,
+                payload: json!({
+                    "webhookId": webhook.get::<Uuid, _>("id"),
+                    "event": event,
+                    "message": payload,
+                }),
+            },
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MessageJob {
+    webhook_id: Uuid,
+    event: String,
+    message: Value,
+}
+
+/// Job handler of [`emit`]: posts the rendered message unless the webhook was deleted or
+/// switched off in the meantime.
+pub async fn run_message_job(state: &AppState, payload: Value) -> anyhow::Result<Value> {
+    let job: MessageJob = serde_json::from_value(payload)?;
+    let row = sqlx::query(
+        "SELECT url, url_encrypted FROM chat_webhooks WHERE id = $1 AND is_active = TRUE",
+    )
+    .bind(job.webhook_id)
+    .fetch_optional(&state.db)
+    .await?;
+    let Some(row) = row else {
+        return Ok(Value::Null);
+    };
+    let url = secrets::reveal(state, row.get("url"), row.get("url_encrypted"))
+        .map_err(|_| anyhow::anyhow!("SECRETS_KEY is not set"))?
+        .ok_or_else(|| anyhow::anyhow!("the chat webhook URL cannot be decrypted"))?;
+    if let Err((_, error)) = state.chat.post(&url, &job.message).await {
+        anyhow::bail!(
+            "chat webhook {} rejected {}: {error}",
+            job.webhook_id,
+            job.event
+        );
+    }
+    Ok(Value::Null)
+}
+
+/// The run was moved to `done`; the message carries the result counts.
+pub fn run_done(state: &AppState, project_id: Uuid, run_id: Uuid, run_title: String) {
+    let state = state.clone();
+    tokio::spawn(async move {
+        let counts = sqlx::query(
+            r#"
+            SELECT
+              COUNT(*) FILTER (WHERE rr.status = 'ok') AS ok,
+              COUNT(*) FILTER (WHERE rr.status = 'fail') AS fail,
+              COUNT(*) FILTER (WHERE rr.status = 'na') AS na,
+              COUNT(*) FILTER (WHERE rr.status = 'blocked') AS blocked,
+              COUNT(*) FILTER (WHERE rr.status = 'skipped') AS skipped,
+              COUNT(*) FILTER (WHERE rr.status = 'retest') AS retest
+            FROM run_items ri
+            JOIN run_results rr ON rr.run_item_id = ri.id
+            WHERE ri.run_id = $1
+            "#,
+        )
+        .bind(run_id)
+        .fetch_one(&state.db)
+        .await;
+        let count = |name: &str| {
+            counts
+                .as_ref()
+                .map(|row| row.get::<i64, _>(name).to_string())
+                .unwrap_or_default()
+        };
+        emit(
+            &state,
+            project_id,
+            ChatMessage {
+                event: ChatEvent::RunDone,
+                vars: vec![
+                    ("run", run_title),
+                    ("ok", count("ok")),
+                    ("fail", count("fail")),
+                    ("na", count("na")),
+                    ("blocked", count("blocked")),
+                    ("skipped", count("skipped")),
+                    ("retest", count("retest")),
+                ],
+                link: Some(run_link(&state, run_id)),
+            },
+        );
+    });
+}
+
+/// The first FAIL of a required item.
+pub fn required_failed(
+    state: &AppState,
+    project_id: Uuid,
+    run_id: Uuid,
+    run_title: String,
+    testcase_title: String,
+    fail_reason: Option<String>,
+    comment: String,
+) {
+    emit(
+        state,
+        project_id,
+        ChatMessage {
+            event: ChatEvent::RequiredFailed,
+            vars: vec![
+                ("run", run_title),
+                ("testcase", testcase_title),
+                ("reason", fail_reason.unwrap_or_default()),
+                ("comment", comment),
+            ],
+            link: Some(run_link(state, run_id)),
+        },
+    );
+}
+
+/// A new executor of a run item, or of the whole run when `testcase_title` is `None`.
+pub fn run_assigned(
+    state: &AppState,
+    project_id: Uuid,
+    run_id: Uuid,
+    run_title: String,
+    testcase_title: Option<String>,
+    assignee_id: String,
+) {
+    let state = state.clone();
+    tokio::spawn(async move {
+        let assignee = {
+            let _guard = state.file_lock.lock().await;
+            read_users(&state.users_file)
+                .await
+                .ok()
+                .and_then(|users| users.into_iter().find(|u| u.id == assignee_id))
+                .map(|u| u.name)
+                .unwrap_or(assignee_id)
+        };
+        emit(
+            &state,
+            project_id,
+            ChatMessage {
+                event: ChatEvent::RunAssigned,
+                vars: vec![
+                    ("run", run_title),
+                    (
+                        "testcase",
+                        testcase_title
+                            .unwrap_or_else(|| "все без отдельного исполнителя".to_string()),
+                    ),
+                    ("assignee", assignee),
+                ],
+                link: Some(run_link(&state, run_id)),
+            },
+        );
+    });
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v2/projects/{project_id}/chat-webhooks",
+    tag = "webhooks",
+    params(("project_id" = String, Path)),
+    responses((status = 200, body = ListChatWebhooksResponse))
+)]
+pub async fn list_chat_webhooks(
+    State(state): State<AppState>,
+    access: ProjectRole,
+) -> Result<Json<ListChatWebhooksResponse>, ApiError> {
+    access.require(Capability::ProjectManage)?;
+    let sql =
+        format!("{CHAT_WEBHOOK_SELECT} WHERE project_id = $1 ORDER BY created_at ASC, id ASC");
+    let rows = sqlx::query(&sql)
+        .bind(access.project_id)
+        .fetch_all(&state.db)
+        .await
+        .map_err(|_| ApiError::ChatWebhooksReadFailed)?;
+    Ok(Json(ListChatWebhooksResponse {
+        webhooks: rows
+            .iter()
+            .map(|row| map_chat_webhook_row(&state, row))
+            .collect(),
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v2/projects/{project_id}/chat-webhooks",
+    tag = "webhooks",
+    params(("project_id" = String, Path)),
+    request_body = CreateChatWebhookRequest,
+    responses((status = 201, body = ChatWebhookView))
+)]
+pub async fn create_chat_webhook(
+    State(state): State<AppState>,
+    access: ProjectRole,
+    Json(payload): Json<CreateChatWebhookRequest>,
+) -> Result<(StatusCode, Json<ChatWebhookView>), ApiError> {
+    access.require(Capability::ProjectManage)?;
+    let ProjectRole {
+        user_id: actor_id,
+        project_id,
+        ..
+    } = access;
+    let provider = Provider::parse(&payload.provider)?;
+    let name = normalize_name(&payload.name)?;
+    let url = normalize_url(&payload.url)?;
+    let sealed_url = secrets::require(&state)?.seal(&url)?;
+    let events = normalize_events(&payload.events)?;
+    let templates = normalize_templates(payload.templates.unwrap_or_default())?;
+    ensure_db_user_exists(&state, &actor_id).await?;
+    let actor_uuid = parse_uuid(&actor_id, ApiError::InvalidUserId)?;
+
+    let create_failed = |_| ApiError::ChatWebhookSaveFailed;
+    let mut tx = state.db.begin().await.map_err(create_failed)?;
+    let webhook_id: Uuid = sqlx::query_scalar(
+        r#"
+        INSERT INTO chat_webhooks (
+          project_id, provider, name, url_encrypted, events, templates, created_by_user_id
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        RETURNING id
+        "#,
+    )
+    .bind(project_id)
+    .bind(provider.as_str())
+    .bind(&name)
+    .bind(&sealed_url)
+    .bind(&events)
+    .bind(&templates)
+    .bind(actor_uuid)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(create_failed)?;
+    audit::record(
+        &mut *tx,
+        audit::AuditEntry {
+            actor_user_id: Some(actor_uuid),
+            action: "create",
+            entity_type: "chat_webhook",
+            entity_id: Some(webhook_id),
+            project_id: Some(project_id),
+            run_id: None,
+            before: None,
+            after: Some(json!({
+                "provider": provider.as_str(),
+                "name": &name,
+                "events": &events,
+                "templates": &templates,
+            })),
+        },
+    )
+    .await
+    .map_err(create_failed)?;
+    tx.commit().await.map_err(create_failed)?;
+
+    let webhook = fetch_chat_webhook(&state, project_id, webhook_id).await?;
+    Ok((StatusCode::CREATED, Json(webhook)))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/api/v2/projects/{project_id}/chat-webhooks/{webhook_id}",
+    tag = "webhooks",
+    params(("project_id" = String, Path), ("webhook_id" = String, Path)),
+    request_body = UpdateChatWebhookRequest,
+    responses((status = 200, body = ChatWebhookView))
+)]
+pub async fn update_chat_webhook(
+    State(state): State<AppState>,
+    Path((_project_id, webhook_id)): Path<(String, String)>,
+    access: ProjectRole,
+    Json(payload): Json<UpdateChatWebhookRequest>,
+) -> Result<Json<ChatWebhookView>, ApiError> {
+    access.require(Capability::ProjectManage)?;
+    let webhook_uuid = parse_uuid(&webhook_id, ApiError::InvalidWebhookId)?;
+    let name = payload.name.as_deref().map(normalize_name).transpose()?;
+    let url = payload.url.as_deref().map(normalize_url).transpose()?;
+    let sealed_url = match &url {
+        Some(url) => Some(secrets::require(&state)?.seal(url)?),
+        None => None,
+    };
+    let events = payload
+        .events
+        .as_deref()
+        .map(normalize_events)
+        .transpose()?;
+    let templates = payload.templates.map(normalize_templates).transpose()?;
+    ensure_db_user_exists(&state, &access.user_id).await?;
+    let actor_uuid = parse_uuid(&access.user_id, ApiError::InvalidUserId)?;
+    let project_id = access.project_id;
+    let before = fetch_chat_webhook(&state, project_id, webhook_uuid).await?;
+
+    let update_failed = |_| ApiError::ChatWebhookSaveFailed;
+    let mut tx = state.db.begin().await.map_err(update_failed)?;
+    sqlx::query(
+        r#"
+        UPDATE chat_webhooks
+        SET name = COALESCE($3, name),
+            url_encrypted = COALESCE($4, url_encrypted),
+            url = CASE WHEN $4 IS NULL THEN url END,
+            events = COALESCE($5, events),
+            templates = COALESCE($6, templates),
+            is_active = COALESCE($7, is_active)
+        WHERE project_id = $1 AND id = $2
+        "#,
+    )
+    .bind(project_id)
+    .bind(webhook_uuid)
+    .bind(&name)
+    .bind(&sealed_url)
+    .bind(&events)
+    .bind(&templates)
+    .bind(payload.is_active)
+    .execute(&mut *tx)
+    .await
+    .map_err(update_failed)?;
+    audit::record(
+        &mut *tx,
+        audit::AuditEntry {
+            actor_user_id: Some(actor_uuid),
+            action: "update",
+            entity_type: "chat_webhook",
+            entity_id: Some(webhook_uuid),
+            project_id: Some(project_id),
+            run_id: None,
+            before: Some(json!({
+                "name": &before.name,
+                "events": &before.events,
+                "templates": &before.templates,
+                "isActive": before.is_active,
+            })),
+            after: Some(json!({
+                "name": name.as_ref().unwrap_or(&before.name),
+                "events": events.as_ref().unwrap_or(&before.events),
+                "templates": templates.as_ref().unwrap_or(&before.templates),
+                "isActive": payload.is_active.unwrap_or(before.is_active),
+                "urlChanged": url.is_some(),
+            })),
+        },
+    )
+    .await
+    .map_err(update_failed)?;
+    tx.commit().await.map_err(update_failed)?;
+
+    Ok(Json(
+        fetch_chat_webhook(&state, project_id, webhook_uuid).await?,
+    ))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v2/projects/{project_id}/chat-webhooks/{webhook_id}",
+    tag = "webhooks",
+    params(("project_id" = String, Path), ("webhook_id" = String, Path)),
+    responses((status = 200, body = DeleteChatWebhookResponse))
+)]
+pub async fn delete_chat_webhook(
+    State(state): State<AppState>,
+    Path((_project_id, webhook_id)): Path<(String, String)>,
+    access: ProjectRole,
+) -> Result<Json<DeleteChatWebhookResponse>, ApiError> {
+    access.require(Capability::ProjectManage)?;
+    let webhook_uuid = parse_uuid(&webhook_id, ApiError::InvalidWebhookId)?;
+    ensure_db_user_exists(&state, &access.user_id).await?;
+    let actor_uuid = parse_uuid(&access.user_id, ApiError::InvalidUserId)?;
+    let project_id = access.project_id;
+    let before = fetch_chat_webhook(&state, project_id, webhook_uuid).await?;
+
+    let delete_failed = |_| ApiError::ChatWebhookSaveFailed;
+    let mut tx = state.db.begin().await.map_err(delete_failed)?;
+    sqlx::query("DELETE FROM chat_webhooks WHERE project_id = $1 AND id = $2")
+        .bind(project_id)
+        .bind(webhook_uuid)
+        .execute(&mut *tx)
+        .await
+        .map_err(delete_failed)?;
+    audit::record(
+        &mut *tx,
+        audit::AuditEntry {
+            actor_user_id: Some(actor_uuid),
+            action: "delete",
+            entity_type: "chat_webhook",
+            entity_id: Some(webhook_uuid),
+            project_id: Some(project_id),
+            run_id: None,
+            before: Some(json!({
+                "provider": &before.provider,
+                "name": &before.name,
+                "events": &before.events,
+            })),
+            after: None,
+        },
+    )
+    .await
+    .map_err(delete_failed)?;
+    tx.commit().await.map_err(delete_failed)?;
+
+    Ok(Json(DeleteChatWebhookResponse { ok: true }))
+}
+
+/// Posts a sample message right away, also for inactive webhooks, and reports what the chat
+/// service answered; nothing is stored.
+#[utoipa::path(
+    post,
+    path = "/api/v2/projects/{project_id}/chat-webhooks/{webhook_id}/test",
+    tag = "webhooks",
+    params(("project_id" = String, Path), ("webhook_id" = String, Path)),
+    request_body(content = Option<TestChatWebhookRequest>),
+    responses((status = 200, body = TestChatWebhookResponse))
+)]
+pub async fn test_chat_webhook(
+    State(state): State<AppState>,
+    Path((_project_id, webhook_id)): Path<(String, String)>,
+    access: ProjectRole,
+    payload: Option<Json<TestChatWebhookRequest>>,
+) -> Result<Json<TestChatWebhookResponse>, ApiError> {
+    access.require(Capability::ProjectManage)?;
+    let webhook_uuid = parse_uuid(&webhook_id, ApiError::InvalidWebhookId)?;
+    let requested = payload
+        .and_then(|Json(p)| p.event)
+        .as_deref()
+        .map(ChatEvent::parse)
+        .transpose()?;
+    let webhook = fetch_chat_webhook(&state, access.project_id, webhook_uuid).await?;
+    let provider = Provider::parse(&webhook.provider)?;
+    let event = match requested {
+        Some(event) => event,
+        None => webhook
+            .events
+            .first()
+            .map(|e| ChatEvent::parse(e))
+            .transpose()?
+            .unwrap_or(ChatEvent::RunDone),
+    };
+
+    let mut message = event.sample();
+    message.link = Some(state.public_url.trim_end_matches('/').to_string());
+    let template = webhook
+        .templates
+        .get(event.as_str())
+        .and_then(Value::as_str);
+    let payload = chat_message::render(provider, template, &message);
+    let url = webhook.url.ok_or(ApiError::ChatWebhookUrlUnreadable)?;
+    let (delivered, status_code, error) = match state.chat.post(&url, &payload).await {
+        Ok(status) => (true, Some(status), None),
+        Err((status, error)) => (false, status, Some(error)),
+    };
+    Ok(Json(TestChatWebhookResponse {
+        delivered,
+        event: event.as_str().to_string(),
+        status_code,
+        error,
+    }))
+}
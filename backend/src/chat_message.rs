@@ -0,0 +1,252 @@
+use serde_json::{json, Value};
+
+use crate::error::ApiError;
+
+const MAX_TEMPLATE_CHARS: usize = 2000;
+
+/// Chat service a webhook posts to; decides the payload shape and the markup of templates.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Provider {
+    /// Incoming webhook with Block Kit blocks; templates use Slack `mrkdwn`.
+    Slack,
+    /// Slack-compatible incoming webhook that only takes `text`; templates use Markdown.
+    Mattermost,
+}
+
+impl Provider {
+    pub fn parse(input: &str) -> Result<Self, ApiError> {
+        match input.trim() {
+            "slack" => Ok(Self::Slack),
+            "mattermost" => Ok(Self::Mattermost),
+            _ => Err(ApiError::InvalidChatProvider),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Slack => "slack",
+            Self::Mattermost => "mattermost",
+        }
+    }
+
+    /// Escapes a substituted value so it renders literally in the provider's markup.
+    fn escape(self, text: &str) -> String {
+        let mut out = String::with_capacity(text.len());
+        for c in text.chars() {
+            match (self, c) {
+                (Self::Slack, '&') => out.push_str("&amp;"),
+                (Self::Slack, '<') => out.push_str("&lt;"),
+                (Self::Slack, '>') => out.push_str("&gt;"),
+                (Self::Slack, '*' | '_' | '~' | '`') => {
+                    // mrkdwn has no escape character; a zero-width space breaks the formatting.
+                    out.push(c);
+                    out.push('\u{200B}');
+                }
+                (
+                    Self::Mattermost,
+                    '\\' | '*' | '_' | '~' | '`' | '[' | ']' | '(' | ')' | '#' | '|' | '<' | '>',
+                ) => {
+                    out.push('\\');
+                    out.push(c);
+                }
+                _ => out.push(c),
+            }
+        }
+        out
+    }
+}
+
+/// Events a chat webhook can subscribe to; named like the email notification kinds.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ChatEvent {
+    RunDone,
+    RequiredFailed,
+    RunAssigned,
+}
+
+impl ChatEvent {
+    pub const ALL: [ChatEvent; 3] = [
+        ChatEvent::RunDone,
+        ChatEvent::RequiredFailed,
+        ChatEvent::RunAssigned,
+    ];
+
+    pub fn parse(input: &str) -> Result<Self, ApiError> {
+        Self::ALL
+            .into_iter()
+            .find(|e| e.as_str() == input.trim())
+            .ok_or(ApiError::InvalidChatEvent)
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::RunDone => "run_done",
+            Self::RequiredFailed => "required_failed",
+            Self::RunAssigned => "run_assigned",
+        }
+    }
+
+    fn title(self) -> &'static str {
+        match self {
+            Self::RunDone => "Прогон завершён",
+            Self::RequiredFailed => "FAIL обязательного теста",
+            Self::RunAssigned => "Назначен исполнитель",
+        }
+    }
+
+    /// Placeholders a template of the event may use, as `{name}`.
+    fn placeholders(self) -> &'static [&'static str] {
+        match self {
+            Self::RunDone => &["run", "ok", "fail", "na", "blocked", "skipped", "retest"],
+            Self::RequiredFailed => &["run", "testcase", "reason", "comment"],
+            Self::RunAssigned => &["run", "testcase", "assignee"],
+        }
+    }
+
+    fn default_template(self) -> &'static str {
+        match self {
+            Self::RunDone => {
+                "Прогон «{run}» завершён: ok {ok}, fail {fail}, blocked {blocked}, skipped {skipped}, retest {retest}, n/a {na}."
+            }
+            Self::RequiredFailed => {
+                "Обязательный тест «{testcase}» в прогоне «{run}» отмечен как FAIL.\nПричина: {reason}\nКомментарий: {comment}"
+            }
+            Self::RunAssigned => "Исполнитель в прогоне «{run}»: {assignee}. Тест: {testcase}.",
+        }
+    }
+
+    /// Values used by the test delivery, so the preview shows every placeholder filled.
+    pub fn sample(self) -> ChatMessage {
+        let vars = match self {
+            Self::RunDone => vec![
+                ("run", "Проверка подключения".to_string()),
+                ("ok", "12".to_string()),
+                ("fail", "1".to_string()),
+                ("na", "0".to_string()),
+                ("blocked", "0".to_string()),
+                ("skipped", "2".to_string()),
+                ("retest", "0".to_string()),
+            ],
+            Self::RequiredFailed => vec![
+                ("run", "Проверка подключения".to_string()),
+                ("testcase", "Вход по паролю".to_string()),
+                ("reason", "product_bug".to_string()),
+                ("comment", "Тестовое сообщение".to_string()),
+            ],
+            Self::RunAssigned => vec![
+                ("run", "Проверка подключения".to_string()),
+                ("testcase", "Вход по паролю".to_string()),
+                ("assignee", "Тестовый пользователь".to_string()),
+            ],
+        };
+        ChatMessage {
+            event: self,
+            vars,
+            link: None,
+        }
+    }
+}
+
+/// An event ready to be rendered for any chat webhook of the project.
+pub struct ChatMessage {
+    pub event: ChatEvent,
+    /// Values of the event's placeholders; missing ones render as `-`.
+    pub vars: Vec<(&'static str, String)>,
+    /// Link to the run in the web UI.
+    pub link: Option<String>,
+}
+
+/// Substitutes `{name}` placeholders; `Err` carries the first unknown placeholder.
+fn fill(
+    template: &str,
+    allowed: &[&str],
+    value: impl Fn(&str) -> String,
+) -> Result<String, String> {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        match after.find('}') {
+            Some(end) if !after[..end].contains('{') => {
+                let name = &after[..end];
+                if !allowed.contains(&name) {
+                    return Err(name.to_string());
+                }
+                out.push_str(&value(name));
+                rest = &after[end + 1..];
+            }
+            _ => {
+                out.push('{');
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Trims a custom template and checks its length and placeholders against the event.
+pub fn validate_template(event: ChatEvent, template: &str) -> Result<String, ApiError> {
+    let template = template.trim();
+    if template.is_empty() || template.chars().count() > MAX_TEMPLATE_CHARS {
+        return Err(ApiError::InvalidChatTemplate);
+    }
+    fill(template, event.placeholders(), |_| String::new())
+        .map_err(|_| ApiError::InvalidChatTemplate)?;
+    Ok(template.to_string())
+}
+
+/// Builds the webhook payload: Block Kit for Slack, a Markdown `text` for Mattermost.
+/// `template` is the webhook's own text for the event, the built-in one when `None`.
+pub fn render(provider: Provider, template: Option<&str>, message: &ChatMessage) -> Value {
+    let event = message.event;
+    let value = |name: &str| {
+        let raw = message
+            .vars
+            .iter()
+            .find(|(key, _)| *key == name)
+            .map(|(_, v)| v.trim())
+            .filter(|v| !v.is_empty())
+            .unwrap_or("-");
+        provider.escape(raw)
+    };
+    // Stored templates are validated on save; the built-in one is the fallback either way.
+    let body = template
+        .and_then(|t| fill(t, event.placeholders(), value).ok())
+        .unwrap_or_else(|| {
+            fill(event.default_template(), event.placeholders(), value).unwrap_or_default()
+        });
+
+    match provider {
+        Provider::Slack => {
+            let mut blocks = vec![
+                json!({
+                    "type": "header",
+                    "text": { "type": "plain_text", "text": event.title() },
+                }),
+                json!({
+                    "type": "section",
+                    "text": { "type": "mrkdwn", "text": body },
+                }),
+            ];
+            if let Some(link) = &message.link {
+                blocks.push(json!({
+                    "type": "context",
+                    "elements": [{ "type": "mrkdwn", "text": format!("<{link}|Открыть прогон>") }],
+                }));
+            }
+            json!({
+                "text": format!("{}: {body}", event.title()),
+                "blocks": blocks,
+            })
+        }
+        Provider::Mattermost => {
+            let mut text = format!("#### {}\n{body}", event.title());
+            if let Some(link) = &message.link {
+                text.push_str(&format!("\n\n[Открыть прогон]({link})"));
+            }
+            json!({ "text": text })
+        }
+    }
+}
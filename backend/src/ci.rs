@@ -0,0 +1,539 @@
+use std::time::Duration;
+
+use axum::{extract::State, Json};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sqlx::Row;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{
+    audit, authz::ProjectRole, ensure_db_user_exists, error::ApiError, jobs, parse_uuid,
+    permissions::Capability, secrets, AppState,
+};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+const MAX_DESCRIPTION_CHARS: usize = 140;
+
+/// HTTP client for the GitHub/GitLab commit status APIs; stored tokens are sealed with
+/// [`secrets`].
+pub struct CiClient {
+    http: reqwest::Client,
+}
+
+impl CiClient {
+    pub fn new() -> anyhow::Result<Self> {
+        Ok(Self {
+            http: reqwest::Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .user_agent("uran-api")
+                .build()?,
+        })
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Provider {
+    Github,
+    Gitlab,
+}
+
+impl Provider {
+    fn parse(input: &str) -> Result<Self, ApiError> {
+        match input.trim() {
+            "github" => Ok(Self::Github),
+            "gitlab" => Ok(Self::Gitlab),
+            _ => Err(ApiError::InvalidCiProvider),
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Github => "github",
+            Self::Gitlab => "gitlab",
+        }
+    }
+
+    fn default_api_url(self) -> &'static str {
+        match self {
+            Self::Github => "https://api.github.com",
+            Self::Gitlab => "https://gitlab.com",
+        }
+    }
+}
+
+/// Commit status a run maps to.
+#[derive(Clone, Copy)]
+enum CommitState {
+    /// The run was created and is not done yet.
+    Pending,
+    /// No required item failed.
+    Success,
+    Failure,
+}
+
+impl CommitState {
+    fn wire_name(self, provider: Provider) -> &'static str {
+        match (self, provider) {
+            (Self::Pending, _) => "pending",
+            (Self::Success, _) => "success",
+            (Self::Failure, Provider::Github) => "failure",
+            (Self::Failure, Provider::Gitlab) => "failed",
+        }
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CiConnectionRequest {
+    /// `github` or `gitlab`.
+    provider: String,
+    /// API root: `https://api.github.com` / `https://gitlab.com` by default; for GitHub
+    /// Enterprise `https://host/api/v3`.
+    api_url: Option<String>,
+    /// `owner/repo` on GitHub, the full project path (`group/subgroup/project`) on GitLab.
+    repository: String,
+    /// `personal` (access token, default) or `oauth` (token of an OAuth app).
+    token_type: Option<String>,
+    /// Required on the first save; omitted keeps the stored token.
+    token: Option<String>,
+    /// Name of the status check, `uran` by default.
+    status_context: Option<String>,
+}
+
+/// The stored connection; the token is never returned.
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CiConnectionView {
+    provider: String,
+    api_url: String,
+    repository: String,
+    token_type: String,
+    status_context: String,
+    updated_at: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct CiConnectionResponse {
+    connection: Option<CiConnectionView>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct DeleteCiConnectionResponse {
+    ok: bool,
+}
+
+/// Lower-case hex of 7 to 64 characters; `None` for an empty value.
+pub fn parse_commit_sha(input: Option<&str>) -> Result<Option<String>, ApiError> {
+    let Some(sha) = input.map(str::trim).filter(|s| !s.is_empty()) else {
+        return Ok(None);
+    };
+    let sha = sha.to_ascii_lowercase();
+    if !(7..=64).contains(&sha.len()) || !sha.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(ApiError::InvalidCommitSha);
+    }
+    Ok(Some(sha))
+}
+
+async fn fetch_connection(
+    state: &AppState,
+    project_id: Uuid,
+) -> Result<Option<sqlx::postgres::PgRow>, ApiError> {
+    sqlx::query(
+        r#"
+        SELECT provider, api_url, repository, token_type, token_encrypted, status_context,
+               updated_at::text AS updated_at
+        FROM project_ci_connections
+        WHERE project_id = $1
+        "#,
+    )
+    .bind(project_id)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|_| ApiError::CiConnectionReadFailed)
+}
+
+fn map_connection_row(r: &sqlx::postgres::PgRow) -> CiConnectionView {
+    CiConnectionView {
+        provider: r.get("provider"),
+        api_url: r.get("api_url"),
+        repository: r.get("repository"),
+        token_type: r.get("token_type"),
+        status_context: r.get("status_context"),
+        updated_at: r.get("updated_at"),
+    }
+}
+
+async fn post_status(
+    state: &AppState,
+    project_id: Uuid,
+    run_id: Uuid,
+    commit_state: CommitState,
+    summary: &str,
+) -> anyhow::Result<()> {
+    let commit_sha: Option<String> =
+        sqlx::query_scalar(r#"SELECT commit_sha FROM runs WHERE id = $1"#)
+            .bind(run_id)
+            .fetch_optional(&state.db)
+            .await?
+            .flatten();
+    let Some(commit_sha) = commit_sha else {
+        return Ok(());
+    };
+    let Some(connection) = fetch_connection(state, project_id)
+        .await
+        .map_err(|_| anyhow::anyhow!("failed to read the CI connection"))?
+    else {
+        return Ok(());
+    };
+    let secrets = state
+        .secrets
+        .as_deref()
+        .ok_or_else(|| anyhow::anyhow!("SECRETS_KEY is not set"))?;
+    let token = secrets
+        .open(connection.get::<&[u8], _>("token_encrypted"))
+        .ok_or_else(|| anyhow::anyhow!("the stored token cannot be decrypted"))?;
+    let provider = Provider::parse(connection.get("provider"))
+        .map_err(|_| anyhow::anyhow!("unknown CI provider"))?;
+    let api_url: String = connection.get("api_url");
+    let repository: String = connection.get("repository");
+    let context: String = connection.get("status_context");
+    let target_url = format!("{}/?run={run_id}", state.public_url.trim_end_matches('/'));
+    let description: String = summary.chars().take(MAX_DESCRIPTION_CHARS).collect();
+    let state_name = commit_state.wire_name(provider);
+
+    let request = match provider {
+        Provider::Github => state
+            .ci
+            .http
+            .post(format!(
+                "{api_url}/repos/{repository}/statuses/{commit_sha}"
+            ))
+            .header("accept", "application/vnd.github+json")
+            .bearer_auth(&token)
+            .json(&json!({
+                "state": state_name,
+                "context": context,
+                "description": description,
+                "target_url": target_url,
+            })),
+        Provider::Gitlab => {
+            let request = state
+                .ci
+                .http
+                .post(format!(
+                    "{api_url}/api/v4/projects/{}/statuses/{commit_sha}",
+                    repository.replace('/', "%2F")
+                ))
+                .json(&json!({
+                    "state": state_name,
+                    "name": context,
+                    "description": description,
+                    "target_url": target_url,
+                }));
+            if connection.get::<String, _>("token_type") == "oauth" {
+                request.bearer_auth(&token)
+            } else {
+                request.header("private-token", &token)
+            }
+        }
+    };
+    let response = request.send().await?;
+    if !response.status().is_success() {
+        anyhow::bail!("{} answered HTTP {}", provider.as_str(), response.status());
+    }
+    Ok(())
+}
+
+/// Which status a `ci_status` job reports.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum RunEvent {
+    Created,
+    Done,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StatusJob {
+    project_id: Uuid,
+    run_id: Uuid,
+    event: RunEvent,
+}
+
+/// Queues a `ci_status` job; it does nothing for runs without a commit or projects without
+/// a connection, and failed posts are retried with backoff.
+fn report(state: &AppState, project_id: Uuid, run_id: Uuid, event: RunEvent) {
+    jobs::submit_in_background(
+        state,
+        jobs::NewJob {
+            kind: jobs::JobKind::CiStatus,
+            project_id: Some(project_id),
+            created_by_user_id: None,
+            payload: json!(StatusJob {
+                project_id,
+                run_id,
+                event,
+            }),
+        },
+    );
+}
+
+/// Marks the commit of a new run as pending.
+pub fn run_created(state: &AppState, project_id: Uuid, run_id: Uuid) {
+    report(state, project_id, run_id, RunEvent::Created);
+}
+
+/// Reports the outcome of a finished run: failure when any required item failed, is blocked
+/// or waits for a retest.
+pub fn run_done(state: &AppState, project_id: Uuid, run_id: Uuid) {
+    report(state, project_id, run_id, RunEvent::Done);
+}
+
+/// Job handler of [`run_created`] and [`run_done`]; the outcome is counted when the job runs.
+pub async fn run_status_job(state: &AppState, payload: Value) -> anyhow::Result<Value> {
+    let job: StatusJob = serde_json::from_value(payload)?;
+    let (commit_state, summary) = match job.event {
+        RunEvent::Created => (
+            CommitState::Pending,
+            "Ручное тестирование в процессе".to_string(),
+        ),
+        RunEvent::Done => {
+            let counts = sqlx::query(
+                r#"
+                SELECT
+                  COUNT(*) FILTER (WHERE rr.status = 'ok') AS ok,
+                  COUNT(*) FILTER (WHERE rr.status = 'fail') AS fail,
+                  COUNT(*) FILTER (WHERE rr.status IN ('blocked', 'retest')) AS unresolved,
+                  COUNT(*) FILTER (WHERE rr.status IS NULL OR rr.status = 'na') AS na
+                FROM run_items ri
+                LEFT JOIN run_results rr ON rr.run_item_id = ri.id
+                WHERE ri.run_id = $1 AND ri.is_required
+                "#,
+            )
+            .bind(job.run_id)
+            .fetch_one(&state.db)
+            .await?;
+            let (ok, fail, unresolved, na) = (
+                counts.get::<i64, _>("ok"),
+                counts.get::<i64, _>("fail"),
+                counts.get::<i64, _>("unresolved"),
+                counts.get::<i64, _>("na"),
+            );
+            let commit_state = if fail > 0 || unresolved > 0 {
+                CommitState::Failure
+            } else {
+                CommitState::Success
+            };
+            (
+                commit_state,
+                format!(
+                    "Обязательные тесты: ok {ok}, fail {fail}, blocked/retest {unresolved}, n/a {na}"
+                ),
+            )
+        }
+    };
+    post_status(state, job.project_id, job.run_id, commit_state, &summary).await?;
+    Ok(Value::Null)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v2/projects/{project_id}/ci",
+    tag = "runs",
+    params(("project_id" = String, Path)),
+    responses((status = 200, body = CiConnectionResponse))
+)]
+pub async fn get_ci_connection(
+    State(state): State<AppState>,
+    access: ProjectRole,
+) -> Result<Json<CiConnectionResponse>, ApiError> {
+    let row = fetch_connection(&state, access.project_id).await?;
+    Ok(Json(CiConnectionResponse {
+        connection: row.as_ref().map(map_connection_row),
+    }))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v2/projects/{project_id}/ci",
+    tag = "runs",
+    params(("project_id" = String, Path)),
+    request_body = CiConnectionRequest,
+    responses((status = 200, body = CiConnectionResponse))
+)]
+pub async fn put_ci_connection(
+    State(state): State<AppState>,
+    access: ProjectRole,
+    Json(payload): Json<CiConnectionRequest>,
+) -> Result<Json<CiConnectionResponse>, ApiError> {
+    access.require(Capability::ProjectManage)?;
+    let ProjectRole {
+        user_id: actor_id,
+        project_id,
+        ..
+    } = access;
+    let provider = Provider::parse(&payload.provider)?;
+    let api_url = payload
+        .api_url
+        .as_deref()
+        .map(str::trim)
+        .filter(|u| !u.is_empty())
+        .unwrap_or(provider.default_api_url())
+        .trim_end_matches('/')
+        .to_string();
+    if !(api_url.starts_with("https://") || api_url.starts_with("http://")) {
+        return Err(ApiError::InvalidCiApiUrl);
+    }
+    let repository = payload.repository.trim().trim_matches('/').to_string();
+    let segments: Vec<&str> = repository.split('/').collect();
+    let repository_valid = match provider {
+        Provider::Github => segments.len() == 2,
+        Provider::Gitlab => segments.len() >= 2,
+    } && segments.iter().all(|s| {
+        !s.is_empty()
+            && s.chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+    });
+    if !repository_valid {
+        return Err(ApiError::InvalidCiRepository);
+    }
+    let token_type = match payload.token_type.as_deref().map(str::trim) {
+        None | Some("") | Some("personal") => "personal",
+        Some("oauth") => "oauth",
+        Some(_) => return Err(ApiError::InvalidCiTokenType),
+    };
+    let status_context = payload
+        .status_context
+        .as_deref()
+        .map(str::trim)
+        .unwrap_or("uran")
+        .to_string();
+    if !(1..=100).contains(&status_context.chars().count()) {
+        return Err(ApiError::InvalidCiStatusContext);
+    }
+    let secrets = secrets::require(&state)?;
+    let sealed_token = payload
+        .token
+        .as_deref()
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .map(|token| secrets.seal(token))
+        .transpose()?;
+    ensure_db_user_exists(&state, &actor_id).await?;
+    let actor_uuid = parse_uuid(&actor_id, ApiError::InvalidUserId)?;
+    let before = fetch_connection(&state, project_id).await?;
+    if before.is_none() && sealed_token.is_none() {
+        return Err(ApiError::CiTokenRequired);
+    }
+
+    let save_failed = |_| ApiError::CiConnectionSaveFailed;
+    let mut tx = state.db.begin().await.map_err(save_failed)?;
+    sqlx::query(
+        r#"
+        INSERT INTO project_ci_connections (
+          project_id, provider, api_url, repository, token_type, token_encrypted,
+          status_context, updated_by_user_id
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        ON CONFLICT (project_id) DO UPDATE SET
+          provider = EXCLUDED.provider,
+          api_url = EXCLUDED.api_url,
+          repository = EXCLUDED.repository,
+          token_type = EXCLUDED.token_type,
+          token_encrypted = COALESCE($6, project_ci_connections.token_encrypted),
+          status_context = EXCLUDED.status_context,
+          updated_by_user_id = EXCLUDED.updated_by_user_id,
+          updated_at = NOW()
+        "#,
+    )
+    .bind(project_id)
+    .bind(provider.as_str())
+    .bind(&api_url)
+    .bind(&repository)
+    .bind(token_type)
+    .bind(&sealed_token)
+    .bind(&status_context)
+    .bind(actor_uuid)
+    .execute(&mut *tx)
+    .await
+    .map_err(save_failed)?;
+    audit::record(
+        &mut *tx,
+        audit::AuditEntry {
+            actor_user_id: Some(actor_uuid),
+            action: if before.is_some() { "update" } else { "create" },
+            entity_type: "ci_connection",
+            entity_id: None,
+            project_id: Some(project_id),
+            run_id: None,
+            before: before.as_ref().map(|r| json!(map_connection_row(r))),
+            after: Some(json!({
+                "provider": provider.as_str(),
+                "apiUrl": &api_url,
+                "repository": &repository,
+                "tokenType": token_type,
+                "statusContext": &status_context,
+                "tokenChanged": sealed_token.is_some(),
+            })),
+        },
+    )
+    .await
+    .map_err(save_failed)?;
+    tx.commit().await.map_err(save_failed)?;
+
+    let row = fetch_connection(&state, project_id).await?;
+    Ok(Json(CiConnectionResponse {
+        connection: row.as_ref().map(map_connection_row),
+    }))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v2/projects/{project_id}/ci",
+    tag = "runs",
+    params(("project_id" = String, Path)),
+    responses((status = 200, body = DeleteCiConnectionResponse))
+)]
+pub async fn delete_ci_connection(
+    State(state): State<AppState>,
+    access: ProjectRole,
+) -> Result<Json<DeleteCiConnectionResponse>, ApiError> {
+    access.require(Capability::ProjectManage)?;
+    let ProjectRole {
+        user_id: actor_id,
+        project_id,
+        ..
+    } = access;
+    ensure_db_user_exists(&state, &actor_id).await?;
+    let actor_uuid = parse_uuid(&actor_id, ApiError::InvalidUserId)?;
+    let before = fetch_connection(&state, project_id)
+        .await?
+        .ok_or(ApiError::CiNotConfigured)?;
+
+    let delete_failed = |_| ApiError::CiConnectionSaveFailed;
+    let mut tx = state.db.begin().await.map_err(delete_failed)?;
+    sqlx::query("DELETE FROM project_ci_connections WHERE project_id = $1")
+        .bind(project_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(delete_failed)?;
+    audit::record(
+        &mut *tx,
+        audit::AuditEntry {
+            actor_user_id: Some(actor_uuid),
+            action: "delete",
+            entity_type: "ci_connection",
+            entity_id: None,
+            project_id: Some(project_id),
+            run_id: None,
+            before: Some(json!(map_connection_row(&before))),
+            after: None,
+        },
+    )
+    .await
+    .map_err(delete_failed)?;
+    tx.commit().await.map_err(delete_failed)?;
+
+    Ok(Json(DeleteCiConnectionResponse { ok: true }))
+}
@@ -0,0 +1,499 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::{postgres::PgRow, PgPool, Row};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::{
+    authz::{self, AuthUser},
+    ensure_db_user_exists,
+    error::ApiError,
+    notifications, parse_uuid,
+    permissions::Capability,
+    read_projects, read_users, AppState,
+};
+
+const MAX_BODY_CHARS: usize = 5000;
+
+#[derive(Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct CommentsQuery {
+    /// Thread of this item; without it the run's own thread is returned.
+    run_item_id: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateCommentRequest {
+    body: String,
+    run_item_id: Option<String>,
+    /// Comment being replied to; it must belong to the same thread.
+    parent_id: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct UpdateCommentRequest {
+    body: String,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CommentView {
+    id: String,
+    run_id: String,
+    run_item_id: Option<String>,
+    parent_id: Option<String>,
+    author_user_id: Option<String>,
+    /// Empty for deleted comments, which stay in the list while they have replies.
+    body: String,
+    mentioned_user_ids: Vec<String>,
+    deleted: bool,
+    created_at: String,
+    updated_at: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct CommentsResponse {
+    comments: Vec<CommentView>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct CommentResponse {
+    comment: CommentView,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct DeleteCommentResponse {
+    ok: bool,
+}
+
+const COMMENT_COLUMNS: &str = r#"
+  id::text AS id,
+  run_id::text AS run_id,
+  run_item_id::text AS run_item_id,
+  parent_id::text AS parent_id,
+  author_user_id::text AS author_user_id,
+  body,
+  ARRAY(SELECT unnest(mentioned_user_ids)::text) AS mentioned_user_ids,
+  deleted_at IS NOT NULL AS deleted,
+  created_at::text AS created_at,
+  updated_at::text AS updated_at
+"#;
+
+fn map_comment_row(row: &PgRow) -> CommentView {
+    CommentView {
+        id: row.get("id"),
+        run_id: row.get("run_id"),
+        run_item_id: row.get("run_item_id"),
+        parent_id: row.get("parent_id"),
+        author_user_id: row.get("author_user_id"),
+        body: row.get("body"),
+        mentioned_user_ids: row.get("mentioned_user_ids"),
+        deleted: row.get("deleted"),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+    }
+}
+
+fn normalize_body(input: &str) -> Result<String, ApiError> {
+    let body = input.trim();
+    if body.is_empty() {
+        return Err(ApiError::CommentEmpty);
+    }
+    if body.chars().count() > MAX_BODY_CHARS {
+        return Err(ApiError::CommentTooLong);
+    }
+    Ok(body.to_string())
+}
+
+/// Lowercased `@handle` tokens of the text. Trailing punctuation is dropped, so
+/// `@ivan.petrov,` yields `ivan.petrov`.
+fn parse_mentions(body: &str) -> Vec<String> {
+    let mut handles: Vec<String> = body
+        .split_whitespace()
+        .filter_map(|word| word.trim_start_matches(['(', '"', '«']).strip_prefix('@'))
+        .map(|handle| {
+            handle
+                .trim_end_matches(|c: char| !c.is_alphanumeric())
+                .to_lowercase()
+        })
+        .filter(|handle| !handle.is_empty())
+        .collect();
+    handles.sort();
+    handles.dedup();
+    handles
+}
+
+/// Project members referenced by the mentions: a handle matches the member's email or the
+/// part of it before `@`. Unknown handles are ignored.
+async fn resolve_mentions(
+    state: &AppState,
+    project_id: &str,
+    body: &str,
+) -> Result<Vec<Uuid>, ApiError> {
+    let handles = parse_mentions(body);
+    if handles.is_empty() {
+        return Ok(Vec::new());
+    }
+    let _guard = state.file_lock.lock().await;
+    let projects = read_projects(&state.projects_file)
+        .await
+        .map_err(|_| ApiError::MembersLoadFailed)?;
+    let users = read_users(&state.users_file)
+        .await
+        .map_err(|_| ApiError::MembersLoadFailed)?;
+    let Some(project) = projects.iter().find(|p| p.id == project_id) else {
+        return Ok(Vec::new());
+    };
+    Ok(users
+        .iter()
+        .filter(|u| project.members.iter().any(|m| m.user_id == u.id))
+        .filter(|u| {
+            let email = u.email.to_lowercase();
+            let local = email.split('@').next().unwrap_or_default().to_string();
+            handles.iter().any(|h| *h == email || *h == local)
+        })
+        .filter_map(|u| Uuid::parse_str(&u.id).ok())
+        .collect())
+}
+
+async fn user_display_name(state: &AppState, user_id: &str) -> String {
+    let _guard = state.file_lock.lock().await;
+    read_users(&state.users_file)
+        .await
+        .ok()
+        .and_then(|users| users.into_iter().find(|u| u.id == user_id))
+        .map(|u| u.name)
+        .unwrap_or_else(|| "Участник проекта".to_string())
+}
+
+/// Emails members mentioned for the first time in this version of the comment.
+async fn notify_mentions(
+    state: &AppState,
+    run_uuid: Uuid,
+    actor_id: &str,
+    mentioned: &[Uuid],
+    already_notified: &[Uuid],
+    body: &str,
+) {
+    let recipients: Vec<String> = mentioned
+        .iter()
+        .filter(|id| !already_notified.contains(id))
+        .map(Uuid::to_string)
+        .filter(|id| id != actor_id)
+        .collect();
+    if recipients.is_empty() {
+        return;
+    }
+    let (run_title, project_id): (String, Option<Uuid>) =
+        sqlx::query_as(r#"SELECT title, project_id FROM runs WHERE id = $1"#)
+            .bind(run_uuid)
+            .fetch_optional(&state.db)
+            .await
+            .ok()
+            .flatten()
+            .unwrap_or_default();
+    let author = user_display_name(state, actor_id).await;
+    notifications::notify(
+        state,
+        notifications::NotificationKind::Mentioned,
+        project_id,
+        Some(run_uuid),
+        recipients,
+        format!("Вас упомянули в прогоне «{run_title}»"),
+        format!("{author} упоминает вас в комментарии к прогону «{run_title}»:\n\n{body}"),
+    );
+}
+
+/// Non-deleted comments of the run's own thread.
+pub async fn count_run_comments(db: &PgPool, run_id: Uuid) -> Result<i64, ApiError> {
+    sqlx::query_scalar(
+        r#"
+        SELECT COUNT(*)
+        FROM comments
+        WHERE run_id = $1 AND run_item_id IS NULL AND deleted_at IS NULL
+        "#,
+    )
+    .bind(run_id)
+    .fetch_one(db)
+    .await
+    .map_err(|_| ApiError::CommentsReadFailed)
+}
+
+struct CommentTarget {
+    id: Uuid,
+    run_id: Uuid,
+    author_user_id: Option<String>,
+    mentioned_user_ids: Vec<Uuid>,
+}
+
+async fn load_comment_target(
+    state: &AppState,
+    comment_id: &str,
+) -> Result<CommentTarget, ApiError> {
+    let comment_uuid = parse_uuid(comment_id, ApiError::InvalidCommentId)?;
+    let row = sqlx::query(
+        r#"
+        SELECT run_id, author_user_id::text AS author_user_id, mentioned_user_ids
+        FROM comments
+        WHERE id = $1 AND deleted_at IS NULL
+        "#,
+    )
+    .bind(comment_uuid)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|_| ApiError::CommentsReadFailed)?
+    .ok_or(ApiError::CommentNotFound)?;
+    Ok(CommentTarget {
+        id: comment_uuid,
+        run_id: row.get("run_id"),
+        author_user_id: row.get("author_user_id"),
+        mentioned_user_ids: row.get("mentioned_user_ids"),
+    })
+}
+
+/// Comments of one thread in creation order; clients nest them by `parentId`.
+#[utoipa::path(
+    get,
+    path = "/api/v2/runs/{run_id}/comments",
+    tag = "comments",
+    params(("run_id" = String, Path), CommentsQuery),
+    responses((status = 200, body = CommentsResponse))
+)]
+pub async fn list_comments(
+    State(state): State<AppState>,
+    Path(run_id): Path<String>,
+    AuthUser(actor_id): AuthUser,
+    Query(query): Query<CommentsQuery>,
+) -> Result<Json<CommentsResponse>, ApiError> {
+    let run_uuid = parse_uuid(&run_id, ApiError::InvalidRunId)?;
+    let run_item_uuid = query
+        .run_item_id
+        .as_deref()
+        .map(|id| parse_uuid(id, ApiError::InvalidRunItemId))
+        .transpose()?;
+    authz::require_run_capability(&state, run_uuid, &actor_id, Capability::ProjectRead).await?;
+
+    let sql = format!(
+        r#"
+        SELECT {COMMENT_COLUMNS}
+        FROM comments
+        WHERE run_id = $1 AND run_item_id IS NOT DISTINCT FROM $2
+        ORDER BY created_at ASC, id ASC
+        "#
+    );
+    let rows = sqlx::query(&sql)
+        .bind(run_uuid)
+        .bind(run_item_uuid)
+        .fetch_all(&state.db)
+        .await
+        .map_err(|_| ApiError::CommentsReadFailed)?;
+
+    Ok(Json(CommentsResponse {
+        comments: rows.iter().map(map_comment_row).collect(),
+    }))
+}
+
+/// Adds a comment to the run or to one of its items. Mentioned project members get an
+/// email unless they turned off `mentioned` notifications.
+#[utoipa::path(
+    post,
+    path = "/api/v2/runs/{run_id}/comments",
+    tag = "comments",
+    params(("run_id" = String, Path)),
+    request_body = CreateCommentRequest,
+    responses((status = 201, body = CommentResponse))
+)]
+pub async fn create_comment(
+    State(state): State<AppState>,
+    Path(run_id): Path<String>,
+    AuthUser(actor_id): AuthUser,
+    Json(payload): Json<CreateCommentRequest>,
+) -> Result<(StatusCode, Json<CommentResponse>), ApiError> {
+    let run_uuid = parse_uuid(&run_id, ApiError::InvalidRunId)?;
+    let run_item_uuid = payload
+        .run_item_id
+        .as_deref()
+        .map(|id| parse_uuid(id, ApiError::InvalidRunItemId))
+        .transpose()?;
+    let parent_uuid = payload
+        .parent_id
+        .as_deref()
+        .map(|id| parse_uuid(id, ApiError::InvalidCommentId))
+        .transpose()?;
+    let body = normalize_body(&payload.body)?;
+    authz::require_run_capability(&state, run_uuid, &actor_id, Capability::ResultEdit).await?;
+    ensure_db_user_exists(&state, &actor_id).await?;
+    let actor_uuid = parse_uuid(&actor_id, ApiError::InvalidUserId)?;
+
+    if let Some(run_item_uuid) = run_item_uuid {
+        let exists: bool = sqlx::query_scalar(
+            r#"SELECT EXISTS (SELECT 1 FROM run_items WHERE id = $1 AND run_id = $2)"#,
+        )
+        .bind(run_item_uuid)
+        .bind(run_uuid)
+        .fetch_one(&state.db)
+        .await
+        .map_err(|_| ApiError::CommentsReadFailed)?;
+        if !exists {
+            return Err(ApiError::RunItemNotFound);
+        }
+    }
+    if let Some(parent_uuid) = parent_uuid {
+        let in_thread: bool = sqlx::query_scalar(
+            r#"
+            SELECT EXISTS (
+              SELECT 1 FROM comments
+              WHERE id = $1 AND run_id = $2 AND run_item_id IS NOT DISTINCT FROM $3
+                AND deleted_at IS NULL
+            )
+            "#,
+        )
+        .bind(parent_uuid)
+        .bind(run_uuid)
+        .bind(run_item_uuid)
+        .fetch_one(&state.db)
+        .await
+        .map_err(|_| ApiError::CommentsReadFailed)?;
+        if !in_thread {
+            return Err(ApiError::CommentParentNotFound);
+        }
+    }
+
+    let project_id: String =
+        sqlx::query_scalar(r#"SELECT project_id::text FROM runs WHERE id = $1"#)
+            .bind(run_uuid)
+            .fetch_one(&state.db)
+            .await
+            .map_err(|_| ApiError::RunReadFailed)?;
+    let mentioned = resolve_mentions(&state, &project_id, &body).await?;
+
+    let sql = format!(
+        r#"
+        INSERT INTO comments (run_id, run_item_id, parent_id, author_user_id, body, mentioned_user_ids)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        RETURNING {COMMENT_COLUMNS}
+        "#
+    );
+    let row = sqlx::query(&sql)
+        .bind(run_uuid)
+        .bind(run_item_uuid)
+        .bind(parent_uuid)
+        .bind(actor_uuid)
+        .bind(&body)
+        .bind(&mentioned)
+        .fetch_one(&state.db)
+        .await
+        .map_err(|_| ApiError::CommentSaveFailed)?;
+
+    notify_mentions(&state, run_uuid, &actor_id, &mentioned, &[], &body).await;
+    Ok((
+        StatusCode::CREATED,
+        Json(CommentResponse {
+            comment: map_comment_row(&row),
+        }),
+    ))
+}
+
+/// Only the author can edit; members newly mentioned by the edit are notified.
+#[utoipa::path(
+    patch,
+    path = "/api/v2/comments/{comment_id}",
+    tag = "comments",
+    params(("comment_id" = String, Path)),
+    request_body = UpdateCommentRequest,
+    responses((status = 200, body = CommentResponse))
+)]
+pub async fn update_comment(
+    State(state): State<AppState>,
+    Path(comment_id): Path<String>,
+    AuthUser(actor_id): AuthUser,
+    Json(payload): Json<UpdateCommentRequest>,
+) -> Result<Json<CommentResponse>, ApiError> {
+    let body = normalize_body(&payload.body)?;
+    let target = load_comment_target(&state, &comment_id).await?;
+    authz::require_run_capability(&state, target.run_id, &actor_id, Capability::ResultEdit).await?;
+    if target.author_user_id.as_deref() != Some(actor_id.as_str()) {
+        return Err(ApiError::CommentEditForbidden);
+    }
+
+    let project_id: String =
+        sqlx::query_scalar(r#"SELECT project_id::text FROM runs WHERE id = $1"#)
+            .bind(target.run_id)
+            .fetch_one(&state.db)
+            .await
+            .map_err(|_| ApiError::RunReadFailed)?;
+    let mentioned = resolve_mentions(&state, &project_id, &body).await?;
+
+    let sql = format!(
+        r#"
+        UPDATE comments
+        SET body = $2, mentioned_user_ids = $3
+        WHERE id = $1 AND deleted_at IS NULL
+        RETURNING {COMMENT_COLUMNS}
+        "#
+    );
+    let row = sqlx::query(&sql)
+        .bind(target.id)
+        .bind(&body)
+        .bind(&mentioned)
+        .fetch_optional(&state.db)
+        .await
+        .map_err(|_| ApiError::CommentSaveFailed)?
+        .ok_or(ApiError::CommentNotFound)?;
+
+    notify_mentions(
+        &state,
+        target.run_id,
+        &actor_id,
+        &mentioned,
+        &target.mentioned_user_ids,
+        &body,
+    )
+    .await;
+    Ok(Json(CommentResponse {
+        comment: map_comment_row(&row),
+    }))
+}
+
+/// The author or a member with `project.manage` can delete. The text is erased but the row
+/// stays as a placeholder so that replies keep their place in the thread.
+#[utoipa::path(
+    delete,
+    path = "/api/v2/comments/{comment_id}",
+    tag = "comments",
+    params(("comment_id" = String, Path)),
+    responses((status = 200, body = DeleteCommentResponse))
+)]
+pub async fn delete_comment(
+    State(state): State<AppState>,
+    Path(comment_id): Path<String>,
+    AuthUser(actor_id): AuthUser,
+) -> Result<Json<DeleteCommentResponse>, ApiError> {
+    let target = load_comment_target(&state, &comment_id).await?;
+    let capability = if target.author_user_id.as_deref() == Some(actor_id.as_str()) {
+        Capability::ResultEdit
+    } else {
+        Capability::ProjectManage
+    };
+    authz::require_run_capability(&state, target.run_id, &actor_id, capability).await?;
+
+    sqlx::query(
+        r#"
+        UPDATE comments
+        SET body = '', mentioned_user_ids = '{}', deleted_at = NOW()
+        WHERE id = $1 AND deleted_at IS NULL
+        "#,
+    )
+    .bind(target.id)
+    .execute(&state.db)
+    .await
+    .map_err(|_| ApiError::CommentDeleteFailed)?;
+
+    Ok(Json(DeleteCommentResponse { ok: true }))
+}
@@ -0,0 +1,314 @@
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+
+use crate::error::ApiError;
+
+/// How far ahead a match is searched; covers `0 0 29 2 *` across a non-leap century year.
+const SEARCH_DAYS: i64 = 366 * 8;
+
+/// A standard 5-field cron expression: minute, hour, day of month, month, day of week.
+/// Fields take `*`, numbers, ranges `a-b`, steps `*/n` or `a-b/n` and comma lists; day of
+/// week is 0–7 with both 0 and 7 meaning Sunday. The `@hourly`, `@daily`, `@weekly` and
+/// `@monthly` shortcuts are accepted too.
+#[derive(Clone, Debug)]
+pub struct CronExpr {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    /// Neither day of month nor day of week starts with `*`: a day matching either one
+    /// fires, as in Vixie cron. Otherwise both must match (`*/2` restricts, but still ANDs).
+    day_or_weekday: bool,
+}
+
+impl CronExpr {
+    pub fn parse(input: &str) -> Result<Self, ApiError> {
+        let input = input.trim();
+        let expanded = match input {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            other => other,
+        };
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(ApiError::InvalidCronExpression);
+        };
+        let mut weekdays = parse_field(weekday, 0, 7)?;
+        if weekdays & (1 << 7) != 0 {
+            weekdays = (weekdays | 1) & !(1 << 7);
+        }
+        Ok(Self {
+            minutes: parse_field(minute, 0, 59)?,
+            hours: parse_field(hour, 0, 23)?,
+            days: parse_field(day, 1, 31)?,
+            months: parse_field(month, 1, 12)?,
+            weekdays,
+            day_or_weekday: !day.starts_with('*') && !weekday.starts_with('*'),
+        })
+    }
+
+    fn matches_date(&self, date: NaiveDate) -> bool {
+        if self.months & (1 << date.month()) == 0 {
+            return false;
+        }
+        let by_day = self.days & (1 << date.day()) != 0;
+        let by_weekday = self.weekdays & (1 << date.weekday().num_days_from_sunday()) != 0;
+        if self.day_or_weekday {
+            by_day || by_weekday
+        } else {
+            by_day && by_weekday
+        }
+    }
+
+    /// First matching minute strictly after `after`, in the same (wall-clock) time scale;
+    /// `None` when nothing matches within the search horizon (e.g. `0 0 31 2 *`).
+    pub fn next_after(&self, after: NaiveDateTime) -> Option<NaiveDateTime> {
+        let start = after.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        let mut date = start.date();
+        for _ in 0..SEARCH_DAYS {
+            if self.matches_date(date) {
+                let from = if date == start.date() {
+                    (start.hour(), start.minute())
+                } else {
+                    (0, 0)
+                };
+                for hour in from.0..24 {
+                    if self.hours & (1 << hour) == 0 {
+                        continue;
+                    }
+                    let first_minute = if hour == from.0 { from.1 } else { 0 };
+                    if let Some(minute) = (first_minute..60).find(|m| self.minutes & (1 << m) != 0)
+                    {
+                        return Some(date.and_time(NaiveTime::from_hms_opt(hour, minute, 0)?));
+                    }
+                }
+            }
+            date = date.succ_opt()?;
+        }
+        None
+    }
+}
+
+/// Bit set of the values a field allows, bit `n` standing for value `n`.
+fn parse_field(field: &str, min: u32, max: u32) -> Result<u64, ApiError> {
+    let invalid = || ApiError::InvalidCronExpression;
+    let number = |s: &str| {
+        s.parse::<u32>()
+            .ok()
+            .filter(|n| (min..=max).contains(n))
+            .ok_or(ApiError::InvalidCronExpression)
+    };
+    let mut bits = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step = step
+                    .parse::<u32>()
+                    .ok()
+                    .filter(|s| *s > 0)
+                    .ok_or_else(invalid)?;
+                (range, Some(step))
+            }
+            None => (part, None),
+        };
+        let (from, to) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((a, b)) => (number(a)?, number(b)?),
+                // `5/15` (and `5/1`) means from 5 to the end of the range.
+                None if step.is_some() => (number(range)?, max),
+                None => {
+                    let n = number(range)?;
+                    (n, n)
+                }
+            },
+        };
+        if from > to {
+            return Err(invalid());
+        }
+        for value in (from..=to).step_by(step.unwrap_or(1) as usize) {
+            bits |= 1 << value;
+        }
+    }
+    Ok(bits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    fn next(expr: &str, after: &str) -> NaiveDateTime {
+        CronExpr::parse(expr)
+            .unwrap()
+            .next_after(at(after))
+            .unwrap()
+    }
+
+    fn values(bits: u64) -> Vec<u32> {
+        (0..64).filter(|n| bits & (1 << n) != 0).collect()
+    }
+
+    #[test]
+    fn star_covers_the_field() {
+        assert_eq!(
+            values(parse_field("*", 0, 59).unwrap()),
+            (0..=59).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            next("* * * * *", "2026-03-01 10:15"),
+            at("2026-03-01 10:16")
+        );
+    }
+
+    #[test]
+    fn ranges_and_lists() {
+        assert_eq!(values(parse_field("1-3", 0, 59).unwrap()), [1, 2, 3]);
+        assert_eq!(values(parse_field("1,5,7-8", 0, 59).unwrap()), [1, 5, 7, 8]);
+        assert_eq!(
+            next("0 9-17 * * 1-5", "2026-03-06 17:30"),
+            at("2026-03-09 09:00")
+        );
+        assert!(parse_field("5-3", 0, 59).is_err());
+        assert!(parse_field("60", 0, 59).is_err());
+        assert!(parse_field("1,,2", 0, 59).is_err());
+    }
+
+    #[test]
+    fn steps() {
+        assert_eq!(values(parse_field("*/15", 0, 59).unwrap()), [0, 15, 30, 45]);
+        assert_eq!(values(parse_field("10-20/5", 0, 59).unwrap()), [10, 15, 20]);
+        assert_eq!(values(parse_field("50/4", 0, 59).unwrap()), [50, 54, 58]);
+        assert_eq!(
+            values(parse_field("5/1", 0, 10).unwrap()),
+            [5, 6, 7, 8, 9, 10]
+        );
+        assert_eq!(values(parse_field("5", 0, 10).unwrap()), [5]);
+        assert!(parse_field("*/0", 0, 59).is_err());
+        assert_eq!(
+            next("*/20 * * * *", "2026-03-01 10:41"),
+            at("2026-03-01 11:00")
+        );
+    }
+
+    #[test]
+    fn sunday_is_zero_or_seven() {
+        // 2026-03-01 is a Sunday.
+        assert_eq!(
+            next("0 0 * * 7", "2026-02-25 12:00"),
+            at("2026-03-01 00:00")
+        );
+        assert_eq!(
+            next("0 0 * * 0", "2026-02-25 12:00"),
+            at("2026-03-01 00:00")
+        );
+        assert_eq!(next("@weekly", "2026-02-25 12:00"), at("2026-03-01 00:00"));
+    }
+
+    #[test]
+    fn day_of_month_or_day_of_week() {
+        // Both restricted: the 15th or any Monday, whichever comes first.
+        assert_eq!(
+            next("0 0 15 * 1", "2026-03-03 00:00"),
+            at("2026-03-09 00:00")
+        );
+        assert_eq!(
+            next("0 0 15 * 1", "2026-03-12 00:00"),
+            at("2026-03-15 00:00")
+        );
+        // Only one restricted: the other `*` does not widen the match.
+        assert_eq!(
+            next("0 0 15 * *", "2026-03-03 00:00"),
+            at("2026-03-15 00:00")
+        );
+        assert_eq!(
+            next("0 0 * * 1", "2026-03-03 00:00"),
+            at("2026-03-09 00:00")
+        );
+    }
+
+    #[test]
+    fn stepped_day_fields_still_restrict() {
+        // 2026-03-01 is a Sunday; `*/2` days are the odd ones.
+        assert_eq!(
+            next("0 0 */2 * *", "2026-03-01 00:00"),
+            at("2026-03-03 00:00")
+        );
+        assert_eq!(
+            next("0 0 */2 * *", "2026-03-03 00:00"),
+            at("2026-03-05 00:00")
+        );
+        // Sunday, Tuesday, Thursday and Saturday.
+        assert_eq!(
+            next("0 0 * * */2", "2026-03-01 00:00"),
+            at("2026-03-03 00:00")
+        );
+        assert_eq!(
+            next("0 0 * * */2", "2026-03-03 00:00"),
+            at("2026-03-05 00:00")
+        );
+        // A field starting with `*` ANDs the two: an odd day that is a Monday.
+        assert_eq!(
+            next("0 0 */2 * 1", "2026-03-01 00:00"),
+            at("2026-03-09 00:00")
+        );
+        // Without `*` they OR: Monday the 2nd already matches.
+        assert_eq!(
+            next("0 0 1-31/2 * 1", "2026-03-01 00:00"),
+            at("2026-03-02 00:00")
+        );
+    }
+
+    #[test]
+    fn rolls_over_months_and_years() {
+        assert_eq!(
+            next("0 0 1 * *", "2026-01-31 23:59"),
+            at("2026-02-01 00:00")
+        );
+        assert_eq!(
+            next("30 6 31 * *", "2026-04-01 00:00"),
+            at("2026-05-31 06:30")
+        );
+        assert_eq!(
+            next("0 0 1 1 *", "2026-06-01 00:00"),
+            at("2027-01-01 00:00")
+        );
+        assert_eq!(
+            next("0 0 29 2 *", "2026-03-01 00:00"),
+            at("2028-02-29 00:00")
+        );
+        assert!(CronExpr::parse("0 0 31 2 *")
+            .unwrap()
+            .next_after(at("2026-01-01 00:00"))
+            .is_none());
+    }
+
+    #[test]
+    fn next_is_strictly_after() {
+        assert_eq!(
+            next("15 10 * * *", "2026-03-01 10:15"),
+            at("2026-03-02 10:15")
+        );
+        let with_seconds = at("2026-03-01 10:14") + Duration::seconds(59);
+        assert_eq!(
+            CronExpr::parse("15 10 * * *")
+                .unwrap()
+                .next_after(with_seconds),
+            Some(at("2026-03-01 10:15"))
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_expressions() {
+        assert!(CronExpr::parse("* * * *").is_err());
+        assert!(CronExpr::parse("* * * * * *").is_err());
+        assert!(CronExpr::parse("@yearly").is_err());
+        assert!(CronExpr::parse("0 24 * * *").is_err());
+        assert!(CronExpr::parse("0 0 0 * *").is_err());
+    }
+}
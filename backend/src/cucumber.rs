@@ -0,0 +1,461 @@
+use std::collections::{HashMap, HashSet};
+
+use axum::body::Bytes;
+use base64::Engine;
+use serde::Deserialize;
+
+use crate::{
+    attachments::AttachmentLimits,
+    error::ApiError,
+    result_import::{
+        case_title, clip, embedded_attachment, worse, ImportedAttachment, ImportedCase,
+        ImportedStep, ParsedReport, ReportParser, ReportSource, MAX_COMMENT_CHARS,
+    },
+};
+
+const MAX_STEP_CHARS: usize = 1000;
+
+static SOURCE: ReportSource = ReportSource {
+    format: "cucumber",
+    name: "Cucumber",
+    max_bytes: 50 * 1024 * 1024,
+    failed: ApiError::ReportImportFailed,
+    rejected: ApiError::ReportRunCreateRejected,
+};
+
+/// One line of the message stream; only the messages the import needs are read.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Envelope {
+    gherkin_document: Option<GherkinDocument>,
+    pickle: Option<Pickle>,
+    test_case: Option<TestCase>,
+    test_case_started: Option<TestCaseStarted>,
+    test_step_finished: Option<TestStepFinished>,
+    test_case_finished: Option<TestCaseFinished>,
+    attachment: Option<Attachment>,
+}
+
+#[derive(Deserialize)]
+struct GherkinDocument {
+    feature: Option<Feature>,
+}
+
+#[derive(Deserialize)]
+struct Feature {
+    #[serde(default)]
+    children: Vec<FeatureChild>,
+}
+
+/// Child of a feature or a rule.
+#[derive(Deserialize)]
+struct FeatureChild {
+    background: Option<StepContainer>,
+    scenario: Option<StepContainer>,
+    rule: Option<Feature>,
+}
+
+#[derive(Deserialize)]
+struct StepContainer {
+    #[serde(default)]
+    steps: Vec<AstStep>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AstStep {
+    id: String,
+    #[serde(default)]
+    keyword: String,
+    keyword_type: Option<String>,
+}
+
+/// A scenario, or one row of a scenario outline, with its steps resolved.
+#[derive(Deserialize)]
+struct Pickle {
+    id: String,
+    #[serde(default)]
+    uri: String,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    steps: Vec<PickleStep>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PickleStep {
+    id: String,
+    #[serde(default)]
+    text: String,
+    #[serde(rename = "type")]
+    kind: Option<String>,
+    #[serde(default)]
+    ast_node_ids: Vec<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TestCase {
+    id: String,
+    pickle_id: String,
+    #[serde(default)]
+    test_steps: Vec<TestStep>,
+}
+
+/// A pickle step or a hook.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TestStep {
+    id: String,
+    pickle_step_id: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+struct Timestamp {
+    #[serde(default)]
+    seconds: i64,
+    #[serde(default)]
+    nanos: i64,
+}
+
+impl Timestamp {
+    fn millis(self) -> i64 {
+        self.seconds * 1000 + self.nanos / 1_000_000
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TestCaseStarted {
+    id: String,
+    test_case_id: String,
+    timestamp: Option<Timestamp>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TestStepFinished {
+    test_case_started_id: String,
+    test_step_id: String,
+    test_step_result: TestStepResult,
+}
+
+#[derive(Deserialize)]
+struct TestStepResult {
+    #[serde(default)]
+    status: String,
+    message: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TestCaseFinished {
+    test_case_started_id: String,
+    timestamp: Option<Timestamp>,
+    #[serde(default)]
+    will_be_retried: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Attachment {
+    test_case_started_id: Option<String>,
+    #[serde(default)]
+    body: String,
+    #[serde(default)]
+    content_encoding: String,
+    #[serde(default)]
+    media_type: String,
+    file_name: Option<String>,
+}
+
+fn map_status(status: &str) -> &'static str {
+    match status {
+        "PASSED" => "ok",
+        "FAILED" | "AMBIGUOUS" => "fail",
+        "SKIPPED" => "skipped",
+        _ => "na",
+    }
+}
+
+/// Source paths are normalised like `gherkin::import_gherkin` does, so results find the
+/// scenarios imported from the same files.
+fn source_path(uri: &str) -> String {
+    let path = uri.trim().replace('\\', "/");
+    path.trim_start_matches("./").to_string()
+}
+
+#[derive(Default)]
+struct Messages {
+    /// Keyword and keyword type of every Gherkin step, by AST node id.
+    ast_steps: HashMap<String, (String, Option<String>)>,
+    background_steps: HashSet<String>,
+    pickles: Vec<Pickle>,
+    test_cases: HashMap<String, TestCase>,
+    /// Attempts by test case; the last finished one that is not retried counts.
+    attempts: HashMap<String, Vec<TestCaseStarted>>,
+    results: HashMap<(String, String), TestStepResult>,
+    finished: HashMap<String, TestCaseFinished>,
+    attachments: HashMap<String, Vec<Attachment>>,
+}
+
+impl Messages {
+    fn read(body: &[u8]) -> Result<Self, ApiError> {
+        let mut messages = Messages::default();
+        for line in body.split(|b| *b == b'\n') {
+            if line.trim_ascii().is_empty() {
+                continue;
+            }
+            let envelope: Envelope =
+                serde_json::from_slice(line).map_err(|_| ApiError::InvalidCucumberMessages)?;
+            if let Some(feature) = envelope.gherkin_document.and_then(|d| d.feature) {
+                messages.add_children(&feature.children);
+            }
+            if let Some(pickle) = envelope.pickle {
+                messages.pickles.push(pickle);
+            }
+            if let Some(test_case) = envelope.test_case {
+                messages
+                    .test_cases
+                    .insert(test_case.pickle_id.clone(), test_case);
+            }
+            if let Some(started) = envelope.test_case_started {
+                messages
+                    .attempts
+                    .entry(started.test_case_id.clone())
+                    .or_default()
+                    .push(started);
+            }
+            if let Some(step) = envelope.test_step_finished {
+                messages.results.insert(
+                    (step.test_case_started_id, step.test_step_id),
+                    step.test_step_result,
+                );
+            }
+            if let Some(finished) = envelope.test_case_finished {
+                messages
+                    .finished
+                    .insert(finished.test_case_started_id.clone(), finished);
+            }
+            if let Some(attachment) = envelope.attachment {
+                if let Some(started_id) = attachment.test_case_started_id.clone() {
+                    messages
+                        .attachments
+                        .entry(started_id)
+                        .or_default()
+                        .push(attachment);
+                }
+            }
+        }
+        Ok(messages)
+    }
+
+    fn add_children(&mut self, children: &[FeatureChild]) {
+        for child in children {
+            if let Some(background) = &child.background {
+                for step in &background.steps {
+                    self.background_steps.insert(step.id.clone());
+                    self.add_step(step);
+                }
+            }
+            if let Some(scenario) = &child.scenario {
+                scenario.steps.iter().for_each(|step| self.add_step(step));
+            }
+            if let Some(rule) = &child.rule {
+                self.add_children(&rule.children);
+            }
+        }
+    }
+
+    fn add_step(&mut self, step: &AstStep) {
+        self.ast_steps.insert(
+            step.id.clone(),
+            (step.keyword.trim().to_string(), step.keyword_type.clone()),
+        );
+    }
+
+    /// Result of the pickle's last attempt, or `None` when it did not run.
+    fn case(
+        &self,
+        pickle: &Pickle,
+        limits: &AttachmentLimits,
+        skipped_attachments: &mut usize,
+    ) -> Option<ImportedCase> {
+        let test_case = self.test_cases.get(&pickle.id)?;
+        let attempt = self
+            .attempts
+            .get(&test_case.id)?
+            .iter()
+            .rev()
+            .find(|a| !self.finished.get(&a.id).is_some_and(|f| f.will_be_retried))?;
+        let pickle_steps: HashMap<&str, &PickleStep> =
+            pickle.steps.iter().map(|s| (s.id.as_str(), s)).collect();
+
+        let mut status = "ok";
+        let mut messages = Vec::new();
+        let mut steps: Vec<ImportedStep> = Vec::new();
+        for test_step in &test_case.test_steps {
+            let Some(result) = self
+                .results
+                .get(&(attempt.id.clone(), test_step.id.clone()))
+            else {
+                continue;
+            };
+            let step_status = map_status(&result.status);
+            status = worse(status, step_status);
+            let message = result.message.as_deref().unwrap_or_default().trim();
+            if !message.is_empty() {
+                messages.push(message.to_string());
+            }
+            // Hooks and Background steps count for the verdict only: the Gherkin import
+            // keeps Background as preconditions.
+            let Some(pickle_step) = test_step
+                .pickle_step_id
+                .as_deref()
+                .and_then(|id| pickle_steps.get(id))
+            else {
+                continue;
+            };
+            let ast = pickle_step.ast_node_ids.first();
+            if ast.is_some_and(|id| self.background_steps.contains(id)) {
+                continue;
+            }
+            let (keyword, keyword_type) = ast
+                .and_then(|id| self.ast_steps.get(id))
+                .map(|(keyword, kind)| (keyword.as_str(), kind.as_deref()))
+                .unwrap_or(("", None));
+            let outcome = pickle_step.kind.as_deref().or(keyword_type) == Some("Outcome");
+            let comment = clip(message, MAX_STEP_CHARS);
+            // A Then step is the expected result of the step before it, as in the Gherkin
+            // import.
+            match steps.last_mut() {
+                Some(previous) if outcome => {
+                    previous.expected = [previous.expected.as_str(), &pickle_step.text]
+                        .iter()
+                        .filter(|t| !t.is_empty())
+                        .copied()
+                        .collect::<Vec<_>>()
+                        .join("\n");
+                    previous.status = worse(previous.status, step_status);
+                    if !comment.is_empty() {
+                        previous.comment = clip(
+                            &format!("{}\n\n{comment}", previous.comment),
+                            MAX_STEP_CHARS,
+                        );
+                    }
+                }
+                _ => steps.push(ImportedStep {
+                    action: clip(
+                        [keyword, &pickle_step.text].join(" ").trim(),
+                        MAX_STEP_CHARS,
+                    ),
+                    expected: String::new(),
+                    status: step_status,
+                    comment,
+                }),
+            }
+        }
+
+        let mut attachments: Vec<ImportedAttachment> = Vec::new();
+        for (idx, attachment) in self
+            .attachments
+            .get(&attempt.id)
+            .into_iter()
+            .flatten()
+            .enumerate()
+        {
+            let data = match attachment.content_encoding.as_str() {
+                "BASE64" => base64::engine::general_purpose::STANDARD
+                    .decode(attachment.body.trim())
+                    .ok(),
+                _ => Some(attachment.body.clone().into_bytes()),
+            };
+            let name = attachment
+                .file_name
+                .clone()
+                .unwrap_or_else(|| format!("attachment-{}", idx + 1));
+            match data
+                .and_then(|data| embedded_attachment(limits, &name, &attachment.media_type, data))
+            {
+                Some(attachment) => attachments.push(attachment),
+                None => *skipped_attachments += 1,
+            }
+        }
+
+        let elapsed_seconds = attempt
+            .timestamp
+            .zip(self.finished.get(&attempt.id).and_then(|f| f.timestamp))
+            .map(|(start, stop)| stop.millis() - start.millis())
+            .filter(|ms| *ms >= 0)
+            .map(|ms| ((ms + 500) / 1000).min(i32::MAX as i64) as i32);
+        let path = source_path(&pickle.uri);
+        let name = pickle.name.trim();
+        Some(ImportedCase {
+            key: format!("{path} › {name}"),
+            title: case_title(name, &path),
+            source_path: Some(path),
+            status,
+            comment: clip(&messages.join("\n\n"), MAX_COMMENT_CHARS),
+            elapsed_seconds,
+            steps,
+            attachments,
+        })
+    }
+}
+
+/// Cucumber message stream (NDJSON, `--format message`). Scenarios are matched to the
+/// testcases of their feature file like the Gherkin import links them, by path and name;
+/// rows of an outline make one result with the worst status. Background steps and hooks
+/// decide the verdict but are not step results; a Then step adds to the expected result of
+/// the step before it.
+pub struct CucumberParser;
+
+impl ReportParser for CucumberParser {
+    fn source(&self) -> &'static ReportSource {
+        &SOURCE
+    }
+
+    fn parse(&self, body: Bytes, limits: &AttachmentLimits) -> Result<ParsedReport, ApiError> {
+        let messages = Messages::read(&body)?;
+        let mut cases: Vec<ImportedCase> = Vec::new();
+        let mut by_key: HashMap<String, usize> = HashMap::new();
+        let mut skipped_attachments = 0;
+        for pickle in &messages.pickles {
+            if pickle.name.trim().is_empty() {
+                continue;
+            }
+            let Some(case) = messages.case(pickle, limits, &mut skipped_attachments) else {
+                continue;
+            };
+            let Some(&idx) = by_key.get(&case.key) else {
+                by_key.insert(case.key.clone(), cases.len());
+                cases.push(case);
+                continue;
+            };
+            let merged = &mut cases[idx];
+            if worse(merged.status, case.status) != merged.status {
+                merged.steps = case.steps;
+            }
+            merged.status = worse(merged.status, case.status);
+            if !case.comment.is_empty() {
+                merged.comment = clip(
+                    &format!("{}\n\n{}", merged.comment, case.comment),
+                    MAX_COMMENT_CHARS,
+                );
+            }
+            merged.elapsed_seconds = match (merged.elapsed_seconds, case.elapsed_seconds) {
+                (Some(a), Some(b)) => Some(a.saturating_add(b)),
+                (a, b) => a.or(b),
+            };
+            merged.attachments.extend(case.attachments);
+        }
+        if cases.is_empty() {
+            return Err(ApiError::CucumberMessagesEmpty);
+        }
+        Ok(ParsedReport {
+            cases,
+            skipped_attachments,
+        })
+    }
+}
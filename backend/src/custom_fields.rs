@@ -0,0 +1,765 @@
+use std::collections::HashSet;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use sqlx::{PgPool, Row};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::{
+    audit,
+    authz::{self, AuthUser, ProjectRole},
+    ensure_db_user_exists,
+    error::ApiError,
+    parse_uuid,
+    permissions::Capability,
+    run_repo::{LockedRun, RunLock},
+    testcases, AppState,
+};
+
+const MAX_OPTIONS: usize = 100;
+const MAX_TEXT_CHARS: usize = 1000;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Entity {
+    Testcase,
+    Run,
+}
+
+impl Entity {
+    fn parse(input: &str) -> Result<Self, ApiError> {
+        match input.trim() {
+            "testcase" => Ok(Self::Testcase),
+            "run" => Ok(Self::Run),
+            _ => Err(ApiError::InvalidCustomFieldEntity),
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Testcase => "testcase",
+            Self::Run => "run",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum FieldType {
+    Text,
+    Number,
+    Boolean,
+    Date,
+    Select,
+    Multiselect,
+}
+
+impl FieldType {
+    fn parse(input: &str) -> Result<Self, ApiError> {
+        match input.trim() {
+            "text" => Ok(Self::Text),
+            "number" => Ok(Self::Number),
+            "boolean" => Ok(Self::Boolean),
+            "date" => Ok(Self::Date),
+            "select" => Ok(Self::Select),
+            "multiselect" => Ok(Self::Multiselect),
+            _ => Err(ApiError::InvalidCustomFieldType),
+        }
+    }
+
+    fn has_options(self) -> bool {
+        matches!(self, Self::Select | Self::Multiselect)
+    }
+}
+
+/// A field definition as needed to check values.
+pub struct FieldDefinition {
+    key: String,
+    field_type: FieldType,
+    options: Vec<String>,
+    is_required: bool,
+}
+
+impl FieldDefinition {
+    /// Checks one value against the field type; dates are normalized to `YYYY-MM-DD`.
+    fn check(&self, value: Value) -> Result<Value, ApiError> {
+        let valid = match (self.field_type, &value) {
+            (FieldType::Text, Value::String(s)) => {
+                !s.trim().is_empty() && s.chars().count() <= MAX_TEXT_CHARS
+            }
+            (FieldType::Number, Value::Number(_)) => true,
+            (FieldType::Boolean, Value::Bool(_)) => true,
+            (FieldType::Date, Value::String(s)) => {
+                return NaiveDate::parse_from_str(s.trim(), "%Y-%m-%d")
+                    .map(|d| json!(d.format("%Y-%m-%d").to_string()))
+                    .map_err(|_| ApiError::InvalidCustomFieldValue);
+            }
+            (FieldType::Select, Value::String(s)) => self.options.contains(s),
+            (FieldType::Multiselect, Value::Array(items)) => {
+                let mut seen = HashSet::new();
+                !items.is_empty()
+                    && items.iter().all(|item| {
+                        item.as_str()
+                            .is_some_and(|s| self.options.iter().any(|o| o == s) && seen.insert(s))
+                    })
+            }
+            _ => false,
+        };
+        if !valid {
+            return Err(ApiError::InvalidCustomFieldValue);
+        }
+        Ok(value)
+    }
+}
+
+/// Field definitions of the project for one entity.
+pub async fn definitions(
+    db: &PgPool,
+    project_id: Uuid,
+    entity: Entity,
+) -> Result<Vec<FieldDefinition>, ApiError> {
+    let rows = sqlx::query(
+        r#"
+        SELECT key, field_type, options, is_required
+        FROM custom_fields
+        WHERE project_id = $1 AND entity = $2
+        "#,
+    )
+    .bind(project_id)
+    .bind(entity.as_str())
+    .fetch_all(db)
+    .await
+    .map_err(|_| ApiError::CustomFieldsReadFailed)?;
+    rows.into_iter()
+        .map(|r| {
+            Ok(FieldDefinition {
+                key: r.get("key"),
+                field_type: FieldType::parse(r.get::<&str, _>("field_type"))?,
+                options: serde_json::from_value(r.get("options")).unwrap_or_default(),
+                is_required: r.get("is_required"),
+            })
+        })
+        .collect()
+}
+
+/// Applies `changes` to the `current` values: a `null` value removes the field, other values
+/// must match the field type. Every required field must have a value afterwards.
+pub fn apply_values(
+    definitions: &[FieldDefinition],
+    current: Map<String, Value>,
+    changes: Map<String, Value>,
+) -> Result<Map<String, Value>, ApiError> {
+    let mut values = current;
+    for (key, value) in changes {
+        let definition = definitions
+            .iter()
+            .find(|d| d.key == key)
+            .ok_or(ApiError::UnknownCustomField)?;
+        if value.is_null() {
+            values.remove(&key);
+        } else {
+            values.insert(key, definition.check(value)?);
+        }
+    }
+    if definitions
+        .iter()
+        .any(|d| d.is_required && !values.contains_key(&d.key))
+    {
+        return Err(ApiError::CustomFieldRequired);
+    }
+    Ok(values)
+}
+
+/// Parses the `customFields` list filter, a JSON object of field key to value, into a
+/// containment pattern for `custom_fields @> $pattern`. A single string matches
+/// multiselect fields that include it.
+pub fn parse_filter(definitions: &[FieldDefinition], raw: &str) -> Result<Value, ApiError> {
+    let filter: Map<String, Value> =
+        serde_json::from_str(raw).map_err(|_| ApiError::InvalidCustomFieldFilter)?;
+    let mut pattern = Map::new();
+    for (key, value) in filter {
+        let definition = definitions
+            .iter()
+            .find(|d| d.key == key)
+            .ok_or(ApiError::UnknownCustomField)?;
+        let value = match (definition.field_type, value) {
+            (FieldType::Multiselect, Value::String(s)) => json!([s]),
+            (_, value) => value,
+        };
+        let value = definition
+            .check(value)
+            .map_err(|_| ApiError::InvalidCustomFieldFilter)?;
+        pattern.insert(key, value);
+    }
+    Ok(Value::Object(pattern))
+}
+
+fn validate_key(key: &str) -> Result<(), ApiError> {
+    let mut chars = key.chars();
+    let valid = (2..=64).contains(&key.len())
+        && chars.next().is_some_and(|c| c.is_ascii_lowercase())
+        && chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+    if !valid {
+        return Err(ApiError::InvalidCustomFieldKey);
+    }
+    Ok(())
+}
+
+fn normalize_name(name: &str) -> Result<String, ApiError> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().count() > 200 {
+        return Err(ApiError::InvalidCustomFieldName);
+    }
+    Ok(name.to_string())
+}
+
+fn normalize_options(field_type: FieldType, options: Vec<String>) -> Result<Vec<String>, ApiError> {
+    let options: Vec<String> = options.iter().map(|o| o.trim().to_string()).collect();
+    let mut seen = HashSet::new();
+    let valid = if field_type.has_options() {
+        !options.is_empty()
+            && options.len() <= MAX_OPTIONS
+            && options
+                .iter()
+                .all(|o| (1..=200).contains(&o.chars().count()) && seen.insert(o.clone()))
+    } else {
+        options.is_empty()
+    };
+    if !valid {
+        return Err(ApiError::InvalidCustomFieldOptions);
+    }
+    Ok(options)
+}
+
+#[derive(Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct ListCustomFieldsQuery {
+    /// `testcase` or `run`; both when omitted.
+    entity: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateCustomFieldRequest {
+    /// `testcase` or `run`.
+    entity: String,
+    /// Key in the values object: `[a-z][a-z0-9_]`, 2–64 characters.
+    key: String,
+    name: String,
+    /// `text|number|boolean|date|select|multiselect`.
+    field_type: String,
+    /// Allowed values of `select`/`multiselect`.
+    options: Option<Vec<String>>,
+    is_required: Option<bool>,
+    position: Option<i32>,
+}
+
+/// `entity`, `key` and `fieldType` cannot change once values may exist.
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateCustomFieldRequest {
+    name: Option<String>,
+    options: Option<Vec<String>>,
+    is_required: Option<bool>,
+    position: Option<i32>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CustomFieldView {
+    id: String,
+    entity: String,
+    key: String,
+    name: String,
+    field_type: String,
+    options: Vec<String>,
+    is_required: bool,
+    position: i32,
+    created_at: String,
+    updated_at: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ListCustomFieldsResponse {
+    fields: Vec<CustomFieldView>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct DeleteCustomFieldResponse {
+    ok: bool,
+}
+
+/// New values of a testcase or run: keys missing from the object stay unchanged, `null`
+/// removes a value.
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateCustomFieldValuesRequest {
+    #[schema(value_type = Object)]
+    values: Map<String, Value>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CustomFieldValuesResponse {
+    #[schema(value_type = Object)]
+    custom_fields: Map<String, Value>,
+}
+
+const FIELD_SELECT: &str = r#"
+    SELECT id::text AS id, entity, key, name, field_type, options, is_required, position,
+           created_at::text AS created_at, updated_at::text AS updated_at
+    FROM custom_fields
+"#;
+
+fn map_field_row(r: &sqlx::postgres::PgRow) -> CustomFieldView {
+    CustomFieldView {
+        id: r.get("id"),
+        entity: r.get("entity"),
+        key: r.get("key"),
+        name: r.get("name"),
+        field_type: r.get("field_type"),
+        options: serde_json::from_value(r.get("options")).unwrap_or_default(),
+        is_required: r.get("is_required"),
+        position: r.get("position"),
+        created_at: r.get("created_at"),
+        updated_at: r.get("updated_at"),
+    }
+}
+
+async fn fetch_field(
+    state: &AppState,
+    project_id: Uuid,
+    field_id: Uuid,
+) -> Result<Option<CustomFieldView>, ApiError> {
+    let sql = format!("{FIELD_SELECT} WHERE project_id = $1 AND id = $2");
+    let row = sqlx::query(&sql)
+        .bind(project_id)
+        .bind(field_id)
+        .fetch_optional(&state.db)
+        .await
+        .map_err(|_| ApiError::CustomFieldsReadFailed)?;
+    Ok(row.as_ref().map(map_field_row))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v2/projects/{project_id}/custom-fields",
+    tag = "custom-fields",
+    params(("project_id" = String, Path), ListCustomFieldsQuery),
+    responses((status = 200, body = ListCustomFieldsResponse))
+)]
+pub async fn list_custom_fields(
+    State(state): State<AppState>,
+    access: ProjectRole,
+    Query(query): Query<ListCustomFieldsQuery>,
+) -> Result<Json<ListCustomFieldsResponse>, ApiError> {
+    let entity = query.entity.as_deref().map(Entity::parse).transpose()?;
+    let sql = format!(
+        "{FIELD_SELECT} WHERE project_id = $1 AND ($2::text IS NULL OR entity = $2)
+         ORDER BY entity ASC, position ASC, name ASC"
+    );
+    let rows = sqlx::query(&sql)
+        .bind(access.project_id)
+        .bind(entity.map(Entity::as_str))
+        .fetch_all(&state.db)
+        .await
+        .map_err(|_| ApiError::CustomFieldsReadFailed)?;
+    Ok(Json(ListCustomFieldsResponse {
+        fields: rows.iter().map(map_field_row).collect(),
+    }))
+}
+
+/// Adds a field definition. A new required field does not touch existing testcases and
+/// runs; it is enforced the next time their values are written.
+#[utoipa::path(
+    post,
+    path = "/api/v2/projects/{project_id}/custom-fields",
+    tag = "custom-fields",
+    params(("project_id" = String, Path)),
+    request_body = CreateCustomFieldRequest,
+    responses((status = 201, body = CustomFieldView))
+)]
+pub async fn create_custom_field(
+    State(state): State<AppState>,
+    access: ProjectRole,
+    Json(payload): Json<CreateCustomFieldRequest>,
+) -> Result<(StatusCode, Json<CustomFieldView>), ApiError> {
+    access.require(Capability::ProjectManage)?;
+    let ProjectRole {
+        user_id: actor_id,
+        project_id,
+        ..
+    } = access;
+    let entity = Entity::parse(&payload.entity)?;
+    let key = payload.key.trim().to_string();
+    validate_key(&key)?;
+    let name = normalize_name(&payload.name)?;
+    let field_type = FieldType::parse(&payload.field_type)?;
+    let options = normalize_options(field_type, payload.options.unwrap_or_default())?;
+    let is_required = payload.is_required.unwrap_or(false);
+    ensure_db_user_exists(&state, &actor_id).await?;
+    let actor_uuid = parse_uuid(&actor_id, ApiError::InvalidUserId)?;
+
+    let create_failed = |_| ApiError::CustomFieldCreateFailed;
+    let mut tx = state.db.begin().await.map_err(create_failed)?;
+    let field_id: Option<Uuid> = sqlx::query_scalar(
+        r#"
+        INSERT INTO custom_fields (
+          project_id, entity, key, name, field_type, options, is_required, position
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        ON CONFLICT (project_id, entity, key) DO NOTHING
+        RETURNING id
+        "#,
+    )
+    .bind(project_id)
+    .bind(entity.as_str())
+    .bind(&key)
+    .bind(&name)
+    .bind(payload.field_type.trim())
+    .bind(json!(options))
+    .bind(is_required)
+    .bind(payload.position.unwrap_or(0))
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(create_failed)?;
+    let field_id = field_id.ok_or(ApiError::CustomFieldExists)?;
+    audit::record(
+        &mut *tx,
+        audit::AuditEntry {
+            actor_user_id: Some(actor_uuid),
+            action: "create",
+            entity_type: "custom_field",
+            entity_id: Some(field_id),
+            project_id: Some(project_id),
+            run_id: None,
+            before: None,
+            after: Some(json!({
+                "entity": entity.as_str(),
+                "key": &key,
+                "name": &name,
+                "fieldType": payload.field_type.trim(),
+                "options": &options,
+                "isRequired": is_required,
+            })),
+        },
+    )
+    .await
+    .map_err(create_failed)?;
+    tx.commit().await.map_err(create_failed)?;
+
+    let field = fetch_field(&state, project_id, field_id)
+        .await?
+        .ok_or(ApiError::CustomFieldNotFound)?;
+    Ok((StatusCode::CREATED, Json(field)))
+}
+
+/// Partial update. Removing an option keeps stored values that use it; they are rejected
+/// the next time the values are written.
+#[utoipa::path(
+    patch,
+    path = "/api/v2/projects/{project_id}/custom-fields/{field_id}",
+    tag = "custom-fields",
+    params(("project_id" = String, Path), ("field_id" = String, Path)),
+    request_body = UpdateCustomFieldRequest,
+    responses((status = 200, body = CustomFieldView))
+)]
+pub async fn update_custom_field(
+    State(state): State<AppState>,
+    Path((_project_id, field_id)): Path<(String, String)>,
+    access: ProjectRole,
+    Json(payload): Json<UpdateCustomFieldRequest>,
+) -> Result<Json<CustomFieldView>, ApiError> {
+    access.require(Capability::ProjectManage)?;
+    let ProjectRole {
+        user_id: actor_id,
+        project_id,
+        ..
+    } = access;
+    let field_uuid = parse_uuid(&field_id, ApiError::InvalidCustomFieldId)?;
+    let name = payload.name.as_deref().map(normalize_name).transpose()?;
+    ensure_db_user_exists(&state, &actor_id).await?;
+    let actor_uuid = parse_uuid(&actor_id, ApiError::InvalidUserId)?;
+    let before = fetch_field(&state, project_id, field_uuid)
+        .await?
+        .ok_or(ApiError::CustomFieldNotFound)?;
+    let options = payload
+        .options
+        .map(|options| normalize_options(FieldType::parse(&before.field_type)?, options))
+        .transpose()?;
+
+    let update_failed = |_| ApiError::CustomFieldUpdateFailed;
+    let mut tx = state.db.begin().await.map_err(update_failed)?;
+    sqlx::query(
+        r#"
+        UPDATE custom_fields
+        SET name = COALESCE($3, name),
+            options = COALESCE($4, options),
+            is_required = COALESCE($5, is_required),
+            position = COALESCE($6, position),
+            updated_at = NOW()
+        WHERE project_id = $1 AND id = $2
+        "#,
+    )
+    .bind(project_id)
+    .bind(field_uuid)
+    .bind(&name)
+    .bind(options.as_ref().map(|o| json!(o)))
+    .bind(payload.is_required)
+    .bind(payload.position)
+    .execute(&mut *tx)
+    .await
+    .map_err(update_failed)?;
+    audit::record(
+        &mut *tx,
+        audit::AuditEntry {
+            actor_user_id: Some(actor_uuid),
+            action: "update",
+            entity_type: "custom_field",
+            entity_id: Some(field_uuid),
+            project_id: Some(project_id),
+            run_id: None,
+            before: Some(json!({
+                "name": &before.name,
+                "options": &before.options,
+                "isRequired": before.is_required,
+                "position": before.position,
+            })),
+            after: Some(json!({
+                "name": name.as_ref().unwrap_or(&before.name),
+                "options": options.as_ref().unwrap_or(&before.options),
+                "isRequired": payload.is_required.unwrap_or(before.is_required),
+                "position": payload.position.unwrap_or(before.position),
+            })),
+        },
+    )
+    .await
+    .map_err(update_failed)?;
+    tx.commit().await.map_err(update_failed)?;
+
+    let field = fetch_field(&state, project_id, field_uuid)
+        .await?
+        .ok_or(ApiError::CustomFieldNotFound)?;
+    Ok(Json(field))
+}
+
+/// Deletes the definition together with its values on the project's testcases or runs.
+#[utoipa::path(
+    delete,
+    path = "/api/v2/projects/{project_id}/custom-fields/{field_id}",
+    tag = "custom-fields",
+    params(("project_id" = String, Path), ("field_id" = String, Path)),
+    responses((status = 200, body = DeleteCustomFieldResponse))
+)]
+pub async fn delete_custom_field(
+    State(state): State<AppState>,
+    Path((_project_id, field_id)): Path<(String, String)>,
+    access: ProjectRole,
+) -> Result<Json<DeleteCustomFieldResponse>, ApiError> {
+    access.require(Capability::ProjectManage)?;
+    let ProjectRole {
+        user_id: actor_id,
+        project_id,
+        ..
+    } = access;
+    let field_uuid = parse_uuid(&field_id, ApiError::InvalidCustomFieldId)?;
+    ensure_db_user_exists(&state, &actor_id).await?;
+    let actor_uuid = parse_uuid(&actor_id, ApiError::InvalidUserId)?;
+    let field = fetch_field(&state, project_id, field_uuid)
+        .await?
+        .ok_or(ApiError::CustomFieldNotFound)?;
+
+    let delete_failed = |_| ApiError::CustomFieldDeleteFailed;
+    let mut tx = state.db.begin().await.map_err(delete_failed)?;
+    sqlx::query("DELETE FROM custom_fields WHERE project_id = $1 AND id = $2")
+        .bind(project_id)
+        .bind(field_uuid)
+        .execute(&mut *tx)
+        .await
+        .map_err(delete_failed)?;
+    let strip_values = match Entity::parse(&field.entity)? {
+        Entity::Testcase => {
+            r#"
+            UPDATE testcases tc
+            SET custom_fields = tc.custom_fields - $2
+            FROM test_suites s
+            WHERE s.id = tc.suite_id AND s.project_id = $1 AND tc.custom_fields ? $2
+            "#
+        }
+        Entity::Run => {
+            r#"
+            UPDATE runs
+            SET custom_fields = custom_fields - $2
+            WHERE project_id = $1 AND custom_fields ? $2
+            "#
+        }
+    };
+    sqlx::query(strip_values)
+        .bind(project_id)
+        .bind(&field.key)
+        .execute(&mut *tx)
+        .await
+        .map_err(delete_failed)?;
+    audit::record(
+        &mut *tx,
+        audit::AuditEntry {
+            actor_user_id: Some(actor_uuid),
+            action: "delete",
+            entity_type: "custom_field",
+            entity_id: Some(field_uuid),
+            project_id: Some(project_id),
+            run_id: None,
+            before: Some(json!({
+                "entity": &field.entity,
+                "key": &field.key,
+                "name": &field.name,
+                "fieldType": &field.field_type,
+            })),
+            after: None,
+        },
+    )
+    .await
+    .map_err(delete_failed)?;
+    tx.commit().await.map_err(delete_failed)?;
+
+    Ok(Json(DeleteCustomFieldResponse { ok: true }))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/api/v2/testcases/{testcase_id}/custom-fields",
+    tag = "custom-fields",
+    params(("testcase_id" = String, Path)),
+    request_body = UpdateCustomFieldValuesRequest,
+    responses((status = 200, body = CustomFieldValuesResponse))
+)]
+pub async fn update_testcase_values(
+    State(state): State<AppState>,
+    Path(testcase_id): Path<String>,
+    AuthUser(actor_id): AuthUser,
+    Json(payload): Json<UpdateCustomFieldValuesRequest>,
+) -> Result<Json<CustomFieldValuesResponse>, ApiError> {
+    let testcase_uuid = parse_uuid(&testcase_id, ApiError::InvalidTestcaseId)?;
+    let project_uuid =
+        testcases::authorize_testcase(&state, testcase_uuid, &actor_id, Capability::LibraryEdit)
+            .await?;
+    ensure_db_user_exists(&state, &actor_id).await?;
+    let actor_uuid = parse_uuid(&actor_id, ApiError::InvalidUserId)?;
+    let definitions = definitions(&state.db, project_uuid, Entity::Testcase).await?;
+
+    let update_failed = |_| ApiError::CustomFieldValuesUpdateFailed;
+    let mut tx = state.db.begin().await.map_err(update_failed)?;
+    let current: Value =
+        sqlx::query_scalar("SELECT custom_fields FROM testcases WHERE id = $1 FOR UPDATE")
+            .bind(testcase_uuid)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(update_failed)?;
+    let before = current.as_object().cloned().unwrap_or_default();
+    let values = apply_values(&definitions, before.clone(), payload.values)?;
+    sqlx::query(
+        r#"
+        UPDATE testcases
+        SET custom_fields = $2, updated_at = NOW(), updated_by_user_id = $3
+        WHERE id = $1
+        "#,
+    )
+    .bind(testcase_uuid)
+    .bind(Value::Object(values.clone()))
+    .bind(actor_uuid)
+    .execute(&mut *tx)
+    .await
+    .map_err(update_failed)?;
+    audit::record(
+        &mut *tx,
+        audit::AuditEntry {
+            actor_user_id: Some(actor_uuid),
+            action: "update",
+            entity_type: "testcase",
+            entity_id: Some(testcase_uuid),
+            project_id: Some(project_uuid),
+            run_id: None,
+            before: Some(json!({ "customFields": before })),
+            after: Some(json!({ "customFields": &values })),
+        },
+    )
+    .await
+    .map_err(update_failed)?;
+    tx.commit().await.map_err(update_failed)?;
+
+    Ok(Json(CustomFieldValuesResponse {
+        custom_fields: values,
+    }))
+}
+
+/// Values of a run; not allowed once the run is locked.
+#[utoipa::path(
+    patch,
+    path = "/api/v2/runs/{run_id}/custom-fields",
+    tag = "custom-fields",
+    params(("run_id" = String, Path)),
+    request_body = UpdateCustomFieldValuesRequest,
+    responses((status = 200, body = CustomFieldValuesResponse))
+)]
+pub async fn update_run_values(
+    State(state): State<AppState>,
+    Path(run_id): Path<String>,
+    AuthUser(actor_id): AuthUser,
+    Json(payload): Json<UpdateCustomFieldValuesRequest>,
+) -> Result<Json<CustomFieldValuesResponse>, ApiError> {
+    let run_uuid = parse_uuid(&run_id, ApiError::InvalidRunId)?;
+    authz::require_run_capability(&state, run_uuid, &actor_id, Capability::RunCreate).await?;
+    ensure_db_user_exists(&state, &actor_id).await?;
+    let actor_uuid = parse_uuid(&actor_id, ApiError::InvalidUserId)?;
+
+    let mut run = LockedRun::begin(
+        &state.db,
+        run_uuid,
+        RunLock::Exclusive,
+        ApiError::CustomFieldValuesUpdateFailed,
+    )
+    .await?;
+    run.ensure_unlocked(ApiError::RunLockedCustomFields)?;
+    let project_id = run.project_id;
+    let definitions = definitions(&state.db, project_id, Entity::Run).await?;
+    let update_failed = |_| ApiError::CustomFieldValuesUpdateFailed;
+    let current: Value = sqlx::query_scalar("SELECT custom_fields FROM runs WHERE id = $1")
+        .bind(run_uuid)
+        .fetch_one(run.conn())
+        .await
+        .map_err(update_failed)?;
+    let before = current.as_object().cloned().unwrap_or_default();
+    let values = apply_values(&definitions, before.clone(), payload.values)?;
+    sqlx::query("UPDATE runs SET custom_fields = $2, updated_at = NOW() WHERE id = $1")
+        .bind(run_uuid)
+        .bind(Value::Object(values.clone()))
+        .execute(run.conn())
+        .await
+        .map_err(update_failed)?;
+    audit::record(
+        run.conn(),
+        audit::AuditEntry {
+            actor_user_id: Some(actor_uuid),
+            action: "update",
+            entity_type: "run",
+            entity_id: Some(run_uuid),
+            project_id: Some(project_id),
+            run_id: Some(run_uuid),
+            before: Some(json!({ "customFields": before })),
+            after: Some(json!({ "customFields": &values })),
+        },
+    )
+    .await
+    .map_err(update_failed)?;
+    run.commit(ApiError::CustomFieldValuesUpdateFailed).await?;
+
+    Ok(Json(CustomFieldValuesResponse {
+        custom_fields: values,
+    }))
+}
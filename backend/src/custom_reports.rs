@@ -0,0 +1,1208 @@
+use std::time::Duration;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Days, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sqlx::{Postgres, QueryBuilder, Row};
+use tracing::warn;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::{
+    audit,
+    authz::{self, ProjectRole},
+    cron::CronExpr,
+    db_errors, ensure_db_user_exists,
+    error::ApiError,
+    export,
+    jobs::{self, JobContext},
+    membership_role, notifications, now_iso, parse_uuid,
+    permissions::Capability,
+    read_projects, read_users, schedules, AppState,
+};
+
+/// How often due report deliveries are looked for.
+const POLL_INTERVAL: Duration = Duration::from_secs(60);
+/// Rows returned by one execution; `truncated` tells when there were more.
+const MAX_ROWS: i64 = 10_000;
+const MAX_FILTERS: usize = 20;
+const MAX_COLUMNS: usize = 30;
+const MAX_GROUP_BY: usize = 3;
+const MAX_IN_VALUES: usize = 100;
+const MAX_RECIPIENTS: usize = 50;
+
+#[derive(Clone, Copy, PartialEq)]
+enum FieldType {
+    Text,
+    Bool,
+    Time,
+}
+
+/// A column a report may show, filter or group by. `sql` is a fixed expression over the
+/// entity's joins; user input never becomes SQL text, only bound values.
+struct Field {
+    key: &'static str,
+    sql: &'static str,
+    field_type: FieldType,
+}
+
+/// A column of grouped reports.
+struct Aggregate {
+    key: &'static str,
+    sql: &'static str,
+}
+
+const fn field(key: &'static str, sql: &'static str, field_type: FieldType) -> Field {
+    Field {
+        key,
+        sql,
+        field_type,
+    }
+}
+
+const RESULT_FIELDS: &[Field] = &[
+    field("runId", "r.id::text", FieldType::Text),
+    field("runTitle", "r.title", FieldType::Text),
+    field("runStatus", "r.status::text", FieldType::Text),
+    field("runCreatedAt", "r.created_at", FieldType::Time),
+    field("runFinishedAt", "r.finished_at", FieldType::Time),
+    field("testcaseKey", "tc.key", FieldType::Text),
+    field("testcaseTitle", "tc.title", FieldType::Text),
+    field("isRequired", "ri.is_required", FieldType::Bool),
+    field("status", "COALESCE(rr.status::text, 'na')", FieldType::Text),
+    field("failReasonCode", "rr.fail_reason_code", FieldType::Text),
+    field("comment", "COALESCE(rr.comment, '')", FieldType::Text),
+    field("executor", "u.display_name", FieldType::Text),
+    field("updatedAt", "rr.updated_at", FieldType::Time),
+    field("assetName", "a.name", FieldType::Text),
+    field("assetVersion", "r.asset_version", FieldType::Text),
+];
+
+const RUN_FIELDS: &[Field] = &[
+    field("id", "r.id::text", FieldType::Text),
+    field("title", "r.title", FieldType::Text),
+    field("status", "r.status::text", FieldType::Text),
+    field("createdAt", "r.created_at", FieldType::Time),
+    field("startedAt", "r.started_at", FieldType::Time),
+    field("finishedAt", "r.finished_at", FieldType::Time),
+    field("executor", "u.display_name", FieldType::Text),
+    field("assetName", "a.name", FieldType::Text),
+    field("assetVersion", "r.asset_version", FieldType::Text),
+    field("commitSha", "r.commit_sha", FieldType::Text),
+];
+
+const TESTCASE_FIELDS: &[Field] = &[
+    field("id", "tc.id::text", FieldType::Text),
+    field("key", "tc.key", FieldType::Text),
+    field("title", "tc.title", FieldType::Text),
+    field("suite", "s.name", FieldType::Text),
+    field("isRequired", "tc.is_required", FieldType::Bool),
+    field("isArchived", "tc.is_archived", FieldType::Bool),
+    field("createdAt", "tc.created_at", FieldType::Time),
+    field("updatedAt", "tc.updated_at", FieldType::Time),
+];
+
+const COUNT: Aggregate = Aggregate {
+    key: "count",
+    sql: "COUNT(*)",
+};
+
+const RESULT_AGGREGATES: &[Aggregate] = &[
+    COUNT,
+    Aggregate {
+        key: "ok",
+        sql: "COUNT(*) FILTER (WHERE rr.status = 'ok')",
+    },
+    Aggregate {
+        key: "fail",
+        sql: "COUNT(*) FILTER (WHERE rr.status = 'fail')",
+    },
+    Aggregate {
+        key: "passRate",
+        sql: "ROUND(100.0 * COUNT(*) FILTER (WHERE rr.status = 'ok') \
+              / NULLIF(COUNT(*) FILTER (WHERE rr.status IN ('ok', 'fail')), 0), 1)",
+    },
+];
+
+const RUN_AGGREGATES: &[Aggregate] = &[
+    COUNT,
+    Aggregate {
+        key: "averageDurationSeconds",
+        sql: "ROUND(AVG(EXTRACT(EPOCH FROM r.finished_at - r.started_at)))",
+    },
+];
+
+const TESTCASE_AGGREGATES: &[Aggregate] = &[COUNT];
+
+#[derive(Clone, Copy, PartialEq)]
+enum Entity {
+    Results,
+    Runs,
+    Testcases,
+}
+
+impl Entity {
+    fn parse(input: &str) -> Result<Self, ApiError> {
+        match input.trim() {
+            "results" => Ok(Self::Results),
+            "runs" => Ok(Self::Runs),
+            "testcases" => Ok(Self::Testcases),
+            _ => Err(ApiError::InvalidReportEntity),
+        }
+    }
+
+    fn fields(self) -> &'static [Field] {
+        match self {
+            Self::Results => RESULT_FIELDS,
+            Self::Runs => RUN_FIELDS,
+            Self::Testcases => TESTCASE_FIELDS,
+        }
+    }
+
+    fn aggregates(self) -> &'static [Aggregate] {
+        match self {
+            Self::Results => RESULT_AGGREGATES,
+            Self::Runs => RUN_AGGREGATES,
+            Self::Testcases => TESTCASE_AGGREGATES,
+        }
+    }
+
+    /// The joins and the project condition, which takes the project id as its only bind.
+    fn source(self) -> (&'static str, &'static str) {
+        match self {
+            Self::Results => (
+                r#"
+                FROM run_items ri
+                JOIN runs r ON r.id = ri.run_id
+                JOIN testcase_versions tv ON tv.id = ri.testcase_version_id
+                JOIN testcases tc ON tc.id = tv.testcase_id
+                LEFT JOIN run_results rr ON rr.run_item_id = ri.id
+                LEFT JOIN users u ON u.id = rr.updated_by_user_id
+                LEFT JOIN assets a ON a.id = r.asset_id
+                "#,
+                "r.project_id = ",
+            ),
+            Self::Runs => (
+                r#"
+                FROM runs r
+                LEFT JOIN users u ON u.id = r.executed_by_user_id
+                LEFT JOIN assets a ON a.id = r.asset_id
+                "#,
+                "r.project_id = ",
+            ),
+            Self::Testcases => (
+                r#"
+                FROM testcases tc
+                JOIN test_suites s ON s.id = tc.suite_id
+                "#,
+                "s.project_id = ",
+            ),
+        }
+    }
+
+    fn field(self, key: &str) -> Result<&'static Field, ApiError> {
+        self.fields()
+            .iter()
+            .find(|f| f.key == key)
+            .ok_or(ApiError::UnknownReportField)
+    }
+}
+
+/// What a report shows, stored as JSON with the saved report.
+#[derive(Serialize, Deserialize, Clone, ToSchema)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct ReportDefinition {
+    /// `results` (run items with their current result), `runs` or `testcases`.
+    entity: String,
+    /// All filters must match.
+    #[serde(default)]
+    filters: Vec<ReportFilter>,
+    /// Up to 3 fields. A grouped report lists aggregates in `columns` (`count` for every
+    /// entity, `ok`, `fail`, `passRate` for results, `averageDurationSeconds` for runs);
+    /// its rows start with the group values.
+    #[serde(default)]
+    group_by: Vec<String>,
+    /// Fields, or aggregates for a grouped report; aggregates alone give one total row.
+    columns: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ReportFilter {
+    field: String,
+    /// `eq`, `ne` (text and flags), `in`, `contains` (text), `gte`, `lte` (time).
+    op: String,
+    /// A string, a boolean for flags, a string array for `in`; times are `YYYY-MM-DD`
+    /// (UTC, `lte` includes the whole day) or RFC 3339.
+    #[schema(value_type = Object)]
+    value: Value,
+}
+
+enum Bound {
+    Text(String),
+    Texts(Vec<String>),
+    Bool(bool),
+    Time(DateTime<Utc>),
+}
+
+struct Condition {
+    sql: &'static str,
+    operator: &'static str,
+    bound: Bound,
+}
+
+/// A checked definition, ready to run.
+struct Plan {
+    entity: Entity,
+    conditions: Vec<Condition>,
+    group_by: Vec<&'static Field>,
+    fields: Vec<&'static Field>,
+    aggregates: Vec<&'static Aggregate>,
+    columns: Vec<String>,
+}
+
+fn parse_time(value: &Value, end_of_day: bool) -> Result<(DateTime<Utc>, bool), ApiError> {
+    let text = value.as_str().ok_or(ApiError::InvalidReportFilter)?.trim();
+    if let Ok(date) = NaiveDate::parse_from_str(text, "%Y-%m-%d") {
+        let date = if end_of_day {
+            date.checked_add_days(Days::new(1))
+                .ok_or(ApiError::InvalidReportFilter)?
+        } else {
+            date
+        };
+        return Ok((date.and_time(Default::default()).and_utc(), end_of_day));
+    }
+    let at = DateTime::parse_from_rfc3339(text).map_err(|_| ApiError::InvalidReportFilter)?;
+    Ok((at.with_timezone(&Utc), false))
+}
+
+fn plan_condition(entity: Entity, filter: &ReportFilter) -> Result<Condition, ApiError> {
+    let field = entity.field(filter.field.trim())?;
+    let invalid = ApiError::InvalidReportFilter;
+    let (operator, bound) = match (field.field_type, filter.op.trim(), &filter.value) {
+        (FieldType::Text, op @ ("eq" | "ne"), Value::String(s)) => (
+            if op == "eq" {
+                " = "
+            } else {
+                " IS DISTINCT FROM "
+            },
+            Bound::Text(s.clone()),
+        ),
+        (FieldType::Text, "contains", Value::String(s)) => {
+            let escaped = s
+                .replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_");
+            (" ILIKE ", Bound::Text(format!("%{escaped}%")))
+        }
+        (FieldType::Text, "in", Value::Array(items)) => {
+            if items.is_empty() || items.len() > MAX_IN_VALUES {
+                return Err(invalid);
+            }
+            let values = items
+                .iter()
+                .map(|v| v.as_str().map(str::to_string))
+                .collect::<Option<Vec<_>>>()
+                .ok_or(invalid)?;
+            (" = ANY", Bound::Texts(values))
+        }
+        (FieldType::Bool, op @ ("eq" | "ne"), Value::Bool(b)) => {
+            (if op == "eq" { " = " } else { " <> " }, Bound::Bool(*b))
+        }
+        (FieldType::Time, "gte", value) => (" >= ", Bound::Time(parse_time(value, false)?.0)),
+        (FieldType::Time, "lte", value) => {
+            let (at, exclusive) = parse_time(value, true)?;
+            (if exclusive { " < " } else { " <= " }, Bound::Time(at))
+        }
+        _ => return Err(invalid),
+    };
+    Ok(Condition {
+        sql: field.sql,
+        operator,
+        bound,
+    })
+}
+
+fn plan(definition: &ReportDefinition) -> Result<Plan, ApiError> {
+    let entity = Entity::parse(&definition.entity)?;
+    if definition.filters.len() > MAX_FILTERS
+        || definition.group_by.len() > MAX_GROUP_BY
+        || definition.columns.is_empty()
+        || definition.columns.len() > MAX_COLUMNS
+    {
+        return Err(ApiError::InvalidReportColumns);
+    }
+    let conditions = definition
+        .filters
+        .iter()
+        .map(|filter| plan_condition(entity, filter))
+        .collect::<Result<Vec<_>, _>>()?;
+    let group_by = definition
+        .group_by
+        .iter()
+        .map(|key| entity.field(key.trim()))
+        .collect::<Result<Vec<_>, _>>()?;
+    let aggregate = |key: &str| entity.aggregates().iter().find(|a| a.key == key);
+    let grouped = !group_by.is_empty()
+        || definition
+            .columns
+            .iter()
+            .any(|c| aggregate(c.trim()).is_some());
+
+    let mut fields = Vec::new();
+    let mut aggregates = Vec::new();
+    for column in &definition.columns {
+        let column = column.trim();
+        if grouped {
+            aggregates.push(aggregate(column).ok_or(ApiError::InvalidReportColumns)?);
+        } else {
+            fields.push(entity.field(column)?);
+        }
+    }
+    let columns = group_by
+        .iter()
+        .map(|f| f.key)
+        .chain(fields.iter().map(|f| f.key))
+        .chain(aggregates.iter().map(|a| a.key))
+        .map(str::to_string)
+        .collect();
+    Ok(Plan {
+        entity,
+        conditions,
+        group_by,
+        fields,
+        aggregates,
+        columns,
+    })
+}
+
+/// The result of running a report: one array per row, values in the order of `columns`.
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportResult {
+    columns: Vec<String>,
+    #[schema(value_type = Vec<Vec<Object>>)]
+    rows: Vec<Value>,
+    /// There were more than 10000 rows; only the first ones are returned.
+    truncated: bool,
+    generated_at: String,
+}
+
+async fn execute(state: &AppState, project_id: Uuid, plan: Plan) -> Result<ReportResult, ApiError> {
+    let (source, project_condition) = plan.entity.source();
+    let selected: Vec<&str> = plan
+        .group_by
+        .iter()
+        .chain(plan.fields.iter())
+        .map(|f| f.sql)
+        .chain(plan.aggregates.iter().map(|a| a.sql))
+        .collect();
+
+    let mut query = QueryBuilder::<Postgres>::new("SELECT jsonb_build_array(");
+    query.push(selected.join(", "));
+    query.push(") AS row ");
+    query.push(source);
+    query.push(" WHERE ");
+    query.push(project_condition);
+    query.push_bind(project_id);
+    for condition in plan.conditions {
+        query.push(" AND ");
+        query.push(condition.sql);
+        query.push(condition.operator);
+        match condition.bound {
+            Bound::Text(value) => query.push_bind(value),
+            Bound::Texts(values) => query.push("(").push_bind(values).push(")"),
+            Bound::Bool(value) => query.push_bind(value),
+            Bound::Time(value) => query.push_bind(value),
+        };
+    }
+    let order: Vec<&str> = if plan.group_by.is_empty() {
+        plan.fields.iter().map(|f| f.sql).collect()
+    } else {
+        let keys: Vec<&str> = plan.group_by.iter().map(|f| f.sql).collect();
+        query.push(" GROUP BY ");
+        query.push(keys.join(", "));
+        keys
+    };
+    if !order.is_empty() {
+        query.push(" ORDER BY ");
+        query.push(order.join(", "));
+    }
+    query.push(" LIMIT ");
+    query.push_bind(MAX_ROWS + 1);
+
+    let mut rows: Vec<Value> = query
+        .build()
+        .fetch_all(state.read_db())
+        .await
+        .map_err(|_| ApiError::ReportRunFailed)?
+        .iter()
+        .map(|row| row.get("row"))
+        .collect();
+    let truncated = rows.len() as i64 > MAX_ROWS;
+    rows.truncate(MAX_ROWS as usize);
+    Ok(ReportResult {
+        columns: plan.columns,
+        rows,
+        truncated,
+        generated_at: now_iso(),
+    })
+}
+
+fn render_csv(result: &ReportResult) -> anyhow::Result<Vec<u8>> {
+    let mut writer = csv::Writer::from_writer(export::UTF8_BOM.to_vec());
+    writer.write_record(&result.columns)?;
+    for row in &result.rows {
+        let cells = row.as_array().map(Vec::as_slice).unwrap_or_default();
+        writer.write_record(cells.iter().map(|cell| match cell {
+            Value::Null => String::new(),
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        }))?;
+    }
+    Ok(writer.into_inner()?)
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ReportFormatQuery {
+    /// `json` (default) or `csv`.
+    format: Option<String>,
+}
+
+fn wants_csv(query: &ReportFormatQuery) -> Result<bool, ApiError> {
+    match query.format.as_deref().map(str::trim) {
+        None | Some("json") => Ok(false),
+        Some("csv") => Ok(true),
+        Some(_) => Err(ApiError::InvalidReportFormat),
+    }
+}
+
+fn respond(result: ReportResult, csv: bool, file_stem: &str) -> Result<Response, ApiError> {
+    if !csv {
+        return Ok(Json(result).into_response());
+    }
+    let body = render_csv(&result).map_err(|_| ApiError::ExportFailed)?;
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{file_stem}.csv\""),
+            ),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportScheduleRequest {
+    /// Five fields or `@hourly|@daily|@weekly|@monthly`, as for run schedules.
+    cron: String,
+    /// IANA time zone; the project's zone by default.
+    timezone: Option<String>,
+    /// Project members who get the CSV by email.
+    recipient_user_ids: Vec<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportRequest {
+    name: String,
+    definition: ReportDefinition,
+    /// Visible to every project member; private to the author by default.
+    is_shared: Option<bool>,
+    /// Emails the report as CSV on a schedule; none by default.
+    schedule: Option<ReportScheduleRequest>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportScheduleView {
+    cron: String,
+    timezone: String,
+    recipient_user_ids: Vec<String>,
+    next_run_at: Option<DateTime<Utc>>,
+    last_run_at: Option<DateTime<Utc>>,
+    /// The delivery job of the last run; see `GET /api/v2/jobs/{job_id}`.
+    last_job_id: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportView {
+    id: String,
+    name: String,
+    definition: ReportDefinition,
+    is_shared: bool,
+    owner_user_id: String,
+    schedule: Option<ReportScheduleView>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ListReportsResponse {
+    reports: Vec<ReportView>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct DeleteReportResponse {
+    ok: bool,
+}
+
+/// A `report_definitions` row as the queries below select it.
+struct ReportRow {
+    id: Uuid,
+    project_id: Uuid,
+    owner_user_id: Uuid,
+    name: String,
+    definition: Value,
+    is_shared: bool,
+    cron: Option<String>,
+    timezone: Option<String>,
+    recipient_user_ids: Vec<Uuid>,
+    next_run_at: Option<DateTime<Utc>>,
+    last_run_at: Option<DateTime<Utc>>,
+    last_job_id: Option<Uuid>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+impl ReportRow {
+    fn into_view(self) -> Result<ReportView, ApiError> {
+        let definition =
+            serde_json::from_value(self.definition).map_err(|_| ApiError::ReportsReadFailed)?;
+        let schedule = match (self.cron, self.timezone) {
+            (Some(cron), Some(timezone)) => Some(ReportScheduleView {
+                cron,
+                timezone,
+                recipient_user_ids: self
+                    .recipient_user_ids
+                    .iter()
+                    .map(Uuid::to_string)
+                    .collect(),
+                next_run_at: self.next_run_at,
+                last_run_at: self.last_run_at,
+                last_job_id: self.last_job_id.map(|id| id.to_string()),
+            }),
+            _ => None,
+        };
+        Ok(ReportView {
+            id: self.id.to_string(),
+            name: self.name,
+            definition,
+            is_shared: self.is_shared,
+            owner_user_id: self.owner_user_id.to_string(),
+            schedule,
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+        })
+    }
+}
+
+/// A report of the project visible to the actor.
+async fn fetch_report(
+    state: &AppState,
+    project_id: Uuid,
+    report_id: Uuid,
+    actor_id: Uuid,
+) -> Result<ReportView, ApiError> {
+    sqlx::query_as!(
+        ReportRow,
+        r#"
+        SELECT id, project_id, owner_user_id, name, definition, is_shared, cron, timezone,
+               recipient_user_ids, next_run_at, last_run_at, last_job_id, created_at, updated_at
+        FROM report_definitions
+        WHERE project_id = $1 AND id = $2 AND (owner_user_id = $3 OR is_shared)
+        "#,
+        project_id,
+        report_id,
+        actor_id
+    )
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|_| ApiError::ReportsReadFailed)?
+    .ok_or(ApiError::ReportNotFound)?
+    .into_view()
+}
+
+/// Loads a report the actor may change: their own, or a shared one for project managers.
+async fn fetch_editable(
+    state: &AppState,
+    access: &ProjectRole,
+    report_id: Uuid,
+    actor_id: Uuid,
+) -> Result<ReportView, ApiError> {
+    let report = fetch_report(state, access.project_id, report_id, actor_id).await?;
+    if report.owner_user_id != actor_id.to_string() {
+        access
+            .require(Capability::ProjectManage)
+            .map_err(|_| ApiError::ReportForbidden)?;
+    }
+    Ok(report)
+}
+
+/// A validated request, ready to be stored.
+struct ReportInput {
+    name: String,
+    definition: ReportDefinition,
+    is_shared: bool,
+    cron: Option<String>,
+    timezone: Option<String>,
+    recipients: Vec<Uuid>,
+    next_run_at: Option<DateTime<Utc>>,
+}
+
+async fn validate_request(
+    state: &AppState,
+    project_id: Uuid,
+    payload: ReportRequest,
+) -> Result<ReportInput, ApiError> {
+    let name = payload.name.trim().to_string();
+    if name.is_empty() || name.chars().count() > 200 {
+        return Err(ApiError::InvalidReportName);
+    }
+    plan(&payload.definition)?;
+    let mut input = ReportInput {
+        name,
+        definition: payload.definition,
+        is_shared: payload.is_shared.unwrap_or(false),
+        cron: None,
+        timezone: None,
+        recipients: Vec::new(),
+        next_run_at: None,
+    };
+    let Some(schedule) = payload.schedule else {
+        return Ok(input);
+    };
+
+    let cron_text = schedule
+        .cron
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    let cron = CronExpr::parse(&cron_text)?;
+    let timezone =
+        schedules::resolve_timezone(state, project_id, schedule.timezone.as_deref()).await?;
+    if schedule.recipient_user_ids.is_empty() || schedule.recipient_user_ids.len() > MAX_RECIPIENTS
+    {
+        return Err(ApiError::InvalidReportRecipient);
+    }
+    let mut recipients = Vec::new();
+    for user_id in &schedule.recipient_user_ids {
+        let user_uuid = parse_uuid(user_id.trim(), ApiError::InvalidReportRecipient)?;
+        if !recipients.contains(&user_uuid) {
+            recipients.push(user_uuid);
+        }
+    }
+    {
+        let _guard = state.file_lock.lock().await;
+        let projects = read_projects(&state.projects_file)
+            .await
+            .map_err(|_| ApiError::ProjectsReadFailed)?;
+        let project = projects
+            .iter()
+            .find(|p| p.id == project_id.to_string())
+            .ok_or(ApiError::ProjectNotFound)?;
+        if recipients
+            .iter()
+            .any(|id| membership_role(project, &id.to_string()).is_none())
+        {
+            return Err(ApiError::InvalidReportRecipient);
+        }
+    }
+    for user_id in &recipients {
+        ensure_db_user_exists(state, &user_id.to_string()).await?;
+    }
+    input.next_run_at = Some(
+        schedules::next_run_at(&state.db, &cron, &timezone)
+            .await
+            .map_err(|_| ApiError::ReportsReadFailed)?
+            .ok_or(ApiError::InvalidCronExpression)?,
+    );
+    input.cron = Some(cron_text);
+    input.timezone = Some(timezone);
+    input.recipients = recipients;
+    Ok(input)
+}
+
+fn audit_snapshot(input: &ReportInput) -> Value {
+    json!({
+        "name": &input.name,
+        "definition": &input.definition,
+        "isShared": input.is_shared,
+        "cron": &input.cron,
+        "timezone": &input.timezone,
+        "recipientUserIds": &input.recipients,
+    })
+}
+
+fn view_snapshot(report: &ReportView) -> Value {
+    json!({
+        "name": &report.name,
+        "definition": &report.definition,
+        "isShared": report.is_shared,
+        "cron": report.schedule.as_ref().map(|s| &s.cron),
+        "timezone": report.schedule.as_ref().map(|s| &s.timezone),
+        "recipientUserIds": report.schedule.as_ref().map(|s| &s.recipient_user_ids),
+    })
+}
+
+/// The actor's own reports and the ones shared in the project.
+#[utoipa::path(
+    get,
+    path = "/api/v2/projects/{project_id}/reports",
+    tag = "reports",
+    params(("project_id" = String, Path)),
+    responses((status = 200, body = ListReportsResponse))
+)]
+pub async fn list_reports(
+    State(state): State<AppState>,
+    access: ProjectRole,
+) -> Result<Json<ListReportsResponse>, ApiError> {
+    let actor_uuid = parse_uuid(&access.user_id, ApiError::InvalidUserId)?;
+    let rows = sqlx::query_as!(
+        ReportRow,
+        r#"
+        SELECT id, project_id, owner_user_id, name, definition, is_shared, cron, timezone,
+               recipient_user_ids, next_run_at, last_run_at, last_job_id, created_at, updated_at
+        FROM report_definitions
+        WHERE project_id = $1 AND (owner_user_id = $2 OR is_shared)
+        ORDER BY name ASC, id ASC
+        "#,
+        access.project_id,
+        actor_uuid
+    )
+    .fetch_all(&state.db)
+    .await
+    .map_err(|_| ApiError::ReportsReadFailed)?;
+    Ok(Json(ListReportsResponse {
+        reports: rows
+            .into_iter()
+            .map(ReportRow::into_view)
+            .collect::<Result<_, _>>()?,
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v2/projects/{project_id}/reports",
+    tag = "reports",
+    params(("project_id" = String, Path)),
+    request_body = ReportRequest,
+    responses((status = 201, body = ReportView))
+)]
+pub async fn create_report(
+    State(state): State<AppState>,
+    access: ProjectRole,
+    Json(payload): Json<ReportRequest>,
+) -> Result<(StatusCode, Json<ReportView>), ApiError> {
+    let project_id = access.project_id;
+    let input = validate_request(&state, project_id, payload).await?;
+    ensure_db_user_exists(&state, &access.user_id).await?;
+    let actor_uuid = parse_uuid(&access.user_id, ApiError::InvalidUserId)?;
+
+    let create_failed = |err| db_errors::map(err, ApiError::ReportCreateFailed);
+    let mut tx = state.db.begin().await.map_err(create_failed)?;
+    let report_id = sqlx::query_scalar!(
+        r#"
+        INSERT INTO report_definitions (
+          project_id, owner_user_id, name, definition, is_shared, cron, timezone,
+          recipient_user_ids, next_run_at
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        ON CONFLICT (project_id, owner_user_id, name) DO NOTHING
+        RETURNING id
+        "#,
+        project_id,
+        actor_uuid,
+        &input.name,
+        json!(&input.definition),
+        input.is_shared,
+        input.cron.as_deref(),
+        input.timezone.as_deref(),
+        &input.recipients,
+        input.next_run_at
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(create_failed)?;
+    let report_id = report_id.ok_or(ApiError::ReportExists)?;
+    audit::record(
+        &mut *tx,
+        audit::AuditEntry {
+            actor_user_id: Some(actor_uuid),
+            action: "create",
+            entity_type: "report_definition",
+            entity_id: Some(report_id),
+            project_id: Some(project_id),
+            run_id: None,
+            before: None,
+            after: Some(audit_snapshot(&input)),
+        },
+    )
+    .await
+    .map_err(create_failed)?;
+    tx.commit().await.map_err(create_failed)?;
+
+    let report = fetch_report(&state, project_id, report_id, actor_uuid).await?;
+    Ok((StatusCode::CREATED, Json(report)))
+}
+
+/// Replaces the report; the author may change it, project managers also shared ones.
+/// Without `schedule` the report is no longer emailed.
+#[utoipa::path(
+    put,
+    path = "/api/v2/projects/{project_id}/reports/{report_id}",
+    tag = "reports",
+    params(("project_id" = String, Path), ("report_id" = String, Path)),
+    request_body = ReportRequest,
+    responses((status = 200, body = ReportView))
+)]
+pub async fn update_report(
+    State(state): State<AppState>,
+    Path((_project_id, report_id)): Path<(String, String)>,
+    access: ProjectRole,
+    Json(payload): Json<ReportRequest>,
+) -> Result<Json<ReportView>, ApiError> {
+    let report_uuid = parse_uuid(&report_id, ApiError::InvalidReportId)?;
+    ensure_db_user_exists(&state, &access.user_id).await?;
+    let actor_uuid = parse_uuid(&access.user_id, ApiError::InvalidUserId)?;
+    let project_id = access.project_id;
+    let before = fetch_editable(&state, &access, report_uuid, actor_uuid).await?;
+    let input = validate_request(&state, project_id, payload).await?;
+
+    let update_failed = |err| db_errors::map(err, ApiError::ReportUpdateFailed);
+    let mut tx = state.db.begin().await.map_err(update_failed)?;
+    let updated = sqlx::query!(
+        r#"
+        UPDATE report_definitions
+        SET name = $3, definition = $4, is_shared = $5, cron = $6, timezone = $7,
+            recipient_user_ids = $8, next_run_at = $9, updated_at = NOW()
+        WHERE project_id = $1 AND id = $2
+          AND NOT EXISTS (
+            SELECT 1 FROM report_definitions other
+            WHERE other.project_id = $1
+              AND other.owner_user_id = report_definitions.owner_user_id
+              AND other.name = $3 AND other.id <> $2
+          )
+        "#,
+        project_id,
+        report_uuid,
+        &input.name,
+        json!(&input.definition),
+        input.is_shared,
+        input.cron.as_deref(),
+        input.timezone.as_deref(),
+        &input.recipients,
+        input.next_run_at
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(update_failed)?;
+    if updated.rows_affected() == 0 {
+        return Err(ApiError::ReportExists);
+    }
+    audit::record(
+        &mut *tx,
+        audit::AuditEntry {
+            actor_user_id: Some(actor_uuid),
+            action: "update",
+            entity_type: "report_definition",
+            entity_id: Some(report_uuid),
+            project_id: Some(project_id),
+            run_id: None,
+            before: Some(view_snapshot(&before)),
+            after: Some(audit_snapshot(&input)),
+        },
+    )
+    .await
+    .map_err(update_failed)?;
+    tx.commit().await.map_err(update_failed)?;
+
+    Ok(Json(
+        fetch_report(&state, project_id, report_uuid, actor_uuid).await?,
+    ))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v2/projects/{project_id}/reports/{report_id}",
+    tag = "reports",
+    params(("project_id" = String, Path), ("report_id" = String, Path)),
+    responses((status = 200, body = DeleteReportResponse))
+)]
+pub async fn delete_report(
+    State(state): State<AppState>,
+    Path((_project_id, report_id)): Path<(String, String)>,
+    access: ProjectRole,
+) -> Result<Json<DeleteReportResponse>, ApiError> {
+    let report_uuid = parse_uuid(&report_id, ApiError::InvalidReportId)?;
+    ensure_db_user_exists(&state, &access.user_id).await?;
+    let actor_uuid = parse_uuid(&access.user_id, ApiError::InvalidUserId)?;
+    let project_id = access.project_id;
+    let report = fetch_editable(&state, &access, report_uuid, actor_uuid).await?;
+
+    let delete_failed = |err| db_errors::map(err, ApiError::ReportDeleteFailed);
+    let mut tx = state.db.begin().await.map_err(delete_failed)?;
+    sqlx::query!(
+        "DELETE FROM report_definitions WHERE project_id = $1 AND id = $2",
+        project_id,
+        report_uuid
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(delete_failed)?;
+    audit::record(
+        &mut *tx,
+        audit::AuditEntry {
+            actor_user_id: Some(actor_uuid),
+            action: "delete",
+            entity_type: "report_definition",
+            entity_id: Some(report_uuid),
+            project_id: Some(project_id),
+            run_id: None,
+            before: Some(view_snapshot(&report)),
+            after: None,
+        },
+    )
+    .await
+    .map_err(delete_failed)?;
+    tx.commit().await.map_err(delete_failed)?;
+
+    Ok(Json(DeleteReportResponse { ok: true }))
+}
+
+/// Runs a saved report.
+#[utoipa::path(
+    get,
+    path = "/api/v2/projects/{project_id}/reports/{report_id}/result",
+    tag = "reports",
+    params(("project_id" = String, Path), ("report_id" = String, Path), ReportFormatQuery),
+    responses(
+        (status = 200, body = ReportResult),
+        (status = 200, description = "`format=csv`: the same table as a CSV file.", content_type = "text/csv")
+    )
+)]
+pub async fn run_report(
+    State(state): State<AppState>,
+    Path((_project_id, report_id)): Path<(String, String)>,
+    access: ProjectRole,
+    Query(query): Query<ReportFormatQuery>,
+) -> Result<Response, ApiError> {
+    let csv = wants_csv(&query)?;
+    let report_uuid = parse_uuid(&report_id, ApiError::InvalidReportId)?;
+    let actor_uuid = parse_uuid(&access.user_id, ApiError::InvalidUserId)?;
+    let report = fetch_report(&state, access.project_id, report_uuid, actor_uuid).await?;
+    let result = execute(&state, access.project_id, plan(&report.definition)?).await?;
+    respond(result, csv, &format!("report-{report_uuid}"))
+}
+
+/// Runs a definition without saving it, e.g. while the report is being built.
+#[utoipa::path(
+    post,
+    path = "/api/v2/projects/{project_id}/reports/preview",
+    tag = "reports",
+    params(("project_id" = String, Path), ReportFormatQuery),
+    request_body = ReportDefinition,
+    responses(
+        (status = 200, body = ReportResult),
+        (status = 200, description = "`format=csv`: the same table as a CSV file.", content_type = "text/csv")
+    )
+)]
+pub async fn preview_report(
+    State(state): State<AppState>,
+    access: ProjectRole,
+    Query(query): Query<ReportFormatQuery>,
+    Json(definition): Json<ReportDefinition>,
+) -> Result<Response, ApiError> {
+    let csv = wants_csv(&query)?;
+    let result = execute(&state, access.project_id, plan(&definition)?).await?;
+    respond(result, csv, "report")
+}
+
+/// Queues delivery jobs for reports whose schedule is due. Each report is claimed with
+/// `SKIP LOCKED`, so several API instances never send it twice; a delivery missed while
+/// the API was down happens once and the schedule continues from now.
+pub fn spawn_scheduler(state: AppState) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(err) = queue_due(&state).await {
+                warn!(error = %err, "report delivery pass failed");
+            }
+        }
+    });
+}
+
+async fn queue_due(state: &AppState) -> Result<(), sqlx::Error> {
+    loop {
+        let mut tx = state.db.begin().await?;
+        let Some(report) = sqlx::query!(
+            r#"
+            SELECT id, project_id, owner_user_id, cron, timezone
+            FROM report_definitions
+            WHERE next_run_at <= NOW()
+            ORDER BY next_run_at
+            LIMIT 1
+            FOR UPDATE SKIP LOCKED
+            "#,
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        else {
+            return Ok(());
+        };
+        let job_id = jobs::enqueue(
+            &mut *tx,
+            jobs::NewJob {
+                kind: jobs::JobKind::ReportDelivery,
+                project_id: Some(report.project_id),
+                created_by_user_id: Some(report.owner_user_id),
+                payload: json!({ "reportId": report.id }),
+            },
+        )
+        .await?;
+        let next = match (report.cron.as_deref().map(CronExpr::parse), report.timezone) {
+            (Some(Ok(cron)), Some(timezone)) => {
+                schedules::next_run_at(&state.db, &cron, &timezone).await?
+            }
+            _ => None,
+        };
+        sqlx::query!(
+            r#"
+            UPDATE report_definitions
+            SET next_run_at = $2, last_run_at = NOW(), last_job_id = $3
+            WHERE id = $1
+            "#,
+            report.id,
+            next,
+            job_id
+        )
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        state.jobs.wake();
+    }
+}
+
+/// Job handler of scheduled reports: runs the report on behalf of its author, keeps the
+/// CSV as the job file and emails it to the recipients who are still project members.
+pub async fn run_delivery_job(
+    state: &AppState,
+    ctx: &JobContext,
+    payload: Value,
+) -> anyhow::Result<Value> {
+    let report_id: Uuid = serde_json::from_value(payload["reportId"].clone())?;
+    let Some(row) = sqlx::query_as!(
+        ReportRow,
+        r#"
+        SELECT id, project_id, owner_user_id, name, definition, is_shared, cron, timezone,
+               recipient_user_ids, next_run_at, last_run_at, last_job_id, created_at, updated_at
+        FROM report_definitions
+        WHERE id = $1
+        "#,
+        report_id
+    )
+    .fetch_optional(&state.db)
+    .await?
+    else {
+        // Deleted after the delivery was queued.
+        return Ok(Value::Null);
+    };
+    let project_id = row.project_id;
+    let recipients = row.recipient_user_ids.clone();
+    let report = row
+        .into_view()
+        .map_err(|err| anyhow::anyhow!("{}", err.code()))?;
+    authz::require_capability(
+        state,
+        &project_id.to_string(),
+        &report.owner_user_id,
+        Capability::ProjectRead,
+    )
+    .await
+    .map_err(|err| anyhow::anyhow!("report author has no access: {}", err.code()))?;
+
+    let plan = plan(&report.definition).map_err(|err| anyhow::anyhow!("{}", err.code()))?;
+    let result = execute(state, project_id, plan)
+        .await
+        .map_err(|err| anyhow::anyhow!("{}", err.code()))?;
+    let key = format!("reports/custom/{}.csv", ctx.id);
+    let file_name = format!("report-{report_id}.csv");
+    state.storage.put(&key, render_csv(&result)?.into()).await?;
+
+    let (project_name, emails) = recipient_emails(state, project_id, &recipients).await?;
+    let mut body = format!(
+        "Отчёт «{}» по проекту «{project_name}» сформирован: строк — {}.",
+        report.name,
+        result.rows.len()
+    );
+    if result.truncated {
+        body.push_str(&format!(" Показаны первые {MAX_ROWS}."));
+    }
+    body.push_str("\nCSV-файл во вложении.\n");
+    for to in emails {
+        jobs::enqueue(
+            &state.db,
+            jobs::NewJob {
+                kind: jobs::JobKind::Email,
+                project_id: Some(project_id),
+                created_by_user_id: None,
+                payload: json!(notifications::OutgoingEmail {
+                    to,
+                    subject: format!("Uran: отчёт «{}»", report.name),
+                    body: body.clone(),
+                    reply_to: None,
+                    attachment: Some(notifications::EmailAttachment {
+                        file_name: file_name.clone(),
+                        content_type: "text/csv; charset=utf-8".to_string(),
+                        storage_key: key.clone(),
+                        content: Vec::new(),
+                    }),
+                }),
+            },
+        )
+        .await?;
+    }
+    state.jobs.wake();
+    Ok(jobs::file_result(
+        ctx.id,
+        &key,
+        "text/csv; charset=utf-8",
+        &file_name,
+    ))
+}
+
+/// Emails of the recipients who are still active project members, with the project name.
+async fn recipient_emails(
+    state: &AppState,
+    project_id: Uuid,
+    recipients: &[Uuid],
+) -> anyhow::Result<(String, Vec<String>)> {
+    let _guard = state.file_lock.lock().await;
+    let projects = read_projects(&state.projects_file).await?;
+    let Some(project) = projects.iter().find(|p| p.id == project_id.to_string()) else {
+        return Ok((String::new(), Vec::new()));
+    };
+    let users = read_users(&state.users_file).await?;
+    let emails = recipients
+        .iter()
+        .map(Uuid::to_string)
+        .filter(|id| membership_role(project, id).is_some())
+        .filter_map(|id| users.iter().find(|u| u.id == id))
+        .filter(|u| u.deactivated_at.is_none())
+        .map(|u| u.email.clone())
+        .collect();
+    Ok((project.name.clone(), emails))
+}
@@ -0,0 +1,237 @@
+use axum::{extract::State, Json};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::{
+    authz::{self, AuthUser},
+    error::ApiError,
+    parse_uuid, AppState,
+};
+
+/// Entries per list; the full lists have their own paginated endpoints.
+const SECTION_LIMIT: i64 = 10;
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DashboardRunView {
+    id: String,
+    project_id: String,
+    title: String,
+    status: String,
+    started_at: Option<DateTime<Utc>>,
+    created_at: DateTime<Utc>,
+    item_count: i64,
+    /// Items with a result other than `na`.
+    completed_count: i64,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DashboardItemView {
+    run_item_id: String,
+    run_id: String,
+    project_id: String,
+    run_title: String,
+    testcase_key: String,
+    testcase_title: String,
+    is_required: bool,
+    created_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DashboardMentionView {
+    comment_id: String,
+    run_id: String,
+    run_item_id: Option<String>,
+    project_id: String,
+    run_title: String,
+    author_user_id: Option<String>,
+    body: String,
+    created_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DashboardMilestoneView {
+    milestone_id: String,
+    project_id: String,
+    name: String,
+    due_date: NaiveDate,
+    run_count: i64,
+    /// Runs of the milestone still in `draft` or `in_progress`.
+    open_run_count: i64,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DashboardResponse {
+    /// Unfinished runs the caller executes or is the default assignee of.
+    runs: Vec<DashboardRunView>,
+    /// Open items assigned to the caller, as in `GET /api/v2/my/assignments`.
+    assigned_items: Vec<DashboardItemView>,
+    assigned_item_count: i64,
+    /// Newest comments that mention the caller.
+    mentions: Vec<DashboardMentionView>,
+    /// Milestones due today or later, nearest first.
+    upcoming_milestones: Vec<DashboardMilestoneView>,
+}
+
+/// Everything the home screen shows about the caller's own work across their projects,
+/// in one request.
+#[utoipa::path(
+    get,
+    path = "/api/v2/me/dashboard",
+    tag = "assignments",
+    responses((status = 200, body = DashboardResponse))
+)]
+pub async fn get_dashboard(
+    State(state): State<AppState>,
+    AuthUser(actor_id): AuthUser,
+) -> Result<Json<DashboardResponse>, ApiError> {
+    let actor_uuid = parse_uuid(&actor_id, ApiError::InvalidUserId)?;
+    let project_ids = authz::member_project_ids(&state, &actor_id).await?;
+    let read_failed = |_| ApiError::DashboardReadFailed;
+
+    let runs = sqlx::query_as!(
+        DashboardRunView,
+        r#"
+        SELECT
+          r.id::text AS "id!",
+          r.project_id::text AS "project_id!",
+          r.title,
+          r.status::text AS "status!",
+          r.started_at,
+          r.created_at,
+          COUNT(ri.id) AS "item_count!",
+          COUNT(rr.id) FILTER (WHERE rr.status <> 'na') AS "completed_count!"
+        FROM runs r
+        LEFT JOIN run_items ri ON ri.run_id = r.id
+        LEFT JOIN run_results rr ON rr.run_item_id = ri.id
+        WHERE (r.executed_by_user_id = $1 OR r.default_assignee_user_id = $1)
+          AND r.project_id = ANY($2)
+          AND r.status IN ('draft', 'in_progress')
+        GROUP BY r.id
+        ORDER BY r.created_at DESC, r.id DESC
+        LIMIT $3
+        "#,
+        actor_uuid,
+        &project_ids,
+        SECTION_LIMIT,
+    )
+    .fetch_all(state.read_db())
+    .await
+    .map_err(read_failed)?;
+
+    let assigned_items = sqlx::query_as!(
+        DashboardItemView,
+        r#"
+        SELECT
+          ri.id::text AS "run_item_id!",
+          r.id::text AS "run_id!",
+          r.project_id::text AS "project_id!",
+          r.title AS run_title,
+          tc.key AS testcase_key,
+          tc.title AS testcase_title,
+          ri.is_required,
+          ri.created_at
+        FROM run_items ri
+        JOIN runs r ON r.id = ri.run_id
+        JOIN testcase_versions tv ON tv.id = ri.testcase_version_id
+        JOIN testcases tc ON tc.id = tv.testcase_id
+        LEFT JOIN run_results rr ON rr.run_item_id = ri.id
+        WHERE COALESCE(ri.assignee_user_id, r.default_assignee_user_id) = $1
+          AND r.project_id = ANY($2)
+          AND r.status IN ('draft', 'in_progress')
+          AND (rr.id IS NULL OR rr.status IN ('na', 'retest'))
+        ORDER BY ri.created_at DESC, ri.id DESC
+        LIMIT $3
+        "#,
+        actor_uuid,
+        &project_ids,
+        SECTION_LIMIT,
+    )
+    .fetch_all(state.read_db())
+    .await
+    .map_err(read_failed)?;
+
+    let assigned_item_count = sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*) AS "count!"
+        FROM run_items ri
+        JOIN runs r ON r.id = ri.run_id
+        LEFT JOIN run_results rr ON rr.run_item_id = ri.id
+        WHERE COALESCE(ri.assignee_user_id, r.default_assignee_user_id) = $1
+          AND r.project_id = ANY($2)
+          AND r.status IN ('draft', 'in_progress')
+          AND (rr.id IS NULL OR rr.status IN ('na', 'retest'))
+        "#,
+        actor_uuid,
+        &project_ids,
+    )
+    .fetch_one(state.read_db())
+    .await
+    .map_err(read_failed)?;
+
+    let mentions = sqlx::query_as!(
+        DashboardMentionView,
+        r#"
+        SELECT
+          c.id::text AS "comment_id!",
+          c.run_id::text AS "run_id!",
+          c.run_item_id::text AS run_item_id,
+          r.project_id::text AS "project_id!",
+          r.title AS run_title,
+          c.author_user_id::text AS author_user_id,
+          c.body,
+          c.created_at
+        FROM comments c
+        JOIN runs r ON r.id = c.run_id
+        WHERE c.mentioned_user_ids @> ARRAY[$1::uuid]
+          AND c.deleted_at IS NULL
+          AND r.project_id = ANY($2)
+        ORDER BY c.created_at DESC, c.id DESC
+        LIMIT $3
+        "#,
+        actor_uuid,
+        &project_ids,
+        SECTION_LIMIT,
+    )
+    .fetch_all(state.read_db())
+    .await
+    .map_err(read_failed)?;
+
+    let upcoming_milestones = sqlx::query_as!(
+        DashboardMilestoneView,
+        r#"
+        SELECT
+          m.id::text AS "milestone_id!",
+          m.project_id::text AS "project_id!",
+          m.name,
+          m.due_date AS "due_date!",
+          COUNT(r.id) AS "run_count!",
+          COUNT(r.id) FILTER (WHERE r.status IN ('draft', 'in_progress')) AS "open_run_count!"
+        FROM milestones m
+        LEFT JOIN runs r ON r.project_id = m.project_id AND r.milestone = m.name
+        WHERE m.project_id = ANY($1)
+          AND m.due_date >= (NOW() AT TIME ZONE 'UTC')::date
+        GROUP BY m.id
+        ORDER BY m.due_date ASC, m.name ASC
+        LIMIT $2
+        "#,
+        &project_ids,
+        SECTION_LIMIT,
+    )
+    .fetch_all(state.read_db())
+    .await
+    .map_err(read_failed)?;
+
+    Ok(Json(DashboardResponse {
+        runs,
+        assigned_items,
+        assigned_item_count,
+        mentions,
+        upcoming_milestones,
+    }))
+}
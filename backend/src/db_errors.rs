@@ -0,0 +1,158 @@
+use sqlx::{error::ErrorKind, postgres::PgDatabaseError};
+
+use crate::error::ApiError;
+
+/// Constraints whose violation has a dedicated error naming the request field.
+const CONSTRAINTS: &[(&str, ApiError)] = &[
+    ("runs_project_id_fkey", ApiError::UnknownProjectId),
+    ("runs_asset_id_fkey", ApiError::UnknownAssetId),
+    ("runs_template_id_fkey", ApiError::UnknownTemplateId),
+    (
+        "runs_default_assignee_user_id_fkey",
+        ApiError::UnknownAssigneeUser,
+    ),
+    ("runs_lead_user_id_fkey", ApiError::UnknownUserId),
+    ("runs_executed_by_user_id_fkey", ApiError::UnknownUserId),
+    (
+        "run_items_testcase_version_id_fkey",
+        ApiError::UnknownTestcaseVersionId,
+    ),
+    (
+        "run_items_assignee_user_id_fkey",
+        ApiError::UnknownAssigneeUser,
+    ),
+    (
+        "run_items_run_id_testcase_version_id_key",
+        ApiError::DuplicateRunItem,
+    ),
+    ("run_results_run_item_id_fkey", ApiError::UnknownRunItemId),
+    ("run_results_run_item_id_key", ApiError::DuplicateRunResult),
+    (
+        "run_result_steps_run_item_id_fkey",
+        ApiError::UnknownRunItemId,
+    ),
+    ("run_result_steps_step_id_fkey", ApiError::UnknownStepId),
+    (
+        "run_item_dependencies_run_item_id_fkey",
+        ApiError::UnknownRunItemId,
+    ),
+    (
+        "run_item_dependencies_depends_on_run_item_id_fkey",
+        ApiError::UnknownDependsOnRunItemId,
+    ),
+    (
+        "run_template_items_testcase_version_id_fkey",
+        ApiError::UnknownTestcaseVersionId,
+    ),
+    ("run_templates_project_id_fkey", ApiError::UnknownProjectId),
+    (
+        "run_templates_project_id_key_key",
+        ApiError::DuplicateTemplateKey,
+    ),
+    ("schedules_template_id_fkey", ApiError::UnknownTemplateId),
+    (
+        "schedules_assignee_user_id_fkey",
+        ApiError::UnknownAssigneeUser,
+    ),
+    ("assets_project_id_fkey", ApiError::UnknownProjectId),
+    ("test_suites_project_id_fkey", ApiError::UnknownProjectId),
+    ("test_suites_parent_id_fkey", ApiError::UnknownParentSuiteId),
+    (
+        "test_suites_project_id_key_key",
+        ApiError::DuplicateSuiteKey,
+    ),
+    ("testcases_suite_id_fkey", ApiError::UnknownSuiteId),
+    ("testcases_suite_id_key_key", ApiError::TestcaseKeyConflict),
+    (
+        "testcase_steps_testcase_version_id_position_key",
+        ApiError::DuplicateStepPosition,
+    ),
+    ("testcase_tags_tag_id_fkey", ApiError::UnknownTagId),
+    (
+        "testcase_tags_testcase_id_fkey",
+        ApiError::UnknownTestcaseId,
+    ),
+    ("requirements_project_id_fkey", ApiError::UnknownProjectId),
+    (
+        "requirements_project_id_key_key",
+        ApiError::DuplicateRequirementKey,
+    ),
+    (
+        "requirement_testcases_testcase_id_fkey",
+        ApiError::UnknownTestcaseId,
+    ),
+    ("comments_run_item_id_fkey", ApiError::UnknownRunItemId),
+    ("comments_parent_id_fkey", ApiError::UnknownParentCommentId),
+    ("project_members_user_id_fkey", ApiError::UnknownUserId),
+    (
+        "project_fail_reasons_project_id_code_key",
+        ApiError::FailReasonExists,
+    ),
+    (
+        "custom_fields_project_id_entity_key_key",
+        ApiError::CustomFieldExists,
+    ),
+    (
+        "saved_filters_project_id_owner_user_id_target_name_key",
+        ApiError::SavedFilterExists,
+    ),
+    (
+        "defects_run_result_id_issue_key_key",
+        ApiError::DefectAlreadyLinked,
+    ),
+    ("users_email_key", ApiError::EmailTaken),
+];
+
+/// Enum types whose invalid input has a dedicated error naming the request field.
+const ENUM_TYPES: &[(&str, ApiError)] = &[
+    ("run_status", ApiError::InvalidStatusValue),
+    ("result_status", ApiError::InvalidStatusValue),
+    ("user_role", ApiError::InvalidRoleValue),
+    ("project_role", ApiError::InvalidRoleValue),
+];
+
+/// Turns a failed write into an error the client can act on: a missing referenced record
+/// is `404`, a duplicate or a still-referenced record is `409`, a value rejected by an enum
+/// cast or a check is `422`. Anything that is not a constraint violation (connection loss,
+/// timeouts, ...) keeps the handler's own `fallback`.
+pub fn map(err: sqlx::Error, fallback: ApiError) -> ApiError {
+    let Some(db) = err.as_database_error() else {
+        return fallback;
+    };
+    let pg = db.try_downcast_ref::<PgDatabaseError>();
+    match db.kind() {
+        ErrorKind::ForeignKeyViolation => {
+            // The same constraint fires when a referenced row is deleted; Postgres only
+            // tells the two cases apart in the detail text.
+            let still_referenced = pg
+                .and_then(PgDatabaseError::detail)
+                .is_some_and(|detail| detail.contains("is still referenced"));
+            if still_referenced {
+                return ApiError::RecordInUse;
+            }
+            by_constraint(db.constraint()).unwrap_or(ApiError::ReferencedRecordNotFound)
+        }
+        ErrorKind::UniqueViolation => {
+            by_constraint(db.constraint()).unwrap_or(ApiError::DuplicateRecord)
+        }
+        ErrorKind::NotNullViolation | ErrorKind::CheckViolation => ApiError::ConstraintViolation,
+        _ if db.code().as_deref() == Some("22P02") => {
+            // invalid_text_representation: `invalid input value for enum run_status: "x"`.
+            let message = db.message();
+            ENUM_TYPES
+                .iter()
+                .find(|(name, _)| message.contains(&format!("for enum {name}:")))
+                .map(|(_, err)| *err)
+                .unwrap_or(ApiError::InvalidEnumValue)
+        }
+        _ => fallback,
+    }
+}
+
+fn by_constraint(constraint: Option<&str>) -> Option<ApiError> {
+    let constraint = constraint?;
+    CONSTRAINTS
+        .iter()
+        .find(|(name, _)| *name == constraint)
+        .map(|(_, err)| *err)
+}
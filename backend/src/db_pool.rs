@@ -0,0 +1,183 @@
+use std::{
+    str::FromStr,
+    sync::atomic::{AtomicU64, AtomicUsize, Ordering},
+    time::Duration,
+};
+
+use anyhow::Context;
+use axum::{
+    extract::{Request, State},
+    http::{header::RETRY_AFTER, HeaderValue},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+use sqlx::{
+    postgres::{PgConnectOptions, PgPoolOptions},
+    PgPool,
+};
+use tracing::warn;
+use utoipa::ToSchema;
+
+use crate::{authz::AdminUser, config::DatabasePoolConfig, error::ApiError, AppState};
+
+/// Seconds a shed client is told to wait; the pool usually drains well within that.
+const SHED_RETRY_AFTER: u32 = 2;
+
+/// Pool and connection settings for `url` sized by `config`. The statement timeout is a
+/// startup parameter of every connection, so it also bounds queries whose client is gone.
+pub fn options(
+    url: &str,
+    config: &DatabasePoolConfig,
+) -> anyhow::Result<(PgPoolOptions, PgConnectOptions)> {
+    let mut connect = PgConnectOptions::from_str(url).context("invalid PostgreSQL URL")?;
+    if let Some(timeout) = config.statement_timeout {
+        connect = connect.options([("statement_timeout", timeout.as_millis().to_string())]);
+    }
+    let pool = PgPoolOptions::new()
+        .max_connections(config.max_connections)
+        .acquire_timeout(config.acquire_timeout);
+    Ok((pool, connect))
+}
+
+/// Opens connections on first use, for a server that may be down at startup.
+pub fn connect_lazy(url: &str, config: &DatabasePoolConfig) -> anyhow::Result<PgPool> {
+    let (pool, connect) = options(url, config)?;
+    Ok(pool.connect_lazy_with(connect))
+}
+
+/// Load shedding in front of the primary pool. Counts requests whose handler is running
+/// (a WebSocket or SSE response leaves once its headers are sent), and while the pool has
+/// no idle connection turns away those that would queue behind more than
+/// `DATABASE_MAX_WAITING_REQUESTS` others: a quick `503` with `Retry-After` instead of a
+/// `500` after the acquire timeout.
+pub struct Backpressure {
+    in_flight: AtomicUsize,
+    max_waiting: usize,
+    shed_total: AtomicU64,
+    /// Only reported by [`pool_metrics`]; the pool cannot tell it back.
+    statement_timeout: Option<Duration>,
+}
+
+impl Backpressure {
+    pub fn new(config: &DatabasePoolConfig) -> Self {
+        Self {
+            in_flight: AtomicUsize::new(0),
+            max_waiting: config.max_waiting_requests as usize,
+            shed_total: AtomicU64::new(0),
+            statement_timeout: config.statement_timeout,
+        }
+    }
+}
+
+struct InFlight<'a>(&'a AtomicUsize);
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+fn is_saturated(pool: &PgPool) -> bool {
+    pool.size() >= pool.options().get_max_connections() && pool.num_idle() == 0
+}
+
+/// Only `/api` is shed; health checks and the web UI are always served.
+pub async fn shed_load(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    if !request.uri().path().starts_with("/api/") {
+        return next.run(request).await;
+    }
+    let backpressure = &state.backpressure;
+    let ahead = backpressure.in_flight.fetch_add(1, Ordering::Relaxed);
+    let _guard = InFlight(&backpressure.in_flight);
+    let capacity = state.db.options().get_max_connections() as usize;
+    if ahead >= capacity + backpressure.max_waiting && is_saturated(&state.db) {
+        let shed = backpressure.shed_total.fetch_add(1, Ordering::Relaxed) + 1;
+        if shed.is_power_of_two() {
+            warn!(
+                in_flight = ahead,
+                shed_total = shed,
+                "database pool saturated, shedding requests"
+            );
+        }
+        let mut response = ApiError::DatabaseBusy.into_response();
+        response
+            .headers_mut()
+            .insert(RETRY_AFTER, HeaderValue::from(SHED_RETRY_AFTER));
+        return response;
+    }
+    next.run(request).await
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PoolStats {
+    /// Open connections, busy and idle.
+    size: u32,
+    idle: usize,
+    max_connections: u32,
+    saturated: bool,
+}
+
+impl PoolStats {
+    fn of(pool: &PgPool) -> Self {
+        Self {
+            size: pool.size(),
+            idle: pool.num_idle(),
+            max_connections: pool.options().get_max_connections(),
+            saturated: is_saturated(pool),
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplicaPoolStats {
+    #[serde(flatten)]
+    pool: PoolStats,
+    /// Whether reads are routed to the replica right now.
+    healthy: bool,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PoolMetricsResponse {
+    primary: PoolStats,
+    /// Only with `DATABASE_REPLICA_URL`.
+    replica: Option<ReplicaPoolStats>,
+    acquire_timeout_seconds: u64,
+    statement_timeout_seconds: Option<u64>,
+    /// `/api` requests whose handler is running.
+    in_flight_requests: usize,
+    max_waiting_requests: usize,
+    /// Requests answered `503 database_busy` since the start.
+    shed_requests_total: u64,
+}
+
+/// Current state of the database pools and the load shedding, for dashboards and alerts.
+#[utoipa::path(
+    get,
+    path = "/api/admin/database/pool",
+    tag = "admin",
+    responses((status = 200, body = PoolMetricsResponse))
+)]
+pub async fn pool_metrics(
+    State(state): State<AppState>,
+    AdminUser(_admin_id): AdminUser,
+) -> Json<PoolMetricsResponse> {
+    let options = state.db.options();
+    let backpressure = &state.backpressure;
+    Json(PoolMetricsResponse {
+        primary: PoolStats::of(&state.db),
+        replica: state.replica.as_deref().map(|replica| ReplicaPoolStats {
+            pool: PoolStats::of(replica.raw_pool()),
+            healthy: replica.pool().is_some(),
+        }),
+        acquire_timeout_seconds: options.get_acquire_timeout().as_secs(),
+        statement_timeout_seconds: backpressure.statement_timeout.map(|t| t.as_secs()),
+        in_flight_requests: backpressure.in_flight.load(Ordering::Relaxed),
+        max_waiting_requests: backpressure.max_waiting,
+        shed_requests_total: backpressure.shed_total.load(Ordering::Relaxed),
+    })
+}
@@ -0,0 +1,426 @@
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::{
+    audit::{self, AuditEntry},
+    authz::{AuthUser, ProjectRole},
+    db_errors, ensure_db_user_exists,
+    error::ApiError,
+    parse_uuid,
+    permissions::Capability,
+    testcases, AppState,
+};
+
+const DEFAULT_MIN_SCORE: f64 = 0.6;
+const DEFAULT_PAIR_LIMIT: i64 = 50;
+const MAX_PAIR_LIMIT: i64 = 200;
+
+#[derive(Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct DuplicatesQuery {
+    /// Lowest score of a reported pair, 0 to 1; 0.6 by default.
+    min_score: Option<f64>,
+    /// Pairs to return, best first; 50 by default, at most 200.
+    limit: Option<i64>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateCaseView {
+    id: String,
+    key: String,
+    title: String,
+    suite_id: String,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicatePairView {
+    testcase: DuplicateCaseView,
+    duplicate: DuplicateCaseView,
+    title_similarity: f64,
+    /// `null` when neither case has steps.
+    steps_similarity: Option<f64>,
+    /// Mean of both similarities, or the title similarity alone without steps.
+    score: f64,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicatesResponse {
+    pairs: Vec<DuplicatePairView>,
+}
+
+/// Pairs of active test cases of the project that look like the same case: trigram
+/// similarity of the titles and of the step texts of the current versions. Candidates are
+/// found by title (`pg_trgm` default threshold 0.3), so cases with unrelated titles are
+/// never paired.
+#[utoipa::path(
+    get,
+    path = "/api/v2/projects/{project_id}/testcases/duplicates",
+    tag = "library",
+    params(("project_id" = String, Path), DuplicatesQuery),
+    responses((status = 200, body = DuplicatesResponse))
+)]
+pub async fn list_duplicates(
+    State(state): State<AppState>,
+    access: ProjectRole,
+    Query(query): Query<DuplicatesQuery>,
+) -> Result<Json<DuplicatesResponse>, ApiError> {
+    let min_score = query.min_score.unwrap_or(DEFAULT_MIN_SCORE);
+    if !(0.0..=1.0).contains(&min_score) {
+        return Err(ApiError::InvalidMinScore);
+    }
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_PAIR_LIMIT)
+        .clamp(1, MAX_PAIR_LIMIT);
+
+    let rows = sqlx::query!(
+        r#"
+        WITH current AS (
+          SELECT DISTINCT ON (tc.id)
+            tc.id, tc.key, tc.title, tc.suite_id,
+            array_to_string(ARRAY(
+              SELECT jsonb_path_query(tv.steps_json, 'strict $.** ? (@.type() == "string")') #>> '{}'
+            ), ' ') AS steps
+          FROM testcases tc
+          JOIN test_suites s ON s.id = tc.suite_id
+          JOIN testcase_versions tv ON tv.testcase_id = tc.id
+          WHERE s.project_id = $1 AND tc.is_archived = FALSE
+          ORDER BY tc.id, tv.version_number DESC
+        ),
+        pairs AS (
+          SELECT
+            a.id AS a_id, a.key AS a_key, a.title AS a_title, a.suite_id AS a_suite_id,
+            b.id AS b_id, b.key AS b_key, b.title AS b_title, b.suite_id AS b_suite_id,
+            similarity(a.title, b.title)::float8 AS title_similarity,
+            CASE
+              WHEN a.steps = '' AND b.steps = '' THEN NULL
+              ELSE similarity(a.steps, b.steps)::float8
+            END AS steps_similarity
+          FROM current a
+          JOIN testcases candidate ON candidate.title % a.title AND candidate.id > a.id
+          JOIN current b ON b.id = candidate.id
+        )
+        SELECT
+          a_id::text AS "a_id!", a_key AS "a_key!", a_title AS "a_title!",
+          a_suite_id::text AS "a_suite_id!",
+          b_id::text AS "b_id!", b_key AS "b_key!", b_title AS "b_title!",
+          b_suite_id::text AS "b_suite_id!",
+          title_similarity AS "title_similarity!",
+          steps_similarity,
+          COALESCE((title_similarity + steps_similarity) / 2, title_similarity) AS "score!"
+        FROM pairs
+        WHERE COALESCE((title_similarity + steps_similarity) / 2, title_similarity) >= $2
+        ORDER BY "score!" DESC, a_id, b_id
+        LIMIT $3
+        "#,
+        access.project_id,
+        min_score,
+        limit,
+    )
+    .fetch_all(state.read_db())
+    .await
+    .map_err(|_| ApiError::DuplicatesReadFailed)?;
+
+    let pairs = rows
+        .into_iter()
+        .map(|r| DuplicatePairView {
+            testcase: DuplicateCaseView {
+                id: r.a_id,
+                key: r.a_key,
+                title: r.a_title,
+                suite_id: r.a_suite_id,
+            },
+            duplicate: DuplicateCaseView {
+                id: r.b_id,
+                key: r.b_key,
+                title: r.b_title,
+                suite_id: r.b_suite_id,
+            },
+            title_similarity: r.title_similarity,
+            steps_similarity: r.steps_similarity,
+            score: r.score,
+        })
+        .collect();
+    Ok(Json(DuplicatesResponse { pairs }))
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeTestcaseRequest {
+    /// The case that stays; the case in the path is archived.
+    into_testcase_id: String,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeTestcaseResponse {
+    testcase_id: String,
+    into_testcase_id: String,
+    /// Items of draft and running runs moved to the current version of the surviving case.
+    moved_run_items: i64,
+    /// Items of finished runs, or of runs that already contain the surviving case; they keep
+    /// the version they were run against.
+    kept_run_items: i64,
+    moved_template_items: i64,
+    moved_requirement_links: i64,
+}
+
+/// Merges a duplicate into another case of the same project: open runs, run templates,
+/// requirement links and tags move to the surviving case, and the duplicate is archived.
+/// Finished runs keep their items, so recorded results stay bound to the version they
+/// were recorded against.
+#[utoipa::path(
+    post,
+    path = "/api/v2/testcases/{testcase_id}/merge",
+    tag = "library",
+    params(("testcase_id" = String, Path)),
+    request_body = MergeTestcaseRequest,
+    responses((status = 200, body = MergeTestcaseResponse))
+)]
+pub async fn merge_testcase(
+    State(state): State<AppState>,
+    Path(testcase_id): Path<String>,
+    AuthUser(actor_id): AuthUser,
+    Json(payload): Json<MergeTestcaseRequest>,
+) -> Result<Json<MergeTestcaseResponse>, ApiError> {
+    let duplicate_uuid = parse_uuid(&testcase_id, ApiError::InvalidTestcaseId)?;
+    let survivor_uuid = parse_uuid(
+        payload.into_testcase_id.trim(),
+        ApiError::InvalidMergeTargetId,
+    )?;
+    if duplicate_uuid == survivor_uuid {
+        return Err(ApiError::MergeIntoSelf);
+    }
+    let project_uuid =
+        testcases::authorize_testcase(&state, duplicate_uuid, &actor_id, Capability::LibraryEdit)
+            .await?;
+    ensure_db_user_exists(&state, &actor_id).await?;
+    let actor_uuid = parse_uuid(&actor_id, ApiError::InvalidUserId)?;
+
+    let failed = |err| db_errors::map(err, ApiError::TestcaseMergeFailed);
+    let mut tx = state.db.begin().await.map_err(failed)?;
+    // Both rows are locked in id order, so merges in opposite directions cannot deadlock.
+    let cases = sqlx::query!(
+        r#"
+        SELECT tc.id, s.project_id AS "project_id!", tc.is_archived
+        FROM testcases tc
+        JOIN test_suites s ON s.id = tc.suite_id
+        WHERE tc.id = ANY($1)
+        ORDER BY tc.id
+        FOR UPDATE OF tc
+        "#,
+        &[duplicate_uuid, survivor_uuid] as &[Uuid],
+    )
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(failed)?;
+    let survivor = cases
+        .iter()
+        .find(|c| c.id == survivor_uuid)
+        .ok_or(ApiError::MergeTargetNotFound)?;
+    if survivor.project_id != project_uuid {
+        return Err(ApiError::MergeTargetOtherProject);
+    }
+    if cases.iter().any(|c| c.is_archived) {
+        return Err(ApiError::MergeArchivedTestcase);
+    }
+    let survivor_version = sqlx::query_scalar!(
+        r#"
+        SELECT id FROM testcase_versions
+        WHERE testcase_id = $1
+        ORDER BY version_number DESC
+        LIMIT 1
+        "#,
+        survivor_uuid,
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(failed)?
+    .ok_or(ApiError::MergeTargetHasNoVersion)?;
+
+    // Open runs are locked like any composition change; finished ones are left alone.
+    let open_runs = sqlx::query_scalar!(
+        r#"
+        SELECT r.id
+        FROM runs r
+        WHERE r.status IN ('draft', 'in_progress')
+          AND EXISTS (
+            SELECT 1 FROM run_items ri
+            JOIN testcase_versions tv ON tv.id = ri.testcase_version_id
+            WHERE ri.run_id = r.id AND tv.testcase_id = $1
+          )
+          AND NOT EXISTS (
+            SELECT 1 FROM run_items ri
+            JOIN testcase_versions tv ON tv.id = ri.testcase_version_id
+            WHERE ri.run_id = r.id AND tv.testcase_id = $2
+          )
+        ORDER BY r.id
+        FOR UPDATE OF r
+        "#,
+        duplicate_uuid,
+        survivor_uuid,
+    )
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(failed)?;
+    let moved_items = sqlx::query_scalar!(
+        r#"
+        UPDATE run_items ri
+        SET testcase_version_id = $3
+        FROM testcase_versions tv
+        WHERE tv.id = ri.testcase_version_id
+          AND tv.testcase_id = $1
+          AND ri.run_id = ANY($2)
+        RETURNING ri.id
+        "#,
+        duplicate_uuid,
+        &open_runs,
+        survivor_version,
+    )
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(failed)?;
+    // Step outcomes belong to the steps of the old version.
+    sqlx::query!(
+        r#"DELETE FROM run_result_steps WHERE run_item_id = ANY($1)"#,
+        &moved_items,
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(failed)?;
+    let kept_items = sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*) AS "count!"
+        FROM run_items ri
+        JOIN testcase_versions tv ON tv.id = ri.testcase_version_id
+        WHERE tv.testcase_id = $1
+        "#,
+        duplicate_uuid,
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(failed)?;
+
+    // A template that already lists the surviving case just drops the duplicate.
+    let moved_template_items = sqlx::query!(
+        r#"
+        UPDATE run_template_items ti
+        SET testcase_version_id = $2
+        FROM testcase_versions tv
+        WHERE tv.id = ti.testcase_version_id
+          AND tv.testcase_id = $1
+          AND NOT EXISTS (
+            SELECT 1 FROM run_template_items other
+            JOIN testcase_versions ov ON ov.id = other.testcase_version_id
+            WHERE other.template_id = ti.template_id AND ov.testcase_id = $3
+          )
+        "#,
+        duplicate_uuid,
+        survivor_version,
+        survivor_uuid,
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(failed)?
+    .rows_affected();
+    sqlx::query!(
+        r#"
+        DELETE FROM run_template_items ti
+        USING testcase_versions tv
+        WHERE tv.id = ti.testcase_version_id AND tv.testcase_id = $1
+        "#,
+        duplicate_uuid,
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(failed)?;
+
+    let moved_requirement_links = sqlx::query!(
+        r#"
+        INSERT INTO requirement_testcases (requirement_id, testcase_id)
+        SELECT requirement_id, $2 FROM requirement_testcases WHERE testcase_id = $1
+        ON CONFLICT DO NOTHING
+        "#,
+        duplicate_uuid,
+        survivor_uuid,
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(failed)?
+    .rows_affected();
+    sqlx::query!(
+        r#"DELETE FROM requirement_testcases WHERE testcase_id = $1"#,
+        duplicate_uuid,
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(failed)?;
+    sqlx::query!(
+        r#"
+        INSERT INTO testcase_tags (testcase_id, tag_id)
+        SELECT $2, tag_id FROM testcase_tags WHERE testcase_id = $1
+        ON CONFLICT DO NOTHING
+        "#,
+        duplicate_uuid,
+        survivor_uuid,
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(failed)?;
+    sqlx::query!(
+        r#"
+        UPDATE testcases
+        SET is_archived = TRUE, updated_at = NOW(), updated_by_user_id = $2
+        WHERE id = $1
+        "#,
+        duplicate_uuid,
+        actor_uuid,
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(failed)?;
+
+    let response = MergeTestcaseResponse {
+        testcase_id: duplicate_uuid.to_string(),
+        into_testcase_id: survivor_uuid.to_string(),
+        moved_run_items: moved_items.len() as i64,
+        kept_run_items: kept_items,
+        moved_template_items: moved_template_items as i64,
+        moved_requirement_links: moved_requirement_links as i64,
+    };
+    audit::record(
+        &mut *tx,
+        AuditEntry {
+            actor_user_id: Some(actor_uuid),
+            action: "update",
+            entity_type: "testcase",
+            entity_id: Some(duplicate_uuid),
+            project_id: Some(project_uuid),
+            run_id: None,
+            before: Some(json!({ "isArchived": false })),
+            after: Some(json!({
+                "isArchived": true,
+                "mergedInto": survivor_uuid,
+                "movedRunItems": response.moved_run_items,
+                "keptRunItems": response.kept_run_items,
+                "movedTemplateItems": response.moved_template_items,
+                "movedRequirementLinks": response.moved_requirement_links,
+            })),
+        },
+    )
+    .await
+    .map_err(failed)?;
+    tx.commit().await.map_err(failed)?;
+
+    Ok(Json(response))
+}
@@ -0,0 +1,421 @@
+use std::collections::HashMap;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::{PgPool, Row};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{
+    audit,
+    authz::{self, AuthUser, ProjectRole},
+    db_errors, ensure_db_user_exists,
+    error::ApiError,
+    parse_uuid,
+    permissions::Capability,
+    AppState,
+};
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct IssueTrackerRequest {
+    tracker_type: String,
+    base_url: String,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct IssueTrackerView {
+    project_id: String,
+    tracker_type: String,
+    base_url: String,
+    updated_at: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct IssueTrackerResponse {
+    tracker: Option<IssueTrackerView>,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LinkDefectRequest {
+    /// Issue key (`QA-123`, `42`) or a full issue URL.
+    reference: String,
+}
+
+#[derive(Serialize, Clone, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DefectLinkView {
+    id: String,
+    issue_key: String,
+    url: Option<String>,
+    created_at: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct DefectLinkResponse {
+    defect: DefectLinkView,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct DeleteDefectResponse {
+    ok: bool,
+}
+
+fn parse_tracker_type(input: &str) -> Result<&'static str, ApiError> {
+    match input {
+        "jira" => Ok("jira"),
+        "github" => Ok("github"),
+        "gitlab" => Ok("gitlab"),
+        _ => Err(ApiError::InvalidIssueTracker),
+    }
+}
+
+/// Builds the browser URL of an issue for the configured tracker.
+fn resolve_issue_url(tracker_type: &str, base_url: &str, issue_key: &str) -> String {
+    let base = base_url.trim_end_matches('/');
+    let number = issue_key.trim_start_matches('#');
+    match tracker_type {
+        "jira" => format!("{base}/browse/{issue_key}"),
+        "github" => format!("{base}/issues/{number}"),
+        _ => format!("{base}/-/issues/{number}"),
+    }
+}
+
+fn map_defect_row(r: &sqlx::postgres::PgRow) -> DefectLinkView {
+    let issue_key = r.get::<String, _>("issue_key");
+    let explicit_url = r.get::<Option<String>, _>("url");
+    let tracker_type = r.get::<Option<String>, _>("tracker_type");
+    let base_url = r.get::<Option<String>, _>("base_url");
+    let url = explicit_url.or_else(|| match (tracker_type, base_url) {
+        (Some(t), Some(b)) => Some(resolve_issue_url(&t, &b, &issue_key)),
+        _ => None,
+    });
+    DefectLinkView {
+        id: r.get::<String, _>("id"),
+        issue_key,
+        url,
+        created_at: r.get::<String, _>("created_at"),
+    }
+}
+
+const DEFECT_SELECT: &str = r#"
+  SELECT
+    d.id::text AS id,
+    ri.id::text AS run_item_id,
+    d.issue_key,
+    d.url,
+    pit.tracker_type,
+    pit.base_url,
+    d.created_at::text AS created_at
+  FROM defects d
+  JOIN run_results rr ON rr.id = d.run_result_id
+  JOIN run_items ri ON ri.id = rr.run_item_id
+  JOIN runs r ON r.id = ri.run_id
+  LEFT JOIN project_issue_trackers pit ON pit.project_id = r.project_id
+"#;
+
+pub async fn fetch_defect(db: &PgPool, defect_id: Uuid) -> Result<DefectLinkView, ApiError> {
+    let sql = format!("{DEFECT_SELECT} WHERE d.id = $1");
+    let row = sqlx::query(&sql)
+        .bind(defect_id)
+        .fetch_one(db)
+        .await
+        .map_err(|_| ApiError::DefectReadFailed)?;
+    Ok(map_defect_row(&row))
+}
+
+/// Defect links of a run grouped by run item id, for `RunItemView`.
+pub async fn fetch_run_defects(
+    db: &PgPool,
+    run_id: Uuid,
+) -> Result<HashMap<String, Vec<DefectLinkView>>, ApiError> {
+    let sql = format!("{DEFECT_SELECT} WHERE r.id = $1 ORDER BY d.created_at ASC");
+    let rows = sqlx::query(&sql)
+        .bind(run_id)
+        .fetch_all(db)
+        .await
+        .map_err(|_| ApiError::RunDefectsReadFailed)?;
+    let mut grouped: HashMap<String, Vec<DefectLinkView>> = HashMap::new();
+    for row in &rows {
+        grouped
+            .entry(row.get::<String, _>("run_item_id"))
+            .or_default()
+            .push(map_defect_row(row));
+    }
+    Ok(grouped)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v2/projects/{project_id}/issue-tracker",
+    tag = "defects",
+    params(("project_id" = String, Path)),
+    responses((status = 200, body = IssueTrackerResponse))
+)]
+pub async fn get_issue_tracker(
+    State(state): State<AppState>,
+    access: ProjectRole,
+) -> Result<Json<IssueTrackerResponse>, ApiError> {
+    let project_uuid = access.project_id;
+    let row = sqlx::query(
+        r#"
+        SELECT project_id::text AS project_id, tracker_type, base_url, updated_at::text AS updated_at
+        FROM project_issue_trackers
+        WHERE project_id = $1
+        "#,
+    )
+    .bind(project_uuid)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|_| ApiError::IssueTrackerReadFailed)?;
+
+    Ok(Json(IssueTrackerResponse {
+        tracker: row.map(|r| IssueTrackerView {
+            project_id: r.get::<String, _>("project_id"),
+            tracker_type: r.get::<String, _>("tracker_type"),
+            base_url: r.get::<String, _>("base_url"),
+            updated_at: r.get::<String, _>("updated_at"),
+        }),
+    }))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v2/projects/{project_id}/issue-tracker",
+    tag = "defects",
+    params(("project_id" = String, Path)),
+    request_body = IssueTrackerRequest,
+    responses((status = 200, body = IssueTrackerResponse))
+)]
+pub async fn put_issue_tracker(
+    State(state): State<AppState>,
+    access: ProjectRole,
+    Json(payload): Json<IssueTrackerRequest>,
+) -> Result<Json<IssueTrackerResponse>, ApiError> {
+    access.require(Capability::ProjectManage)?;
+    let ProjectRole {
+        user_id: actor_id,
+        project_id: project_uuid,
+        ..
+    } = access;
+    let tracker_type = parse_tracker_type(payload.tracker_type.trim())?;
+    let base_url = payload.base_url.trim().trim_end_matches('/').to_string();
+    if !(base_url.starts_with("https://") || base_url.starts_with("http://")) {
+        return Err(ApiError::InvalidTrackerUrl);
+    }
+    ensure_db_user_exists(&state, &actor_id).await?;
+    let actor_uuid = parse_uuid(&actor_id, ApiError::InvalidUserId)?;
+
+    let row = sqlx::query(
+        r#"
+        INSERT INTO project_issue_trackers (project_id, tracker_type, base_url, updated_by_user_id)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (project_id)
+        DO UPDATE SET
+          tracker_type = EXCLUDED.tracker_type,
+          base_url = EXCLUDED.base_url,
+          updated_by_user_id = EXCLUDED.updated_by_user_id
+        RETURNING project_id::text AS project_id, tracker_type, base_url, updated_at::text AS updated_at
+        "#,
+    )
+    .bind(project_uuid)
+    .bind(tracker_type)
+    .bind(&base_url)
+    .bind(actor_uuid)
+    .fetch_one(&state.db)
+    .await
+    .map_err(|_| ApiError::IssueTrackerRejected)?;
+
+    Ok(Json(IssueTrackerResponse {
+        tracker: Some(IssueTrackerView {
+            project_id: row.get::<String, _>("project_id"),
+            tracker_type: row.get::<String, _>("tracker_type"),
+            base_url: row.get::<String, _>("base_url"),
+            updated_at: row.get::<String, _>("updated_at"),
+        }),
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v2/runs/{run_id}/items/{run_item_id}/defects",
+    tag = "defects",
+    params(("run_id" = String, Path), ("run_item_id" = String, Path)),
+    request_body = LinkDefectRequest,
+    responses((status = 201, body = DefectLinkResponse))
+)]
+pub async fn link_defect(
+    State(state): State<AppState>,
+    Path((run_id, run_item_id)): Path<(String, String)>,
+    AuthUser(actor_id): AuthUser,
+    Json(payload): Json<LinkDefectRequest>,
+) -> Result<(StatusCode, Json<DefectLinkResponse>), ApiError> {
+    let run_uuid = parse_uuid(&run_id, ApiError::InvalidRunId)?;
+    let run_item_uuid = parse_uuid(&run_item_id, ApiError::InvalidRunItemId)?;
+    let reference = payload.reference.trim();
+    if reference.is_empty() || reference.chars().count() > 500 {
+        return Err(ApiError::InvalidDefectUrl);
+    }
+    let (issue_key, url) = if reference.starts_with("http://") || reference.starts_with("https://")
+    {
+        let key = reference
+            .trim_end_matches('/')
+            .rsplit('/')
+            .next()
+            .unwrap_or_default()
+            .to_string();
+        (key, Some(reference.to_string()))
+    } else {
+        (reference.to_string(), None)
+    };
+    if issue_key.is_empty() || issue_key.chars().count() > 200 {
+        return Err(ApiError::InvalidDefectKey);
+    }
+    authz::require_run_capability(&state, run_uuid, &actor_id, Capability::ResultEdit).await?;
+    ensure_db_user_exists(&state, &actor_id).await?;
+    let actor_uuid = parse_uuid(&actor_id, ApiError::InvalidUserId)?;
+
+    let context = sqlx::query(
+        r#"
+        SELECT rr.id AS run_result_id, rr.status::text AS status, r.project_id AS project_id
+        FROM run_items ri
+        JOIN runs r ON r.id = ri.run_id
+        JOIN run_results rr ON rr.run_item_id = ri.id
+        WHERE r.id = $1 AND ri.id = $2
+        "#,
+    )
+    .bind(run_uuid)
+    .bind(run_item_uuid)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|_| ApiError::ResultReadFailed)?
+    .ok_or(ApiError::ResultNotFound)?;
+    if context.get::<String, _>("status") != "fail" {
+        return Err(ApiError::DefectRequiresFail);
+    }
+    let run_result_id = context.get::<Uuid, _>("run_result_id");
+    let project_uuid = context.get::<Uuid, _>("project_id");
+
+    let mut tx = state
+        .db
+        .begin()
+        .await
+        .map_err(|_| ApiError::DefectLinkFailed)?;
+    let defect_id: Uuid = sqlx::query_scalar(
+        r#"
+        INSERT INTO defects (run_result_id, issue_key, url, created_by_user_id)
+        VALUES ($1, $2, $3, $4)
+        RETURNING id
+        "#,
+    )
+    .bind(run_result_id)
+    .bind(&issue_key)
+    .bind(&url)
+    .bind(actor_uuid)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|err| db_errors::map(err, ApiError::DefectLinkFailed))?;
+    audit::record(
+        &mut *tx,
+        audit::AuditEntry {
+            actor_user_id: Some(actor_uuid),
+            action: "create",
+            entity_type: "defect",
+            entity_id: Some(defect_id),
+            project_id: Some(project_uuid),
+            run_id: Some(run_uuid),
+            before: None,
+            after: Some(json!({ "runItemId": run_item_uuid, "issueKey": issue_key, "url": url })),
+        },
+    )
+    .await
+    .map_err(|_| ApiError::AuditWriteFailed)?;
+    tx.commit().await.map_err(|_| ApiError::DefectLinkFailed)?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(DefectLinkResponse {
+            defect: fetch_defect(&state.db, defect_id).await?,
+        }),
+    ))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v2/defects/{defect_id}",
+    tag = "defects",
+    params(("defect_id" = String, Path)),
+    responses((status = 200, body = DeleteDefectResponse))
+)]
+pub async fn unlink_defect(
+    State(state): State<AppState>,
+    Path(defect_id): Path<String>,
+    AuthUser(actor_id): AuthUser,
+) -> Result<Json<DeleteDefectResponse>, ApiError> {
+    let defect_uuid = parse_uuid(&defect_id, ApiError::InvalidDefectId)?;
+
+    let context = sqlx::query(
+        r#"
+        SELECT r.id AS run_id, r.project_id AS project_id, d.issue_key
+        FROM defects d
+        JOIN run_results rr ON rr.id = d.run_result_id
+        JOIN run_items ri ON ri.id = rr.run_item_id
+        JOIN runs r ON r.id = ri.run_id
+        WHERE d.id = $1
+        "#,
+    )
+    .bind(defect_uuid)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|_| ApiError::DefectReadFailed)?
+    .ok_or(ApiError::DefectNotFound)?;
+    let run_uuid = context.get::<Uuid, _>("run_id");
+    let project_uuid = context.get::<Uuid, _>("project_id");
+    authz::require_capability(
+        &state,
+        &project_uuid.to_string(),
+        &actor_id,
+        Capability::ResultEdit,
+    )
+    .await?;
+    ensure_db_user_exists(&state, &actor_id).await?;
+    let actor_uuid = parse_uuid(&actor_id, ApiError::InvalidUserId)?;
+
+    let mut tx = state
+        .db
+        .begin()
+        .await
+        .map_err(|_| ApiError::DefectUnlinkFailed)?;
+    sqlx::query(r#"DELETE FROM defects WHERE id = $1"#)
+        .bind(defect_uuid)
+        .execute(&mut *tx)
+        .await
+        .map_err(|_| ApiError::DefectUnlinkFailed)?;
+    audit::record(
+        &mut *tx,
+        audit::AuditEntry {
+            actor_user_id: Some(actor_uuid),
+            action: "delete",
+            entity_type: "defect",
+            entity_id: Some(defect_uuid),
+            project_id: Some(project_uuid),
+            run_id: Some(run_uuid),
+            before: Some(json!({ "issueKey": context.get::<String, _>("issue_key") })),
+            after: None,
+        },
+    )
+    .await
+    .map_err(|_| ApiError::AuditWriteFailed)?;
+    tx.commit()
+        .await
+        .map_err(|_| ApiError::DefectUnlinkFailed)?;
+
+    Ok(Json(DeleteDefectResponse { ok: true }))
+}
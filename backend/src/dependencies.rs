@@ -0,0 +1,185 @@
+use std::collections::{HashMap, HashSet};
+
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{
+    audit,
+    authz::{self, AuthUser},
+    db_errors, ensure_db_user_exists,
+    error::ApiError,
+    parse_uuid,
+    permissions::Capability,
+    run_repo::{self, LockedRun, RunLock},
+    AppState,
+};
+
+const MAX_DEPENDENCIES: usize = 100;
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SetDependenciesRequest {
+    /// Items of the same run that must pass (`ok`) before this one can be executed; replaces
+    /// the current list, `[]` removes all dependencies.
+    depends_on: Vec<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RunItemDependenciesResponse {
+    run_item_id: String,
+    depends_on: Vec<String>,
+    /// Dependencies that have not passed yet.
+    blocked_by: Vec<String>,
+}
+
+/// Replaces the prerequisites of a run item. While any of them has no `ok` result, the item
+/// cannot be recorded as `ok` or `fail`.
+#[utoipa::path(
+    put,
+    path = "/api/v2/runs/{run_id}/items/{run_item_id}/dependencies",
+    tag = "runs",
+    params(("run_id" = String, Path), ("run_item_id" = String, Path)),
+    request_body = SetDependenciesRequest,
+    responses((status = 200, body = RunItemDependenciesResponse))
+)]
+pub async fn set_item_dependencies(
+    State(state): State<AppState>,
+    Path((run_id, run_item_id)): Path<(String, String)>,
+    AuthUser(actor_id): AuthUser,
+    Json(payload): Json<SetDependenciesRequest>,
+) -> Result<Json<RunItemDependenciesResponse>, ApiError> {
+    let run_uuid = parse_uuid(&run_id, ApiError::InvalidRunId)?;
+    let run_item_uuid = parse_uuid(&run_item_id, ApiError::InvalidRunItemId)?;
+    let mut depends_on: Vec<Uuid> = Vec::with_capacity(payload.depends_on.len());
+    for id in &payload.depends_on {
+        let id = parse_uuid(id.trim(), ApiError::InvalidRunItemId)?;
+        if id == run_item_uuid {
+            return Err(ApiError::RunItemSelfDependency);
+        }
+        if !depends_on.contains(&id) {
+            depends_on.push(id);
+        }
+    }
+    if depends_on.len() > MAX_DEPENDENCIES {
+        return Err(ApiError::TooManyRunItemDependencies);
+    }
+    authz::require_run_capability(&state, run_uuid, &actor_id, Capability::RunCompose).await?;
+    ensure_db_user_exists(&state, &actor_id).await?;
+    let actor_uuid = parse_uuid(&actor_id, ApiError::InvalidUserId)?;
+
+    let mut run = LockedRun::begin(
+        &state.db,
+        run_uuid,
+        RunLock::Exclusive,
+        ApiError::RunItemDependenciesSaveFailed,
+    )
+    .await?;
+    run.ensure_unlocked(ApiError::RunLockedItems)?;
+    let project_uuid = run.project_id;
+
+    let item_ids = sqlx::query_scalar!(r#"SELECT id FROM run_items WHERE run_id = $1"#, run_uuid)
+        .fetch_all(run.conn())
+        .await
+        .map_err(|_| ApiError::RunItemDependenciesReadFailed)?;
+    if !item_ids.contains(&run_item_uuid) {
+        return Err(ApiError::RunItemNotFound);
+    }
+    if depends_on.iter().any(|id| !item_ids.contains(id)) {
+        return Err(ApiError::ForeignRunItems);
+    }
+
+    let edges = sqlx::query!(
+        r#"
+        SELECT run_item_id, depends_on_run_item_id
+        FROM run_item_dependencies
+        WHERE run_id = $1
+        "#,
+        run_uuid,
+    )
+    .fetch_all(run.conn())
+    .await
+    .map_err(|_| ApiError::RunItemDependenciesReadFailed)?;
+    let mut previous: Vec<String> = Vec::new();
+    let mut graph: HashMap<Uuid, Vec<Uuid>> = HashMap::new();
+    for edge in &edges {
+        let (from, to) = (edge.run_item_id, edge.depends_on_run_item_id);
+        if from == run_item_uuid {
+            previous.push(to.to_string());
+        } else {
+            graph.entry(from).or_default().push(to);
+        }
+    }
+    if reaches(&graph, &depends_on, run_item_uuid) {
+        return Err(ApiError::RunItemDependencyCycle);
+    }
+
+    let save_failed = |err| db_errors::map(err, ApiError::RunItemDependenciesSaveFailed);
+    sqlx::query!(
+        r#"DELETE FROM run_item_dependencies WHERE run_item_id = $1"#,
+        run_item_uuid,
+    )
+    .execute(run.conn())
+    .await
+    .map_err(save_failed)?;
+    sqlx::query!(
+        r#"
+        INSERT INTO run_item_dependencies (
+          run_id, run_item_id, depends_on_run_item_id, created_by_user_id
+        )
+        SELECT $1, $2, UNNEST($3::uuid[]), $4
+        "#,
+        run_uuid,
+        run_item_uuid,
+        &depends_on,
+        actor_uuid,
+    )
+    .execute(run.conn())
+    .await
+    .map_err(save_failed)?;
+    let depends_on: Vec<String> = depends_on.iter().map(Uuid::to_string).collect();
+    audit::record(
+        run.conn(),
+        audit::AuditEntry {
+            actor_user_id: Some(actor_uuid),
+            action: "update",
+            entity_type: "run_item",
+            entity_id: Some(run_item_uuid),
+            project_id: Some(project_uuid),
+            run_id: Some(run_uuid),
+            before: Some(json!({ "dependsOn": previous })),
+            after: Some(json!({ "dependsOn": &depends_on })),
+        },
+    )
+    .await
+    .map_err(save_failed)?;
+    let blocked_by = run_repo::unmet_dependencies(&mut run, run_item_uuid).await?;
+    run.commit(ApiError::RunItemDependenciesSaveFailed).await?;
+
+    Ok(Json(RunItemDependenciesResponse {
+        run_item_id: run_item_uuid.to_string(),
+        depends_on,
+        blocked_by,
+    }))
+}
+
+/// Whether `target` is reachable from any of `start` along the dependency edges.
+fn reaches(graph: &HashMap<Uuid, Vec<Uuid>>, start: &[Uuid], target: Uuid) -> bool {
+    let mut stack = start.to_vec();
+    let mut seen = HashSet::new();
+    while let Some(node) = stack.pop() {
+        if node == target {
+            return true;
+        }
+        if seen.insert(node) {
+            stack.extend(graph.get(&node).into_iter().flatten());
+        }
+    }
+    false
+}
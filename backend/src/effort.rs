@@ -0,0 +1,309 @@
+use std::collections::HashMap;
+
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::Row;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{authz::ProjectRole, error::ApiError, parse_uuid, read_users, AppState};
+
+const DEFAULT_DAYS: i64 = 30;
+const MAX_DAYS: i64 = 365;
+/// A gap between two results of the same tester longer than this is a break, not work on
+/// the later item, so it is not counted as an estimate.
+const IDLE_GAP_SECONDS: i64 = 30 * 60;
+
+#[derive(Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct EffortQuery {
+    /// Items first executed within this many days ending now, 1-365 (default 30).
+    days: Option<i64>,
+    /// Only this run.
+    run_id: Option<String>,
+}
+
+/// Effort of a group of executed items. `trackedSeconds` sums the `elapsedSeconds` reported
+/// with results; items without it are estimated from the gap since the tester's previous
+/// result in the same run (or the run start), ignoring gaps over 30 minutes.
+#[derive(Serialize, Default, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct EffortTotals {
+    executed_count: i64,
+    tracked_count: i64,
+    tracked_seconds: i64,
+    estimated_count: i64,
+    estimated_seconds: i64,
+    /// `trackedSeconds + estimatedSeconds`.
+    total_seconds: i64,
+    /// `totalSeconds` per item that has tracked or estimated time; `null` when none has.
+    average_seconds: Option<f64>,
+}
+
+impl EffortTotals {
+    fn add(&mut self, item: &TimedItem) {
+        self.executed_count += 1;
+        if let Some(seconds) = item.tracked {
+            self.tracked_count += 1;
+            self.tracked_seconds += seconds;
+        } else if let Some(seconds) = item.estimated {
+            self.estimated_count += 1;
+            self.estimated_seconds += seconds;
+        }
+    }
+
+    fn finish(mut self) -> Self {
+        self.total_seconds = self.tracked_seconds + self.estimated_seconds;
+        let timed = self.tracked_count + self.estimated_count;
+        self.average_seconds =
+            (timed > 0).then(|| (self.total_seconds as f64 * 10.0 / timed as f64).round() / 10.0);
+        self
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RunEffort {
+    run_id: String,
+    title: String,
+    #[serde(flatten)]
+    totals: EffortTotals,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TesterEffort {
+    /// The user who recorded the results.
+    user_id: Option<String>,
+    name: Option<String>,
+    #[serde(flatten)]
+    totals: EffortTotals,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TestcaseEffort {
+    testcase_id: String,
+    key: String,
+    title: String,
+    #[serde(flatten)]
+    totals: EffortTotals,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct EffortResponse {
+    project_id: String,
+    days: i64,
+    run_id: Option<String>,
+    totals: EffortTotals,
+    /// Most recently executed first.
+    by_run: Vec<RunEffort>,
+    /// Largest `totalSeconds` first.
+    by_tester: Vec<TesterEffort>,
+    /// Largest `averageSeconds` first.
+    by_testcase: Vec<TestcaseEffort>,
+}
+
+struct TimedItem {
+    run_id: String,
+    run_title: String,
+    tester_id: Option<String>,
+    testcase_id: String,
+    testcase_key: String,
+    testcase_title: String,
+    tracked: Option<i64>,
+    estimated: Option<i64>,
+}
+
+/// Execution effort of the project's runs, by run, by tester and by test case, for estimating
+/// future runs.
+#[utoipa::path(
+    get,
+    path = "/api/v2/projects/{project_id}/effort",
+    tag = "analytics",
+    params(("project_id" = String, Path), EffortQuery),
+    responses((status = 200, body = EffortResponse))
+)]
+pub async fn project_effort(
+    State(state): State<AppState>,
+    access: ProjectRole,
+    Query(query): Query<EffortQuery>,
+) -> Result<Json<EffortResponse>, ApiError> {
+    let days = query.days.unwrap_or(DEFAULT_DAYS);
+    if !(1..=MAX_DAYS).contains(&days) {
+        return Err(ApiError::InvalidAnalyticsPeriod);
+    }
+    let run_uuid = query
+        .run_id
+        .as_deref()
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .map(|id| parse_uuid(id, ApiError::InvalidRunId))
+        .transpose()?;
+
+    let rows = sqlx::query(
+        r#"
+        WITH executed AS (
+          SELECT
+            r.id AS run_id,
+            r.title AS run_title,
+            r.started_at,
+            rr.updated_by_user_id AS tester_id,
+            rr.executed_at,
+            rr.elapsed_seconds,
+            tc.id AS testcase_id,
+            tc.key AS testcase_key,
+            tc.title AS testcase_title,
+            LAG(rr.executed_at) OVER (
+              PARTITION BY r.id, rr.updated_by_user_id ORDER BY rr.executed_at
+            ) AS previous_executed_at
+          FROM run_results rr
+          JOIN run_items ri ON ri.id = rr.run_item_id
+          JOIN runs r ON r.id = ri.run_id
+          JOIN testcase_versions tv ON tv.id = ri.testcase_version_id
+          JOIN testcases tc ON tc.id = tv.testcase_id
+          WHERE r.project_id = $1
+            AND rr.executed_at IS NOT NULL
+            AND rr.executed_at >= NOW() - make_interval(days => $2)
+            AND ($3::uuid IS NULL OR r.id = $3)
+        )
+        SELECT
+          run_id::text AS run_id,
+          run_title,
+          tester_id::text AS tester_id,
+          testcase_id::text AS testcase_id,
+          testcase_key,
+          testcase_title,
+          elapsed_seconds::bigint AS tracked,
+          EXTRACT(EPOCH FROM executed_at - COALESCE(previous_executed_at, started_at))::bigint
+            AS gap
+        FROM executed
+        ORDER BY executed_at DESC
+        "#,
+    )
+    .bind(access.project_id)
+    .bind(days as i32)
+    .bind(run_uuid)
+    .fetch_all(state.read_db())
+    .await
+    .map_err(|_| ApiError::AnalyticsFailed)?;
+    let items: Vec<TimedItem> = rows
+        .into_iter()
+        .map(|r| {
+            let tracked = r.get::<Option<i64>, _>("tracked");
+            TimedItem {
+                run_id: r.get("run_id"),
+                run_title: r.get("run_title"),
+                tester_id: r.get("tester_id"),
+                testcase_id: r.get("testcase_id"),
+                testcase_key: r.get("testcase_key"),
+                testcase_title: r.get("testcase_title"),
+                tracked,
+                estimated: r
+                    .get::<Option<i64>, _>("gap")
+                    .filter(|gap| tracked.is_none() && (0..=IDLE_GAP_SECONDS).contains(gap)),
+            }
+        })
+        .collect();
+
+    let names: HashMap<String, String> = {
+        let _guard = state.file_lock.lock().await;
+        read_users(&state.users_file)
+            .await
+            .map_err(|_| ApiError::AnalyticsFailed)?
+            .into_iter()
+            .map(|u| (u.id, u.name))
+            .collect()
+    };
+
+    let mut totals = EffortTotals::default();
+    let mut runs: Vec<RunEffort> = Vec::new();
+    let mut testers: Vec<TesterEffort> = Vec::new();
+    let mut testcases: Vec<TestcaseEffort> = Vec::new();
+    let mut run_index = HashMap::new();
+    let mut tester_index = HashMap::new();
+    let mut testcase_index = HashMap::new();
+    // Items come most recent first, so runs keep that order.
+    for item in &items {
+        totals.add(item);
+        let run = *run_index.entry(item.run_id.clone()).or_insert_with(|| {
+            runs.push(RunEffort {
+                run_id: item.run_id.clone(),
+                title: item.run_title.clone(),
+                totals: EffortTotals::default(),
+            });
+            runs.len() - 1
+        });
+        runs[run].totals.add(item);
+        let tester = *tester_index
+            .entry(item.tester_id.clone())
+            .or_insert_with(|| {
+                testers.push(TesterEffort {
+                    user_id: item.tester_id.clone(),
+                    name: item
+                        .tester_id
+                        .as_ref()
+                        .and_then(|id| names.get(id).cloned()),
+                    totals: EffortTotals::default(),
+                });
+                testers.len() - 1
+            });
+        testers[tester].totals.add(item);
+        let testcase = *testcase_index
+            .entry(item.testcase_id.clone())
+            .or_insert_with(|| {
+                testcases.push(TestcaseEffort {
+                    testcase_id: item.testcase_id.clone(),
+                    key: item.testcase_key.clone(),
+                    title: item.testcase_title.clone(),
+                    totals: EffortTotals::default(),
+                });
+                testcases.len() - 1
+            });
+        testcases[testcase].totals.add(item);
+    }
+
+    let runs = runs
+        .into_iter()
+        .map(|r| RunEffort {
+            totals: r.totals.finish(),
+            ..r
+        })
+        .collect();
+    let mut testers: Vec<TesterEffort> = testers
+        .into_iter()
+        .map(|t| TesterEffort {
+            totals: t.totals.finish(),
+            ..t
+        })
+        .collect();
+    testers.sort_by_key(|t| std::cmp::Reverse(t.totals.total_seconds));
+    let mut testcases: Vec<TestcaseEffort> = testcases
+        .into_iter()
+        .map(|t| TestcaseEffort {
+            totals: t.totals.finish(),
+            ..t
+        })
+        .collect();
+    testcases.sort_by(|a, b| {
+        b.totals
+            .average_seconds
+            .partial_cmp(&a.totals.average_seconds)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.key.cmp(&b.key))
+    });
+
+    Ok(Json(EffortResponse {
+        project_id: access.project_id.to_string(),
+        days,
+        run_id: run_uuid.map(|id| id.to_string()),
+        totals: totals.finish(),
+        by_run: runs,
+        by_tester: testers,
+        by_testcase: testcases,
+    }))
+}
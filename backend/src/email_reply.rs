@@ -0,0 +1,481 @@
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use tracing::warn;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::{
+    audit::{self, AuditEntry},
+    authz,
+    error::{ApiError, Lang},
+    etag::{Precondition, VersionError},
+    jobs, notifications, read_users, record_run_result, AppState, UpdateRunResultRequest,
+};
+
+/// Appended to emails that have a reply address.
+pub const REPLY_HINT: &str = "\n\nЧтобы записать результат, ответьте на это письмо: первая \
+строка — статус (OK, FAIL, BLOCKED, SKIPPED, RETEST), после двоеточия — комментарий, например \
+«FAIL: кнопка не отображается».";
+
+/// A reply address stays valid this long after the last email that carried it.
+const TOKEN_TTL_DAYS: i32 = 30;
+/// Processed replies are kept this long for troubleshooting.
+const REPLY_RETENTION_DAYS: i32 = 30;
+const MAX_BODY_CHARS: usize = 20_000;
+const MAX_COMMENT_CHARS: usize = 4000;
+
+/// `reply+<token>@INBOUND_EMAIL_DOMAIN` for the user and run item, or `None` when inbound
+/// email is not configured or the user can no longer record results (deactivated, or a
+/// service account). The token is reused for the same user and item.
+pub async fn reply_address(
+    state: &AppState,
+    user_id: Uuid,
+    run_item_id: Uuid,
+) -> Result<Option<String>, sqlx::Error> {
+    let Some(config) = state.inbound_email.as_deref() else {
+        return Ok(None);
+    };
+    if !authz::is_active_person(&state.db, user_id).await? {
+        return Ok(None);
+    }
+    let token = sqlx::query_scalar!(
+        r#"
+        INSERT INTO email_reply_tokens (user_id, run_item_id, expires_at)
+        VALUES ($1, $2, NOW() + make_interval(days => $3))
+        ON CONFLICT (user_id, run_item_id) DO UPDATE SET expires_at = EXCLUDED.expires_at
+        RETURNING token
+        "#,
+        user_id,
+        run_item_id,
+        TOKEN_TTL_DAYS,
+    )
+    .fetch_one(&state.db)
+    .await?;
+    Ok(Some(format!("reply+{token}@{}", config.domain)))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct InboundEmailQuery {
+    /// `INBOUND_EMAIL_SECRET`.
+    secret: Option<String>,
+}
+
+/// A received email as posted by the provider. Field names of Postmark and Mailgun are
+/// accepted as aliases.
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct InboundEmailRequest {
+    #[serde(alias = "From", alias = "sender")]
+    from: String,
+    /// Recipients, comma-separated; one of them is the reply address.
+    #[serde(alias = "To", alias = "recipient")]
+    to: String,
+    /// Plain-text body; quoted text below the reply is ignored.
+    #[serde(
+        default,
+        alias = "TextBody",
+        alias = "stripped-text",
+        alias = "body-plain"
+    )]
+    text: String,
+    /// Used to ignore repeated deliveries; derived from the content when absent.
+    #[serde(default, alias = "MessageID", alias = "Message-Id")]
+    message_id: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct InboundEmailResponse {
+    /// `false` when the email has no reply address of this server or was already received.
+    accepted: bool,
+}
+
+/// Webhook of the inbound email provider. The reply is stored and handed to an
+/// `email_reply` job, which checks the sender and records the result.
+#[utoipa::path(
+    post,
+    path = "/api/inbound-email",
+    tag = "results",
+    params(InboundEmailQuery),
+    request_body = InboundEmailRequest,
+    responses((status = 200, body = InboundEmailResponse))
+)]
+pub async fn receive_inbound_email(
+    State(state): State<AppState>,
+    Query(query): Query<InboundEmailQuery>,
+    Json(payload): Json<InboundEmailRequest>,
+) -> Result<Json<InboundEmailResponse>, ApiError> {
+    let config = state
+        .inbound_email
+        .as_deref()
+        .ok_or(ApiError::InboundEmailDisabled)?;
+    // Comparing digests keeps the comparison time independent of the secret's content.
+    let given = Sha256::digest(query.secret.unwrap_or_default().as_bytes());
+    if given != Sha256::digest(config.secret.as_bytes()) {
+        return Err(ApiError::InvalidInboundEmailSecret);
+    }
+    let not_accepted = Ok(Json(InboundEmailResponse { accepted: false }));
+    let Some(token) = reply_token(&payload.to, &config.domain) else {
+        return not_accepted;
+    };
+    let Some(sender) = mailbox_address(&payload.from) else {
+        return not_accepted;
+    };
+    let message_id = payload
+        .message_id
+        .map(|id| id.trim().to_string())
+        .filter(|id| !id.is_empty())
+        .unwrap_or_else(|| {
+            let digest = Sha256::digest(format!(
+                "{}\n{}\n{}",
+                payload.from, payload.to, payload.text
+            ));
+            format!("sha256:{}", hex::encode(digest))
+        });
+    let body: String = payload.text.chars().take(MAX_BODY_CHARS).collect();
+
+    let failed = |_| ApiError::InboundEmailSaveFailed;
+    let mut tx = state.db.begin().await.map_err(failed)?;
+    let reply_id = sqlx::query_scalar!(
+        r#"
+        INSERT INTO email_replies (message_id, token, sender, body)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (message_id) DO NOTHING
+        RETURNING id
+        "#,
+        message_id,
+        token,
+        sender,
+        body,
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(failed)?;
+    let Some(reply_id) = reply_id else {
+        return not_accepted;
+    };
+    jobs::enqueue(
+        &mut *tx,
+        jobs::NewJob {
+            kind: jobs::JobKind::EmailReply,
+            project_id: None,
+            created_by_user_id: None,
+            payload: json!({ "replyId": reply_id }),
+        },
+    )
+    .await
+    .map_err(failed)?;
+    tx.commit().await.map_err(failed)?;
+    state.jobs.wake();
+
+    Ok(Json(InboundEmailResponse { accepted: true }))
+}
+
+/// The token of the first recipient of the form `reply+<token>@domain`.
+fn reply_token(recipients: &str, domain: &str) -> Option<Uuid> {
+    recipients.split([',', ';']).find_map(|recipient| {
+        let address = mailbox_address(recipient)?;
+        let (local, host) = address.rsplit_once('@')?;
+        if host != domain {
+            return None;
+        }
+        Uuid::parse_str(local.strip_prefix("reply+")?).ok()
+    })
+}
+
+/// The lower-cased address of `Name <user@host>` or `user@host`.
+fn mailbox_address(mailbox: &str) -> Option<String> {
+    let mailbox = mailbox.trim();
+    let address = match (mailbox.rfind('<'), mailbox.rfind('>')) {
+        (Some(start), Some(end)) if start < end => &mailbox[start + 1..end],
+        _ => mailbox,
+    };
+    let address = address.trim().to_lowercase();
+    (address.contains('@') && !address.contains(char::is_whitespace)).then_some(address)
+}
+
+/// Status and comment from the reply text: the first non-empty line is `STATUS: comment`
+/// (or `STATUS comment`), following lines are added to the comment up to the quoted
+/// original or a signature.
+fn parse_reply(text: &str) -> Option<(&'static str, String)> {
+    let mut lines = text.lines().map(str::trim_end);
+    let first = lines
+        .by_ref()
+        .map(str::trim)
+        .find(|line| !line.is_empty())?;
+    let word_end = first
+        .find(|c: char| !c.is_alphabetic())
+        .unwrap_or(first.len());
+    let status = match first[..word_end].to_lowercase().as_str() {
+        "ok" | "ок" | "pass" | "passed" => "ok",
+        "fail" | "failed" => "fail",
+        "blocked" => "blocked",
+        "skip" | "skipped" => "skipped",
+        "retest" => "retest",
+        "na" => "na",
+        _ => return None,
+    };
+    let mut comment = vec![first[word_end..]
+        .trim_start_matches(|c: char| c == ':' || c == '-' || c == '—' || c.is_whitespace())
+        .to_string()];
+    for line in lines {
+        let trimmed = line.trim();
+        if trimmed.starts_with('>')
+            || line == "--"
+            || line == "-- "
+            || trimmed.ends_with("wrote:")
+            || trimmed.ends_with("пишет:")
+        {
+            break;
+        }
+        comment.push(line.to_string());
+    }
+    let comment: String = comment
+        .join("\n")
+        .trim()
+        .chars()
+        .take(MAX_COMMENT_CHARS)
+        .collect();
+    Some((status, comment))
+}
+
+/// Why a reply was not applied; stored in `email_replies.error_code`.
+enum Rejection {
+    UnknownToken,
+    TokenExpired,
+    SenderMismatch,
+    /// The token's owner was deactivated or is a service account.
+    UserInactive,
+    /// The sender is the token's owner; they are told what went wrong.
+    Unrecognized {
+        email: String,
+    },
+    Api {
+        email: String,
+        error: ApiError,
+    },
+}
+
+impl Rejection {
+    fn code(&self) -> &'static str {
+        match self {
+            Rejection::UnknownToken => "unknown_token",
+            Rejection::TokenExpired => "token_expired",
+            Rejection::SenderMismatch => "sender_mismatch",
+            Rejection::UserInactive => "user_deactivated",
+            Rejection::Unrecognized { .. } => "unrecognized_reply",
+            Rejection::Api { error, .. } => error.code(),
+        }
+    }
+}
+
+/// Job handler of [`receive_inbound_email`]: verifies that the sender owns the reply token
+/// and records the result as that user, with the same permission checks as
+/// `PATCH .../result`.
+pub async fn run_reply_job(state: &AppState, payload: Value) -> anyhow::Result<Value> {
+    let reply_id: Uuid = serde_json::from_value(payload["replyId"].clone())?;
+    let Some(reply) = sqlx::query!(
+        r#"
+        SELECT
+          er.message_id,
+          er.sender,
+          er.body,
+          er.status,
+          t.user_id AS "user_id?",
+          t.run_item_id AS "run_item_id?",
+          t.expires_at > NOW() AS "token_valid?",
+          ri.run_id AS "run_id?",
+          r.project_id AS "project_id?"
+        FROM email_replies er
+        LEFT JOIN email_reply_tokens t ON t.token = er.token
+        LEFT JOIN run_items ri ON ri.id = t.run_item_id
+        LEFT JOIN runs r ON r.id = ri.run_id
+        WHERE er.id = $1
+        "#,
+        reply_id,
+    )
+    .fetch_optional(&state.db)
+    .await?
+    else {
+        return Ok(Value::Null);
+    };
+    if reply.status != "received" {
+        return Ok(json!({ "status": reply.status }));
+    }
+
+    let outcome = match (
+        reply.user_id,
+        reply.run_item_id,
+        reply.run_id,
+        reply.project_id,
+    ) {
+        (Some(user_id), Some(run_item_id), Some(run_id), Some(project_id)) => {
+            if reply.token_valid != Some(true) {
+                Err(Rejection::TokenExpired)
+            } else {
+                apply(
+                    state,
+                    &reply.message_id,
+                    &reply.sender,
+                    &reply.body,
+                    user_id,
+                    run_item_id,
+                    run_id,
+                    project_id,
+                )
+                .await?
+            }
+        }
+        _ => Err(Rejection::UnknownToken),
+    };
+
+    let error_code = outcome.as_ref().err().map(Rejection::code);
+    let status = if error_code.is_some() {
+        "rejected"
+    } else {
+        "applied"
+    };
+    sqlx::query!(
+        r#"
+        UPDATE email_replies
+        SET status = $2, error_code = $3, run_item_id = $4, user_id = $5, processed_at = NOW()
+        WHERE id = $1
+        "#,
+        reply_id,
+        status,
+        error_code,
+        reply.run_item_id,
+        reply.user_id,
+    )
+    .execute(&state.db)
+    .await?;
+
+    match outcome {
+        Err(Rejection::Unrecognized { email }) => notify_rejected(
+            state,
+            email,
+            "Первая строка ответа должна начинаться со статуса: OK, FAIL, BLOCKED, SKIPPED \
+             или RETEST."
+                .to_string(),
+        ),
+        Err(Rejection::Api { email, error }) => {
+            notify_rejected(state, email, error.message(Lang::Ru).to_string())
+        }
+        _ => {}
+    }
+    Ok(json!({ "status": status, "errorCode": error_code }))
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn apply(
+    state: &AppState,
+    message_id: &str,
+    sender: &str,
+    body: &str,
+    user_id: Uuid,
+    run_item_id: Uuid,
+    run_id: Uuid,
+    project_id: Uuid,
+) -> anyhow::Result<Result<(), Rejection>> {
+    let user_id_text = user_id.to_string();
+    let email = {
+        let _guard = state.file_lock.lock().await;
+        read_users(&state.users_file)
+            .await?
+            .into_iter()
+            .find(|u| u.id == user_id_text)
+            .map(|u| u.email.trim().to_lowercase())
+    };
+    let Some(email) = email.filter(|email| email == sender) else {
+        return Ok(Err(Rejection::SenderMismatch));
+    };
+    if !authz::is_active_person(&state.db, user_id).await? {
+        return Ok(Err(Rejection::UserInactive));
+    }
+    let Some((status, comment)) = parse_reply(body) else {
+        return Ok(Err(Rejection::Unrecognized { email }));
+    };
+
+    let saved = record_run_result(
+        state,
+        &user_id_text,
+        &run_id.to_string(),
+        &run_item_id.to_string(),
+        Precondition::None,
+        UpdateRunResultRequest {
+            status: status.to_string(),
+            fail_reason_code: None,
+            comment: Some(comment.clone()),
+            elapsed_seconds: None,
+            steps: None,
+        },
+    )
+    .await;
+    if let Err(VersionError::Api(error) | VersionError::Conflict { error, .. }) = saved {
+        // Database or network trouble is retried by the job queue; anything else is final.
+        if error.status().is_server_error() {
+            anyhow::bail!("failed to record the result: {}", error.code());
+        }
+        return Ok(Err(Rejection::Api { email, error }));
+    }
+
+    let audited = audit::record(
+        &state.db,
+        AuditEntry {
+            actor_user_id: Some(user_id),
+            action: "update",
+            entity_type: "run_item",
+            entity_id: Some(run_item_id),
+            project_id: Some(project_id),
+            run_id: Some(run_id),
+            before: None,
+            after: Some(json!({
+                "status": status,
+                "comment": comment,
+                "source": "email",
+                "messageId": message_id,
+            })),
+        },
+    )
+    .await;
+    if let Err(err) = audited {
+        warn!("failed to audit an email reply: {err}");
+    }
+    Ok(Ok(()))
+}
+
+fn notify_rejected(state: &AppState, email: String, reason: String) {
+    notifications::send_transactional(
+        state,
+        notifications::OutgoingEmail {
+            to: email,
+            subject: "Uran: результат не записан".to_string(),
+            body: format!("Не удалось записать результат из вашего ответа.\n\n{reason}"),
+            reply_to: None,
+            attachment: None,
+        },
+    );
+}
+
+/// Deletes expired reply tokens and old processed replies.
+pub async fn prune(state: &AppState) -> Result<(), sqlx::Error> {
+    sqlx::query!("DELETE FROM email_reply_tokens WHERE expires_at < NOW()")
+        .execute(&state.db)
+        .await?;
+    sqlx::query!(
+        r#"
+        DELETE FROM email_replies
+        WHERE status <> 'received' AND created_at < NOW() - make_interval(days => $1)
+        "#,
+        REPLY_RETENTION_DAYS,
+    )
+    .execute(&state.db)
+    .await?;
+    Ok(())
+}
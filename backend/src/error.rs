@@ -0,0 +1,1999 @@
+use axum::{
+    extract::Request,
+    http::{
+        header::{
+            ACCEPT_LANGUAGE, CONTENT_LANGUAGE, CONTENT_LENGTH, CONTENT_TYPE, TRANSFER_ENCODING,
+        },
+        HeaderValue, StatusCode,
+    },
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Lang {
+    Ru,
+    En,
+}
+
+impl Lang {
+    pub fn tag(self) -> &'static str {
+        match self {
+            Lang::Ru => "ru",
+            Lang::En => "en",
+        }
+    }
+
+    /// Picks the supported language with the highest `q` from an `Accept-Language` value,
+    /// falling back to Russian when nothing matches.
+    pub fn negotiate(accept_language: Option<&str>) -> Self {
+        let Some(accept_language) = accept_language else {
+            return Lang::Ru;
+        };
+        let mut best: Option<(f32, Lang)> = None;
+        for range in accept_language.split(',') {
+            let mut parts = range.split(';').map(str::trim);
+            let tag = parts.next().unwrap_or_default().to_ascii_lowercase();
+            let q = parts
+                .find_map(|p| p.strip_prefix("q="))
+                .and_then(|q| q.parse::<f32>().ok())
+                .unwrap_or(1.0);
+            let lang = match tag.split('-').next() {
+                Some("ru") => Lang::Ru,
+                Some("en") => Lang::En,
+                _ => continue,
+            };
+            if q > 0.0 && best.is_none_or(|(best_q, _)| q > best_q) {
+                best = Some((q, lang));
+            }
+        }
+        best.map(|(_, lang)| lang).unwrap_or(Lang::Ru)
+    }
+}
+
+tokio::task_local! {
+    static LANG: Lang;
+}
+
+/// Language negotiated for the current request; Russian outside of a request scope.
+pub fn current_lang() -> Lang {
+    LANG.try_with(|lang| *lang).unwrap_or(Lang::Ru)
+}
+
+/// Resolves `Accept-Language` once per request so error responses can be localized
+/// wherever they are built.
+pub async fn negotiate_language(request: Request, next: Next) -> Response {
+    let lang = Lang::negotiate(
+        request
+            .headers()
+            .get(ACCEPT_LANGUAGE)
+            .and_then(|v| v.to_str().ok()),
+    );
+    LANG.scope(lang, next.run(request)).await
+}
+
+/// Rejects `/api` requests that carry a body without `Content-Type`, and turns the plain-text
+/// `413`/`415` of the body size limit and of axum extractors into the usual error JSON.
+pub async fn structured_body_errors(request: Request, next: Next) -> Response {
+    if !request.uri().path().starts_with("/api/") {
+        return next.run(request).await;
+    }
+    let headers = request.headers();
+    let has_body = headers.contains_key(TRANSFER_ENCODING)
+        || headers
+            .get(CONTENT_LENGTH)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.trim() != "0");
+    if has_body && !headers.contains_key(CONTENT_TYPE) {
+        return ApiError::UnsupportedMediaType.into_response();
+    }
+    let response = next.run(request).await;
+    let is_json = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("application/json"));
+    match response.status() {
+        StatusCode::PAYLOAD_TOO_LARGE if !is_json => ApiError::PayloadTooLarge.into_response(),
+        StatusCode::UNSUPPORTED_MEDIA_TYPE if !is_json => {
+            ApiError::UnsupportedMediaType.into_response()
+        }
+        _ => response,
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ErrorResponse {
+    pub error: ErrorBody,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ErrorBody {
+    /// Stable machine-readable code, e.g. `run_not_found`.
+    pub code: &'static str,
+    /// Human-readable message in the negotiated language.
+    pub message: &'static str,
+    /// Only on a stale `If-Match`: the version the server has now.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current_version: Option<i64>,
+}
+
+macro_rules! api_errors {
+    ($($variant:ident => $status:ident, $code:literal, $ru:literal, $en:literal;)*) => {
+        /// Every error the API can return: HTTP status, code and message catalog.
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        pub enum ApiError {
+            $($variant,)*
+        }
+
+        impl ApiError {
+            pub fn status(self) -> StatusCode {
+                match self {
+                    $(ApiError::$variant => StatusCode::$status,)*
+                }
+            }
+
+            pub fn code(self) -> &'static str {
+                match self {
+                    $(ApiError::$variant => $code,)*
+                }
+            }
+
+            pub fn message(self, lang: Lang) -> &'static str {
+                match (self, lang) {
+                    $((ApiError::$variant, Lang::Ru) => $ru,
+                    (ApiError::$variant, Lang::En) => $en,)*
+                }
+            }
+        }
+    };
+}
+
+api_errors! {
+    // General
+    Unauthorized => UNAUTHORIZED, "unauthorized",
+        "Требуется авторизация.",
+        "Authorization required.";
+    InvalidToken => UNAUTHORIZED, "invalid_token",
+        "Недействительный токен.",
+        "Invalid token.";
+    EndpointNotFound => NOT_FOUND, "endpoint_not_found",
+        "API endpoint не найден.",
+        "API endpoint not found.";
+    InvalidIdempotencyKey => BAD_REQUEST, "invalid_idempotency_key",
+        "Idempotency-Key должен содержать от 1 до 255 символов.",
+        "Idempotency-Key must be 1 to 255 characters.";
+    IdempotencyKeyInProgress => CONFLICT, "idempotency_key_in_progress",
+        "Запрос с этим Idempotency-Key ещё выполняется.",
+        "A request with this Idempotency-Key is still in progress.";
+    IdempotencyKeyReused => UNPROCESSABLE_ENTITY, "idempotency_key_reused",
+        "Idempotency-Key уже использован для другого запроса.",
+        "The Idempotency-Key was already used for a different request.";
+    IdempotencyCheckFailed => INTERNAL_SERVER_ERROR, "idempotency_check_failed",
+        "Ошибка проверки Idempotency-Key.",
+        "Failed to check the Idempotency-Key.";
+    ResponseReadFailed => INTERNAL_SERVER_ERROR, "response_read_failed",
+        "Ошибка формирования ответа.",
+        "Failed to build the response.";
+    PayloadTooLarge => PAYLOAD_TOO_LARGE, "payload_too_large",
+        "Тело запроса слишком большое.",
+        "The request body is too large.";
+    UnsupportedMediaType => UNSUPPORTED_MEDIA_TYPE, "unsupported_media_type",
+        "Неподдерживаемый Content-Type тела запроса.",
+        "Unsupported request body Content-Type.";
+    InvalidIfMatch => BAD_REQUEST, "invalid_if_match",
+        "Некорректный заголовок If-Match. Ожидается версия из ETag или *.",
+        "Invalid If-Match header. Expected a version from ETag or *.";
+    RateLimited => TOO_MANY_REQUESTS, "rate_limited",
+        "Слишком много запросов. Повторите позже.",
+        "Too many requests. Try again later.";
+    DatabaseBusy => SERVICE_UNAVAILABLE, "database_busy",
+        "Сервер перегружен. Повторите позже.",
+        "The server is overloaded. Try again later.";
+    AccessCheckFailed => INTERNAL_SERVER_ERROR, "access_check_failed",
+        "Ошибка проверки доступа.",
+        "Failed to check access.";
+    UserSyncFailed => INTERNAL_SERVER_ERROR, "user_sync_failed",
+        "Не удалось синхронизировать пользователя в БД.",
+        "Failed to sync the user to the database.";
+    AuditWriteFailed => INTERNAL_SERVER_ERROR, "audit_write_failed",
+        "Не удалось записать аудит.",
+        "Failed to write the audit log.";
+    InvalidCursor => BAD_REQUEST, "invalid_cursor",
+        "Некорректный cursor.",
+        "Invalid cursor.";
+    InvalidUserId => BAD_REQUEST, "invalid_user_id",
+        "Некорректный идентификатор пользователя.",
+        "Invalid user id.";
+    InvalidProjectId => BAD_REQUEST, "invalid_project_id",
+        "Некорректный project_id.",
+        "Invalid project_id.";
+    InvalidProjectIdParam => BAD_REQUEST, "invalid_project_id",
+        "Некорректный projectId.",
+        "Invalid projectId.";
+    InvalidRunId => BAD_REQUEST, "invalid_run_id",
+        "Некорректный run_id.",
+        "Invalid run_id.";
+    InvalidRunIdParam => BAD_REQUEST, "invalid_run_id",
+        "Некорректный runId.",
+        "Invalid runId.";
+    InvalidRunItemId => BAD_REQUEST, "invalid_run_item_id",
+        "Некорректный run_item_id.",
+        "Invalid run_item_id.";
+    InvalidSuiteId => BAD_REQUEST, "invalid_suite_id",
+        "Некорректный suite_id.",
+        "Invalid suite_id.";
+    InvalidSuiteIdParam => BAD_REQUEST, "invalid_suite_id",
+        "Некорректный suiteId.",
+        "Invalid suiteId.";
+    InvalidParentId => BAD_REQUEST, "invalid_parent_id",
+        "Некорректный parent_id.",
+        "Invalid parent_id.";
+    InvalidTestcaseId => BAD_REQUEST, "invalid_testcase_id",
+        "Некорректный testcase_id.",
+        "Invalid testcase_id.";
+    InvalidTestcaseIdParam => BAD_REQUEST, "invalid_testcase_id",
+        "Некорректный testcaseId.",
+        "Invalid testcaseId.";
+    InvalidTestcaseVersionId => BAD_REQUEST, "invalid_testcase_version_id",
+        "Некорректный testcase_version_id.",
+        "Invalid testcase_version_id.";
+    InvalidAssetId => BAD_REQUEST, "invalid_asset_id",
+        "Некорректный asset_id.",
+        "Invalid asset_id.";
+    InvalidTemplateId => BAD_REQUEST, "invalid_template_id",
+        "Некорректный template_id.",
+        "Invalid template_id.";
+    InvalidAttachmentId => BAD_REQUEST, "invalid_attachment_id",
+        "Некорректный attachment_id.",
+        "Invalid attachment_id.";
+    InvalidDefectId => BAD_REQUEST, "invalid_defect_id",
+        "Некорректный defect_id.",
+        "Invalid defect_id.";
+    InvalidRequirementId => BAD_REQUEST, "invalid_requirement_id",
+        "Некорректный requirement_id.",
+        "Invalid requirement_id.";
+    InvalidWebhookId => BAD_REQUEST, "invalid_webhook_id",
+        "Некорректный webhook_id.",
+        "Invalid webhook_id.";
+    InvalidApiKeyId => BAD_REQUEST, "invalid_api_key_id",
+        "Некорректный id API-ключа.",
+        "Invalid API key id.";
+    InvalidJobId => BAD_REQUEST, "invalid_job_id",
+        "Некорректный job_id.",
+        "Invalid job_id.";
+    InvalidScheduleId => BAD_REQUEST, "invalid_schedule_id",
+        "Некорректный schedule_id.",
+        "Invalid schedule_id.";
+    InvalidAssigneeUserId => BAD_REQUEST, "invalid_assignee_user_id",
+        "Некорректный assigneeUserId.",
+        "Invalid assigneeUserId.";
+    InvalidExecutorUserId => BAD_REQUEST, "invalid_executor_user_id",
+        "Некорректный executorUserId.",
+        "Invalid executorUserId.";
+    InvalidDefaultRunTemplateId => BAD_REQUEST, "invalid_default_run_template_id",
+        "Некорректный defaultRunTemplateId.",
+        "Invalid defaultRunTemplateId.";
+    // Database constraints
+    UnknownProjectId => NOT_FOUND, "unknown_project_id",
+        "projectId: проект не найден.",
+        "projectId: the project does not exist.";
+    UnknownAssetId => NOT_FOUND, "unknown_asset_id",
+        "assetId: asset не найден.",
+        "assetId: the asset does not exist.";
+    UnknownTemplateId => NOT_FOUND, "unknown_template_id",
+        "templateId: шаблон run не найден.",
+        "templateId: the run template does not exist.";
+    UnknownSuiteId => NOT_FOUND, "unknown_suite_id",
+        "suiteId: набор не найден.",
+        "suiteId: the suite does not exist.";
+    UnknownParentSuiteId => NOT_FOUND, "unknown_parent_suite_id",
+        "parentId: родительский набор не найден.",
+        "parentId: the parent suite does not exist.";
+    UnknownTestcaseId => NOT_FOUND, "unknown_testcase_id",
+        "testcaseId: тест-кейс не найден.",
+        "testcaseId: the test case does not exist.";
+    UnknownTestcaseVersionId => NOT_FOUND, "unknown_testcase_version_id",
+        "testcaseVersionId: версия тест-кейса не найдена.",
+        "testcaseVersionId: the test case version does not exist.";
+    UnknownStepId => NOT_FOUND, "unknown_step_id",
+        "stepId: шаг не найден.",
+        "stepId: the step does not exist.";
+    UnknownRunItemId => NOT_FOUND, "unknown_run_item_id",
+        "runItemId: пункт run не найден.",
+        "runItemId: the run item does not exist.";
+    UnknownDependsOnRunItemId => NOT_FOUND, "unknown_depends_on_run_item_id",
+        "dependsOn: пункт run не найден.",
+        "dependsOn: the run item does not exist.";
+    UnknownParentCommentId => NOT_FOUND, "unknown_parent_comment_id",
+        "parentId: комментарий не найден.",
+        "parentId: the comment does not exist.";
+    UnknownTagId => NOT_FOUND, "unknown_tag_id",
+        "tagIds: тег не найден.",
+        "tagIds: the tag does not exist.";
+    UnknownAssigneeUser => NOT_FOUND, "unknown_assignee_user",
+        "assigneeUserId: пользователь не найден.",
+        "assigneeUserId: the user does not exist.";
+    UnknownUserId => NOT_FOUND, "unknown_user_id",
+        "userId: пользователь не найден.",
+        "userId: the user does not exist.";
+    ReferencedRecordNotFound => NOT_FOUND, "referenced_record_not_found",
+        "Связанная запись не найдена.",
+        "A referenced record does not exist.";
+    RecordInUse => CONFLICT, "record_in_use",
+        "Запись используется другими данными и не может быть удалена.",
+        "The record is still referenced by other data and cannot be removed.";
+    DuplicateRunItem => CONFLICT, "duplicate_run_item",
+        "testcaseVersionId: эта версия тест-кейса уже есть в run.",
+        "testcaseVersionId: this test case version is already in the run.";
+    DuplicateRunResult => CONFLICT, "duplicate_run_result",
+        "runItemId: у пункта уже есть результат.",
+        "runItemId: the item already has a result.";
+    DuplicateSuiteKey => CONFLICT, "duplicate_suite_key",
+        "key: набор с таким ключом уже есть в проекте.",
+        "key: a suite with this key already exists in the project.";
+    DuplicateRequirementKey => CONFLICT, "duplicate_requirement_key",
+        "key: требование с таким ключом уже есть в проекте.",
+        "key: a requirement with this key already exists in the project.";
+    DuplicateTemplateKey => CONFLICT, "duplicate_template_key",
+        "key: шаблон с таким ключом уже есть в проекте.",
+        "key: a template with this key already exists in the project.";
+    DuplicateStepPosition => CONFLICT, "duplicate_step_position",
+        "position: шаг с такой позицией уже есть в версии.",
+        "position: a step with this position already exists in the version.";
+    DuplicateRecord => CONFLICT, "duplicate_record",
+        "Запись с такими значениями уже существует.",
+        "A record with these values already exists.";
+    InvalidStatusValue => UNPROCESSABLE_ENTITY, "invalid_status_value",
+        "status: недопустимое значение.",
+        "status: the value is not allowed.";
+    InvalidRoleValue => UNPROCESSABLE_ENTITY, "invalid_role_value",
+        "role: недопустимое значение.",
+        "role: the value is not allowed.";
+    InvalidEnumValue => UNPROCESSABLE_ENTITY, "invalid_enum_value",
+        "Недопустимое значение перечисления.",
+        "The value is not one of the allowed options.";
+    ConstraintViolation => UNPROCESSABLE_ENTITY, "constraint_violation",
+        "Значения не проходят проверки базы данных.",
+        "The values do not pass database checks.";
+    // Permissions and roles
+    NoProjectAccess => FORBIDDEN, "no_project_access",
+        "Нет доступа к проекту.",
+        "No access to the project.";
+    NoLibraryEditRight => FORBIDDEN, "no_library_edit_right",
+        "Нет прав на редактирование библиотеки тестов.",
+        "Not allowed to edit the test library.";
+    NoRunCreateRight => FORBIDDEN, "no_run_create_right",
+        "Нет прав на создание прогонов.",
+        "Not allowed to create runs.";
+    NoRunComposeRight => FORBIDDEN, "no_run_compose_right",
+        "Нет прав на изменение состава прогона.",
+        "Not allowed to change run items.";
+    NoResultEditRight => FORBIDDEN, "no_result_edit_right",
+        "Нет прав на заполнение результатов.",
+        "Not allowed to record results.";
+    NoRunStatusRight => FORBIDDEN, "no_run_status_right",
+        "Нет прав на смену статуса прогона.",
+        "Not allowed to change the run status.";
+    NoRunLockRight => FORBIDDEN, "no_run_lock_right",
+        "Нет прав на фиксацию прогона.",
+        "Not allowed to lock the run.";
+    RunUnlockOwnerOnly => FORBIDDEN, "run_unlock_owner_only",
+        "Разблокировать прогон может только владелец проекта.",
+        "Only a project owner can unlock the run.";
+    NoProjectManageRight => FORBIDDEN, "no_project_manage_right",
+        "Нет прав на управление проектом.",
+        "Not allowed to manage the project.";
+    InvalidRoleName => BAD_REQUEST, "invalid_role_name",
+        "Имя роли: 2-32 символа a-z, 0-9, _ или -.",
+        "Role name: 2-32 characters a-z, 0-9, _ or -.";
+    BuiltinRoleReadOnly => BAD_REQUEST, "builtin_role_read_only",
+        "Встроенные роли owner/editor/viewer нельзя изменить.",
+        "Built-in roles owner/editor/viewer cannot be changed.";
+    UnknownCapability => BAD_REQUEST, "unknown_capability",
+        "Неизвестная capability.",
+        "Unknown capability.";
+    RoleNotFound => NOT_FOUND, "role_not_found",
+        "Роль не найдена.",
+        "Role not found.";
+    RoleInUse => CONFLICT, "role_in_use",
+        "Роль назначена участникам или приглашениям проекта.",
+        "The role is assigned to project members or invitations.";
+    RolesLoadFailed => INTERNAL_SERVER_ERROR, "roles_load_failed",
+        "Ошибка загрузки ролей.",
+        "Failed to load roles.";
+    RoleSaveFailed => INTERNAL_SERVER_ERROR, "role_save_failed",
+        "Ошибка сохранения роли.",
+        "Failed to save the role.";
+    RoleDeleteFailed => INTERNAL_SERVER_ERROR, "role_delete_failed",
+        "Ошибка удаления роли.",
+        "Failed to delete the role.";
+    // Authentication and API keys
+    NameTooShort => BAD_REQUEST, "name_too_short",
+        "Имя должно быть не короче 2 символов.",
+        "Name must be at least 2 characters long.";
+    InvalidEmail => BAD_REQUEST, "invalid_email",
+        "Некорректный email.",
+        "Invalid email.";
+    PasswordTooShort => BAD_REQUEST, "password_too_short",
+        "Пароль должен быть не короче 8 символов.",
+        "Password must be at least 8 characters long.";
+    EmailTaken => CONFLICT, "email_taken",
+        "Пользователь с таким email уже существует.",
+        "A user with this email already exists.";
+    InvalidCredentials => UNAUTHORIZED, "invalid_credentials",
+        "Неверный email или пароль.",
+        "Invalid email or password.";
+    UserDeactivated => FORBIDDEN, "user_deactivated",
+        "Учётная запись отключена администратором.",
+        "The account has been deactivated by an administrator.";
+    PasswordResetRequired => FORBIDDEN, "password_reset_required",
+        "Администратор потребовал сменить пароль: воспользуйтесь ссылкой из письма.",
+        "An administrator requires a password reset: use the link from the email.";
+    PasswordResetLinkInvalid => NOT_FOUND, "password_reset_link_invalid",
+        "Ссылка сброса пароля недействительна.",
+        "The password reset link is no longer valid.";
+    InvalidRefreshToken => UNAUTHORIZED, "invalid_refresh_token",
+        "Недействительный refresh-токен.",
+        "Invalid refresh token.";
+    TokenUserNotFound => UNAUTHORIZED, "token_user_not_found",
+        "Пользователь не найден.",
+        "User not found.";
+    UserNotFound => NOT_FOUND, "user_not_found",
+        "Пользователь не найден.",
+        "User not found.";
+    RegistrationFailed => INTERNAL_SERVER_ERROR, "registration_failed",
+        "Ошибка регистрации.",
+        "Registration failed.";
+    LoginFailed => INTERNAL_SERVER_ERROR, "login_failed",
+        "Ошибка входа.",
+        "Login failed.";
+    TokenRefreshFailed => INTERNAL_SERVER_ERROR, "token_refresh_failed",
+        "Ошибка обновления токена.",
+        "Failed to refresh the token.";
+    ProfileLoadFailed => INTERNAL_SERVER_ERROR, "profile_load_failed",
+        "Ошибка загрузки профиля.",
+        "Failed to load the profile.";
+    AccountDeleteFailed => INTERNAL_SERVER_ERROR, "account_delete_failed",
+        "Не удалось удалить учётную запись.",
+        "Failed to delete the account.";
+    AccountExportFailed => INTERNAL_SERVER_ERROR, "account_export_failed",
+        "Не удалось выгрузить данные учётной записи.",
+        "Failed to export the account data.";
+    LogoutFailed => INTERNAL_SERVER_ERROR, "logout_failed",
+        "Не удалось завершить сеанс.",
+        "Failed to log out.";
+    ProfileUpdateEmpty => BAD_REQUEST, "profile_update_empty",
+        "Нужно передать name или email.",
+        "Either name or email is required.";
+    InvalidCurrentPassword => FORBIDDEN, "invalid_current_password",
+        "Неверный текущий пароль.",
+        "The current password is incorrect.";
+    EmailChangeLinkInvalid => NOT_FOUND, "email_change_link_invalid",
+        "Ссылка подтверждения email недействительна или истекла.",
+        "The email confirmation link is invalid or expired.";
+    EmailVerificationLinkInvalid => NOT_FOUND, "email_verification_link_invalid",
+        "Ссылка подтверждения email недействительна или истекла.",
+        "The email verification link is invalid or expired.";
+    EmailAlreadyVerified => CONFLICT, "email_already_verified",
+        "Email уже подтверждён.",
+        "The email is already verified.";
+    ProfileUpdateFailed => INTERNAL_SERVER_ERROR, "profile_update_failed",
+        "Ошибка обновления профиля.",
+        "Failed to update the profile.";
+    PasswordChangeFailed => INTERNAL_SERVER_ERROR, "password_change_failed",
+        "Не удалось сменить пароль.",
+        "Failed to change the password.";
+    AvatarTypeNotAllowed => UNSUPPORTED_MEDIA_TYPE, "avatar_type_not_allowed",
+        "Аватар должен быть изображением PNG, JPEG, WebP или GIF.",
+        "The avatar must be a PNG, JPEG, WebP or GIF image.";
+    AvatarTooLarge => PAYLOAD_TOO_LARGE, "avatar_too_large",
+        "Аватар превышает допустимый размер (2 МиБ).",
+        "The avatar exceeds the size limit (2 MiB).";
+    AvatarEmpty => BAD_REQUEST, "avatar_empty",
+        "Файл аватара пуст.",
+        "The avatar file is empty.";
+    AvatarNotFound => NOT_FOUND, "avatar_not_found",
+        "Аватар не найден.",
+        "Avatar not found.";
+    AvatarSaveFailed => INTERNAL_SERVER_ERROR, "avatar_save_failed",
+        "Не удалось сохранить аватар.",
+        "Failed to save the avatar.";
+    ApiKeyOutsideV2 => UNAUTHORIZED, "api_key_outside_v2",
+        "API-ключи принимаются только для /api/v2.",
+        "API keys are only accepted for /api/v2.";
+    InvalidApiKey => UNAUTHORIZED, "invalid_api_key",
+        "Недействительный API-ключ.",
+        "Invalid API key.";
+    ApiKeyWrongProject => FORBIDDEN, "api_key_wrong_project",
+        "API-ключ выдан для другого проекта.",
+        "The API key was issued for another project.";
+    ApiKeyScopeDenied => FORBIDDEN, "api_key_scope_denied",
+        "Недостаточно прав API-ключа.",
+        "The API key scopes do not allow this action.";
+    InvalidApiKeyName => BAD_REQUEST, "invalid_api_key_name",
+        "Название ключа должно быть от 1 до 200 символов.",
+        "Key name must be 1 to 200 characters long.";
+    InvalidApiKeyScope => BAD_REQUEST, "invalid_api_key_scope",
+        "Некорректный scope. Ожидается read|write.",
+        "Invalid scope. Expected read|write.";
+    ApiKeyScopesEmpty => BAD_REQUEST, "api_key_scopes_empty",
+        "Нужно выбрать хотя бы один scope.",
+        "At least one scope is required.";
+    InvalidApiKeyExpiry => BAD_REQUEST, "invalid_api_key_expiry",
+        "expiresAt должен быть в формате RFC 3339.",
+        "expiresAt must be an RFC 3339 timestamp.";
+    ApiKeyExpiryInPast => BAD_REQUEST, "api_key_expiry_in_past",
+        "expiresAt должен быть в будущем.",
+        "expiresAt must be in the future.";
+    ApiKeyRejected => BAD_REQUEST, "api_key_rejected",
+        "Не удалось создать API-ключ.",
+        "Failed to create the API key.";
+    ApiKeyNotFound => NOT_FOUND, "api_key_not_found",
+        "API-ключ не найден.",
+        "API key not found.";
+    ApiKeyCheckFailed => INTERNAL_SERVER_ERROR, "api_key_check_failed",
+        "Ошибка проверки API-ключа.",
+        "Failed to verify the API key.";
+    ApiKeyCreateFailed => INTERNAL_SERVER_ERROR, "api_key_create_failed",
+        "Не удалось создать API-ключ.",
+        "Failed to create the API key.";
+    ApiKeysReadFailed => INTERNAL_SERVER_ERROR, "api_keys_read_failed",
+        "Ошибка чтения API-ключей.",
+        "Failed to read API keys.";
+    ApiKeyReadFailed => INTERNAL_SERVER_ERROR, "api_key_read_failed",
+        "Ошибка чтения API-ключа.",
+        "Failed to read the API key.";
+    ApiKeyRevokeFailed => INTERNAL_SERVER_ERROR, "api_key_revoke_failed",
+        "Не удалось отозвать API-ключ.",
+        "Failed to revoke the API key.";
+    OidcNotConfigured => NOT_FOUND, "oidc_not_configured",
+        "Единый вход (OIDC) не настроен.",
+        "Single sign-on (OIDC) is not configured.";
+    OidcLoginStateInvalid => BAD_REQUEST, "oidc_login_state_invalid",
+        "Сеанс входа через OIDC недействителен или истёк. Начните вход заново.",
+        "The OIDC sign-in session is invalid or expired. Start the sign-in again.";
+    OidcLoginDenied => UNAUTHORIZED, "oidc_login_denied",
+        "Провайдер отклонил вход.",
+        "The identity provider declined the sign-in.";
+    OidcTokenInvalid => UNAUTHORIZED, "oidc_token_invalid",
+        "Провайдер вернул недействительный ID-токен.",
+        "The identity provider returned an invalid ID token.";
+    OidcEmailNotVerified => FORBIDDEN, "oidc_email_not_verified",
+        "Провайдер не подтвердил email учётной записи.",
+        "The identity provider did not verify the account email.";
+    OidcAccountLinkedElsewhere => CONFLICT, "oidc_account_linked_elsewhere",
+        "Пользователь с этим email уже привязан к другой учётной записи провайдера.",
+        "The user with this email is already linked to another provider account.";
+    OidcProviderUnavailable => BAD_GATEWAY, "oidc_provider_unavailable",
+        "Провайдер OIDC недоступен.",
+        "The OIDC provider is unavailable.";
+    OidcLoginFailed => INTERNAL_SERVER_ERROR, "oidc_login_failed",
+        "Ошибка входа через OIDC.",
+        "OIDC sign-in failed.";
+    // Administration
+    AdminRequired => FORBIDDEN, "admin_required",
+        "Доступно только администраторам.",
+        "Administrators only.";
+    AdminSelfAction => CONFLICT, "admin_self_action",
+        "Нельзя отключить свою учётную запись или снять с себя роль администратора.",
+        "You cannot deactivate your own account or drop your own admin role.";
+    AdminFromConfig => CONFLICT, "admin_from_config",
+        "Администратор задан в ADMIN_EMAILS, роль снимается только в конфигурации.",
+        "The admin comes from ADMIN_EMAILS; the role can only be removed there.";
+    InvalidUserStatus => BAD_REQUEST, "invalid_user_status",
+        "Некорректный status. Ожидается active|deactivated.",
+        "Invalid status. Expected active|deactivated.";
+    NewOwnerNotFound => NOT_FOUND, "new_owner_not_found",
+        "Новый владелец не найден или отключён.",
+        "The new owner does not exist or is deactivated.";
+    AdminUsersReadFailed => INTERNAL_SERVER_ERROR, "admin_users_read_failed",
+        "Ошибка чтения пользователей.",
+        "Failed to read users.";
+    AdminUserUpdateFailed => INTERNAL_SERVER_ERROR, "admin_user_update_failed",
+        "Не удалось изменить пользователя.",
+        "Failed to update the user.";
+    AdminProjectsReadFailed => INTERNAL_SERVER_ERROR, "admin_projects_read_failed",
+        "Ошибка чтения проектов.",
+        "Failed to read projects.";
+    ProjectOwnerUpdateFailed => INTERNAL_SERVER_ERROR, "project_owner_update_failed",
+        "Не удалось сменить владельца проекта.",
+        "Failed to change the project owner.";
+    // Service accounts
+    InvalidServiceAccountName => BAD_REQUEST, "invalid_service_account_name",
+        "Название сервисного аккаунта должно быть от 1 до 200 символов.",
+        "Service account name must be 1 to 200 characters long.";
+    ServiceAccountNotFound => NOT_FOUND, "service_account_not_found",
+        "Сервисный аккаунт не найден.",
+        "Service account not found.";
+    ServiceAccountDeactivated => CONFLICT, "service_account_deactivated",
+        "Сервисный аккаунт отключён.",
+        "The service account is deactivated.";
+    ServiceAccountSignIn => FORBIDDEN, "service_account_sign_in",
+        "Сервисный аккаунт не может входить в систему, он работает только через API-ключи.",
+        "A service account cannot sign in; it works through API keys only.";
+    ServiceAccountsReadFailed => INTERNAL_SERVER_ERROR, "service_accounts_read_failed",
+        "Ошибка чтения сервисных аккаунтов.",
+        "Failed to read service accounts.";
+    ServiceAccountCreateFailed => INTERNAL_SERVER_ERROR, "service_account_create_failed",
+        "Не удалось создать сервисный аккаунт.",
+        "Failed to create the service account.";
+    ServiceAccountDeleteFailed => INTERNAL_SERVER_ERROR, "service_account_delete_failed",
+        "Не удалось удалить сервисный аккаунт.",
+        "Failed to delete the service account.";
+    // Organizations
+    OrganizationNotFound => NOT_FOUND, "organization_not_found",
+        "Организация не найдена.",
+        "Organization not found.";
+    NoOrganizationAccess => FORBIDDEN, "no_organization_access",
+        "Нет доступа к организации.",
+        "No access to the organization.";
+    NoOrganizationManageRight => FORBIDDEN, "no_organization_manage_right",
+        "Нужны права администратора организации.",
+        "Organization admin rights are required.";
+    OrganizationNameTooShort => BAD_REQUEST, "organization_name_too_short",
+        "Название организации должно быть не короче 3 символов.",
+        "Organization name must be at least 3 characters long.";
+    InvalidOrganizationRole => BAD_REQUEST, "invalid_organization_role",
+        "Роль в организации должна быть owner, admin или member.",
+        "Organization role must be owner, admin or member.";
+    OrganizationOwnerRoleDenied => FORBIDDEN, "organization_owner_role_denied",
+        "Назначать и снимать владельцев организации может только владелец.",
+        "Only an owner can grant or revoke the organization owner role.";
+    OrganizationLastOwner => CONFLICT, "organization_last_owner",
+        "В организации должен остаться хотя бы один владелец.",
+        "The organization must keep at least one owner.";
+    OrganizationMemberNotFound => NOT_FOUND, "organization_member_not_found",
+        "Участник организации не найден.",
+        "Organization member not found.";
+    OrganizationsLoadFailed => INTERNAL_SERVER_ERROR, "organizations_load_failed",
+        "Ошибка загрузки организаций.",
+        "Failed to load organizations.";
+    OrganizationSaveFailed => INTERNAL_SERVER_ERROR, "organization_save_failed",
+        "Ошибка сохранения организации.",
+        "Failed to save the organization.";
+    // Projects, members and invitations
+    ProjectNotFound => NOT_FOUND, "project_not_found",
+        "Проект не найден.",
+        "Project not found.";
+    ProjectNameTooShort => BAD_REQUEST, "project_name_too_short",
+        "Название проекта должно быть не короче 3 символов.",
+        "Project name must be at least 3 characters long.";
+    ProjectUpdateEmpty => BAD_REQUEST, "project_update_empty",
+        "Нужно передать name, settings или organizationId.",
+        "One of name, settings or organizationId is required.";
+    RunTemplateNotInProject => BAD_REQUEST, "run_template_not_in_project",
+        "Шаблон прогона не найден в проекте.",
+        "Run template not found in the project.";
+    UnknownFailReasonCode => BAD_REQUEST, "unknown_fail_reason_code",
+        "Неизвестный или неактивный код причины FAIL.",
+        "Unknown or inactive FAIL reason code.";
+    InvalidTimezone => BAD_REQUEST, "invalid_timezone",
+        "Некорректный часовой пояс. Ожидается имя IANA, например Europe/Moscow.",
+        "Invalid time zone. Expected an IANA name such as Europe/Moscow.";
+    InvalidMemberRole => BAD_REQUEST, "invalid_member_role",
+        "Роль должна быть editor, viewer или пользовательской ролью проекта.",
+        "Role must be editor, viewer or a custom project role.";
+    MemberEmailNotFound => NOT_FOUND, "member_email_not_found",
+        "Пользователь с таким email не найден. Отправьте приглашение.",
+        "No user with this email. Send an invitation instead.";
+    MemberEmailNotVerified => CONFLICT, "member_email_not_verified",
+        "Пользователь ещё не подтвердил email.",
+        "The user has not verified their email yet.";
+    OwnerRoleImmutable => BAD_REQUEST, "owner_role_immutable",
+        "Нельзя изменить роль владельца.",
+        "The owner role cannot be changed.";
+    OwnerNotRemovable => BAD_REQUEST, "owner_not_removable",
+        "Нельзя удалить владельца из проекта.",
+        "The owner cannot be removed from the project.";
+    MemberNotFound => NOT_FOUND, "member_not_found",
+        "Участник не найден.",
+        "Member not found.";
+    InviteeRegistered => CONFLICT, "invitee_registered",
+        "Пользователь уже зарегистрирован, добавьте его как участника.",
+        "The user is already registered, add them as a member.";
+    InvitationNotFound => NOT_FOUND, "invitation_not_found",
+        "Приглашение не найдено.",
+        "Invitation not found.";
+    InvitationExpired => NOT_FOUND, "invitation_expired",
+        "Приглашение не найдено или истекло.",
+        "Invitation not found or expired.";
+    ProjectsReadFailed => INTERNAL_SERVER_ERROR, "projects_read_failed",
+        "Ошибка чтения проектов.",
+        "Failed to read projects.";
+    ProjectsLoadFailed => INTERNAL_SERVER_ERROR, "projects_load_failed",
+        "Ошибка загрузки проектов.",
+        "Failed to load projects.";
+    ProjectCreateFailed => INTERNAL_SERVER_ERROR, "project_create_failed",
+        "Ошибка создания проекта.",
+        "Failed to create the project.";
+    ProjectUpdateFailed => INTERNAL_SERVER_ERROR, "project_update_failed",
+        "Ошибка обновления проекта.",
+        "Failed to update the project.";
+    ProjectSettingsCheckFailed => INTERNAL_SERVER_ERROR, "project_settings_check_failed",
+        "Ошибка проверки настроек проекта.",
+        "Failed to validate project settings.";
+    ProjectSettingsLoadFailed => INTERNAL_SERVER_ERROR, "project_settings_load_failed",
+        "Ошибка загрузки настроек проекта.",
+        "Failed to load project settings.";
+    MemberAddFailed => INTERNAL_SERVER_ERROR, "member_add_failed",
+        "Ошибка выдачи доступа.",
+        "Failed to grant access.";
+    MembersLoadFailed => INTERNAL_SERVER_ERROR, "members_load_failed",
+        "Ошибка загрузки участников.",
+        "Failed to load members.";
+    MemberUpdateFailed => INTERNAL_SERVER_ERROR, "member_update_failed",
+        "Ошибка обновления роли участника.",
+        "Failed to update the member role.";
+    MemberRemoveFailed => INTERNAL_SERVER_ERROR, "member_remove_failed",
+        "Ошибка удаления участника.",
+        "Failed to remove the member.";
+    SessionLoadFailed => INTERNAL_SERVER_ERROR, "session_load_failed",
+        "Ошибка загрузки сессии проекта.",
+        "Failed to load the project session.";
+    SessionSaveFailed => INTERNAL_SERVER_ERROR, "session_save_failed",
+        "Ошибка сохранения сессии проекта.",
+        "Failed to save the project session.";
+    SessionVersionConflict => CONFLICT, "session_version_conflict",
+        "Сессию проекта уже изменил кто-то другой. Загрузите текущую версию и повторите.",
+        "The project session was changed by someone else. Reload the current version and retry.";
+    InvalidSessionPatch => BAD_REQUEST, "invalid_session_patch",
+        "Некорректный patch сессии.",
+        "Invalid session patch.";
+    SessionPatchNotApplicable => UNPROCESSABLE_ENTITY, "session_patch_not_applicable",
+        "Patch нельзя применить к текущей сессии (путь не найден или не прошла операция test).",
+        "The patch cannot be applied to the current session (missing path or a failed test operation).";
+    UnsupportedSessionPatchType => UNSUPPORTED_MEDIA_TYPE, "unsupported_session_patch_type",
+        "Ожидается Content-Type application/merge-patch+json или application/json-patch+json.",
+        "Expected Content-Type application/merge-patch+json or application/json-patch+json.";
+    InvitationCreateFailed => INTERNAL_SERVER_ERROR, "invitation_create_failed",
+        "Ошибка создания приглашения.",
+        "Failed to create the invitation.";
+    InvitationsLoadFailed => INTERNAL_SERVER_ERROR, "invitations_load_failed",
+        "Ошибка загрузки приглашений.",
+        "Failed to load invitations.";
+    InvitationLoadFailed => INTERNAL_SERVER_ERROR, "invitation_load_failed",
+        "Ошибка загрузки приглашения.",
+        "Failed to load the invitation.";
+    InvitationRevokeFailed => INTERNAL_SERVER_ERROR, "invitation_revoke_failed",
+        "Ошибка отзыва приглашения.",
+        "Failed to revoke the invitation.";
+    // Runs
+    RunNotFound => NOT_FOUND, "run_not_found",
+        "Run не найден.",
+        "Run not found.";
+    RunNotFoundAfterUpdate => NOT_FOUND, "run_not_found_after_update",
+        "Run не найден после обновления.",
+        "Run not found after the update.";
+    RunItemNotFound => NOT_FOUND, "run_item_not_found",
+        "Run item не найден.",
+        "Run item not found.";
+    RunOrItemNotFound => NOT_FOUND, "run_or_item_not_found",
+        "Run или run_item не найден.",
+        "Run or run item not found.";
+    ResultTargetNotFound => NOT_FOUND, "result_target_not_found",
+        "Run или run_item не найден для обновления результата.",
+        "Run or run item not found for the result update.";
+    InvalidRunStatus => BAD_REQUEST, "invalid_run_status",
+        "Некорректный статус run. Ожидается draft|in_progress|done|locked.",
+        "Invalid run status. Expected draft|in_progress|done|locked.";
+    CorruptRunStatus => BAD_REQUEST, "corrupt_run_status",
+        "Некорректный статус run.",
+        "Invalid run status.";
+    InvalidResultStatus => BAD_REQUEST, "invalid_result_status",
+        "Некорректный статус результата. Ожидается ok|fail|na|blocked|skipped|retest.",
+        "Invalid result status. Expected ok|fail|na|blocked|skipped|retest.";
+    InvalidStepId => BAD_REQUEST, "invalid_step_id",
+        "Некорректный stepId.",
+        "Invalid stepId.";
+    DuplicateStepResult => BAD_REQUEST, "duplicate_step_result",
+        "Шаг указан в steps несколько раз.",
+        "A step is listed in steps more than once.";
+    StepNotInRunItem => BAD_REQUEST, "step_not_in_run_item",
+        "Шаг не относится к версии тест-кейса этого элемента прогона.",
+        "The step does not belong to the test case version of this run item.";
+    RunCreateRejected => BAD_REQUEST, "run_create_rejected",
+        "Не удалось создать run. Проверь проект/asset/template.",
+        "Failed to create the run. Check the project/asset/template.";
+    JunitRunCreateRejected => BAD_REQUEST, "junit_run_create_rejected",
+        "Не удалось создать run. Проверь проект.",
+        "Failed to create the run. Check the project.";
+    NoFailedItemsToClone => CONFLICT, "no_failed_items_to_clone",
+        "В run нет пунктов с результатом fail.",
+        "The run has no items with a fail result.";
+    NoItemsToClone => CONFLICT, "no_items_to_clone",
+        "В run нет пунктов для копирования.",
+        "The run has no items to copy.";
+    RunLockedItems => CONFLICT, "run_locked_items",
+        "Run в статусе locked, состав менять нельзя.",
+        "The run is locked, its items cannot be changed.";
+    RunLockedResults => CONFLICT, "run_locked_results",
+        "Run в статусе locked, результаты менять нельзя.",
+        "The run is locked, its results cannot be changed.";
+    RunNotLocked => CONFLICT, "run_not_locked",
+        "Прогон не в статусе locked.",
+        "The run is not locked.";
+    ResultVersionConflict => CONFLICT, "result_version_conflict",
+        "Результат уже изменил кто-то другой. Загрузите текущую версию и повторите.",
+        "The result was changed by someone else. Reload the current version and retry.";
+    RunLockedAttachments => CONFLICT, "run_locked_attachments",
+        "Run в статусе locked, вложения менять нельзя.",
+        "The run is locked, its attachments cannot be changed.";
+    RunLockedAssignees => CONFLICT, "run_locked_assignees",
+        "Run в статусе locked, исполнителей менять нельзя.",
+        "The run is locked, its assignees cannot be changed.";
+    RunLockedCustomFields => CONFLICT, "run_locked_custom_fields",
+        "Run в статусе locked, значения полей менять нельзя.",
+        "The run is locked, its field values cannot be changed.";
+    RunLockedDetails => CONFLICT, "run_locked_details",
+        "Run в статусе locked, его описание менять нельзя.",
+        "The run is locked, its details cannot be changed.";
+    RunCompositionFrozen => CONFLICT, "run_composition_frozen",
+        "Asset и шаблон run можно менять только в статусе draft.",
+        "The asset and template of a run can only be changed while it is a draft.";
+    RunItemRejected => BAD_REQUEST, "run_item_rejected",
+        "Не удалось добавить пункт в run (проверь testcase_version или дубликат).",
+        "Failed to add the item to the run (check the testcase version or duplicates).";
+    TestcaseVersionIdsEmpty => BAD_REQUEST, "testcase_version_ids_empty",
+        "Список testcaseVersionIds не должен быть пустым.",
+        "testcaseVersionIds must not be empty.";
+    TooManyRunItems => BAD_REQUEST, "too_many_run_items",
+        "За один запрос можно добавить не более 1000 пунктов.",
+        "At most 1000 items can be added per request.";
+    DuplicateTestcaseVersionIds => BAD_REQUEST, "duplicate_testcase_version_ids",
+        "testcaseVersionIds содержит дубликаты.",
+        "testcaseVersionIds contains duplicates.";
+    RunItemsRejected => BAD_REQUEST, "run_items_rejected",
+        "Не удалось добавить пункты в run (проверь testcase_version или дубликаты).",
+        "Failed to add items to the run (check testcase versions or duplicates).";
+    ReorderItemsEmpty => BAD_REQUEST, "reorder_items_empty",
+        "Список items не должен быть пустым.",
+        "items must not be empty.";
+    DuplicateReorderItems => BAD_REQUEST, "duplicate_reorder_items",
+        "items содержит дубликаты id.",
+        "items contains duplicate ids.";
+    NegativePosition => BAD_REQUEST, "negative_position",
+        "Позиция не может быть отрицательной.",
+        "Position cannot be negative.";
+    ForeignRunItems => BAD_REQUEST, "foreign_run_items",
+        "Часть пунктов не принадлежит этому run.",
+        "Some items do not belong to this run.";
+    RunItemSelfDependency => BAD_REQUEST, "run_item_self_dependency",
+        "Пункт не может зависеть от самого себя.",
+        "An item cannot depend on itself.";
+    TooManyRunItemDependencies => BAD_REQUEST, "too_many_run_item_dependencies",
+        "Слишком много зависимостей у пункта (не более 100).",
+        "Too many dependencies for one item (at most 100).";
+    RunItemDependencyCycle => CONFLICT, "run_item_dependency_cycle",
+        "Зависимости пунктов образуют цикл.",
+        "The item dependencies form a cycle.";
+    RunItemDependenciesUnmet => CONFLICT, "run_item_dependencies_unmet",
+        "Пункт можно выполнить только после того, как пройдены (ok) пункты, от которых он зависит.",
+        "The item can only be executed after the items it depends on have passed (ok).";
+    RunItemDependenciesReadFailed => INTERNAL_SERVER_ERROR, "run_item_dependencies_read_failed",
+        "Не удалось получить зависимости пункта.",
+        "Failed to load the item dependencies.";
+    RunItemDependenciesSaveFailed => INTERNAL_SERVER_ERROR, "run_item_dependencies_save_failed",
+        "Не удалось сохранить зависимости пункта.",
+        "Failed to save the item dependencies.";
+    BulkResultsEmpty => BAD_REQUEST, "bulk_results_empty",
+        "Список результатов не должен быть пустым.",
+        "The list of results must not be empty.";
+    TooManyBulkResults => BAD_REQUEST, "too_many_bulk_results",
+        "За один запрос можно записать не более 1000 результатов.",
+        "At most 1000 results can be recorded per request.";
+    DuplicateBulkResultItems => BAD_REQUEST, "duplicate_bulk_result_items",
+        "runItemId повторяется в списке результатов.",
+        "A runItemId is listed more than once.";
+    IngestEmpty => BAD_REQUEST, "ingest_empty",
+        "Загрузка не содержит ни одной записи.",
+        "The upload contains no records.";
+    TooManyIngestRecords => BAD_REQUEST, "too_many_ingest_records",
+        "За одну загрузку можно записать не более 100000 результатов; записанные до этого сохранены.",
+        "At most 100000 results can be recorded per upload; the ones before that are saved.";
+    IngestBodyReadFailed => BAD_REQUEST, "ingest_body_read_failed",
+        "Не удалось дочитать загрузку; записанные до обрыва результаты сохранены.",
+        "Failed to read the upload; the results before the break are saved.";
+    InvalidIngestRecord => BAD_REQUEST, "invalid_ingest_record",
+        "Строка должна быть JSON-объектом со status и runItemId или testcaseKey.",
+        "The line must be a JSON object with status and runItemId or testcaseKey.";
+    IngestLineTooLong => BAD_REQUEST, "ingest_line_too_long",
+        "Строка загрузки длиннее 64 KiB.",
+        "The upload line is longer than 64 KiB.";
+    IngestTestcaseNotInRun => NOT_FOUND, "ingest_testcase_not_in_run",
+        "В run нет тест-кейса с таким testcaseKey.",
+        "The run has no test case with this testcaseKey.";
+    FailReasonRequired => BAD_REQUEST, "fail_reason_required",
+        "Для FAIL в этом проекте нужна причина из списка проекта.",
+        "A FAIL result in this project requires a reason from the project list.";
+    ResultRejected => BAD_REQUEST, "result_rejected",
+        "Не удалось обновить run_result.",
+        "Failed to update the run result.";
+    ResultHistoryReadFailed => INTERNAL_SERVER_ERROR, "result_history_read_failed",
+        "Не удалось получить историю результата.",
+        "Failed to load the result history.";
+    RunMissingL0 => CONFLICT, "run_missing_l0",
+        "Run нельзя закрыть: отсутствуют L0 тесты в составе прогона.",
+        "The run cannot be closed: it has no L0 tests.";
+    RunL0Incomplete => CONFLICT, "run_l0_incomplete",
+        "Run нельзя закрыть: не все L0 пункты имеют зафиксированный результат.",
+        "The run cannot be closed: not all L0 items have a recorded result.";
+    InvalidRunTransition => CONFLICT, "invalid_run_transition",
+        "Недопустимый переход статуса run.",
+        "Invalid run status transition.";
+    InvalidRunTitle => BAD_REQUEST, "invalid_run_title",
+        "Название run не должно быть пустым (до 500 символов).",
+        "The run title must not be empty (up to 500 characters).";
+    InvalidRunMilestone => BAD_REQUEST, "invalid_run_milestone",
+        "Milestone run — до 200 символов.",
+        "The run milestone is limited to 200 characters.";
+    InvalidRunDescription => BAD_REQUEST, "invalid_run_description",
+        "Описание run — до 20000 символов.",
+        "The run description is limited to 20000 characters.";
+    InvalidUnlockReason => BAD_REQUEST, "invalid_unlock_reason",
+        "Укажите причину разблокировки (до 1000 символов).",
+        "Give a reason for unlocking (up to 1000 characters).";
+    InvalidAssignee => BAD_REQUEST, "invalid_assignee",
+        "Исполнитель должен быть участником проекта с правом result.edit.",
+        "The assignee must be a project member with result.edit.";
+    InvalidExecutor => BAD_REQUEST, "invalid_executor",
+        "Исполнитель прогона должен быть участником проекта с правом result.edit.",
+        "The run executor must be a project member with result.edit.";
+    RunReadFailed => INTERNAL_SERVER_ERROR, "run_read_failed",
+        "Ошибка чтения run.",
+        "Failed to read the run.";
+    RunDbReadFailed => INTERNAL_SERVER_ERROR, "run_db_read_failed",
+        "Ошибка чтения run из БД.",
+        "Failed to read the run from the database.";
+    RunStatusReadFailed => INTERNAL_SERVER_ERROR, "run_status_read_failed",
+        "Ошибка чтения run status.",
+        "Failed to read the run status.";
+    InboundEmailDisabled => NOT_FOUND, "inbound_email_disabled",
+        "Приём ответов на письма не настроен.",
+        "Inbound email is not configured.";
+    InvalidInboundEmailSecret => UNAUTHORIZED, "invalid_inbound_email_secret",
+        "Некорректный secret webhook входящей почты.",
+        "Invalid inbound email webhook secret.";
+    InboundEmailSaveFailed => INTERNAL_SERVER_ERROR, "inbound_email_save_failed",
+        "Не удалось сохранить входящее письмо.",
+        "Failed to store the inbound email.";
+    TelegramDisabled => NOT_FOUND, "telegram_disabled",
+        "Telegram-бот не настроен.",
+        "The Telegram bot is not configured.";
+    TelegramUnavailable => BAD_GATEWAY, "telegram_unavailable",
+        "Telegram API недоступен.",
+        "The Telegram API is unavailable.";
+    TelegramLinkReadFailed => INTERNAL_SERVER_ERROR, "telegram_link_read_failed",
+        "Ошибка чтения привязки Telegram.",
+        "Failed to read the Telegram link.";
+    TelegramLinkFailed => INTERNAL_SERVER_ERROR, "telegram_link_failed",
+        "Не удалось изменить привязку Telegram.",
+        "Failed to update the Telegram link.";
+    TelegramChatNotLinked => FORBIDDEN, "telegram_chat_not_linked",
+        "Этот чат не привязан к учётной записи Uran.",
+        "This chat is not linked to a Uran account.";
+    InvalidGroupBy => BAD_REQUEST, "invalid_group_by",
+        "Некорректный groupBy. Ожидается suite|tag|assignee.",
+        "Invalid groupBy. Expected suite|tag|assignee.";
+    RunItemsReadFailed => INTERNAL_SERVER_ERROR, "run_items_read_failed",
+        "Ошибка чтения run items.",
+        "Failed to read run items.";
+    RunItemLookupFailed => INTERNAL_SERVER_ERROR, "run_item_lookup_failed",
+        "Ошибка чтения run item.",
+        "Failed to read the run item.";
+    RunItemReadFailed => INTERNAL_SERVER_ERROR, "run_item_read_failed",
+        "Ошибка чтения run_item.",
+        "Failed to read the run item.";
+    RunsListFailed => INTERNAL_SERVER_ERROR, "runs_list_failed",
+        "Ошибка чтения списка runs.",
+        "Failed to list runs.";
+    RunSummaryFailed => INTERNAL_SERVER_ERROR, "run_summary_failed",
+        "Ошибка расчёта сводки run.",
+        "Failed to compute the run summary.";
+    RunCreateFailed => INTERNAL_SERVER_ERROR, "run_create_failed",
+        "Не удалось создать run.",
+        "Failed to create the run.";
+    RunCreatedNotFound => INTERNAL_SERVER_ERROR, "run_created_not_found",
+        "Run создан, но не найден.",
+        "The run was created but could not be found.";
+    RunSuiteItemsFailed => INTERNAL_SERVER_ERROR, "run_suite_items_failed",
+        "Не удалось добавить тесты набора в run.",
+        "Failed to add the suite tests to the run.";
+    RunCloneFailed => INTERNAL_SERVER_ERROR, "run_clone_failed",
+        "Не удалось скопировать run.",
+        "Failed to copy the run.";
+    ResultCreateFailed => INTERNAL_SERVER_ERROR, "result_create_failed",
+        "Не удалось создать run_result.",
+        "Failed to create the run result.";
+    ResultSaveFailed => INTERNAL_SERVER_ERROR, "result_save_failed",
+        "Не удалось сохранить результат.",
+        "Failed to save the result.";
+    RunItemsAddFailed => INTERNAL_SERVER_ERROR, "run_items_add_failed",
+        "Не удалось добавить пункты в run.",
+        "Failed to add items to the run.";
+    RunItemsRenumberFailed => INTERNAL_SERVER_ERROR, "run_items_renumber_failed",
+        "Не удалось пересчитать позиции run items.",
+        "Failed to renumber run item positions.";
+    RunItemDeleteFailed => INTERNAL_SERVER_ERROR, "run_item_delete_failed",
+        "Не удалось удалить пункт run.",
+        "Failed to delete the run item.";
+    RunItemsReorderFailed => INTERNAL_SERVER_ERROR, "run_items_reorder_failed",
+        "Не удалось изменить порядок пунктов.",
+        "Failed to reorder the items.";
+    L0CoverageCheckFailed => INTERNAL_SERVER_ERROR, "l0_coverage_check_failed",
+        "Ошибка проверки L0 покрытия.",
+        "Failed to check L0 coverage.";
+    L0ResultsCheckFailed => INTERNAL_SERVER_ERROR, "l0_results_check_failed",
+        "Ошибка проверки L0 результатов.",
+        "Failed to check L0 results.";
+    RunStatusUpdateFailed => INTERNAL_SERVER_ERROR, "run_status_update_failed",
+        "Не удалось обновить статус run.",
+        "Failed to update the run status.";
+    RunUpdateFailed => INTERNAL_SERVER_ERROR, "run_update_failed",
+        "Не удалось обновить run.",
+        "Failed to update the run.";
+    AssigneeUpdateFailed => INTERNAL_SERVER_ERROR, "assignee_update_failed",
+        "Не удалось назначить исполнителя.",
+        "Failed to set the assignee.";
+    ExecutorUpdateFailed => INTERNAL_SERVER_ERROR, "executor_update_failed",
+        "Не удалось передать прогон другому исполнителю.",
+        "Failed to hand the run over to another executor.";
+    AssignmentsReadFailed => INTERNAL_SERVER_ERROR, "assignments_read_failed",
+        "Ошибка чтения назначений.",
+        "Failed to read assignments.";
+    DashboardReadFailed => INTERNAL_SERVER_ERROR, "dashboard_read_failed",
+        "Не удалось собрать сводку по вашей работе.",
+        "Failed to load your dashboard.";
+    InvalidElapsedSeconds => BAD_REQUEST, "invalid_elapsed_seconds",
+        "elapsedSeconds должен быть от 0 до 86400.",
+        "elapsedSeconds must be between 0 and 86400.";
+    InvalidAnalyticsPeriod => BAD_REQUEST, "invalid_analytics_period",
+        "days должен быть от 1 до 365.",
+        "days must be between 1 and 365.";
+    AnalyticsFailed => INTERNAL_SERVER_ERROR, "analytics_failed",
+        "Не удалось посчитать аналитику проекта.",
+        "Failed to compute project analytics.";
+    InvalidReportPeriod => BAD_REQUEST, "invalid_report_period",
+        "Период отчёта: from не позже to, не больше 365 дней.",
+        "Report period: from must not be after to, at most 365 days.";
+    InvalidReportFormat => BAD_REQUEST, "invalid_report_format",
+        "Некорректный формат. Ожидается json|csv.",
+        "Invalid format. Expected json|csv.";
+    ReportScopeRequired => FORBIDDEN, "report_scope_required",
+        "Сводный отчёт доступен администраторам системы и организаций.",
+        "The overview report is available to system and organization admins.";
+    ReportFailed => INTERNAL_SERVER_ERROR, "report_failed",
+        "Не удалось собрать сводный отчёт.",
+        "Failed to build the overview report.";
+    InvalidScheduleName => BAD_REQUEST, "invalid_schedule_name",
+        "Название расписания и заголовок прогонов: до 200 символов, название не пустое.",
+        "Schedule name and run title: up to 200 characters, the name must not be empty.";
+    InvalidCronExpression => BAD_REQUEST, "invalid_cron_expression",
+        "Некорректное cron-выражение. Ожидается 5 полей (минута час день месяц день_недели), например 0 3 * * 1-5.",
+        "Invalid cron expression. Expected 5 fields (minute hour day month weekday), e.g. 0 3 * * 1-5.";
+    RunTemplateEmpty => CONFLICT, "run_template_empty",
+        "В шаблоне прогона нет тестов.",
+        "The run template has no test cases.";
+    ScheduleAuthorRemoved => CONFLICT, "schedule_author_removed",
+        "Автор расписания удалён; сохраните расписание заново от имени другого пользователя.",
+        "The schedule author was removed; save the schedule again as another user.";
+    ScheduleNotFound => NOT_FOUND, "schedule_not_found",
+        "Расписание не найдено.",
+        "Schedule not found.";
+    SchedulesReadFailed => INTERNAL_SERVER_ERROR, "schedules_read_failed",
+        "Ошибка чтения расписаний.",
+        "Failed to read schedules.";
+    ScheduleSaveFailed => INTERNAL_SERVER_ERROR, "schedule_save_failed",
+        "Не удалось сохранить расписание.",
+        "Failed to save the schedule.";
+    ScheduleRunFailed => INTERNAL_SERVER_ERROR, "schedule_run_failed",
+        "Не удалось создать прогон по расписанию.",
+        "Failed to create the scheduled run.";
+    // Export, reports and import
+    RunDiffProjectMismatch => BAD_REQUEST, "run_diff_project_mismatch",
+        "Сравнивать можно только run одного проекта.",
+        "Only runs of the same project can be compared.";
+    RunDiffFailed => INTERNAL_SERVER_ERROR, "run_diff_failed",
+        "Не удалось сравнить run.",
+        "Failed to compare the runs.";
+    InvalidExportFormat => BAD_REQUEST, "invalid_export_format",
+        "Некорректный формат. Ожидается csv|xlsx|ndjson.",
+        "Invalid format. Expected csv|xlsx|ndjson.";
+    InvalidStreamExportFormat => BAD_REQUEST, "invalid_export_format",
+        "Некорректный формат. Ожидается csv|ndjson.",
+        "Invalid format. Expected csv|ndjson.";
+    ExportFailed => INTERNAL_SERVER_ERROR, "export_failed",
+        "Не удалось сформировать экспорт.",
+        "Failed to build the export.";
+    ReportFontMissing => INTERNAL_SERVER_ERROR, "report_font_missing",
+        "Не найден шрифт для PDF отчёта (REPORT_FONT_PATH).",
+        "PDF report font not found (REPORT_FONT_PATH).";
+    ReportRenderFailed => INTERNAL_SERVER_ERROR, "report_render_failed",
+        "Не удалось сформировать PDF отчёт.",
+        "Failed to render the PDF report.";
+    InvalidJunitXml => BAD_REQUEST, "invalid_junit_xml",
+        "Некорректный JUnit XML.",
+        "Invalid JUnit XML.";
+    JunitXmlEmpty => BAD_REQUEST, "junit_xml_empty",
+        "В JUnit XML нет ни одного testcase.",
+        "The JUnit XML contains no testcase.";
+    JunitImportFailed => INTERNAL_SERVER_ERROR, "junit_import_failed",
+        "Не удалось импортировать JUnit отчёт.",
+        "Failed to import the JUnit report.";
+    InvalidAllureArchive => BAD_REQUEST, "invalid_allure_archive",
+        "Файл не является zip-архивом результатов Allure.",
+        "The file is not a zip archive of Allure results.";
+    AllureArchiveEmpty => BAD_REQUEST, "allure_archive_empty",
+        "В архиве нет ни одного *-result.json.",
+        "The archive contains no *-result.json file.";
+    AllureArchiveTooLarge => PAYLOAD_TOO_LARGE, "allure_archive_too_large",
+        "Архив Allure распаковывается больше чем в 256 MiB.",
+        "The Allure archive unpacks to more than 256 MiB.";
+    AllureRunCreateRejected => BAD_REQUEST, "allure_run_create_rejected",
+        "Не удалось создать run. Проверь проект.",
+        "Failed to create the run. Check the project.";
+    AllureImportFailed => INTERNAL_SERVER_ERROR, "allure_import_failed",
+        "Не удалось импортировать результаты Allure.",
+        "Failed to import the Allure results.";
+    UnsupportedReportFormat => NOT_FOUND, "unsupported_report_format",
+        "Неизвестный формат отчёта. Доступны junit, allure, playwright и cucumber.",
+        "Unknown report format. Use junit, allure, playwright or cucumber.";
+    InvalidPlaywrightReport => BAD_REQUEST, "invalid_playwright_report",
+        "Некорректный JSON отчёт Playwright.",
+        "Invalid Playwright JSON report.";
+    PlaywrightReportEmpty => BAD_REQUEST, "playwright_report_empty",
+        "В отчёте Playwright нет ни одного теста.",
+        "The Playwright report contains no test.";
+    InvalidCucumberMessages => BAD_REQUEST, "invalid_cucumber_messages",
+        "Некорректный поток сообщений Cucumber (NDJSON).",
+        "Invalid Cucumber message stream (NDJSON).";
+    CucumberMessagesEmpty => BAD_REQUEST, "cucumber_messages_empty",
+        "В сообщениях Cucumber нет ни одного выполненного сценария.",
+        "The Cucumber messages contain no executed scenario.";
+    ReportRunCreateRejected => BAD_REQUEST, "report_run_create_rejected",
+        "Не удалось создать run. Проверь проект.",
+        "Failed to create the run. Check the project.";
+    ReportImportFailed => INTERNAL_SERVER_ERROR, "report_import_failed",
+        "Не удалось импортировать отчёт.",
+        "Failed to import the report.";
+    UnsupportedProjectBundle => BAD_REQUEST, "unsupported_project_bundle",
+        "Неподдерживаемый формат или версия пакета проекта.",
+        "Unsupported project bundle format or version.";
+    InvalidProjectBundle => BAD_REQUEST, "invalid_project_bundle",
+        "Пакет проекта ссылается на отсутствующие в нём сущности или повторяет id.",
+        "The project bundle references entities it does not contain or repeats an id.";
+    ProjectBundleRejected => BAD_REQUEST, "project_bundle_rejected",
+        "Данные пакета проекта не прошли проверки базы данных.",
+        "The project bundle data failed database checks.";
+    ProjectImportFailed => INTERNAL_SERVER_ERROR, "project_import_failed",
+        "Не удалось импортировать проект.",
+        "Failed to import the project.";
+    // Attachments and defects
+    AttachmentNotFound => NOT_FOUND, "attachment_not_found",
+        "Вложение не найдено.",
+        "Attachment not found.";
+    AttachmentFileMissing => NOT_FOUND, "attachment_file_missing",
+        "Файл вложения не найден в хранилище.",
+        "Attachment file not found in storage.";
+    InvalidMultipart => BAD_REQUEST, "invalid_multipart",
+        "Некорректное multipart-тело запроса.",
+        "Invalid multipart request body.";
+    AttachmentTypeNotAllowed => UNSUPPORTED_MEDIA_TYPE, "attachment_type_not_allowed",
+        "Недопустимый тип файла вложения.",
+        "Attachment file type is not allowed.";
+    AttachmentUploadReadFailed => BAD_REQUEST, "attachment_upload_read_failed",
+        "Ошибка чтения файла вложения.",
+        "Failed to read the attachment file.";
+    AttachmentTooLarge => PAYLOAD_TOO_LARGE, "attachment_too_large",
+        "Файл вложения превышает допустимый размер.",
+        "The attachment file exceeds the size limit.";
+    StorageQuotaExceeded => INSUFFICIENT_STORAGE, "storage_quota_exceeded",
+        "Превышена квота хранилища проекта. Удалите ненужные вложения или обратитесь к администратору.",
+        "The project storage quota is exceeded. Delete unneeded attachments or contact an administrator.";
+    InvalidStorageQuota => BAD_REQUEST, "invalid_storage_quota",
+        "quotaBytes должен быть неотрицательным числом или null.",
+        "quotaBytes must be a non-negative number or null.";
+    StorageUsageReadFailed => INTERNAL_SERVER_ERROR, "storage_usage_read_failed",
+        "Не удалось посчитать занятое место проекта.",
+        "Failed to compute the project storage usage.";
+    StorageQuotaSaveFailed => INTERNAL_SERVER_ERROR, "storage_quota_save_failed",
+        "Не удалось сохранить квоту хранилища.",
+        "Failed to save the storage quota.";
+    InvalidRetentionPolicy => BAD_REQUEST, "invalid_retention_policy",
+        "runRetentionMonths должен быть от 1 до 1200, attachmentRetentionDays — от 1 до 36500, или null.",
+        "runRetentionMonths must be between 1 and 1200 and attachmentRetentionDays between 1 and 36500, or null.";
+    RetentionReadFailed => INTERNAL_SERVER_ERROR, "retention_read_failed",
+        "Не удалось загрузить политику хранения.",
+        "Failed to load the retention policy.";
+    RetentionSaveFailed => INTERNAL_SERVER_ERROR, "retention_save_failed",
+        "Не удалось сохранить политику хранения.",
+        "Failed to save the retention policy.";
+    AttachmentFileRequired => BAD_REQUEST, "attachment_file_required",
+        "Поле file обязательно.",
+        "The file field is required.";
+    AttachmentEmpty => BAD_REQUEST, "attachment_empty",
+        "Файл вложения пуст.",
+        "The attachment file is empty.";
+    AttachmentReadFailed => INTERNAL_SERVER_ERROR, "attachment_read_failed",
+        "Ошибка чтения вложения.",
+        "Failed to read the attachment.";
+    AttachmentsReadFailed => INTERNAL_SERVER_ERROR, "attachments_read_failed",
+        "Ошибка чтения вложений.",
+        "Failed to read attachments.";
+    AttachmentStoreFailed => INTERNAL_SERVER_ERROR, "attachment_store_failed",
+        "Не удалось сохранить файл вложения.",
+        "Failed to store the attachment file.";
+    AttachmentSaveFailed => INTERNAL_SERVER_ERROR, "attachment_save_failed",
+        "Не удалось сохранить метаданные вложения.",
+        "Failed to save attachment metadata.";
+    AttachmentDeleteFailed => INTERNAL_SERVER_ERROR, "attachment_delete_failed",
+        "Не удалось удалить вложение.",
+        "Failed to delete the attachment.";
+    InvalidIssueTracker => BAD_REQUEST, "invalid_issue_tracker",
+        "Некорректный трекер. Ожидается jira|github|gitlab.",
+        "Invalid tracker. Expected jira|github|gitlab.";
+    InvalidTrackerUrl => BAD_REQUEST, "invalid_tracker_url",
+        "baseUrl должен начинаться с http:// или https://.",
+        "baseUrl must start with http:// or https://.";
+    IssueTrackerRejected => BAD_REQUEST, "issue_tracker_rejected",
+        "Не удалось сохранить настройки трекера.",
+        "Failed to save the tracker settings.";
+    InvalidDefectUrl => BAD_REQUEST, "invalid_defect_url",
+        "Некорректная ссылка на дефект.",
+        "Invalid defect link.";
+    InvalidDefectKey => BAD_REQUEST, "invalid_defect_key",
+        "Некорректный ключ дефекта.",
+        "Invalid defect key.";
+    ResultNotFound => NOT_FOUND, "result_not_found",
+        "Результат run_item не найден.",
+        "Run item result not found.";
+    DefectRequiresFail => CONFLICT, "defect_requires_fail",
+        "Дефект можно привязать только к результату fail.",
+        "A defect can only be linked to a fail result.";
+    DefectAlreadyLinked => CONFLICT, "defect_already_linked",
+        "Этот дефект уже привязан к результату.",
+        "This defect is already linked to the result.";
+    DefectNotFound => NOT_FOUND, "defect_not_found",
+        "Дефект не найден.",
+        "Defect not found.";
+    RunDefectsReadFailed => INTERNAL_SERVER_ERROR, "run_defects_read_failed",
+        "Ошибка чтения дефектов run.",
+        "Failed to read run defects.";
+    IssueTrackerReadFailed => INTERNAL_SERVER_ERROR, "issue_tracker_read_failed",
+        "Ошибка чтения настроек трекера.",
+        "Failed to read the tracker settings.";
+    ResultReadFailed => INTERNAL_SERVER_ERROR, "result_read_failed",
+        "Ошибка чтения результата.",
+        "Failed to read the result.";
+    DefectLinkFailed => INTERNAL_SERVER_ERROR, "defect_link_failed",
+        "Не удалось привязать дефект.",
+        "Failed to link the defect.";
+    DefectReadFailed => INTERNAL_SERVER_ERROR, "defect_read_failed",
+        "Ошибка чтения дефекта.",
+        "Failed to read the defect.";
+    DefectUnlinkFailed => INTERNAL_SERVER_ERROR, "defect_unlink_failed",
+        "Не удалось отвязать дефект.",
+        "Failed to unlink the defect.";
+    InvalidJiraProjectKey => BAD_REQUEST, "invalid_jira_project_key",
+        "Некорректный ключ проекта Jira (латинские заглавные буквы, цифры и _, например QA).",
+        "Invalid Jira project key (uppercase letters, digits and _, e.g. QA).";
+    InvalidJiraIssueType => BAD_REQUEST, "invalid_jira_issue_type",
+        "Тип задачи Jira должен содержать от 1 до 100 символов.",
+        "The Jira issue type must be 1 to 100 characters.";
+    InvalidJiraSummary => BAD_REQUEST, "invalid_jira_summary",
+        "Заголовок задачи должен содержать от 1 до 255 символов.",
+        "The issue summary must be 1 to 255 characters.";
+    JiraTokenRequired => BAD_REQUEST, "jira_token_required",
+        "Укажите apiToken для подключения Jira.",
+        "apiToken is required to connect Jira.";
+    SecretsKeyMissing => SERVICE_UNAVAILABLE, "secrets_key_missing",
+        "На сервере не задан SECRETS_KEY, хранить токены интеграций нельзя.",
+        "SECRETS_KEY is not set on the server, integration tokens cannot be stored.";
+    SecretSealFailed => INTERNAL_SERVER_ERROR, "secret_seal_failed",
+        "Не удалось зашифровать токен интеграции.",
+        "Failed to encrypt the integration token.";
+    JiraNotConfigured => CONFLICT, "jira_not_configured",
+        "Для проекта не настроено подключение к Jira.",
+        "Jira is not connected for the project.";
+    JiraTokenUnreadable => CONFLICT, "jira_token_unreadable",
+        "Токен Jira не расшифровывается (сменился SECRETS_KEY?), сохраните подключение заново.",
+        "The Jira token cannot be decrypted (was SECRETS_KEY changed?); save the connection again.";
+    JiraRequestFailed => BAD_GATEWAY, "jira_request_failed",
+        "Jira отклонила запрос или недоступна.",
+        "Jira rejected the request or is unavailable.";
+    JiraConnectionReadFailed => INTERNAL_SERVER_ERROR, "jira_connection_read_failed",
+        "Ошибка чтения подключения к Jira.",
+        "Failed to read the Jira connection.";
+    JiraConnectionSaveFailed => INTERNAL_SERVER_ERROR, "jira_connection_save_failed",
+        "Не удалось сохранить подключение к Jira.",
+        "Failed to save the Jira connection.";
+    InvalidCiProvider => BAD_REQUEST, "invalid_ci_provider",
+        "Некорректный provider. Ожидается github|gitlab.",
+        "Invalid provider. Expected github|gitlab.";
+    InvalidCiApiUrl => BAD_REQUEST, "invalid_ci_api_url",
+        "apiUrl должен начинаться с http:// или https://.",
+        "apiUrl must start with http:// or https://.";
+    InvalidCiRepository => BAD_REQUEST, "invalid_ci_repository",
+        "Некорректный репозиторий: owner/repo для GitHub, путь проекта для GitLab.",
+        "Invalid repository: owner/repo for GitHub, the project path for GitLab.";
+    InvalidCiTokenType => BAD_REQUEST, "invalid_ci_token_type",
+        "Некорректный tokenType. Ожидается personal|oauth.",
+        "Invalid tokenType. Expected personal|oauth.";
+    InvalidCiStatusContext => BAD_REQUEST, "invalid_ci_status_context",
+        "Название статуса должно содержать от 1 до 100 символов.",
+        "The status name must be 1 to 100 characters.";
+    CiTokenRequired => BAD_REQUEST, "ci_token_required",
+        "Укажите token для подключения репозитория.",
+        "token is required to connect the repository.";
+    InvalidCommitSha => BAD_REQUEST, "invalid_commit_sha",
+        "commitSha — hex-хэш коммита от 7 до 64 символов.",
+        "commitSha must be a hex commit hash of 7 to 64 characters.";
+    CiNotConfigured => CONFLICT, "ci_not_configured",
+        "Для проекта не настроен репозиторий CI.",
+        "No CI repository is connected for the project.";
+    CiConnectionReadFailed => INTERNAL_SERVER_ERROR, "ci_connection_read_failed",
+        "Ошибка чтения подключения к репозиторию.",
+        "Failed to read the repository connection.";
+    CiConnectionSaveFailed => INTERNAL_SERVER_ERROR, "ci_connection_save_failed",
+        "Не удалось сохранить подключение к репозиторию.",
+        "Failed to save the repository connection.";
+    // Background jobs
+    JobNotFound => NOT_FOUND, "job_not_found",
+        "Задача не найдена.",
+        "Job not found.";
+    JobFileNotReady => CONFLICT, "job_file_not_ready",
+        "Файл задачи ещё не готов или уже удалён.",
+        "The job file is not ready yet or was already deleted.";
+    JobsReadFailed => INTERNAL_SERVER_ERROR, "jobs_read_failed",
+        "Ошибка чтения задач.",
+        "Failed to read jobs.";
+    JobQueueFailed => INTERNAL_SERVER_ERROR, "job_queue_failed",
+        "Не удалось поставить задачу в очередь.",
+        "Failed to queue the job.";
+    // Comments
+    InvalidCommentId => BAD_REQUEST, "invalid_comment_id",
+        "Некорректный id комментария.",
+        "Invalid comment id.";
+    CommentEmpty => BAD_REQUEST, "comment_empty",
+        "Текст комментария не может быть пустым.",
+        "The comment text cannot be empty.";
+    CommentTooLong => BAD_REQUEST, "comment_too_long",
+        "Комментарий длиннее 5000 символов.",
+        "The comment is longer than 5000 characters.";
+    CommentNotFound => NOT_FOUND, "comment_not_found",
+        "Комментарий не найден.",
+        "Comment not found.";
+    CommentParentNotFound => BAD_REQUEST, "comment_parent_not_found",
+        "Комментарий, на который отвечают, не найден в этом обсуждении.",
+        "The comment being replied to is not in this thread.";
+    CommentEditForbidden => FORBIDDEN, "comment_edit_forbidden",
+        "Редактировать комментарий может только автор.",
+        "Only the author can edit the comment.";
+    CommentsReadFailed => INTERNAL_SERVER_ERROR, "comments_read_failed",
+        "Не удалось получить комментарии.",
+        "Failed to load comments.";
+    CommentSaveFailed => INTERNAL_SERVER_ERROR, "comment_save_failed",
+        "Не удалось сохранить комментарий.",
+        "Failed to save the comment.";
+    CommentDeleteFailed => INTERNAL_SERVER_ERROR, "comment_delete_failed",
+        "Не удалось удалить комментарий.",
+        "Failed to delete the comment.";
+    // FAIL reasons
+    FailReasonNotAllowed => BAD_REQUEST, "fail_reason_not_allowed",
+        "Неизвестная или неактивная причина FAIL для этого проекта.",
+        "Unknown or inactive FAIL reason for this project.";
+    InvalidFailReasonCode => BAD_REQUEST, "invalid_fail_reason_code",
+        "Код причины: 2-64 символа a-z, 0-9 или _.",
+        "Reason code: 2-64 characters a-z, 0-9 or _.";
+    InvalidFailReasonTitle => BAD_REQUEST, "invalid_fail_reason_title",
+        "Название причины должно быть от 1 до 200 символов.",
+        "Reason title must be 1 to 200 characters long.";
+    InvalidColor => BAD_REQUEST, "invalid_color",
+        "Цвет должен быть в формате #rrggbb.",
+        "Color must be in #rrggbb format.";
+    FailReasonRejected => BAD_REQUEST, "fail_reason_rejected",
+        "Не удалось создать причину FAIL.",
+        "Failed to create the FAIL reason.";
+    FailReasonExists => CONFLICT, "fail_reason_exists",
+        "Причина с таким кодом уже есть в проекте.",
+        "A reason with this code already exists in the project.";
+    FailReasonNotFound => NOT_FOUND, "fail_reason_not_found",
+        "Причина FAIL не найдена.",
+        "FAIL reason not found.";
+    FailReasonInUse => CONFLICT, "fail_reason_in_use",
+        "Причина уже используется в результатах, её можно только деактивировать.",
+        "The reason is used in results and can only be deactivated.";
+    FailReasonCheckFailed => INTERNAL_SERVER_ERROR, "fail_reason_check_failed",
+        "Ошибка проверки причины FAIL.",
+        "Failed to check the FAIL reason.";
+    FailReasonReadFailed => INTERNAL_SERVER_ERROR, "fail_reason_read_failed",
+        "Ошибка чтения причины FAIL.",
+        "Failed to read the FAIL reason.";
+    FailReasonsLoadFailed => INTERNAL_SERVER_ERROR, "fail_reasons_load_failed",
+        "Не удалось загрузить причины FAIL.",
+        "Failed to load FAIL reasons.";
+    FailReasonCreateFailed => INTERNAL_SERVER_ERROR, "fail_reason_create_failed",
+        "Не удалось создать причину FAIL.",
+        "Failed to create the FAIL reason.";
+    FailReasonUpdateFailed => INTERNAL_SERVER_ERROR, "fail_reason_update_failed",
+        "Не удалось обновить причину FAIL.",
+        "Failed to update the FAIL reason.";
+    FailReasonDeleteFailed => INTERNAL_SERVER_ERROR, "fail_reason_delete_failed",
+        "Не удалось удалить причину FAIL.",
+        "Failed to delete the FAIL reason.";
+    // Test library and requirements
+    InvalidSuiteTitle => BAD_REQUEST, "invalid_suite_title",
+        "Название набора должно быть от 2 до 200 символов.",
+        "Suite title must be 2 to 200 characters long.";
+    SuiteNotFound => NOT_FOUND, "suite_not_found",
+        "Набор тестов не найден.",
+        "Test suite not found.";
+    SuiteNotInProject => NOT_FOUND, "suite_not_in_project",
+        "Набор тестов не найден в проекте.",
+        "Test suite not found in the project.";
+    SuiteRejected => BAD_REQUEST, "suite_rejected",
+        "Не удалось создать набор тестов (проверь проект или дубликат key).",
+        "Failed to create the test suite (check the project or duplicate key).";
+    SuiteUpdateRejected => BAD_REQUEST, "suite_update_rejected",
+        "Не удалось обновить набор тестов.",
+        "Failed to update the test suite.";
+    SuiteCycle => CONFLICT, "suite_cycle",
+        "Нельзя переместить набор внутрь самого себя.",
+        "A suite cannot be moved inside itself.";
+    SuiteMoveRejected => BAD_REQUEST, "suite_move_rejected",
+        "Не удалось переместить набор тестов.",
+        "Failed to move the test suite.";
+    TestcaseNotFound => NOT_FOUND, "testcase_not_found",
+        "Тест-кейс не найден.",
+        "Test case not found.";
+    TestcaseSuiteProjectMismatch => BAD_REQUEST, "testcase_suite_project_mismatch",
+        "Тест-кейс и набор принадлежат разным проектам.",
+        "The test case and the suite belong to different projects.";
+    TestcaseKeyConflict => CONFLICT, "testcase_key_conflict",
+        "Не удалось перенести тест-кейс (дубликат key в целевом наборе).",
+        "Failed to move the test case (duplicate key in the target suite).";
+    SuiteReadFailed => INTERNAL_SERVER_ERROR, "suite_read_failed",
+        "Ошибка чтения набора тестов.",
+        "Failed to read the test suite.";
+    SuiteCreatedNotFound => INTERNAL_SERVER_ERROR, "suite_created_not_found",
+        "Набор создан, но не найден.",
+        "The suite was created but could not be found.";
+    SuitesReadFailed => INTERNAL_SERVER_ERROR, "suites_read_failed",
+        "Ошибка чтения наборов тестов.",
+        "Failed to read test suites.";
+    SuiteHierarchyCheckFailed => INTERNAL_SERVER_ERROR, "suite_hierarchy_check_failed",
+        "Ошибка проверки иерархии наборов.",
+        "Failed to check the suite hierarchy.";
+    TestcaseReadFailed => INTERNAL_SERVER_ERROR, "testcase_read_failed",
+        "Ошибка чтения тест-кейса.",
+        "Failed to read the test case.";
+    TestcasesReadFailed => INTERNAL_SERVER_ERROR, "testcases_read_failed",
+        "Ошибка чтения тест-кейсов.",
+        "Failed to read test cases.";
+    InvalidTestcaseSteps => BAD_REQUEST, "invalid_testcase_steps",
+        "Нужно от 1 до 200 шагов; действие шага обязательно, действие и ожидаемый результат — до 4000 символов.",
+        "1 to 200 steps are required; a step needs an action, and the action and expected result are limited to 4000 characters.";
+    InvalidTestcaseVersionText => BAD_REQUEST, "invalid_testcase_version_text",
+        "Описание и предусловия — до 20000 символов, комментарий к изменению — до 500.",
+        "Summary and preconditions are limited to 20000 characters, the change note to 500.";
+    TestcaseVersionsReadFailed => INTERNAL_SERVER_ERROR, "testcase_versions_read_failed",
+        "Ошибка чтения версий тест-кейса.",
+        "Failed to read test case versions.";
+    TestcaseVersionCreateFailed => INTERNAL_SERVER_ERROR, "testcase_version_create_failed",
+        "Не удалось создать версию тест-кейса.",
+        "Failed to create the test case version.";
+    InvalidMinScore => BAD_REQUEST, "invalid_min_score",
+        "minScore должен быть числом от 0 до 1.",
+        "minScore must be a number from 0 to 1.";
+    DuplicatesReadFailed => INTERNAL_SERVER_ERROR, "duplicates_read_failed",
+        "Не удалось найти похожие тест-кейсы.",
+        "Failed to look for similar test cases.";
+    InvalidMergeTargetId => BAD_REQUEST, "invalid_merge_target_id",
+        "Некорректный intoTestcaseId.",
+        "Invalid intoTestcaseId.";
+    MergeIntoSelf => BAD_REQUEST, "merge_into_self",
+        "Тест-кейс нельзя объединить с самим собой.",
+        "A test case cannot be merged into itself.";
+    MergeTargetNotFound => NOT_FOUND, "merge_target_not_found",
+        "intoTestcaseId: тест-кейс не найден.",
+        "intoTestcaseId: the test case does not exist.";
+    MergeTargetOtherProject => BAD_REQUEST, "merge_target_other_project",
+        "Объединять можно только тест-кейсы одного проекта.",
+        "Only test cases of the same project can be merged.";
+    MergeArchivedTestcase => CONFLICT, "merge_archived_testcase",
+        "Один из тест-кейсов уже в архиве.",
+        "One of the test cases is already archived.";
+    MergeTargetHasNoVersion => CONFLICT, "merge_target_has_no_version",
+        "У тест-кейса intoTestcaseId нет версий.",
+        "The intoTestcaseId test case has no versions.";
+    TestcaseMergeFailed => INTERNAL_SERVER_ERROR, "testcase_merge_failed",
+        "Не удалось объединить тест-кейсы.",
+        "Failed to merge the test cases.";
+    TestcaseArchived => CONFLICT, "testcase_archived",
+        "Тест-кейс в архиве: его нельзя добавить в прогон.",
+        "The test case is archived and cannot be added to a run.";
+    TestcaseAlreadyArchived => CONFLICT, "testcase_already_archived",
+        "Тест-кейс уже в архиве.",
+        "The test case is already archived.";
+    TestcaseNotArchived => CONFLICT, "testcase_not_archived",
+        "Тест-кейс не в архиве.",
+        "The test case is not archived.";
+    TestcaseArchiveFailed => INTERNAL_SERVER_ERROR, "testcase_archive_failed",
+        "Не удалось изменить архивный статус тест-кейса.",
+        "Failed to change the archive state of the test case.";
+    TestcaseUsageReadFailed => INTERNAL_SERVER_ERROR, "testcase_usage_read_failed",
+        "Ошибка чтения мест использования тест-кейса.",
+        "Failed to read where the test case is used.";
+    TestcaseUsedInRuns => CONFLICT, "testcase_used_in_runs",
+        "Версии тест-кейса есть в прогонах: его можно только архивировать.",
+        "Versions of the test case are used in runs; archive it instead.";
+    TestcaseUsedInTemplates => CONFLICT, "testcase_used_in_templates",
+        "Версии тест-кейса есть в шаблонах прогонов: сначала уберите их из шаблонов.",
+        "Versions of the test case are used in run templates; remove them from the templates first.";
+    TestcaseDeleteFailed => INTERNAL_SERVER_ERROR, "testcase_delete_failed",
+        "Не удалось удалить тест-кейс.",
+        "Failed to delete the test case.";
+    InvalidImportFormat => BAD_REQUEST, "invalid_import_format",
+        "Некорректный format. Ожидается csv|testrail.",
+        "Invalid format. Expected csv|testrail.";
+    ImportFileRequired => BAD_REQUEST, "import_file_required",
+        "Нужно передать файл в поле file.",
+        "A file is required in the file field.";
+    ImportFileEmpty => BAD_REQUEST, "import_file_empty",
+        "В файле нет тест-кейсов.",
+        "The file contains no test cases.";
+    ImportTooManyRows => BAD_REQUEST, "import_too_many_rows",
+        "Файл содержит больше 10000 строк.",
+        "The file has more than 10000 rows.";
+    InvalidImportMapping => BAD_REQUEST, "invalid_import_mapping",
+        "Некорректный mapping: колонка не найдена в заголовке файла или нет колонки title.",
+        "Invalid mapping: a column is missing from the file header or there is no title column.";
+    InvalidImportCsv => BAD_REQUEST, "invalid_import_csv",
+        "Не удалось прочитать заголовок CSV.",
+        "Failed to read the CSV header.";
+    InvalidTestrailXml => BAD_REQUEST, "invalid_testrail_xml",
+        "Некорректный XML экспорта TestRail.",
+        "Invalid TestRail XML export.";
+    ImportRowMalformed => BAD_REQUEST, "import_row_malformed",
+        "Строку CSV не удалось разобрать.",
+        "The CSV row could not be parsed.";
+    ImportRowTitleInvalid => BAD_REQUEST, "import_row_title_invalid",
+        "Название тест-кейса должно быть от 2 до 240 символов.",
+        "Test case title must be 2 to 240 characters long.";
+    ImportRowSectionInvalid => BAD_REQUEST, "import_row_section_invalid",
+        "Каждый уровень section должен быть от 2 до 200 символов.",
+        "Each section level must be 2 to 200 characters long.";
+    ImportRowInvalidRequired => BAD_REQUEST, "import_row_invalid_required",
+        "isRequired должен быть true/false, yes/no или 1/0.",
+        "isRequired must be true/false, yes/no or 1/0.";
+    ImportRowInvalidEstimate => BAD_REQUEST, "import_row_invalid_estimate",
+        "Оценка должна быть положительным числом минут или вида 1h 30m.",
+        "The estimate must be a positive number of minutes or look like 1h 30m.";
+    ImportRowInvalidComplexity => BAD_REQUEST, "import_row_invalid_complexity",
+        "complexity должна быть от 1 до 5.",
+        "complexity must be between 1 and 5.";
+    ImportRowKeyTaken => CONFLICT, "import_row_key_taken",
+        "Тест-кейс с таким key уже есть в наборе.",
+        "A test case with this key already exists in the suite.";
+    ImportReportNotFound => NOT_FOUND, "import_report_not_found",
+        "Отчёт об ошибках импорта не найден.",
+        "Import error report not found.";
+    ImportReportStoreFailed => INTERNAL_SERVER_ERROR, "import_report_store_failed",
+        "Не удалось сохранить отчёт об ошибках импорта.",
+        "Failed to store the import error report.";
+    TestcaseImportFailed => INTERNAL_SERVER_ERROR, "testcase_import_failed",
+        "Ошибка импорта тест-кейсов.",
+        "Failed to import test cases.";
+    GherkinFileRequired => BAD_REQUEST, "gherkin_file_required",
+        "Передайте хотя бы один файл .feature в поле file.",
+        "Upload at least one .feature file in the file field.";
+    GherkinPathRequired => BAD_REQUEST, "gherkin_path_required",
+        "У файла нет имени: путь к файлу нужен для повторного импорта.",
+        "The file has no name: the source path is needed for re-import.";
+    GherkinDuplicateFile => BAD_REQUEST, "gherkin_duplicate_file",
+        "Файл с таким путём уже передан в этом запросе.",
+        "A file with this path was already uploaded in this request.";
+    GherkinNotUtf8 => BAD_REQUEST, "gherkin_not_utf8",
+        "Файл .feature должен быть в кодировке UTF-8.",
+        "The .feature file must be UTF-8 encoded.";
+    GherkinLanguageUnsupported => BAD_REQUEST, "gherkin_language_unsupported",
+        "Поддерживаются только языки Gherkin en и ru.",
+        "Only the en and ru Gherkin languages are supported.";
+    GherkinFeatureMissing => BAD_REQUEST, "gherkin_feature_missing",
+        "Файл должен начинаться с Feature (Функция).",
+        "The file must start with a Feature.";
+    GherkinUnexpectedLine => BAD_REQUEST, "gherkin_unexpected_line",
+        "Строка не соответствует синтаксису Gherkin.",
+        "The line does not match Gherkin syntax.";
+    GherkinUnclosedDocString => BAD_REQUEST, "gherkin_unclosed_doc_string",
+        "Многострочный аргумент шага не закрыт.",
+        "The step doc string is not closed.";
+    GherkinDuplicateScenario => CONFLICT, "gherkin_duplicate_scenario",
+        "Сценарий с таким названием уже есть в этом файле.",
+        "A scenario with this name already exists in the file.";
+    GherkinImportFailed => INTERNAL_SERVER_ERROR, "gherkin_import_failed",
+        "Ошибка импорта файлов .feature.",
+        "Failed to import feature files.";
+    InvalidSearchQuery => BAD_REQUEST, "invalid_search_query",
+        "Поисковый запрос должен быть от 2 до 200 символов.",
+        "Search query must be 2 to 200 characters long.";
+    SearchFailed => INTERNAL_SERVER_ERROR, "search_failed",
+        "Ошибка поиска.",
+        "Search failed.";
+    InvalidRequirementKey => BAD_REQUEST, "invalid_requirement_key",
+        "Ключ требования должен быть от 1 до 64 символов.",
+        "Requirement key must be 1 to 64 characters long.";
+    InvalidRequirementTitle => BAD_REQUEST, "invalid_requirement_title",
+        "Название требования должно быть от 2 до 240 символов.",
+        "Requirement title must be 2 to 240 characters long.";
+    RequirementNotFound => NOT_FOUND, "requirement_not_found",
+        "Требование не найдено.",
+        "Requirement not found.";
+    RequirementTestcasesForeign => BAD_REQUEST, "requirement_testcases_foreign",
+        "Тест-кейсы должны принадлежать проекту требования.",
+        "Test cases must belong to the requirement project.";
+    RequirementRejected => BAD_REQUEST, "requirement_rejected",
+        "Не удалось создать требование (проверь проект или дубликат key).",
+        "Failed to create the requirement (check the project or duplicate key).";
+    RequirementUpdateRejected => BAD_REQUEST, "requirement_update_rejected",
+        "Не удалось обновить требование.",
+        "Failed to update the requirement.";
+    RequirementReadFailed => INTERNAL_SERVER_ERROR, "requirement_read_failed",
+        "Ошибка чтения требования.",
+        "Failed to read the requirement.";
+    RequirementsReadFailed => INTERNAL_SERVER_ERROR, "requirements_read_failed",
+        "Ошибка чтения требований.",
+        "Failed to read requirements.";
+    RequirementLinkFailed => INTERNAL_SERVER_ERROR, "requirement_link_failed",
+        "Не удалось связать требование с тест-кейсами.",
+        "Failed to link the requirement to test cases.";
+    RequirementCreateFailed => INTERNAL_SERVER_ERROR, "requirement_create_failed",
+        "Не удалось создать требование.",
+        "Failed to create the requirement.";
+    RequirementCreatedNotFound => INTERNAL_SERVER_ERROR, "requirement_created_not_found",
+        "Требование создано, но не найдено.",
+        "The requirement was created but could not be found.";
+    RequirementDeleteFailed => INTERNAL_SERVER_ERROR, "requirement_delete_failed",
+        "Не удалось удалить требование.",
+        "Failed to delete the requirement.";
+    TraceabilityFailed => INTERNAL_SERVER_ERROR, "traceability_failed",
+        "Ошибка построения матрицы трассируемости.",
+        "Failed to build the traceability matrix.";
+    InvalidTagName => BAD_REQUEST, "invalid_tag_name",
+        "Тег должен содержать от 1 до 100 символов без кавычек, скобок и запятых.",
+        "A tag must be 1 to 100 characters without quotes, parentheses or commas.";
+    TooManyTags => BAD_REQUEST, "too_many_tags",
+        "У тест-кейса может быть не более 50 тегов.",
+        "A test case can have at most 50 tags.";
+    InvalidTagQuery => BAD_REQUEST, "invalid_tag_query",
+        "Некорректный tagQuery: используйте теги, AND, OR, NOT и скобки (до 500 символов и 32 тегов).",
+        "Invalid tagQuery: use tags, AND, OR, NOT and parentheses (up to 500 characters and 32 tags).";
+    TagQueryNoMatches => BAD_REQUEST, "tag_query_no_matches",
+        "Ни один активный тест-кейс не подходит под tagQuery.",
+        "No active test case matches the tagQuery.";
+    TagNotFound => NOT_FOUND, "tag_not_found",
+        "Тег не используется в проекте.",
+        "The tag is not used in the project.";
+    TagsReadFailed => INTERNAL_SERVER_ERROR, "tags_read_failed",
+        "Ошибка чтения тегов.",
+        "Failed to read tags.";
+    TagsUpdateFailed => INTERNAL_SERVER_ERROR, "tags_update_failed",
+        "Не удалось обновить теги.",
+        "Failed to update tags.";
+    // Assets
+    AssetNotFound => NOT_FOUND, "asset_not_found",
+        "Asset не найден в проекте.",
+        "Asset not found in the project.";
+    AssetInactive => CONFLICT, "asset_inactive",
+        "Asset выведен из эксплуатации (isActive = false).",
+        "The asset is deactivated (isActive = false).";
+    AssetInUse => CONFLICT, "asset_in_use",
+        "Asset используется в прогонах; вместо удаления отключите его (isActive = false).",
+        "The asset is used by runs; deactivate it (isActive = false) instead of deleting.";
+    InvalidAssetName => BAD_REQUEST, "invalid_asset_name",
+        "Название asset должно быть от 1 до 200 символов.",
+        "Asset name must be 1 to 200 characters long.";
+    InvalidAssetType => BAD_REQUEST, "invalid_asset_type",
+        "Тип asset должен быть от 1 до 60 символов.",
+        "Asset type must be 1 to 60 characters long.";
+    InvalidAssetField => BAD_REQUEST, "invalid_asset_field",
+        "Версия, модель, серийный номер, площадка и стенд asset — не длиннее 200 символов.",
+        "Asset version, model, serial number, location and stand must be at most 200 characters.";
+    InvalidAssetMetadata => BAD_REQUEST, "invalid_asset_metadata",
+        "metadata должен быть JSON-объектом.",
+        "metadata must be a JSON object.";
+    AssetsReadFailed => INTERNAL_SERVER_ERROR, "assets_read_failed",
+        "Ошибка чтения asset.",
+        "Failed to read assets.";
+    AssetSaveFailed => INTERNAL_SERVER_ERROR, "asset_save_failed",
+        "Не удалось сохранить asset.",
+        "Failed to save the asset.";
+    AssetDeleteFailed => INTERNAL_SERVER_ERROR, "asset_delete_failed",
+        "Не удалось удалить asset.",
+        "Failed to delete the asset.";
+    // Custom fields
+    InvalidCustomFieldId => BAD_REQUEST, "invalid_custom_field_id",
+        "Некорректный field_id.",
+        "Invalid field_id.";
+    InvalidCustomFieldEntity => BAD_REQUEST, "invalid_custom_field_entity",
+        "Некорректный entity. Ожидается testcase|run.",
+        "Invalid entity. Expected testcase|run.";
+    InvalidCustomFieldKey => BAD_REQUEST, "invalid_custom_field_key",
+        "key поля: 2–64 символа a-z, 0-9, _, начинается с буквы.",
+        "Field key: 2–64 characters a-z, 0-9, _, starting with a letter.";
+    InvalidCustomFieldName => BAD_REQUEST, "invalid_custom_field_name",
+        "Название поля должно быть от 1 до 200 символов.",
+        "Field name must be 1 to 200 characters long.";
+    InvalidCustomFieldType => BAD_REQUEST, "invalid_custom_field_type",
+        "Некорректный fieldType. Ожидается text|number|boolean|date|select|multiselect.",
+        "Invalid fieldType. Expected text|number|boolean|date|select|multiselect.";
+    InvalidCustomFieldOptions => BAD_REQUEST, "invalid_custom_field_options",
+        "Для select/multiselect нужно от 1 до 100 различных вариантов до 200 символов, у других типов вариантов нет.",
+        "select/multiselect need 1 to 100 distinct options of up to 200 characters; other types take no options.";
+    CustomFieldExists => CONFLICT, "custom_field_exists",
+        "Поле с таким key уже есть.",
+        "A field with this key already exists.";
+    CustomFieldNotFound => NOT_FOUND, "custom_field_not_found",
+        "Поле не найдено.",
+        "Custom field not found.";
+    UnknownCustomField => BAD_REQUEST, "unknown_custom_field",
+        "В проекте нет поля с таким key.",
+        "The project has no field with this key.";
+    InvalidCustomFieldValue => BAD_REQUEST, "invalid_custom_field_value",
+        "Значение не подходит к типу поля или его вариантам.",
+        "The value does not match the field type or its options.";
+    CustomFieldRequired => BAD_REQUEST, "custom_field_required",
+        "Не заполнено обязательное поле.",
+        "A required field has no value.";
+    InvalidCustomFieldFilter => BAD_REQUEST, "invalid_custom_field_filter",
+        "customFields — JSON-объект «key поля → значение» с допустимыми значениями.",
+        "customFields must be a JSON object of field key to a valid value.";
+    CustomFieldFilterNeedsProject => BAD_REQUEST, "custom_field_filter_needs_project",
+        "Фильтр customFields работает только вместе с projectId.",
+        "The customFields filter requires projectId.";
+    CustomFieldsReadFailed => INTERNAL_SERVER_ERROR, "custom_fields_read_failed",
+        "Ошибка чтения полей.",
+        "Failed to read custom fields.";
+    CustomFieldCreateFailed => INTERNAL_SERVER_ERROR, "custom_field_create_failed",
+        "Не удалось создать поле.",
+        "Failed to create the custom field.";
+    CustomFieldUpdateFailed => INTERNAL_SERVER_ERROR, "custom_field_update_failed",
+        "Не удалось обновить поле.",
+        "Failed to update the custom field.";
+    CustomFieldDeleteFailed => INTERNAL_SERVER_ERROR, "custom_field_delete_failed",
+        "Не удалось удалить поле.",
+        "Failed to delete the custom field.";
+    CustomFieldValuesUpdateFailed => INTERNAL_SERVER_ERROR, "custom_field_values_update_failed",
+        "Не удалось сохранить значения полей.",
+        "Failed to save custom field values.";
+    // Saved filters
+    InvalidSavedFilterId => BAD_REQUEST, "invalid_saved_filter_id",
+        "Некорректный filterId.",
+        "Invalid filterId.";
+    InvalidSavedFilterTarget => BAD_REQUEST, "invalid_saved_filter_target",
+        "target должен быть runs или testcases.",
+        "target must be runs or testcases.";
+    InvalidSavedFilterName => BAD_REQUEST, "invalid_saved_filter_name",
+        "Название фильтра должно содержать от 1 до 200 символов.",
+        "The filter name must be 1 to 200 characters.";
+    InvalidSavedFilterParams => BAD_REQUEST, "invalid_saved_filter_params",
+        "params — объект со строковыми параметрами списка (runs: status, customFields; testcases: suiteId, customFields).",
+        "params must be an object of string list parameters (runs: status, customFields; testcases: suiteId, customFields).";
+    SavedFilterNotFound => NOT_FOUND, "saved_filter_not_found",
+        "Сохранённый фильтр не найден.",
+        "Saved filter not found.";
+    SavedFilterExists => CONFLICT, "saved_filter_exists",
+        "У вас уже есть фильтр с таким названием.",
+        "You already have a filter with this name.";
+    SavedFilterForbidden => FORBIDDEN, "saved_filter_forbidden",
+        "Изменять общий фильтр может только автор или владелец проекта.",
+        "Only the author or a project owner can change a shared filter.";
+    SavedFilterProjectMismatch => BAD_REQUEST, "saved_filter_project_mismatch",
+        "Фильтр сохранён в другом проекте.",
+        "The filter belongs to another project.";
+    SavedFiltersReadFailed => INTERNAL_SERVER_ERROR, "saved_filters_read_failed",
+        "Ошибка чтения сохранённых фильтров.",
+        "Failed to read saved filters.";
+    SavedFilterCreateFailed => INTERNAL_SERVER_ERROR, "saved_filter_create_failed",
+        "Не удалось сохранить фильтр.",
+        "Failed to save the filter.";
+    SavedFilterUpdateFailed => INTERNAL_SERVER_ERROR, "saved_filter_update_failed",
+        "Не удалось обновить фильтр.",
+        "Failed to update the filter.";
+    SavedFilterDeleteFailed => INTERNAL_SERVER_ERROR, "saved_filter_delete_failed",
+        "Не удалось удалить фильтр.",
+        "Failed to delete the filter.";
+    // Custom reports
+    InvalidReportId => BAD_REQUEST, "invalid_report_id",
+        "Некорректный reportId.",
+        "Invalid reportId.";
+    InvalidReportName => BAD_REQUEST, "invalid_report_name",
+        "Название отчёта: от 1 до 200 символов.",
+        "Report name: 1 to 200 characters.";
+    InvalidReportEntity => BAD_REQUEST, "invalid_report_entity",
+        "Некорректная сущность отчёта. Ожидается results|runs|testcases.",
+        "Invalid report entity. Expected results|runs|testcases.";
+    UnknownReportField => BAD_REQUEST, "unknown_report_field",
+        "Неизвестное поле отчёта.",
+        "Unknown report field.";
+    InvalidReportFilter => BAD_REQUEST, "invalid_report_filter",
+        "Некорректный фильтр отчёта: оператор не подходит к полю или значение неверного типа.",
+        "Invalid report filter: the operator does not fit the field or the value has a wrong type.";
+    InvalidReportColumns => BAD_REQUEST, "invalid_report_columns",
+        "Некорректные колонки отчёта: от 1 до 30 колонок, до 3 полей группировки и 20 фильтров; в сгруппированном отчёте колонки — только агрегаты.",
+        "Invalid report columns: 1 to 30 columns, up to 3 group-by fields and 20 filters; a grouped report may only have aggregate columns.";
+    InvalidReportRecipient => BAD_REQUEST, "invalid_report_recipient",
+        "Получатели отчёта: от 1 до 50 участников проекта.",
+        "Report recipients: 1 to 50 project members.";
+    ReportNotFound => NOT_FOUND, "report_not_found",
+        "Отчёт не найден.",
+        "Report not found.";
+    ReportExists => CONFLICT, "report_exists",
+        "У вас уже есть отчёт с таким названием.",
+        "You already have a report with this name.";
+    ReportForbidden => FORBIDDEN, "report_forbidden",
+        "Изменять общий отчёт может только автор или владелец проекта.",
+        "Only the author or a project owner can change a shared report.";
+    ReportsReadFailed => INTERNAL_SERVER_ERROR, "reports_read_failed",
+        "Ошибка чтения отчётов.",
+        "Failed to read reports.";
+    ReportCreateFailed => INTERNAL_SERVER_ERROR, "report_create_failed",
+        "Не удалось сохранить отчёт.",
+        "Failed to save the report.";
+    ReportUpdateFailed => INTERNAL_SERVER_ERROR, "report_update_failed",
+        "Не удалось обновить отчёт.",
+        "Failed to update the report.";
+    ReportDeleteFailed => INTERNAL_SERVER_ERROR, "report_delete_failed",
+        "Не удалось удалить отчёт.",
+        "Failed to delete the report.";
+    ReportRunFailed => INTERNAL_SERVER_ERROR, "report_run_failed",
+        "Не удалось выполнить отчёт.",
+        "Failed to run the report.";
+    // Notifications, webhooks and audit
+    InvalidNotificationKind => BAD_REQUEST, "invalid_notification_kind",
+        "Некорректный тип уведомления.",
+        "Invalid notification type.";
+    InvalidUnsubscribeLink => BAD_REQUEST, "invalid_unsubscribe_link",
+        "Некорректная ссылка отписки.",
+        "Invalid unsubscribe link.";
+    UnsubscribeLinkExpired => NOT_FOUND, "unsubscribe_link_expired",
+        "Ссылка отписки недействительна.",
+        "The unsubscribe link is no longer valid.";
+    NotificationPrefsLoadFailed => INTERNAL_SERVER_ERROR, "notification_prefs_load_failed",
+        "Ошибка загрузки настроек уведомлений.",
+        "Failed to load notification settings.";
+    NotificationPrefsSaveFailed => INTERNAL_SERVER_ERROR, "notification_prefs_save_failed",
+        "Ошибка сохранения настроек уведомлений.",
+        "Failed to save notification settings.";
+    DuplicateNotificationSetting => BAD_REQUEST, "duplicate_notification_setting",
+        "Событие или проект указаны в настройках уведомлений дважды.",
+        "An event or project is listed twice in the notification settings.";
+    InvalidNotificationId => BAD_REQUEST, "invalid_notification_id",
+        "Некорректный id уведомления.",
+        "Invalid notification id.";
+    TooManyNotificationIds => BAD_REQUEST, "too_many_notification_ids",
+        "За один запрос можно отметить не больше 500 уведомлений.",
+        "At most 500 notifications can be marked in one request.";
+    NotificationsReadFailed => INTERNAL_SERVER_ERROR, "notifications_read_failed",
+        "Ошибка чтения уведомлений.",
+        "Failed to read notifications.";
+    NotificationsUpdateFailed => INTERNAL_SERVER_ERROR, "notifications_update_failed",
+        "Не удалось отметить уведомления прочитанными.",
+        "Failed to mark notifications as read.";
+    InvalidWebhookUrl => BAD_REQUEST, "invalid_webhook_url",
+        "url должен начинаться с http:// или https://.",
+        "url must start with http:// or https://.";
+    InvalidWebhookEvent => BAD_REQUEST, "invalid_webhook_event",
+        "Некорректное событие. Ожидается run.created|run.done|result.updated|result.failed|member.added.",
+        "Invalid event. Expected run.created|run.done|result.updated|result.failed|member.added.";
+    WebhookEventsEmpty => BAD_REQUEST, "webhook_events_empty",
+        "Нужно выбрать хотя бы одно событие.",
+        "At least one event is required.";
+    WebhookSecretTooShort => BAD_REQUEST, "webhook_secret_too_short",
+        "secret должен быть не короче 16 символов.",
+        "secret must be at least 16 characters long.";
+    WebhookRejected => BAD_REQUEST, "webhook_rejected",
+        "Не удалось создать webhook.",
+        "Failed to create the webhook.";
+    WebhookNotFound => NOT_FOUND, "webhook_not_found",
+        "Webhook не найден.",
+        "Webhook not found.";
+    WebhooksReadFailed => INTERNAL_SERVER_ERROR, "webhooks_read_failed",
+        "Ошибка чтения webhooks.",
+        "Failed to read webhooks.";
+    WebhookReadFailed => INTERNAL_SERVER_ERROR, "webhook_read_failed",
+        "Ошибка чтения webhook.",
+        "Failed to read the webhook.";
+    WebhookDeleteFailed => INTERNAL_SERVER_ERROR, "webhook_delete_failed",
+        "Не удалось удалить webhook.",
+        "Failed to delete the webhook.";
+    DeliveriesReadFailed => INTERNAL_SERVER_ERROR, "deliveries_read_failed",
+        "Ошибка чтения доставок.",
+        "Failed to read deliveries.";
+    InvalidChatProvider => BAD_REQUEST, "invalid_chat_provider",
+        "Некорректный provider. Ожидается slack|mattermost.",
+        "Invalid provider. Expected slack|mattermost.";
+    InvalidChatEvent => BAD_REQUEST, "invalid_chat_event",
+        "Некорректное событие. Ожидается run_done|required_failed|run_assigned.",
+        "Invalid event. Expected run_done|required_failed|run_assigned.";
+    InvalidChatWebhookName => BAD_REQUEST, "invalid_chat_webhook_name",
+        "Название должно содержать от 1 до 200 символов.",
+        "The name must be 1 to 200 characters.";
+    InvalidChatTemplate => BAD_REQUEST, "invalid_chat_template",
+        "Шаблон: от 1 до 2000 символов, только плейсхолдеры своего события.",
+        "Template: 1 to 2000 characters using only the placeholders of its event.";
+    ChatWebhookNotFound => NOT_FOUND, "chat_webhook_not_found",
+        "Чат-webhook не найден.",
+        "Chat webhook not found.";
+    ChatWebhooksReadFailed => INTERNAL_SERVER_ERROR, "chat_webhooks_read_failed",
+        "Ошибка чтения чат-webhooks.",
+        "Failed to read chat webhooks.";
+    ChatWebhookUrlUnreadable => CONFLICT, "chat_webhook_url_unreadable",
+        "URL чат-webhook не расшифровывается (сменился SECRETS_KEY?), укажите его заново.",
+        "The chat webhook URL cannot be decrypted (was SECRETS_KEY changed?); set it again.";
+    ChatWebhookSaveFailed => INTERNAL_SERVER_ERROR, "chat_webhook_save_failed",
+        "Не удалось сохранить чат-webhook.",
+        "Failed to save the chat webhook.";
+    InvalidLastEventId => BAD_REQUEST, "invalid_last_event_id",
+        "Last-Event-ID должен быть номером события.",
+        "Last-Event-ID must be an event number.";
+    ProjectEventsReadFailed => INTERNAL_SERVER_ERROR, "project_events_read_failed",
+        "Ошибка чтения событий проекта.",
+        "Failed to read project events.";
+    AuditReadFailed => INTERNAL_SERVER_ERROR, "audit_read_failed",
+        "Ошибка чтения аудита.",
+        "Failed to read the audit log.";
+    // Milestones
+    InvalidMilestoneId => BAD_REQUEST, "invalid_milestone_id",
+        "Некорректный идентификатор вехи.",
+        "Invalid milestone id.";
+    InvalidMilestoneName => BAD_REQUEST, "invalid_milestone_name",
+        "Название вехи должно быть от 1 до 200 символов.",
+        "Milestone name must be 1 to 200 characters long.";
+    InvalidDueDate => BAD_REQUEST, "invalid_due_date",
+        "Срок должен быть датой в формате YYYY-MM-DD.",
+        "Due date must be a YYYY-MM-DD date.";
+    MilestoneExists => CONFLICT, "milestone_exists",
+        "Веха с таким названием уже есть в проекте.",
+        "A milestone with this name already exists in the project.";
+    MilestoneNotFound => NOT_FOUND, "milestone_not_found",
+        "Веха не найдена.",
+        "Milestone not found.";
+    MilestonesReadFailed => INTERNAL_SERVER_ERROR, "milestones_read_failed",
+        "Ошибка чтения вех.",
+        "Failed to read milestones.";
+    MilestoneWriteFailed => INTERNAL_SERVER_ERROR, "milestone_write_failed",
+        "Не удалось сохранить веху.",
+        "Failed to save the milestone.";
+    BurndownReadFailed => INTERNAL_SERVER_ERROR, "burndown_read_failed",
+        "Ошибка построения диаграммы сгорания.",
+        "Failed to build the burn-down chart.";
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        self.into_response_with_version(None)
+    }
+}
+
+impl ApiError {
+    pub fn into_response_with_version(self, current_version: Option<i64>) -> Response {
+        let lang = current_lang();
+        let body = ErrorResponse {
+            error: ErrorBody {
+                code: self.code(),
+                message: self.message(lang),
+                current_version,
+            },
+        };
+        let mut response = (self.status(), Json(body)).into_response();
+        response
+            .headers_mut()
+            .insert(CONTENT_LANGUAGE, HeaderValue::from_static(lang.tag()));
+        response
+    }
+}
@@ -0,0 +1,178 @@
+use axum::{
+    body::{to_bytes, Body, HttpBody},
+    extract::Request,
+    http::{
+        header::{CACHE_CONTROL, ETAG, IF_MATCH, IF_NONE_MATCH, VARY},
+        HeaderMap, HeaderValue, Method, StatusCode,
+    },
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use sha2::{Digest, Sha256};
+
+use crate::error::ApiError;
+
+/// Bodies up to this size get a content hash `ETag`; larger and streamed ones get none.
+const MAX_HASHED_BODY: u64 = 16 * 1024 * 1024;
+
+/// Strong entity tag of a version counter, e.g. `"3"`.
+pub fn etag(version: i64) -> Result<HeaderValue, ApiError> {
+    HeaderValue::from_str(&format!("\"{version}\"")).map_err(|_| ApiError::ResponseReadFailed)
+}
+
+/// What the client expects the current version to be.
+pub enum Precondition {
+    /// No `If-Match`: the write is unconditional, as before versions existed.
+    None,
+    /// `If-Match: *`.
+    Any,
+    /// One or more versions from `If-Match`.
+    Versions(Vec<i64>),
+}
+
+impl Precondition {
+    pub fn from_headers(headers: &HeaderMap) -> Result<Self, ApiError> {
+        let Some(value) = headers.get(IF_MATCH) else {
+            return Ok(Precondition::None);
+        };
+        let value = value.to_str().map_err(|_| ApiError::InvalidIfMatch)?.trim();
+        if value == "*" {
+            return Ok(Precondition::Any);
+        }
+        value
+            .split(',')
+            .map(|tag| {
+                let tag = tag.trim();
+                let tag = tag.strip_prefix("W/").unwrap_or(tag);
+                tag.trim_matches('"')
+                    .parse::<i64>()
+                    .map_err(|_| ApiError::InvalidIfMatch)
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(Precondition::Versions)
+    }
+
+    /// Fails with [`VersionError::Conflict`] when `current` is not what the client saw.
+    /// `current` is `None` when the resource does not exist yet.
+    pub fn check(&self, current: Option<i64>, conflict: ApiError) -> Result<(), VersionError> {
+        let matches = match self {
+            Precondition::None => true,
+            Precondition::Any => current.is_some(),
+            Precondition::Versions(versions) => {
+                current.is_some_and(|current| versions.contains(&current))
+            }
+        };
+        if matches {
+            return Ok(());
+        }
+        Err(VersionError::Conflict {
+            error: conflict,
+            current_version: current.unwrap_or(0),
+        })
+    }
+}
+
+/// Error of a conditional write: any [`ApiError`], or a lost update reported with the
+/// server's current version so the client can reload and merge.
+pub enum VersionError {
+    Api(ApiError),
+    Conflict {
+        error: ApiError,
+        current_version: i64,
+    },
+}
+
+impl From<ApiError> for VersionError {
+    fn from(error: ApiError) -> Self {
+        VersionError::Api(error)
+    }
+}
+
+impl IntoResponse for VersionError {
+    fn into_response(self) -> Response {
+        match self {
+            VersionError::Api(error) => error.into_response(),
+            VersionError::Conflict {
+                error,
+                current_version,
+            } => {
+                let mut response = error.into_response_with_version(Some(current_version));
+                if let Ok(tag) = etag(current_version) {
+                    response.headers_mut().insert(ETAG, tag);
+                }
+                response
+            }
+        }
+    }
+}
+
+/// Whether `If-None-Match` lists `tag` or is `*`. Comparison is weak, as for any GET.
+pub fn none_match(headers: &HeaderMap, tag: &HeaderValue) -> bool {
+    let Some(value) = headers.get(IF_NONE_MATCH).and_then(|v| v.to_str().ok()) else {
+        return false;
+    };
+    let Ok(tag) = tag.to_str() else {
+        return false;
+    };
+    let opaque = |t: &str| t.trim().trim_start_matches("W/").to_string();
+    let tag = opaque(tag);
+    value
+        .split(',')
+        .any(|t| t.trim() == "*" || opaque(t) == tag)
+}
+
+/// `304 Not Modified` carrying the validator and caching headers of the full response.
+pub fn not_modified(headers: &HeaderMap) -> Response {
+    let mut response = StatusCode::NOT_MODIFIED.into_response();
+    for name in [ETAG, CACHE_CONTROL, VARY] {
+        if let Some(value) = headers.get(&name) {
+            response.headers_mut().insert(name, value.clone());
+        }
+    }
+    response
+}
+
+/// Conditional GET for `/api/*`: a `200` without its own `ETag` (handlers that version
+/// their resource set one, e.g. the session) gets one from a hash of the body, and a request
+/// whose `If-None-Match` matches gets an empty `304` instead. Polling clients keep their copy
+/// and download only what changed.
+pub async fn conditional_get(request: Request, next: Next) -> Response {
+    if !matches!(*request.method(), Method::GET | Method::HEAD)
+        || !request.uri().path().starts_with("/api/")
+    {
+        return next.run(request).await;
+    }
+    let request_headers = request.headers().clone();
+    let mut response = next.run(request).await;
+    if response.status() != StatusCode::OK {
+        return response;
+    }
+    if !response.headers().contains_key(ETAG) {
+        let size = response.body().size_hint().exact();
+        if size.is_none_or(|s| s > MAX_HASHED_BODY) {
+            return response;
+        }
+        let (mut parts, body) = response.into_parts();
+        let Ok(body) = to_bytes(body, MAX_HASHED_BODY as usize).await else {
+            return ApiError::ResponseReadFailed.into_response();
+        };
+        let digest = hex::encode(Sha256::digest(&body));
+        // Weak: the same content may go out gzip- or brotli-encoded.
+        if let Ok(tag) = HeaderValue::from_str(&format!("W/\"{}\"", &digest[..32])) {
+            parts.headers.insert(ETAG, tag);
+        }
+        response = Response::from_parts(parts, Body::from(body));
+    }
+    let headers = response.headers_mut();
+    if !headers.contains_key(CACHE_CONTROL) {
+        // Stored by the browser, but revalidated on every request.
+        headers.insert(CACHE_CONTROL, HeaderValue::from_static("private, no-cache"));
+    }
+    let Some(tag) = headers.get(ETAG).cloned() else {
+        return response;
+    };
+    if none_match(&request_headers, &tag) {
+        return not_modified(response.headers());
+    }
+    response
+}
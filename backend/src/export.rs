@@ -0,0 +1,455 @@
+use std::io;
+
+use axum::{
+    body::{Body, Bytes},
+    extract::{Path, Query, State},
+    http::header,
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+use rust_xlsxwriter::{Format, Workbook};
+use serde::{Deserialize, Serialize};
+use sqlx::{
+    postgres::{PgArguments, PgRow},
+    Arguments, FromRow, PgPool,
+};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tracing::warn;
+use utoipa::IntoParams;
+use uuid::Uuid;
+
+use crate::{
+    authz::{self, AuthUser, ProjectRole},
+    error::ApiError,
+    parse_run_status, parse_uuid,
+    permissions::Capability,
+    AppState,
+};
+
+/// Starts the CSV files of exports and reports: the BOM makes Excel detect UTF-8 instead of
+/// mangling Cyrillic text.
+pub const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+/// Rows fetched from the export cursor per round trip.
+const CURSOR_BATCH: usize = 500;
+/// Rendered batches buffered ahead of a slow client.
+const STREAM_BUFFER: usize = 4;
+
+const COLUMNS: [&str; 11] = [
+    "#",
+    "Ключ",
+    "Тест-кейс",
+    "Версия",
+    "Обязательный",
+    "Статус",
+    "Причина fail",
+    "Комментарий",
+    "Исполнитель",
+    "Обновлено",
+    "Run",
+];
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ExportQuery {
+    format: Option<String>,
+}
+
+#[derive(Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportRow {
+    pub position: i32,
+    pub testcase_key: String,
+    pub testcase_title: String,
+    pub version_number: i32,
+    pub is_required: bool,
+    pub status: String,
+    pub fail_reason: String,
+    pub comment: String,
+    pub executor: String,
+    pub updated_at: String,
+    /// Run title.
+    pub run: String,
+}
+
+impl StreamRow for ExportRow {
+    const CSV_COLUMNS: &'static [&'static str] = &COLUMNS;
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.position.to_string(),
+            self.testcase_key.clone(),
+            self.testcase_title.clone(),
+            self.version_number.to_string(),
+            if self.is_required { "да" } else { "нет" }.to_string(),
+            self.status.clone(),
+            self.fail_reason.clone(),
+            self.comment.clone(),
+            self.executor.clone(),
+            self.updated_at.clone(),
+            self.run.clone(),
+        ]
+    }
+}
+
+const RUN_ROWS_SQL: &str = r#"
+    SELECT
+      ri.position AS position,
+      tc.key AS testcase_key,
+      tc.title AS testcase_title,
+      tv.version_number AS version_number,
+      ri.is_required AS is_required,
+      COALESCE(rr.status::text, 'untested') AS status,
+      CASE
+        WHEN rr.fail_reason_code IS NULL THEN ''
+        ELSE COALESCE(pfr.title, fr.title, rr.fail_reason_code)
+      END AS fail_reason,
+      COALESCE(rr.comment, '') AS comment,
+      COALESCE(u.display_name, u.email::text, '') AS executor,
+      COALESCE(rr.updated_at::text, '') AS updated_at,
+      r.title AS run
+    FROM run_items ri
+    JOIN runs r ON r.id = ri.run_id
+    JOIN testcase_versions tv ON tv.id = ri.testcase_version_id
+    JOIN testcases tc ON tc.id = tv.testcase_id
+    LEFT JOIN run_results rr ON rr.run_item_id = ri.id
+    LEFT JOIN project_fail_reasons pfr
+      ON pfr.project_id = r.project_id AND pfr.code = rr.fail_reason_code
+    LEFT JOIN fail_reasons fr ON fr.code = rr.fail_reason_code
+    LEFT JOIN users u ON u.id = rr.updated_by_user_id
+    WHERE ri.run_id = $1
+    ORDER BY ri.position ASC, ri.created_at ASC
+"#;
+
+/// Run title and its items in position order, shared by the CSV/XLSX export and the PDF report.
+pub async fn load_export_rows(
+    state: &AppState,
+    run_uuid: Uuid,
+) -> Result<(String, Vec<ExportRow>), ApiError> {
+    let run_title: String = sqlx::query_scalar(r#"SELECT title FROM runs WHERE id = $1"#)
+        .bind(run_uuid)
+        .fetch_optional(state.read_db())
+        .await
+        .map_err(|_| ApiError::RunReadFailed)?
+        .ok_or(ApiError::RunNotFound)?;
+
+    let items = sqlx::query_as::<_, ExportRow>(RUN_ROWS_SQL)
+        .bind(run_uuid)
+        .fetch_all(state.read_db())
+        .await
+        .map_err(|_| ApiError::RunItemsReadFailed)?;
+    Ok((run_title, items))
+}
+
+/// Format of a streamed export.
+#[derive(Clone, Copy, PartialEq)]
+pub enum StreamFormat {
+    Csv,
+    Ndjson,
+}
+
+impl StreamFormat {
+    /// `csv` when not given.
+    pub fn parse(format: Option<&str>) -> Result<Self, ApiError> {
+        match format.map(str::trim) {
+            None | Some("csv") => Ok(Self::Csv),
+            Some("ndjson") => Ok(Self::Ndjson),
+            Some(_) => Err(ApiError::InvalidStreamExportFormat),
+        }
+    }
+
+    fn content_type(self) -> &'static str {
+        match self {
+            Self::Csv => "text/csv; charset=utf-8",
+            Self::Ndjson => "application/x-ndjson",
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Ndjson => "ndjson",
+        }
+    }
+}
+
+/// A row of a streamed export: one JSON object per line in NDJSON, `cells` under
+/// `CSV_COLUMNS` in CSV.
+pub trait StreamRow: Serialize + for<'r> FromRow<'r, PgRow> + Send + Unpin + 'static {
+    const CSV_COLUMNS: &'static [&'static str];
+
+    fn cells(&self) -> Vec<String>;
+}
+
+fn render_batch<T: StreamRow>(
+    format: StreamFormat,
+    rows: &[T],
+    first: bool,
+) -> anyhow::Result<Vec<u8>> {
+    match format {
+        StreamFormat::Csv => {
+            let start = if first { UTF8_BOM.to_vec() } else { Vec::new() };
+            let mut writer = csv::Writer::from_writer(start);
+            if first {
+                writer.write_record(T::CSV_COLUMNS)?;
+            }
+            for row in rows {
+                writer.write_record(row.cells())?;
+            }
+            Ok(writer.into_inner()?)
+        }
+        StreamFormat::Ndjson => {
+            let mut out = Vec::new();
+            for row in rows {
+                serde_json::to_writer(&mut out, row)?;
+                out.push(b'\n');
+            }
+            Ok(out)
+        }
+    }
+}
+
+/// Streams the rows of `sql` as a file download without holding them in memory. The query
+/// runs behind a server-side cursor in a transaction of its own, read `CURSOR_BATCH` rows at a
+/// time as the client takes them, so the download keeps one connection of `db` until it ends.
+/// Errors before the first byte are `500 export_failed`; a later failure is logged and cuts
+/// the response short, which the client sees as an incomplete download.
+pub async fn stream<T: StreamRow>(
+    db: &PgPool,
+    sql: &str,
+    args: PgArguments,
+    format: StreamFormat,
+    filename: &str,
+) -> Result<Response, ApiError> {
+    let mut tx = db.begin().await.map_err(|_| ApiError::ExportFailed)?;
+    sqlx::query_with(
+        &format!("DECLARE export_rows NO SCROLL CURSOR FOR {sql}"),
+        args,
+    )
+    .persistent(false)
+    .execute(&mut *tx)
+    .await
+    .map_err(|_| ApiError::ExportFailed)?;
+
+    let (sender, receiver) = mpsc::channel::<io::Result<Bytes>>(STREAM_BUFFER);
+    tokio::spawn(async move {
+        let fetch = format!("FETCH {CURSOR_BATCH} FROM export_rows");
+        let mut first = true;
+        loop {
+            // Not prepared: the same FETCH returns other columns for another export, and a
+            // cached statement would keep the columns of the first one.
+            let batch = sqlx::query_as::<_, T>(&fetch)
+                .persistent(false)
+                .fetch_all(&mut *tx)
+                .await
+                .map_err(anyhow::Error::from)
+                .and_then(|rows| Ok((render_batch(format, &rows, first)?, rows.len())));
+            let (chunk, fetched) = match batch {
+                Ok(batch) => batch,
+                Err(err) => {
+                    warn!("export stream failed: {err:#}");
+                    let _ = sender.send(Err(io::Error::other(err))).await;
+                    return;
+                }
+            };
+            first = false;
+            // A send error means the client went away; dropping `tx` closes the cursor.
+            if sender.send(Ok(chunk.into())).await.is_err() || fetched < CURSOR_BATCH {
+                break;
+            }
+        }
+        let _ = tx.commit().await;
+    });
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, format.content_type().to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{filename}.{}\"", format.extension()),
+            ),
+        ],
+        Body::from_stream(ReceiverStream::new(receiver)),
+    )
+        .into_response())
+}
+
+fn render_xlsx(rows: &[ExportRow]) -> anyhow::Result<Vec<u8>> {
+    let mut workbook = Workbook::new();
+    let sheet = workbook.add_worksheet();
+    sheet.set_name("Results")?;
+    let bold = Format::new().set_bold();
+    for (col, title) in COLUMNS.iter().enumerate() {
+        sheet.write_string_with_format(0, col as u16, *title, &bold)?;
+    }
+    for (idx, row) in rows.iter().enumerate() {
+        let line = idx as u32 + 1;
+        sheet.write_number(line, 0, row.position)?;
+        for (col, value) in row.cells().iter().enumerate().skip(1) {
+            sheet.write_string(line, col as u16, value)?;
+        }
+    }
+    sheet.set_freeze_panes(1, 0)?;
+    sheet.autofit();
+    Ok(workbook.save_to_buffer()?)
+}
+
+/// Results of a run. CSV and NDJSON are streamed; XLSX is built in memory, since a workbook
+/// cannot be written before all its rows are known.
+#[utoipa::path(
+    get,
+    path = "/api/v2/runs/{run_id}/export",
+    tag = "export",
+    params(("run_id" = String, Path), ExportQuery),
+    responses((status = 200, description = "Файл выгрузки (`format=csv|xlsx|ndjson`).", content_type = "application/octet-stream"))
+)]
+pub async fn export_run(
+    State(state): State<AppState>,
+    Path(run_id): Path<String>,
+    AuthUser(actor_id): AuthUser,
+    Query(query): Query<ExportQuery>,
+) -> Result<Response, ApiError> {
+    let run_uuid = parse_uuid(&run_id, ApiError::InvalidRunId)?;
+    let format = query.format.as_deref().unwrap_or("csv").trim();
+    if !matches!(format, "csv" | "xlsx" | "ndjson") {
+        return Err(ApiError::InvalidExportFormat);
+    }
+    authz::require_run_capability(&state, run_uuid, &actor_id, Capability::ProjectRead).await?;
+
+    let filename = format!("run-{run_uuid}");
+    if format != "xlsx" {
+        let mut args = PgArguments::default();
+        args.add(run_uuid).map_err(|_| ApiError::ExportFailed)?;
+        let format = StreamFormat::parse(Some(format))?;
+        return stream::<ExportRow>(state.read_db(), RUN_ROWS_SQL, args, format, &filename).await;
+    }
+
+    let (_, rows) = load_export_rows(&state, run_uuid).await?;
+    let body = render_xlsx(&rows).map_err(|_| ApiError::ExportFailed)?;
+    Ok((
+        [
+            (
+                header::CONTENT_TYPE,
+                "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet".to_string(),
+            ),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{filename}.xlsx\""),
+            ),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+const RUN_LIST_COLUMNS: [&str; 11] = [
+    "ID",
+    "Run",
+    "Статус",
+    "Исполнитель",
+    "Создан",
+    "Начат",
+    "Завершён",
+    "Пунктов",
+    "OK",
+    "FAIL",
+    "Без результата",
+];
+
+#[derive(Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct RunExportRow {
+    id: Uuid,
+    title: String,
+    status: String,
+    executor: String,
+    created_at: DateTime<Utc>,
+    started_at: Option<DateTime<Utc>>,
+    finished_at: Option<DateTime<Utc>>,
+    total: i64,
+    ok: i64,
+    fail: i64,
+    /// Items still at `na` or without a result.
+    untested: i64,
+}
+
+impl StreamRow for RunExportRow {
+    const CSV_COLUMNS: &'static [&'static str] = &RUN_LIST_COLUMNS;
+
+    fn cells(&self) -> Vec<String> {
+        let time = |t: Option<DateTime<Utc>>| t.map(|t| t.to_rfc3339()).unwrap_or_default();
+        vec![
+            self.id.to_string(),
+            self.title.clone(),
+            self.status.clone(),
+            self.executor.clone(),
+            self.created_at.to_rfc3339(),
+            time(self.started_at),
+            time(self.finished_at),
+            self.total.to_string(),
+            self.ok.to_string(),
+            self.fail.to_string(),
+            self.untested.to_string(),
+        ]
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ExportRunsQuery {
+    /// `csv` (default) or `ndjson`.
+    format: Option<String>,
+    /// Only runs in this status.
+    status: Option<String>,
+}
+
+/// All runs of the project with result counts, newest first, streamed.
+#[utoipa::path(
+    get,
+    path = "/api/v2/projects/{project_id}/runs/export",
+    tag = "export",
+    params(("project_id" = String, Path), ExportRunsQuery),
+    responses((status = 200, description = "Файл выгрузки (`format=csv|ndjson`).", content_type = "application/octet-stream"))
+)]
+pub async fn export_project_runs(
+    State(state): State<AppState>,
+    access: ProjectRole,
+    Query(query): Query<ExportRunsQuery>,
+) -> Result<Response, ApiError> {
+    access.require(Capability::ProjectRead)?;
+    let format = StreamFormat::parse(query.format.as_deref())?;
+    let status = query.status.as_deref().map(parse_run_status).transpose()?;
+
+    let mut args = PgArguments::default();
+    args.add(access.project_id)
+        .and_then(|()| args.add(status))
+        .map_err(|_| ApiError::ExportFailed)?;
+    stream::<RunExportRow>(
+        state.read_db(),
+        r#"
+        SELECT
+          r.id,
+          r.title,
+          r.status::text AS status,
+          COALESCE(u.display_name, u.email::text, '') AS executor,
+          r.created_at,
+          r.started_at,
+          r.finished_at,
+          COUNT(ri.id) AS total,
+          COUNT(ri.id) FILTER (WHERE rr.status = 'ok') AS ok,
+          COUNT(ri.id) FILTER (WHERE rr.status = 'fail') AS fail,
+          COUNT(ri.id) FILTER (WHERE rr.status IS NULL OR rr.status = 'na') AS untested
+        FROM runs r
+        LEFT JOIN users u ON u.id = r.executed_by_user_id
+        LEFT JOIN run_items ri ON ri.run_id = r.id
+        LEFT JOIN run_results rr ON rr.run_item_id = ri.id
+        WHERE r.project_id = $1 AND ($2::text IS NULL OR r.status::text = $2)
+        GROUP BY r.id, u.id
+        ORDER BY r.created_at DESC, r.id DESC
+        "#,
+        args,
+        format,
+        &format!("runs-{}", access.project_id),
+    )
+    .await
+}
@@ -0,0 +1,463 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::Row;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::{
+    audit, authz::ProjectRole, db_errors, ensure_db_user_exists, error::ApiError, parse_uuid,
+    permissions::Capability, AppState,
+};
+
+const DEFAULT_COLOR: &str = "#9e9e9e";
+
+/// How many of `codes` a FAIL result in the project may use: the project's active dictionary
+/// when it has one, otherwise the active global catalog.
+pub async fn count_allowed_codes(
+    db: &sqlx::PgPool,
+    project_id: Uuid,
+    codes: &[String],
+) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar(
+        r#"
+        SELECT COUNT(*)
+        FROM unnest($2::text[]) AS c(code)
+        WHERE CASE
+          WHEN EXISTS (SELECT 1 FROM project_fail_reasons WHERE project_id = $1)
+            THEN EXISTS (
+              SELECT 1 FROM project_fail_reasons
+              WHERE project_id = $1 AND code = c.code AND is_active = TRUE
+            )
+          ELSE EXISTS (SELECT 1 FROM fail_reasons WHERE code = c.code AND is_active = TRUE)
+        END
+        "#,
+    )
+    .bind(project_id)
+    .bind(codes)
+    .fetch_one(db)
+    .await
+}
+
+pub async fn ensure_code_allowed(
+    state: &AppState,
+    project_id: Uuid,
+    code: &str,
+) -> Result<(), ApiError> {
+    let allowed = count_allowed_codes(&state.db, project_id, &[code.to_string()])
+        .await
+        .map_err(|_| ApiError::FailReasonCheckFailed)?;
+    if allowed == 0 {
+        return Err(ApiError::FailReasonNotAllowed);
+    }
+    Ok(())
+}
+
+fn validate_code(code: &str) -> Result<(), ApiError> {
+    let valid = (2..=64).contains(&code.len())
+        && code
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+    if !valid {
+        return Err(ApiError::InvalidFailReasonCode);
+    }
+    Ok(())
+}
+
+fn normalize_title(title: &str) -> Result<String, ApiError> {
+    let title = title.trim();
+    if title.is_empty() || title.chars().count() > 200 {
+        return Err(ApiError::InvalidFailReasonTitle);
+    }
+    Ok(title.to_string())
+}
+
+fn normalize_color(color: &str) -> Result<String, ApiError> {
+    let color = color.trim().to_lowercase();
+    let valid = color.len() == 7
+        && color.starts_with('#')
+        && color[1..].chars().all(|c| c.is_ascii_hexdigit());
+    if !valid {
+        return Err(ApiError::InvalidColor);
+    }
+    Ok(color)
+}
+
+#[derive(Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct ListFailReasonsQuery {
+    include_inactive: Option<bool>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateFailReasonRequest {
+    code: String,
+    title: String,
+    description: Option<String>,
+    color: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateFailReasonRequest {
+    title: Option<String>,
+    description: Option<String>,
+    color: Option<String>,
+    is_active: Option<bool>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct FailReasonView {
+    code: String,
+    title: String,
+    description: String,
+    color: String,
+    is_active: bool,
+    /// `true` for entries of the global catalog shown while the project has no dictionary.
+    is_global: bool,
+    /// FAIL results in the project's runs that use this code.
+    usage_count: i64,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ListFailReasonsResponse {
+    reasons: Vec<FailReasonView>,
+    inherits_global: bool,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct DeleteFailReasonResponse {
+    ok: bool,
+}
+
+const USAGE_CTE: &str = r#"
+  usage AS (
+    SELECT rr.fail_reason_code AS code, COUNT(*) AS usage_count
+    FROM run_results rr
+    JOIN run_items ri ON ri.id = rr.run_item_id
+    JOIN runs r ON r.id = ri.run_id
+    WHERE r.project_id = $1 AND rr.fail_reason_code IS NOT NULL
+    GROUP BY rr.fail_reason_code
+  )
+"#;
+
+fn map_fail_reason_row(r: &sqlx::postgres::PgRow) -> FailReasonView {
+    FailReasonView {
+        code: r.get::<String, _>("code"),
+        title: r.get::<String, _>("title"),
+        description: r.get::<String, _>("description"),
+        color: r.get::<String, _>("color"),
+        is_active: r.get::<bool, _>("is_active"),
+        is_global: r.get::<bool, _>("is_global"),
+        usage_count: r.get::<i64, _>("usage_count"),
+    }
+}
+
+async fn fetch_fail_reason(
+    state: &AppState,
+    project_id: Uuid,
+    code: &str,
+) -> Result<Option<FailReasonView>, ApiError> {
+    let sql = format!(
+        r#"
+        WITH {USAGE_CTE}
+        SELECT p.code, p.title, p.description, p.color, p.is_active,
+               FALSE AS is_global, COALESCE(u.usage_count, 0) AS usage_count
+        FROM project_fail_reasons p
+        LEFT JOIN usage u ON u.code = p.code
+        WHERE p.project_id = $1 AND p.code = $2
+        "#
+    );
+    let row = sqlx::query(&sql)
+        .bind(project_id)
+        .bind(code)
+        .fetch_optional(&state.db)
+        .await
+        .map_err(|_| ApiError::FailReasonReadFailed)?;
+    Ok(row.as_ref().map(map_fail_reason_row))
+}
+
+/// The project's dictionary with usage counts. A project without its own dictionary inherits
+/// the global catalog, which is returned read-only with `isGlobal = true`.
+#[utoipa::path(
+    get,
+    path = "/api/v2/projects/{project_id}/fail-reasons",
+    tag = "fail-reasons",
+    params(("project_id" = String, Path), ListFailReasonsQuery),
+    responses((status = 200, body = ListFailReasonsResponse))
+)]
+pub async fn list_project_fail_reasons(
+    State(state): State<AppState>,
+    access: ProjectRole,
+    Query(query): Query<ListFailReasonsQuery>,
+) -> Result<Json<ListFailReasonsResponse>, ApiError> {
+    let project_uuid = access.project_id;
+    let sql = format!(
+        r#"
+        WITH {USAGE_CTE},
+        own AS (
+          SELECT p.code, p.title, p.description, p.color, p.is_active, FALSE AS is_global
+          FROM project_fail_reasons p
+          WHERE p.project_id = $1
+        ),
+        effective AS (
+          SELECT * FROM own
+          UNION ALL
+          SELECT f.code, f.title, f.description, '{DEFAULT_COLOR}', f.is_active, TRUE
+          FROM fail_reasons f
+          WHERE NOT EXISTS (SELECT 1 FROM own)
+        )
+        SELECT e.*, COALESCE(u.usage_count, 0) AS usage_count
+        FROM effective e
+        LEFT JOIN usage u ON u.code = e.code
+        WHERE e.is_active = TRUE OR $2
+        ORDER BY e.title ASC, e.code ASC
+        "#
+    );
+    let rows = sqlx::query(&sql)
+        .bind(project_uuid)
+        .bind(query.include_inactive.unwrap_or(false))
+        .fetch_all(&state.db)
+        .await
+        .map_err(|_| ApiError::FailReasonsLoadFailed)?;
+
+    let reasons: Vec<FailReasonView> = rows.iter().map(map_fail_reason_row).collect();
+    let inherits_global = reasons.iter().all(|r| r.is_global);
+    Ok(Json(ListFailReasonsResponse {
+        reasons,
+        inherits_global,
+    }))
+}
+
+/// Adds a code to the project dictionary. The first entry switches the project from the
+/// global catalog to its own dictionary.
+#[utoipa::path(
+    post,
+    path = "/api/v2/projects/{project_id}/fail-reasons",
+    tag = "fail-reasons",
+    params(("project_id" = String, Path)),
+    request_body = CreateFailReasonRequest,
+    responses((status = 201, body = FailReasonView))
+)]
+pub async fn create_project_fail_reason(
+    State(state): State<AppState>,
+    access: ProjectRole,
+    Json(payload): Json<CreateFailReasonRequest>,
+) -> Result<(StatusCode, Json<FailReasonView>), ApiError> {
+    access.require(Capability::ProjectManage)?;
+    let ProjectRole {
+        user_id: actor_id,
+        project_id: project_uuid,
+        ..
+    } = access;
+    let code = payload.code.trim().to_string();
+    validate_code(&code)?;
+    let title = normalize_title(&payload.title)?;
+    let description = payload.description.unwrap_or_default().trim().to_string();
+    let color = normalize_color(payload.color.as_deref().unwrap_or(DEFAULT_COLOR))?;
+    ensure_db_user_exists(&state, &actor_id).await?;
+    let actor_uuid = parse_uuid(&actor_id, ApiError::InvalidUserId)?;
+
+    let create_failed = |_| ApiError::FailReasonCreateFailed;
+    let mut tx = state.db.begin().await.map_err(create_failed)?;
+    let inserted: Option<Uuid> = sqlx::query_scalar(
+        r#"
+        INSERT INTO project_fail_reasons (project_id, code, title, description, color)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (project_id, code) DO NOTHING
+        RETURNING id
+        "#,
+    )
+    .bind(project_uuid)
+    .bind(&code)
+    .bind(&title)
+    .bind(&description)
+    .bind(&color)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|err| db_errors::map(err, ApiError::FailReasonRejected))?;
+    let reason_id = inserted.ok_or(ApiError::FailReasonExists)?;
+    audit::record(
+        &mut *tx,
+        audit::AuditEntry {
+            actor_user_id: Some(actor_uuid),
+            action: "create",
+            entity_type: "fail_reason",
+            entity_id: Some(reason_id),
+            project_id: Some(project_uuid),
+            run_id: None,
+            before: None,
+            after: Some(json!({
+                "code": &code, "title": &title, "description": &description, "color": &color
+            })),
+        },
+    )
+    .await
+    .map_err(create_failed)?;
+    tx.commit().await.map_err(create_failed)?;
+
+    let reason = fetch_fail_reason(&state, project_uuid, &code)
+        .await?
+        .ok_or(ApiError::FailReasonNotFound)?;
+    Ok((StatusCode::CREATED, Json(reason)))
+}
+
+/// Partial update; deactivating a code keeps historical results but rejects it for new ones.
+#[utoipa::path(
+    patch,
+    path = "/api/v2/projects/{project_id}/fail-reasons/{code}",
+    tag = "fail-reasons",
+    params(("project_id" = String, Path), ("code" = String, Path)),
+    request_body = UpdateFailReasonRequest,
+    responses((status = 200, body = FailReasonView))
+)]
+pub async fn update_project_fail_reason(
+    State(state): State<AppState>,
+    Path((_project_id, code)): Path<(String, String)>,
+    access: ProjectRole,
+    Json(payload): Json<UpdateFailReasonRequest>,
+) -> Result<Json<FailReasonView>, ApiError> {
+    access.require(Capability::ProjectManage)?;
+    let ProjectRole {
+        user_id: actor_id,
+        project_id: project_uuid,
+        ..
+    } = access;
+    let title = payload.title.as_deref().map(normalize_title).transpose()?;
+    let color = payload.color.as_deref().map(normalize_color).transpose()?;
+    let description = payload.description.map(|d| d.trim().to_string());
+    ensure_db_user_exists(&state, &actor_id).await?;
+    let actor_uuid = parse_uuid(&actor_id, ApiError::InvalidUserId)?;
+
+    let update_failed = |_| ApiError::FailReasonUpdateFailed;
+    let mut tx = state.db.begin().await.map_err(update_failed)?;
+    let before = sqlx::query(
+        r#"
+        SELECT id, title, description, color, is_active
+        FROM project_fail_reasons
+        WHERE project_id = $1 AND code = $2
+        FOR UPDATE
+        "#,
+    )
+    .bind(project_uuid)
+    .bind(&code)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(update_failed)?
+    .ok_or(ApiError::FailReasonNotFound)?;
+    let after = sqlx::query(
+        r#"
+        UPDATE project_fail_reasons
+        SET title = COALESCE($2, title),
+            description = COALESCE($3, description),
+            color = COALESCE($4, color),
+            is_active = COALESCE($5, is_active)
+        WHERE id = $1
+        RETURNING title, description, color, is_active
+        "#,
+    )
+    .bind(before.get::<Uuid, _>("id"))
+    .bind(&title)
+    .bind(&description)
+    .bind(&color)
+    .bind(payload.is_active)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(update_failed)?;
+    let snapshot = |r: &sqlx::postgres::PgRow| {
+        json!({
+            "title": r.get::<String, _>("title"),
+            "description": r.get::<String, _>("description"),
+            "color": r.get::<String, _>("color"),
+            "isActive": r.get::<bool, _>("is_active"),
+        })
+    };
+    audit::record(
+        &mut *tx,
+        audit::AuditEntry {
+            actor_user_id: Some(actor_uuid),
+            action: "update",
+            entity_type: "fail_reason",
+            entity_id: Some(before.get::<Uuid, _>("id")),
+            project_id: Some(project_uuid),
+            run_id: None,
+            before: Some(snapshot(&before)),
+            after: Some(snapshot(&after)),
+        },
+    )
+    .await
+    .map_err(update_failed)?;
+    tx.commit().await.map_err(update_failed)?;
+
+    let reason = fetch_fail_reason(&state, project_uuid, &code)
+        .await?
+        .ok_or(ApiError::FailReasonNotFound)?;
+    Ok(Json(reason))
+}
+
+/// Removes an unused code; codes referenced by results can only be deactivated.
+#[utoipa::path(
+    delete,
+    path = "/api/v2/projects/{project_id}/fail-reasons/{code}",
+    tag = "fail-reasons",
+    params(("project_id" = String, Path), ("code" = String, Path)),
+    responses((status = 200, body = DeleteFailReasonResponse))
+)]
+pub async fn delete_project_fail_reason(
+    State(state): State<AppState>,
+    Path((_project_id, code)): Path<(String, String)>,
+    access: ProjectRole,
+) -> Result<Json<DeleteFailReasonResponse>, ApiError> {
+    access.require(Capability::ProjectManage)?;
+    let ProjectRole {
+        user_id: actor_id,
+        project_id: project_uuid,
+        ..
+    } = access;
+    ensure_db_user_exists(&state, &actor_id).await?;
+    let actor_uuid = parse_uuid(&actor_id, ApiError::InvalidUserId)?;
+
+    let reason = fetch_fail_reason(&state, project_uuid, &code)
+        .await?
+        .ok_or(ApiError::FailReasonNotFound)?;
+    if reason.usage_count > 0 {
+        return Err(ApiError::FailReasonInUse);
+    }
+
+    let delete_failed = |_| ApiError::FailReasonDeleteFailed;
+    let mut tx = state.db.begin().await.map_err(delete_failed)?;
+    let reason_id: Option<Uuid> = sqlx::query_scalar(
+        r#"DELETE FROM project_fail_reasons WHERE project_id = $1 AND code = $2 RETURNING id"#,
+    )
+    .bind(project_uuid)
+    .bind(&code)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(delete_failed)?;
+    let reason_id = reason_id.ok_or(ApiError::FailReasonNotFound)?;
+    audit::record(
+        &mut *tx,
+        audit::AuditEntry {
+            actor_user_id: Some(actor_uuid),
+            action: "delete",
+            entity_type: "fail_reason",
+            entity_id: Some(reason_id),
+            project_id: Some(project_uuid),
+            run_id: None,
+            before: Some(json!({ "code": &reason.code, "title": &reason.title })),
+            after: None,
+        },
+    )
+    .await
+    .map_err(delete_failed)?;
+    tx.commit().await.map_err(delete_failed)?;
+
+    Ok(Json(DeleteFailReasonResponse { ok: true }))
+}
@@ -0,0 +1,896 @@
+use std::collections::{HashMap, HashSet};
+
+use axum::{
+    extract::{Multipart, Query, State},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sqlx::Row;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::{
+    audit::{self, AuditEntry},
+    authz::ProjectRole,
+    ensure_db_user_exists,
+    error::{current_lang, ApiError},
+    parse_uuid,
+    permissions::Capability,
+    suites, AppState,
+};
+
+struct Keywords {
+    feature: &'static [&'static str],
+    rule: &'static [&'static str],
+    background: &'static [&'static str],
+    scenario: &'static [&'static str],
+    outline: &'static [&'static str],
+    examples: &'static [&'static str],
+    given: &'static [&'static str],
+    when: &'static [&'static str],
+    then: &'static [&'static str],
+    /// `And`, `But` and `*`: the step continues the kind of the previous one.
+    continuation: &'static [&'static str],
+}
+
+const ENGLISH: Keywords = Keywords {
+    feature: &["Feature", "Business Need", "Ability"],
+    rule: &["Rule"],
+    background: &["Background"],
+    scenario: &["Scenario", "Example"],
+    outline: &["Scenario Outline", "Scenario Template"],
+    examples: &["Examples", "Scenarios"],
+    given: &["Given"],
+    when: &["When"],
+    then: &["Then"],
+    continuation: &["And", "But", "*"],
+};
+
+const RUSSIAN: Keywords = Keywords {
+    feature: &["Функция", "Функциональность", "Функционал", "Свойство"],
+    rule: &["Правило"],
+    background: &["Предыстория", "Контекст"],
+    scenario: &["Сценарий", "Пример"],
+    outline: &["Структура сценария", "Шаблон сценария"],
+    examples: &["Примеры"],
+    given: &["Допустим", "Дано", "Пусть"],
+    when: &["Когда", "Если"],
+    then: &["Тогда", "То", "Затем"],
+    continuation: &["К тому же", "Также", "И", "Но", "А", "Иначе", "*"],
+};
+
+#[derive(Clone, Copy, PartialEq)]
+enum Header {
+    Feature,
+    Rule,
+    Background,
+    Scenario,
+    Examples,
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum StepKind {
+    Given,
+    When,
+    Then,
+    /// Examples table of a scenario outline, kept after its steps.
+    Examples,
+}
+
+/// One element of `steps_json` for a scenario: the keyword as written in the file plus its
+/// meaning, with the doc string or data table that belongs to the step.
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct Step {
+    keyword: String,
+    kind: StepKind,
+    text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    doc_string: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    data_table: Vec<Vec<String>>,
+}
+
+struct Scenario {
+    name: String,
+    line: usize,
+    tags: Vec<String>,
+    description: Vec<String>,
+    /// Background steps that run before this scenario.
+    background: Vec<Step>,
+    steps: Vec<Step>,
+}
+
+struct Feature {
+    name: String,
+    scenarios: Vec<Scenario>,
+}
+
+struct ParseError {
+    line: usize,
+    error: ApiError,
+}
+
+impl ParseError {
+    fn at(line: usize, error: ApiError) -> Self {
+        Self { line, error }
+    }
+}
+
+struct DocString {
+    fence: &'static str,
+    indent: usize,
+    line: usize,
+    lines: Vec<String>,
+}
+
+fn header<'a>(keywords: &Keywords, line: &'a str) -> Option<(Header, &'a str)> {
+    [
+        (Header::Feature, keywords.feature),
+        (Header::Rule, keywords.rule),
+        (Header::Background, keywords.background),
+        (Header::Scenario, keywords.outline),
+        (Header::Scenario, keywords.scenario),
+        (Header::Examples, keywords.examples),
+    ]
+    .into_iter()
+    .find_map(|(header, words)| {
+        words.iter().find_map(|word| {
+            line.strip_prefix(word)
+                .and_then(|rest| rest.strip_prefix(':'))
+                .map(|rest| (header, rest.trim()))
+        })
+    })
+}
+
+fn step<'a>(
+    keywords: &Keywords,
+    line: &'a str,
+    previous: StepKind,
+) -> Option<(&'a str, StepKind, &'a str)> {
+    [
+        (Some(StepKind::Given), keywords.given),
+        (Some(StepKind::When), keywords.when),
+        (Some(StepKind::Then), keywords.then),
+        (None, keywords.continuation),
+    ]
+    .into_iter()
+    .find_map(|(kind, words)| {
+        words.iter().find_map(|word| {
+            let rest = line.strip_prefix(word)?;
+            rest.starts_with(char::is_whitespace)
+                .then(|| (&line[..word.len()], kind.unwrap_or(previous), rest.trim()))
+        })
+    })
+}
+
+fn table_row(line: &str) -> Vec<String> {
+    let inner = line.trim().trim_start_matches('|');
+    let inner = inner.strip_suffix('|').unwrap_or(inner);
+    let mut cells = Vec::new();
+    let mut cell = String::new();
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('n') => cell.push('\n'),
+                Some(escaped) => cell.push(escaped),
+                None => cell.push('\\'),
+            },
+            '|' => cells.push(std::mem::take(&mut cell).trim().to_string()),
+            _ => cell.push(c),
+        }
+    }
+    cells.push(cell.trim().to_string());
+    cells
+}
+
+fn tags(line: &str) -> impl Iterator<Item = String> + '_ {
+    line.split_whitespace()
+        .take_while(|word| !word.starts_with('#'))
+        .filter_map(|word| word.strip_prefix('@'))
+        .filter(|tag| !tag.is_empty())
+        .map(str::to_string)
+}
+
+/// Parses one `.feature` file. English keywords by default, Russian after `# language: ru`.
+fn parse_feature(text: &str) -> Result<Feature, ParseError> {
+    let mut keywords = &ENGLISH;
+    let mut feature: Option<(String, Vec<String>)> = None;
+    let mut section = Header::Feature;
+    let mut pending_tags: Vec<String> = Vec::new();
+    let mut rule_tags: Vec<String> = Vec::new();
+    let mut feature_background: Vec<Step> = Vec::new();
+    let mut rule_background: Vec<Step> = Vec::new();
+    let mut in_rule = false;
+    let mut scenarios: Vec<Scenario> = Vec::new();
+    let mut previous_kind = StepKind::Given;
+    let mut doc: Option<DocString> = None;
+
+    for (idx, raw) in text.lines().enumerate() {
+        let line_no = idx + 1;
+        let line = raw.trim();
+
+        if let Some(mut open) = doc.take() {
+            if line == open.fence {
+                let background = if in_rule {
+                    &mut rule_background
+                } else {
+                    &mut feature_background
+                };
+                let target = match section {
+                    Header::Background => background.last_mut(),
+                    _ => scenarios.last_mut().and_then(|s| s.steps.last_mut()),
+                };
+                if let Some(step) = target {
+                    step.doc_string = Some(open.lines.join("\n"));
+                }
+            } else {
+                let strip = raw
+                    .char_indices()
+                    .take(open.indent)
+                    .take_while(|(_, c)| c.is_whitespace())
+                    .last()
+                    .map(|(i, c)| i + c.len_utf8())
+                    .unwrap_or(0);
+                open.lines.push(raw[strip..].trim_end().to_string());
+                doc = Some(open);
+            }
+            continue;
+        }
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(comment) = line.strip_prefix('#') {
+            if feature.is_none() {
+                if let Some(language) = comment.trim().strip_prefix("language:") {
+                    keywords = match language.trim() {
+                        "en" => &ENGLISH,
+                        "ru" => &RUSSIAN,
+                        _ => {
+                            return Err(ParseError::at(
+                                line_no,
+                                ApiError::GherkinLanguageUnsupported,
+                            ))
+                        }
+                    };
+                }
+            }
+            continue;
+        }
+        if line.starts_with('@') {
+            pending_tags.extend(tags(line));
+            continue;
+        }
+
+        if let Some((found, name)) = header(keywords, line) {
+            if found != Header::Feature && feature.is_none() {
+                return Err(ParseError::at(line_no, ApiError::GherkinFeatureMissing));
+            }
+            match found {
+                Header::Feature => {
+                    if feature.is_some() {
+                        return Err(ParseError::at(line_no, ApiError::GherkinUnexpectedLine));
+                    }
+                    feature = Some((name.to_string(), std::mem::take(&mut pending_tags)));
+                }
+                Header::Rule => {
+                    in_rule = true;
+                    rule_tags = std::mem::take(&mut pending_tags);
+                    rule_background.clear();
+                }
+                Header::Background => previous_kind = StepKind::Given,
+                Header::Scenario => {
+                    let feature_tags = feature.as_ref().map(|(_, t)| t.clone()).unwrap_or_default();
+                    let mut all_tags = feature_tags;
+                    if in_rule {
+                        all_tags.extend(rule_tags.iter().cloned());
+                    }
+                    all_tags.append(&mut pending_tags);
+                    let mut background = feature_background.clone();
+                    if in_rule {
+                        background.extend(rule_background.iter().cloned());
+                    }
+                    scenarios.push(Scenario {
+                        name: name.to_string(),
+                        line: line_no,
+                        tags: all_tags,
+                        description: Vec::new(),
+                        background,
+                        steps: Vec::new(),
+                    });
+                    previous_kind = StepKind::Given;
+                }
+                Header::Examples => {
+                    let scenario = scenarios
+                        .last_mut()
+                        .filter(|_| matches!(section, Header::Scenario | Header::Examples))
+                        .ok_or(ParseError::at(line_no, ApiError::GherkinUnexpectedLine))?;
+                    pending_tags.clear();
+                    scenario.steps.push(Step {
+                        keyword: line[..line.find(':').unwrap_or(0)].to_string(),
+                        kind: StepKind::Examples,
+                        text: name.to_string(),
+                        doc_string: None,
+                        data_table: Vec::new(),
+                    });
+                }
+            }
+            section = found;
+            continue;
+        }
+        if feature.is_none() {
+            return Err(ParseError::at(line_no, ApiError::GherkinFeatureMissing));
+        }
+
+        if let Some((keyword, kind, text)) = step(keywords, line, previous_kind) {
+            let new_step = Step {
+                keyword: keyword.to_string(),
+                kind,
+                text: text.to_string(),
+                doc_string: None,
+                data_table: Vec::new(),
+            };
+            match section {
+                Header::Background if in_rule => rule_background.push(new_step),
+                Header::Background => feature_background.push(new_step),
+                Header::Scenario => match scenarios.last_mut() {
+                    Some(scenario) => scenario.steps.push(new_step),
+                    None => return Err(ParseError::at(line_no, ApiError::GherkinUnexpectedLine)),
+                },
+                _ => return Err(ParseError::at(line_no, ApiError::GherkinUnexpectedLine)),
+            }
+            previous_kind = kind;
+            continue;
+        }
+
+        let background = if in_rule {
+            &mut rule_background
+        } else {
+            &mut feature_background
+        };
+        let background_empty = background.is_empty();
+        let last_step = match section {
+            Header::Background => background.last_mut(),
+            Header::Scenario | Header::Examples => {
+                scenarios.last_mut().and_then(|s| s.steps.last_mut())
+            }
+            _ => None,
+        };
+        if line.starts_with('|') {
+            let step = last_step.ok_or(ParseError::at(line_no, ApiError::GherkinUnexpectedLine))?;
+            step.data_table.push(table_row(line));
+            continue;
+        }
+        if let Some(fence) = ["\"\"\"", "```"].into_iter().find(|f| line.starts_with(f)) {
+            if last_step.is_none() || section == Header::Examples {
+                return Err(ParseError::at(line_no, ApiError::GherkinUnexpectedLine));
+            }
+            doc = Some(DocString {
+                fence,
+                indent: raw.len() - raw.trim_start().len(),
+                line: line_no,
+                lines: Vec::new(),
+            });
+            continue;
+        }
+
+        // Free text is a description, allowed right after a header and before any step.
+        match section {
+            Header::Feature | Header::Rule => {}
+            Header::Background if background_empty => {}
+            Header::Scenario => match scenarios.last_mut() {
+                Some(scenario) if scenario.steps.is_empty() => {
+                    scenario.description.push(line.to_string())
+                }
+                _ => return Err(ParseError::at(line_no, ApiError::GherkinUnexpectedLine)),
+            },
+            Header::Examples if last_step.is_some_and(|s| s.data_table.is_empty()) => {}
+            _ => return Err(ParseError::at(line_no, ApiError::GherkinUnexpectedLine)),
+        }
+    }
+
+    if let Some(open) = doc {
+        return Err(ParseError::at(
+            open.line,
+            ApiError::GherkinUnclosedDocString,
+        ));
+    }
+    let (name, _) = feature.ok_or(ParseError::at(1, ApiError::GherkinFeatureMissing))?;
+    Ok(Feature { name, scenarios })
+}
+
+/// What a scenario becomes in the library; compared with the stored latest version on
+/// re-import.
+#[derive(PartialEq)]
+struct ScenarioContent {
+    summary: String,
+    preconditions: String,
+    steps: Value,
+    expected: Value,
+    tags: Vec<String>,
+}
+
+impl ScenarioContent {
+    fn from_scenario(scenario: &Scenario) -> Self {
+        let expected: Vec<&str> = scenario
+            .steps
+            .iter()
+            .filter(|s| s.kind == StepKind::Then)
+            .map(|s| s.text.as_str())
+            .collect();
+        Self {
+            summary: scenario.description.join("\n"),
+            preconditions: scenario
+                .background
+                .iter()
+                .map(|s| format!("{} {}", s.keyword, s.text))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            steps: json!(scenario.steps),
+            expected: json!(expected),
+            tags: normalize_tags(scenario.tags.iter().cloned()),
+        }
+    }
+}
+
+fn normalize_tags(tags: impl Iterator<Item = String>) -> Vec<String> {
+    let mut seen = HashSet::new();
+    let mut tags: Vec<String> = tags.filter(|t| seen.insert(t.to_lowercase())).collect();
+    tags.sort_by_key(|t| t.to_lowercase());
+    tags
+}
+
+/// File names become source paths: `\` is turned into `/` and a leading `./` is dropped, so
+/// the same file uploaded from Windows and from CI is recognised.
+fn source_path(file_name: &str) -> String {
+    let path = file_name.trim().replace('\\', "/");
+    path.trim_start_matches("./").to_string()
+}
+
+#[derive(Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct ImportGherkinQuery {
+    /// Suite under which each feature gets its own child suite.
+    suite_id: String,
+}
+
+/// Multipart form of `import_gherkin`; only describes the request in the OpenAPI spec.
+#[derive(ToSchema)]
+#[allow(dead_code)]
+pub struct GherkinUpload {
+    /// One or more `.feature` files; the file name (e.g. `features/login.feature`) is the
+    /// source path that links scenarios to testcases.
+    #[schema(value_type = Vec<String>, format = Binary)]
+    file: Vec<Vec<u8>>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GherkinTestcaseView {
+    id: String,
+    key: String,
+    title: String,
+    source_path: String,
+    line: usize,
+    version_number: i32,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GherkinMissingView {
+    id: String,
+    title: String,
+    source_path: String,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GherkinRejectedView {
+    source_path: String,
+    line: usize,
+    code: String,
+    message: String,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportGherkinResponse {
+    created: Vec<GherkinTestcaseView>,
+    /// Scenarios whose content changed: a new testcase version was added.
+    updated: Vec<GherkinTestcaseView>,
+    unchanged: usize,
+    /// Testcases linked to an imported file whose scenario is no longer in it; they are left
+    /// as is.
+    missing: Vec<GherkinMissingView>,
+    /// Files that could not be parsed and scenarios that could not be imported.
+    rejected: Vec<GherkinRejectedView>,
+}
+
+struct LinkedTestcase {
+    id: Uuid,
+    key: String,
+    version_number: i32,
+    content: ScenarioContent,
+}
+
+/// Imports Gherkin scenarios as testcases. Every scenario (or outline, with its examples
+/// kept after the steps) of a feature becomes a testcase in a child suite named after the
+/// feature; `Then` steps are also its expected results and background steps its
+/// preconditions. Testcases remember the file and scenario name, so importing the file
+/// again adds a version to changed scenarios instead of creating duplicates.
+#[utoipa::path(
+    post,
+    path = "/api/v2/projects/{project_id}/testcases/import/gherkin",
+    tag = "library",
+    params(("project_id" = String, Path), ImportGherkinQuery),
+    request_body(content = GherkinUpload, content_type = "multipart/form-data"),
+    responses((status = 200, body = ImportGherkinResponse))
+)]
+pub async fn import_gherkin(
+    State(state): State<AppState>,
+    access: ProjectRole,
+    Query(query): Query<ImportGherkinQuery>,
+    mut multipart: Multipart,
+) -> Result<Json<ImportGherkinResponse>, ApiError> {
+    access.require(Capability::LibraryEdit)?;
+    let ProjectRole {
+        user_id: actor_id,
+        project_id,
+        ..
+    } = access;
+    let target_suite = parse_uuid(&query.suite_id, ApiError::InvalidSuiteId)?;
+
+    let mut rejected: Vec<(String, ParseError)> = Vec::new();
+    let mut features: Vec<(String, Feature)> = Vec::new();
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|_| ApiError::InvalidMultipart)?
+    {
+        if field.name() != Some("file") {
+            continue;
+        }
+        let path = source_path(field.file_name().unwrap_or_default());
+        let data = field
+            .bytes()
+            .await
+            .map_err(|_| ApiError::InvalidMultipart)?;
+        if path.is_empty() {
+            rejected.push((path, ParseError::at(0, ApiError::GherkinPathRequired)));
+            continue;
+        }
+        if features.iter().any(|(p, _)| *p == path) {
+            rejected.push((path, ParseError::at(0, ApiError::GherkinDuplicateFile)));
+            continue;
+        }
+        let parsed = std::str::from_utf8(&data)
+            .map_err(|_| ParseError::at(0, ApiError::GherkinNotUtf8))
+            .and_then(|text| parse_feature(text.strip_prefix('\u{feff}').unwrap_or(text)));
+        match parsed {
+            Ok(feature) => features.push((path, feature)),
+            Err(err) => rejected.push((path, err)),
+        }
+    }
+    if features.is_empty() && rejected.is_empty() {
+        return Err(ApiError::GherkinFileRequired);
+    }
+
+    suites::ensure_suite_in_project(&state, target_suite, project_id).await?;
+    ensure_db_user_exists(&state, &actor_id).await?;
+    let actor_uuid = parse_uuid(&actor_id, ApiError::InvalidUserId)?;
+
+    let paths: Vec<String> = features.iter().map(|(p, _)| p.clone()).collect();
+    let mut linked: HashMap<(String, String), LinkedTestcase> = HashMap::new();
+    for r in sqlx::query(
+        r#"
+        SELECT
+          tc.id, tc.key, tc.source_path, tc.source_name,
+          tv.version_number, tv.summary, tv.preconditions, tv.steps_json, tv.expected_json,
+          ARRAY(
+            SELECT t.name::text FROM testcase_tags tt JOIN tags t ON t.id = tt.tag_id
+            WHERE tt.testcase_id = tc.id
+          ) AS tags
+        FROM testcases tc
+        JOIN test_suites s ON s.id = tc.suite_id
+        JOIN LATERAL (
+          SELECT * FROM testcase_versions v
+          WHERE v.testcase_id = tc.id
+          ORDER BY v.version_number DESC
+          LIMIT 1
+        ) tv ON TRUE
+        WHERE s.project_id = $1 AND tc.is_archived = FALSE AND tc.source_path = ANY($2)
+        "#,
+    )
+    .bind(project_id)
+    .bind(&paths)
+    .fetch_all(&state.db)
+    .await
+    .map_err(|_| ApiError::TestcasesReadFailed)?
+    {
+        linked.insert(
+            (r.get("source_path"), r.get("source_name")),
+            LinkedTestcase {
+                id: r.get("id"),
+                key: r.get("key"),
+                version_number: r.get("version_number"),
+                content: ScenarioContent {
+                    summary: r.get("summary"),
+                    preconditions: r.get("preconditions"),
+                    steps: r.get("steps_json"),
+                    expected: r.get("expected_json"),
+                    tags: normalize_tags(r.get::<Vec<String>, _>("tags").into_iter()),
+                },
+            },
+        );
+    }
+
+    let import_failed = |_| ApiError::GherkinImportFailed;
+    let mut created = Vec::new();
+    let mut updated = Vec::new();
+    let mut unchanged = 0;
+    let mut tx = state.db.begin().await.map_err(import_failed)?;
+    for (path, feature) in &features {
+        let mut feature_suite: Option<Uuid> = None;
+        let mut seen: HashSet<String> = HashSet::new();
+        for scenario in &feature.scenarios {
+            let title = scenario.name.trim().to_string();
+            if !(2..=240).contains(&title.chars().count()) {
+                rejected.push((
+                    path.clone(),
+                    ParseError::at(scenario.line, ApiError::ImportRowTitleInvalid),
+                ));
+                continue;
+            }
+            if !seen.insert(title.clone()) {
+                rejected.push((
+                    path.clone(),
+                    ParseError::at(scenario.line, ApiError::GherkinDuplicateScenario),
+                ));
+                continue;
+            }
+            let content = ScenarioContent::from_scenario(scenario);
+
+            let (id, key, version_number, is_new) = match linked.get(&(path.clone(), title.clone()))
+            {
+                Some(existing) if existing.content == content => {
+                    unchanged += 1;
+                    continue;
+                }
+                Some(existing) => {
+                    sqlx::query(
+                        r#"
+                        UPDATE testcases
+                        SET updated_at = NOW(), updated_by_user_id = $2
+                        WHERE id = $1
+                        "#,
+                    )
+                    .bind(existing.id)
+                    .bind(actor_uuid)
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(import_failed)?;
+                    sqlx::query("DELETE FROM testcase_tags WHERE testcase_id = $1")
+                        .bind(existing.id)
+                        .execute(&mut *tx)
+                        .await
+                        .map_err(import_failed)?;
+                    (
+                        existing.id,
+                        existing.key.clone(),
+                        existing.version_number + 1,
+                        false,
+                    )
+                }
+                None => {
+                    let suite_id = match feature_suite {
+                        Some(id) => id,
+                        None => {
+                            let id = feature_suite_id(
+                                &mut tx,
+                                project_id,
+                                target_suite,
+                                feature,
+                                actor_uuid,
+                            )
+                            .await
+                            .map_err(import_failed)?;
+                            feature_suite = Some(id);
+                            id
+                        }
+                    };
+                    let id = Uuid::new_v4();
+                    let key = format!("TC-{}", &id.simple().to_string()[..8]);
+                    sqlx::query(
+                        r#"
+                        INSERT INTO testcases (
+                          id, suite_id, key, title, source_path, source_name,
+                          created_by_user_id, updated_by_user_id
+                        )
+                        VALUES ($1, $2, $3, $4, $5, $4, $6, $6)
+                        "#,
+                    )
+                    .bind(id)
+                    .bind(suite_id)
+                    .bind(&key)
+                    .bind(&title)
+                    .bind(path)
+                    .bind(actor_uuid)
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(import_failed)?;
+                    (id, key, 1, true)
+                }
+            };
+
+            let change_note = if is_new {
+                format!("Imported from {path}")
+            } else {
+                format!("Updated from {path}")
+            };
+            sqlx::query(
+                r#"
+                INSERT INTO testcase_versions (
+                  testcase_id, version_number, summary, preconditions, steps_json, expected_json,
+                  change_note, created_by_user_id
+                )
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                "#,
+            )
+            .bind(id)
+            .bind(version_number)
+            .bind(&content.summary)
+            .bind(&content.preconditions)
+            .bind(&content.steps)
+            .bind(&content.expected)
+            .bind(&change_note)
+            .bind(actor_uuid)
+            .execute(&mut *tx)
+            .await
+            .map_err(import_failed)?;
+            if !content.tags.is_empty() {
+                sqlx::query(
+                    r#"
+                    WITH upserted AS (
+                      INSERT INTO tags (name)
+                      SELECT DISTINCT t FROM UNNEST($2::text[]) AS t
+                      ON CONFLICT (name) DO UPDATE SET name = tags.name
+                      RETURNING id
+                    )
+                    INSERT INTO testcase_tags (testcase_id, tag_id)
+                    SELECT $1, id FROM upserted
+                    ON CONFLICT DO NOTHING
+                    "#,
+                )
+                .bind(id)
+                .bind(&content.tags)
+                .execute(&mut *tx)
+                .await
+                .map_err(import_failed)?;
+            }
+
+            let view = GherkinTestcaseView {
+                id: id.to_string(),
+                key,
+                title,
+                source_path: path.clone(),
+                line: scenario.line,
+                version_number,
+            };
+            if is_new {
+                created.push(view);
+            } else {
+                updated.push(view);
+            }
+        }
+    }
+
+    let mut missing: Vec<GherkinMissingView> = linked
+        .iter()
+        .filter(|((path, name), _)| {
+            features
+                .iter()
+                .find(|(p, _)| p == path)
+                .is_some_and(|(_, f)| !f.scenarios.iter().any(|s| s.name.trim() == name))
+        })
+        .map(|((path, name), testcase)| GherkinMissingView {
+            id: testcase.id.to_string(),
+            title: name.clone(),
+            source_path: path.clone(),
+        })
+        .collect();
+    missing.sort_by(|a, b| (&a.source_path, &a.title).cmp(&(&b.source_path, &b.title)));
+
+    audit::record(
+        &mut *tx,
+        AuditEntry {
+            actor_user_id: Some(actor_uuid),
+            action: "create",
+            entity_type: "testcase_import",
+            entity_id: None,
+            project_id: Some(project_id),
+            run_id: None,
+            before: None,
+            after: Some(json!({
+                "format": "gherkin",
+                "suiteId": target_suite,
+                "files": paths,
+                "created": created.len(),
+                "updated": updated.len(),
+                "unchanged": unchanged,
+            })),
+        },
+    )
+    .await
+    .map_err(import_failed)?;
+    tx.commit().await.map_err(import_failed)?;
+
+    let lang = current_lang();
+    Ok(Json(ImportGherkinResponse {
+        created,
+        updated,
+        unchanged,
+        missing,
+        rejected: rejected
+            .into_iter()
+            .map(|(source_path, err)| GherkinRejectedView {
+                source_path,
+                line: err.line,
+                code: err.error.code().to_string(),
+                message: err.error.message(lang).to_string(),
+            })
+            .collect(),
+    }))
+}
+
+/// Child suite of `parent` named after the feature, created on first use.
+async fn feature_suite_id(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    project_id: Uuid,
+    parent: Uuid,
+    feature: &Feature,
+    actor_uuid: Uuid,
+) -> Result<Uuid, sqlx::Error> {
+    let name: String = if feature.name.chars().count() >= 2 {
+        feature.name.chars().take(200).collect()
+    } else {
+        "Feature".to_string()
+    };
+    let existing: Option<Uuid> = sqlx::query_scalar(
+        r#"
+        SELECT id FROM test_suites
+        WHERE project_id = $1 AND parent_id = $2 AND lower(name) = lower($3) AND is_archived = FALSE
+        ORDER BY position ASC, created_at ASC
+        LIMIT 1
+        "#,
+    )
+    .bind(project_id)
+    .bind(parent)
+    .bind(&name)
+    .fetch_optional(&mut **tx)
+    .await?;
+    if let Some(id) = existing {
+        return Ok(id);
+    }
+    let id = Uuid::new_v4();
+    sqlx::query(
+        r#"
+        INSERT INTO test_suites (
+          id, project_id, parent_id, key, name, created_by_user_id, updated_by_user_id
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $6)
+        "#,
+    )
+    .bind(id)
+    .bind(project_id)
+    .bind(parent)
+    .bind(format!("suite-{}", &id.simple().to_string()[..8]))
+    .bind(&name)
+    .bind(actor_uuid)
+    .execute(&mut **tx)
+    .await?;
+    Ok(id)
+}
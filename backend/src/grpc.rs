@@ -0,0 +1,382 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use axum::{extract::State, Json};
+use serde::Deserialize;
+use tokio::net::TcpListener;
+use tokio_stream::{
+    wrappers::{errors::BroadcastStreamRecvError, BroadcastStream, TcpListenerStream},
+    Stream,
+};
+use tonic::{metadata::MetadataMap, transport::Server, Code, Request, Response, Status};
+use uuid::Uuid;
+
+use crate::{
+    api_keys::{self, ApiKeyGrant},
+    authz::{self, AuthUser},
+    create_run_v2,
+    error::{ApiError, Lang},
+    etag::{Precondition, VersionError},
+    jwt,
+    live::RunHub,
+    parse_uuid,
+    permissions::Capability,
+    record_run_result, AppState, CreateRunRequest, RunItemStepView, RunView,
+    UpdateRunResultRequest, UpdateStepResultRequest,
+};
+
+mod pb {
+    tonic::include_proto!("uran.agent.v1");
+}
+
+/// `AgentService` from `proto/agent.proto`. Each call runs the same code as its `/api/v2`
+/// counterpart, so permissions, validation, audit and notifications are identical.
+struct AgentService {
+    state: AppState,
+}
+
+/// Serves the agent API on its own listener until `shutdown` resolves.
+pub async fn serve(
+    state: AppState,
+    listener: TcpListener,
+    shutdown: impl Future<Output = ()>,
+) -> Result<(), tonic::transport::Error> {
+    Server::builder()
+        .add_service(pb::agent_service_server::AgentServiceServer::new(
+            AgentService { state },
+        ))
+        .serve_with_incoming_shutdown(TcpListenerStream::new(listener), shutdown)
+        .await
+}
+
+/// Who is calling: the user, plus the API key grant when authenticated with a key.
+struct Caller {
+    user_id: String,
+    grant: Option<ApiKeyGrant>,
+    lang: Lang,
+}
+
+async fn caller(state: &AppState, metadata: &MetadataMap) -> Result<Caller, Status> {
+    let lang = Lang::negotiate(
+        metadata
+            .get("accept-language")
+            .and_then(|v| v.to_str().ok()),
+    );
+    let token = metadata
+        .get("authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(str::trim)
+        .ok_or_else(|| status(ApiError::Unauthorized, lang))?;
+    if token.starts_with(api_keys::KEY_PREFIX) {
+        let grant = api_keys::resolve_key(state, token)
+            .await
+            .map_err(|err| status(err, lang))?;
+        return Ok(Caller {
+            user_id: grant.user_id.clone(),
+            grant: Some(grant),
+            lang,
+        });
+    }
+    let claims = state
+        .jwt
+        .verify(token, jwt::TokenKind::Access)
+        .ok_or_else(|| status(ApiError::InvalidToken, lang))?;
+    Ok(Caller {
+        user_id: claims.sub,
+        grant: None,
+        lang,
+    })
+}
+
+/// The HTTP status of the error picks the gRPC code; the stable error code travels in the
+/// `x-error-code` metadata.
+fn status(error: ApiError, lang: Lang) -> Status {
+    let code = match error.status().as_u16() {
+        400 | 413 | 415 | 422 => Code::InvalidArgument,
+        401 => Code::Unauthenticated,
+        403 => Code::PermissionDenied,
+        404 => Code::NotFound,
+        409 | 412 => Code::FailedPrecondition,
+        429 | 507 => Code::ResourceExhausted,
+        503 => Code::Unavailable,
+        _ => Code::Internal,
+    };
+    status_with_code(code, error, lang)
+}
+
+fn status_with_code(code: Code, error: ApiError, lang: Lang) -> Status {
+    let mut status = Status::new(code, error.message(lang));
+    if let Ok(value) = error.code().parse() {
+        status.metadata_mut().insert("x-error-code", value);
+    }
+    status
+}
+
+/// A lost update is `ABORTED`, with the server's version in `x-current-version`.
+fn version_status(error: VersionError, lang: Lang) -> Status {
+    match error {
+        VersionError::Api(error) => status(error, lang),
+        VersionError::Conflict {
+            error,
+            current_version,
+        } => {
+            let mut status = status_with_code(Code::Aborted, error, lang);
+            if let Ok(value) = current_version.to_string().parse() {
+                status.metadata_mut().insert("x-current-version", value);
+            }
+            status
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl pb::agent_service_server::AgentService for AgentService {
+    async fn create_run(
+        &self,
+        request: Request<pb::CreateRunRequest>,
+    ) -> Result<Response<pb::Run>, Status> {
+        let caller = caller(&self.state, request.metadata()).await?;
+        let request = request.into_inner();
+        let custom_fields = request
+            .custom_fields_json
+            .filter(|raw| !raw.trim().is_empty())
+            .map(|raw| serde_json::from_str(&raw))
+            .transpose()
+            .map_err(|_| status(ApiError::InvalidCustomFieldValue, caller.lang))?;
+        let payload = CreateRunRequest {
+            project_id: request.project_id,
+            asset_id: request.asset_id,
+            template_id: request.template_id,
+            suite_id: request.suite_id,
+            tag_query: request.tag_query,
+            title: request.title,
+            custom_fields,
+            commit_sha: request.commit_sha,
+            milestone: None,
+            description: None,
+        };
+        let created = api_keys::scope(
+            caller.grant,
+            create_run_v2(
+                State(self.state.clone()),
+                AuthUser(caller.user_id),
+                Json(payload),
+            ),
+        )
+        .await
+        .map_err(|err| status(err, caller.lang))?;
+        let (_, Json(created)) = created;
+        Ok(Response::new(run(created.run)))
+    }
+
+    async fn submit_result(
+        &self,
+        request: Request<pb::SubmitResultRequest>,
+    ) -> Result<Response<pb::SubmitResultResponse>, Status> {
+        let caller = caller(&self.state, request.metadata()).await?;
+        let request = request.into_inner();
+        let precondition = match request.expected_version {
+            Some(version) => Precondition::Versions(vec![version]),
+            None => Precondition::None,
+        };
+        let payload = UpdateRunResultRequest {
+            status: request.status,
+            fail_reason_code: request.fail_reason_code,
+            comment: request.comment,
+            elapsed_seconds: request.elapsed_seconds,
+            steps: Some(
+                request
+                    .steps
+                    .into_iter()
+                    .map(|step| UpdateStepResultRequest {
+                        step_id: step.step_id,
+                        status: step.status,
+                        comment: step.comment,
+                    })
+                    .collect(),
+            ),
+        };
+        let saved = api_keys::scope(
+            caller.grant,
+            record_run_result(
+                &self.state,
+                &caller.user_id,
+                &request.run_id,
+                &request.run_item_id,
+                precondition,
+                payload,
+            ),
+        )
+        .await
+        .map_err(|err| version_status(err, caller.lang))?;
+        Ok(Response::new(pb::SubmitResultResponse {
+            updated_at: saved.updated_at.to_rfc3339(),
+            version: saved.version,
+            steps: saved.steps.into_iter().map(step).collect(),
+        }))
+    }
+
+    type WatchRunStream = RunUpdates;
+
+    async fn watch_run(
+        &self,
+        request: Request<pb::WatchRunRequest>,
+    ) -> Result<Response<RunUpdates>, Status> {
+        let caller = caller(&self.state, request.metadata()).await?;
+        let run_id = request.into_inner().run_id;
+        let run_uuid =
+            parse_uuid(&run_id, ApiError::InvalidRunId).map_err(|err| status(err, caller.lang))?;
+        api_keys::scope(
+            caller.grant,
+            authz::require_run_capability(
+                &self.state,
+                run_uuid,
+                &caller.user_id,
+                Capability::ProjectRead,
+            ),
+        )
+        .await
+        .map_err(|err| status(err, caller.lang))?;
+
+        let rx = self.state.live.subscribe(run_uuid);
+        Ok(Response::new(RunUpdates {
+            inner: Some(BroadcastStream::new(rx)),
+            hub: self.state.live.clone(),
+            run_id: run_uuid,
+        }))
+    }
+}
+
+/// Events of one run for a `WatchRun` call; releases the hub channel when the call ends.
+pub struct RunUpdates {
+    inner: Option<BroadcastStream<String>>,
+    hub: Arc<RunHub>,
+    run_id: Uuid,
+}
+
+impl Stream for RunUpdates {
+    type Item = Result<pb::RunUpdate, Status>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let Some(inner) = self.inner.as_mut() else {
+            return Poll::Ready(None);
+        };
+        loop {
+            match Pin::new(&mut *inner).poll_next(cx) {
+                Poll::Ready(Some(Ok(payload))) => {
+                    if let Some(update) = run_update(&payload) {
+                        return Poll::Ready(Some(Ok(update)));
+                    }
+                }
+                // A slow agent skips what it missed, as a WebSocket client does.
+                Poll::Ready(Some(Err(BroadcastStreamRecvError::Lagged(_)))) => {}
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+impl Drop for RunUpdates {
+    fn drop(&mut self) {
+        // The receiver must be gone before the hub checks whether anyone still listens.
+        self.inner.take();
+        self.hub.release(self.run_id);
+    }
+}
+
+/// The hub carries the JSON sent to WebSocket clients; agents get the same events typed.
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum HubEvent {
+    ResultUpdated(HubResult),
+    StatusChanged { run: Box<RunView> },
+    AssigneeChanged(HubAssignee),
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HubResult {
+    run_item_id: String,
+    status: String,
+    fail_reason_code: Option<String>,
+    comment: String,
+    updated_by_user_id: String,
+    updated_at: String,
+    version: i64,
+    steps: Vec<RunItemStepView>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HubAssignee {
+    run_item_id: Option<String>,
+    assignee_user_id: Option<String>,
+}
+
+fn run_update(payload: &str) -> Option<pb::RunUpdate> {
+    let event = match serde_json::from_str(payload).ok()? {
+        HubEvent::ResultUpdated(result) => {
+            pb::run_update::Event::ResultUpdated(pb::ResultUpdated {
+                run_item_id: result.run_item_id,
+                status: result.status,
+                fail_reason_code: result.fail_reason_code,
+                comment: result.comment,
+                updated_by_user_id: result.updated_by_user_id,
+                updated_at: result.updated_at,
+                version: result.version,
+                steps: result.steps.into_iter().map(step).collect(),
+            })
+        }
+        HubEvent::StatusChanged { run: view } => {
+            pb::run_update::Event::StatusChanged(Box::new(run(*view)))
+        }
+        HubEvent::AssigneeChanged(change) => {
+            pb::run_update::Event::AssigneeChanged(pb::AssigneeChanged {
+                run_item_id: change.run_item_id,
+                assignee_user_id: change.assignee_user_id,
+            })
+        }
+    };
+    Some(pb::RunUpdate { event: Some(event) })
+}
+
+fn run(view: RunView) -> pb::Run {
+    pb::Run {
+        id: view.id,
+        project_id: view.project_id,
+        asset_id: view.asset_id,
+        asset_version: view.asset_version,
+        template_id: view.template_id,
+        title: view.title,
+        status: view.status,
+        executed_by_user_id: view.executed_by_user_id,
+        default_assignee_user_id: view.default_assignee_user_id,
+        started_at: view.started_at.map(|at| at.to_rfc3339()),
+        finished_at: view.finished_at.map(|at| at.to_rfc3339()),
+        locked_at: view.locked_at.map(|at| at.to_rfc3339()),
+        custom_fields_json: view.custom_fields.to_string(),
+        commit_sha: view.commit_sha,
+        created_at: view.created_at.to_rfc3339(),
+        updated_at: view.updated_at.to_rfc3339(),
+        milestone: view.milestone,
+        description: view.description,
+    }
+}
+
+fn step(view: RunItemStepView) -> pb::Step {
+    pb::Step {
+        step_id: view.step_id,
+        position: view.position,
+        action: view.action,
+        expected_result: view.expected_result,
+        status: view.status,
+        comment: view.comment,
+        updated_at: view.updated_at.map(|at| at.to_rfc3339()),
+    }
+}
@@ -0,0 +1,153 @@
+use std::{
+    future::Future,
+    time::{Duration, Instant},
+};
+
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use serde::Serialize;
+use tracing::warn;
+use utoipa::ToSchema;
+
+use crate::AppState;
+
+/// A dependency slower than this counts as down, so a hung probe cannot hang the kubelet.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+#[derive(Serialize, ToSchema)]
+pub struct HealthResponse {
+    status: &'static str,
+    service: &'static str,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DependencyStatus {
+    /// `ok` or `down`.
+    status: &'static str,
+    latency_ms: u64,
+    /// `failed` or `timeout`; details are in the server log.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<&'static str>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadinessChecks {
+    database: DependencyStatus,
+    storage: DependencyStatus,
+    /// Only with `DATABASE_REPLICA_URL`; also `down` while it lags more than
+    /// `DATABASE_REPLICA_MAX_LAG_SECONDS`. Informational: reads fall back to the primary, so
+    /// it does not make the instance unready.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    replica: Option<DependencyStatus>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadinessResponse {
+    /// `ok` when every dependency is, otherwise `unavailable` (with `503`).
+    status: &'static str,
+    service: &'static str,
+    checks: ReadinessChecks,
+}
+
+#[utoipa::path(
+    get,
+    path = "/health",
+    tag = "system",
+    responses((status = 200, body = HealthResponse)),
+    security(())
+)]
+pub async fn health() -> Json<HealthResponse> {
+    Json(HealthResponse {
+        status: "ok",
+        service: "uran-api",
+    })
+}
+
+/// Liveness: the process serves requests. Never touches dependencies, so a database outage
+/// does not get every pod restarted.
+#[utoipa::path(
+    get,
+    path = "/health/live",
+    tag = "system",
+    responses((status = 200, body = HealthResponse)),
+    security(())
+)]
+pub async fn live() -> Json<HealthResponse> {
+    health().await
+}
+
+/// Readiness: PostgreSQL answers a query and the storage backend answers a metadata request;
+/// the read replica is reported but does not count.
+/// `503` while any of them is down, so the instance is taken out of the load balancer.
+#[utoipa::path(
+    get,
+    path = "/health/ready",
+    tag = "system",
+    responses(
+        (status = 200, body = ReadinessResponse),
+        (status = 503, body = ReadinessResponse, description = "A dependency is down.")
+    ),
+    security(())
+)]
+pub async fn ready(State(state): State<AppState>) -> impl IntoResponse {
+    let (database, storage, replica) = tokio::join!(
+        probe("database", async {
+            sqlx::query("SELECT 1")
+                .execute(&state.db)
+                .await
+                .map(|_| ())
+                .map_err(anyhow::Error::from)
+        }),
+        probe("storage", state.storage.probe()),
+        async {
+            match &state.replica {
+                Some(replica) => Some(probe("replica", replica.probe()).await),
+                None => None,
+            }
+        },
+    );
+    let healthy = database.status == "ok" && storage.status == "ok";
+    let status = if healthy {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (
+        status,
+        Json(ReadinessResponse {
+            status: if healthy { "ok" } else { "unavailable" },
+            service: "uran-api",
+            checks: ReadinessChecks {
+                database,
+                storage,
+                replica,
+            },
+        }),
+    )
+}
+
+/// The endpoint is unauthenticated, so the cause is only logged; the response says whether
+/// the dependency failed or timed out.
+async fn probe(name: &str, check: impl Future<Output = anyhow::Result<()>>) -> DependencyStatus {
+    let started = Instant::now();
+    let result = tokio::time::timeout(PROBE_TIMEOUT, check).await;
+    let latency_ms = started.elapsed().as_millis() as u64;
+    let error = match result {
+        Ok(Ok(())) => None,
+        Ok(Err(err)) => {
+            warn!("readiness check of {name} failed: {err:#}");
+            Some("failed")
+        }
+        Err(_) => {
+            warn!("readiness check of {name} timed out");
+            Some("timeout")
+        }
+    };
+    DependencyStatus {
+        status: if error.is_none() { "ok" } else { "down" },
+        latency_ms,
+        error,
+    }
+}
@@ -0,0 +1,340 @@
+use std::{
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use axum::{
+    body::{to_bytes, Body, BodyDataStream, Bytes, HttpBody},
+    extract::{Request, State},
+    http::{HeaderMap, HeaderName, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use sqlx::Row;
+use tokio_stream::{Stream, StreamExt};
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::{ensure_db_user_exists, error::ApiError, parse_bearer_user_id, AppState};
+
+const IDEMPOTENCY_KEY: &str = "idempotency-key";
+/// Set on responses served from storage instead of running the handler again.
+const REPLAYED: &str = "idempotent-replayed";
+const MAX_KEY_LEN: usize = 255;
+/// Larger (or streamed) responses are not stored; repeating the key runs the request again.
+const MAX_STORED_BODY: u64 = 1024 * 1024;
+/// An `in_progress` key older than this belongs to a request that never finished (the instance
+/// died mid-request) and may be taken over.
+const ABANDONED_AFTER_SECS: i32 = 10 * 60;
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+enum Claim {
+    /// The key is ours: run the request and store its response.
+    Acquired,
+    /// `body_hash` is `None` when the first request's body was not read to the end.
+    Replay {
+        body_hash: Option<String>,
+        response: Response,
+    },
+}
+
+/// SHA-256 of a request body, computed while the handler reads it.
+#[derive(Default)]
+struct BodyHash {
+    hasher: Mutex<Sha256>,
+    complete: AtomicBool,
+}
+
+impl BodyHash {
+    /// Known only once the body was read to the end.
+    fn finish(&self) -> Option<String> {
+        if !self.complete.load(Ordering::Acquire) {
+            return None;
+        }
+        let hasher = self
+            .hasher
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        Some(hex::encode(hasher.finalize()))
+    }
+}
+
+struct HashingStream {
+    inner: BodyDataStream,
+    hash: Arc<BodyHash>,
+}
+
+impl Stream for HashingStream {
+    type Item = Result<Bytes, axum::Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let polled = Pin::new(&mut self.inner).poll_next(cx);
+        match &polled {
+            Poll::Ready(Some(Ok(chunk))) => self
+                .hash
+                .hasher
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .update(chunk),
+            Poll::Ready(None) => self.hash.complete.store(true, Ordering::Release),
+            _ => {}
+        }
+        polled
+    }
+}
+
+/// Reads a repeated request's body only to hash it; nothing is kept in memory.
+async fn hash_body(body: Body) -> Result<String, axum::Error> {
+    let mut stream = body.into_data_stream();
+    let mut hasher = Sha256::new();
+    while let Some(chunk) = stream.next().await {
+        hasher.update(chunk?);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Makes `POST /api/...` with an `Idempotency-Key` header safe to retry: the first response
+/// is kept for 24 hours per user and key, and a repeat returns it unchanged (with
+/// `Idempotent-Replayed: true`) without running the handler. A repeat while the first request
+/// is still running gets `409`; the same key on another endpoint or with another body gets
+/// `422`. Server errors are not stored, so a retry after one runs again. Requests without
+/// credentials ignore the key.
+pub async fn replay(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    if request.method() != Method::POST || !request.uri().path().starts_with("/api/") {
+        return next.run(request).await;
+    }
+    let Some(key) = request.headers().get(IDEMPOTENCY_KEY) else {
+        return next.run(request).await;
+    };
+    let key = match key.to_str().map(str::trim) {
+        Ok(key) if !key.is_empty() && key.len() <= MAX_KEY_LEN => key.to_string(),
+        _ => return ApiError::InvalidIdempotencyKey.into_response(),
+    };
+    let user_id = match parse_bearer_user_id(&state.jwt, request.headers())
+        .ok()
+        .and_then(|id| Uuid::parse_str(&id).ok())
+    {
+        Some(user_id) => user_id,
+        None => return next.run(request).await,
+    };
+    let path = request
+        .uri()
+        .path_and_query()
+        .map(|p| p.as_str().to_string())
+        .unwrap_or_default();
+
+    // The key row references the user, who may have no DB row before their first write.
+    if let Err(err) = ensure_db_user_exists(&state, &user_id.to_string()).await {
+        return err.into_response();
+    }
+
+    match claim(&state, user_id, &key, &path).await {
+        Ok(Claim::Acquired) => {}
+        Ok(Claim::Replay {
+            body_hash,
+            response,
+        }) => {
+            let Some(expected) = body_hash else {
+                return response;
+            };
+            return match hash_body(request.into_body()).await {
+                Ok(hash) if hash == expected => response,
+                Ok(_) => ApiError::IdempotencyKeyReused.into_response(),
+                Err(_) => ApiError::IdempotencyCheckFailed.into_response(),
+            };
+        }
+        Err(err) => return err.into_response(),
+    }
+    let hash = Arc::new(BodyHash::default());
+    let (parts, body) = request.into_parts();
+    let body = Body::from_stream(HashingStream {
+        inner: body.into_data_stream(),
+        hash: hash.clone(),
+    });
+    let response = next.run(Request::from_parts(parts, body)).await;
+    match store(&state, user_id, &key, hash.finish(), response).await {
+        Ok(response) => response,
+        Err((err, response)) => {
+            warn!("failed to store idempotent response: {err}");
+            release(&state, user_id, &key).await;
+            response
+        }
+    }
+}
+
+async fn claim(state: &AppState, user_id: Uuid, key: &str, path: &str) -> Result<Claim, ApiError> {
+    // Expired and abandoned rows are replaced in place, so a key is never blocked by one.
+    let inserted = sqlx::query(
+        r#"
+        INSERT INTO idempotency_keys (user_id, key, path)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (user_id, key) DO UPDATE
+        SET path = EXCLUDED.path,
+            status = 'in_progress',
+            response_status = NULL,
+            response_headers = NULL,
+            response_body = NULL,
+            body_hash = NULL,
+            created_at = NOW(),
+            expires_at = NOW() + INTERVAL '24 hours'
+        WHERE idempotency_keys.expires_at <= NOW()
+           OR (idempotency_keys.status = 'in_progress'
+               AND idempotency_keys.created_at < NOW() - make_interval(secs => $4))
+        RETURNING 1
+        "#,
+    )
+    .bind(user_id)
+    .bind(key)
+    .bind(path)
+    .bind(ABANDONED_AFTER_SECS)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|_| ApiError::IdempotencyCheckFailed)?;
+    if inserted.is_some() {
+        return Ok(Claim::Acquired);
+    }
+
+    let row = sqlx::query(
+        r#"
+        SELECT path, status, response_status, response_headers, response_body, body_hash
+        FROM idempotency_keys
+        WHERE user_id = $1 AND key = $2
+        "#,
+    )
+    .bind(user_id)
+    .bind(key)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|_| ApiError::IdempotencyCheckFailed)?
+    // Released between the two queries: the first request failed and may be retried.
+    .ok_or(ApiError::IdempotencyKeyInProgress)?;
+    if row.get::<String, _>("path") != path {
+        return Err(ApiError::IdempotencyKeyReused);
+    }
+    if row.get::<String, _>("status") != "completed" {
+        return Err(ApiError::IdempotencyKeyInProgress);
+    }
+
+    let status = row
+        .get::<Option<i32>, _>("response_status")
+        .and_then(|s| u16::try_from(s).ok())
+        .and_then(|s| StatusCode::from_u16(s).ok())
+        .ok_or(ApiError::IdempotencyCheckFailed)?;
+    let body = row
+        .get::<Option<Vec<u8>>, _>("response_body")
+        .unwrap_or_default();
+    let mut response = (status, body).into_response();
+    let headers = response.headers_mut();
+    headers.clear();
+    if let Some(Value::Array(stored)) = row.get::<Option<Value>, _>("response_headers") {
+        for pair in stored {
+            let (Some(name), Some(value)) = (pair[0].as_str(), pair[1].as_str()) else {
+                continue;
+            };
+            if let (Ok(name), Ok(value)) =
+                (HeaderName::try_from(name), HeaderValue::from_str(value))
+            {
+                headers.append(name, value);
+            }
+        }
+    }
+    headers.insert(REPLAYED, HeaderValue::from_static("true"));
+    Ok(Claim::Replay {
+        body_hash: row.get("body_hash"),
+        response,
+    })
+}
+
+/// Saves a finished response under the key and hands it back to the client. Server errors,
+/// streamed and large bodies release the key instead.
+async fn store(
+    state: &AppState,
+    user_id: Uuid,
+    key: &str,
+    body_hash: Option<String>,
+    response: Response,
+) -> Result<Response, (sqlx::Error, Response)> {
+    let size = response.body().size_hint().exact();
+    if response.status().is_server_error() || size.is_none_or(|s| s > MAX_STORED_BODY) {
+        release(state, user_id, key).await;
+        return Ok(response);
+    }
+    let (parts, body) = response.into_parts();
+    let body = match to_bytes(body, MAX_STORED_BODY as usize).await {
+        Ok(body) => body,
+        Err(_) => {
+            release(state, user_id, key).await;
+            return Ok(ApiError::IdempotencyCheckFailed.into_response());
+        }
+    };
+    let response = Response::from_parts(parts, Body::from(body.clone()));
+    let saved = sqlx::query(
+        r#"
+        UPDATE idempotency_keys
+        SET status = 'completed',
+            response_status = $3,
+            response_headers = $4,
+            response_body = $5,
+            body_hash = $6
+        WHERE user_id = $1 AND key = $2
+        "#,
+    )
+    .bind(user_id)
+    .bind(key)
+    .bind(i32::from(response.status().as_u16()))
+    .bind(stored_headers(response.headers()))
+    .bind(body.to_vec())
+    .bind(body_hash)
+    .execute(&state.db)
+    .await;
+    match saved {
+        Ok(_) => Ok(response),
+        Err(err) => Err((err, response)),
+    }
+}
+
+fn stored_headers(headers: &HeaderMap) -> Value {
+    Value::Array(
+        headers
+            .iter()
+            .filter_map(|(name, value)| {
+                let value = value.to_str().ok()?;
+                Some(serde_json::json!([name.as_str(), value]))
+            })
+            .collect(),
+    )
+}
+
+async fn release(state: &AppState, user_id: Uuid, key: &str) {
+    let deleted = sqlx::query(r#"DELETE FROM idempotency_keys WHERE user_id = $1 AND key = $2"#)
+        .bind(user_id)
+        .bind(key)
+        .execute(&state.db)
+        .await;
+    if let Err(err) = deleted {
+        warn!("failed to release idempotency key: {err}");
+    }
+}
+
+pub fn spawn_cleanup(state: AppState) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(CLEANUP_INTERVAL);
+        loop {
+            interval.tick().await;
+            let deleted = sqlx::query(r#"DELETE FROM idempotency_keys WHERE expires_at <= NOW()"#)
+                .execute(&state.db)
+                .await;
+            if let Err(err) = deleted {
+                warn!("failed to delete expired idempotency keys: {err}");
+            }
+        }
+    });
+}
@@ -0,0 +1,286 @@
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::{
+    authz::AuthUser,
+    ensure_db_user_exists,
+    error::ApiError,
+    notifications::{self, Channel, NotificationKind},
+    pagination, parse_uuid, AppState,
+};
+
+/// Notifications are kept this long, read or not.
+const RETENTION_DAYS: i32 = 90;
+const MAX_READ_IDS: usize = 500;
+
+/// Where a feed entry leads; the run is looked up from the item when only that is known.
+#[derive(Clone, Copy, Default)]
+pub struct Target {
+    pub project_id: Option<Uuid>,
+    pub run_id: Option<Uuid>,
+    pub run_item_id: Option<Uuid>,
+}
+
+/// Adds an entry to the feed of every recipient who keeps the in-app channel on for the
+/// event. Runs in the background, like queuing the email jobs.
+pub fn deliver_in_background(
+    state: &AppState,
+    kind: NotificationKind,
+    target: Target,
+    recipients: Vec<String>,
+    title: String,
+    body: String,
+) {
+    let state = state.clone();
+    tokio::spawn(async move {
+        for user_id in recipients {
+            if let Err(err) = deliver(&state, kind, target, &user_id, &title, &body).await {
+                warn!("failed to add an in-app notification for {user_id}: {err}");
+            }
+        }
+    });
+}
+
+async fn deliver(
+    state: &AppState,
+    kind: NotificationKind,
+    target: Target,
+    user_id: &str,
+    title: &str,
+    body: &str,
+) -> anyhow::Result<()> {
+    ensure_db_user_exists(state, user_id)
+        .await
+        .map_err(|err| anyhow::anyhow!("failed to sync user: {}", err.code()))?;
+    let user_uuid = Uuid::parse_str(user_id)?;
+    let wanted =
+        notifications::is_enabled(state, user_uuid, target.project_id, kind, Channel::InApp)
+            .await?;
+    if !wanted {
+        return Ok(());
+    }
+    sqlx::query!(
+        r#"
+        INSERT INTO notifications (user_id, kind, project_id, run_id, run_item_id, title, body)
+        VALUES (
+          $1, $2, $3,
+          COALESCE($4, (SELECT run_id FROM run_items WHERE id = $5)),
+          $5, $6, $7
+        )
+        "#,
+        user_uuid,
+        kind.column(),
+        target.project_id,
+        target.run_id,
+        target.run_item_id,
+        title,
+        body,
+    )
+    .execute(&state.db)
+    .await?;
+    Ok(())
+}
+
+#[derive(Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct ListNotificationsQuery {
+    /// Only notifications that have not been read.
+    #[serde(default)]
+    unread_only: bool,
+    limit: Option<i64>,
+    cursor: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationView {
+    id: String,
+    /// The event, as in the notification settings.
+    kind: String,
+    project_id: Option<String>,
+    run_id: Option<String>,
+    run_item_id: Option<String>,
+    title: String,
+    body: String,
+    read_at: Option<DateTime<Utc>>,
+    created_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ListNotificationsResponse {
+    notifications: Vec<NotificationView>,
+    unread_count: i64,
+    next_cursor: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct MarkNotificationsReadRequest {
+    /// Up to 500 ids; ids of other users' or already read notifications are ignored.
+    ids: Vec<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct MarkNotificationsReadResponse {
+    /// Notifications marked read by this request.
+    updated: i64,
+    unread_count: i64,
+}
+
+/// The caller's feed, newest first.
+#[utoipa::path(
+    get,
+    path = "/api/v2/me/notifications",
+    tag = "notifications",
+    params(ListNotificationsQuery),
+    responses((status = 200, body = ListNotificationsResponse))
+)]
+pub async fn list_notifications(
+    State(state): State<AppState>,
+    AuthUser(user_id): AuthUser,
+    Query(query): Query<ListNotificationsQuery>,
+) -> Result<Json<ListNotificationsResponse>, ApiError> {
+    let user_uuid = parse_uuid(&user_id, ApiError::InvalidUserId)?;
+    let limit = pagination::page_limit(query.limit);
+    let cursor = pagination::parse_cursor(query.cursor.as_deref())?;
+
+    let rows = sqlx::query_as!(
+        NotificationView,
+        r#"
+        SELECT
+          id::text AS "id!",
+          kind,
+          project_id::text AS project_id,
+          run_id::text AS run_id,
+          run_item_id::text AS run_item_id,
+          title,
+          body,
+          read_at,
+          created_at
+        FROM notifications
+        WHERE user_id = $1
+          AND (NOT $2 OR read_at IS NULL)
+          AND ($3::timestamptz IS NULL OR (created_at, id) < ($3::timestamptz, $4::uuid))
+        ORDER BY created_at DESC, id DESC
+        LIMIT $5
+        "#,
+        user_uuid,
+        query.unread_only,
+        cursor.as_ref().map(|c| c.timestamp()),
+        cursor.as_ref().map(|c| c.uuid()),
+        limit + 1,
+    )
+    .fetch_all(&state.db)
+    .await
+    .map_err(|_| ApiError::NotificationsReadFailed)?;
+    let (notifications, next_cursor) =
+        pagination::finish_page(rows, limit, |n| pagination::Cursor {
+            created_at: n.created_at.to_rfc3339(),
+            id: n.id.clone(),
+        });
+
+    Ok(Json(ListNotificationsResponse {
+        notifications,
+        unread_count: unread_count(&state, user_uuid).await?,
+        next_cursor,
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v2/me/notifications/read",
+    tag = "notifications",
+    request_body = MarkNotificationsReadRequest,
+    responses((status = 200, body = MarkNotificationsReadResponse))
+)]
+pub async fn mark_read(
+    State(state): State<AppState>,
+    AuthUser(user_id): AuthUser,
+    Json(payload): Json<MarkNotificationsReadRequest>,
+) -> Result<Json<MarkNotificationsReadResponse>, ApiError> {
+    let user_uuid = parse_uuid(&user_id, ApiError::InvalidUserId)?;
+    if payload.ids.len() > MAX_READ_IDS {
+        return Err(ApiError::TooManyNotificationIds);
+    }
+    let ids = payload
+        .ids
+        .iter()
+        .map(|id| parse_uuid(id, ApiError::InvalidNotificationId))
+        .collect::<Result<Vec<_>, _>>()?;
+    let updated = sqlx::query!(
+        r#"
+        UPDATE notifications
+        SET read_at = NOW()
+        WHERE user_id = $1 AND id = ANY($2) AND read_at IS NULL
+        "#,
+        user_uuid,
+        &ids,
+    )
+    .execute(&state.db)
+    .await
+    .map_err(|_| ApiError::NotificationsUpdateFailed)?
+    .rows_affected();
+    Ok(Json(MarkNotificationsReadResponse {
+        updated: updated as i64,
+        unread_count: unread_count(&state, user_uuid).await?,
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v2/me/notifications/read-all",
+    tag = "notifications",
+    responses((status = 200, body = MarkNotificationsReadResponse))
+)]
+pub async fn mark_all_read(
+    State(state): State<AppState>,
+    AuthUser(user_id): AuthUser,
+) -> Result<Json<MarkNotificationsReadResponse>, ApiError> {
+    let user_uuid = parse_uuid(&user_id, ApiError::InvalidUserId)?;
+    let updated = sqlx::query!(
+        "UPDATE notifications SET read_at = NOW() WHERE user_id = $1 AND read_at IS NULL",
+        user_uuid,
+    )
+    .execute(&state.db)
+    .await
+    .map_err(|_| ApiError::NotificationsUpdateFailed)?
+    .rows_affected();
+    Ok(Json(MarkNotificationsReadResponse {
+        updated: updated as i64,
+        unread_count: unread_count(&state, user_uuid).await?,
+    }))
+}
+
+async fn unread_count(state: &AppState, user_id: Uuid) -> Result<i64, ApiError> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*) AS "count!"
+        FROM notifications
+        WHERE user_id = $1 AND read_at IS NULL
+        "#,
+        user_id,
+    )
+    .fetch_one(&state.db)
+    .await
+    .map_err(|_| ApiError::NotificationsReadFailed)
+}
+
+/// Deletes notifications older than [`RETENTION_DAYS`].
+pub async fn prune(state: &AppState) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        "DELETE FROM notifications WHERE created_at < NOW() - make_interval(days => $1)",
+        RETENTION_DAYS,
+    )
+    .execute(&state.db)
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,307 @@
+use std::collections::HashMap;
+
+use axum::{
+    body::Body,
+    extract::{Path, State},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use tokio_stream::StreamExt;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{
+    authz::{self, AuthUser},
+    ensure_db_user_exists,
+    error::{current_lang, ApiError, ErrorBody},
+    etag::VersionError,
+    parse_uuid,
+    permissions::Capability,
+    save_result_batch, version_error_body, AppState, ResultInput, UpdateRunResultRequest,
+};
+
+/// Records written per transaction.
+const INGEST_BATCH: usize = 200;
+const MAX_INGEST_RECORDS: usize = 100_000;
+const MAX_LINE_BYTES: usize = 64 * 1024;
+/// Failures listed in the response; the counters include all of them.
+const MAX_REPORTED_ERRORS: usize = 1000;
+
+/// One line of the upload. The item is given by `runItemId` or, for frameworks that only
+/// know their tests, by `testcaseKey`.
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct IngestRecord {
+    run_item_id: Option<String>,
+    testcase_key: Option<String>,
+    status: String,
+    fail_reason_code: Option<String>,
+    comment: Option<String>,
+    elapsed_seconds: Option<i32>,
+}
+
+/// Where a record came from, to report it back.
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct IngestSource {
+    /// 1-based line of the upload.
+    line: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    run_item_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    testcase_key: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct IngestErrorView {
+    #[serde(flatten)]
+    source: IngestSource,
+    error: ErrorBody,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct IngestResultsResponse {
+    /// Non-empty lines read.
+    received: usize,
+    succeeded: usize,
+    failed: usize,
+    /// Rejected lines in upload order, at most 1000.
+    errors: Vec<IngestErrorView>,
+    errors_truncated: bool,
+}
+
+/// Splits the body into lines as chunks arrive; a line longer than `MAX_LINE_BYTES` is
+/// dropped up to its end and reported as `Err`.
+#[derive(Default)]
+struct LineSplitter {
+    pending: Vec<u8>,
+    skipping: bool,
+}
+
+impl LineSplitter {
+    fn push(&mut self, mut chunk: &[u8], lines: &mut Vec<Result<Vec<u8>, ()>>) {
+        while let Some(end) = chunk.iter().position(|b| *b == b'\n') {
+            if self.skipping {
+                self.skipping = false;
+            } else if self.pending.len() + end > MAX_LINE_BYTES {
+                self.pending.clear();
+                lines.push(Err(()));
+            } else {
+                self.pending.extend_from_slice(&chunk[..end]);
+                lines.push(Ok(std::mem::take(&mut self.pending)));
+            }
+            chunk = &chunk[end + 1..];
+        }
+        if self.skipping {
+            return;
+        }
+        self.pending.extend_from_slice(chunk);
+        if self.pending.len() > MAX_LINE_BYTES {
+            self.pending.clear();
+            self.skipping = true;
+            lines.push(Err(()));
+        }
+    }
+
+    fn finish(self, lines: &mut Vec<Result<Vec<u8>, ()>>) {
+        if !self.skipping && !self.pending.is_empty() {
+            lines.push(Ok(self.pending));
+        }
+    }
+}
+
+struct Ingest<'a> {
+    state: &'a AppState,
+    run_uuid: Uuid,
+    actor_id: &'a str,
+    actor_uuid: Uuid,
+    /// Run items by test case key, loaded with the first record that uses a key.
+    items_by_key: Option<HashMap<String, Uuid>>,
+    batch: Vec<(IngestSource, Uuid, ResultInput)>,
+    response: IngestResultsResponse,
+}
+
+impl Ingest<'_> {
+    fn reject(&mut self, source: IngestSource, error: impl Into<VersionError>) {
+        self.response.failed += 1;
+        if self.response.errors.len() < MAX_REPORTED_ERRORS {
+            self.response.errors.push(IngestErrorView {
+                source,
+                error: version_error_body(error.into(), current_lang()),
+            });
+        } else {
+            self.response.errors_truncated = true;
+        }
+    }
+
+    async fn item_by_key(&mut self, key: &str) -> Result<Option<Uuid>, ApiError> {
+        if self.items_by_key.is_none() {
+            let rows: Vec<(String, Uuid)> = sqlx::query_as(
+                r#"
+                SELECT DISTINCT ON (tc.key) tc.key, ri.id
+                FROM run_items ri
+                JOIN testcase_versions tv ON tv.id = ri.testcase_version_id
+                JOIN testcases tc ON tc.id = tv.testcase_id
+                WHERE ri.run_id = $1
+                ORDER BY tc.key, ri.position ASC, ri.created_at ASC
+                "#,
+            )
+            .bind(self.run_uuid)
+            .fetch_all(&self.state.db)
+            .await
+            .map_err(|_| ApiError::RunItemsReadFailed)?;
+            self.items_by_key = Some(rows.into_iter().collect());
+        }
+        Ok(self
+            .items_by_key
+            .as_ref()
+            .and_then(|items| items.get(key).copied()))
+    }
+
+    async fn line(&mut self, line: usize, raw: Result<Vec<u8>, ()>) -> Result<(), ApiError> {
+        let mut source = IngestSource {
+            line,
+            run_item_id: None,
+            testcase_key: None,
+        };
+        let Ok(raw) = raw else {
+            self.response.received += 1;
+            self.reject(source, ApiError::IngestLineTooLong);
+            return Ok(());
+        };
+        if raw.trim_ascii().is_empty() {
+            return Ok(());
+        }
+        self.response.received += 1;
+        if self.response.received > MAX_INGEST_RECORDS {
+            self.flush().await?;
+            return Err(ApiError::TooManyIngestRecords);
+        }
+        let Ok(record) = serde_json::from_slice::<IngestRecord>(&raw) else {
+            self.reject(source, ApiError::InvalidIngestRecord);
+            return Ok(());
+        };
+        source.run_item_id = record.run_item_id.filter(|v| !v.trim().is_empty());
+        source.testcase_key = record.testcase_key.filter(|v| !v.trim().is_empty());
+        let run_item_uuid = match (&source.run_item_id, &source.testcase_key) {
+            (Some(id), _) => parse_uuid(id.trim(), ApiError::InvalidRunItemId),
+            (None, Some(key)) => self
+                .item_by_key(key.trim())
+                .await?
+                .ok_or(ApiError::IngestTestcaseNotInRun),
+            (None, None) => Err(ApiError::InvalidIngestRecord),
+        };
+        let parsed = run_item_uuid.and_then(|uuid| {
+            let input = ResultInput::parse(UpdateRunResultRequest {
+                status: record.status,
+                fail_reason_code: record.fail_reason_code,
+                comment: record.comment,
+                elapsed_seconds: record.elapsed_seconds,
+                steps: None,
+            })?;
+            Ok((uuid, input))
+        });
+        match parsed {
+            Ok((uuid, input)) => {
+                self.batch.push((source, uuid, input));
+                if self.batch.len() >= INGEST_BATCH {
+                    self.flush().await?;
+                }
+            }
+            Err(err) => self.reject(source, err),
+        }
+        Ok(())
+    }
+
+    async fn flush(&mut self) -> Result<(), ApiError> {
+        if self.batch.is_empty() {
+            return Ok(());
+        }
+        let (sources, pending): (Vec<_>, Vec<_>) = std::mem::take(&mut self.batch)
+            .into_iter()
+            .map(|(source, uuid, input)| (source, (uuid, input)))
+            .unzip();
+        let outcomes = save_result_batch(
+            self.state,
+            self.run_uuid,
+            self.actor_id,
+            self.actor_uuid,
+            pending,
+        )
+        .await?;
+        for (source, outcome) in sources.into_iter().zip(outcomes) {
+            match outcome {
+                Ok(_) => self.response.succeeded += 1,
+                Err(err) => self.reject(source, err),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Records results streamed as NDJSON, one `IngestRecord` per line, for uploads too large
+/// for `results/bulk`. The body is read as it arrives and written every 200 records, each
+/// batch in its own transaction: a rejected line is reported in `errors` and does not stop
+/// the upload, while an error response (the run got locked, the database failed, more than
+/// 100000 records) leaves the batches before it saved. Sending the same upload again is
+/// safe, it only records the same results once more. A later line for the same item wins.
+#[utoipa::path(
+    post,
+    path = "/api/v2/runs/{run_id}/results/ingest",
+    tag = "results",
+    params(("run_id" = String, Path)),
+    request_body(content = IngestRecord, content_type = "application/x-ndjson"),
+    responses((status = 200, body = IngestResultsResponse))
+)]
+pub async fn ingest_results(
+    State(state): State<AppState>,
+    Path(run_id): Path<String>,
+    AuthUser(actor_id): AuthUser,
+    body: Body,
+) -> Result<Json<IngestResultsResponse>, ApiError> {
+    let run_uuid = parse_uuid(&run_id, ApiError::InvalidRunId)?;
+    authz::require_run_capability(&state, run_uuid, &actor_id, Capability::ResultEdit).await?;
+    ensure_db_user_exists(&state, &actor_id).await?;
+    let actor_uuid = parse_uuid(&actor_id, ApiError::InvalidUserId)?;
+
+    let mut ingest = Ingest {
+        state: &state,
+        run_uuid,
+        actor_id: &actor_id,
+        actor_uuid,
+        items_by_key: None,
+        batch: Vec::new(),
+        response: IngestResultsResponse {
+            received: 0,
+            succeeded: 0,
+            failed: 0,
+            errors: Vec::new(),
+            errors_truncated: false,
+        },
+    };
+    let mut stream = body.into_data_stream();
+    let mut splitter = LineSplitter::default();
+    let mut lines = Vec::new();
+    let mut line_number = 0;
+    loop {
+        let chunk = stream.next().await;
+        match &chunk {
+            Some(Ok(bytes)) => splitter.push(bytes, &mut lines),
+            Some(Err(_)) => return Err(ApiError::IngestBodyReadFailed),
+            None => std::mem::take(&mut splitter).finish(&mut lines),
+        }
+        for raw in lines.drain(..) {
+            line_number += 1;
+            ingest.line(line_number, raw).await?;
+        }
+        if chunk.is_none() {
+            break;
+        }
+    }
+    ingest.flush().await?;
+    if ingest.response.received == 0 {
+        return Err(ApiError::IngestEmpty);
+    }
+    Ok(Json(ingest.response))
+}
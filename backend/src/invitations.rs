@@ -0,0 +1,331 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{
+    authz::AuthUser, error::ApiError, now_iso, permissions, permissions::Capability, read_projects,
+    read_users, webhooks, write_projects, AppState, Project, ProjectMember, User,
+};
+
+const INVITATION_TTL_DAYS: i64 = 14;
+
+/// Pending invitation of a not yet registered email, stored in `projects.json`.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectInvitation {
+    pub id: String,
+    pub email: String,
+    pub role: String,
+    pub token_hash: String,
+    pub invited_by_user_id: String,
+    pub created_at: String,
+    pub expires_at: String,
+}
+
+impl ProjectInvitation {
+    fn is_expired(&self) -> bool {
+        chrono::DateTime::parse_from_rfc3339(&self.expires_at)
+            .map(|t| t <= chrono::Utc::now())
+            .unwrap_or(true)
+    }
+}
+
+fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+fn invite_url(state: &AppState, token: &str) -> String {
+    format!("{}/?invite={token}", state.public_url.trim_end_matches('/'))
+}
+
+/// Adds the user to every project with a live invitation for their email and drops the
+/// consumed and expired invitations. Returns `(project_id, role)` of the attached projects.
+pub fn attach_pending_invitations(projects: &mut [Project], user: &User) -> Vec<(String, String)> {
+    let mut attached = Vec::new();
+    for project in projects.iter_mut() {
+        let before = project.invitations.len();
+        let mut role = None;
+        project.invitations.retain(|invite| {
+            if invite.is_expired() {
+                return false;
+            }
+            if invite.email == user.email {
+                role = Some(invite.role.clone());
+                return false;
+            }
+            true
+        });
+        if let Some(role) = role {
+            if !project.members.iter().any(|m| m.user_id == user.id) {
+                project.members.push(ProjectMember {
+                    user_id: user.id.clone(),
+                    role: role.clone(),
+                });
+            }
+            attached.push((project.id.clone(), role));
+        }
+        if project.invitations.len() != before {
+            project.updated_at = now_iso();
+        }
+    }
+    attached
+}
+
+/// Emits `member.added` for projects joined through invitations; call after the write.
+pub async fn emit_attached(state: &AppState, user: &User, attached: &[(String, String)]) {
+    for (project_id, role) in attached {
+        if let Ok(project_uuid) = Uuid::parse_str(project_id) {
+            webhooks::emit(
+                state,
+                project_uuid,
+                "member.added",
+                json!({ "userId": &user.id, "email": &user.email, "name": &user.name, "role": role }),
+            )
+            .await;
+        }
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateInvitationRequest {
+    email: String,
+    role: String,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct InvitationView {
+    id: String,
+    email: String,
+    role: String,
+    invited_by_user_id: String,
+    created_at: String,
+    expires_at: String,
+}
+
+impl From<&ProjectInvitation> for InvitationView {
+    fn from(invite: &ProjectInvitation) -> Self {
+        Self {
+            id: invite.id.clone(),
+            email: invite.email.clone(),
+            role: invite.role.clone(),
+            invited_by_user_id: invite.invited_by_user_id.clone(),
+            created_at: invite.created_at.clone(),
+            expires_at: invite.expires_at.clone(),
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateInvitationResponse {
+    invitation: InvitationView,
+    /// Contains the token, so it is returned only once.
+    invite_url: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ListInvitationsResponse {
+    invitations: Vec<InvitationView>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RevokeInvitationResponse {
+    ok: bool,
+    updated_at: String,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct InvitationLookupResponse {
+    email: String,
+    role: String,
+    project_name: String,
+    expires_at: String,
+}
+
+/// Invites an email that has no account yet. Re-inviting the same email replaces the previous
+/// invitation, so the old link stops working.
+#[utoipa::path(
+    post,
+    path = "/api/projects/{project_id}/invitations",
+    tag = "invitations",
+    params(("project_id" = String, Path)),
+    request_body = CreateInvitationRequest,
+    responses((status = 201, body = CreateInvitationResponse))
+)]
+pub async fn create_invitation(
+    State(state): State<AppState>,
+    Path(project_id): Path<String>,
+    AuthUser(actor_id): AuthUser,
+    Json(payload): Json<CreateInvitationRequest>,
+) -> Result<(StatusCode, Json<CreateInvitationResponse>), ApiError> {
+    let email = payload.email.trim().to_lowercase();
+    let role = payload.role.trim().to_lowercase();
+    if !email.contains('@') {
+        return Err(ApiError::InvalidEmail);
+    }
+
+    let _guard = state.file_lock.lock().await;
+    let users = read_users(&state.users_file)
+        .await
+        .map_err(|_| ApiError::InvitationCreateFailed)?;
+    let mut projects = read_projects(&state.projects_file)
+        .await
+        .map_err(|_| ApiError::InvitationCreateFailed)?;
+    let project = projects
+        .iter_mut()
+        .find(|p| p.id == project_id)
+        .ok_or(ApiError::ProjectNotFound)?;
+    permissions::check(project, &actor_id, Capability::ProjectManage)?;
+    if !permissions::is_assignable_role(project, &role) {
+        return Err(ApiError::InvalidMemberRole);
+    }
+    if users.iter().any(|u| u.email == email) {
+        return Err(ApiError::InviteeRegistered);
+    }
+
+    let token = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+    let now = chrono::Utc::now();
+    let invite = ProjectInvitation {
+        id: Uuid::new_v4().to_string(),
+        email: email.clone(),
+        role,
+        token_hash: hash_token(&token),
+        invited_by_user_id: actor_id,
+        created_at: now.to_rfc3339(),
+        expires_at: (now + chrono::Duration::days(INVITATION_TTL_DAYS)).to_rfc3339(),
+    };
+    project
+        .invitations
+        .retain(|i| i.email != email && !i.is_expired());
+    project.invitations.push(invite.clone());
+    project.updated_at = now_iso();
+    write_projects(&state, &projects)
+        .await
+        .map_err(|_| ApiError::InvitationCreateFailed)?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(CreateInvitationResponse {
+            invitation: InvitationView::from(&invite),
+            invite_url: invite_url(&state, &token),
+        }),
+    ))
+}
+
+/// Pending (not expired) invitations of the project.
+#[utoipa::path(
+    get,
+    path = "/api/projects/{project_id}/invitations",
+    tag = "invitations",
+    params(("project_id" = String, Path)),
+    responses((status = 200, body = ListInvitationsResponse))
+)]
+pub async fn list_invitations(
+    State(state): State<AppState>,
+    Path(project_id): Path<String>,
+    AuthUser(actor_id): AuthUser,
+) -> Result<Json<ListInvitationsResponse>, ApiError> {
+    let _guard = state.file_lock.lock().await;
+    let projects = read_projects(&state.projects_file)
+        .await
+        .map_err(|_| ApiError::InvitationsLoadFailed)?;
+    let project = projects
+        .iter()
+        .find(|p| p.id == project_id)
+        .ok_or(ApiError::ProjectNotFound)?;
+    permissions::check(project, &actor_id, Capability::ProjectManage)?;
+
+    Ok(Json(ListInvitationsResponse {
+        invitations: project
+            .invitations
+            .iter()
+            .filter(|i| !i.is_expired())
+            .map(InvitationView::from)
+            .collect(),
+    }))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/projects/{project_id}/invitations/{invitation_id}",
+    tag = "invitations",
+    params(("project_id" = String, Path), ("invitation_id" = String, Path)),
+    responses((status = 200, body = RevokeInvitationResponse))
+)]
+pub async fn revoke_invitation(
+    State(state): State<AppState>,
+    Path((project_id, invitation_id)): Path<(String, String)>,
+    AuthUser(actor_id): AuthUser,
+) -> Result<Json<RevokeInvitationResponse>, ApiError> {
+    let _guard = state.file_lock.lock().await;
+    let mut projects = read_projects(&state.projects_file)
+        .await
+        .map_err(|_| ApiError::InvitationRevokeFailed)?;
+    let project = projects
+        .iter_mut()
+        .find(|p| p.id == project_id)
+        .ok_or(ApiError::ProjectNotFound)?;
+    permissions::check(project, &actor_id, Capability::ProjectManage)?;
+
+    let before = project.invitations.len();
+    project.invitations.retain(|i| i.id != invitation_id);
+    if project.invitations.len() == before {
+        return Err(ApiError::InvitationNotFound);
+    }
+    project.updated_at = now_iso();
+    let updated_at = project.updated_at.clone();
+    write_projects(&state, &projects)
+        .await
+        .map_err(|_| ApiError::InvitationRevokeFailed)?;
+
+    Ok(Json(RevokeInvitationResponse {
+        ok: true,
+        updated_at,
+    }))
+}
+
+/// Public lookup used by the registration page to prefill the invited email.
+#[utoipa::path(
+    get,
+    path = "/api/invitations/{token}",
+    tag = "invitations",
+    params(("token" = String, Path)),
+    responses((status = 200, body = InvitationLookupResponse)),
+    security(())
+)]
+pub async fn get_invitation(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+) -> Result<Json<InvitationLookupResponse>, ApiError> {
+    let token_hash = hash_token(token.trim());
+
+    let _guard = state.file_lock.lock().await;
+    let projects = read_projects(&state.projects_file)
+        .await
+        .map_err(|_| ApiError::InvitationLoadFailed)?;
+    projects
+        .iter()
+        .find_map(|p| {
+            p.invitations
+                .iter()
+                .find(|i| i.token_hash == token_hash && !i.is_expired())
+                .map(|i| InvitationLookupResponse {
+                    email: i.email.clone(),
+                    role: i.role.clone(),
+                    project_name: p.name.clone(),
+                    expires_at: i.expires_at.clone(),
+                })
+        })
+        .map(Json)
+        .ok_or(ApiError::InvitationExpired)
+}
@@ -0,0 +1,585 @@
+use std::time::Duration;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sqlx::Row;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{
+    audit,
+    authz::{self, AuthUser, ProjectRole},
+    db_errors,
+    defects::{self, DefectLinkView},
+    ensure_db_user_exists,
+    error::ApiError,
+    parse_uuid,
+    permissions::Capability,
+    secrets, AppState,
+};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+const MAX_SUMMARY_CHARS: usize = 255;
+
+/// HTTP client for the Jira REST API; stored API tokens are sealed with [`secrets`].
+pub struct JiraClient {
+    http: reqwest::Client,
+}
+
+impl JiraClient {
+    pub fn new() -> anyhow::Result<Self> {
+        Ok(Self {
+            http: reqwest::Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .build()?,
+        })
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct JiraConnectionRequest {
+    /// `https://example.atlassian.net`
+    base_url: String,
+    /// Account email for Jira Cloud; without it the token is sent as a personal access token
+    /// (Jira Server / Data Center).
+    email: Option<String>,
+    /// Required on the first save; omitted keeps the stored token.
+    api_token: Option<String>,
+    /// Key of the Jira project issues are created in, e.g. `QA`.
+    jira_project_key: String,
+    /// `Bug` by default.
+    issue_type: Option<String>,
+}
+
+/// The stored connection; the API token is never returned.
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct JiraConnectionView {
+    base_url: String,
+    email: Option<String>,
+    jira_project_key: String,
+    issue_type: String,
+    updated_at: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct JiraConnectionResponse {
+    connection: Option<JiraConnectionView>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct DeleteJiraConnectionResponse {
+    ok: bool,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateJiraIssueRequest {
+    /// Issue title; the testcase title by default.
+    summary: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct JiraIssueResponse {
+    defect: DefectLinkView,
+}
+
+async fn fetch_connection(
+    state: &AppState,
+    project_id: Uuid,
+) -> Result<Option<sqlx::postgres::PgRow>, ApiError> {
+    sqlx::query(
+        r#"
+        SELECT base_url, email, api_token_encrypted, jira_project_key, issue_type,
+               updated_at::text AS updated_at
+        FROM project_jira_connections
+        WHERE project_id = $1
+        "#,
+    )
+    .bind(project_id)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|_| ApiError::JiraConnectionReadFailed)
+}
+
+fn map_connection_row(r: &sqlx::postgres::PgRow) -> JiraConnectionView {
+    JiraConnectionView {
+        base_url: r.get("base_url"),
+        email: r.get("email"),
+        jira_project_key: r.get("jira_project_key"),
+        issue_type: r.get("issue_type"),
+        updated_at: r.get("updated_at"),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v2/projects/{project_id}/jira",
+    tag = "defects",
+    params(("project_id" = String, Path)),
+    responses((status = 200, body = JiraConnectionResponse))
+)]
+pub async fn get_jira_connection(
+    State(state): State<AppState>,
+    access: ProjectRole,
+) -> Result<Json<JiraConnectionResponse>, ApiError> {
+    let row = fetch_connection(&state, access.project_id).await?;
+    Ok(Json(JiraConnectionResponse {
+        connection: row.as_ref().map(map_connection_row),
+    }))
+}
+
+/// Saves the connection. The project's issue tracker is set to this Jira unless one is
+/// configured already, so manually linked keys resolve to Jira URLs too.
+#[utoipa::path(
+    put,
+    path = "/api/v2/projects/{project_id}/jira",
+    tag = "defects",
+    params(("project_id" = String, Path)),
+    request_body = JiraConnectionRequest,
+    responses((status = 200, body = JiraConnectionResponse))
+)]
+pub async fn put_jira_connection(
+    State(state): State<AppState>,
+    access: ProjectRole,
+    Json(payload): Json<JiraConnectionRequest>,
+) -> Result<Json<JiraConnectionResponse>, ApiError> {
+    access.require(Capability::ProjectManage)?;
+    let ProjectRole {
+        user_id: actor_id,
+        project_id,
+        ..
+    } = access;
+    let base_url = payload.base_url.trim().trim_end_matches('/').to_string();
+    if !(base_url.starts_with("https://") || base_url.starts_with("http://")) {
+        return Err(ApiError::InvalidTrackerUrl);
+    }
+    let email = payload
+        .email
+        .as_deref()
+        .map(str::trim)
+        .filter(|e| !e.is_empty())
+        .map(str::to_string);
+    let jira_project_key = payload.jira_project_key.trim().to_string();
+    let mut chars = jira_project_key.chars();
+    let key_valid = (1..=50).contains(&jira_project_key.len())
+        && chars.next().is_some_and(|c| c.is_ascii_uppercase())
+        && chars.all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_');
+    if !key_valid {
+        return Err(ApiError::InvalidJiraProjectKey);
+    }
+    let issue_type = payload
+        .issue_type
+        .as_deref()
+        .map(str::trim)
+        .unwrap_or("Bug")
+        .to_string();
+    if !(1..=100).contains(&issue_type.chars().count()) {
+        return Err(ApiError::InvalidJiraIssueType);
+    }
+    let secrets = secrets::require(&state)?;
+    let sealed_token = payload
+        .api_token
+        .as_deref()
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .map(|token| secrets.seal(token))
+        .transpose()?;
+    ensure_db_user_exists(&state, &actor_id).await?;
+    let actor_uuid = parse_uuid(&actor_id, ApiError::InvalidUserId)?;
+    let before = fetch_connection(&state, project_id).await?;
+    if before.is_none() && sealed_token.is_none() {
+        return Err(ApiError::JiraTokenRequired);
+    }
+
+    let save_failed = |_| ApiError::JiraConnectionSaveFailed;
+    let mut tx = state.db.begin().await.map_err(save_failed)?;
+    sqlx::query(
+        r#"
+        INSERT INTO project_jira_connections (
+          project_id, base_url, email, api_token_encrypted, jira_project_key, issue_type,
+          updated_by_user_id
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        ON CONFLICT (project_id) DO UPDATE SET
+          base_url = EXCLUDED.base_url,
+          email = EXCLUDED.email,
+          api_token_encrypted = COALESCE($4, project_jira_connections.api_token_encrypted),
+          jira_project_key = EXCLUDED.jira_project_key,
+          issue_type = EXCLUDED.issue_type,
+          updated_by_user_id = EXCLUDED.updated_by_user_id,
+          updated_at = NOW()
+        "#,
+    )
+    .bind(project_id)
+    .bind(&base_url)
+    .bind(&email)
+    .bind(&sealed_token)
+    .bind(&jira_project_key)
+    .bind(&issue_type)
+    .bind(actor_uuid)
+    .execute(&mut *tx)
+    .await
+    .map_err(save_failed)?;
+    sqlx::query(
+        r#"
+        INSERT INTO project_issue_trackers (project_id, tracker_type, base_url, updated_by_user_id)
+        VALUES ($1, 'jira', $2, $3)
+        ON CONFLICT (project_id) DO NOTHING
+        "#,
+    )
+    .bind(project_id)
+    .bind(&base_url)
+    .bind(actor_uuid)
+    .execute(&mut *tx)
+    .await
+    .map_err(save_failed)?;
+    audit::record(
+        &mut *tx,
+        audit::AuditEntry {
+            actor_user_id: Some(actor_uuid),
+            action: if before.is_some() { "update" } else { "create" },
+            entity_type: "jira_connection",
+            entity_id: None,
+            project_id: Some(project_id),
+            run_id: None,
+            before: before.as_ref().map(|r| json!(map_connection_row(r))),
+            after: Some(json!({
+                "baseUrl": &base_url,
+                "email": &email,
+                "jiraProjectKey": &jira_project_key,
+                "issueType": &issue_type,
+                "tokenChanged": sealed_token.is_some(),
+            })),
+        },
+    )
+    .await
+    .map_err(save_failed)?;
+    tx.commit().await.map_err(save_failed)?;
+
+    let row = fetch_connection(&state, project_id).await?;
+    Ok(Json(JiraConnectionResponse {
+        connection: row.as_ref().map(map_connection_row),
+    }))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v2/projects/{project_id}/jira",
+    tag = "defects",
+    params(("project_id" = String, Path)),
+    responses((status = 200, body = DeleteJiraConnectionResponse))
+)]
+pub async fn delete_jira_connection(
+    State(state): State<AppState>,
+    access: ProjectRole,
+) -> Result<Json<DeleteJiraConnectionResponse>, ApiError> {
+    access.require(Capability::ProjectManage)?;
+    let ProjectRole {
+        user_id: actor_id,
+        project_id,
+        ..
+    } = access;
+    ensure_db_user_exists(&state, &actor_id).await?;
+    let actor_uuid = parse_uuid(&actor_id, ApiError::InvalidUserId)?;
+    let before = fetch_connection(&state, project_id)
+        .await?
+        .ok_or(ApiError::JiraNotConfigured)?;
+
+    let delete_failed = |_| ApiError::JiraConnectionSaveFailed;
+    let mut tx = state.db.begin().await.map_err(delete_failed)?;
+    sqlx::query("DELETE FROM project_jira_connections WHERE project_id = $1")
+        .bind(project_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(delete_failed)?;
+    audit::record(
+        &mut *tx,
+        audit::AuditEntry {
+            actor_user_id: Some(actor_uuid),
+            action: "delete",
+            entity_type: "jira_connection",
+            entity_id: None,
+            project_id: Some(project_id),
+            run_id: None,
+            before: Some(json!(map_connection_row(&before))),
+            after: None,
+        },
+    )
+    .await
+    .map_err(delete_failed)?;
+    tx.commit().await.map_err(delete_failed)?;
+
+    Ok(Json(DeleteJiraConnectionResponse { ok: true }))
+}
+
+/// Escapes text for Jira wiki markup, which the v2 REST API uses for descriptions.
+fn wiki(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.trim().chars() {
+        if matches!(
+            c,
+            '{' | '}' | '[' | ']' | '|' | '*' | '_' | '#' | '-' | '^' | '+'
+        ) {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Creates a Jira issue for a failed result, pre-filled with the testcase, its steps and
+/// their results, the fail reason, the comment and attachment links, and links its key to
+/// the result like a manually added defect.
+#[utoipa::path(
+    post,
+    path = "/api/v2/runs/{run_id}/items/{run_item_id}/jira-issue",
+    tag = "defects",
+    params(("run_id" = String, Path), ("run_item_id" = String, Path)),
+    request_body(content = Option<CreateJiraIssueRequest>),
+    responses((status = 201, body = JiraIssueResponse))
+)]
+pub async fn create_jira_issue(
+    State(state): State<AppState>,
+    Path((run_id, run_item_id)): Path<(String, String)>,
+    AuthUser(actor_id): AuthUser,
+    payload: Option<Json<CreateJiraIssueRequest>>,
+) -> Result<(StatusCode, Json<JiraIssueResponse>), ApiError> {
+    let run_uuid = parse_uuid(&run_id, ApiError::InvalidRunId)?;
+    let run_item_uuid = parse_uuid(&run_item_id, ApiError::InvalidRunItemId)?;
+    let summary_override = match payload.and_then(|Json(p)| p.summary) {
+        Some(summary) => {
+            let summary = summary.trim().to_string();
+            if !(1..=MAX_SUMMARY_CHARS).contains(&summary.chars().count()) {
+                return Err(ApiError::InvalidJiraSummary);
+            }
+            Some(summary)
+        }
+        None => None,
+    };
+    authz::require_run_capability(&state, run_uuid, &actor_id, Capability::ResultEdit).await?;
+    ensure_db_user_exists(&state, &actor_id).await?;
+    let actor_uuid = parse_uuid(&actor_id, ApiError::InvalidUserId)?;
+
+    let context = sqlx::query(
+        r#"
+        SELECT
+          rr.id AS run_result_id,
+          rr.status::text AS status,
+          rr.fail_reason_code,
+          COALESCE(pfr.title, fr.title) AS fail_reason_title,
+          rr.comment,
+          r.project_id,
+          r.title AS run_title,
+          tc.key AS testcase_key,
+          tc.title AS testcase_title,
+          tv.version_number,
+          tv.preconditions
+        FROM run_items ri
+        JOIN runs r ON r.id = ri.run_id
+        JOIN run_results rr ON rr.run_item_id = ri.id
+        JOIN testcase_versions tv ON tv.id = ri.testcase_version_id
+        JOIN testcases tc ON tc.id = tv.testcase_id
+        LEFT JOIN project_fail_reasons pfr
+          ON pfr.project_id = r.project_id AND pfr.code = rr.fail_reason_code
+        LEFT JOIN fail_reasons fr ON fr.code = rr.fail_reason_code
+        WHERE r.id = $1 AND ri.id = $2
+        "#,
+    )
+    .bind(run_uuid)
+    .bind(run_item_uuid)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|_| ApiError::ResultReadFailed)?
+    .ok_or(ApiError::ResultNotFound)?;
+    if context.get::<String, _>("status") != "fail" {
+        return Err(ApiError::DefectRequiresFail);
+    }
+    let run_result_id: Uuid = context.get("run_result_id");
+    let project_id: Uuid = context.get("project_id");
+    let connection = fetch_connection(&state, project_id)
+        .await?
+        .ok_or(ApiError::JiraNotConfigured)?;
+    let token = secrets::require(&state)?
+        .open(connection.get::<&[u8], _>("api_token_encrypted"))
+        .ok_or(ApiError::JiraTokenUnreadable)?;
+    let base_url: String = connection.get("base_url");
+
+    let steps = sqlx::query(
+        r#"
+        SELECT ts.action, ts.expected_result, rs.status::text AS status, rs.comment
+        FROM run_items ri
+        JOIN testcase_steps ts ON ts.testcase_version_id = ri.testcase_version_id
+        LEFT JOIN run_result_steps rs ON rs.run_item_id = ri.id AND rs.step_id = ts.id
+        WHERE ri.id = $1
+        ORDER BY ts.position ASC
+        "#,
+    )
+    .bind(run_item_uuid)
+    .fetch_all(&state.db)
+    .await
+    .map_err(|_| ApiError::ResultReadFailed)?;
+    let attachments = sqlx::query(
+        r#"
+        SELECT id::text AS id, file_name
+        FROM attachments
+        WHERE run_result_id = $1
+        ORDER BY created_at ASC
+        "#,
+    )
+    .bind(run_result_id)
+    .fetch_all(&state.db)
+    .await
+    .map_err(|_| ApiError::ResultReadFailed)?;
+
+    let testcase_title: String = context.get("testcase_title");
+    let summary = summary_override
+        .unwrap_or_else(|| testcase_title.chars().take(MAX_SUMMARY_CHARS).collect());
+    let mut description = format!(
+        "*Тест-кейс:* {} {} (версия {})\n*Прогон:* {}\n",
+        wiki(context.get("testcase_key")),
+        wiki(&testcase_title),
+        context.get::<i32, _>("version_number"),
+        wiki(context.get("run_title")),
+    );
+    let preconditions: String = context.get("preconditions");
+    if !preconditions.trim().is_empty() {
+        description.push_str(&format!("\n*Предусловия:*\n{}\n", wiki(&preconditions)));
+    }
+    if !steps.is_empty() {
+        description.push_str("\n*Шаги:*\n");
+        for step in &steps {
+            description.push_str(&format!("# {}", wiki(step.get("action"))));
+            let expected: String = step.get("expected_result");
+            if !expected.trim().is_empty() {
+                description.push_str(&format!(" — ожидается: {}", wiki(&expected)));
+            }
+            if let Some(status) = step.get::<Option<String>, _>("status") {
+                description.push_str(&format!(" — *{status}*"));
+            }
+            if let Some(comment) = step
+                .get::<Option<String>, _>("comment")
+                .filter(|c| !c.trim().is_empty())
+            {
+                description.push_str(&format!(" ({})", wiki(&comment)));
+            }
+            description.push('\n');
+        }
+    }
+    if let Some(code) = context.get::<Option<String>, _>("fail_reason_code") {
+        let title = context
+            .get::<Option<String>, _>("fail_reason_title")
+            .unwrap_or_else(|| code.clone());
+        description.push_str(&format!(
+            "\n*Причина fail:* {} ({})\n",
+            wiki(&title),
+            wiki(&code)
+        ));
+    }
+    let comment: String = context.get("comment");
+    if !comment.trim().is_empty() {
+        description.push_str(&format!("\n*Комментарий:*\n{}\n", wiki(&comment)));
+    }
+    if !attachments.is_empty() {
+        description.push_str("\n*Вложения:*\n");
+        for attachment in &attachments {
+            let file_name: String = attachment.get("file_name");
+            description.push_str(&format!(
+                "* [{}|{}/api/v2/attachments/{}/download]\n",
+                wiki(if file_name.is_empty() {
+                    "file"
+                } else {
+                    &file_name
+                }),
+                state.public_url.trim_end_matches('/'),
+                attachment.get::<String, _>("id"),
+            ));
+        }
+    }
+
+    let request = state
+        .jira
+        .http
+        .post(format!("{base_url}/rest/api/2/issue"))
+        .json(&json!({
+            "fields": {
+                "project": { "key": connection.get::<String, _>("jira_project_key") },
+                "issuetype": { "name": connection.get::<String, _>("issue_type") },
+                "summary": summary,
+                "description": description,
+            }
+        }));
+    let request = match connection.get::<Option<String>, _>("email") {
+        Some(email) => request.basic_auth(email, Some(token)),
+        None => request.bearer_auth(token),
+    };
+    let response = request.send().await.map_err(|err| {
+        tracing::warn!(project_id = %project_id, error = %err, "jira request failed");
+        ApiError::JiraRequestFailed
+    })?;
+    let status = response.status();
+    let body: Value = response.json().await.unwrap_or(Value::Null);
+    let issue_key = match body.get("key").and_then(Value::as_str) {
+        Some(key) if status.is_success() => key.to_string(),
+        _ => {
+            tracing::warn!(
+                project_id = %project_id,
+                status = %status,
+                errors = %body,
+                "jira rejected the issue"
+            );
+            return Err(ApiError::JiraRequestFailed);
+        }
+    };
+    let url = format!("{base_url}/browse/{issue_key}");
+
+    let link_failed = |_| ApiError::DefectLinkFailed;
+    let mut tx = state.db.begin().await.map_err(link_failed)?;
+    let defect_id: Uuid = sqlx::query_scalar(
+        r#"
+        INSERT INTO defects (run_result_id, issue_key, url, created_by_user_id)
+        VALUES ($1, $2, $3, $4)
+        RETURNING id
+        "#,
+    )
+    .bind(run_result_id)
+    .bind(&issue_key)
+    .bind(&url)
+    .bind(actor_uuid)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|err| db_errors::map(err, ApiError::DefectLinkFailed))?;
+    audit::record(
+        &mut *tx,
+        audit::AuditEntry {
+            actor_user_id: Some(actor_uuid),
+            action: "create",
+            entity_type: "defect",
+            entity_id: Some(defect_id),
+            project_id: Some(project_id),
+            run_id: Some(run_uuid),
+            before: None,
+            after: Some(json!({
+                "runItemId": run_item_uuid,
+                "issueKey": &issue_key,
+                "url": &url,
+                "createdInJira": true,
+            })),
+        },
+    )
+    .await
+    .map_err(|_| ApiError::AuditWriteFailed)?;
+    tx.commit().await.map_err(link_failed)?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(JiraIssueResponse {
+            defect: defects::fetch_defect(&state.db, defect_id).await?,
+        }),
+    ))
+}
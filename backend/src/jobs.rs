@@ -0,0 +1,526 @@
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+use axum::{
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+use serde_json::{json, Value};
+use sqlx::{PgExecutor, PgPool, Row};
+use tokio::{sync::Notify, task::JoinHandle};
+use tracing::warn;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{
+    authz::{self, AuthUser},
+    chat, ci, custom_reports, email_reply,
+    error::ApiError,
+    inbox, notifications, parse_uuid,
+    permissions::Capability,
+    report, retention, telegram, testcase_import, webhooks, AppState,
+};
+
+/// Jobs run concurrently by one API instance.
+const WORKERS: usize = 4;
+const POLL_INTERVAL: Duration = Duration::from_secs(15);
+/// A claimed job is hidden from other workers this long; after that it counts as abandoned
+/// and is claimed again.
+const VISIBILITY_TIMEOUT_SECS: f64 = 300.0;
+const BASE_BACKOFF_SECS: i64 = 30;
+/// Finished jobs are kept this long for status polling, then deleted.
+const FINISHED_RETENTION_DAYS: i32 = 7;
+const PRUNE_INTERVAL: Duration = Duration::from_secs(3600);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum JobKind {
+    /// A service email, sent regardless of notification preferences.
+    Email,
+    /// A notification email for one recipient, subject to their preferences.
+    Notification,
+    WebhookDelivery,
+    /// A rendered message for one chat webhook.
+    ChatMessage,
+    CiStatus,
+    RunReport,
+    TestcaseImport,
+    /// A reply to an assignment email, see `email_reply`.
+    EmailReply,
+    /// A notification for one user's linked Telegram chat.
+    TelegramMessage,
+    /// A scheduled custom report, emailed as CSV.
+    ReportDelivery,
+    /// Deletes a project's data past its retention policy.
+    RetentionPurge,
+}
+
+impl JobKind {
+    const ALL: [JobKind; 11] = [
+        JobKind::Email,
+        JobKind::Notification,
+        JobKind::WebhookDelivery,
+        JobKind::ChatMessage,
+        JobKind::CiStatus,
+        JobKind::RunReport,
+        JobKind::TestcaseImport,
+        JobKind::EmailReply,
+        JobKind::TelegramMessage,
+        JobKind::ReportDelivery,
+        JobKind::RetentionPurge,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            JobKind::Email => "email",
+            JobKind::Notification => "notification",
+            JobKind::WebhookDelivery => "webhook_delivery",
+            JobKind::ChatMessage => "chat_message",
+            JobKind::CiStatus => "ci_status",
+            JobKind::RunReport => "run_report",
+            JobKind::TestcaseImport => "testcase_import",
+            JobKind::EmailReply => "email_reply",
+            JobKind::TelegramMessage => "telegram_message",
+            JobKind::ReportDelivery => "report_delivery",
+            JobKind::RetentionPurge => "retention_purge",
+        }
+    }
+
+    fn parse(input: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.as_str() == input)
+    }
+
+    pub fn max_attempts(self) -> i32 {
+        match self {
+            JobKind::WebhookDelivery => 6,
+            JobKind::RunReport | JobKind::TestcaseImport | JobKind::ReportDelivery => 2,
+            _ => 5,
+        }
+    }
+}
+
+/// Delay before retrying after the given failed attempt: 30s, 1m, 2m, 4m...
+pub fn retry_delay_secs(attempt: i32) -> i64 {
+    BASE_BACKOFF_SECS * (1_i64 << (attempt - 1).clamp(0, 16))
+}
+
+/// Wakes idle workers when a job is queued and tells them to finish on shutdown.
+pub struct JobQueue {
+    notify: Notify,
+    stopping: AtomicBool,
+}
+
+impl JobQueue {
+    pub fn new() -> Self {
+        Self {
+            notify: Notify::new(),
+            stopping: AtomicBool::new(false),
+        }
+    }
+
+    pub fn wake(&self) {
+        self.notify.notify_one();
+    }
+
+    /// Workers finish the jobs they are running and exit; queued jobs wait for the next start.
+    pub fn stop(&self) {
+        self.stopping.store(true, Ordering::SeqCst);
+        self.notify.notify_waiters();
+    }
+}
+
+pub struct NewJob {
+    pub kind: JobKind,
+    pub project_id: Option<Uuid>,
+    pub created_by_user_id: Option<Uuid>,
+    pub payload: Value,
+}
+
+/// Inserts a job; call [`JobQueue::wake`] once the surrounding transaction is committed.
+pub async fn enqueue<'e, E: PgExecutor<'e>>(executor: E, job: NewJob) -> Result<Uuid, sqlx::Error> {
+    sqlx::query_scalar(
+        r#"
+        INSERT INTO jobs (kind, project_id, payload, max_attempts, created_by_user_id)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING id
+        "#,
+    )
+    .bind(job.kind.as_str())
+    .bind(job.project_id)
+    .bind(job.payload)
+    .bind(job.kind.max_attempts())
+    .bind(job.created_by_user_id)
+    .fetch_one(executor)
+    .await
+}
+
+/// [`enqueue`] outside a transaction, waking a worker right away.
+pub async fn submit(state: &AppState, job: NewJob) -> Result<Uuid, sqlx::Error> {
+    let job_id = enqueue(&state.db, job).await?;
+    state.jobs.wake();
+    Ok(job_id)
+}
+
+/// [`submit`] from code that cannot wait for the insert, such as fire-and-forget
+/// notifications; a failed insert is only logged.
+pub fn submit_in_background(state: &AppState, job: NewJob) {
+    let state = state.clone();
+    tokio::spawn(async move {
+        let kind = job.kind;
+        if let Err(err) = submit(&state, job).await {
+            warn!("failed to queue a {} job: {err}", kind.as_str());
+        }
+    });
+}
+
+/// What a handler knows about the job it runs.
+pub struct JobContext {
+    pub id: Uuid,
+    /// 1 for the first run of the job.
+    pub attempt: i32,
+    pub max_attempts: i32,
+}
+
+impl JobContext {
+    pub fn is_last_attempt(&self) -> bool {
+        self.attempt >= self.max_attempts
+    }
+}
+
+/// Runs queued jobs with [`WORKERS`] concurrent workers until [`JobQueue::stop`], and deletes
+/// finished jobs after [`FINISHED_RETENTION_DAYS`]. Every API instance runs its own workers;
+/// `SKIP LOCKED` keeps them from taking the same job.
+pub fn spawn_workers(state: AppState) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut workers = tokio::task::JoinSet::new();
+        for _ in 0..WORKERS {
+            let state = state.clone();
+            workers.spawn(async move { work(&state).await });
+        }
+        let mut prune = tokio::time::interval(PRUNE_INTERVAL);
+        loop {
+            tokio::select! {
+                joined = workers.join_next() => {
+                    if joined.is_none() {
+                        return;
+                    }
+                }
+                _ = prune.tick() => {
+                    if let Err(err) = prune_finished(&state).await {
+                        warn!("failed to delete finished jobs: {err}");
+                    }
+                    if let Err(err) = email_reply::prune(&state).await {
+                        warn!("failed to delete old email replies: {err}");
+                    }
+                    if let Err(err) = inbox::prune(&state).await {
+                        warn!("failed to delete old in-app notifications: {err}");
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// Deletes old finished jobs together with the files they produced.
+async fn prune_finished(state: &AppState) -> Result<(), sqlx::Error> {
+    let files: Vec<Option<String>> = sqlx::query_scalar(
+        r#"
+        DELETE FROM jobs
+        WHERE finished_at < NOW() - make_interval(days => $1)
+        RETURNING result -> 'file' ->> 'key'
+        "#,
+    )
+    .bind(FINISHED_RETENTION_DAYS)
+    .fetch_all(&state.db)
+    .await?;
+    for key in files.into_iter().flatten() {
+        if let Err(err) = state.storage.delete(&key).await {
+            warn!("failed to delete job file {key}: {err:#}");
+        }
+    }
+    Ok(())
+}
+
+async fn work(state: &AppState) {
+    loop {
+        if state.jobs.stopping.load(Ordering::SeqCst) {
+            return;
+        }
+        match claim(&state.db).await {
+            Ok(Some(row)) => {
+                run(state, row).await;
+                continue;
+            }
+            Ok(None) => {}
+            Err(err) => warn!("failed to claim a job: {err}"),
+        }
+        tokio::select! {
+            _ = state.jobs.notify.notified() => {}
+            _ = tokio::time::sleep(POLL_INTERVAL) => {}
+        }
+    }
+}
+
+async fn claim(db: &PgPool) -> Result<Option<sqlx::postgres::PgRow>, sqlx::Error> {
+    sqlx::query(
+        r#"
+        WITH next AS (
+          SELECT id
+          FROM jobs
+          WHERE status IN ('queued', 'running') AND run_after <= NOW()
+          ORDER BY run_after
+          LIMIT 1
+          FOR UPDATE SKIP LOCKED
+        )
+        UPDATE jobs j
+        SET status = 'running',
+            attempts = j.attempts + 1,
+            started_at = NOW(),
+            run_after = NOW() + make_interval(secs => $1)
+        FROM next
+        WHERE j.id = next.id
+        RETURNING j.id, j.kind, j.payload, j.attempts, j.max_attempts
+        "#,
+    )
+    .bind(VISIBILITY_TIMEOUT_SECS)
+    .fetch_optional(db)
+    .await
+}
+
+async fn run(state: &AppState, row: sqlx::postgres::PgRow) {
+    let ctx = JobContext {
+        id: row.get("id"),
+        attempt: row.get("attempts"),
+        max_attempts: row.get("max_attempts"),
+    };
+    let kind_name: String = row.get("kind");
+    let outcome = match JobKind::parse(&kind_name) {
+        // A job claimed again after its last attempt timed out is not run once more.
+        _ if ctx.attempt > ctx.max_attempts => Err(anyhow::anyhow!("visibility timeout exceeded")),
+        Some(kind) => execute(state, kind, &ctx, row.get("payload")).await,
+        None => Err(anyhow::anyhow!("unknown job kind {kind_name}")),
+    };
+
+    let finished = match outcome {
+        Ok(result) => {
+            sqlx::query(
+                r#"
+            UPDATE jobs
+            SET status = 'succeeded', result = $2, last_error = NULL, finished_at = NOW()
+            WHERE id = $1
+            "#,
+            )
+            .bind(ctx.id)
+            .bind(result)
+            .execute(&state.db)
+            .await
+        }
+        Err(err) => {
+            let error = format!("{err:#}");
+            warn!(job_id = %ctx.id, kind = %kind_name, attempt = ctx.attempt, "job failed: {error}");
+            sqlx::query(
+                r#"
+                UPDATE jobs
+                SET status = CASE WHEN $3 THEN 'failed' ELSE 'queued' END,
+                    last_error = $2,
+                    run_after = NOW() + make_interval(secs => $4),
+                    finished_at = CASE WHEN $3 THEN NOW() ELSE NULL END
+                WHERE id = $1
+                "#,
+            )
+            .bind(ctx.id)
+            .bind(error)
+            .bind(ctx.is_last_attempt())
+            .bind(retry_delay_secs(ctx.attempt) as f64)
+            .execute(&state.db)
+            .await
+        }
+    };
+    if let Err(err) = finished {
+        warn!(job_id = %ctx.id, "failed to record the job outcome: {err}");
+    }
+}
+
+/// Runs one attempt; the returned value is stored as the job result.
+async fn execute(
+    state: &AppState,
+    kind: JobKind,
+    ctx: &JobContext,
+    payload: Value,
+) -> anyhow::Result<Value> {
+    match kind {
+        JobKind::Email => notifications::run_email_job(state, payload).await,
+        JobKind::Notification => notifications::run_notification_job(state, payload).await,
+        JobKind::WebhookDelivery => webhooks::run_delivery_job(state, ctx, payload).await,
+        JobKind::ChatMessage => chat::run_message_job(state, payload).await,
+        JobKind::CiStatus => ci::run_status_job(state, payload).await,
+        JobKind::RunReport => report::run_report_job(state, ctx, payload).await,
+        JobKind::TestcaseImport => testcase_import::run_import_job(state, ctx, payload).await,
+        JobKind::EmailReply => email_reply::run_reply_job(state, payload).await,
+        JobKind::TelegramMessage => telegram::run_message_job(state, payload).await,
+        JobKind::ReportDelivery => custom_reports::run_delivery_job(state, ctx, payload).await,
+        JobKind::RetentionPurge => retention::run_purge_job(state, ctx, payload).await,
+    }
+}
+
+/// Returned with `202 Accepted` by endpoints that hand their work to a job.
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct JobAcceptedResponse {
+    job_id: String,
+    /// Poll this until `status` is `succeeded` or `failed`.
+    status_url: String,
+}
+
+pub fn accepted(job_id: Uuid) -> (StatusCode, Json<JobAcceptedResponse>) {
+    (
+        StatusCode::ACCEPTED,
+        Json(JobAcceptedResponse {
+            job_id: job_id.to_string(),
+            status_url: format!("/api/v2/jobs/{job_id}"),
+        }),
+    )
+}
+
+/// A file a job produced, kept in the attachment storage; described in the job result as
+/// `file` and served by `GET /api/v2/jobs/{job_id}/download`.
+pub fn file_result(job_id: Uuid, key: &str, content_type: &str, file_name: &str) -> Value {
+    json!({
+        "downloadUrl": format!("/api/v2/jobs/{job_id}/download"),
+        "file": {
+            "key": key,
+            "contentType": content_type,
+            "fileName": file_name,
+        },
+    })
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct JobView {
+    id: String,
+    kind: String,
+    /// `queued`, `running`, `succeeded` or `failed`.
+    status: String,
+    attempts: i32,
+    max_attempts: i32,
+    last_error: Option<String>,
+    /// Set when the job succeeded; its shape depends on `kind`.
+    #[schema(value_type = Option<Object>)]
+    result: Option<Value>,
+    created_at: String,
+    started_at: Option<String>,
+    finished_at: Option<String>,
+}
+
+/// Loads a job the actor may see: their own, or any job of a project they can read.
+async fn load_visible_job(
+    state: &AppState,
+    job_id: &str,
+    actor_id: &str,
+) -> Result<sqlx::postgres::PgRow, ApiError> {
+    let job_uuid = parse_uuid(job_id, ApiError::InvalidJobId)?;
+    let row = sqlx::query(
+        r#"
+        SELECT id::text AS id, kind, status, attempts, max_attempts, last_error, result,
+               project_id, created_by_user_id::text AS created_by_user_id,
+               created_at::text AS created_at, started_at::text AS started_at,
+               finished_at::text AS finished_at
+        FROM jobs
+        WHERE id = $1
+        "#,
+    )
+    .bind(job_uuid)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|_| ApiError::JobsReadFailed)?
+    .ok_or(ApiError::JobNotFound)?;
+    if row.get::<Option<&str>, _>("created_by_user_id") == Some(actor_id) {
+        return Ok(row);
+    }
+    let Some(project_id) = row.get::<Option<Uuid>, _>("project_id") else {
+        return Err(ApiError::JobNotFound);
+    };
+    authz::require_capability(
+        state,
+        &project_id.to_string(),
+        actor_id,
+        Capability::ProjectRead,
+    )
+    .await
+    .map_err(|_| ApiError::JobNotFound)?;
+    Ok(row)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v2/jobs/{job_id}",
+    tag = "jobs",
+    params(("job_id" = String, Path)),
+    responses((status = 200, body = JobView))
+)]
+pub async fn get_job(
+    State(state): State<AppState>,
+    Path(job_id): Path<String>,
+    AuthUser(actor_id): AuthUser,
+) -> Result<Json<JobView>, ApiError> {
+    let r = load_visible_job(&state, &job_id, &actor_id).await?;
+    Ok(Json(JobView {
+        id: r.get("id"),
+        kind: r.get("kind"),
+        status: r.get("status"),
+        attempts: r.get("attempts"),
+        max_attempts: r.get("max_attempts"),
+        last_error: r.get("last_error"),
+        result: r.get("result"),
+        created_at: r.get("created_at"),
+        started_at: r.get("started_at"),
+        finished_at: r.get("finished_at"),
+    }))
+}
+
+/// The file produced by a succeeded job, e.g. a rendered PDF report.
+#[utoipa::path(
+    get,
+    path = "/api/v2/jobs/{job_id}/download",
+    tag = "jobs",
+    params(("job_id" = String, Path)),
+    responses((status = 200, description = "Файл, созданный задачей.", content_type = "application/octet-stream"))
+)]
+pub async fn download_job_file(
+    State(state): State<AppState>,
+    Path(job_id): Path<String>,
+    AuthUser(actor_id): AuthUser,
+) -> Result<Response, ApiError> {
+    let row = load_visible_job(&state, &job_id, &actor_id).await?;
+    let file = row
+        .get::<Option<Value>, _>("result")
+        .and_then(|result| result.get("file").cloned())
+        .ok_or(ApiError::JobFileNotReady)?;
+    let field = |name: &str| {
+        file.get(name)
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string()
+    };
+    let body = state
+        .storage
+        .get(&field("key"))
+        .await
+        .map_err(|_| ApiError::JobFileNotReady)?;
+    Ok((
+        [
+            (header::CONTENT_TYPE, field("contentType")),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", field("fileName")),
+            ),
+        ],
+        body,
+    )
+        .into_response())
+}
@@ -0,0 +1,92 @@
+use axum::body::Bytes;
+
+use crate::{
+    attachments::AttachmentLimits,
+    error::ApiError,
+    result_import::{
+        case_title, clip, ImportedCase, ParsedReport, ReportParser, ReportSource, MAX_COMMENT_CHARS,
+    },
+};
+
+static SOURCE: ReportSource = ReportSource {
+    format: "junit",
+    name: "JUnit",
+    // CI reports are larger than regular JSON payloads.
+    max_bytes: 10 * 1024 * 1024,
+    failed: ApiError::JunitImportFailed,
+    rejected: ApiError::JunitRunCreateRejected,
+};
+
+fn parse_junit(xml: &str) -> Result<Vec<ImportedCase>, ApiError> {
+    let doc = roxmltree::Document::parse(xml).map_err(|_| ApiError::InvalidJunitXml)?;
+    let mut cases: Vec<ImportedCase> = Vec::new();
+    for node in doc.descendants().filter(|n| n.has_tag_name("testcase")) {
+        let name = node.attribute("name").unwrap_or_default().trim();
+        if name.is_empty() {
+            continue;
+        }
+        let key = match node.attribute("classname").map(str::trim) {
+            Some(class) if !class.is_empty() => format!("{class}.{name}"),
+            _ => name.to_string(),
+        };
+        if cases.iter().any(|c| c.key == key) {
+            continue;
+        }
+        let failure = node
+            .children()
+            .find(|c| c.has_tag_name("failure") || c.has_tag_name("error"));
+        let skipped = node.children().find(|c| c.has_tag_name("skipped"));
+        let (status, comment) = match (failure, skipped) {
+            (Some(f), _) => {
+                let message = f.attribute("message").unwrap_or_default().trim();
+                let body = f.text().unwrap_or_default().trim();
+                let comment = match (message.is_empty(), body.is_empty()) {
+                    (false, false) => format!("{message}\n\n{body}"),
+                    (false, true) => message.to_string(),
+                    _ => body.to_string(),
+                };
+                ("fail", comment)
+            }
+            (None, Some(s)) => (
+                "skipped",
+                s.attribute("message").unwrap_or_default().to_string(),
+            ),
+            (None, None) => ("ok", String::new()),
+        };
+        let elapsed_seconds = node
+            .attribute("time")
+            .and_then(|t| t.trim().parse::<f64>().ok())
+            .filter(|t| t.is_finite() && *t >= 0.0)
+            .map(|t| t.round().min(i32::MAX as f64) as i32);
+        cases.push(ImportedCase {
+            title: case_title(name, &key),
+            key,
+            source_path: None,
+            status,
+            comment: clip(&comment, MAX_COMMENT_CHARS),
+            elapsed_seconds,
+            steps: Vec::new(),
+            attachments: Vec::new(),
+        });
+    }
+    if cases.is_empty() {
+        return Err(ApiError::JunitXmlEmpty);
+    }
+    Ok(cases)
+}
+
+pub struct JunitParser;
+
+impl ReportParser for JunitParser {
+    fn source(&self) -> &'static ReportSource {
+        &SOURCE
+    }
+
+    fn parse(&self, body: Bytes, _limits: &AttachmentLimits) -> Result<ParsedReport, ApiError> {
+        let xml = std::str::from_utf8(&body).map_err(|_| ApiError::InvalidJunitXml)?;
+        Ok(ParsedReport {
+            cases: parse_junit(xml)?,
+            skipped_attachments: 0,
+        })
+    }
+}
@@ -0,0 +1,135 @@
+use std::{collections::HashMap, sync::RwLock};
+
+use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+pub const ACCESS_TOKEN_TTL_SECS: i64 = 15 * 60;
+pub const REFRESH_TOKEN_TTL_SECS: i64 = 30 * 24 * 60 * 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TokenKind {
+    Access,
+    Refresh,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Claims {
+    pub sub: String,
+    pub kind: TokenKind,
+    pub iat: i64,
+    pub exp: i64,
+    pub jti: String,
+}
+
+pub struct TokenPair {
+    pub access_token: String,
+    pub refresh_token: String,
+    pub expires_in: i64,
+}
+
+pub struct JwtKeys {
+    encoding: EncodingKey,
+    decoding: DecodingKey,
+    /// Revoked token ids (`jti` -> `exp`), an in-memory copy of `revoked_tokens` so that
+    /// [`JwtKeys::verify`] stays synchronous.
+    revoked: RwLock<HashMap<String, i64>>,
+    /// User id -> unix time: tokens of the user issued at or before it are rejected (sessions
+    /// ended by an admin). A copy of `users.tokens_valid_after`.
+    sessions_ended: RwLock<HashMap<String, i64>>,
+}
+
+impl JwtKeys {
+    pub fn from_secret(secret: &str) -> Self {
+        Self {
+            encoding: EncodingKey::from_secret(secret.as_bytes()),
+            decoding: DecodingKey::from_secret(secret.as_bytes()),
+            revoked: RwLock::new(HashMap::new()),
+            sessions_ended: RwLock::new(HashMap::new()),
+        }
+    }
+
+    fn sign(&self, user_id: &str, kind: TokenKind, ttl_secs: i64) -> anyhow::Result<String> {
+        let iat = chrono::Utc::now().timestamp();
+        let claims = Claims {
+            sub: user_id.to_string(),
+            kind,
+            iat,
+            exp: iat + ttl_secs,
+            jti: Uuid::new_v4().to_string(),
+        };
+        Ok(encode(
+            &Header::new(Algorithm::HS256),
+            &claims,
+            &self.encoding,
+        )?)
+    }
+
+    pub fn issue_pair(&self, user_id: &str) -> anyhow::Result<TokenPair> {
+        Ok(TokenPair {
+            access_token: self.sign(user_id, TokenKind::Access, ACCESS_TOKEN_TTL_SECS)?,
+            refresh_token: self.sign(user_id, TokenKind::Refresh, REFRESH_TOKEN_TTL_SECS)?,
+            expires_in: ACCESS_TOKEN_TTL_SECS,
+        })
+    }
+
+    /// Returns the claims only for a valid, unexpired, not revoked token of the expected kind.
+    pub fn verify(&self, token: &str, kind: TokenKind) -> Option<Claims> {
+        let mut validation = Validation::new(Algorithm::HS256);
+        validation.leeway = 0;
+        let data = decode::<Claims>(token, &self.decoding, &validation).ok()?;
+        if data.claims.kind != kind || Uuid::parse_str(&data.claims.sub).is_err() {
+            return None;
+        }
+        if self.is_revoked(&data.claims.jti) || self.session_ended(&data.claims) {
+            return None;
+        }
+        Some(data.claims)
+    }
+
+    fn is_revoked(&self, jti: &str) -> bool {
+        self.revoked
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .contains_key(jti)
+    }
+
+    fn session_ended(&self, claims: &Claims) -> bool {
+        self.sessions_ended
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&claims.sub)
+            .is_some_and(|ended_at| claims.iat <= *ended_at)
+    }
+
+    pub fn end_sessions(&self, user_id: &str, ended_at: i64) {
+        self.sessions_ended
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(user_id.to_string(), ended_at);
+    }
+
+    pub fn reset_ended_sessions(&self, entries: HashMap<String, i64>) {
+        *self
+            .sessions_ended
+            .write()
+            .unwrap_or_else(|e| e.into_inner()) = entries;
+    }
+
+    pub fn mark_revoked(&self, jti: &str, exp: i64) {
+        self.revoked
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(jti.to_string(), exp);
+    }
+
+    /// Replaces the in-memory set with the persisted one; expired tokens fail validation
+    /// anyway, so they are dropped.
+    pub fn reset_revoked(&self, entries: HashMap<String, i64>) {
+        let now = chrono::Utc::now().timestamp();
+        let mut revoked = self.revoked.write().unwrap_or_else(|e| e.into_inner());
+        *revoked = entries;
+        revoked.retain(|_, exp| *exp > now);
+    }
+}
@@ -0,0 +1,375 @@
+use std::{collections::HashMap, convert::Infallible, sync::Mutex, time::Duration};
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Path, Query, State,
+    },
+    http::HeaderMap,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::{postgres::PgListener, Row};
+use tokio::sync::{broadcast, mpsc};
+use tokio_stream::wrappers::ReceiverStream;
+use tracing::warn;
+use utoipa::IntoParams;
+use uuid::Uuid;
+
+use crate::{
+    authz, error::ApiError, jwt, parse_bearer_user_id, parse_uuid, permissions::Capability,
+    AppState, RunItemStepView, RunView,
+};
+
+/// Events a slow client may fall behind by before it starts skipping messages.
+const RUN_CHANNEL_CAPACITY: usize = 64;
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RunEvent<'a> {
+    ResultUpdated(RunResultEvent<'a>),
+    StatusChanged { run: &'a RunView },
+    AssigneeChanged(RunAssigneeEvent<'a>),
+    ExecutorChanged { run: &'a RunView },
+    DetailsChanged { run: &'a RunView },
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunResultEvent<'a> {
+    pub run_item_id: Uuid,
+    pub status: &'a str,
+    pub fail_reason_code: Option<&'a str>,
+    pub comment: &'a str,
+    pub updated_by_user_id: &'a str,
+    pub updated_at: DateTime<Utc>,
+    pub version: i64,
+    pub steps: &'a [RunItemStepView],
+}
+
+/// `run_item_id` is `None` when the run default assignee changed.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunAssigneeEvent<'a> {
+    pub run_item_id: Option<Uuid>,
+    pub assignee_user_id: Option<&'a str>,
+}
+
+/// Per-run broadcast channels; a channel lives while at least one socket is subscribed.
+#[derive(Default)]
+pub struct RunHub {
+    channels: Mutex<HashMap<Uuid, broadcast::Sender<String>>>,
+}
+
+impl RunHub {
+    pub fn subscribe(&self, run_id: Uuid) -> broadcast::Receiver<String> {
+        let mut channels = self.channels.lock().unwrap_or_else(|e| e.into_inner());
+        channels
+            .entry(run_id)
+            .or_insert_with(|| broadcast::channel(RUN_CHANNEL_CAPACITY).0)
+            .subscribe()
+    }
+
+    /// Drops the run's channel once its last receiver is gone.
+    pub fn release(&self, run_id: Uuid) {
+        let mut channels = self.channels.lock().unwrap_or_else(|e| e.into_inner());
+        if channels
+            .get(&run_id)
+            .is_some_and(|tx| tx.receiver_count() == 0)
+        {
+            channels.remove(&run_id);
+        }
+    }
+
+    /// Sends the event to sockets watching the run; a no-op when nobody is connected.
+    pub fn publish(&self, run_id: Uuid, event: &RunEvent<'_>) {
+        let channels = self.channels.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(tx) = channels.get(&run_id) else {
+            return;
+        };
+        if let Ok(payload) = serde_json::to_string(event) {
+            let _ = tx.send(payload);
+        }
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct RunSocketQuery {
+    /// Browsers cannot set headers on a WebSocket handshake, so the access token may come here.
+    token: Option<String>,
+}
+
+/// Bearer token, or the `token` query parameter for clients that cannot set headers.
+fn stream_actor(
+    state: &AppState,
+    token: Option<String>,
+    headers: &HeaderMap,
+) -> Result<String, ApiError> {
+    match token {
+        Some(token) if !headers.contains_key("authorization") => state
+            .jwt
+            .verify(token.trim(), jwt::TokenKind::Access)
+            .map(|claims| claims.sub)
+            .ok_or(ApiError::InvalidToken),
+        _ => parse_bearer_user_id(&state.jwt, headers),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v2/runs/{run_id}/ws",
+    tag = "live",
+    params(("run_id" = String, Path), RunSocketQuery),
+    responses((status = 101, description = "WebSocket с событиями run.")),
+    security(())
+)]
+pub async fn run_socket(
+    State(state): State<AppState>,
+    Path(run_id): Path<String>,
+    Query(query): Query<RunSocketQuery>,
+    headers: HeaderMap,
+    ws: WebSocketUpgrade,
+) -> Result<Response, ApiError> {
+    let actor_id = stream_actor(&state, query.token, &headers)?;
+    let run_uuid = parse_uuid(&run_id, ApiError::InvalidRunId)?;
+    authz::require_run_capability(&state, run_uuid, &actor_id, Capability::ProjectRead).await?;
+
+    let rx = state.live.subscribe(run_uuid);
+    Ok(ws.on_upgrade(move |socket| async move {
+        forward_run_events(socket, rx).await;
+        state.live.release(run_uuid);
+    }))
+}
+
+async fn forward_run_events(mut socket: WebSocket, mut rx: broadcast::Receiver<String>) {
+    loop {
+        tokio::select! {
+            event = rx.recv() => match event {
+                Ok(payload) => {
+                    if socket.send(Message::Text(payload.into())).await.is_err() {
+                        break;
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
+
+/// Project activity events read per query while a stream catches up.
+const PROJECT_EVENTS_BATCH: i64 = 200;
+/// Wake-ups buffered for slow streams; a stream that lags simply re-reads its project.
+const PROJECT_FEED_CAPACITY: usize = 256;
+/// How long `project_events` rows are kept for `Last-Event-ID` resumes.
+const PROJECT_EVENTS_RETENTION_DAYS: i32 = 7;
+const PROJECT_EVENTS_PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+const LISTENER_RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// Fan-out of `NOTIFY project_events` to the SSE streams of this instance. The message is the
+/// project that got new events; `None` asks every stream to re-read (the listener reconnected
+/// and may have missed notifications).
+pub struct ProjectFeed {
+    tx: broadcast::Sender<Option<Uuid>>,
+}
+
+impl Default for ProjectFeed {
+    fn default() -> Self {
+        Self {
+            tx: broadcast::channel(PROJECT_FEED_CAPACITY).0,
+        }
+    }
+}
+
+/// Listens for new `project_events` rows (inserted by `webhooks::emit` on any instance) and
+/// deletes rows older than [`PROJECT_EVENTS_RETENTION_DAYS`].
+pub fn spawn_project_feed(state: AppState) {
+    tokio::spawn(async move {
+        let mut prune = tokio::time::interval(PROJECT_EVENTS_PRUNE_INTERVAL);
+        let mut listener: Option<PgListener> = None;
+        loop {
+            let Some(active) = listener.as_mut() else {
+                match connect_listener(&state).await {
+                    Ok(connected) => {
+                        listener = Some(connected);
+                        let _ = state.activity.tx.send(None);
+                    }
+                    Err(err) => {
+                        warn!("failed to listen for project events: {err}");
+                        tokio::time::sleep(LISTENER_RETRY_DELAY).await;
+                    }
+                }
+                continue;
+            };
+            tokio::select! {
+                received = active.try_recv() => match received {
+                    Ok(Some(notification)) => {
+                        if let Ok(project_id) = Uuid::parse_str(notification.payload()) {
+                            let _ = state.activity.tx.send(Some(project_id));
+                        }
+                    }
+                    // The connection dropped; the next call reconnects, and streams catch up.
+                    Ok(None) => {
+                        let _ = state.activity.tx.send(None);
+                    }
+                    Err(err) => {
+                        warn!("project events listener failed: {err}");
+                        listener = None;
+                    }
+                },
+                _ = prune.tick() => {
+                    if let Err(err) = prune_project_events(&state).await {
+                        warn!("failed to delete old project events: {err}");
+                    }
+                }
+            }
+        }
+    });
+}
+
+async fn connect_listener(state: &AppState) -> Result<PgListener, sqlx::Error> {
+    let mut listener = PgListener::connect_with(&state.db).await?;
+    listener.listen("project_events").await?;
+    Ok(listener)
+}
+
+async fn prune_project_events(state: &AppState) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"DELETE FROM project_events WHERE created_at < NOW() - make_interval(days => $1)"#,
+    )
+    .bind(PROJECT_EVENTS_RETENTION_DAYS)
+    .execute(&state.db)
+    .await?;
+    Ok(())
+}
+
+#[derive(Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct ProjectEventsQuery {
+    /// `EventSource` cannot set headers, so the access token may come here.
+    token: Option<String>,
+    /// Same as the `Last-Event-ID` header, for clients that open the stream themselves.
+    last_event_id: Option<String>,
+}
+
+/// Live project activity as server-sent events: the same events and payloads as webhooks
+/// (`run.created`, `run.done`, `result.updated`, `result.failed`, `member.added`). Each SSE
+/// event carries the event name and an `id`; a client reconnecting with `Last-Event-ID` first
+/// receives everything after that id that is still kept, then live events.
+#[utoipa::path(
+    get,
+    path = "/api/projects/{project_id}/events",
+    tag = "live",
+    params(
+        ("project_id" = String, Path),
+        ("Last-Event-ID" = Option<String>, Header, description = "Id of the last received event."),
+        ProjectEventsQuery
+    ),
+    responses((status = 200, content_type = "text/event-stream", description = "Поток событий проекта.")),
+    security(())
+)]
+pub async fn project_events(
+    State(state): State<AppState>,
+    Path(project_id): Path<String>,
+    Query(query): Query<ProjectEventsQuery>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let actor_id = stream_actor(&state, query.token, &headers)?;
+    let project_uuid = parse_uuid(&project_id, ApiError::InvalidProjectId)?;
+    authz::require_capability(&state, &project_id, &actor_id, Capability::ProjectRead).await?;
+
+    let last_event_id = headers
+        .get("last-event-id")
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_owned)
+        .or(query.last_event_id);
+    let after = match last_event_id.as_deref().map(str::trim) {
+        Some(v) if !v.is_empty() => v.parse::<i64>().map_err(|_| ApiError::InvalidLastEventId)?,
+        // A fresh subscriber only gets events from now on.
+        _ => sqlx::query_scalar(
+            r#"SELECT COALESCE(MAX(id), 0) FROM project_events WHERE project_id = $1"#,
+        )
+        .bind(project_uuid)
+        .fetch_one(&state.db)
+        .await
+        .map_err(|_| ApiError::ProjectEventsReadFailed)?,
+    };
+
+    // Subscribe before the first read so nothing inserted in between is missed.
+    let wake = state.activity.tx.subscribe();
+    let (tx, rx) = mpsc::channel(PROJECT_EVENTS_BATCH as usize);
+    tokio::spawn(forward_project_events(state, project_uuid, after, wake, tx));
+    Ok(Sse::new(ReceiverStream::new(rx))
+        .keep_alive(KeepAlive::default())
+        .into_response())
+}
+
+async fn forward_project_events(
+    state: AppState,
+    project_id: Uuid,
+    mut after: i64,
+    mut wake: broadcast::Receiver<Option<Uuid>>,
+    tx: mpsc::Sender<Result<Event, Infallible>>,
+) {
+    loop {
+        loop {
+            let rows = match sqlx::query(
+                r#"
+                SELECT id, event, payload
+                FROM project_events
+                WHERE project_id = $1 AND id > $2
+                ORDER BY id
+                LIMIT $3
+                "#,
+            )
+            .bind(project_id)
+            .bind(after)
+            .bind(PROJECT_EVENTS_BATCH)
+            .fetch_all(&state.db)
+            .await
+            {
+                Ok(rows) => rows,
+                // Ending the stream makes the client reconnect with its Last-Event-ID.
+                Err(err) => {
+                    warn!("failed to read events of project {project_id}: {err}");
+                    return;
+                }
+            };
+            let count = rows.len() as i64;
+            for row in rows {
+                after = row.get::<i64, _>("id");
+                let event = Event::default()
+                    .id(after.to_string())
+                    .event(row.get::<String, _>("event"))
+                    .data(row.get::<Value, _>("payload").to_string());
+                if tx.send(Ok(event)).await.is_err() {
+                    return;
+                }
+            }
+            if count < PROJECT_EVENTS_BATCH {
+                break;
+            }
+        }
+        loop {
+            tokio::select! {
+                received = wake.recv() => match received {
+                    Ok(Some(id)) if id != project_id => continue,
+                    Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => break,
+                    Err(broadcast::error::RecvError::Closed) => return,
+                },
+                _ = tx.closed() => return,
+            }
+        }
+    }
+}
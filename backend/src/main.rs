@@ -1,15 +1,22 @@
 use anyhow::Context;
 use axum::{
-    extract::{Path, Query, State},
-    http::{HeaderMap, StatusCode},
-    routing::{any, get, patch, post},
+    body::Bytes,
+    extract::{DefaultBodyLimit, Path, Query, State},
+    http::{
+        header::{CONTENT_TYPE, ETAG},
+        HeaderMap, StatusCode,
+    },
+    middleware,
+    response::{IntoResponse, Response},
+    routing::{any, delete, get, patch, post, put},
     Json, Router,
 };
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
-use sqlx::{postgres::PgPoolOptions, PgPool, Row};
+use serde_json::{json, Value};
+use sqlx::{PgPool, Row};
 use std::{
-    env,
+    collections::HashSet,
     net::SocketAddr,
     path::{Path as StdPath, PathBuf},
     sync::Arc,
@@ -17,30 +24,160 @@ use std::{
 };
 use tokio::{fs, sync::Mutex};
 use tower_http::{
+    compression::CompressionLayer,
     cors::CorsLayer,
+    limit::RequestBodyLimitLayer,
     services::{ServeDir, ServeFile},
     trace::TraceLayer,
 };
-use tracing::info;
+use tracing::{info, warn};
+use utoipa::{IntoParams, OpenApi, ToSchema};
+use utoipa_swagger_ui::SwaggerUi;
 use uuid::Uuid;
 
-#[derive(Serialize)]
-struct HealthResponse {
-    status: &'static str,
-    service: &'static str,
-}
+use authz::AuthUser;
+use error::{current_lang, ApiError, ErrorBody, Lang};
+use etag::{Precondition, VersionError};
+use permissions::Capability;
+use run_repo::{LockedRun, RunLock};
+
+mod account;
+mod admin;
+mod allure;
+mod analytics;
+mod api_keys;
+mod assets;
+mod assignments;
+mod attachments;
+mod audit;
+mod authz;
+mod bundle;
+mod cache;
+mod chat;
+mod chat_message;
+mod ci;
+mod comments;
+mod config;
+mod cron;
+mod cucumber;
+mod custom_fields;
+mod custom_reports;
+mod dashboard;
+mod db_errors;
+mod db_pool;
+mod dedup;
+mod defects;
+mod dependencies;
+mod effort;
+mod email_reply;
+mod error;
+mod etag;
+mod export;
+mod fail_reasons;
+mod gherkin;
+mod grpc;
+mod health;
+mod idempotency;
+mod inbox;
+mod ingest;
+mod invitations;
+mod jira;
+mod jobs;
+mod junit;
+mod jwt;
+mod live;
+mod migration_import;
+mod migrations;
+mod milestones;
+mod notifications;
+mod oidc;
+mod openapi;
+mod organizations;
+mod overview;
+mod pagination;
+mod password;
+mod permissions;
+mod playwright;
+mod profile;
+mod quotas;
+mod rate_limit;
+mod replica;
+mod report;
+mod requirements;
+mod result_history;
+mod result_import;
+mod retention;
+mod revocation;
+mod run_diff;
+mod run_groups;
+mod run_repo;
+mod saved_filters;
+mod schedules;
+mod search;
+mod secrets;
+mod service_accounts;
+mod session;
+mod shutdown;
+mod storage;
+mod suites;
+mod tags;
+mod telegram;
+mod testcase_import;
+mod testcases;
+mod testrail;
+mod webhooks;
+mod xray;
 
 #[derive(Clone)]
 struct AppState {
     users_file: PathBuf,
     projects_file: PathBuf,
+    organizations_file: PathBuf,
     file_lock: Arc<Mutex<()>>,
     db: PgPool,
+    /// `None` without `DATABASE_REPLICA_URL`; use [`AppState::read_db`] rather than this.
+    replica: Option<Arc<replica::ReadReplica>>,
+    backpressure: Arc<db_pool::Backpressure>,
+    jwt: Arc<jwt::JwtKeys>,
+    storage: Arc<storage::Storage>,
+    attachment_limits: attachments::AttachmentLimits,
+    live: Arc<live::RunHub>,
+    activity: Arc<live::ProjectFeed>,
+    report_settings: report::ReportSettings,
+    webhooks: Arc<webhooks::WebhookDispatcher>,
+    /// Base URL of the web UI, used in links sent to users.
+    public_url: String,
+    mailer: Arc<dyn notifications::Mailer>,
+    /// `None` when results cannot be recorded by replying to emails.
+    inbound_email: Option<Arc<config::InboundEmailConfig>>,
+    telegram: Option<Arc<telegram::TelegramBot>>,
+    rate_limiter: Arc<rate_limit::RateLimiter>,
+    analytics: Arc<analytics::AnalyticsCache>,
+    cache: Arc<cache::HotCache>,
+    /// `None` when single sign-on is not configured.
+    oidc: Option<Arc<oidc::OidcClient>>,
+    /// `None` without `SECRETS_KEY`: integration credentials can be neither stored nor used.
+    secrets: Option<Arc<secrets::SecretBox>>,
+    jira: Arc<jira::JiraClient>,
+    chat: Arc<chat::ChatClient>,
+    ci: Arc<ci::CiClient>,
+    jobs: Arc<jobs::JobQueue>,
+    /// `ADMIN_EMAILS`, lower-cased.
+    admin_emails: Arc<Vec<String>>,
+    /// `INVITES_REQUIRE_VERIFIED_EMAIL`: only verified accounts can be added to projects.
+    invites_require_verified_email: bool,
 }
 
-#[derive(Serialize)]
-struct ErrorResponse {
-    error: String,
+impl AppState {
+    /// Pool for analytics, reports and large lists that tolerate replication lag: the read
+    /// replica while it is healthy, otherwise the primary. Never for writes, for reads in a
+    /// transaction with them, or for reading back what the same request just wrote.
+    fn read_db(&self) -> &PgPool {
+        self.replica
+            .as_deref()
+            .and_then(replica::ReadReplica::pool)
+            .unwrap_or(&self.db)
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -49,8 +186,37 @@ struct User {
     id: String,
     name: String,
     email: String,
-    password: String,
+    #[serde(alias = "password")]
+    password_hash: String,
     created_at: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pending_email: Option<profile::PendingEmailChange>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    avatar: Option<profile::Avatar>,
+    /// Provider account linked through single sign-on.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    oidc: Option<oidc::OidcIdentity>,
+    /// Accounts created before verification existed have no value and count as verified.
+    #[serde(default = "legacy_email_verified")]
+    email_verified: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    email_verification: Option<profile::EmailVerification>,
+    /// Granted through `/api/admin`; `ADMIN_EMAILS` are admins regardless.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    is_admin: bool,
+    /// Set while the account is deactivated: no sign-in, tokens rejected.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    deactivated_at: Option<String>,
+    /// Reset forced by an admin; sign-in is refused until the emailed link is used.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    password_reset: Option<admin::PasswordReset>,
+    /// Set for service accounts, which act only through API keys.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    service_account: Option<service_accounts::ServiceAccount>,
+}
+
+fn legacy_email_verified() -> bool {
+    true
 }
 
 #[derive(Serialize, Deserialize)]
@@ -74,7 +240,53 @@ struct Project {
     created_at: String,
     updated_at: String,
     members: Vec<ProjectMember>,
+    #[serde(default)]
+    roles: Vec<permissions::ProjectRole>,
+    #[serde(default)]
+    settings: ProjectSettings,
+    #[serde(default)]
+    invitations: Vec<invitations::ProjectInvitation>,
     session: Option<Value>,
+    /// Bumped on every session save; the session's ETag.
+    #[serde(default)]
+    session_version: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    organization_id: Option<String>,
+    /// Admins of the organization, filled in by [`read_projects`]; they act as `org_admin`.
+    #[serde(skip)]
+    organization_admins: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct ProjectSettings {
+    /// Used by `POST /api/v2/runs` when the request has no `templateId`.
+    default_run_template_id: Option<String>,
+    /// When non-empty, a `fail` result must use one of these fail-reason codes.
+    #[serde(default)]
+    required_fail_reason_codes: Vec<String>,
+    /// IANA timezone name.
+    #[serde(default = "default_project_timezone")]
+    timezone: String,
+    /// Move an `in_progress` run to `done` once every required item is `ok`, `fail` or
+    /// `skipped` (and the run passes the close checks).
+    #[serde(default)]
+    auto_complete_runs: bool,
+}
+
+fn default_project_timezone() -> String {
+    "UTC".to_string()
+}
+
+impl Default for ProjectSettings {
+    fn default() -> Self {
+        Self {
+            default_run_template_id: None,
+            required_fail_reason_codes: Vec::new(),
+            timezone: default_project_timezone(),
+            auto_complete_runs: false,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize)]
@@ -82,72 +294,115 @@ struct ProjectsFile {
     projects: Vec<Project>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 struct RegisterRequest {
     name: String,
     email: String,
     password: String,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 struct LoginRequest {
     email: String,
     password: String,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
 struct AuthResponse {
     token: String,
+    refresh_token: String,
+    expires_in: i64,
     user: SafeUser,
 }
 
-#[derive(Serialize)]
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct RefreshRequest {
+    refresh_token: String,
+}
+
+#[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 struct SafeUser {
     id: String,
     name: String,
     email: String,
+    /// New address waiting for confirmation via the emailed link.
+    pending_email: Option<String>,
+    /// The address was confirmed through `/api/auth/verify` or a single sign-on provider.
+    email_verified: bool,
+    avatar_url: Option<String>,
     created_at: String,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 struct MeResponse {
     user: SafeUser,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 struct ProjectsResponse {
     projects: Vec<ProjectForUser>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 struct ProjectForUser {
     id: String,
     name: String,
     role: String,
+    capabilities: Vec<&'static str>,
     owner_id: String,
+    organization_id: Option<String>,
+    settings: ProjectSettings,
     created_at: String,
     updated_at: String,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
 struct CreateProjectRequest {
     name: String,
+    /// Organization to create the project in; the caller must be its member.
+    organization_id: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct UpdateProjectRequest {
+    name: Option<String>,
+    /// Replaces the whole settings object when present.
+    settings: Option<ProjectSettings>,
+    /// Moves the project into this organization; requires org admin rights in it and in the
+    /// current organization.
+    organization_id: Option<String>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+struct ListProjectsQuery {
+    organization_id: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+struct UpdateProjectResponse {
+    project: ProjectForUser,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 struct CreateProjectResponse {
     project: ProjectForUser,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 struct AddMemberRequest {
     email: String,
     role: String,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 struct AddedMember {
     id: String,
@@ -156,69 +411,83 @@ struct AddedMember {
     role: String,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 struct AddMemberResponse {
     added: AddedMember,
     project: ProjectForUser,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 struct ProjectMemberView {
     user_id: String,
     role: String,
     email: String,
     name: String,
+    /// An automation account managed by admins, not a person.
+    service_account: bool,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
 struct MembersResponse {
     members: Vec<ProjectMemberView>,
+    next_cursor: Option<String>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct ListMembersQuery {
+    limit: Option<i64>,
+    cursor: Option<String>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 struct UpdateMemberRoleRequest {
     role: String,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 struct UpdateMemberRoleResponse {
     member: ProjectMemberView,
     updated_at: String,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 struct RemoveMemberResponse {
     ok: bool,
     updated_at: String,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 struct ProjectSessionResponse {
     project: ProjectForUser,
     session: Option<Value>,
+    /// Also sent as `ETag`; pass it in `If-Match` when saving.
+    version: i64,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 struct SaveSessionRequest {
     session: Value,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 struct SaveSessionResponse {
     ok: bool,
     updated_at: String,
+    version: i64,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 struct FailReasonsResponse {
     reasons: Vec<FailReasonDto>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 struct FailReasonDto {
     code: String,
@@ -226,16 +495,36 @@ struct FailReasonDto {
     description: String,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 struct CreateRunRequest {
     project_id: String,
     asset_id: Option<String>,
     template_id: Option<String>,
+    suite_id: Option<String>,
+    /// Adds the active test cases matching the tag expression, e.g. `smoke AND payments`;
+    /// with `suiteId` only the suite subtree is searched.
+    tag_query: Option<String>,
     title: Option<String>,
+    /// Release or sprint the run belongs to.
+    milestone: Option<String>,
+    description: Option<String>,
+    /// Values of the project's run fields; required fields must be set.
+    #[schema(value_type = Option<Object>)]
+    custom_fields: Option<serde_json::Map<String, Value>>,
+    /// Commit under test; with a CI connection its status is set to pending now and to the
+    /// outcome when the run is done.
+    commit_sha: Option<String>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+struct CloneRunQuery {
+    only_failed: Option<bool>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 struct AddRunItemRequest {
     testcase_version_id: String,
@@ -243,100 +532,290 @@ struct AddRunItemRequest {
     is_required: Option<bool>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct BulkAddRunItemsRequest {
+    testcase_version_ids: Vec<String>,
+    is_required: Option<bool>,
+}
+
+#[derive(Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 struct UpdateRunResultRequest {
     status: String,
     fail_reason_code: Option<String>,
     comment: Option<String>,
+    /// Time spent on the item; omitted keeps the previously reported value.
+    elapsed_seconds: Option<i32>,
+    /// Outcomes of individual steps; steps not listed keep their previous outcome.
+    steps: Option<Vec<UpdateStepResultRequest>>,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct BulkRunResultRequest {
+    run_item_id: String,
+    status: String,
+    fail_reason_code: Option<String>,
+    comment: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct UpdateStepResultRequest {
+    step_id: String,
+    status: String,
+    comment: Option<String>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 struct UpdateRunStatusRequest {
     status: String,
 }
 
-#[derive(Deserialize)]
+/// Absent fields stay as they are; an empty `assetId`, `templateId` or `milestone` clears it.
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct UpdateRunRequest {
+    title: Option<String>,
+    /// Changes only while the run is a draft.
+    asset_id: Option<String>,
+    /// Changes only while the run is a draft.
+    template_id: Option<String>,
+    milestone: Option<String>,
+    description: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct UnlockRunRequest {
+    /// Why the run is reopened; kept in the audit log and sent to the members.
+    reason: String,
+}
+
+#[derive(Deserialize, IntoParams)]
 #[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
 struct ListRunsQuery {
     project_id: Option<String>,
     status: Option<String>,
+    asset_id: Option<String>,
+    /// Exact asset version the runs were executed against.
+    asset_version: Option<String>,
+    /// Runs whose executor is this user.
+    executed_by_user_id: Option<String>,
+    /// JSON object of field key to value, e.g. `{"priority":"high"}`; needs `projectId`.
+    custom_fields: Option<String>,
+    /// Saved filter of the project; explicit parameters override the saved ones.
+    filter_id: Option<String>,
     limit: Option<i64>,
+    cursor: Option<String>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 struct RunView {
     id: String,
     project_id: String,
     asset_id: Option<String>,
+    /// Version of the asset when it was set on the run.
+    asset_version: Option<String>,
     template_id: Option<String>,
     title: String,
+    /// Release or sprint the run belongs to.
+    milestone: Option<String>,
+    description: String,
     status: String,
     executed_by_user_id: String,
-    started_at: Option<String>,
-    finished_at: Option<String>,
-    locked_at: Option<String>,
-    created_at: String,
-    updated_at: String,
+    default_assignee_user_id: Option<String>,
+    started_at: Option<DateTime<Utc>>,
+    finished_at: Option<DateTime<Utc>>,
+    locked_at: Option<DateTime<Utc>>,
+    #[schema(value_type = Object)]
+    custom_fields: Value,
+    commit_sha: Option<String>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 struct RunItemView {
     id: String,
     testcase_version_id: String,
+    testcase_id: String,
+    testcase_key: String,
+    testcase_title: String,
+    version_number: i32,
+    suite_id: String,
+    /// Suite names from the root down to the testcase's suite.
+    suite_path: Vec<String>,
+    tags: Vec<String>,
     position: i32,
     is_required: bool,
+    /// Effective executor: the item's own assignee or the run default.
+    assignee_user_id: Option<String>,
+    assignee_display_name: Option<String>,
+    assignee_inherited: bool,
     status: String,
     fail_reason_code: Option<String>,
     comment: String,
     updated_at: Option<String>,
+    /// `null` when the item has no result row.
+    result_version: Option<i64>,
+    /// Reported with the result, see `/api/v2/projects/{project_id}/effort`.
+    elapsed_seconds: Option<i32>,
+    /// When the item first got a status other than `na`.
+    executed_at: Option<String>,
+    /// Items that must pass (`ok`) before this one can be recorded as `ok` or `fail`.
+    depends_on: Vec<String>,
+    /// `dependsOn` items without an `ok` result yet.
+    blocked_by: Vec<String>,
+    defects: Vec<defects::DefectLinkView>,
+    /// Non-deleted comments in the item's thread.
+    comment_count: i64,
+    /// Steps of the testcase version with their outcomes.
+    steps: Vec<RunItemStepView>,
 }
 
-#[derive(Serialize)]
+/// A step of the run item's testcase version and its outcome in this run.
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct RunItemStepView {
+    step_id: String,
+    position: i32,
+    action: String,
+    expected_result: String,
+    /// `null` while the step has no outcome.
+    status: Option<String>,
+    comment: String,
+    updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct RunSummaryView {
+    run_id: String,
+    total: i64,
+    ok: i64,
+    fail: i64,
+    na: i64,
+    blocked: i64,
+    skipped: i64,
+    retest: i64,
+    untested: i64,
+    percent_complete: f64,
+    required_failing: i64,
+    elapsed_seconds: Option<i64>,
+}
+
+#[derive(Serialize, ToSchema)]
+struct RunSummaryResponse {
+    summary: RunSummaryView,
+}
+
+#[derive(Serialize, ToSchema)]
 struct CreateRunResponse {
     run: RunView,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
 struct ListRunsResponse {
     runs: Vec<RunView>,
+    next_cursor: Option<String>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
 struct RunDetailsResponse {
     run: RunView,
     items: Vec<RunItemView>,
+    /// Sections of `items` for `groupBy`; `null` without it.
+    groups: Option<Vec<run_groups::RunItemGroupView>>,
+    /// Non-deleted comments in the run's own thread, not counting item threads.
+    comment_count: i64,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct CreatedRunItemView {
+    id: String,
+    testcase_version_id: String,
+    position: i32,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct RunItemPositionRequest {
+    id: String,
+    position: i32,
+}
+
+#[derive(Deserialize, ToSchema)]
+struct ReorderRunItemsRequest {
+    items: Vec<RunItemPositionRequest>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct RunItemPositionView {
+    id: String,
+    position: i32,
+}
+
+#[derive(Serialize, ToSchema)]
+struct ReorderRunItemsResponse {
+    items: Vec<RunItemPositionView>,
+}
+
+#[derive(Serialize, ToSchema)]
+struct BulkAddRunItemsResponse {
+    items: Vec<CreatedRunItemView>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 struct UpdateRunResultResponse {
     ok: bool,
-    updated_at: String,
+    updated_at: DateTime<Utc>,
+    /// Also sent as `ETag`; pass it in `If-Match` on the next update.
+    version: i64,
+    steps: Vec<RunItemStepView>,
 }
 
-#[derive(Serialize)]
-struct UpdateRunStatusResponse {
-    run: RunView,
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct BulkRunResultView {
+    run_item_id: String,
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    updated_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version: Option<i64>,
+    /// Why the entry was not written; the other entries are saved regardless.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<ErrorBody>,
 }
 
-async fn health() -> Json<HealthResponse> {
-    Json(HealthResponse {
-        status: "ok",
-        service: "uran-api",
-    })
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct BulkUpdateRunResultsResponse {
+    succeeded: usize,
+    failed: usize,
+    /// One entry per request entry, in request order.
+    results: Vec<BulkRunResultView>,
 }
 
-fn api_error(status: StatusCode, message: &str) -> (StatusCode, Json<ErrorResponse>) {
-    (
-        status,
-        Json(ErrorResponse {
-            error: message.to_string(),
-        }),
-    )
+#[derive(Serialize, ToSchema)]
+struct UpdateRunStatusResponse {
+    run: RunView,
+}
+
+#[derive(Serialize, ToSchema)]
+struct UpdateRunResponse {
+    run: RunView,
 }
 
 fn now_iso() -> String {
@@ -348,6 +827,13 @@ fn map_safe_user(user: &User) -> SafeUser {
         id: user.id.clone(),
         name: user.name.clone(),
         email: user.email.clone(),
+        pending_email: user
+            .pending_email
+            .as_ref()
+            .filter(|p| !p.is_expired())
+            .map(|p| p.email.clone()),
+        email_verified: user.email_verified,
+        avatar_url: profile::avatar_url(&user.id, user.avatar.as_ref()),
         created_at: user.created_at.clone(),
     }
 }
@@ -358,50 +844,75 @@ fn membership_role(project: &Project, user_id: &str) -> Option<String> {
         .iter()
         .find(|m| m.user_id == user_id)
         .map(|m| m.role.clone())
+        .or_else(|| {
+            project
+                .organization_admins
+                .iter()
+                .any(|id| id == user_id)
+                .then(|| permissions::ORG_ADMIN_ROLE.to_string())
+        })
 }
 
 fn map_project_for_user(project: &Project, user_id: &str) -> Option<ProjectForUser> {
     let role = membership_role(project, user_id)?;
+    let capabilities = permissions::role_capabilities(project, &role)
+        .into_iter()
+        .map(Capability::as_str)
+        .collect();
     Some(ProjectForUser {
         id: project.id.clone(),
         name: project.name.clone(),
         role,
+        capabilities,
         owner_id: project.owner_id.clone(),
+        organization_id: project.organization_id.clone(),
+        settings: project.settings.clone(),
         created_at: project.created_at.clone(),
         updated_at: project.updated_at.clone(),
     })
 }
 
-fn can_write_project(role: &str) -> bool {
-    role == "owner" || role == "editor"
-}
-
-fn parse_bearer_user_id(headers: &HeaderMap) -> Result<String, (StatusCode, Json<ErrorResponse>)> {
+fn parse_bearer_user_id(keys: &jwt::JwtKeys, headers: &HeaderMap) -> Result<String, ApiError> {
+    if let Some(grant) = api_keys::current() {
+        return Ok(grant.user_id);
+    }
     let auth = headers
         .get("authorization")
         .and_then(|v| v.to_str().ok())
         .unwrap_or_default();
     if !auth.starts_with("Bearer ") {
-        return Err(api_error(
-            StatusCode::UNAUTHORIZED,
-            "Требуется авторизация.",
-        ));
+        return Err(ApiError::Unauthorized);
     }
     let token = auth.trim_start_matches("Bearer ").trim();
-    if !token.starts_with("uran.") {
-        return Err(api_error(
-            StatusCode::UNAUTHORIZED,
-            "Недействительный токен.",
-        ));
+    let claims = keys
+        .verify(token, jwt::TokenKind::Access)
+        .ok_or(ApiError::InvalidToken)?;
+    Ok(claims.sub)
+}
+
+/// Every sign-in path (password, refresh, single sign-on) ends here, so this is where
+/// deactivated accounts and forced password resets are turned away.
+fn issue_auth_response(
+    keys: &jwt::JwtKeys,
+    user: &User,
+    err: ApiError,
+) -> Result<AuthResponse, ApiError> {
+    if user.deactivated_at.is_some() {
+        return Err(ApiError::UserDeactivated);
     }
-    let user_id = token.trim_start_matches("uran.").to_string();
-    if Uuid::parse_str(&user_id).is_err() {
-        return Err(api_error(
-            StatusCode::UNAUTHORIZED,
-            "Недействительный токен.",
-        ));
+    if user.password_reset.is_some() {
+        return Err(ApiError::PasswordResetRequired);
     }
-    Ok(user_id)
+    if user.service_account.is_some() {
+        return Err(ApiError::ServiceAccountSignIn);
+    }
+    let pair = keys.issue_pair(&user.id).map_err(|_| err)?;
+    Ok(AuthResponse {
+        token: pair.access_token,
+        refresh_token: pair.refresh_token,
+        expires_in: pair.expires_in,
+        user: map_safe_user(user),
+    })
 }
 
 async fn ensure_json_file(path: &StdPath, content: &str) -> anyhow::Result<()> {
@@ -438,10 +949,10 @@ async fn read_users(path: &StdPath) -> anyhow::Result<Vec<User>> {
                         .and_then(|v| v.as_str())
                         .unwrap_or("User")
                         .to_string();
-                    let password = obj
-                        .get("password")
+                    let password_hash = obj
+                        .get("passwordHash")
                         .and_then(|v| v.as_str())
-                        .or_else(|| obj.get("passwordHash").and_then(|v| v.as_str()))
+                        .or_else(|| obj.get("password").and_then(|v| v.as_str()))
                         .unwrap_or_default()
                         .to_string();
                     let created_at = obj
@@ -455,8 +966,17 @@ async fn read_users(path: &StdPath) -> anyhow::Result<Vec<User>> {
                         id,
                         name,
                         email,
-                        password,
+                        password_hash,
                         created_at,
+                        pending_email: None,
+                        avatar: None,
+                        oidc: None,
+                        email_verified: true,
+                        email_verification: None,
+                        is_admin: false,
+                        deactivated_at: None,
+                        password_reset: None,
+                        service_account: None,
                     })
                 })
                 .collect();
@@ -477,11 +997,11 @@ async fn write_users(path: &StdPath, users: &[User]) -> anyhow::Result<()> {
 async fn read_projects(path: &StdPath) -> anyhow::Result<Vec<Project>> {
     ensure_json_file(path, "{\n  \"projects\": []\n}\n").await?;
     let raw = fs::read_to_string(path).await?;
-    match serde_json::from_str::<ProjectsFile>(&raw) {
-        Ok(parsed) => Ok(parsed.projects),
+    let mut projects = match serde_json::from_str::<ProjectsFile>(&raw) {
+        Ok(parsed) => parsed.projects,
         Err(_) => {
             let value: Value = serde_json::from_str(&raw)?;
-            let projects = value
+            value
                 .get("projects")
                 .and_then(|v| v.as_array())
                 .cloned()
@@ -536,7 +1056,31 @@ async fn read_projects(path: &StdPath) -> anyhow::Result<Vec<Project>> {
                         })
                         .collect::<Vec<_>>();
 
+                    let roles = obj
+                        .get("roles")
+                        .cloned()
+                        .and_then(|v| serde_json::from_value(v).ok())
+                        .unwrap_or_default();
+                    let settings = obj
+                        .get("settings")
+                        .cloned()
+                        .and_then(|v| serde_json::from_value(v).ok())
+                        .unwrap_or_default();
+                    let invitations = obj
+                        .get("invitations")
+                        .cloned()
+                        .and_then(|v| serde_json::from_value(v).ok())
+                        .unwrap_or_default();
                     let session = obj.get("session").cloned();
+                    let session_version = obj
+                        .get("sessionVersion")
+                        .and_then(|v| v.as_i64())
+                        .unwrap_or_default();
+
+                    let organization_id = obj
+                        .get("organizationId")
+                        .and_then(|v| v.as_str())
+                        .map(str::to_string);
 
                     Some(Project {
                         id,
@@ -545,16 +1089,31 @@ async fn read_projects(path: &StdPath) -> anyhow::Result<Vec<Project>> {
                         created_at,
                         updated_at,
                         members,
+                        roles,
+                        settings,
+                        invitations,
                         session,
+                        session_version,
+                        organization_id,
+                        organization_admins: Vec::new(),
                     })
                 })
-                .collect();
-            Ok(projects)
+                .collect()
         }
-    }
+    };
+    organizations::attach_admins(path, &mut projects).await?;
+    Ok(projects)
+}
+
+/// Callers hold `file_lock`, so the cached projects dropped here cannot be refilled from
+/// the old file.
+async fn write_projects(state: &AppState, projects: &[Project]) -> anyhow::Result<()> {
+    write_projects_file(&state.projects_file, projects).await?;
+    state.cache.projects.invalidate_all();
+    Ok(())
 }
 
-async fn write_projects(path: &StdPath, projects: &[Project]) -> anyhow::Result<()> {
+async fn write_projects_file(path: &StdPath, projects: &[Project]) -> anyhow::Result<()> {
     let data = ProjectsFile {
         projects: projects.to_vec(),
     };
@@ -563,147 +1122,244 @@ async fn write_projects(path: &StdPath, projects: &[Project]) -> anyhow::Result<
     Ok(())
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/auth/register",
+    tag = "auth",
+    request_body = RegisterRequest,
+    responses((status = 201, body = AuthResponse)),
+    security(())
+)]
 async fn register(
     State(state): State<AppState>,
     Json(payload): Json<RegisterRequest>,
-) -> Result<(StatusCode, Json<AuthResponse>), (StatusCode, Json<ErrorResponse>)> {
+) -> Result<(StatusCode, Json<AuthResponse>), ApiError> {
     let name = payload.name.trim();
     let email = payload.email.trim().to_lowercase();
     let password = payload.password;
 
     if name.chars().count() < 2 {
-        return Err(api_error(
-            StatusCode::BAD_REQUEST,
-            "Имя должно быть не короче 2 символов.",
-        ));
+        return Err(ApiError::NameTooShort);
     }
     if !email.contains('@') {
-        return Err(api_error(StatusCode::BAD_REQUEST, "Некорректный email."));
+        return Err(ApiError::InvalidEmail);
     }
     if password.chars().count() < 8 {
-        return Err(api_error(
-            StatusCode::BAD_REQUEST,
-            "Пароль должен быть не короче 8 символов.",
-        ));
+        return Err(ApiError::PasswordTooShort);
     }
 
     let _guard = state.file_lock.lock().await;
     let mut users = read_users(&state.users_file)
         .await
-        .map_err(|_| api_error(StatusCode::INTERNAL_SERVER_ERROR, "Ошибка регистрации."))?;
+        .map_err(|_| ApiError::RegistrationFailed)?;
 
     if users.iter().any(|u| u.email == email) {
-        return Err(api_error(
-            StatusCode::CONFLICT,
-            "Пользователь с таким email уже существует.",
-        ));
+        return Err(ApiError::EmailTaken);
     }
 
+    let password_hash =
+        password::hash_password(&password).map_err(|_| ApiError::RegistrationFailed)?;
+    let (verification, token) = profile::EmailVerification::issue();
     let user = User {
         id: Uuid::new_v4().to_string(),
         name: name.to_string(),
         email,
-        password,
+        password_hash,
         created_at: now_iso(),
+        pending_email: None,
+        avatar: None,
+        oidc: None,
+        email_verified: false,
+        email_verification: Some(verification),
+        is_admin: false,
+        deactivated_at: None,
+        password_reset: None,
+        service_account: None,
+    };
+    let mut projects = read_projects(&state.projects_file)
+        .await
+        .map_err(|_| ApiError::RegistrationFailed)?;
+    // When invitations require a verified address, they are accepted by `/api/auth/verify`.
+    let attached = if state.invites_require_verified_email {
+        Vec::new()
+    } else {
+        invitations::attach_pending_invitations(&mut projects, &user)
     };
     users.push(user.clone());
     write_users(&state.users_file, &users)
         .await
-        .map_err(|_| api_error(StatusCode::INTERNAL_SERVER_ERROR, "Ошибка регистрации."))?;
+        .map_err(|_| ApiError::RegistrationFailed)?;
+    if !attached.is_empty() {
+        write_projects(&state, &projects)
+            .await
+            .map_err(|_| ApiError::RegistrationFailed)?;
+    }
+    invitations::emit_attached(&state, &user, &attached).await;
+    profile::send_verification_email(&state, &user, &token);
 
-    let token = format!("uran.{}", user.id);
-    Ok((
-        StatusCode::CREATED,
-        Json(AuthResponse {
-            token,
-            user: map_safe_user(&user),
-        }),
-    ))
+    let response = issue_auth_response(&state.jwt, &user, ApiError::RegistrationFailed)?;
+    Ok((StatusCode::CREATED, Json(response)))
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/auth/login",
+    tag = "auth",
+    request_body = LoginRequest,
+    responses((status = 200, body = AuthResponse)),
+    security(())
+)]
 async fn login(
     State(state): State<AppState>,
     Json(payload): Json<LoginRequest>,
-) -> Result<Json<AuthResponse>, (StatusCode, Json<ErrorResponse>)> {
+) -> Result<Json<AuthResponse>, ApiError> {
     let email = payload.email.trim().to_lowercase();
     let password = payload.password;
 
     let _guard = state.file_lock.lock().await;
-    let users = read_users(&state.users_file)
+    let mut users = read_users(&state.users_file)
+        .await
+        .map_err(|_| ApiError::LoginFailed)?;
+
+    let stored = users
+        .iter_mut()
+        .find(|u| u.email == email && password::verify_password(&password, &u.password_hash))
+        .ok_or(ApiError::InvalidCredentials)?;
+
+    // Lazy migration: plaintext entries are rehashed on the first successful login.
+    let needs_rehash = password::rehash_if_plaintext(&mut stored.password_hash, &password)
+        .map_err(|_| ApiError::LoginFailed)?;
+    let user = stored.clone();
+    if needs_rehash {
+        write_users(&state.users_file, &users)
+            .await
+            .map_err(|_| ApiError::LoginFailed)?;
+    }
+
+    let response = issue_auth_response(&state.jwt, &user, ApiError::LoginFailed)?;
+    Ok(Json(response))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/auth/refresh",
+    tag = "auth",
+    request_body = RefreshRequest,
+    responses((status = 200, body = AuthResponse)),
+    security(())
+)]
+async fn refresh(
+    State(state): State<AppState>,
+    Json(payload): Json<RefreshRequest>,
+) -> Result<Json<AuthResponse>, ApiError> {
+    let claims = state
+        .jwt
+        .verify(payload.refresh_token.trim(), jwt::TokenKind::Refresh)
+        .ok_or(ApiError::InvalidRefreshToken)?;
+    // Refresh tokens are single-use: a replayed one has already been revoked here.
+    ensure_db_user_exists(&state, &claims.sub).await?;
+    let rotated = revocation::revoke(&state.db, &state.jwt, &claims)
         .await
-        .map_err(|_| api_error(StatusCode::INTERNAL_SERVER_ERROR, "Ошибка входа."))?;
+        .map_err(|_| ApiError::TokenRefreshFailed)?;
+    if !rotated {
+        return Err(ApiError::InvalidRefreshToken);
+    }
 
+    let _guard = state.file_lock.lock().await;
+    let users = read_users(&state.users_file)
+        .await
+        .map_err(|_| ApiError::TokenRefreshFailed)?;
     let user = users
         .iter()
-        .find(|u| u.email == email && u.password == password)
-        .cloned()
-        .ok_or_else(|| api_error(StatusCode::UNAUTHORIZED, "Неверный email или пароль."))?;
+        .find(|u| u.id == claims.sub)
+        .ok_or(ApiError::TokenUserNotFound)?;
 
-    let token = format!("uran.{}", user.id);
-    Ok(Json(AuthResponse {
-        token,
-        user: map_safe_user(&user),
-    }))
+    let response = issue_auth_response(&state.jwt, user, ApiError::TokenRefreshFailed)?;
+    Ok(Json(response))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/auth/me",
+    tag = "auth",
+    responses((status = 200, body = MeResponse))
+)]
 async fn me(
     State(state): State<AppState>,
-    headers: HeaderMap,
-) -> Result<Json<MeResponse>, (StatusCode, Json<ErrorResponse>)> {
-    let user_id = parse_bearer_user_id(&headers)?;
-
+    AuthUser(user_id): AuthUser,
+) -> Result<Json<MeResponse>, ApiError> {
     let _guard = state.file_lock.lock().await;
     let users = read_users(&state.users_file)
         .await
-        .map_err(|_| api_error(StatusCode::INTERNAL_SERVER_ERROR, "Ошибка загрузки профиля."))?;
+        .map_err(|_| ApiError::ProfileLoadFailed)?;
     let user = users
         .iter()
         .find(|u| u.id == user_id)
-        .ok_or_else(|| api_error(StatusCode::NOT_FOUND, "Пользователь не найден."))?;
+        .ok_or(ApiError::UserNotFound)?;
 
     Ok(Json(MeResponse {
         user: map_safe_user(user),
     }))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/projects",
+    tag = "projects",
+    params(ListProjectsQuery),
+    responses((status = 200, body = ProjectsResponse))
+)]
 async fn list_projects(
     State(state): State<AppState>,
-    headers: HeaderMap,
-) -> Result<Json<ProjectsResponse>, (StatusCode, Json<ErrorResponse>)> {
-    let user_id = parse_bearer_user_id(&headers)?;
-
+    AuthUser(user_id): AuthUser,
+    Query(query): Query<ListProjectsQuery>,
+) -> Result<Json<ProjectsResponse>, ApiError> {
     let _guard = state.file_lock.lock().await;
     let projects = read_projects(&state.projects_file)
         .await
-        .map_err(|_| api_error(StatusCode::INTERNAL_SERVER_ERROR, "Ошибка загрузки проектов."))?;
+        .map_err(|_| ApiError::ProjectsLoadFailed)?;
 
     let visible: Vec<ProjectForUser> = projects
         .iter()
+        .filter(|p| query.organization_id.is_none() || p.organization_id == query.organization_id)
         .filter_map(|p| map_project_for_user(p, &user_id))
         .collect();
 
     Ok(Json(ProjectsResponse { projects: visible }))
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/projects",
+    tag = "projects",
+    request_body = CreateProjectRequest,
+    responses((status = 201, body = CreateProjectResponse))
+)]
 async fn create_project(
     State(state): State<AppState>,
-    headers: HeaderMap,
+    AuthUser(user_id): AuthUser,
     Json(payload): Json<CreateProjectRequest>,
-) -> Result<(StatusCode, Json<CreateProjectResponse>), (StatusCode, Json<ErrorResponse>)> {
-    let user_id = parse_bearer_user_id(&headers)?;
+) -> Result<(StatusCode, Json<CreateProjectResponse>), ApiError> {
     let name = payload.name.trim();
 
     if name.chars().count() < 3 {
-        return Err(api_error(
-            StatusCode::BAD_REQUEST,
-            "Название проекта должно быть не короче 3 символов.",
-        ));
+        return Err(ApiError::ProjectNameTooShort);
     }
 
     let _guard = state.file_lock.lock().await;
     let mut projects = read_projects(&state.projects_file)
         .await
-        .map_err(|_| api_error(StatusCode::INTERNAL_SERVER_ERROR, "Ошибка создания проекта."))?;
+        .map_err(|_| ApiError::ProjectCreateFailed)?;
+    let organization_admins = match payload.organization_id.as_deref() {
+        Some(organization_id) => {
+            let organizations = organizations::read_organizations(&state.organizations_file)
+                .await
+                .map_err(|_| ApiError::ProjectCreateFailed)?;
+            organizations::require_member(&organizations, organization_id, &user_id, false)?
+                .admin_ids()
+        }
+        None => Vec::new(),
+    };
 
     let now = now_iso();
     let project = Project {
@@ -716,14 +1372,19 @@ async fn create_project(
             user_id: user_id.clone(),
             role: "owner".to_string(),
         }],
+        roles: Vec::new(),
+        settings: ProjectSettings::default(),
+        invitations: Vec::new(),
         session: None,
+        session_version: 0,
+        organization_id: payload.organization_id,
+        organization_admins,
     };
-    let mapped = map_project_for_user(&project, &user_id)
-        .ok_or_else(|| api_error(StatusCode::INTERNAL_SERVER_ERROR, "Ошибка создания проекта."))?;
+    let mapped = map_project_for_user(&project, &user_id).ok_or(ApiError::ProjectCreateFailed)?;
     projects.push(project);
-    write_projects(&state.projects_file, &projects)
+    write_projects(&state, &projects)
         .await
-        .map_err(|_| api_error(StatusCode::INTERNAL_SERVER_ERROR, "Ошибка создания проекта."))?;
+        .map_err(|_| ApiError::ProjectCreateFailed)?;
 
     Ok((
         StatusCode::CREATED,
@@ -731,64 +1392,213 @@ async fn create_project(
     ))
 }
 
+/// Checks settings against the DB catalogs and returns them normalized.
+async fn validate_project_settings(
+    state: &AppState,
+    project_id: &str,
+    settings: ProjectSettings,
+) -> Result<ProjectSettings, ApiError> {
+    let validation_failed = |_| ApiError::ProjectSettingsCheckFailed;
+    let project_uuid = Uuid::parse_str(project_id).ok();
+
+    let default_run_template_id = match settings.default_run_template_id.as_deref().map(str::trim) {
+        Some(v) if !v.is_empty() => {
+            let template_uuid = parse_uuid(v, ApiError::InvalidDefaultRunTemplateId)?;
+            let exists: bool = sqlx::query_scalar(
+                r#"
+                SELECT EXISTS (
+                  SELECT 1 FROM run_templates
+                  WHERE id = $1 AND is_active = TRUE AND (project_id IS NULL OR project_id = $2)
+                )
+                "#,
+            )
+            .bind(template_uuid)
+            .bind(project_uuid)
+            .fetch_one(&state.db)
+            .await
+            .map_err(validation_failed)?;
+            if !exists {
+                return Err(ApiError::RunTemplateNotInProject);
+            }
+            Some(template_uuid.to_string())
+        }
+        _ => None,
+    };
+
+    let mut required_fail_reason_codes: Vec<String> = Vec::new();
+    for code in settings.required_fail_reason_codes.iter().map(|c| c.trim()) {
+        if !code.is_empty() && !required_fail_reason_codes.iter().any(|c| c == code) {
+            required_fail_reason_codes.push(code.to_string());
+        }
+    }
+    let known = fail_reasons::count_allowed_codes(
+        &state.db,
+        project_uuid.unwrap_or_default(),
+        &required_fail_reason_codes,
+    )
+    .await
+    .map_err(validation_failed)?;
+    if known as usize != required_fail_reason_codes.len() {
+        return Err(ApiError::UnknownFailReasonCode);
+    }
+
+    let timezone = settings.timezone.trim().to_string();
+    let timezone_known: bool =
+        sqlx::query_scalar(r#"SELECT EXISTS (SELECT 1 FROM pg_timezone_names WHERE name = $1)"#)
+            .bind(&timezone)
+            .fetch_one(&state.db)
+            .await
+            .map_err(validation_failed)?;
+    if !timezone_known {
+        return Err(ApiError::InvalidTimezone);
+    }
+
+    Ok(ProjectSettings {
+        default_run_template_id,
+        required_fail_reason_codes,
+        timezone,
+        auto_complete_runs: settings.auto_complete_runs,
+    })
+}
+
+/// Settings of a file-based project, used by the DB-backed run handlers.
+async fn load_project_settings(
+    state: &AppState,
+    project_id: &str,
+) -> Result<ProjectSettings, ApiError> {
+    let _guard = state.file_lock.lock().await;
+    let projects = read_projects(&state.projects_file)
+        .await
+        .map_err(|_| ApiError::ProjectSettingsLoadFailed)?;
+    projects
+        .iter()
+        .find(|p| p.id == project_id)
+        .map(|p| p.settings.clone())
+        .ok_or(ApiError::ProjectNotFound)
+}
+
+#[utoipa::path(
+    patch,
+    path = "/api/projects/{project_id}",
+    tag = "projects",
+    params(("project_id" = String, Path)),
+    request_body = UpdateProjectRequest,
+    responses((status = 200, body = UpdateProjectResponse))
+)]
+async fn update_project(
+    State(state): State<AppState>,
+    Path(project_id): Path<String>,
+    AuthUser(user_id): AuthUser,
+    Json(payload): Json<UpdateProjectRequest>,
+) -> Result<Json<UpdateProjectResponse>, ApiError> {
+    let name = payload.name.as_deref().map(str::trim).map(str::to_string);
+    if let Some(name) = &name {
+        if name.chars().count() < 3 {
+            return Err(ApiError::ProjectNameTooShort);
+        }
+    }
+    let settings = match payload.settings {
+        Some(settings) => Some(validate_project_settings(&state, &project_id, settings).await?),
+        None => None,
+    };
+    let organization_id = payload
+        .organization_id
+        .as_deref()
+        .map(str::trim)
+        .map(str::to_string);
+    if name.is_none() && settings.is_none() && organization_id.is_none() {
+        return Err(ApiError::ProjectUpdateEmpty);
+    }
+
+    let _guard = state.file_lock.lock().await;
+    let mut projects = read_projects(&state.projects_file)
+        .await
+        .map_err(|_| ApiError::ProjectUpdateFailed)?;
+    let project = projects
+        .iter_mut()
+        .find(|p| p.id == project_id)
+        .ok_or(ApiError::ProjectNotFound)?;
+    permissions::check(project, &user_id, Capability::ProjectManage)?;
+
+    if let Some(organization_id) = organization_id {
+        let organizations = organizations::read_organizations(&state.organizations_file)
+            .await
+            .map_err(|_| ApiError::ProjectUpdateFailed)?;
+        if let Some(current) = project.organization_id.as_deref() {
+            organizations::require_member(&organizations, current, &user_id, true)?;
+        }
+        project.organization_admins =
+            organizations::require_member(&organizations, &organization_id, &user_id, true)?
+                .admin_ids();
+        project.organization_id = Some(organization_id);
+    }
+    if let Some(name) = name {
+        project.name = name;
+    }
+    if let Some(settings) = settings {
+        project.settings = settings;
+    }
+    project.updated_at = now_iso();
+    let mapped = map_project_for_user(project, &user_id).ok_or(ApiError::ProjectUpdateFailed)?;
+    write_projects(&state, &projects)
+        .await
+        .map_err(|_| ApiError::ProjectUpdateFailed)?;
+
+    Ok(Json(UpdateProjectResponse { project: mapped }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/projects/{project_id}/members",
+    tag = "members",
+    params(("project_id" = String, Path)),
+    request_body = AddMemberRequest,
+    responses((status = 200, body = AddMemberResponse))
+)]
 async fn add_member(
     State(state): State<AppState>,
     Path(project_id): Path<String>,
-    headers: HeaderMap,
+    AuthUser(actor_id): AuthUser,
     Json(payload): Json<AddMemberRequest>,
-) -> Result<Json<AddMemberResponse>, (StatusCode, Json<ErrorResponse>)> {
-    let actor_id = parse_bearer_user_id(&headers)?;
+) -> Result<Json<AddMemberResponse>, ApiError> {
     let email = payload.email.trim().to_lowercase();
     let role = payload.role.trim().to_lowercase();
 
-    if role != "editor" && role != "viewer" {
-        return Err(api_error(
-            StatusCode::BAD_REQUEST,
-            "Роль должна быть editor или viewer.",
-        ));
-    }
     if !email.contains('@') {
-        return Err(api_error(StatusCode::BAD_REQUEST, "Некорректный email."));
+        return Err(ApiError::InvalidEmail);
     }
 
     let _guard = state.file_lock.lock().await;
     let users = read_users(&state.users_file)
         .await
-        .map_err(|_| api_error(StatusCode::INTERNAL_SERVER_ERROR, "Ошибка выдачи доступа."))?;
+        .map_err(|_| ApiError::MemberAddFailed)?;
+    // Service accounts stay in the project they were created for.
     let invitee = users
         .iter()
-        .find(|u| u.email == email)
+        .find(|u| u.email == email && u.service_account.is_none())
         .cloned()
-        .ok_or_else(|| {
-            api_error(
-                StatusCode::NOT_FOUND,
-                "Пользователь с таким email не найден.",
-            )
-        })?;
+        .ok_or(ApiError::MemberEmailNotFound)?;
+    if state.invites_require_verified_email && !invitee.email_verified {
+        return Err(ApiError::MemberEmailNotVerified);
+    }
 
     let mut projects = read_projects(&state.projects_file)
         .await
-        .map_err(|_| api_error(StatusCode::INTERNAL_SERVER_ERROR, "Ошибка выдачи доступа."))?;
+        .map_err(|_| ApiError::MemberAddFailed)?;
     let project = projects
         .iter_mut()
         .find(|p| p.id == project_id)
-        .ok_or_else(|| api_error(StatusCode::NOT_FOUND, "Проект не найден."))?;
+        .ok_or(ApiError::ProjectNotFound)?;
 
-    let actor_role = membership_role(project, &actor_id)
-        .ok_or_else(|| api_error(StatusCode::FORBIDDEN, "Только владелец может управлять доступом."))?;
-    if actor_role != "owner" {
-        return Err(api_error(
-            StatusCode::FORBIDDEN,
-            "Только владелец может управлять доступом.",
-        ));
+    permissions::check(project, &actor_id, Capability::ProjectManage)?;
+    if !permissions::is_assignable_role(project, &role) {
+        return Err(ApiError::InvalidMemberRole);
     }
 
+    let is_new_member = !project.members.iter().any(|m| m.user_id == invitee.id);
     if let Some(existing) = project.members.iter_mut().find(|m| m.user_id == invitee.id) {
         if invitee.id == project.owner_id {
-            return Err(api_error(
-                StatusCode::BAD_REQUEST,
-                "Нельзя изменить роль владельца.",
-            ));
+            return Err(ApiError::OwnerRoleImmutable);
         }
         existing.role = role.clone();
     } else {
@@ -799,12 +1609,34 @@ async fn add_member(
     }
 
     project.updated_at = now_iso();
-    let mapped_project = map_project_for_user(project, &actor_id)
-        .ok_or_else(|| api_error(StatusCode::INTERNAL_SERVER_ERROR, "Ошибка выдачи доступа."))?;
+    let mapped_project =
+        map_project_for_user(project, &actor_id).ok_or(ApiError::MemberAddFailed)?;
     let updated_at = project.updated_at.clone();
-    write_projects(&state.projects_file, &projects)
+    let project_name = project.name.clone();
+    write_projects(&state, &projects)
         .await
-        .map_err(|_| api_error(StatusCode::INTERNAL_SERVER_ERROR, "Ошибка выдачи доступа."))?;
+        .map_err(|_| ApiError::MemberAddFailed)?;
+
+    if let (true, Ok(project_uuid)) = (is_new_member, Uuid::parse_str(&project_id)) {
+        webhooks::emit(
+            &state,
+            project_uuid,
+            "member.added",
+            json!({ "userId": &invitee.id, "email": &invitee.email, "name": &invitee.name, "role": &role }),
+        )
+        .await;
+    }
+    if is_new_member {
+        notifications::notify(
+            &state,
+            notifications::NotificationKind::MemberAdded,
+            Uuid::parse_str(&project_id).ok(),
+            None,
+            vec![invitee.id.clone()],
+            format!("Доступ к проекту «{project_name}»"),
+            format!("Вам выдан доступ к проекту «{project_name}» с ролью {role}."),
+        );
+    }
 
     Ok(Json(AddMemberResponse {
         added: AddedMember {
@@ -820,109 +1652,124 @@ async fn add_member(
     }))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/projects/{project_id}/members",
+    tag = "members",
+    params(("project_id" = String, Path), ListMembersQuery),
+    responses((status = 200, body = MembersResponse))
+)]
 async fn list_members(
     State(state): State<AppState>,
     Path(project_id): Path<String>,
-    headers: HeaderMap,
-) -> Result<Json<MembersResponse>, (StatusCode, Json<ErrorResponse>)> {
-    let user_id = parse_bearer_user_id(&headers)?;
+    AuthUser(user_id): AuthUser,
+    Query(query): Query<ListMembersQuery>,
+) -> Result<Json<MembersResponse>, ApiError> {
+    let limit = pagination::page_limit(query.limit);
+    let cursor = pagination::parse_cursor(query.cursor.as_deref())?;
 
     let _guard = state.file_lock.lock().await;
     let users = read_users(&state.users_file)
         .await
-        .map_err(|_| api_error(StatusCode::INTERNAL_SERVER_ERROR, "Ошибка загрузки участников."))?;
+        .map_err(|_| ApiError::MembersLoadFailed)?;
     let projects = read_projects(&state.projects_file)
         .await
-        .map_err(|_| api_error(StatusCode::INTERNAL_SERVER_ERROR, "Ошибка загрузки участников."))?;
+        .map_err(|_| ApiError::MembersLoadFailed)?;
     let project = projects
         .iter()
         .find(|p| p.id == project_id)
-        .ok_or_else(|| api_error(StatusCode::NOT_FOUND, "Проект не найден."))?;
+        .ok_or(ApiError::ProjectNotFound)?;
 
-    if membership_role(project, &user_id).is_none() {
-        return Err(api_error(StatusCode::FORBIDDEN, "Нет доступа к проекту."));
-    }
+    permissions::check(project, &user_id, Capability::ProjectRead)?;
 
-    let members = project
+    // Members have no timestamp of their own; they are paged in the order users registered.
+    let mut members: Vec<(String, ProjectMemberView)> = project
         .members
         .iter()
         .map(|m| {
             let user = users.iter().find(|u| u.id == m.user_id);
-            ProjectMemberView {
-                user_id: m.user_id.clone(),
-                role: m.role.clone(),
-                email: user.map(|u| u.email.clone()).unwrap_or_default(),
-                name: user.map(|u| u.name.clone()).unwrap_or_default(),
-            }
+            (
+                user.map(|u| u.created_at.clone()).unwrap_or_default(),
+                ProjectMemberView {
+                    user_id: m.user_id.clone(),
+                    role: m.role.clone(),
+                    email: user.map(|u| u.email.clone()).unwrap_or_default(),
+                    name: user.map(|u| u.name.clone()).unwrap_or_default(),
+                    service_account: user.is_some_and(|u| u.service_account.is_some()),
+                },
+            )
         })
         .collect();
-    Ok(Json(MembersResponse { members }))
+    members.sort_by(|a, b| (&a.0, &a.1.user_id).cmp(&(&b.0, &b.1.user_id)));
+    let members = members
+        .into_iter()
+        .filter(|(created_at, m)| match &cursor {
+            Some(c) => (created_at, &m.user_id) > (&c.created_at, &c.id),
+            None => true,
+        })
+        .take(limit as usize + 1)
+        .collect();
+    let (members, next_cursor) =
+        pagination::finish_page(members, limit, |(created_at, m)| pagination::Cursor {
+            created_at: created_at.clone(),
+            id: m.user_id.clone(),
+        });
+    let members = members.into_iter().map(|(_, m)| m).collect();
+    Ok(Json(MembersResponse {
+        members,
+        next_cursor,
+    }))
 }
 
+#[utoipa::path(
+    patch,
+    path = "/api/projects/{project_id}/members/{user_id}",
+    tag = "members",
+    params(("project_id" = String, Path), ("user_id" = String, Path)),
+    request_body = UpdateMemberRoleRequest,
+    responses((status = 200, body = UpdateMemberRoleResponse))
+)]
 async fn update_member(
     State(state): State<AppState>,
     Path((project_id, target_user_id)): Path<(String, String)>,
-    headers: HeaderMap,
+    AuthUser(actor_id): AuthUser,
     Json(payload): Json<UpdateMemberRoleRequest>,
-) -> Result<Json<UpdateMemberRoleResponse>, (StatusCode, Json<ErrorResponse>)> {
-    let actor_id = parse_bearer_user_id(&headers)?;
+) -> Result<Json<UpdateMemberRoleResponse>, ApiError> {
     let role = payload.role.trim().to_lowercase();
-    if role != "editor" && role != "viewer" {
-        return Err(api_error(
-            StatusCode::BAD_REQUEST,
-            "Роль должна быть editor или viewer.",
-        ));
-    }
 
     let _guard = state.file_lock.lock().await;
-    let users = read_users(&state.users_file).await.map_err(|_| {
-        api_error(
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "Ошибка обновления роли участника.",
-        )
-    })?;
-    let mut projects = read_projects(&state.projects_file).await.map_err(|_| {
-        api_error(
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "Ошибка обновления роли участника.",
-        )
-    })?;
+    let users = read_users(&state.users_file)
+        .await
+        .map_err(|_| ApiError::MemberUpdateFailed)?;
+    let mut projects = read_projects(&state.projects_file)
+        .await
+        .map_err(|_| ApiError::MemberUpdateFailed)?;
     let project = projects
         .iter_mut()
         .find(|p| p.id == project_id)
-        .ok_or_else(|| api_error(StatusCode::NOT_FOUND, "Проект не найден."))?;
+        .ok_or(ApiError::ProjectNotFound)?;
 
-    let actor_role = membership_role(project, &actor_id)
-        .ok_or_else(|| api_error(StatusCode::FORBIDDEN, "Только владелец может управлять доступом."))?;
-    if actor_role != "owner" {
-        return Err(api_error(
-            StatusCode::FORBIDDEN,
-            "Только владелец может управлять доступом.",
-        ));
+    permissions::check(project, &actor_id, Capability::ProjectManage)?;
+    if !permissions::is_assignable_role(project, &role) {
+        return Err(ApiError::InvalidMemberRole);
     }
     if target_user_id == project.owner_id {
-        return Err(api_error(
-            StatusCode::BAD_REQUEST,
-            "Нельзя изменить роль владельца.",
-        ));
+        return Err(ApiError::OwnerRoleImmutable);
     }
 
     let member = project
         .members
         .iter_mut()
         .find(|m| m.user_id == target_user_id)
-        .ok_or_else(|| api_error(StatusCode::NOT_FOUND, "Участник не найден."))?;
+        .ok_or(ApiError::MemberNotFound)?;
     member.role = role;
     let member_snapshot = member.clone();
     project.updated_at = now_iso();
     let updated_at = project.updated_at.clone();
 
-    write_projects(&state.projects_file, &projects).await.map_err(|_| {
-        api_error(
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "Ошибка обновления роли участника.",
-        )
-    })?;
+    write_projects(&state, &projects)
+        .await
+        .map_err(|_| ApiError::MemberUpdateFailed)?;
 
     let user = users.iter().find(|u| u.id == member_snapshot.user_id);
     Ok(Json(UpdateMemberRoleResponse {
@@ -931,136 +1778,166 @@ async fn update_member(
             role: member_snapshot.role,
             email: user.map(|u| u.email.clone()).unwrap_or_default(),
             name: user.map(|u| u.name.clone()).unwrap_or_default(),
+            service_account: user.is_some_and(|u| u.service_account.is_some()),
         },
         updated_at,
     }))
 }
 
+#[utoipa::path(
+    delete,
+    path = "/api/projects/{project_id}/members/{user_id}",
+    tag = "members",
+    params(("project_id" = String, Path), ("user_id" = String, Path)),
+    responses((status = 200, body = RemoveMemberResponse))
+)]
 async fn remove_member(
     State(state): State<AppState>,
     Path((project_id, target_user_id)): Path<(String, String)>,
-    headers: HeaderMap,
-) -> Result<Json<RemoveMemberResponse>, (StatusCode, Json<ErrorResponse>)> {
-    let actor_id = parse_bearer_user_id(&headers)?;
-
+    AuthUser(actor_id): AuthUser,
+) -> Result<Json<RemoveMemberResponse>, ApiError> {
     let _guard = state.file_lock.lock().await;
-    let mut projects = read_projects(&state.projects_file).await.map_err(|_| {
-        api_error(StatusCode::INTERNAL_SERVER_ERROR, "Ошибка удаления участника.")
-    })?;
-    let project = projects
+    let mut projects = read_projects(&state.projects_file)
+        .await
+        .map_err(|_| ApiError::MemberRemoveFailed)?;
+    let project = projects
         .iter_mut()
         .find(|p| p.id == project_id)
-        .ok_or_else(|| api_error(StatusCode::NOT_FOUND, "Проект не найден."))?;
+        .ok_or(ApiError::ProjectNotFound)?;
 
-    let actor_role = membership_role(project, &actor_id)
-        .ok_or_else(|| api_error(StatusCode::FORBIDDEN, "Только владелец может управлять доступом."))?;
-    if actor_role != "owner" {
-        return Err(api_error(
-            StatusCode::FORBIDDEN,
-            "Только владелец может управлять доступом.",
-        ));
-    }
+    permissions::check(project, &actor_id, Capability::ProjectManage)?;
     if target_user_id == project.owner_id {
-        return Err(api_error(
-            StatusCode::BAD_REQUEST,
-            "Нельзя удалить владельца из проекта.",
-        ));
+        return Err(ApiError::OwnerNotRemovable);
     }
     let before = project.members.len();
     project.members.retain(|m| m.user_id != target_user_id);
     if project.members.len() == before {
-        return Err(api_error(StatusCode::NOT_FOUND, "Участник не найден."));
+        return Err(ApiError::MemberNotFound);
     }
 
     project.updated_at = now_iso();
     let updated_at = project.updated_at.clone();
-    write_projects(&state.projects_file, &projects)
+    write_projects(&state, &projects)
         .await
-        .map_err(|_| api_error(StatusCode::INTERNAL_SERVER_ERROR, "Ошибка удаления участника."))?;
+        .map_err(|_| ApiError::MemberRemoveFailed)?;
     Ok(Json(RemoveMemberResponse {
         ok: true,
         updated_at,
     }))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/projects/{project_id}/session",
+    tag = "projects",
+    params(("project_id" = String, Path)),
+    responses((status = 200, body = ProjectSessionResponse))
+)]
 async fn get_session(
     State(state): State<AppState>,
     Path(project_id): Path<String>,
+    AuthUser(user_id): AuthUser,
     headers: HeaderMap,
-) -> Result<Json<ProjectSessionResponse>, (StatusCode, Json<ErrorResponse>)> {
-    let user_id = parse_bearer_user_id(&headers)?;
-
-    let _guard = state.file_lock.lock().await;
-    let projects = read_projects(&state.projects_file).await.map_err(|_| {
-        api_error(
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "Ошибка загрузки сессии проекта.",
-        )
-    })?;
-    let project = projects
-        .iter()
-        .find(|p| p.id == project_id)
-        .ok_or_else(|| api_error(StatusCode::NOT_FOUND, "Проект не найден."))?;
+) -> Result<Response, ApiError> {
+    let project = match state.cache.projects.get(&project_id) {
+        Some(project) => project,
+        None => {
+            let _guard = state.file_lock.lock().await;
+            let projects = read_projects(&state.projects_file)
+                .await
+                .map_err(|_| ApiError::SessionLoadFailed)?;
+            let project = projects
+                .into_iter()
+                .find(|p| p.id == project_id)
+                .map(Arc::new)
+                .ok_or(ApiError::ProjectNotFound)?;
+            state
+                .cache
+                .projects
+                .insert(project_id.clone(), project.clone());
+            project
+        }
+    };
 
-    let mapped = map_project_for_user(project, &user_id)
-        .ok_or_else(|| api_error(StatusCode::FORBIDDEN, "Нет доступа к проекту."))?;
-    Ok(Json(ProjectSessionResponse {
-        project: mapped,
-        session: project.session.clone(),
-    }))
+    let mapped = map_project_for_user(&project, &user_id).ok_or(ApiError::NoProjectAccess)?;
+    let tag = etag::etag(project.session_version)?;
+    // Answered before the session blob is cloned and serialized.
+    if etag::none_match(&headers, &tag) {
+        return Ok(etag::not_modified(&HeaderMap::from_iter([(ETAG, tag)])));
+    }
+    Ok((
+        [(ETAG, tag)],
+        Json(ProjectSessionResponse {
+            project: mapped,
+            session: project.session.clone(),
+            version: project.session_version,
+        }),
+    )
+        .into_response())
 }
 
+/// Replaces the session. With `If-Match` the save only succeeds if the session is still at
+/// that version; otherwise 409 with `currentVersion` and the current `ETag`.
+#[utoipa::path(
+    put,
+    path = "/api/projects/{project_id}/session",
+    tag = "projects",
+    params(("project_id" = String, Path)),
+    request_body = SaveSessionRequest,
+    responses((status = 200, body = SaveSessionResponse))
+)]
 async fn save_session(
     State(state): State<AppState>,
     Path(project_id): Path<String>,
+    AuthUser(user_id): AuthUser,
     headers: HeaderMap,
     Json(payload): Json<SaveSessionRequest>,
-) -> Result<Json<SaveSessionResponse>, (StatusCode, Json<ErrorResponse>)> {
-    let user_id = parse_bearer_user_id(&headers)?;
-
+) -> Result<impl IntoResponse, VersionError> {
+    let precondition = Precondition::from_headers(&headers)?;
     let _guard = state.file_lock.lock().await;
-    let mut projects = read_projects(&state.projects_file).await.map_err(|_| {
-        api_error(
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "Ошибка сохранения сессии проекта.",
-        )
-    })?;
+    let mut projects = read_projects(&state.projects_file)
+        .await
+        .map_err(|_| ApiError::SessionSaveFailed)?;
     let project = projects
         .iter_mut()
         .find(|p| p.id == project_id)
-        .ok_or_else(|| api_error(StatusCode::NOT_FOUND, "Проект не найден."))?;
+        .ok_or(ApiError::ProjectNotFound)?;
 
-    let role = membership_role(project, &user_id)
-        .ok_or_else(|| api_error(StatusCode::FORBIDDEN, "Нет доступа к проекту."))?;
-    if !can_write_project(&role) {
-        return Err(api_error(
-            StatusCode::FORBIDDEN,
-            "У вас только режим просмотра.",
-        ));
-    }
+    permissions::check(project, &user_id, Capability::ResultEdit)?;
+    precondition.check(
+        Some(project.session_version),
+        ApiError::SessionVersionConflict,
+    )?;
 
     project.session = Some(payload.session);
+    project.session_version += 1;
     project.updated_at = now_iso();
     let updated_at = project.updated_at.clone();
-    write_projects(&state.projects_file, &projects).await.map_err(|_| {
-        api_error(
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "Ошибка сохранения сессии проекта.",
-        )
-    })?;
+    let version = project.session_version;
+    write_projects(&state, &projects)
+        .await
+        .map_err(|_| ApiError::SessionSaveFailed)?;
 
-    Ok(Json(SaveSessionResponse {
-        ok: true,
-        updated_at,
-    }))
+    Ok((
+        [(ETAG, etag::etag(version)?)],
+        Json(SaveSessionResponse {
+            ok: true,
+            updated_at,
+            version,
+        }),
+    ))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/fail-reasons",
+    tag = "fail-reasons",
+    responses((status = 200, body = FailReasonsResponse))
+)]
 async fn list_fail_reasons(
     State(state): State<AppState>,
-    headers: HeaderMap,
-) -> Result<Json<FailReasonsResponse>, (StatusCode, Json<ErrorResponse>)> {
-    let _actor_id = parse_bearer_user_id(&headers)?;
-
+    AuthUser(_actor_id): AuthUser,
+) -> Result<Json<FailReasonsResponse>, ApiError> {
     let rows = sqlx::query(
         r#"
         SELECT code, title, description
@@ -1071,7 +1948,7 @@ async fn list_fail_reasons(
     )
     .fetch_all(&state.db)
     .await
-    .map_err(|_| api_error(StatusCode::INTERNAL_SERVER_ERROR, "Не удалось загрузить причины FAIL."))?;
+    .map_err(|_| ApiError::FailReasonsLoadFailed)?;
 
     let reasons = rows
         .into_iter()
@@ -1085,40 +1962,42 @@ async fn list_fail_reasons(
     Ok(Json(FailReasonsResponse { reasons }))
 }
 
-fn parse_uuid(input: &str, err_message: &str) -> Result<Uuid, (StatusCode, Json<ErrorResponse>)> {
-    Uuid::parse_str(input).map_err(|_| api_error(StatusCode::BAD_REQUEST, err_message))
+fn parse_uuid(input: &str, err: ApiError) -> Result<Uuid, ApiError> {
+    Uuid::parse_str(input).map_err(|_| err)
 }
 
-fn parse_run_status(input: &str) -> Result<&'static str, (StatusCode, Json<ErrorResponse>)> {
+fn parse_run_status(input: &str) -> Result<&'static str, ApiError> {
     match input {
         "draft" => Ok("draft"),
         "in_progress" => Ok("in_progress"),
         "done" => Ok("done"),
         "locked" => Ok("locked"),
-        _ => Err(api_error(
-            StatusCode::BAD_REQUEST,
-            "Некорректный статус run. Ожидается draft|in_progress|done|locked.",
-        )),
+        _ => Err(ApiError::InvalidRunStatus),
     }
 }
 
-fn parse_result_status(input: &str) -> Result<&'static str, (StatusCode, Json<ErrorResponse>)> {
+/// A day; longer values are almost certainly milliseconds or a client clock bug.
+const MAX_ELAPSED_SECONDS: i32 = 24 * 60 * 60;
+
+fn parse_result_status(input: &str) -> Result<&'static str, ApiError> {
     match input {
         "ok" => Ok("ok"),
         "fail" => Ok("fail"),
         "na" => Ok("na"),
-        _ => Err(api_error(
-            StatusCode::BAD_REQUEST,
-            "Некорректный статус результата. Ожидается ok|fail|na.",
-        )),
+        "blocked" => Ok("blocked"),
+        "skipped" => Ok("skipped"),
+        "retest" => Ok("retest"),
+        _ => Err(ApiError::InvalidResultStatus),
     }
 }
 
-async fn ensure_db_user_exists(
-    state: &AppState,
-    user_id: &str,
-) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
-    let user_uuid = parse_uuid(user_id, "Некорректный идентификатор пользователя.")?;
+async fn ensure_db_user_exists(state: &AppState, user_id: &str) -> Result<(), ApiError> {
+    ensure_db_user_row(&state.db, user_id).await
+}
+
+/// Users live in `users.json`; the tables reference a placeholder row with the same id.
+async fn ensure_db_user_row(db: &PgPool, user_id: &str) -> Result<(), ApiError> {
+    let user_uuid = parse_uuid(user_id, ApiError::InvalidUserId)?;
     let fallback_email = format!("{}@local.invalid", user_uuid);
     let fallback_name = format!("User-{}", &user_id[..8.min(user_id.len())]);
 
@@ -1132,80 +2011,106 @@ async fn ensure_db_user_exists(
     .bind(user_uuid)
     .bind(fallback_email)
     .bind(fallback_name)
-    .execute(&state.db)
+    .execute(db)
     .await
-    .map_err(|_| {
-        api_error(
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "Не удалось синхронизировать пользователя в БД.",
-        )
-    })?;
+    .map_err(|_| ApiError::UserSyncFailed)?;
 
     Ok(())
 }
 
-async fn fetch_run_view(
-    db: &PgPool,
-    run_id: Uuid,
-) -> Result<Option<RunView>, (StatusCode, Json<ErrorResponse>)> {
-    let row = sqlx::query(
+async fn fetch_run_view(db: &PgPool, run_id: Uuid) -> Result<Option<RunView>, ApiError> {
+    sqlx::query_as!(
+        RunView,
         r#"
         SELECT
-          id::text AS id,
-          project_id::text AS project_id,
+          id::text AS "id!",
+          project_id::text AS "project_id!",
           asset_id::text AS asset_id,
+          asset_version,
           template_id::text AS template_id,
           title,
-          status::text AS status,
-          executed_by_user_id::text AS executed_by_user_id,
-          started_at::text AS started_at,
-          finished_at::text AS finished_at,
-          locked_at::text AS locked_at,
-          created_at::text AS created_at,
-          updated_at::text AS updated_at
+          milestone,
+          description,
+          status::text AS "status!",
+          executed_by_user_id::text AS "executed_by_user_id!",
+          default_assignee_user_id::text AS default_assignee_user_id,
+          started_at,
+          finished_at,
+          locked_at,
+          custom_fields,
+          commit_sha,
+          created_at,
+          updated_at
         FROM runs
         WHERE id = $1
         "#,
+        run_id,
     )
-    .bind(run_id)
     .fetch_optional(db)
     .await
-    .map_err(|_| api_error(StatusCode::INTERNAL_SERVER_ERROR, "Ошибка чтения run из БД."))?;
-
-    Ok(row.map(|r| RunView {
-        id: r.get::<String, _>("id"),
-        project_id: r.get::<String, _>("project_id"),
-        asset_id: r.get::<Option<String>, _>("asset_id"),
-        template_id: r.get::<Option<String>, _>("template_id"),
-        title: r.get::<String, _>("title"),
-        status: r.get::<String, _>("status"),
-        executed_by_user_id: r.get::<String, _>("executed_by_user_id"),
-        started_at: r.get::<Option<String>, _>("started_at"),
-        finished_at: r.get::<Option<String>, _>("finished_at"),
-        locked_at: r.get::<Option<String>, _>("locked_at"),
-        created_at: r.get::<String, _>("created_at"),
-        updated_at: r.get::<String, _>("updated_at"),
-    }))
+    .map_err(|_| ApiError::RunDbReadFailed)
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v2/runs",
+    tag = "runs",
+    request_body = CreateRunRequest,
+    responses((status = 201, body = CreateRunResponse))
+)]
 async fn create_run_v2(
     State(state): State<AppState>,
-    headers: HeaderMap,
+    AuthUser(actor_id): AuthUser,
     Json(payload): Json<CreateRunRequest>,
-) -> Result<(StatusCode, Json<CreateRunResponse>), (StatusCode, Json<ErrorResponse>)> {
-    let actor_id = parse_bearer_user_id(&headers)?;
+) -> Result<(StatusCode, Json<CreateRunResponse>), ApiError> {
     ensure_db_user_exists(&state, &actor_id).await?;
 
-    let project_id = parse_uuid(&payload.project_id, "Некорректный project_id.")?;
+    let project_id = parse_uuid(&payload.project_id, ApiError::InvalidProjectId)?;
     let asset_id = match payload.asset_id.as_deref() {
-        Some(v) if !v.trim().is_empty() => Some(parse_uuid(v, "Некорректный asset_id.")?),
+        Some(v) if !v.trim().is_empty() => Some(parse_uuid(v, ApiError::InvalidAssetId)?),
         _ => None,
     };
     let template_id = match payload.template_id.as_deref() {
-        Some(v) if !v.trim().is_empty() => Some(parse_uuid(v, "Некорректный template_id.")?),
+        Some(v) if !v.trim().is_empty() => Some(parse_uuid(v, ApiError::InvalidTemplateId)?),
+        _ => None,
+    };
+    let suite_id = match payload.suite_id.as_deref() {
+        Some(v) if !v.trim().is_empty() => Some(parse_uuid(v, ApiError::InvalidSuiteId)?),
+        _ => None,
+    };
+    let tag_query = match payload.tag_query.as_deref() {
+        Some(v) if !v.trim().is_empty() => Some(tags::TagQuery::parse(v)?),
         _ => None,
     };
-    let actor_uuid = parse_uuid(&actor_id, "Некорректный идентификатор пользователя.")?;
+    let commit_sha = ci::parse_commit_sha(payload.commit_sha.as_deref())?;
+    let actor_uuid = parse_uuid(&actor_id, ApiError::InvalidUserId)?;
+    authz::require_capability(
+        &state,
+        &project_id.to_string(),
+        &actor_id,
+        Capability::RunCreate,
+    )
+    .await?;
+    if let Some(suite_id) = suite_id {
+        suites::ensure_suite_in_project(&state, suite_id, project_id).await?;
+    }
+    if let Some(asset_id) = asset_id {
+        assets::ensure_active_asset_in_project(&state, asset_id, project_id).await?;
+    }
+    let field_definitions =
+        custom_fields::definitions(&state.db, project_id, custom_fields::Entity::Run).await?;
+    let field_values = custom_fields::apply_values(
+        &field_definitions,
+        serde_json::Map::new(),
+        payload.custom_fields.unwrap_or_default(),
+    )?;
+    let template_id = match template_id {
+        Some(id) => Some(id),
+        None => load_project_settings(&state, &project_id.to_string())
+            .await?
+            .default_run_template_id
+            .and_then(|id| Uuid::parse_str(&id).ok()),
+    };
     let title = payload
         .title
         .as_deref()
@@ -1213,13 +2118,21 @@ async fn create_run_v2(
         .filter(|t| !t.is_empty())
         .unwrap_or("New run")
         .to_string();
+    let milestone = parse_run_milestone(payload.milestone.as_deref())?;
+    let description = parse_run_description(payload.description.as_deref())?;
 
+    let mut tx = state
+        .db
+        .begin()
+        .await
+        .map_err(|_| ApiError::RunCreateFailed)?;
     let run_id: Uuid = sqlx::query_scalar(
         r#"
         INSERT INTO runs (
-          project_id, asset_id, template_id, title, status, executed_by_user_id
+          project_id, asset_id, template_id, title, status, executed_by_user_id, custom_fields,
+          commit_sha, milestone, description
         )
-        VALUES ($1, $2, $3, $4, 'draft', $5)
+        VALUES ($1, $2, $3, $4, 'draft', $5, $6, $7, $8, $9)
         RETURNING id
         "#,
     )
@@ -1228,107 +2141,404 @@ async fn create_run_v2(
     .bind(template_id)
     .bind(title)
     .bind(actor_uuid)
-    .fetch_one(&state.db)
+    .bind(Value::Object(field_values))
+    .bind(&commit_sha)
+    .bind(&milestone)
+    .bind(&description)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|err| db_errors::map(err, ApiError::RunCreateRejected))?;
+
+    if let Some(query) = &tag_query {
+        let added = tags::expand_tag_query_into_run(
+            &mut tx, run_id, project_id, suite_id, query, actor_uuid,
+        )
+        .await
+        .map_err(|_| ApiError::RunSuiteItemsFailed)?;
+        if added == 0 {
+            return Err(ApiError::TagQueryNoMatches);
+        }
+    } else if let Some(suite_id) = suite_id {
+        suites::expand_suite_into_run(&mut tx, run_id, suite_id, actor_uuid)
+            .await
+            .map_err(|_| ApiError::RunSuiteItemsFailed)?;
+    }
+
+    tx.commit().await.map_err(|_| ApiError::RunCreateFailed)?;
+
+    let run = fetch_run_view(&state.db, run_id)
+        .await?
+        .ok_or(ApiError::RunCreatedNotFound)?;
+
+    webhooks::emit(&state, project_id, "run.created", json!({ "run": &run })).await;
+    if run.commit_sha.is_some() {
+        ci::run_created(&state, project_id, run_id);
+    }
+
+    Ok((StatusCode::CREATED, Json(CreateRunResponse { run })))
+}
+
+/// Re-run: a fresh draft run in the same project with the source's items, positions and
+/// assignees. `onlyFailed=true` keeps only items whose result is `fail`; items of archived
+/// test cases are left out.
+#[utoipa::path(
+    post,
+    path = "/api/v2/runs/{run_id}/clone",
+    tag = "runs",
+    params(("run_id" = String, Path), CloneRunQuery),
+    responses((status = 201, body = CreateRunResponse))
+)]
+async fn clone_run_v2(
+    State(state): State<AppState>,
+    Path(run_id): Path<String>,
+    AuthUser(actor_id): AuthUser,
+    Query(query): Query<CloneRunQuery>,
+) -> Result<(StatusCode, Json<CreateRunResponse>), ApiError> {
+    let source_uuid = parse_uuid(&run_id, ApiError::InvalidRunId)?;
+    let only_failed = query.only_failed.unwrap_or(false);
+    authz::require_run_capability(&state, source_uuid, &actor_id, Capability::RunCreate).await?;
+    ensure_db_user_exists(&state, &actor_id).await?;
+    let actor_uuid = parse_uuid(&actor_id, ApiError::InvalidUserId)?;
+
+    let source = fetch_run_view(&state.db, source_uuid)
+        .await?
+        .ok_or(ApiError::RunNotFound)?;
+    let project_id = parse_uuid(&source.project_id, ApiError::InvalidProjectId)?;
+    let title = if only_failed {
+        format!("{} — повтор FAIL", source.title)
+    } else {
+        format!("{} — повтор", source.title)
+    };
+
+    let clone_failed = |_| ApiError::RunCloneFailed;
+    let mut tx = state.db.begin().await.map_err(clone_failed)?;
+    let run_id: Uuid = sqlx::query_scalar(
+        r#"
+        INSERT INTO runs (
+          project_id, asset_id, template_id, title, status, executed_by_user_id,
+          default_assignee_user_id, custom_fields, commit_sha, milestone, description
+        )
+        SELECT project_id, asset_id, template_id, $2, 'draft', $3, default_assignee_user_id,
+               custom_fields, commit_sha, milestone, description
+        FROM runs
+        WHERE id = $1
+        RETURNING id
+        "#,
+    )
+    .bind(source_uuid)
+    .bind(&title)
+    .bind(actor_uuid)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(clone_failed)?;
+    let copied = sqlx::query(
+        r#"
+        WITH inserted AS (
+          INSERT INTO run_items (run_id, testcase_version_id, position, is_required, assignee_user_id)
+          SELECT
+            $2,
+            ri.testcase_version_id,
+            (ROW_NUMBER() OVER (ORDER BY ri.position ASC, ri.created_at ASC) - 1)::int,
+            ri.is_required,
+            ri.assignee_user_id
+          FROM run_items ri
+          JOIN testcase_versions tv ON tv.id = ri.testcase_version_id
+          JOIN testcases tc ON tc.id = tv.testcase_id
+          LEFT JOIN run_results rr ON rr.run_item_id = ri.id
+          WHERE ri.run_id = $1 AND tc.is_archived = FALSE AND (NOT $3 OR rr.status = 'fail')
+          RETURNING id
+        )
+        INSERT INTO run_results (run_item_id, status, comment, updated_by_user_id)
+        SELECT id, 'na', '', $4 FROM inserted
+        "#,
+    )
+    .bind(source_uuid)
+    .bind(run_id)
+    .bind(only_failed)
+    .bind(actor_uuid)
+    .execute(&mut *tx)
+    .await
+    .map_err(clone_failed)?
+    .rows_affected();
+    if copied == 0 {
+        return Err(if only_failed {
+            ApiError::NoFailedItemsToClone
+        } else {
+            ApiError::NoItemsToClone
+        });
+    }
+    // Dependencies between copied items follow them; items match by testcase version,
+    // which is unique within a run.
+    sqlx::query(
+        r#"
+        INSERT INTO run_item_dependencies (
+          run_id, run_item_id, depends_on_run_item_id, created_by_user_id
+        )
+        SELECT $2, ni.id, nd.id, $3
+        FROM run_item_dependencies d
+        JOIN run_items si ON si.id = d.run_item_id
+        JOIN run_items sd ON sd.id = d.depends_on_run_item_id
+        JOIN run_items ni ON ni.run_id = $2 AND ni.testcase_version_id = si.testcase_version_id
+        JOIN run_items nd ON nd.run_id = $2 AND nd.testcase_version_id = sd.testcase_version_id
+        WHERE d.run_id = $1
+        "#,
+    )
+    .bind(source_uuid)
+    .bind(run_id)
+    .bind(actor_uuid)
+    .execute(&mut *tx)
     .await
-    .map_err(|_| api_error(StatusCode::BAD_REQUEST, "Не удалось создать run. Проверь проект/asset/template."))?;
+    .map_err(clone_failed)?;
+    tx.commit().await.map_err(clone_failed)?;
 
     let run = fetch_run_view(&state.db, run_id)
         .await?
-        .ok_or_else(|| api_error(StatusCode::INTERNAL_SERVER_ERROR, "Run создан, но не найден."))?;
+        .ok_or(ApiError::RunCreatedNotFound)?;
+
+    webhooks::emit(
+        &state,
+        project_id,
+        "run.created",
+        json!({ "run": &run, "clonedFromRunId": &source.id }),
+    )
+    .await;
+    if run.commit_sha.is_some() {
+        ci::run_created(&state, project_id, run_id);
+    }
 
     Ok((StatusCode::CREATED, Json(CreateRunResponse { run })))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v2/runs",
+    tag = "runs",
+    params(ListRunsQuery),
+    responses((status = 200, body = ListRunsResponse))
+)]
 async fn list_runs_v2(
     State(state): State<AppState>,
-    headers: HeaderMap,
+    AuthUser(actor_id): AuthUser,
     Query(query): Query<ListRunsQuery>,
-) -> Result<Json<ListRunsResponse>, (StatusCode, Json<ErrorResponse>)> {
-    let _actor_id = parse_bearer_user_id(&headers)?;
-    let project_id = match query.project_id.as_deref() {
-        Some(v) if !v.trim().is_empty() => Some(parse_uuid(v, "Некорректный project_id.")?),
+) -> Result<Json<ListRunsResponse>, ApiError> {
+    let query = match query.filter_id.as_deref() {
+        Some(v) if !v.trim().is_empty() => {
+            let saved =
+                saved_filters::load(&state, v, &actor_id, saved_filters::Target::Runs).await?;
+            if let Some(p) = query.project_id.as_deref().filter(|p| !p.trim().is_empty()) {
+                if parse_uuid(p, ApiError::InvalidProjectId)? != saved.project_id {
+                    return Err(ApiError::SavedFilterProjectMismatch);
+                }
+            }
+            ListRunsQuery {
+                project_id: Some(saved.project_id.to_string()),
+                status: query.status.or_else(|| saved.param("status")),
+                asset_id: query.asset_id.or_else(|| saved.param("assetId")),
+                asset_version: query.asset_version.or_else(|| saved.param("assetVersion")),
+                executed_by_user_id: query
+                    .executed_by_user_id
+                    .or_else(|| saved.param("executedByUserId")),
+                custom_fields: query.custom_fields.or_else(|| saved.param("customFields")),
+                ..query
+            }
+        }
+        _ => query,
+    };
+    let project_ids = match query.project_id.as_deref() {
+        Some(v) if !v.trim().is_empty() => {
+            let project_id = parse_uuid(v, ApiError::InvalidProjectId)?;
+            authz::require_capability(
+                &state,
+                &project_id.to_string(),
+                &actor_id,
+                Capability::ProjectRead,
+            )
+            .await?;
+            vec![project_id]
+        }
+        _ => authz::member_project_ids(&state, &actor_id).await?,
+    };
+    let field_filter = match query.custom_fields.as_deref() {
+        Some(raw) if !raw.trim().is_empty() => {
+            // Definitions are per project, so the filter only applies to one.
+            let project_id = match (query.project_id.as_deref(), &project_ids[..]) {
+                (Some(_), [project_id]) => *project_id,
+                _ => return Err(ApiError::CustomFieldFilterNeedsProject),
+            };
+            let definitions =
+                custom_fields::definitions(&state.db, project_id, custom_fields::Entity::Run)
+                    .await?;
+            Some(custom_fields::parse_filter(&definitions, raw)?)
+        }
         _ => None,
     };
     let status = match query.status.as_deref() {
         Some(v) => Some(parse_run_status(v)?.to_string()),
         None => None,
     };
-    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+    let asset_id = match query.asset_id.as_deref() {
+        Some(v) if !v.trim().is_empty() => Some(parse_uuid(v, ApiError::InvalidAssetId)?),
+        _ => None,
+    };
+    let asset_version = query
+        .asset_version
+        .as_deref()
+        .map(str::trim)
+        .filter(|v| !v.is_empty());
+    let executed_by = match query.executed_by_user_id.as_deref() {
+        Some(v) if !v.trim().is_empty() => {
+            Some(parse_uuid(v.trim(), ApiError::InvalidExecutorUserId)?)
+        }
+        _ => None,
+    };
+    let limit = pagination::page_limit(query.limit);
+    let cursor = pagination::parse_cursor(query.cursor.as_deref())?;
 
-    let rows = sqlx::query(
+    let runs = sqlx::query_as!(
+        RunView,
         r#"
         SELECT
-          id::text AS id,
-          project_id::text AS project_id,
+          id::text AS "id!",
+          project_id::text AS "project_id!",
           asset_id::text AS asset_id,
+          asset_version,
           template_id::text AS template_id,
           title,
-          status::text AS status,
-          executed_by_user_id::text AS executed_by_user_id,
-          started_at::text AS started_at,
-          finished_at::text AS finished_at,
-          locked_at::text AS locked_at,
-          created_at::text AS created_at,
-          updated_at::text AS updated_at
+          milestone,
+          description,
+          status::text AS "status!",
+          executed_by_user_id::text AS "executed_by_user_id!",
+          default_assignee_user_id::text AS default_assignee_user_id,
+          started_at,
+          finished_at,
+          locked_at,
+          custom_fields,
+          commit_sha,
+          created_at,
+          updated_at
         FROM runs
-        WHERE ($1::uuid IS NULL OR project_id = $1)
-          AND ($2::run_status IS NULL OR status = $2)
-        ORDER BY created_at DESC
-        LIMIT $3
+        WHERE project_id = ANY($1)
+          AND ($2::text IS NULL OR status::text = $2)
+          AND ($3::timestamptz IS NULL OR (created_at, id) < ($3, $4))
+          AND ($6::jsonb IS NULL OR custom_fields @> $6)
+          AND ($7::uuid IS NULL OR asset_id = $7)
+          AND ($8::text IS NULL OR asset_version = $8)
+          AND ($9::uuid IS NULL OR executed_by_user_id = $9)
+        ORDER BY created_at DESC, id DESC
+        LIMIT $5
         "#,
+        &project_ids,
+        status,
+        cursor.as_ref().map(pagination::Cursor::timestamp),
+        cursor.as_ref().map(pagination::Cursor::uuid),
+        limit + 1,
+        field_filter,
+        asset_id,
+        asset_version,
+        executed_by,
     )
-    .bind(project_id)
-    .bind(status)
-    .bind(limit)
-    .fetch_all(&state.db)
+    .fetch_all(state.read_db())
     .await
-    .map_err(|_| api_error(StatusCode::INTERNAL_SERVER_ERROR, "Ошибка чтения списка runs."))?;
+    .map_err(|_| ApiError::RunsListFailed)?;
 
-    let runs = rows
-        .into_iter()
-        .map(|r| RunView {
-            id: r.get::<String, _>("id"),
-            project_id: r.get::<String, _>("project_id"),
-            asset_id: r.get::<Option<String>, _>("asset_id"),
-            template_id: r.get::<Option<String>, _>("template_id"),
-            title: r.get::<String, _>("title"),
-            status: r.get::<String, _>("status"),
-            executed_by_user_id: r.get::<String, _>("executed_by_user_id"),
-            started_at: r.get::<Option<String>, _>("started_at"),
-            finished_at: r.get::<Option<String>, _>("finished_at"),
-            locked_at: r.get::<Option<String>, _>("locked_at"),
-            created_at: r.get::<String, _>("created_at"),
-            updated_at: r.get::<String, _>("updated_at"),
-        })
-        .collect();
+    let (runs, next_cursor) =
+        pagination::finish_page(runs, limit, |r: &RunView| pagination::Cursor {
+            created_at: r.created_at.to_rfc3339(),
+            id: r.id.clone(),
+        });
+
+    Ok(Json(ListRunsResponse { runs, next_cursor }))
+}
 
-    Ok(Json(ListRunsResponse { runs }))
+#[derive(Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+struct RunDetailsQuery {
+    /// `suite|tag|assignee` — also return `groups`, the items split into sections.
+    group_by: Option<String>,
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v2/runs/{run_id}",
+    tag = "runs",
+    params(("run_id" = String, Path), RunDetailsQuery),
+    responses((status = 200, body = RunDetailsResponse))
+)]
 async fn get_run_details_v2(
     State(state): State<AppState>,
     Path(run_id): Path<String>,
-    headers: HeaderMap,
-) -> Result<Json<RunDetailsResponse>, (StatusCode, Json<ErrorResponse>)> {
-    let _actor_id = parse_bearer_user_id(&headers)?;
-    let run_uuid = parse_uuid(&run_id, "Некорректный run_id.")?;
+    AuthUser(actor_id): AuthUser,
+    Query(query): Query<RunDetailsQuery>,
+) -> Result<Response, ApiError> {
+    let run_uuid = parse_uuid(&run_id, ApiError::InvalidRunId)?;
+    let group_by = run_groups::GroupBy::parse(query.group_by.as_deref())?;
+    authz::require_run_capability(&state, run_uuid, &actor_id, Capability::ProjectRead).await?;
+
+    let json_body = |body: Bytes| ([(CONTENT_TYPE, "application/json")], body).into_response();
+    if let Some(body) = state.cache.run_details.get(&(run_uuid, group_by)) {
+        return Ok(json_body(body));
+    }
+    let epoch = state.cache.run_details_epoch(run_uuid);
 
     let run = fetch_run_view(&state.db, run_uuid)
         .await?
-        .ok_or_else(|| api_error(StatusCode::NOT_FOUND, "Run не найден."))?;
+        .ok_or(ApiError::RunNotFound)?;
 
     let rows = sqlx::query(
         r#"
         SELECT
           ri.id::text AS id,
           ri.testcase_version_id::text AS testcase_version_id,
+          tc.id::text AS testcase_id,
+          tc.key AS testcase_key,
+          tc.title AS testcase_title,
+          tv.version_number,
+          tc.suite_id::text AS suite_id,
+          ARRAY(
+            SELECT t.name::text
+            FROM testcase_tags tt
+            JOIN tags t ON t.id = tt.tag_id
+            WHERE tt.testcase_id = tc.id
+            ORDER BY lower(t.name::text)
+          ) AS tags,
           ri.position AS position,
           ri.is_required AS is_required,
+          COALESCE(ri.assignee_user_id, r.default_assignee_user_id)::text AS assignee_user_id,
+          au.display_name AS assignee_display_name,
+          ri.assignee_user_id IS NULL AND r.default_assignee_user_id IS NOT NULL
+            AS assignee_inherited,
           COALESCE(rr.status::text, 'na') AS status,
           rr.fail_reason_code AS fail_reason_code,
           COALESCE(rr.comment, '') AS comment,
-          rr.updated_at::text AS updated_at
+          rr.updated_at::text AS updated_at,
+          rr.version::bigint AS result_version,
+          rr.elapsed_seconds,
+          rr.executed_at::text AS executed_at,
+          ARRAY(
+            SELECT d.depends_on_run_item_id::text
+            FROM run_item_dependencies d
+            JOIN run_items dri ON dri.id = d.depends_on_run_item_id
+            WHERE d.run_item_id = ri.id
+            ORDER BY dri.position ASC, dri.created_at ASC
+          ) AS depends_on,
+          ARRAY(
+            SELECT d.depends_on_run_item_id::text
+            FROM run_item_dependencies d
+            JOIN run_items dri ON dri.id = d.depends_on_run_item_id
+            LEFT JOIN run_results drr ON drr.run_item_id = d.depends_on_run_item_id
+            WHERE d.run_item_id = ri.id AND drr.status IS DISTINCT FROM 'ok'
+            ORDER BY dri.position ASC, dri.created_at ASC
+          ) AS blocked_by,
+          (
+            SELECT COUNT(*) FROM comments c
+            WHERE c.run_item_id = ri.id AND c.deleted_at IS NULL
+          ) AS comment_count
         FROM run_items ri
+        JOIN runs r ON r.id = ri.run_id
+        JOIN testcase_versions tv ON tv.id = ri.testcase_version_id
+        JOIN testcases tc ON tc.id = tv.testcase_id
+        LEFT JOIN users au ON au.id = COALESCE(ri.assignee_user_id, r.default_assignee_user_id)
         LEFT JOIN run_results rr ON rr.run_item_id = ri.id
         WHERE ri.run_id = $1
         ORDER BY ri.position ASC, ri.created_at ASC
@@ -1337,243 +2547,882 @@ async fn get_run_details_v2(
     .bind(run_uuid)
     .fetch_all(&state.db)
     .await
-    .map_err(|_| api_error(StatusCode::INTERNAL_SERVER_ERROR, "Ошибка чтения run items."))?;
+    .map_err(|_| ApiError::RunItemsReadFailed)?;
+    let suite_paths = run_groups::fetch_suite_paths(&state.db, run_uuid).await?;
+    let mut defects_by_item = defects::fetch_run_defects(&state.db, run_uuid).await?;
+    let mut steps_by_item = run_repo::fetch_run_steps(&state.db, run_uuid).await?;
 
-    let items = rows
+    let items: Vec<RunItemView> = rows
         .into_iter()
-        .map(|r| RunItemView {
-            id: r.get::<String, _>("id"),
-            testcase_version_id: r.get::<String, _>("testcase_version_id"),
-            position: r.get::<i32, _>("position"),
-            is_required: r.get::<bool, _>("is_required"),
-            status: r.get::<String, _>("status"),
-            fail_reason_code: r.get::<Option<String>, _>("fail_reason_code"),
-            comment: r.get::<String, _>("comment"),
-            updated_at: r.get::<Option<String>, _>("updated_at"),
+        .map(|r| {
+            let id = r.get::<String, _>("id");
+            let defects = defects_by_item.remove(&id).unwrap_or_default();
+            let steps = steps_by_item.remove(&id).unwrap_or_default();
+            let suite_id = r.get::<String, _>("suite_id");
+            let suite_path = suite_paths
+                .get(&suite_id)
+                .map(|p| p.names.clone())
+                .unwrap_or_default();
+            RunItemView {
+                id,
+                testcase_version_id: r.get::<String, _>("testcase_version_id"),
+                testcase_id: r.get::<String, _>("testcase_id"),
+                testcase_key: r.get::<String, _>("testcase_key"),
+                testcase_title: r.get::<String, _>("testcase_title"),
+                version_number: r.get::<i32, _>("version_number"),
+                suite_id,
+                suite_path,
+                tags: r.get::<Vec<String>, _>("tags"),
+                position: r.get::<i32, _>("position"),
+                is_required: r.get::<bool, _>("is_required"),
+                assignee_user_id: r.get::<Option<String>, _>("assignee_user_id"),
+                assignee_display_name: r.get::<Option<String>, _>("assignee_display_name"),
+                assignee_inherited: r.get::<bool, _>("assignee_inherited"),
+                status: r.get::<String, _>("status"),
+                fail_reason_code: r.get::<Option<String>, _>("fail_reason_code"),
+                comment: r.get::<String, _>("comment"),
+                updated_at: r.get::<Option<String>, _>("updated_at"),
+                result_version: r.get::<Option<i64>, _>("result_version"),
+                elapsed_seconds: r.get::<Option<i32>, _>("elapsed_seconds"),
+                executed_at: r.get::<Option<String>, _>("executed_at"),
+                depends_on: r.get::<Vec<String>, _>("depends_on"),
+                blocked_by: r.get::<Vec<String>, _>("blocked_by"),
+                defects,
+                comment_count: r.get::<i64, _>("comment_count"),
+                steps,
+            }
         })
         .collect();
+    let comment_count = comments::count_run_comments(&state.db, run_uuid).await?;
+    let groups = group_by.map(|by| run_groups::group(by, &items, &suite_paths));
+
+    let body = serde_json::to_vec(&RunDetailsResponse {
+        run,
+        items,
+        groups,
+        comment_count,
+    })
+    .map(Bytes::from)
+    .map_err(|_| ApiError::RunItemsReadFailed)?;
+    state
+        .cache
+        .store_run_details(run_uuid, group_by, epoch, body.clone());
+    Ok(json_body(body))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v2/runs/{run_id}/summary",
+    tag = "runs",
+    params(("run_id" = String, Path)),
+    responses((status = 200, body = RunSummaryResponse))
+)]
+async fn get_run_summary_v2(
+    State(state): State<AppState>,
+    Path(run_id): Path<String>,
+    AuthUser(actor_id): AuthUser,
+) -> Result<Json<RunSummaryResponse>, ApiError> {
+    let run_uuid = parse_uuid(&run_id, ApiError::InvalidRunId)?;
+    authz::require_run_capability(&state, run_uuid, &actor_id, Capability::ProjectRead).await?;
+
+    let row = sqlx::query(
+        r#"
+        SELECT
+          COUNT(ri.id) AS total,
+          COUNT(*) FILTER (WHERE rr.status = 'ok') AS ok_count,
+          COUNT(*) FILTER (WHERE rr.status = 'fail') AS fail_count,
+          COUNT(*) FILTER (WHERE rr.status = 'na') AS na_count,
+          COUNT(*) FILTER (WHERE rr.status = 'blocked') AS blocked_count,
+          COUNT(*) FILTER (WHERE rr.status = 'skipped') AS skipped_count,
+          COUNT(*) FILTER (WHERE rr.status = 'retest') AS retest_count,
+          COUNT(ri.id) FILTER (WHERE rr.run_item_id IS NULL) AS untested_count,
+          COUNT(*) FILTER (WHERE ri.is_required AND rr.status = 'fail') AS required_failing,
+          EXTRACT(EPOCH FROM (COALESCE(r.finished_at, NOW()) - r.started_at))::bigint AS elapsed_seconds
+        FROM runs r
+        LEFT JOIN run_items ri ON ri.run_id = r.id
+        LEFT JOIN run_results rr ON rr.run_item_id = ri.id
+        WHERE r.id = $1
+        GROUP BY r.id
+        "#,
+    )
+    .bind(run_uuid)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|_| ApiError::RunSummaryFailed)?
+    .ok_or(ApiError::RunNotFound)?;
+
+    let total = row.get::<i64, _>("total");
+    let untested = row.get::<i64, _>("untested_count");
+    let percent_complete = if total == 0 {
+        0.0
+    } else {
+        (((total - untested) as f64 / total as f64) * 1000.0).round() / 10.0
+    };
 
-    Ok(Json(RunDetailsResponse { run, items }))
+    Ok(Json(RunSummaryResponse {
+        summary: RunSummaryView {
+            run_id: run_uuid.to_string(),
+            total,
+            ok: row.get::<i64, _>("ok_count"),
+            fail: row.get::<i64, _>("fail_count"),
+            na: row.get::<i64, _>("na_count"),
+            blocked: row.get::<i64, _>("blocked_count"),
+            skipped: row.get::<i64, _>("skipped_count"),
+            retest: row.get::<i64, _>("retest_count"),
+            untested,
+            percent_complete,
+            required_failing: row.get::<i64, _>("required_failing"),
+            elapsed_seconds: row.get::<Option<i64>, _>("elapsed_seconds"),
+        },
+    }))
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v2/runs/{run_id}/items",
+    tag = "runs",
+    params(("run_id" = String, Path)),
+    request_body = AddRunItemRequest,
+    responses((status = 201, description = "Создано."))
+)]
 async fn add_run_item_v2(
     State(state): State<AppState>,
     Path(run_id): Path<String>,
-    headers: HeaderMap,
+    AuthUser(actor_id): AuthUser,
     Json(payload): Json<AddRunItemRequest>,
-) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
-    let actor_id = parse_bearer_user_id(&headers)?;
+) -> Result<StatusCode, ApiError> {
     ensure_db_user_exists(&state, &actor_id).await?;
-    let run_uuid = parse_uuid(&run_id, "Некорректный run_id.")?;
+    let run_uuid = parse_uuid(&run_id, ApiError::InvalidRunId)?;
     let testcase_version_id = parse_uuid(
         &payload.testcase_version_id,
-        "Некорректный testcase_version_id.",
+        ApiError::InvalidTestcaseVersionId,
     )?;
-    let actor_uuid = parse_uuid(&actor_id, "Некорректный идентификатор пользователя.")?;
+    let actor_uuid = parse_uuid(&actor_id, ApiError::InvalidUserId)?;
     let position = payload.position.unwrap_or(0);
     let is_required = payload.is_required.unwrap_or(true);
+    authz::require_run_capability(&state, run_uuid, &actor_id, Capability::RunCompose).await?;
 
-    let run_status: Option<String> = sqlx::query_scalar(
-        r#"SELECT status::text FROM runs WHERE id = $1"#,
+    let mut run = LockedRun::begin(
+        &state.db,
+        run_uuid,
+        RunLock::Exclusive,
+        ApiError::RunItemsAddFailed,
     )
-    .bind(run_uuid)
-    .fetch_optional(&state.db)
-    .await
-    .map_err(|_| api_error(StatusCode::INTERNAL_SERVER_ERROR, "Ошибка чтения run."))?;
-    let run_status = run_status.ok_or_else(|| api_error(StatusCode::NOT_FOUND, "Run не найден."))?;
-    if run_status == "locked" {
-        return Err(api_error(
-            StatusCode::CONFLICT,
-            "Run в статусе locked, состав менять нельзя.",
-        ));
+    .await?;
+    run.ensure_unlocked(ApiError::RunLockedItems)?;
+    run_repo::insert_items(
+        &mut run,
+        &[testcase_version_id],
+        Some(position),
+        is_required,
+        actor_uuid,
+        ApiError::RunItemRejected,
+    )
+    .await?;
+    run.commit(ApiError::RunItemsAddFailed).await?;
+
+    Ok(StatusCode::CREATED)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v2/runs/{run_id}/items/bulk",
+    tag = "runs",
+    params(("run_id" = String, Path)),
+    request_body = BulkAddRunItemsRequest,
+    responses((status = 201, body = BulkAddRunItemsResponse))
+)]
+async fn bulk_add_run_items_v2(
+    State(state): State<AppState>,
+    Path(run_id): Path<String>,
+    AuthUser(actor_id): AuthUser,
+    Json(payload): Json<BulkAddRunItemsRequest>,
+) -> Result<(StatusCode, Json<BulkAddRunItemsResponse>), ApiError> {
+    ensure_db_user_exists(&state, &actor_id).await?;
+    let run_uuid = parse_uuid(&run_id, ApiError::InvalidRunId)?;
+    let actor_uuid = parse_uuid(&actor_id, ApiError::InvalidUserId)?;
+    let is_required = payload.is_required.unwrap_or(true);
+
+    if payload.testcase_version_ids.is_empty() {
+        return Err(ApiError::TestcaseVersionIdsEmpty);
+    }
+    if payload.testcase_version_ids.len() > 1000 {
+        return Err(ApiError::TooManyRunItems);
+    }
+    let mut version_ids: Vec<Uuid> = Vec::with_capacity(payload.testcase_version_ids.len());
+    for raw in &payload.testcase_version_ids {
+        let id = parse_uuid(raw, ApiError::InvalidTestcaseVersionId)?;
+        if version_ids.contains(&id) {
+            return Err(ApiError::DuplicateTestcaseVersionIds);
+        }
+        version_ids.push(id);
     }
+    authz::require_run_capability(&state, run_uuid, &actor_id, Capability::RunCompose).await?;
 
-    let run_item_id: Uuid = sqlx::query_scalar(
-        r#"
-        INSERT INTO run_items (run_id, testcase_version_id, position, is_required)
-        VALUES ($1, $2, $3, $4)
-        RETURNING id
-        "#,
+    let mut run = LockedRun::begin(
+        &state.db,
+        run_uuid,
+        RunLock::Exclusive,
+        ApiError::RunItemsAddFailed,
     )
-    .bind(run_uuid)
-    .bind(testcase_version_id)
-    .bind(position)
-    .bind(is_required)
-    .fetch_one(&state.db)
-    .await
-    .map_err(|_| {
-        api_error(
-            StatusCode::BAD_REQUEST,
-            "Не удалось добавить пункт в run (проверь testcase_version или дубликат).",
-        )
-    })?;
-
-    sqlx::query(
-        r#"
-        INSERT INTO run_results (run_item_id, status, comment, updated_by_user_id)
-        VALUES ($1, 'na', '', $2)
-        ON CONFLICT (run_item_id) DO NOTHING
-        "#,
+    .await?;
+    run.ensure_unlocked(ApiError::RunLockedItems)?;
+    let inserted = run_repo::insert_items(
+        &mut run,
+        &version_ids,
+        None,
+        is_required,
+        actor_uuid,
+        ApiError::RunItemsRejected,
     )
-    .bind(run_item_id)
-    .bind(actor_uuid)
-    .execute(&state.db)
-    .await
-    .map_err(|_| api_error(StatusCode::INTERNAL_SERVER_ERROR, "Не удалось создать run_result."))?;
+    .await?;
+    run.commit(ApiError::RunItemsAddFailed).await?;
 
-    Ok(StatusCode::CREATED)
+    let mut items: Vec<CreatedRunItemView> = inserted
+        .into_iter()
+        .map(|item| CreatedRunItemView {
+            id: item.id.to_string(),
+            testcase_version_id: item.testcase_version_id.to_string(),
+            position: item.position,
+        })
+        .collect();
+    items.sort_by_key(|i| i.position);
+
+    Ok((StatusCode::CREATED, Json(BulkAddRunItemsResponse { items })))
 }
 
-async fn update_run_result_v2(
+#[utoipa::path(
+    delete,
+    path = "/api/v2/runs/{run_id}/items/{run_item_id}",
+    tag = "runs",
+    params(("run_id" = String, Path), ("run_item_id" = String, Path)),
+    responses((status = 200, body = ReorderRunItemsResponse))
+)]
+async fn delete_run_item_v2(
     State(state): State<AppState>,
     Path((run_id, run_item_id)): Path<(String, String)>,
-    headers: HeaderMap,
-    Json(payload): Json<UpdateRunResultRequest>,
-) -> Result<Json<UpdateRunResultResponse>, (StatusCode, Json<ErrorResponse>)> {
-    let actor_id = parse_bearer_user_id(&headers)?;
-    ensure_db_user_exists(&state, &actor_id).await?;
-    let run_uuid = parse_uuid(&run_id, "Некорректный run_id.")?;
-    let run_item_uuid = parse_uuid(&run_item_id, "Некорректный run_item_id.")?;
-    let actor_uuid = parse_uuid(&actor_id, "Некорректный идентификатор пользователя.")?;
-    let status = parse_result_status(payload.status.trim())?;
-    let comment = payload.comment.unwrap_or_default();
-    let fail_reason_code = if status == "fail" {
-        payload.fail_reason_code
-    } else {
-        None
-    };
-
-    let run_status: Option<String> = sqlx::query_scalar(
-        r#"
-        SELECT r.status::text
-        FROM runs r
-        JOIN run_items ri ON ri.run_id = r.id
-        WHERE r.id = $1 AND ri.id = $2
-        "#,
+    AuthUser(actor_id): AuthUser,
+) -> Result<Json<ReorderRunItemsResponse>, ApiError> {
+    let run_uuid = parse_uuid(&run_id, ApiError::InvalidRunId)?;
+    let run_item_uuid = parse_uuid(&run_item_id, ApiError::InvalidRunItemId)?;
+    authz::require_run_capability(&state, run_uuid, &actor_id, Capability::RunCompose).await?;
+
+    let mut run = LockedRun::begin(
+        &state.db,
+        run_uuid,
+        RunLock::Exclusive,
+        ApiError::RunItemDeleteFailed,
     )
-    .bind(run_uuid)
-    .bind(run_item_uuid)
-    .fetch_optional(&state.db)
-    .await
-    .map_err(|_| api_error(StatusCode::INTERNAL_SERVER_ERROR, "Ошибка чтения run status."))?;
+    .await?;
+    run.ensure_unlocked(ApiError::RunLockedItems)?;
 
-    let run_status = run_status.ok_or_else(|| {
-        api_error(
-            StatusCode::NOT_FOUND,
-            "Run или run_item не найден для обновления результата.",
-        )
-    })?;
-    if run_status == "locked" {
-        return Err(api_error(
-            StatusCode::CONFLICT,
-            "Run в статусе locked, результаты менять нельзя.",
-        ));
+    let deleted = sqlx::query(r#"DELETE FROM run_items WHERE id = $1 AND run_id = $2"#)
+        .bind(run_item_uuid)
+        .bind(run_uuid)
+        .execute(run.conn())
+        .await
+        .map_err(|_| ApiError::RunItemDeleteFailed)?;
+    if deleted.rows_affected() == 0 {
+        return Err(ApiError::RunItemNotFound);
     }
 
-    let updated_at: String = sqlx::query_scalar(
-        r#"
-        INSERT INTO run_results (run_item_id, status, fail_reason_code, comment, updated_by_user_id, updated_at)
-        VALUES ($1, $2::result_status, $3, $4, $5, NOW())
-        ON CONFLICT (run_item_id)
-        DO UPDATE SET
-          status = EXCLUDED.status,
-          fail_reason_code = EXCLUDED.fail_reason_code,
-          comment = EXCLUDED.comment,
-          updated_by_user_id = EXCLUDED.updated_by_user_id,
-          updated_at = NOW()
-        RETURNING updated_at::text
-        "#,
-    )
-    .bind(run_item_uuid)
-    .bind(status)
-    .bind(fail_reason_code)
-    .bind(comment)
-    .bind(actor_uuid)
-    .fetch_one(&state.db)
-    .await
-    .map_err(|_| api_error(StatusCode::BAD_REQUEST, "Не удалось обновить run_result."))?;
+    run_repo::compact_positions(&mut run).await?;
+    let items = run_repo::item_positions(&mut run).await?;
+    run.commit(ApiError::RunItemDeleteFailed).await?;
 
-    Ok(Json(UpdateRunResultResponse {
-        ok: true,
-        updated_at,
-    }))
+    Ok(Json(ReorderRunItemsResponse { items }))
 }
 
-async fn validate_run_dod_for_close(
-    state: &AppState,
-    run_uuid: Uuid,
-) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
-    let l0_count: i64 = sqlx::query_scalar(
-        r#"
-        SELECT COUNT(*)
-        FROM run_items ri
-        JOIN testcase_versions tv ON tv.id = ri.testcase_version_id
-        JOIN testcases tc ON tc.id = tv.testcase_id
-        JOIN testcase_tags tt ON tt.testcase_id = tc.id
-        JOIN tags t ON t.id = tt.tag_id
-        WHERE ri.run_id = $1
-          AND lower(t.name::text) = 'l0'
-        "#,
-    )
-    .bind(run_uuid)
-    .fetch_one(&state.db)
-    .await
-    .map_err(|_| api_error(StatusCode::INTERNAL_SERVER_ERROR, "Ошибка проверки L0 покрытия."))?;
+#[utoipa::path(
+    patch,
+    path = "/api/v2/runs/{run_id}/items/reorder",
+    tag = "runs",
+    params(("run_id" = String, Path)),
+    request_body = ReorderRunItemsRequest,
+    responses((status = 200, body = ReorderRunItemsResponse))
+)]
+async fn reorder_run_items_v2(
+    State(state): State<AppState>,
+    Path(run_id): Path<String>,
+    AuthUser(actor_id): AuthUser,
+    Json(payload): Json<ReorderRunItemsRequest>,
+) -> Result<Json<ReorderRunItemsResponse>, ApiError> {
+    let run_uuid = parse_uuid(&run_id, ApiError::InvalidRunId)?;
 
-    if l0_count == 0 {
-        return Err(api_error(
-            StatusCode::CONFLICT,
-            "Run нельзя закрыть: отсутствуют L0 тесты в составе прогона.",
-        ));
+    if payload.items.is_empty() {
+        return Err(ApiError::ReorderItemsEmpty);
+    }
+    let mut ids: Vec<Uuid> = Vec::with_capacity(payload.items.len());
+    let mut positions: Vec<i32> = Vec::with_capacity(payload.items.len());
+    for item in &payload.items {
+        let id = parse_uuid(&item.id, ApiError::InvalidRunItemId)?;
+        if ids.contains(&id) {
+            return Err(ApiError::DuplicateReorderItems);
+        }
+        if item.position < 0 {
+            return Err(ApiError::NegativePosition);
+        }
+        ids.push(id);
+        positions.push(item.position);
     }
+    authz::require_run_capability(&state, run_uuid, &actor_id, Capability::RunCompose).await?;
+
+    let mut run = LockedRun::begin(
+        &state.db,
+        run_uuid,
+        RunLock::Exclusive,
+        ApiError::RunItemsReorderFailed,
+    )
+    .await?;
+    run.ensure_unlocked(ApiError::RunLockedItems)?;
 
-    let unresolved_l0_count: i64 = sqlx::query_scalar(
+    let updated = sqlx::query(
         r#"
-        SELECT COUNT(*)
-        FROM run_items ri
-        JOIN testcase_versions tv ON tv.id = ri.testcase_version_id
-        JOIN testcases tc ON tc.id = tv.testcase_id
-        JOIN testcase_tags tt ON tt.testcase_id = tc.id
-        JOIN tags t ON t.id = tt.tag_id
-        LEFT JOIN run_results rr ON rr.run_item_id = ri.id
-        WHERE ri.run_id = $1
-          AND lower(t.name::text) = 'l0'
-          AND rr.run_item_id IS NULL
+        UPDATE run_items ri
+        SET position = v.position
+        FROM UNNEST($2::uuid[], $3::int[]) AS v(id, position)
+        WHERE ri.id = v.id AND ri.run_id = $1
         "#,
     )
     .bind(run_uuid)
-    .fetch_one(&state.db)
+    .bind(&ids)
+    .bind(&positions)
+    .execute(run.conn())
     .await
-    .map_err(|_| api_error(StatusCode::INTERNAL_SERVER_ERROR, "Ошибка проверки L0 результатов."))?;
-
-    if unresolved_l0_count > 0 {
-        return Err(api_error(
-            StatusCode::CONFLICT,
-            "Run нельзя закрыть: не все L0 пункты имеют зафиксированный результат.",
-        ));
+    .map_err(|_| ApiError::RunItemsReorderFailed)?;
+    if updated.rows_affected() != ids.len() as u64 {
+        return Err(ApiError::ForeignRunItems);
     }
 
-    Ok(())
+    run_repo::compact_positions(&mut run).await?;
+    let items = run_repo::item_positions(&mut run).await?;
+    run.commit(ApiError::RunItemsReorderFailed).await?;
+
+    Ok(Json(ReorderRunItemsResponse { items }))
 }
 
-async fn update_run_status_v2(
+/// Records the result of a run item. `If-Match` with the version from the previous
+/// response or the run details makes the write conditional, as for the session.
+#[utoipa::path(
+    patch,
+    path = "/api/v2/runs/{run_id}/items/{run_item_id}/result",
+    tag = "results",
+    params(("run_id" = String, Path), ("run_item_id" = String, Path)),
+    request_body = UpdateRunResultRequest,
+    responses((status = 200, body = UpdateRunResultResponse))
+)]
+async fn update_run_result_v2(
     State(state): State<AppState>,
-    Path(run_id): Path<String>,
+    Path((run_id, run_item_id)): Path<(String, String)>,
+    AuthUser(actor_id): AuthUser,
     headers: HeaderMap,
+    Json(payload): Json<UpdateRunResultRequest>,
+) -> Result<impl IntoResponse, VersionError> {
+    let precondition = Precondition::from_headers(&headers)?;
+    let response = record_run_result(
+        &state,
+        &actor_id,
+        &run_id,
+        &run_item_id,
+        precondition,
+        payload,
+    )
+    .await?;
+    Ok(([(ETAG, etag::etag(response.version)?)], Json(response)))
+}
+
+/// A result payload that passed validation and can be written.
+struct ResultInput {
+    status: &'static str,
+    fail_reason_code: Option<String>,
+    comment: String,
+    elapsed_seconds: Option<i32>,
+    steps: Vec<run_repo::StepResultChange>,
+}
+
+impl ResultInput {
+    fn parse(payload: UpdateRunResultRequest) -> Result<Self, ApiError> {
+        let status = parse_result_status(payload.status.trim())?;
+        if payload
+            .elapsed_seconds
+            .is_some_and(|s| !(0..=MAX_ELAPSED_SECONDS).contains(&s))
+        {
+            return Err(ApiError::InvalidElapsedSeconds);
+        }
+        let fail_reason_code = if status == "fail" {
+            payload
+                .fail_reason_code
+                .map(|code| code.trim().to_string())
+                .filter(|code| !code.is_empty())
+        } else {
+            None
+        };
+        let mut steps = Vec::new();
+        for step in payload.steps.unwrap_or_default() {
+            let step_id = parse_uuid(&step.step_id, ApiError::InvalidStepId)?;
+            if steps
+                .iter()
+                .any(|s: &run_repo::StepResultChange| s.step_id == step_id)
+            {
+                return Err(ApiError::DuplicateStepResult);
+            }
+            steps.push(run_repo::StepResultChange {
+                step_id,
+                status: parse_result_status(step.status.trim())?,
+                comment: step.comment.unwrap_or_default(),
+            });
+        }
+        Ok(Self {
+            status,
+            fail_reason_code,
+            comment: payload.comment.unwrap_or_default(),
+            elapsed_seconds: payload.elapsed_seconds,
+            steps,
+        })
+    }
+}
+
+/// A written result together with what the item looked like before, for notifications.
+struct SavedResult {
+    run_item_id: Uuid,
+    input: ResultInput,
+    updated_at: DateTime<Utc>,
+    version: i64,
+    steps: Vec<RunItemStepView>,
+    run_title: String,
+    testcase_title: String,
+    is_required: bool,
+    previous_status: Option<String>,
+}
+
+/// Saves a result and announces it; shared by the HTTP and gRPC entry points.
+async fn record_run_result(
+    state: &AppState,
+    actor_id: &str,
+    run_id: &str,
+    run_item_id: &str,
+    precondition: Precondition,
+    payload: UpdateRunResultRequest,
+) -> Result<UpdateRunResultResponse, VersionError> {
+    ensure_db_user_exists(state, actor_id).await?;
+    let run_uuid = parse_uuid(run_id, ApiError::InvalidRunId)?;
+    let run_item_uuid = parse_uuid(run_item_id, ApiError::InvalidRunItemId)?;
+    let actor_uuid = parse_uuid(actor_id, ApiError::InvalidUserId)?;
+    let input = ResultInput::parse(payload)?;
+    authz::require_run_capability(state, run_uuid, actor_id, Capability::ResultEdit).await?;
+
+    let mut run = LockedRun::begin(
+        &state.db,
+        run_uuid,
+        RunLock::Shared,
+        ApiError::ResultSaveFailed,
+    )
+    .await?;
+    run.ensure_unlocked(ApiError::RunLockedResults)?;
+    let saved = save_result(
+        state,
+        &mut run,
+        run_item_uuid,
+        &precondition,
+        input,
+        actor_uuid,
+    )
+    .await?;
+    let run_status = run.status.clone();
+    let project_uuid = run.project_id;
+    run.commit(ApiError::ResultSaveFailed).await?;
+
+    announce_result(state, run_uuid, project_uuid, actor_id, &saved).await;
+    if saved.input.status != "na" {
+        advance_after_result(state, run_uuid, project_uuid, &run_status, actor_id).await;
+    }
+
+    Ok(UpdateRunResultResponse {
+        ok: true,
+        updated_at: saved.updated_at,
+        version: saved.version,
+        steps: saved.steps,
+    })
+}
+
+/// Writes one result inside the caller's run transaction.
+async fn save_result(
+    state: &AppState,
+    run: &mut LockedRun,
+    run_item_uuid: Uuid,
+    precondition: &Precondition,
+    input: ResultInput,
+    actor_uuid: Uuid,
+) -> Result<SavedResult, VersionError> {
+    let project_id = run.project_id.to_string();
+    // The item row lock serializes edits of the same result, so `previous_status` is exact.
+    let run_row = sqlx::query(
+        r#"
+        SELECT
+          r.title AS run_title,
+          ri.is_required,
+          tc.title AS testcase_title,
+          rr.status::text AS previous_status,
+          rr.version::bigint AS result_version
+        FROM runs r
+        JOIN run_items ri ON ri.run_id = r.id
+        JOIN testcase_versions tv ON tv.id = ri.testcase_version_id
+        JOIN testcases tc ON tc.id = tv.testcase_id
+        LEFT JOIN run_results rr ON rr.run_item_id = ri.id
+        WHERE r.id = $1 AND ri.id = $2
+        FOR UPDATE OF ri
+        "#,
+    )
+    .bind(run.id)
+    .bind(run_item_uuid)
+    .fetch_optional(run.conn())
+    .await
+    .map_err(|_| ApiError::RunStatusReadFailed)?
+    .ok_or(ApiError::ResultTargetNotFound)?;
+    precondition.check(
+        run_row.get::<Option<i64>, _>("result_version"),
+        ApiError::ResultVersionConflict,
+    )?;
+
+    let status = input.status;
+    if (status == "ok" || status == "fail")
+        && !run_repo::unmet_dependencies(run, run_item_uuid)
+            .await?
+            .is_empty()
+    {
+        return Err(ApiError::RunItemDependenciesUnmet.into());
+    }
+    if let Some(code) = input.fail_reason_code.as_deref() {
+        fail_reasons::ensure_code_allowed(state, run.project_id, code).await?;
+    }
+    if status == "fail" {
+        let settings = load_project_settings(state, &project_id).await?;
+        let code_allowed = input.fail_reason_code.as_deref().is_some_and(|code| {
+            settings
+                .required_fail_reason_codes
+                .iter()
+                .any(|c| c == code)
+        });
+        if !settings.required_fail_reason_codes.is_empty() && !code_allowed {
+            return Err(ApiError::FailReasonRequired.into());
+        }
+    }
+
+    let (updated_at, version) = run_repo::upsert_result(
+        run,
+        run_repo::ResultChange {
+            run_item_id: run_item_uuid,
+            status,
+            fail_reason_code: input.fail_reason_code.as_deref(),
+            comment: &input.comment,
+            elapsed_seconds: input.elapsed_seconds,
+            actor_uuid,
+        },
+    )
+    .await?;
+    run_repo::upsert_step_results(run, run_item_uuid, &input.steps, actor_uuid).await?;
+    let steps = run_repo::item_steps(run, run_item_uuid).await?;
+
+    Ok(SavedResult {
+        run_item_id: run_item_uuid,
+        input,
+        updated_at,
+        version,
+        steps,
+        run_title: run_row.get("run_title"),
+        testcase_title: run_row.get("testcase_title"),
+        is_required: run_row.get("is_required"),
+        previous_status: run_row.get("previous_status"),
+    })
+}
+
+/// Sends webhooks, chat messages, notifications and the live event of a committed result.
+async fn announce_result(
+    state: &AppState,
+    run_uuid: Uuid,
+    project_uuid: Uuid,
+    actor_id: &str,
+    saved: &SavedResult,
+) {
+    let status = saved.input.status;
+    let fail_reason_code = &saved.input.fail_reason_code;
+    let comment = &saved.input.comment;
+    let event = live::RunResultEvent {
+        run_item_id: saved.run_item_id,
+        status,
+        fail_reason_code: fail_reason_code.as_deref(),
+        comment,
+        updated_by_user_id: actor_id,
+        updated_at: saved.updated_at,
+        version: saved.version,
+        steps: &saved.steps,
+    };
+    let data = json!({ "runId": run_uuid, "result": &event });
+    webhooks::emit(state, project_uuid, "result.updated", data.clone()).await;
+    if status == "fail" {
+        webhooks::emit(state, project_uuid, "result.failed", data).await;
+    }
+    let newly_failed = saved.previous_status.as_deref() != Some("fail");
+    if status == "fail" && newly_failed && saved.is_required {
+        let run_title = &saved.run_title;
+        let testcase_title = &saved.testcase_title;
+        chat::required_failed(
+            state,
+            project_uuid,
+            run_uuid,
+            run_title.clone(),
+            testcase_title.clone(),
+            fail_reason_code.clone(),
+            comment.clone(),
+        );
+        let project_id = project_uuid.to_string();
+        let mut recipients = notifications::project_managers(state, &project_id).await;
+        recipients.retain(|id| id != actor_id);
+        notifications::notify(
+            state,
+            notifications::NotificationKind::RequiredFailed,
+            Some(project_uuid),
+            Some(run_uuid),
+            recipients,
+            format!("FAIL обязательного теста в прогоне «{run_title}»"),
+            format!(
+                "Обязательный тест «{testcase_title}» в прогоне «{run_title}» отмечен как FAIL.\nПричина: {}\nКомментарий: {comment}",
+                fail_reason_code.as_deref().unwrap_or("-")
+            ),
+        );
+    }
+    state.cache.invalidate_run(run_uuid);
+    state
+        .live
+        .publish(run_uuid, &live::RunEvent::ResultUpdated(event));
+}
+
+/// Moves a draft or running run forward after a result other than `na` was recorded.
+async fn advance_after_result(
+    state: &AppState,
+    run_uuid: Uuid,
+    project_uuid: Uuid,
+    run_status: &str,
+    actor_id: &str,
+) {
+    if !matches!(run_status, "draft" | "in_progress") {
+        return;
+    }
+    let auto_complete = load_project_settings(state, &project_uuid.to_string())
+        .await
+        .is_ok_and(|s| s.auto_complete_runs);
+    // The result is saved either way; a failed transition is retried by the next one.
+    if let Err(err) = advance_run_status(state, run_uuid, auto_complete, actor_id).await {
+        warn!("failed to advance the status of run {run_uuid}: {err:?}");
+    }
+}
+
+const MAX_BULK_RESULTS: usize = 1000;
+
+/// Why an entry of a batch was not written, as reported next to the entry.
+fn version_error_body(err: VersionError, lang: Lang) -> ErrorBody {
+    let (error, current_version) = match err {
+        VersionError::Api(error) => (error, None),
+        VersionError::Conflict {
+            error,
+            current_version,
+        } => (error, Some(current_version)),
+    };
+    ErrorBody {
+        code: error.code(),
+        message: error.message(lang),
+        current_version,
+    }
+}
+
+/// Records results of items of one run in one transaction, each behind a savepoint so that a
+/// rejected entry does not undo the others; then announces them and advances the run. The
+/// outcomes are in the order of `pending`. Entries for the same item are applied in order.
+async fn save_result_batch(
+    state: &AppState,
+    run_uuid: Uuid,
+    actor_id: &str,
+    actor_uuid: Uuid,
+    pending: Vec<(Uuid, ResultInput)>,
+) -> Result<Vec<Result<SavedResult, VersionError>>, ApiError> {
+    let mut outcomes: Vec<Result<SavedResult, VersionError>> = pending
+        .iter()
+        .map(|_| Err(ApiError::ResultSaveFailed.into()))
+        .collect();
+    let mut pending: Vec<_> = pending.into_iter().enumerate().collect();
+    // Item rows are locked in id order, so two overlapping batches cannot deadlock.
+    pending.sort_by_key(|(_, (run_item_uuid, _))| *run_item_uuid);
+
+    let mut run = LockedRun::begin(
+        &state.db,
+        run_uuid,
+        RunLock::Shared,
+        ApiError::ResultSaveFailed,
+    )
+    .await?;
+    run.ensure_unlocked(ApiError::RunLockedResults)?;
+    let precondition = Precondition::None;
+    for (index, (run_item_uuid, input)) in pending {
+        run.savepoint(ApiError::ResultSaveFailed).await?;
+        let saved = save_result(
+            state,
+            &mut run,
+            run_item_uuid,
+            &precondition,
+            input,
+            actor_uuid,
+        )
+        .await;
+        if saved.is_ok() {
+            run.release_savepoint(ApiError::ResultSaveFailed).await?;
+        } else {
+            run.rollback_to_savepoint(ApiError::ResultSaveFailed)
+                .await?;
+        }
+        outcomes[index] = saved;
+    }
+    let run_status = run.status.clone();
+    let project_uuid = run.project_id;
+    run.commit(ApiError::ResultSaveFailed).await?;
+
+    let mut advance = false;
+    for saved in outcomes.iter().flatten() {
+        announce_result(state, run_uuid, project_uuid, actor_id, saved).await;
+        advance |= saved.input.status != "na";
+    }
+    if advance {
+        advance_after_result(state, run_uuid, project_uuid, &run_status, actor_id).await;
+    }
+    Ok(outcomes)
+}
+
+impl BulkRunResultRequest {
+    fn parse(self) -> Result<(Uuid, ResultInput), ApiError> {
+        let run_item_uuid = parse_uuid(self.run_item_id.trim(), ApiError::InvalidRunItemId)?;
+        let input = ResultInput::parse(UpdateRunResultRequest {
+            status: self.status,
+            fail_reason_code: self.fail_reason_code,
+            comment: self.comment,
+            elapsed_seconds: None,
+            steps: None,
+        })?;
+        Ok((run_item_uuid, input))
+    }
+}
+
+/// Records results of many items of one run in one transaction. Entries are applied one
+/// by one: a rejected entry is reported in `results` and does not undo the others.
+#[utoipa::path(
+    patch,
+    path = "/api/v2/runs/{run_id}/results/bulk",
+    tag = "results",
+    params(("run_id" = String, Path)),
+    request_body = Vec<BulkRunResultRequest>,
+    responses((status = 200, body = BulkUpdateRunResultsResponse))
+)]
+async fn bulk_update_run_results_v2(
+    State(state): State<AppState>,
+    Path(run_id): Path<String>,
+    AuthUser(actor_id): AuthUser,
+    Json(payload): Json<Vec<BulkRunResultRequest>>,
+) -> Result<Json<BulkUpdateRunResultsResponse>, ApiError> {
+    ensure_db_user_exists(&state, &actor_id).await?;
+    let run_uuid = parse_uuid(&run_id, ApiError::InvalidRunId)?;
+    let actor_uuid = parse_uuid(&actor_id, ApiError::InvalidUserId)?;
+    if payload.is_empty() {
+        return Err(ApiError::BulkResultsEmpty);
+    }
+    if payload.len() > MAX_BULK_RESULTS {
+        return Err(ApiError::TooManyBulkResults);
+    }
+    let mut seen = HashSet::new();
+    if !payload
+        .iter()
+        .all(|entry| seen.insert(entry.run_item_id.trim()))
+    {
+        return Err(ApiError::DuplicateBulkResultItems);
+    }
+    authz::require_run_capability(&state, run_uuid, &actor_id, Capability::ResultEdit).await?;
+
+    let mut outcomes = Vec::with_capacity(payload.len());
+    let mut pending = Vec::new();
+    let mut pending_index = Vec::new();
+    for (index, entry) in payload.into_iter().enumerate() {
+        let run_item_id = entry.run_item_id.clone();
+        match entry.parse() {
+            Ok(parsed) => {
+                pending.push(parsed);
+                pending_index.push(index);
+                // Replaced once the entry is written below.
+                outcomes.push((run_item_id, Err(ApiError::ResultSaveFailed.into())));
+            }
+            Err(err) => outcomes.push((run_item_id, Err(VersionError::from(err)))),
+        }
+    }
+    let saved = save_result_batch(&state, run_uuid, &actor_id, actor_uuid, pending).await?;
+    for (index, saved) in pending_index.into_iter().zip(saved) {
+        outcomes[index].1 = saved;
+    }
+
+    let lang = current_lang();
+    let results: Vec<BulkRunResultView> = outcomes
+        .into_iter()
+        .map(|(run_item_id, outcome)| match outcome {
+            Ok(saved) => BulkRunResultView {
+                run_item_id,
+                ok: true,
+                updated_at: Some(saved.updated_at),
+                version: Some(saved.version),
+                error: None,
+            },
+            Err(err) => BulkRunResultView {
+                run_item_id,
+                ok: false,
+                updated_at: None,
+                version: None,
+                error: Some(version_error_body(err, lang)),
+            },
+        })
+        .collect();
+    let succeeded = results.iter().filter(|r| r.ok).count();
+    Ok(Json(BulkUpdateRunResultsResponse {
+        succeeded,
+        failed: results.len() - succeeded,
+        results,
+    }))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/api/v2/runs/{run_id}/status",
+    tag = "runs",
+    params(("run_id" = String, Path)),
+    request_body = UpdateRunStatusRequest,
+    responses((status = 200, body = UpdateRunStatusResponse))
+)]
+async fn update_run_status_v2(
+    State(state): State<AppState>,
+    Path(run_id): Path<String>,
+    AuthUser(actor_id): AuthUser,
     Json(payload): Json<UpdateRunStatusRequest>,
-) -> Result<Json<UpdateRunStatusResponse>, (StatusCode, Json<ErrorResponse>)> {
-    let _actor_id = parse_bearer_user_id(&headers)?;
-    let run_uuid = parse_uuid(&run_id, "Некорректный run_id.")?;
+) -> Result<Json<UpdateRunStatusResponse>, ApiError> {
+    let run_uuid = parse_uuid(&run_id, ApiError::InvalidRunId)?;
     let next = parse_run_status(payload.status.trim())?;
+    let required_capability = if next == "locked" {
+        Capability::RunLock
+    } else {
+        Capability::RunStatus
+    };
+    authz::require_run_capability(&state, run_uuid, &actor_id, required_capability).await?;
 
-    let current: Option<String> =
-        sqlx::query_scalar(r#"SELECT status::text FROM runs WHERE id = $1"#)
-            .bind(run_uuid)
-            .fetch_optional(&state.db)
-            .await
-            .map_err(|_| api_error(StatusCode::INTERNAL_SERVER_ERROR, "Ошибка чтения run status."))?;
-
-    let current = current.ok_or_else(|| api_error(StatusCode::NOT_FOUND, "Run не найден."))?;
+    let mut run = LockedRun::begin(
+        &state.db,
+        run_uuid,
+        RunLock::Exclusive,
+        ApiError::RunStatusUpdateFailed,
+    )
+    .await?;
+    let current = run.status.clone();
     let allowed = matches!(
         (current.as_str(), next),
         ("draft", "draft")
@@ -1585,88 +3434,416 @@ async fn update_run_status_v2(
             | ("locked", "locked")
     );
     if !allowed {
-        return Err(api_error(
-            StatusCode::CONFLICT,
-            "Недопустимый переход статуса run.",
-        ));
+        return Err(ApiError::InvalidRunTransition);
     }
 
     if next == "done" || next == "locked" {
-        validate_run_dod_for_close(&state, run_uuid).await?;
+        run_repo::validate_dod_for_close(&mut run).await?;
     }
+    run_repo::set_status(&mut run, next).await?;
+    run.commit(ApiError::RunStatusUpdateFailed).await?;
 
-    match next {
-        "draft" => {
-            sqlx::query(r#"UPDATE runs SET status = 'draft', updated_at = NOW() WHERE id = $1"#)
-                .bind(run_uuid)
-                .execute(&state.db)
-                .await
-                .map_err(|_| api_error(StatusCode::INTERNAL_SERVER_ERROR, "Не удалось обновить статус run."))?;
-        }
-        "in_progress" => {
-            sqlx::query(
-                r#"
-                UPDATE runs
-                SET status = 'in_progress',
-                    started_at = COALESCE(started_at, NOW()),
-                    updated_at = NOW()
-                WHERE id = $1
-                "#,
-            )
-            .bind(run_uuid)
-            .execute(&state.db)
-            .await
-            .map_err(|_| api_error(StatusCode::INTERNAL_SERVER_ERROR, "Не удалось обновить статус run."))?;
-        }
-        "done" => {
-            sqlx::query(
-                r#"
-                UPDATE runs
-                SET status = 'done',
-                    started_at = COALESCE(started_at, NOW()),
-                    finished_at = COALESCE(finished_at, NOW()),
-                    updated_at = NOW()
-                WHERE id = $1
-                "#,
-            )
-            .bind(run_uuid)
-            .execute(&state.db)
-            .await
-            .map_err(|_| api_error(StatusCode::INTERNAL_SERVER_ERROR, "Не удалось обновить статус run."))?;
+    let run = announce_run_status(&state, run_uuid, &current, next, &actor_id).await?;
+    Ok(Json(UpdateRunStatusResponse { run }))
+}
+
+const MAX_RUN_TITLE_CHARS: usize = 500;
+const MAX_RUN_MILESTONE_CHARS: usize = 200;
+const MAX_RUN_DESCRIPTION_CHARS: usize = 20_000;
+
+/// Trimmed milestone; an empty one means the run has none.
+fn parse_run_milestone(raw: Option<&str>) -> Result<Option<String>, ApiError> {
+    match raw.map(str::trim) {
+        Some(v) if v.chars().count() > MAX_RUN_MILESTONE_CHARS => {
+            Err(ApiError::InvalidRunMilestone)
         }
-        "locked" => {
-            sqlx::query(
-                r#"
-                UPDATE runs
-                SET status = 'locked',
-                    started_at = COALESCE(started_at, NOW()),
-                    finished_at = COALESCE(finished_at, NOW()),
-                    locked_at = NOW(),
-                    updated_at = NOW()
-                WHERE id = $1
-                "#,
-            )
-            .bind(run_uuid)
-            .execute(&state.db)
-            .await
-            .map_err(|_| api_error(StatusCode::INTERNAL_SERVER_ERROR, "Не удалось обновить статус run."))?;
+        Some(v) if !v.is_empty() => Ok(Some(v.to_string())),
+        _ => Ok(None),
+    }
+}
+
+fn parse_run_description(raw: Option<&str>) -> Result<String, ApiError> {
+    let description = raw.unwrap_or_default().trim();
+    if description.chars().count() > MAX_RUN_DESCRIPTION_CHARS {
+        return Err(ApiError::InvalidRunDescription);
+    }
+    Ok(description.to_string())
+}
+
+/// Edits the run details. Title, milestone and description can change until the run is locked;
+/// the asset and the template define what the run covers and are frozen once it leaves draft.
+#[utoipa::path(
+    patch,
+    path = "/api/v2/runs/{run_id}",
+    tag = "runs",
+    params(("run_id" = String, Path)),
+    request_body = UpdateRunRequest,
+    responses((status = 200, body = UpdateRunResponse))
+)]
+async fn update_run_v2(
+    State(state): State<AppState>,
+    Path(run_id): Path<String>,
+    AuthUser(actor_id): AuthUser,
+    Json(payload): Json<UpdateRunRequest>,
+) -> Result<Json<UpdateRunResponse>, ApiError> {
+    let run_uuid = parse_uuid(&run_id, ApiError::InvalidRunId)?;
+    let title = match payload.title.as_deref().map(str::trim) {
+        Some(t) if t.is_empty() || t.chars().count() > MAX_RUN_TITLE_CHARS => {
+            return Err(ApiError::InvalidRunTitle);
         }
-        _ => {
-            return Err(api_error(
-                StatusCode::BAD_REQUEST,
-                "Некорректный статус run.",
-            ))
+        t => t.map(str::to_string),
+    };
+    let asset_id = match payload.asset_id.as_deref().map(str::trim) {
+        Some("") => Some(None),
+        Some(v) => Some(Some(parse_uuid(v, ApiError::InvalidAssetId)?)),
+        None => None,
+    };
+    let template_id = match payload.template_id.as_deref().map(str::trim) {
+        Some("") => Some(None),
+        Some(v) => Some(Some(parse_uuid(v, ApiError::InvalidTemplateId)?)),
+        None => None,
+    };
+    let milestone = match payload.milestone.as_deref() {
+        Some(v) => Some(parse_run_milestone(Some(v))?),
+        None => None,
+    };
+    let description = match payload.description.as_deref() {
+        Some(v) => Some(parse_run_description(Some(v))?),
+        None => None,
+    };
+    authz::require_run_capability(&state, run_uuid, &actor_id, Capability::RunCompose).await?;
+    ensure_db_user_exists(&state, &actor_id).await?;
+    let actor_uuid = parse_uuid(&actor_id, ApiError::InvalidUserId)?;
+
+    let mut run = LockedRun::begin(
+        &state.db,
+        run_uuid,
+        RunLock::Exclusive,
+        ApiError::RunUpdateFailed,
+    )
+    .await?;
+    run.ensure_unlocked(ApiError::RunLockedDetails)?;
+    let current = fetch_run_view(&state.db, run_uuid)
+        .await?
+        .ok_or(ApiError::RunNotFound)?;
+
+    let title = title.filter(|t| *t != current.title);
+    let asset_id = asset_id.filter(|id| id.map(|id| id.to_string()) != current.asset_id);
+    let template_id = template_id.filter(|id| id.map(|id| id.to_string()) != current.template_id);
+    let milestone = milestone.filter(|m| *m != current.milestone);
+    let description = description.filter(|d| *d != current.description);
+    if (asset_id.is_some() || template_id.is_some()) && run.status != "draft" {
+        return Err(ApiError::RunCompositionFrozen);
+    }
+    if let Some(Some(asset_id)) = asset_id {
+        assets::ensure_active_asset_in_project(&state, asset_id, run.project_id).await?;
+    }
+    if let Some(Some(template_id)) = template_id {
+        let exists = sqlx::query_scalar!(
+            r#"
+            SELECT EXISTS (
+              SELECT 1 FROM run_templates
+              WHERE id = $1 AND is_active = TRUE AND (project_id IS NULL OR project_id = $2)
+            ) AS "exists!"
+            "#,
+            template_id,
+            run.project_id,
+        )
+        .fetch_one(&state.db)
+        .await
+        .map_err(|err| db_errors::map(err, ApiError::RunUpdateFailed))?;
+        if !exists {
+            return Err(ApiError::RunTemplateNotInProject);
         }
     }
 
+    let mut before = serde_json::Map::new();
+    let mut after = serde_json::Map::new();
+    if let Some(title) = &title {
+        before.insert("title".into(), json!(current.title));
+        after.insert("title".into(), json!(title));
+    }
+    if let Some(asset_id) = asset_id {
+        before.insert("assetId".into(), json!(current.asset_id));
+        after.insert("assetId".into(), json!(asset_id));
+    }
+    if let Some(template_id) = template_id {
+        before.insert("templateId".into(), json!(current.template_id));
+        after.insert("templateId".into(), json!(template_id));
+    }
+    if let Some(milestone) = &milestone {
+        before.insert("milestone".into(), json!(current.milestone));
+        after.insert("milestone".into(), json!(milestone));
+    }
+    if let Some(description) = &description {
+        before.insert("description".into(), json!(current.description));
+        after.insert("description".into(), json!(description));
+    }
+    if after.is_empty() {
+        return Ok(Json(UpdateRunResponse { run: current }));
+    }
+
+    let project_id = run.project_id;
+    let update_failed = |err| db_errors::map(err, ApiError::RunUpdateFailed);
+    sqlx::query!(
+        r#"
+        UPDATE runs
+        SET title = COALESCE($2, title),
+            asset_id = CASE WHEN $3 THEN $4 ELSE asset_id END,
+            template_id = CASE WHEN $5 THEN $6 ELSE template_id END,
+            milestone = CASE WHEN $7 THEN $8 ELSE milestone END,
+            description = COALESCE($9, description)
+        WHERE id = $1
+        "#,
+        run_uuid,
+        title.as_deref(),
+        asset_id.is_some(),
+        asset_id.flatten(),
+        template_id.is_some(),
+        template_id.flatten(),
+        milestone.is_some(),
+        milestone.flatten(),
+        description.as_deref(),
+    )
+    .execute(run.conn())
+    .await
+    .map_err(update_failed)?;
+    audit::record(
+        run.conn(),
+        audit::AuditEntry {
+            actor_user_id: Some(actor_uuid),
+            action: "update",
+            entity_type: "run",
+            entity_id: Some(run_uuid),
+            project_id: Some(project_id),
+            run_id: Some(run_uuid),
+            before: Some(Value::Object(before)),
+            after: Some(Value::Object(after)),
+        },
+    )
+    .await
+    .map_err(update_failed)?;
+    run.commit(ApiError::RunUpdateFailed).await?;
+
     let run = fetch_run_view(&state.db, run_uuid)
         .await?
-        .ok_or_else(|| api_error(StatusCode::NOT_FOUND, "Run не найден после обновления."))?;
+        .ok_or(ApiError::RunNotFoundAfterUpdate)?;
+    state.cache.invalidate_run(run_uuid);
+    state
+        .live
+        .publish(run_uuid, &live::RunEvent::DetailsChanged { run: &run });
+    Ok(Json(UpdateRunResponse { run }))
+}
+
+const MAX_UNLOCK_REASON_CHARS: usize = 1000;
+
+/// Returns a `locked` run to `done` so its results can be corrected. Only project owners may do
+/// it; the reason goes to the audit log and to every other project member.
+#[utoipa::path(
+    post,
+    path = "/api/v2/runs/{run_id}/unlock",
+    tag = "runs",
+    params(("run_id" = String, Path)),
+    request_body = UnlockRunRequest,
+    responses((status = 200, body = UpdateRunStatusResponse))
+)]
+async fn unlock_run_v2(
+    State(state): State<AppState>,
+    Path(run_id): Path<String>,
+    AuthUser(actor_id): AuthUser,
+    Json(payload): Json<UnlockRunRequest>,
+) -> Result<Json<UpdateRunStatusResponse>, ApiError> {
+    let run_uuid = parse_uuid(&run_id, ApiError::InvalidRunId)?;
+    let role =
+        authz::require_run_capability(&state, run_uuid, &actor_id, Capability::RunLock).await?;
+    if role != "owner" && role != permissions::ORG_ADMIN_ROLE {
+        return Err(ApiError::RunUnlockOwnerOnly);
+    }
+    let reason = payload.reason.trim();
+    if reason.is_empty() || reason.chars().count() > MAX_UNLOCK_REASON_CHARS {
+        return Err(ApiError::InvalidUnlockReason);
+    }
+    ensure_db_user_exists(&state, &actor_id).await?;
+    let actor_uuid = parse_uuid(&actor_id, ApiError::InvalidUserId)?;
+
+    let mut run = LockedRun::begin(
+        &state.db,
+        run_uuid,
+        RunLock::Exclusive,
+        ApiError::RunStatusUpdateFailed,
+    )
+    .await?;
+    if run.status != "locked" {
+        return Err(ApiError::RunNotLocked);
+    }
+    let project_id = run.project_id;
+    let lock = run_repo::unlock(&mut run).await?;
+    audit::record(
+        run.conn(),
+        audit::AuditEntry {
+            actor_user_id: Some(actor_uuid),
+            action: "unlock",
+            entity_type: "run",
+            entity_id: Some(run_uuid),
+            project_id: Some(project_id),
+            run_id: Some(run_uuid),
+            before: Some(json!({
+                "status": "locked",
+                "lockedAt": lock.locked_at,
+                "lockedByUserId": lock.locked_by_user_id,
+            })),
+            after: Some(json!({ "status": "done", "reason": reason })),
+        },
+    )
+    .await
+    .map_err(|_| ApiError::RunStatusUpdateFailed)?;
+    // The cached report is served as long as the run is locked; after the results are
+    // corrected and the run locked again it would be stale.
+    report::drop_cached(&state, run_uuid)
+        .await
+        .map_err(|_| ApiError::RunStatusUpdateFailed)?;
+    run.commit(ApiError::RunStatusUpdateFailed).await?;
+
+    let run = announce_run_status(&state, run_uuid, "locked", "done", &actor_id).await?;
+    let mut recipients = notifications::project_members(&state, &run.project_id).await;
+    recipients.retain(|id| *id != actor_id);
+    notifications::notify(
+        &state,
+        notifications::NotificationKind::RunUnlocked,
+        Uuid::parse_str(&run.project_id).ok(),
+        Some(run_uuid),
+        recipients,
+        format!("Прогон «{}» разблокирован", run.title),
+        format!(
+            "Прогон «{}» снова открыт для изменений (статус done).\nПричина: {reason}",
+            run.title
+        ),
+    );
     Ok(Json(UpdateRunStatusResponse { run }))
 }
 
-async fn api_not_found() -> (StatusCode, Json<ErrorResponse>) {
-    api_error(StatusCode::NOT_FOUND, "API endpoint не найден.")
+/// Tells subscribers about a committed status change of a run, the same way for manual and
+/// automatic transitions. Returns the updated run.
+async fn announce_run_status(
+    state: &AppState,
+    run_uuid: Uuid,
+    previous: &str,
+    next: &str,
+    actor_id: &str,
+) -> Result<RunView, ApiError> {
+    let run = fetch_run_view(&state.db, run_uuid)
+        .await?
+        .ok_or(ApiError::RunNotFoundAfterUpdate)?;
+    state.cache.invalidate_run(run_uuid);
+    state
+        .live
+        .publish(run_uuid, &live::RunEvent::StatusChanged { run: &run });
+    if next == "done" && previous != "done" {
+        if let Ok(project_id) = Uuid::parse_str(&run.project_id) {
+            webhooks::emit(state, project_id, "run.done", json!({ "run": &run })).await;
+            chat::run_done(state, project_id, run_uuid, run.title.clone());
+            ci::run_done(state, project_id, run_uuid);
+        }
+        let mut recipients = notifications::project_managers(state, &run.project_id).await;
+        recipients.push(run.executed_by_user_id.clone());
+        recipients.retain(|id| id != actor_id);
+        notifications::notify(
+            state,
+            notifications::NotificationKind::RunDone,
+            Uuid::parse_str(&run.project_id).ok(),
+            Some(run_uuid),
+            recipients,
+            format!("Прогон «{}» завершён", run.title),
+            format!("Прогон «{}» переведён в статус done.", run.title),
+        );
+    }
+    Ok(run)
+}
+
+/// Moves a run forward after a result was saved: a `draft` run with a result becomes
+/// `in_progress`, and with `autoCompleteRuns` an `in_progress` run whose required items are
+/// all resolved becomes `done`; `na`, `blocked` and `retest` leave an item unresolved. Runs
+/// in its own transaction after the result commits; a run that fails the close checks just
+/// stays `in_progress`.
+async fn advance_run_status(
+    state: &AppState,
+    run_uuid: Uuid,
+    auto_complete: bool,
+    actor_id: &str,
+) -> Result<(), ApiError> {
+    let mut run = LockedRun::begin(
+        &state.db,
+        run_uuid,
+        RunLock::Exclusive,
+        ApiError::RunStatusUpdateFailed,
+    )
+    .await?;
+    let mut transitions = Vec::new();
+    if run.status == "draft" {
+        let has_result: bool = sqlx::query_scalar(
+            r#"
+            SELECT EXISTS (
+              SELECT 1 FROM run_items ri
+              JOIN run_results rr ON rr.run_item_id = ri.id
+              WHERE ri.run_id = $1 AND rr.status <> 'na'
+            )
+            "#,
+        )
+        .bind(run_uuid)
+        .fetch_one(run.conn())
+        .await
+        .map_err(|_| ApiError::RunStatusReadFailed)?;
+        if has_result {
+            run_repo::set_status(&mut run, "in_progress").await?;
+            transitions.push(("draft", "in_progress"));
+        }
+    }
+    if auto_complete && run.status == "in_progress" {
+        let row = sqlx::query(
+            r#"
+            SELECT
+              COUNT(*) FILTER (WHERE ri.is_required) AS required,
+              COUNT(*) FILTER (
+                WHERE ri.is_required
+                  AND COALESCE(rr.status::text, 'na') IN ('na', 'blocked', 'retest')
+              ) AS pending
+            FROM run_items ri
+            LEFT JOIN run_results rr ON rr.run_item_id = ri.id
+            WHERE ri.run_id = $1
+            "#,
+        )
+        .bind(run_uuid)
+        .fetch_one(run.conn())
+        .await
+        .map_err(|_| ApiError::RunStatusReadFailed)?;
+        let complete = row.get::<i64, _>("required") > 0 && row.get::<i64, _>("pending") == 0;
+        if complete && run_repo::validate_dod_for_close(&mut run).await.is_ok() {
+            run_repo::set_status(&mut run, "done").await?;
+            transitions.push(("in_progress", "done"));
+        }
+    }
+    if transitions.is_empty() {
+        return Ok(());
+    }
+    run.commit(ApiError::RunStatusUpdateFailed).await?;
+    for (previous, next) in transitions {
+        announce_run_status(state, run_uuid, previous, next, actor_id).await?;
+    }
+    Ok(())
+}
+
+async fn api_not_found() -> ApiError {
+    ApiError::EndpointNotFound
+}
+
+async fn connect_db(
+    database_url: &str,
+    pool: &config::DatabasePoolConfig,
+) -> anyhow::Result<PgPool> {
+    let (pool, connect) = db_pool::options(database_url, pool)?;
+    pool.connect_with(connect)
+        .await
+        .context("failed to connect to PostgreSQL")
 }
 
 #[tokio::main]
@@ -1680,64 +3857,752 @@ async fn main() -> anyhow::Result<()> {
         )
         .init();
 
-    let host = env::var("API_HOST").unwrap_or_else(|_| "0.0.0.0".to_string());
-    let port = env::var("API_PORT").unwrap_or_else(|_| "8181".to_string());
-    let repo_root = env::var("REPO_ROOT").unwrap_or_else(|_| "..".to_string());
-    let database_url = env::var("DATABASE_URL").context("DATABASE_URL is required")?;
-    let addr: SocketAddr = format!("{}:{}", host, port)
-        .parse()
-        .context("failed to parse API_HOST/API_PORT")?;
-    let db = PgPoolOptions::new()
-        .max_connections(10)
-        .connect(&database_url)
+    // Operator modes: touch only the schema or the stored credentials and exit.
+    match std::env::args().nth(1).as_deref() {
+        None => {}
+        Some("--migrate-only") => {
+            let db = connect_db(&config::Config::load_database_url()?, &Default::default()).await?;
+            migrations::run(&db).await?;
+            db.close().await;
+            return Ok(());
+        }
+        Some("--baseline-migrations") => {
+            let db = connect_db(&config::Config::load_database_url()?, &Default::default()).await?;
+            migrations::baseline(&db).await?;
+            db.close().await;
+            return Ok(());
+        }
+        Some("--reencrypt-secrets") => {
+            let (database_url, keys) = config::Config::load_secrets()?;
+            let db = connect_db(&database_url, &Default::default()).await?;
+            let secrets = secrets::SecretBox::new(&keys.key, &keys.previous_keys);
+            let result = secrets::reencrypt(&db, &secrets).await;
+            db.close().await;
+            return result;
+        }
+        Some("import-testrail") => {
+            let args: Vec<String> = std::env::args().skip(2).collect();
+            return migration_import::run(config::Config::load_migration()?, &args).await;
+        }
+        Some(other) => anyhow::bail!(
+            "unknown argument {other}; usage: uran-api [--migrate-only | --baseline-migrations \
+             | --reencrypt-secrets | import-testrail --url URL --token TOKEN]"
+        ),
+    }
+
+    let mut config = config::Config::load()?;
+    let db = connect_db(&config.database_url, &config.database_pool).await?;
+    if config.run_migrations {
+        migrations::run(&db).await?;
+    }
+
+    let attachment_limits = attachments::AttachmentLimits::new(
+        config.attachments_max_bytes,
+        config.attachments_allowed_types.clone(),
+        config.project_storage_quota_bytes,
+    );
+    let upload_body_limit = attachment_limits.max_bytes + 64 * 1024;
+
+    let jwt = Arc::new(jwt::JwtKeys::from_secret(&config.jwt_secret));
+    revocation::load(&db, &jwt)
         .await
-        .context("failed to connect to PostgreSQL")?;
+        .context("failed to load revoked tokens")?;
 
-    let data_dir = PathBuf::from(&repo_root).join("backend").join("data");
+    let data_dir = config.repo_root.join("backend").join("data");
     let state = AppState {
         users_file: data_dir.join("users.json"),
         projects_file: data_dir.join("projects.json"),
+        organizations_file: organizations::file_next_to(&data_dir.join("projects.json")),
         file_lock: Arc::new(Mutex::new(())),
         db,
+        replica: config
+            .database_replica_url
+            .as_deref()
+            .map(|url| {
+                replica::ReadReplica::new(
+                    url,
+                    &config.database_pool,
+                    config.database_replica_max_lag,
+                )
+            })
+            .transpose()?
+            .map(Arc::new),
+        jwt,
+        storage: Arc::new(storage::Storage::new(&config.storage)?),
+        attachment_limits,
+        live: Arc::new(live::RunHub::default()),
+        activity: Arc::new(live::ProjectFeed::default()),
+        report_settings: report::ReportSettings::new(config.report_font_path.clone()),
+        webhooks: Arc::new(webhooks::WebhookDispatcher::new()?),
+        public_url: config.public_url.clone(),
+        mailer: notifications::mailer(config.smtp.as_ref())?,
+        inbound_email: config.inbound_email.take().map(Arc::new),
+        telegram: config
+            .telegram
+            .as_ref()
+            .map(telegram::TelegramBot::new)
+            .transpose()?
+            .map(Arc::new),
+        rate_limiter: Arc::new(rate_limit::RateLimiter::new(&config.rate_limit)),
+        analytics: Arc::new(analytics::AnalyticsCache::new(config.analytics_cache_ttl)),
+        cache: Arc::new(cache::HotCache::new(&config.cache)),
+        oidc: config
+            .oidc
+            .as_ref()
+            .map(|oidc| oidc::OidcClient::new(oidc, &config.jwt_secret, &config.public_url))
+            .transpose()?
+            .map(Arc::new),
+        secrets: config
+            .secrets
+            .as_ref()
+            .map(|s| Arc::new(secrets::SecretBox::new(&s.key, &s.previous_keys))),
+        jira: Arc::new(jira::JiraClient::new()?),
+        chat: Arc::new(chat::ChatClient::new()?),
+        ci: Arc::new(ci::CiClient::new()?),
+        jobs: Arc::new(jobs::JobQueue::new()),
+        backpressure: Arc::new(db_pool::Backpressure::new(&config.database_pool)),
+        admin_emails: Arc::new(config.admin_emails.clone()),
+        invites_require_verified_email: config.invites_require_verified_email,
     };
-
-    let frontend_dist = PathBuf::from(repo_root).join("frontend").join("dist");
+    let shutdown_timeout = config.shutdown_timeout;
+    let job_workers = jobs::spawn_workers(state.clone());
+    rate_limit::spawn_cleanup(state.rate_limiter.clone());
+    revocation::spawn_sync(state.db.clone(), state.jwt.clone());
+    if let Some(replica) = &state.replica {
+        replica::spawn_monitor(replica.clone());
+    }
+    schedules::spawn_scheduler(state.clone());
+    custom_reports::spawn_scheduler(state.clone());
+    retention::spawn_scheduler(state.clone());
+    live::spawn_project_feed(state.clone());
+    cache::spawn_invalidation_listener(state.clone());
+    idempotency::spawn_cleanup(state.clone());
+    telegram::spawn_poller(state.clone());
+
+    let frontend_dist = config.repo_root.join("frontend").join("dist");
     let frontend_index = frontend_dist.join("index.html");
     let static_service = ServeDir::new(frontend_dist).fallback(ServeFile::new(frontend_index));
 
+    // Uploads and imports carry their own, larger body limits; every other route gets
+    // `REQUEST_BODY_MAX_BYTES`.
+    let uploads = Router::new()
+        .route(
+            "/api/auth/me/avatar",
+            put(profile::upload_avatar)
+                .layer(DefaultBodyLimit::max(profile::AVATAR_MAX_BYTES + 64 * 1024))
+                .delete(profile::delete_avatar),
+        )
+        .route(
+            "/api/projects/import",
+            post(bundle::import_project).layer(DefaultBodyLimit::max(bundle::MAX_BUNDLE_BYTES)),
+        )
+        .route(
+            "/api/v2/runs/import/{format}",
+            post(result_import::import_report)
+                .layer(DefaultBodyLimit::max(result_import::max_report_bytes())),
+        )
+        .route(
+            "/api/v2/projects/{project_id}/testcases/import",
+            post(testcase_import::import_testcases)
+                .layer(DefaultBodyLimit::max(testcase_import::MAX_IMPORT_BYTES)),
+        )
+        .route(
+            "/api/v2/projects/{project_id}/testcases/import/gherkin",
+            post(gherkin::import_gherkin)
+                .layer(DefaultBodyLimit::max(testcase_import::MAX_IMPORT_BYTES)),
+        )
+        // Streamed and limited by record count, see `ingest::ingest_results`.
+        .route(
+            "/api/v2/runs/{run_id}/results/ingest",
+            post(ingest::ingest_results),
+        )
+        .route(
+            "/api/v2/runs/{run_id}/items/{run_item_id}/attachments",
+            post(attachments::upload_attachment)
+                .layer(DefaultBodyLimit::max(upload_body_limit))
+                .get(attachments::list_item_attachments),
+        );
+
     let app = Router::new()
-        .route("/health", get(health))
+        .route("/health", get(health::health))
+        .route("/health/live", get(health::live))
+        .route("/health/ready", get(health::ready))
         .route("/api/auth/register", post(register))
         .route("/api/auth/login", post(login))
-        .route("/api/auth/me", get(me))
+        .route("/api/auth/refresh", post(refresh))
+        .route("/api/auth/logout", post(revocation::logout))
+        .route("/api/auth/oidc/login", get(oidc::oidc_login))
+        .route("/api/auth/oidc/callback", get(oidc::oidc_callback))
+        .route(
+            "/api/auth/me",
+            get(me)
+                .patch(profile::update_profile)
+                .delete(account::delete_account),
+        )
+        .route("/api/auth/me/export", get(account::export_account))
+        .route(
+            "/api/auth/me/notifications",
+            get(notifications::get_settings).put(notifications::update_settings),
+        )
+        .route("/api/auth/me/password", post(profile::change_password))
+        .route("/api/auth/me/email/confirm", get(profile::confirm_email))
+        .route("/api/auth/password-reset", post(profile::reset_password))
+        .route("/api/auth/verify", get(profile::verify_email))
+        .route("/api/auth/me/verify", post(profile::resend_verification))
+        .route("/api/users/{user_id}/avatar", get(profile::get_avatar))
+        .route(
+            "/api/auth/api-keys",
+            post(api_keys::create_api_key).get(api_keys::list_api_keys),
+        )
+        .route(
+            "/api/auth/api-keys/{key_id}",
+            delete(api_keys::revoke_api_key),
+        )
         .route("/api/fail-reasons", get(list_fail_reasons))
+        .route("/api/admin/users", get(admin::list_users))
+        .route("/api/admin/database/pool", get(db_pool::pool_metrics))
+        .route("/api/admin/cache", get(cache::cache_metrics))
+        .route("/api/admin/users/{user_id}", patch(admin::update_user))
+        .route(
+            "/api/admin/users/{user_id}/deactivate",
+            post(admin::deactivate_user),
+        )
+        .route(
+            "/api/admin/users/{user_id}/reactivate",
+            post(admin::reactivate_user),
+        )
+        .route(
+            "/api/admin/users/{user_id}/password-reset",
+            post(admin::force_password_reset),
+        )
+        .route("/api/admin/projects", get(admin::list_projects))
+        .route(
+            "/api/admin/projects/{project_id}/owner",
+            put(admin::reassign_owner),
+        )
+        .route(
+            "/api/admin/projects/{project_id}/storage-quota",
+            put(quotas::set_storage_quota).delete(quotas::reset_storage_quota),
+        )
+        .route(
+            "/api/admin/service-accounts",
+            get(service_accounts::list_service_accounts)
+                .post(service_accounts::create_service_account),
+        )
+        .route(
+            "/api/admin/service-accounts/{user_id}",
+            delete(service_accounts::delete_service_account),
+        )
+        .route(
+            "/api/admin/service-accounts/{user_id}/api-keys",
+            get(service_accounts::list_service_account_keys)
+                .post(service_accounts::create_service_account_key),
+        )
+        .route(
+            "/api/admin/service-accounts/{user_id}/api-keys/{key_id}",
+            delete(service_accounts::revoke_service_account_key),
+        )
+        .route(
+            "/api/organizations",
+            post(organizations::create_organization).get(organizations::list_organizations),
+        )
+        .route(
+            "/api/organizations/{organization_id}",
+            get(organizations::get_organization).patch(organizations::update_organization),
+        )
+        .route(
+            "/api/organizations/{organization_id}/members",
+            post(organizations::add_organization_member),
+        )
+        .route(
+            "/api/organizations/{organization_id}/members/{user_id}",
+            patch(organizations::update_organization_member)
+                .delete(organizations::remove_organization_member),
+        )
         .route("/api/projects", get(list_projects).post(create_project))
-        .route("/api/projects/{project_id}/members", post(add_member).get(list_members))
+        .route("/api/projects/{project_id}", patch(update_project))
+        .route(
+            "/api/projects/{project_id}/invitations",
+            post(invitations::create_invitation).get(invitations::list_invitations),
+        )
+        .route(
+            "/api/projects/{project_id}/invitations/{invitation_id}",
+            delete(invitations::revoke_invitation),
+        )
+        .route("/api/invitations/{token}", get(invitations::get_invitation))
+        .route(
+            "/api/notifications/preferences",
+            get(notifications::get_preferences).put(notifications::update_preferences),
+        )
+        .route(
+            "/api/notifications/unsubscribe",
+            get(notifications::unsubscribe),
+        )
+        .route(
+            "/api/inbound-email",
+            post(email_reply::receive_inbound_email),
+        )
+        .route(
+            "/api/projects/{project_id}/members",
+            post(add_member).get(list_members),
+        )
         .route(
             "/api/projects/{project_id}/members/{user_id}",
             patch(update_member).delete(remove_member),
         )
         .route(
             "/api/projects/{project_id}/session",
-            get(get_session).put(save_session),
+            get(get_session)
+                .put(save_session)
+                .patch(session::patch_session),
+        )
+        .route(
+            "/api/projects/{project_id}/export",
+            get(bundle::export_project),
+        )
+        .route(
+            "/api/projects/{project_id}/roles",
+            get(permissions::list_roles),
+        )
+        .route(
+            "/api/projects/{project_id}/roles/{role_name}",
+            put(permissions::upsert_role).delete(permissions::delete_role),
         )
         .route("/api/v2/runs", post(create_run_v2).get(list_runs_v2))
-        .route("/api/v2/runs/{run_id}", get(get_run_details_v2))
+        .route("/api/v2/search", get(search::search))
+        .route(
+            "/api/v2/projects/{project_id}/suites",
+            post(suites::create_suite).get(suites::get_suite_tree),
+        )
+        .route("/api/v2/suites/{suite_id}", patch(suites::update_suite))
+        .route("/api/v2/suites/{suite_id}/move", post(suites::move_suite))
+        .route(
+            "/api/v2/projects/{project_id}/testcases",
+            get(testcases::list_testcases),
+        )
+        .route(
+            "/api/v2/projects/{project_id}/testcases/imports/{import_id}/errors",
+            get(testcase_import::download_import_errors),
+        )
+        .route(
+            "/api/v2/projects/{project_id}/requirements",
+            get(requirements::list_requirements).post(requirements::create_requirement),
+        )
+        .route(
+            "/api/v2/requirements/{requirement_id}",
+            patch(requirements::update_requirement).delete(requirements::delete_requirement),
+        )
+        .route(
+            "/api/v2/requirements/{requirement_id}/testcases",
+            put(requirements::set_requirement_testcases),
+        )
+        .route(
+            "/api/v2/projects/{project_id}/traceability",
+            get(requirements::get_traceability),
+        )
+        .route(
+            "/api/v2/projects/{project_id}/fail-reasons",
+            get(fail_reasons::list_project_fail_reasons)
+                .post(fail_reasons::create_project_fail_reason),
+        )
+        .route(
+            "/api/v2/projects/{project_id}/fail-reasons/{code}",
+            patch(fail_reasons::update_project_fail_reason)
+                .delete(fail_reasons::delete_project_fail_reason),
+        )
+        .route(
+            "/api/v2/projects/{project_id}/milestones",
+            get(milestones::list_milestones).post(milestones::create_milestone),
+        )
+        .route(
+            "/api/v2/milestones/{milestone_id}",
+            patch(milestones::update_milestone).delete(milestones::delete_milestone),
+        )
+        .route(
+            "/api/v2/milestones/{milestone_id}/burndown",
+            get(milestones::milestone_burndown),
+        )
+        .route(
+            "/api/v2/projects/{project_id}/webhooks",
+            post(webhooks::create_webhook).get(webhooks::list_webhooks),
+        )
+        .route(
+            "/api/v2/webhooks/{webhook_id}",
+            delete(webhooks::delete_webhook),
+        )
+        .route(
+            "/api/v2/webhooks/{webhook_id}/deliveries",
+            get(webhooks::list_deliveries),
+        )
+        .route(
+            "/api/v2/projects/{project_id}/chat-webhooks",
+            get(chat::list_chat_webhooks).post(chat::create_chat_webhook),
+        )
+        .route(
+            "/api/v2/projects/{project_id}/chat-webhooks/{webhook_id}",
+            patch(chat::update_chat_webhook).delete(chat::delete_chat_webhook),
+        )
+        .route(
+            "/api/v2/projects/{project_id}/chat-webhooks/{webhook_id}/test",
+            post(chat::test_chat_webhook),
+        )
+        .route(
+            "/api/v2/projects/{project_id}/audit-log",
+            get(audit::list_project_audit),
+        )
+        .route(
+            "/api/v2/projects/{project_id}/audit-log/export",
+            get(audit::export_project_audit),
+        )
+        .route(
+            "/api/v2/projects/{project_id}/runs/export",
+            get(export::export_project_runs),
+        )
+        .route(
+            "/api/v2/testcases/{testcase_id}/suite",
+            patch(suites::assign_testcase_suite),
+        )
+        .route(
+            "/api/v2/projects/{project_id}/custom-fields",
+            get(custom_fields::list_custom_fields).post(custom_fields::create_custom_field),
+        )
+        .route(
+            "/api/v2/projects/{project_id}/custom-fields/{field_id}",
+            patch(custom_fields::update_custom_field).delete(custom_fields::delete_custom_field),
+        )
+        .route(
+            "/api/v2/testcases/{testcase_id}/custom-fields",
+            patch(custom_fields::update_testcase_values),
+        )
+        .route(
+            "/api/v2/runs/{run_id}/custom-fields",
+            patch(custom_fields::update_run_values),
+        )
+        .route(
+            "/api/v2/projects/{project_id}/saved-filters",
+            get(saved_filters::list_saved_filters).post(saved_filters::create_saved_filter),
+        )
+        .route(
+            "/api/v2/projects/{project_id}/saved-filters/{filter_id}",
+            patch(saved_filters::update_saved_filter).delete(saved_filters::delete_saved_filter),
+        )
+        .route(
+            "/api/v2/projects/{project_id}/reports",
+            get(custom_reports::list_reports).post(custom_reports::create_report),
+        )
+        .route(
+            "/api/v2/projects/{project_id}/reports/preview",
+            post(custom_reports::preview_report),
+        )
+        .route(
+            "/api/v2/projects/{project_id}/reports/{report_id}",
+            put(custom_reports::update_report).delete(custom_reports::delete_report),
+        )
+        .route(
+            "/api/v2/projects/{project_id}/reports/{report_id}/result",
+            get(custom_reports::run_report),
+        )
+        .route(
+            "/api/v2/testcases/{testcase_id}/tags",
+            get(tags::get_testcase_tags)
+                .put(tags::replace_testcase_tags)
+                .post(tags::add_testcase_tags),
+        )
+        .route(
+            "/api/v2/projects/{project_id}/tags",
+            get(tags::list_project_tags),
+        )
+        .route(
+            "/api/v2/projects/{project_id}/tags/{tag}",
+            patch(tags::rename_project_tag).delete(tags::delete_project_tag),
+        )
+        .route(
+            "/api/v2/testcases/{testcase_id}/versions",
+            get(testcases::list_testcase_versions).post(testcases::create_testcase_version),
+        )
+        .route(
+            "/api/v2/testcases/{testcase_id}",
+            delete(testcases::delete_testcase),
+        )
+        .route(
+            "/api/v2/testcases/{testcase_id}/archive",
+            post(testcases::archive_testcase),
+        )
+        .route(
+            "/api/v2/testcases/{testcase_id}/restore",
+            post(testcases::restore_testcase),
+        )
+        .route(
+            "/api/v2/testcases/{testcase_id}/usage",
+            get(testcases::get_testcase_usage),
+        )
+        .route(
+            "/api/v2/testcases/{testcase_id}/executions",
+            get(result_history::list_testcase_executions),
+        )
+        .route(
+            "/api/v2/projects/{project_id}/testcases/duplicates",
+            get(dedup::list_duplicates),
+        )
+        .route(
+            "/api/v2/testcases/{testcase_id}/merge",
+            post(dedup::merge_testcase),
+        )
+        .route(
+            "/api/v2/runs/{run_id}",
+            get(get_run_details_v2).patch(update_run_v2),
+        )
         .route("/api/v2/runs/{run_id}/status", patch(update_run_status_v2))
+        .route("/api/v2/runs/{run_id}/unlock", post(unlock_run_v2))
+        .route("/api/v2/runs/{run_id}/clone", post(clone_run_v2))
+        .route("/api/v2/runs/{run_id}/summary", get(get_run_summary_v2))
+        .route("/api/v2/runs/{run_id}/export", get(export::export_run))
+        .route("/api/v2/runs/{run_id}/diff", get(run_diff::diff_runs))
+        .route(
+            "/api/v2/runs/{run_id}/report.pdf",
+            get(report::run_report_pdf),
+        )
+        .route(
+            "/api/v2/runs/{run_id}/report-jobs",
+            post(report::queue_run_report),
+        )
+        .route("/api/v2/jobs/{job_id}", get(jobs::get_job))
+        .route(
+            "/api/v2/jobs/{job_id}/download",
+            get(jobs::download_job_file),
+        )
         .route("/api/v2/runs/{run_id}/items", post(add_run_item_v2))
+        .route(
+            "/api/v2/runs/{run_id}/items/bulk",
+            post(bulk_add_run_items_v2),
+        )
+        .route(
+            "/api/v2/runs/{run_id}/items/reorder",
+            patch(reorder_run_items_v2),
+        )
+        .route(
+            "/api/v2/runs/{run_id}/items/{run_item_id}",
+            delete(delete_run_item_v2),
+        )
+        .route(
+            "/api/v2/runs/{run_id}/assignee",
+            patch(assignments::set_run_default_assignee),
+        )
+        .route(
+            "/api/v2/runs/{run_id}/executor",
+            patch(assignments::set_run_executor),
+        )
+        .route(
+            "/api/v2/runs/{run_id}/items/{run_item_id}/assignee",
+            patch(assignments::set_item_assignee),
+        )
+        .route(
+            "/api/v2/projects/{project_id}/usage",
+            get(quotas::project_usage),
+        )
+        .route(
+            "/api/v2/projects/{project_id}/retention",
+            get(retention::get_retention).put(retention::set_retention),
+        )
+        .route(
+            "/api/v2/projects/{project_id}/retention/preview",
+            get(retention::preview_retention),
+        )
+        .route(
+            "/api/v2/projects/{project_id}/assets",
+            get(assets::list_assets).post(assets::create_asset),
+        )
+        .route(
+            "/api/v2/projects/{project_id}/assets/{asset_id}",
+            get(assets::get_asset)
+                .patch(assets::update_asset)
+                .delete(assets::delete_asset),
+        )
+        .route(
+            "/api/v2/projects/{project_id}/assets/{asset_id}/versions",
+            get(assets::list_asset_versions),
+        )
+        .route(
+            "/api/v2/runs/{run_id}/items/{run_item_id}/dependencies",
+            put(dependencies::set_item_dependencies),
+        )
+        .route(
+            "/api/v2/my/assignments",
+            get(assignments::list_my_assignments),
+        )
+        .route("/api/v2/me/dashboard", get(dashboard::get_dashboard))
+        .route("/api/v2/me/notifications", get(inbox::list_notifications))
+        .route("/api/v2/me/notifications/read", post(inbox::mark_read))
+        .route(
+            "/api/v2/me/notifications/read-all",
+            post(inbox::mark_all_read),
+        )
+        .route(
+            "/api/v2/me/telegram",
+            get(telegram::get_link).delete(telegram::delete_link),
+        )
+        .route("/api/v2/me/telegram/link", post(telegram::create_link_code))
         .route(
             "/api/v2/runs/{run_id}/items/{run_item_id}/result",
             patch(update_run_result_v2),
         )
+        .route(
+            "/api/v2/runs/{run_id}/results/bulk",
+            patch(bulk_update_run_results_v2),
+        )
+        .route(
+            "/api/v2/runs/{run_id}/items/{run_item_id}/history",
+            get(result_history::get_result_history),
+        )
+        .route(
+            "/api/v2/runs/{run_id}/comments",
+            get(comments::list_comments).post(comments::create_comment),
+        )
+        .route(
+            "/api/v2/comments/{comment_id}",
+            patch(comments::update_comment).delete(comments::delete_comment),
+        )
+        .route("/api/v2/runs/{run_id}/ws", get(live::run_socket))
+        .route(
+            "/api/projects/{project_id}/events",
+            get(live::project_events),
+        )
+        .route(
+            "/api/v2/runs/{run_id}/items/{run_item_id}/defects",
+            post(defects::link_defect),
+        )
+        .route(
+            "/api/v2/runs/{run_id}/items/{run_item_id}/jira-issue",
+            post(jira::create_jira_issue),
+        )
+        .route(
+            "/api/v2/projects/{project_id}/schedules",
+            get(schedules::list_schedules).post(schedules::create_schedule),
+        )
+        .route(
+            "/api/v2/projects/{project_id}/schedules/{schedule_id}",
+            put(schedules::update_schedule).delete(schedules::delete_schedule),
+        )
+        .route(
+            "/api/v2/projects/{project_id}/schedules/{schedule_id}/run",
+            post(schedules::run_schedule_now),
+        )
+        .route(
+            "/api/v2/projects/{project_id}/ci",
+            get(ci::get_ci_connection)
+                .put(ci::put_ci_connection)
+                .delete(ci::delete_ci_connection),
+        )
+        .route(
+            "/api/v2/projects/{project_id}/jira",
+            get(jira::get_jira_connection)
+                .put(jira::put_jira_connection)
+                .delete(jira::delete_jira_connection),
+        )
+        .route(
+            "/api/v2/defects/{defect_id}",
+            delete(defects::unlink_defect),
+        )
+        .route(
+            "/api/v2/projects/{project_id}/analytics",
+            get(analytics::project_analytics),
+        )
+        .route("/api/v2/reports/overview", get(overview::reports_overview))
+        .route(
+            "/api/v2/projects/{project_id}/effort",
+            get(effort::project_effort),
+        )
+        .route(
+            "/api/v2/projects/{project_id}/issue-tracker",
+            get(defects::get_issue_tracker).put(defects::put_issue_tracker),
+        )
+        .route(
+            "/api/v2/attachments/{attachment_id}",
+            delete(attachments::delete_attachment),
+        )
+        .route(
+            "/api/v2/attachments/{attachment_id}/download",
+            get(attachments::download_attachment),
+        )
+        .layer(RequestBodyLimitLayer::new(config.request_body_max_bytes))
+        .merge(uploads)
+        .merge(SwaggerUi::new("/api/docs").url("/api/openapi.json", openapi::ApiDoc::openapi()))
         .route("/api/{*path}", any(api_not_found))
         .fallback_service(static_service)
+        .layer(middleware::from_fn(etag::conditional_get))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            idempotency::replay,
+        ))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            api_keys::authenticate,
+        ))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            db_pool::shed_load,
+        ))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            rate_limit::enforce,
+        ))
+        .layer(middleware::from_fn(error::structured_body_errors))
+        .layer(middleware::from_fn(error::negotiate_language))
+        .layer(CompressionLayer::new())
         .layer(CorsLayer::permissive())
         .layer(TraceLayer::new_for_http())
-        .with_state(state);
-
-    info!("uran-api listening on http://{}", addr);
+        .with_state(state.clone());
+
+    info!("uran-api listening on http://{}", config.addr);
+
+    // After a signal the listener stops accepting and in-flight requests finish; connections
+    // still open after `shutdown_timeout` (e.g. run WebSockets) are dropped.
+    let (stop_tx, mut stop_rx) = tokio::sync::watch::channel(false);
+    let listener = tokio::net::TcpListener::bind(config.addr).await?;
+    if let Some(grpc_addr) = config.grpc_addr {
+        let grpc_listener = tokio::net::TcpListener::bind(grpc_addr).await?;
+        let mut grpc_stop = stop_rx.clone();
+        let grpc_state = state.clone();
+        info!("uran-api gRPC listening on {grpc_addr}");
+        // Streaming calls never finish on their own; like WebSockets they are cut off when
+        // the process exits.
+        tokio::spawn(async move {
+            let shutdown = async move {
+                let _ = grpc_stop.wait_for(|stopping| *stopping).await;
+            };
+            if let Err(err) = grpc::serve(grpc_state, grpc_listener, shutdown).await {
+                warn!("gRPC server failed: {err}");
+            }
+        });
+    }
+    let server = axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(async move {
+        shutdown::signal().await;
+        let _ = stop_tx.send(true);
+    });
+    let drain_deadline = async {
+        if stop_rx.wait_for(|stopping| *stopping).await.is_ok() {
+            tokio::time::sleep(shutdown_timeout).await;
+        } else {
+            std::future::pending::<()>().await;
+        }
+    };
+    tokio::select! {
+        result = async { server.await } => result?,
+        _ = drain_deadline => warn!("connections still open after {shutdown_timeout:?}, closing them"),
+    }
 
-    let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(listener, app).await?;
+    state.jobs.stop();
+    if tokio::time::timeout(shutdown_timeout, job_workers)
+        .await
+        .is_err()
+    {
+        warn!("running jobs did not finish in {shutdown_timeout:?}; they will be retried");
+    }
+    state.db.close().await;
+    info!("uran-api stopped");
     Ok(())
 }
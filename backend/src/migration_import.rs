@@ -0,0 +1,757 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+};
+
+use anyhow::{anyhow, bail, Context};
+use chrono::{DateTime, Utc};
+use serde_json::json;
+use sqlx::{PgPool, Postgres, Transaction};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::{
+    audit::{self, AuditEntry},
+    config::MigrationConfig,
+    connect_db, ensure_db_user_row,
+    error::Lang,
+    now_iso, read_projects, read_users,
+    result_import::{clip, MAX_COMMENT_CHARS},
+    testrail, write_projects_file, xray, Project, ProjectMember, ProjectSettings,
+};
+
+const USAGE: &str = "usage: uran-api import-testrail --url URL --token TOKEN \
+                     [--source testrail|xray] [--project ID]... [--owner EMAIL]";
+/// Testcases written per transaction.
+const CASE_BATCH: usize = 200;
+
+/// A project of the source instance.
+pub struct SourceProject {
+    pub id: String,
+    pub name: String,
+}
+
+/// A suite or section; parents are listed before their children.
+pub struct SourceSuite {
+    pub id: String,
+    pub parent_id: Option<String>,
+    pub name: String,
+    pub description: String,
+}
+
+pub struct SourceCase {
+    pub id: String,
+    pub suite_id: String,
+    /// Testcase key in the imported project, e.g. `C1234` or the Jira issue key.
+    pub key: String,
+    pub title: String,
+    pub preconditions: String,
+    /// Step actions and their expected results, of the same length.
+    pub steps: Vec<String>,
+    pub expected: Vec<String>,
+    pub estimated_minutes: Option<i32>,
+}
+
+/// A run without its tests, which are fetched only when the run is imported.
+pub struct SourceRun {
+    pub id: String,
+    pub title: String,
+    pub milestone: Option<String>,
+    pub author_email: Option<String>,
+    pub created_at: Option<DateTime<Utc>>,
+    /// Set for a closed run; it is imported as `done`.
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+pub struct SourceTest {
+    pub case_id: String,
+    /// The latest result; `None` while the test is untested.
+    pub result: Option<SourceResult>,
+}
+
+pub struct SourceResult {
+    pub status: &'static str,
+    pub comment: String,
+    pub elapsed_seconds: Option<i32>,
+    pub author_email: Option<String>,
+    pub recorded_at: Option<DateTime<Utc>>,
+    /// Outcomes of the testcase steps by position; `None` for a step without one.
+    pub steps: Vec<Option<(&'static str, String)>>,
+}
+
+#[derive(Clone, Copy)]
+enum SourceKind {
+    TestRail,
+    Xray,
+}
+
+impl SourceKind {
+    fn name(self) -> &'static str {
+        match self {
+            SourceKind::TestRail => "testrail",
+            SourceKind::Xray => "xray",
+        }
+    }
+}
+
+struct Options {
+    url: String,
+    token: String,
+    source: SourceKind,
+    projects: Vec<String>,
+    owner: Option<String>,
+}
+
+impl Options {
+    fn parse(args: &[String]) -> anyhow::Result<Self> {
+        let (mut url, mut token, mut source, mut owner) = (None, None, SourceKind::TestRail, None);
+        let mut projects = Vec::new();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .filter(|v| !v.trim().is_empty())
+                    .map(|v| v.trim().to_string())
+                    .ok_or_else(|| anyhow!("{arg} needs a value; {USAGE}"))
+            };
+            match arg.as_str() {
+                "--url" => url = Some(value()?.trim_end_matches('/').to_string()),
+                "--token" => token = Some(value()?),
+                "--source" => {
+                    source = match value()?.as_str() {
+                        "testrail" => SourceKind::TestRail,
+                        "xray" => SourceKind::Xray,
+                        other => bail!("unknown source {other}; {USAGE}"),
+                    }
+                }
+                "--project" => projects.push(value()?),
+                "--owner" => owner = Some(value()?.to_lowercase()),
+                other => bail!("unknown argument {other}; {USAGE}"),
+            }
+        }
+        let (Some(url), Some(token)) = (url, token) else {
+            bail!("--url and --token are required; {USAGE}");
+        };
+        Ok(Self {
+            url,
+            token,
+            source,
+            projects,
+            owner,
+        })
+    }
+}
+
+enum Client {
+    TestRail(testrail::Client),
+    Xray(xray::Client),
+}
+
+impl Client {
+    async fn projects(&self, selected: &[String]) -> anyhow::Result<Vec<SourceProject>> {
+        match self {
+            Client::TestRail(client) => client.projects(selected).await,
+            Client::Xray(client) => client.projects(selected).await,
+        }
+    }
+
+    async fn suites(&self, project: &SourceProject) -> anyhow::Result<Vec<SourceSuite>> {
+        match self {
+            Client::TestRail(client) => client.suites(project).await,
+            Client::Xray(client) => client.suites(project).await,
+        }
+    }
+
+    async fn cases(&self, project: &SourceProject) -> anyhow::Result<Vec<SourceCase>> {
+        match self {
+            Client::TestRail(client) => client.cases(project).await,
+            Client::Xray(client) => client.cases(project).await,
+        }
+    }
+
+    async fn runs(&self, project: &SourceProject) -> anyhow::Result<Vec<SourceRun>> {
+        match self {
+            Client::TestRail(client) => client.runs(project).await,
+            Client::Xray(client) => client.runs(project).await,
+        }
+    }
+
+    async fn tests(&self, run: &SourceRun) -> anyhow::Result<Vec<SourceTest>> {
+        match self {
+            Client::TestRail(client) => client.tests(run).await,
+            Client::Xray(client) => client.tests(run).await,
+        }
+    }
+}
+
+/// `name` trimmed and clipped to what the column takes, or `fallback` when too short.
+fn fit(name: &str, fallback: &str, min_chars: usize, max_chars: usize) -> String {
+    let name = clip(name.trim(), max_chars);
+    if name.trim().chars().count() >= min_chars {
+        name
+    } else {
+        clip(fallback, max_chars)
+    }
+}
+
+async fn remember(
+    tx: &mut Transaction<'_, Postgres>,
+    source: &str,
+    entity: &str,
+    source_id: &str,
+    local_id: Uuid,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        INSERT INTO migration_imports (source, entity, source_id, local_id)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (source, entity, source_id)
+        DO UPDATE SET local_id = EXCLUDED.local_id, imported_at = NOW()
+        "#,
+        source,
+        entity,
+        source_id,
+        local_id,
+    )
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+#[derive(Default)]
+struct RunCounts {
+    items: usize,
+    results: usize,
+    steps: usize,
+    missing_cases: usize,
+}
+
+/// Writes what the client returns; every entity is recorded in `migration_imports` in the
+/// transaction that creates it.
+struct Importer {
+    db: PgPool,
+    projects_file: PathBuf,
+    /// `testrail:https://example.testrail.io`
+    source: String,
+    owner_id: String,
+    owner: Uuid,
+    /// Local account ids by lowercased email.
+    users: HashMap<String, String>,
+    synced_users: HashSet<Uuid>,
+}
+
+impl Importer {
+    /// Source ids already imported whose rows still exist.
+    async fn mapped(&self, entity: &str, table: &str) -> anyhow::Result<HashMap<String, Uuid>> {
+        let rows: Vec<(String, Uuid)> = sqlx::query_as(&format!(
+            "SELECT m.source_id, m.local_id FROM migration_imports m \
+             JOIN {table} t ON t.id = m.local_id \
+             WHERE m.source = $1 AND m.entity = $2"
+        ))
+        .bind(&self.source)
+        .bind(entity)
+        .fetch_all(&self.db)
+        .await?;
+        Ok(rows.into_iter().collect())
+    }
+
+    /// The local account with this email, or the owner.
+    async fn user(&mut self, email: Option<&str>) -> anyhow::Result<Uuid> {
+        let Some(user_id) = email.and_then(|e| self.users.get(&e.trim().to_lowercase())) else {
+            return Ok(self.owner);
+        };
+        let uuid = Uuid::parse_str(user_id)?;
+        if self.synced_users.insert(uuid) {
+            ensure_db_user_row(&self.db, user_id)
+                .await
+                .map_err(|err| anyhow!(err.message(Lang::En)))?;
+        }
+        Ok(uuid)
+    }
+
+    /// Creates the project owned by the owner, unless an earlier run already did. Like the
+    /// bundle import, `projects.json` is written before the commit.
+    async fn project(&self, project: &SourceProject) -> anyhow::Result<Uuid> {
+        if let Some(id) = self.mapped("project", "projects").await?.get(&project.id) {
+            info!("{}: project already imported, resuming", project.name);
+            return Ok(*id);
+        }
+        let project_id = Uuid::new_v4();
+        let name = fit(&project.name, &format!("Project {}", project.id), 3, 160);
+        let mut tx = self.db.begin().await?;
+        sqlx::query!(
+            "INSERT INTO projects (id, name, owner_user_id) VALUES ($1, $2, $3)",
+            project_id,
+            &name,
+            self.owner,
+        )
+        .execute(&mut *tx)
+        .await?;
+        remember(&mut tx, &self.source, "project", &project.id, project_id).await?;
+        audit::record(
+            &mut *tx,
+            AuditEntry {
+                actor_user_id: Some(self.owner),
+                action: "create",
+                entity_type: "project",
+                entity_id: Some(project_id),
+                project_id: Some(project_id),
+                run_id: None,
+                before: None,
+                after: Some(json!({
+                    "name": &name,
+                    "importedFrom": &self.source,
+                    "sourceId": &project.id,
+                })),
+            },
+        )
+        .await?;
+
+        let now = now_iso();
+        let mut projects = read_projects(&self.projects_file).await?;
+        projects.push(Project {
+            id: project_id.to_string(),
+            name,
+            owner_id: self.owner_id.clone(),
+            created_at: now.clone(),
+            updated_at: now,
+            members: vec![ProjectMember {
+                user_id: self.owner_id.clone(),
+                role: "owner".to_string(),
+            }],
+            roles: Vec::new(),
+            settings: ProjectSettings::default(),
+            invitations: Vec::new(),
+            session: None,
+            session_version: 0,
+            organization_id: None,
+            organization_admins: Vec::new(),
+        });
+        write_projects_file(&self.projects_file, &projects).await?;
+        tx.commit().await?;
+        info!("{}: project created", project.name);
+        Ok(project_id)
+    }
+
+    /// Creates the suites not imported yet, in one transaction; returns all of them.
+    async fn suites(
+        &self,
+        project: &SourceProject,
+        project_id: Uuid,
+        suites: &[SourceSuite],
+    ) -> anyhow::Result<HashMap<String, Uuid>> {
+        let mut ids = self.mapped("suite", "test_suites").await?;
+        let mut created = 0;
+        let mut tx = self.db.begin().await?;
+        for (position, suite) in suites.iter().enumerate() {
+            if ids.contains_key(&suite.id) {
+                continue;
+            }
+            let parent_id = match &suite.parent_id {
+                Some(parent) => Some(*ids.get(parent).with_context(|| {
+                    format!("suite {} is listed before its parent {parent}", suite.id)
+                })?),
+                None => None,
+            };
+            let id = Uuid::new_v4();
+            sqlx::query!(
+                r#"
+                INSERT INTO test_suites (
+                  id, project_id, parent_id, key, name, description, position,
+                  created_by_user_id, updated_by_user_id
+                )
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $8)
+                "#,
+                id,
+                project_id,
+                parent_id,
+                &suite.id,
+                fit(&suite.name, &suite.id, 2, 200),
+                &suite.description,
+                position as i32,
+                self.owner,
+            )
+            .execute(&mut *tx)
+            .await?;
+            remember(&mut tx, &self.source, "suite", &suite.id, id).await?;
+            ids.insert(suite.id.clone(), id);
+            created += 1;
+        }
+        tx.commit().await?;
+        info!(
+            "{}: {created} suites imported, {} already there",
+            project.name,
+            suites.len() - created
+        );
+        Ok(ids)
+    }
+
+    /// Creates the testcases not imported yet with their first version, 200 per transaction,
+    /// so an interruption loses at most one batch; returns all of them.
+    async fn cases(
+        &self,
+        project: &SourceProject,
+        suites: &HashMap<String, Uuid>,
+        cases: &[SourceCase],
+    ) -> anyhow::Result<HashMap<String, Uuid>> {
+        let mut ids = self.mapped("testcase", "testcases").await?;
+        let pending: Vec<&SourceCase> = cases.iter().filter(|c| !ids.contains_key(&c.id)).collect();
+        let change_note = format!("Imported from {}", self.source);
+        let mut done = 0;
+        for batch in pending.chunks(CASE_BATCH) {
+            let mut tx = self.db.begin().await?;
+            let mut created = Vec::with_capacity(batch.len());
+            for case in batch {
+                let Some(suite_id) = suites.get(&case.suite_id) else {
+                    warn!(
+                        "{}: case {} is in unknown suite {}",
+                        project.name, case.id, case.suite_id
+                    );
+                    continue;
+                };
+                let title = fit(&case.title, &case.key, 2, 240);
+                let estimated_minutes = case.estimated_minutes.filter(|m| *m > 0);
+                let id = sqlx::query_scalar!(
+                    r#"
+                    INSERT INTO testcases (
+                      suite_id, key, title, estimated_minutes, created_by_user_id,
+                      updated_by_user_id
+                    )
+                    VALUES ($1, $2, $3, $4, $5, $5)
+                    RETURNING id
+                    "#,
+                    suite_id,
+                    &case.key,
+                    &title,
+                    estimated_minutes,
+                    self.owner,
+                )
+                .fetch_one(&mut *tx)
+                .await
+                .with_context(|| format!("case {}", case.id))?;
+                sqlx::query!(
+                    r#"
+                    INSERT INTO testcase_versions (
+                      testcase_id, version_number, summary, preconditions, steps_json,
+                      expected_json, estimated_minutes, change_note, created_by_user_id
+                    )
+                    VALUES ($1, 1, $2, $3, $4, $5, $6, $7, $8)
+                    "#,
+                    id,
+                    &title,
+                    &case.preconditions,
+                    json!(case.steps),
+                    json!(case.expected),
+                    estimated_minutes,
+                    &change_note,
+                    self.owner,
+                )
+                .execute(&mut *tx)
+                .await
+                .with_context(|| format!("case {}", case.id))?;
+                remember(&mut tx, &self.source, "testcase", &case.id, id).await?;
+                created.push((case.id.clone(), id));
+            }
+            tx.commit().await?;
+            done += batch.len();
+            ids.extend(created);
+            info!("{}: testcases {done}/{}", project.name, pending.len());
+        }
+        info!(
+            "{}: {} testcases imported, {} already there",
+            project.name,
+            pending.len(),
+            cases.len() - pending.len()
+        );
+        Ok(ids)
+    }
+
+    /// Creates the run with an item per test whose case was imported, in one transaction.
+    /// A closed run becomes `done`, one with results `in_progress`, the rest stay drafts.
+    async fn run(
+        &mut self,
+        project_id: Uuid,
+        cases: &HashMap<String, Uuid>,
+        run: &SourceRun,
+        tests: &[SourceTest],
+    ) -> anyhow::Result<RunCounts> {
+        let executor = self.user(run.author_email.as_deref()).await?;
+        let mut result_authors = Vec::with_capacity(tests.len());
+        for test in tests {
+            let author = match &test.result {
+                Some(result) => self.user(result.author_email.as_deref()).await?,
+                None => executor,
+            };
+            result_authors.push(author);
+        }
+
+        let now = Utc::now();
+        let created_at = run.created_at.unwrap_or(now);
+        let first_result = tests
+            .iter()
+            .filter_map(|t| t.result.as_ref()?.recorded_at)
+            .min();
+        let started_at = first_result.map_or(created_at, |first| first.min(created_at));
+        let (status, started_at, finished_at) = match run.completed_at {
+            Some(completed) => ("done", Some(started_at), Some(completed.max(started_at))),
+            None if tests.iter().any(|t| t.result.is_some()) => {
+                ("in_progress", Some(started_at), None)
+            }
+            None => ("draft", None, None),
+        };
+        let milestone = run
+            .milestone
+            .as_deref()
+            .map(|m| clip(m.trim(), 200))
+            .filter(|m| !m.is_empty());
+
+        let run_id = Uuid::new_v4();
+        let mut tx = self.db.begin().await?;
+        sqlx::query!(
+            r#"
+            INSERT INTO runs (
+              id, project_id, title, status, executed_by_user_id, started_at, finished_at,
+              created_at, milestone
+            )
+            VALUES ($1, $2, $3, $4::text::run_status, $5, $6, $7, $8, $9)
+            "#,
+            run_id,
+            project_id,
+            fit(&run.title, &format!("Run {}", run.id), 1, 500),
+            status,
+            executor,
+            started_at,
+            finished_at,
+            created_at,
+            milestone,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let mut counts = RunCounts::default();
+        let mut versions = HashSet::new();
+        for (test, author) in tests.iter().zip(result_authors) {
+            let Some(testcase_id) = cases.get(&test.case_id) else {
+                counts.missing_cases += 1;
+                continue;
+            };
+            let version_id = sqlx::query_scalar!(
+                r#"
+                SELECT id FROM testcase_versions
+                WHERE testcase_id = $1
+                ORDER BY version_number DESC
+                LIMIT 1
+                "#,
+                testcase_id,
+            )
+            .fetch_one(&mut *tx)
+            .await?;
+            if !versions.insert(version_id) {
+                continue;
+            }
+            counts.items += 1;
+            let item_id = Uuid::new_v4();
+            let result = test.result.as_ref();
+            sqlx::query!(
+                r#"
+                WITH item AS (
+                  INSERT INTO run_items (id, run_id, testcase_version_id, position, is_required)
+                  VALUES ($1, $2, $3, $4, TRUE)
+                  RETURNING id
+                )
+                INSERT INTO run_results (
+                  run_item_id, status, comment, elapsed_seconds, updated_by_user_id, updated_at
+                )
+                SELECT id, $5::text::result_status, $6, $7, $8, COALESCE($9, NOW()) FROM item
+                "#,
+                item_id,
+                run_id,
+                version_id,
+                counts.items as i32,
+                result.map_or("na", |r| r.status),
+                result.map_or(String::new(), |r| clip(&r.comment, MAX_COMMENT_CHARS)),
+                result.and_then(|r| r.elapsed_seconds),
+                author,
+                result.and_then(|r| r.recorded_at),
+            )
+            .execute(&mut *tx)
+            .await?;
+            let Some(result) = result else {
+                continue;
+            };
+            counts.results += 1;
+            if result.steps.iter().all(Option::is_none) {
+                continue;
+            }
+            let step_ids = sqlx::query_scalar!(
+                r#"
+                SELECT id FROM testcase_steps
+                WHERE testcase_version_id = $1
+                ORDER BY position ASC
+                "#,
+                version_id,
+            )
+            .fetch_all(&mut *tx)
+            .await?;
+            for (step_id, step) in step_ids.iter().zip(&result.steps) {
+                let Some((status, comment)) = step else {
+                    continue;
+                };
+                sqlx::query!(
+                    r#"
+                    INSERT INTO run_result_steps (
+                      run_item_id, step_id, status, comment, updated_by_user_id, updated_at
+                    )
+                    VALUES ($1, $2, $3::text::result_status, $4, $5, COALESCE($6, NOW()))
+                    "#,
+                    item_id,
+                    step_id,
+                    status,
+                    clip(comment, MAX_COMMENT_CHARS),
+                    author,
+                    result.recorded_at,
+                )
+                .execute(&mut *tx)
+                .await?;
+                counts.steps += 1;
+            }
+        }
+        remember(&mut tx, &self.source, "run", &run.id, run_id).await?;
+        tx.commit().await?;
+        Ok(counts)
+    }
+}
+
+async fn import(config: MigrationConfig, options: Options, db: &PgPool) -> anyhow::Result<()> {
+    let table = sqlx::query_scalar!("SELECT to_regclass('migration_imports')::text")
+        .fetch_one(db)
+        .await?;
+    if table.is_none() {
+        bail!("the schema is not up to date; run `uran-api --migrate-only` first");
+    }
+
+    let data_dir = config.repo_root.join("backend").join("data");
+    let users: HashMap<String, String> = read_users(&data_dir.join("users.json"))
+        .await?
+        .into_iter()
+        .map(|u| (u.email.trim().to_lowercase(), u.id))
+        .collect();
+    let owner_email = options
+        .owner
+        .clone()
+        .or_else(|| config.admin_emails.first().cloned())
+        .context("pass --owner or set ADMIN_EMAILS: imported projects need an owner")?;
+    let owner_id = users
+        .get(&owner_email)
+        .cloned()
+        .with_context(|| format!("no local account with email {owner_email}"))?;
+    ensure_db_user_row(db, &owner_id)
+        .await
+        .map_err(|err| anyhow!(err.message(Lang::En)))?;
+
+    let client = match options.source {
+        SourceKind::TestRail => {
+            Client::TestRail(testrail::Client::connect(&options.url, &options.token).await?)
+        }
+        SourceKind::Xray => {
+            Client::Xray(xray::Client::connect(&options.url, &options.token).await?)
+        }
+    };
+    let mut importer = Importer {
+        db: db.clone(),
+        projects_file: data_dir.join("projects.json"),
+        source: format!("{}:{}", options.source.name(), options.url),
+        owner: Uuid::parse_str(&owner_id)?,
+        owner_id,
+        users,
+        synced_users: HashSet::new(),
+    };
+
+    let projects = client.projects(&options.projects).await?;
+    if projects.is_empty() {
+        bail!("{} has no projects to import", options.url);
+    }
+    info!(
+        "importing {} project(s) from {}",
+        projects.len(),
+        importer.source
+    );
+    for project in &projects {
+        let project_id = importer.project(project).await?;
+        let suites = client.suites(project).await?;
+        let suites = importer.suites(project, project_id, &suites).await?;
+        let cases = client.cases(project).await?;
+        let cases = importer.cases(project, &suites, &cases).await?;
+
+        let imported_runs = importer.mapped("run", "runs").await?;
+        let runs: Vec<SourceRun> = client
+            .runs(project)
+            .await?
+            .into_iter()
+            .filter(|run| !imported_runs.contains_key(&run.id))
+            .collect();
+        for (idx, run) in runs.iter().enumerate() {
+            let tests = client.tests(run).await?;
+            let counts = importer.run(project_id, &cases, run, &tests).await?;
+            info!(
+                "{}: run {}/{} \"{}\": {} items, {} results, {} step results",
+                project.name,
+                idx + 1,
+                runs.len(),
+                run.title,
+                counts.items,
+                counts.results,
+                counts.steps
+            );
+            if counts.missing_cases > 0 {
+                warn!(
+                    "{}: run \"{}\": {} tests skipped, their cases were not imported",
+                    project.name, run.title, counts.missing_cases
+                );
+            }
+        }
+        info!(
+            "{}: done, {} runs imported, {} already there",
+            project.name,
+            runs.len(),
+            imported_runs.len()
+        );
+    }
+    Ok(())
+}
+
+/// `uran-api import-testrail`: copies projects with their suites, testcases, milestones and
+/// runs from TestRail (API v2, `--token user:api_key`) or Xray Cloud (`--source xray`,
+/// `--token client_id:client_secret`, a `--project` per Jira project key) into new local
+/// projects owned by `--owner`, the first of `ADMIN_EMAILS` by default. Everything created
+/// is recorded in `migration_imports`, so running the command again after an interruption
+/// continues with what is missing; a run already imported is not updated.
+pub async fn run(config: MigrationConfig, args: &[String]) -> anyhow::Result<()> {
+    let options = Options::parse(args)?;
+    let db = connect_db(&config.database_url, &Default::default()).await?;
+    let result = import(config, options, &db).await;
+    db.close().await;
+    result
+}
+
+/// Seconds from a `1h 30m`-style duration; days and weeks are calendar ones.
+pub fn parse_timespan(raw: &str) -> Option<i64> {
+    let mut total = 0i64;
+    let mut parsed = false;
+    for part in raw.split_whitespace() {
+        let split = part.find(|c: char| !c.is_ascii_digit())?;
+        let (number, unit) = part.split_at(split);
+        let number: i64 = number.parse().ok()?;
+        let unit_seconds = match unit {
+            "s" => 1,
+            "m" => 60,
+            "h" => 3600,
+            "d" => 86_400,
+            "w" => 604_800,
+            _ => return None,
+        };
+        total = total.checked_add(number.checked_mul(unit_seconds)?)?;
+        parsed = true;
+    }
+    parsed.then_some(total)
+}
@@ -0,0 +1,108 @@
+use std::collections::HashSet;
+
+use anyhow::Context;
+use sqlx::{migrate::Migrator, PgPool};
+use tracing::info;
+
+/// `backend/migrations`, compiled into the binary. Only the `.up.sql` halves are applied;
+/// the `.down.sql` files stay a manual rollback tool.
+static MIGRATOR: Migrator = sqlx::migrate!();
+
+/// Applies pending migrations, each in its own transaction; concurrent instances wait on
+/// sqlx's advisory lock, so only one of them migrates.
+pub async fn run(db: &PgPool) -> anyhow::Result<()> {
+    let applied = match applied_versions(db).await? {
+        Some(applied) => applied,
+        None if has_unmanaged_schema(db).await? => anyhow::bail!(
+            "the database schema was created without the migration table; apply the missing \
+             backend/migrations/*.up.sql files with psql, then run `uran-api --baseline-migrations`"
+        ),
+        None => HashSet::new(),
+    };
+    MIGRATOR
+        .run(db)
+        .await
+        .context("failed to apply database migrations")?;
+    let mut count = 0;
+    for migration in pending(&applied) {
+        info!(
+            "applied migration {:04} {}",
+            migration.version, migration.description
+        );
+        count += 1;
+    }
+    if count == 0 {
+        info!("database schema is up to date");
+    }
+    Ok(())
+}
+
+/// Records every embedded migration as applied without running it. For databases that were
+/// migrated by hand with psql before migrations were embedded.
+pub async fn baseline(db: &PgPool) -> anyhow::Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS _sqlx_migrations (
+          version BIGINT PRIMARY KEY,
+          description TEXT NOT NULL,
+          installed_on TIMESTAMPTZ NOT NULL DEFAULT now(),
+          success BOOLEAN NOT NULL,
+          checksum BYTEA NOT NULL,
+          execution_time BIGINT NOT NULL
+        )
+        "#,
+    )
+    .execute(db)
+    .await
+    .context("failed to create the migration table")?;
+    let applied = applied_versions(db).await?.unwrap_or_default();
+    for migration in pending(&applied) {
+        sqlx::query(
+            r#"
+            INSERT INTO _sqlx_migrations (version, description, success, checksum, execution_time)
+            VALUES ($1, $2, TRUE, $3, -1)
+            "#,
+        )
+        .bind(migration.version)
+        .bind(migration.description.as_ref())
+        .bind(migration.checksum.as_ref())
+        .execute(db)
+        .await
+        .with_context(|| format!("failed to record migration {}", migration.version))?;
+        info!(
+            "marked migration {:04} {} as applied",
+            migration.version, migration.description
+        );
+    }
+    Ok(())
+}
+
+fn pending(applied: &HashSet<i64>) -> impl Iterator<Item = &'static sqlx::migrate::Migration> + '_ {
+    MIGRATOR
+        .iter()
+        .filter(|m| !m.migration_type.is_down_migration() && !applied.contains(&m.version))
+}
+
+/// `None` when the migration table does not exist yet.
+async fn applied_versions(db: &PgPool) -> anyhow::Result<Option<HashSet<i64>>> {
+    let exists: bool = sqlx::query_scalar("SELECT to_regclass('_sqlx_migrations') IS NOT NULL")
+        .fetch_one(db)
+        .await
+        .context("failed to inspect the migration table")?;
+    if !exists {
+        return Ok(None);
+    }
+    let versions: Vec<i64> = sqlx::query_scalar("SELECT version FROM _sqlx_migrations")
+        .fetch_all(db)
+        .await
+        .context("failed to read applied migrations")?;
+    Ok(Some(versions.into_iter().collect()))
+}
+
+/// Tables of `0001_init` exist although no migration was ever recorded.
+async fn has_unmanaged_schema(db: &PgPool) -> anyhow::Result<bool> {
+    sqlx::query_scalar("SELECT to_regclass('users') IS NOT NULL")
+        .fetch_one(db)
+        .await
+        .context("failed to inspect the database schema")
+}
@@ -0,0 +1,527 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{
+    audit,
+    authz::{self, AuthUser, ProjectRole},
+    db_errors, ensure_db_user_exists,
+    error::ApiError,
+    parse_uuid,
+    permissions::Capability,
+    AppState,
+};
+
+const MAX_NAME_CHARS: usize = 200;
+/// Burn-down snapshots cover at most this many days, ending today.
+const MAX_BURNDOWN_DAYS: i64 = 366;
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateMilestoneRequest {
+    /// The `milestone` of the runs it covers.
+    name: String,
+    /// `YYYY-MM-DD`.
+    due_date: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateMilestoneRequest {
+    /// `YYYY-MM-DD`; an empty string clears the due date.
+    due_date: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct MilestoneView {
+    pub id: String,
+    pub project_id: String,
+    pub name: String,
+    pub due_date: Option<NaiveDate>,
+    /// Runs of the project with this milestone.
+    pub run_count: i64,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ListMilestonesResponse {
+    milestones: Vec<MilestoneView>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct DeleteMilestoneResponse {
+    ok: bool,
+}
+
+/// Items of the milestone's runs at the end of a day (UTC).
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BurndownDay {
+    date: NaiveDate,
+    total: i64,
+    /// No result yet, or `na`, `retest` or `blocked`.
+    untested: i64,
+    failed: i64,
+    /// `untested + failed`.
+    remaining: i64,
+    /// Straight line from the first day's `remaining` to zero on the due date.
+    ideal: Option<f64>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct BurndownResponse {
+    milestone: MilestoneView,
+    days: Vec<BurndownDay>,
+}
+
+impl BurndownDay {
+    fn new(date: NaiveDate, total: i64, untested: i64, failed: i64) -> Self {
+        Self {
+            date,
+            total,
+            untested,
+            failed,
+            remaining: untested + failed,
+            ideal: None,
+        }
+    }
+}
+
+/// Sets `ideal` on consecutive `days`: from the first day's `remaining` down to zero on
+/// `due_date`, zero from then on. Without a due date there is no line.
+fn draw_ideal_line(days: &mut [BurndownDay], due_date: Option<NaiveDate>) {
+    let (Some(due_date), Some(first)) = (due_date, days.first()) else {
+        return;
+    };
+    let start = first.remaining as f64;
+    let from = first.date;
+    let span = (due_date - from).num_days();
+    for day in days {
+        let elapsed = (day.date - from).num_days();
+        day.ideal = Some(if elapsed >= span {
+            0.0
+        } else {
+            start * (span - elapsed) as f64 / span as f64
+        });
+    }
+}
+
+fn normalize_name(name: &str) -> Result<String, ApiError> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().count() > MAX_NAME_CHARS {
+        return Err(ApiError::InvalidMilestoneName);
+    }
+    Ok(name.to_string())
+}
+
+/// `None` for an absent or empty value.
+fn parse_due_date(raw: Option<&str>) -> Result<Option<NaiveDate>, ApiError> {
+    match raw.map(str::trim) {
+        Some(v) if !v.is_empty() => NaiveDate::parse_from_str(v, "%Y-%m-%d")
+            .map(Some)
+            .map_err(|_| ApiError::InvalidDueDate),
+        _ => Ok(None),
+    }
+}
+
+async fn fetch_milestone(state: &AppState, milestone_id: Uuid) -> Result<MilestoneView, ApiError> {
+    sqlx::query_as!(
+        MilestoneView,
+        r#"
+        SELECT
+          m.id::text AS "id!",
+          m.project_id::text AS "project_id!",
+          m.name,
+          m.due_date,
+          (SELECT COUNT(*) FROM runs r WHERE r.project_id = m.project_id AND r.milestone = m.name)
+            AS "run_count!"
+        FROM milestones m
+        WHERE m.id = $1
+        "#,
+        milestone_id,
+    )
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|_| ApiError::MilestonesReadFailed)?
+    .ok_or(ApiError::MilestoneNotFound)
+}
+
+/// The milestone, after checking that the caller has `capability` in its project.
+async fn authorized_milestone(
+    state: &AppState,
+    milestone_id: &str,
+    actor_id: &str,
+    capability: Capability,
+) -> Result<MilestoneView, ApiError> {
+    let milestone_uuid = parse_uuid(milestone_id, ApiError::InvalidMilestoneId)?;
+    let milestone = fetch_milestone(state, milestone_uuid).await?;
+    authz::require_capability(state, &milestone.project_id, actor_id, capability).await?;
+    Ok(milestone)
+}
+
+/// Milestones of the project by due date; those without one come last.
+#[utoipa::path(
+    get,
+    path = "/api/v2/projects/{project_id}/milestones",
+    tag = "milestones",
+    params(("project_id" = String, Path)),
+    responses((status = 200, body = ListMilestonesResponse))
+)]
+pub async fn list_milestones(
+    State(state): State<AppState>,
+    access: ProjectRole,
+) -> Result<Json<ListMilestonesResponse>, ApiError> {
+    let milestones = sqlx::query_as!(
+        MilestoneView,
+        r#"
+        SELECT
+          m.id::text AS "id!",
+          m.project_id::text AS "project_id!",
+          m.name,
+          m.due_date,
+          (SELECT COUNT(*) FROM runs r WHERE r.project_id = m.project_id AND r.milestone = m.name)
+            AS "run_count!"
+        FROM milestones m
+        WHERE m.project_id = $1
+        ORDER BY m.due_date ASC NULLS LAST, m.name ASC
+        "#,
+        access.project_id,
+    )
+    .fetch_all(&state.db)
+    .await
+    .map_err(|_| ApiError::MilestonesReadFailed)?;
+    Ok(Json(ListMilestonesResponse { milestones }))
+}
+
+/// Defines a milestone; runs already labelled with the name belong to it at once.
+#[utoipa::path(
+    post,
+    path = "/api/v2/projects/{project_id}/milestones",
+    tag = "milestones",
+    params(("project_id" = String, Path)),
+    request_body = CreateMilestoneRequest,
+    responses((status = 201, body = MilestoneView))
+)]
+pub async fn create_milestone(
+    State(state): State<AppState>,
+    access: ProjectRole,
+    Json(payload): Json<CreateMilestoneRequest>,
+) -> Result<(StatusCode, Json<MilestoneView>), ApiError> {
+    access.require(Capability::RunCompose)?;
+    let name = normalize_name(&payload.name)?;
+    let due_date = parse_due_date(payload.due_date.as_deref())?;
+    ensure_db_user_exists(&state, &access.user_id).await?;
+    let actor_uuid = parse_uuid(&access.user_id, ApiError::InvalidUserId)?;
+
+    let write_failed = |err| db_errors::map(err, ApiError::MilestoneWriteFailed);
+    let mut tx = state.db.begin().await.map_err(write_failed)?;
+    let inserted = sqlx::query_scalar!(
+        r#"
+        INSERT INTO milestones (project_id, name, due_date)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (project_id, name) DO NOTHING
+        RETURNING id
+        "#,
+        access.project_id,
+        &name,
+        due_date,
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(write_failed)?;
+    let milestone_id = inserted.ok_or(ApiError::MilestoneExists)?;
+    audit::record(
+        &mut *tx,
+        audit::AuditEntry {
+            actor_user_id: Some(actor_uuid),
+            action: "create",
+            entity_type: "milestone",
+            entity_id: Some(milestone_id),
+            project_id: Some(access.project_id),
+            run_id: None,
+            before: None,
+            after: Some(json!({ "name": &name, "dueDate": due_date })),
+        },
+    )
+    .await
+    .map_err(write_failed)?;
+    tx.commit().await.map_err(write_failed)?;
+
+    let milestone = fetch_milestone(&state, milestone_id).await?;
+    Ok((StatusCode::CREATED, Json(milestone)))
+}
+
+/// Changes the due date. The name stays: it is the label the runs carry.
+#[utoipa::path(
+    patch,
+    path = "/api/v2/milestones/{milestone_id}",
+    tag = "milestones",
+    params(("milestone_id" = String, Path)),
+    request_body = UpdateMilestoneRequest,
+    responses((status = 200, body = MilestoneView))
+)]
+pub async fn update_milestone(
+    State(state): State<AppState>,
+    Path(milestone_id): Path<String>,
+    AuthUser(actor_id): AuthUser,
+    Json(payload): Json<UpdateMilestoneRequest>,
+) -> Result<Json<MilestoneView>, ApiError> {
+    let due_date = parse_due_date(payload.due_date.as_deref())?;
+    let before =
+        authorized_milestone(&state, &milestone_id, &actor_id, Capability::RunCompose).await?;
+    if payload.due_date.is_none() || before.due_date == due_date {
+        return Ok(Json(before));
+    }
+    ensure_db_user_exists(&state, &actor_id).await?;
+    let actor_uuid = parse_uuid(&actor_id, ApiError::InvalidUserId)?;
+    let milestone_uuid = parse_uuid(&before.id, ApiError::InvalidMilestoneId)?;
+    let project_uuid = parse_uuid(&before.project_id, ApiError::InvalidProjectId)?;
+
+    let write_failed = |err| db_errors::map(err, ApiError::MilestoneWriteFailed);
+    let mut tx = state.db.begin().await.map_err(write_failed)?;
+    sqlx::query!(
+        "UPDATE milestones SET due_date = $2 WHERE id = $1",
+        milestone_uuid,
+        due_date,
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(write_failed)?;
+    audit::record(
+        &mut *tx,
+        audit::AuditEntry {
+            actor_user_id: Some(actor_uuid),
+            action: "update",
+            entity_type: "milestone",
+            entity_id: Some(milestone_uuid),
+            project_id: Some(project_uuid),
+            run_id: None,
+            before: Some(json!({ "dueDate": before.due_date })),
+            after: Some(json!({ "dueDate": due_date })),
+        },
+    )
+    .await
+    .map_err(write_failed)?;
+    tx.commit().await.map_err(write_failed)?;
+
+    Ok(Json(fetch_milestone(&state, milestone_uuid).await?))
+}
+
+/// Removes the milestone and its due date; the runs keep their `milestone` label.
+#[utoipa::path(
+    delete,
+    path = "/api/v2/milestones/{milestone_id}",
+    tag = "milestones",
+    params(("milestone_id" = String, Path)),
+    responses((status = 200, body = DeleteMilestoneResponse))
+)]
+pub async fn delete_milestone(
+    State(state): State<AppState>,
+    Path(milestone_id): Path<String>,
+    AuthUser(actor_id): AuthUser,
+) -> Result<Json<DeleteMilestoneResponse>, ApiError> {
+    let milestone =
+        authorized_milestone(&state, &milestone_id, &actor_id, Capability::RunCompose).await?;
+    ensure_db_user_exists(&state, &actor_id).await?;
+    let actor_uuid = parse_uuid(&actor_id, ApiError::InvalidUserId)?;
+    let milestone_uuid = parse_uuid(&milestone.id, ApiError::InvalidMilestoneId)?;
+    let project_uuid = parse_uuid(&milestone.project_id, ApiError::InvalidProjectId)?;
+
+    let write_failed = |err| db_errors::map(err, ApiError::MilestoneWriteFailed);
+    let mut tx = state.db.begin().await.map_err(write_failed)?;
+    let deleted = sqlx::query!("DELETE FROM milestones WHERE id = $1", milestone_uuid)
+        .execute(&mut *tx)
+        .await
+        .map_err(write_failed)?
+        .rows_affected();
+    if deleted == 0 {
+        return Err(ApiError::MilestoneNotFound);
+    }
+    audit::record(
+        &mut *tx,
+        audit::AuditEntry {
+            actor_user_id: Some(actor_uuid),
+            action: "delete",
+            entity_type: "milestone",
+            entity_id: Some(milestone_uuid),
+            project_id: Some(project_uuid),
+            run_id: None,
+            before: Some(json!({ "name": &milestone.name, "dueDate": milestone.due_date })),
+            after: None,
+        },
+    )
+    .await
+    .map_err(write_failed)?;
+    tx.commit().await.map_err(write_failed)?;
+
+    Ok(Json(DeleteMilestoneResponse { ok: true }))
+}
+
+/// Daily snapshots of the milestone's run items, from the day the first item was added until
+/// today (at most a year back). Each item counts from the day it was added, with the last
+/// status its result history has by the end of the day; items removed from a run are not
+/// counted on any day. With a due date, `ideal` is the line to reach zero on it.
+#[utoipa::path(
+    get,
+    path = "/api/v2/milestones/{milestone_id}/burndown",
+    tag = "milestones",
+    params(("milestone_id" = String, Path)),
+    responses((status = 200, body = BurndownResponse))
+)]
+pub async fn milestone_burndown(
+    State(state): State<AppState>,
+    Path(milestone_id): Path<String>,
+    AuthUser(actor_id): AuthUser,
+) -> Result<Json<BurndownResponse>, ApiError> {
+    let milestone =
+        authorized_milestone(&state, &milestone_id, &actor_id, Capability::ProjectRead).await?;
+    let project_uuid = parse_uuid(&milestone.project_id, ApiError::InvalidProjectId)?;
+    let read_failed = |_| ApiError::BurndownReadFailed;
+
+    let today = Utc::now().date_naive();
+    let first_item = sqlx::query_scalar!(
+        r#"
+        SELECT MIN((ri.created_at AT TIME ZONE 'UTC')::date)
+        FROM run_items ri
+        JOIN runs r ON r.id = ri.run_id
+        WHERE r.project_id = $1 AND r.milestone = $2
+        "#,
+        project_uuid,
+        &milestone.name,
+    )
+    .fetch_one(state.read_db())
+    .await
+    .map_err(read_failed)?;
+    let Some(first_item) = first_item else {
+        return Ok(Json(BurndownResponse {
+            milestone,
+            days: Vec::new(),
+        }));
+    };
+    let from = first_item
+        .max(today - Duration::days(MAX_BURNDOWN_DAYS - 1))
+        .min(today);
+
+    let rows = sqlx::query!(
+        r#"
+        WITH items AS (
+          SELECT ri.id, ri.created_at
+          FROM run_items ri
+          JOIN runs r ON r.id = ri.run_id
+          WHERE r.project_id = $1 AND r.milestone = $2
+        ),
+        days AS (
+          SELECT d::date AS day,
+                 ((d::date + 1)::timestamp AT TIME ZONE 'UTC') AS day_end
+          FROM generate_series($3::date, $4::date, INTERVAL '1 day') AS d
+        )
+        SELECT
+          d.day AS "day!",
+          COUNT(i.id) AS "total!",
+          COUNT(i.id) FILTER (
+            WHERE s.status IS NULL OR s.status IN ('na', 'retest', 'blocked')
+          ) AS "untested!",
+          COUNT(i.id) FILTER (WHERE s.status = 'fail') AS "failed!"
+        FROM days d
+        LEFT JOIN items i ON i.created_at < d.day_end
+        LEFT JOIN LATERAL (
+          SELECT h.status::text AS status
+          FROM run_result_history h
+          WHERE h.run_item_id = i.id AND h.changed_at < d.day_end
+          ORDER BY h.changed_at DESC
+          LIMIT 1
+        ) s ON TRUE
+        GROUP BY d.day
+        ORDER BY d.day
+        "#,
+        project_uuid,
+        &milestone.name,
+        from,
+        today,
+    )
+    .fetch_all(state.read_db())
+    .await
+    .map_err(read_failed)?;
+
+    let mut days: Vec<BurndownDay> = rows
+        .into_iter()
+        .map(|r| BurndownDay::new(r.day, r.total, r.untested, r.failed))
+        .collect();
+    draw_ideal_line(&mut days, milestone.due_date);
+    Ok(Json(BurndownResponse { milestone, days }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    /// Five days from 2026-03-01 with 10 items burning down by two a day.
+    fn week() -> Vec<BurndownDay> {
+        (0..5)
+            .map(|n| {
+                let left = 10 - 2 * n;
+                BurndownDay::new(date("2026-03-01") + Duration::days(n), 10, left - 1, 1)
+            })
+            .collect()
+    }
+
+    fn ideal(days: &[BurndownDay]) -> Vec<Option<f64>> {
+        days.iter().map(|d| d.ideal).collect()
+    }
+
+    #[test]
+    fn remaining_is_untested_plus_failed() {
+        let day = BurndownDay::new(date("2026-03-01"), 12, 5, 3);
+        assert_eq!(day.remaining, 8);
+        assert_eq!(week()[4].remaining, 2);
+    }
+
+    #[test]
+    fn ideal_line_reaches_zero_on_the_due_date() {
+        let mut days = week();
+        draw_ideal_line(&mut days, Some(date("2026-03-05")));
+        assert_eq!(
+            ideal(&days),
+            [Some(10.0), Some(7.5), Some(5.0), Some(2.5), Some(0.0)]
+        );
+    }
+
+    #[test]
+    fn ideal_line_stays_at_zero_after_the_due_date() {
+        let mut days = week();
+        draw_ideal_line(&mut days, Some(date("2026-03-03")));
+        assert_eq!(
+            ideal(&days),
+            [Some(10.0), Some(5.0), Some(0.0), Some(0.0), Some(0.0)]
+        );
+    }
+
+    #[test]
+    fn ideal_line_past_due_is_zero() {
+        let mut days = week();
+        draw_ideal_line(&mut days, Some(date("2026-02-20")));
+        assert!(ideal(&days).iter().all(|i| *i == Some(0.0)));
+        let mut days = week();
+        draw_ideal_line(&mut days, Some(date("2026-03-01")));
+        assert!(ideal(&days).iter().all(|i| *i == Some(0.0)));
+    }
+
+    #[test]
+    fn no_ideal_line_without_a_due_date() {
+        let mut days = week();
+        draw_ideal_line(&mut days, None);
+        assert!(ideal(&days).iter().all(Option::is_none));
+        draw_ideal_line(&mut [], Some(date("2026-03-05")));
+    }
+}
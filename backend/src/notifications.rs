@@ -0,0 +1,1047 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use lettre::{
+    message::{header::ContentType, Attachment, Mailbox, MultiPart, SinglePart},
+    transport::smtp::authentication::Credentials,
+    Message, SmtpTransport, Transport,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sqlx::{PgExecutor, Row};
+use tracing::info;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::{
+    authz::{self, AuthUser},
+    config::SmtpConfig,
+    email_reply, ensure_db_user_exists,
+    error::ApiError,
+    inbox, jobs, parse_uuid, permissions,
+    permissions::Capability,
+    read_projects, read_users, service_accounts, telegram, AppState,
+};
+
+#[derive(Serialize, Deserialize)]
+pub struct OutgoingEmail {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+    #[serde(default)]
+    pub reply_to: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attachment: Option<EmailAttachment>,
+}
+
+/// A file sent with an email. The job payload only names it; [`run_email_job`] loads the
+/// content from the attachment storage right before sending.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EmailAttachment {
+    pub file_name: String,
+    pub content_type: String,
+    pub storage_key: String,
+    #[serde(skip)]
+    pub content: Vec<u8>,
+}
+
+/// Email transport behind [`notify`]; tests substitute a recording implementation.
+pub trait Mailer: Send + Sync {
+    fn send(&self, email: &OutgoingEmail) -> anyhow::Result<()>;
+}
+
+pub struct SmtpMailer {
+    transport: SmtpTransport,
+    from: Mailbox,
+}
+
+impl Mailer for SmtpMailer {
+    fn send(&self, email: &OutgoingEmail) -> anyhow::Result<()> {
+        let mut builder = Message::builder()
+            .from(self.from.clone())
+            .to(email.to.parse()?)
+            .subject(&email.subject);
+        if let Some(reply_to) = &email.reply_to {
+            builder = builder.reply_to(reply_to.parse()?);
+        }
+        let message = match &email.attachment {
+            Some(attachment) => builder.multipart(
+                MultiPart::mixed()
+                    .singlepart(SinglePart::plain(email.body.clone()))
+                    .singlepart(Attachment::new(attachment.file_name.clone()).body(
+                        attachment.content.clone(),
+                        ContentType::parse(&attachment.content_type)?,
+                    )),
+            )?,
+            None => builder.body(email.body.clone())?,
+        };
+        self.transport.send(&message)?;
+        Ok(())
+    }
+}
+
+/// Used when SMTP is not configured: emails are only written to the log.
+pub struct LogMailer;
+
+impl Mailer for LogMailer {
+    fn send(&self, email: &OutgoingEmail) -> anyhow::Result<()> {
+        info!("email to {}: {}", email.to, email.subject);
+        Ok(())
+    }
+}
+
+/// Hands the email to the mailer on a blocking thread; SMTP delivery blocks.
+async fn send_email(mailer: Arc<dyn Mailer>, email: OutgoingEmail) -> anyhow::Result<()> {
+    tokio::task::spawn_blocking(move || mailer.send(&email)).await?
+}
+
+/// SMTP over STARTTLS, or [`LogMailer`] when SMTP is not configured.
+pub fn mailer(smtp: Option<&SmtpConfig>) -> anyhow::Result<Arc<dyn Mailer>> {
+    let Some(smtp) = smtp else {
+        return Ok(Arc::new(LogMailer));
+    };
+    let mut builder = SmtpTransport::starttls_relay(&smtp.host)?.port(smtp.port);
+    if let Some((username, password)) = &smtp.credentials {
+        builder = builder.credentials(Credentials::new(username.clone(), password.clone()));
+    }
+    Ok(Arc::new(SmtpMailer {
+        transport: builder.build(),
+        from: smtp.from.clone(),
+    }))
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum NotificationKind {
+    MemberAdded,
+    RunAssigned,
+    RunDone,
+    RequiredFailed,
+    Mentioned,
+    RunUnlocked,
+}
+
+impl NotificationKind {
+    const ALL: [NotificationKind; 6] = [
+        NotificationKind::MemberAdded,
+        NotificationKind::RunAssigned,
+        NotificationKind::RunDone,
+        NotificationKind::RequiredFailed,
+        NotificationKind::Mentioned,
+        NotificationKind::RunUnlocked,
+    ];
+
+    /// Column of `notification_preferences`; also the value of `?kind=` in unsubscribe links.
+    pub fn column(self) -> &'static str {
+        match self {
+            NotificationKind::MemberAdded => "member_added",
+            NotificationKind::RunAssigned => "run_assigned",
+            NotificationKind::RunDone => "run_done",
+            NotificationKind::RequiredFailed => "required_failed",
+            NotificationKind::Mentioned => "mentioned",
+            NotificationKind::RunUnlocked => "run_unlocked",
+        }
+    }
+
+    pub fn parse(input: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.column() == input)
+    }
+
+    /// Setting of a channel the user has not configured. Email is on for everything; the
+    /// personal chat only gets what needs a reaction.
+    fn default_enabled(self, channel: Channel) -> bool {
+        match channel {
+            Channel::Chat => matches!(
+                self,
+                NotificationKind::RunAssigned | NotificationKind::RequiredFailed
+            ),
+            Channel::Email | Channel::InApp => true,
+        }
+    }
+}
+
+/// Where a notification is delivered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Channel {
+    Email,
+    /// The linked Telegram chat, see `telegram`.
+    Chat,
+    InApp,
+}
+
+impl Channel {
+    fn as_str(self) -> &'static str {
+        match self {
+            Channel::Email => "email",
+            Channel::Chat => "chat",
+            Channel::InApp => "in_app",
+        }
+    }
+}
+
+/// Queues `subject`/`body` for every recipient; each one gets a `notification` job for the
+/// email, a `telegram_message` job and an entry in the in-app feed, subject to the
+/// recipient's settings for the project. Never fails the request that triggered it.
+pub fn notify(
+    state: &AppState,
+    kind: NotificationKind,
+    project_id: Option<Uuid>,
+    run_id: Option<Uuid>,
+    mut recipients: Vec<String>,
+    subject: String,
+    body: String,
+) {
+    recipients.sort();
+    recipients.dedup();
+    inbox::deliver_in_background(
+        state,
+        kind,
+        inbox::Target {
+            project_id,
+            run_id,
+            run_item_id: None,
+        },
+        recipients.clone(),
+        subject.clone(),
+        body.clone(),
+    );
+    for user_id in recipients {
+        jobs::submit_in_background(
+            state,
+            jobs::NewJob {
+                kind: jobs::JobKind::Notification,
+                project_id: None,
+                created_by_user_id: None,
+                payload: json!({
+                    "kind": kind.column(),
+                    "projectId": project_id,
+                    "userId": user_id,
+                    "subject": &subject,
+                    "body": &body,
+                }),
+            },
+        );
+        telegram::submit(state, kind, project_id, &user_id, &subject, &body, None);
+    }
+}
+
+/// [`notify`] for one recipient about one run item: when inbound email is configured, the
+/// email gets a reply address through which the recipient can record the item's result
+/// (see `email_reply`); the Telegram message gets buttons for the same purpose.
+pub fn notify_about_item(
+    state: &AppState,
+    kind: NotificationKind,
+    project_id: Uuid,
+    user_id: String,
+    run_item_id: Uuid,
+    subject: String,
+    body: String,
+) {
+    inbox::deliver_in_background(
+        state,
+        kind,
+        inbox::Target {
+            project_id: Some(project_id),
+            run_id: None,
+            run_item_id: Some(run_item_id),
+        },
+        vec![user_id.clone()],
+        subject.clone(),
+        body.clone(),
+    );
+    telegram::submit(
+        state,
+        kind,
+        Some(project_id),
+        &user_id,
+        &subject,
+        &body,
+        Some(run_item_id),
+    );
+    jobs::submit_in_background(
+        state,
+        jobs::NewJob {
+            kind: jobs::JobKind::Notification,
+            project_id: None,
+            created_by_user_id: None,
+            payload: json!({
+                "kind": kind.column(),
+                "projectId": project_id,
+                "userId": user_id,
+                "subject": subject,
+                "body": body,
+                "replyRunItemId": run_item_id,
+            }),
+        },
+    );
+}
+
+/// Queues a service email (confirmation links etc.), sent regardless of the recipient's
+/// notification preferences.
+pub fn send_transactional(state: &AppState, email: OutgoingEmail) {
+    jobs::submit_in_background(
+        state,
+        jobs::NewJob {
+            kind: jobs::JobKind::Email,
+            project_id: None,
+            created_by_user_id: None,
+            payload: json!(email),
+        },
+    );
+}
+
+/// Job handler of [`send_transactional`].
+pub async fn run_email_job(state: &AppState, payload: Value) -> anyhow::Result<Value> {
+    let mut email: OutgoingEmail = serde_json::from_value(payload)?;
+    if let Some(attachment) = email.attachment.as_mut() {
+        attachment.content = state.storage.get(&attachment.storage_key).await?.to_vec();
+    }
+    send_email(state.mailer.clone(), email).await?;
+    Ok(Value::Null)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NotificationJob {
+    kind: String,
+    /// Absent in jobs queued before per-project settings.
+    #[serde(default)]
+    project_id: Option<Uuid>,
+    user_id: String,
+    subject: String,
+    body: String,
+    /// Set by [`notify_about_item`].
+    #[serde(default)]
+    reply_run_item_id: Option<Uuid>,
+}
+
+/// Job handler of [`notify`] for one recipient.
+pub async fn run_notification_job(state: &AppState, payload: Value) -> anyhow::Result<Value> {
+    let job: NotificationJob = serde_json::from_value(payload)?;
+    let kind = NotificationKind::parse(&job.kind)
+        .ok_or_else(|| anyhow::anyhow!("unknown notification kind {}", job.kind))?;
+    deliver(
+        state,
+        kind,
+        job.project_id,
+        &job.user_id,
+        &job.subject,
+        &job.body,
+        job.reply_run_item_id,
+    )
+    .await?;
+    Ok(Value::Null)
+}
+
+async fn deliver(
+    state: &AppState,
+    kind: NotificationKind,
+    project_id: Option<Uuid>,
+    user_id: &str,
+    subject: &str,
+    body: &str,
+    reply_run_item_id: Option<Uuid>,
+) -> anyhow::Result<()> {
+    let email = {
+        let _guard = state.file_lock.lock().await;
+        let users = read_users(&state.users_file).await?;
+        if service_accounts::is_service_account(&users, user_id) {
+            return Ok(());
+        }
+        users
+            .into_iter()
+            .find(|u| u.id == user_id)
+            .map(|u| u.email)
+            .ok_or_else(|| anyhow::anyhow!("user not found"))?
+    };
+    ensure_db_user_exists(state, user_id)
+        .await
+        .map_err(|_| anyhow::anyhow!("failed to sync user"))?;
+    let user_uuid = Uuid::parse_str(user_id)?;
+    let row = load_preferences(state, user_uuid).await?;
+    let overridden = configured(state, user_uuid, project_id, kind, Channel::Email).await?;
+    if !overridden.unwrap_or_else(|| row.get::<bool, _>(kind.column())) {
+        return Ok(());
+    }
+
+    let unsubscribe_url = format!(
+        "{}/api/notifications/unsubscribe?token={}&kind={}",
+        state.public_url.trim_end_matches('/'),
+        row.get::<Uuid, _>("unsubscribe_token"),
+        kind.column()
+    );
+    let reply_to = match reply_run_item_id {
+        Some(run_item_id) => email_reply::reply_address(state, user_uuid, run_item_id).await?,
+        None => None,
+    };
+    let email = notification_email(email, subject, body, &unsubscribe_url, reply_to);
+    send_email(state.mailer.clone(), email).await
+}
+
+/// The notification text with the reply hint (when replies are possible) and the
+/// unsubscribe link appended.
+fn notification_email(
+    to: String,
+    subject: &str,
+    body: &str,
+    unsubscribe_url: &str,
+    reply_to: Option<String>,
+) -> OutgoingEmail {
+    let reply_hint = if reply_to.is_some() {
+        email_reply::REPLY_HINT
+    } else {
+        ""
+    };
+    OutgoingEmail {
+        to,
+        subject: subject.to_string(),
+        body: format!("{body}{reply_hint}\n\n--\nОтписаться от таких писем: {unsubscribe_url}\n"),
+        reply_to,
+        attachment: None,
+    }
+}
+
+/// Members whose role can manage the project; they receive project-wide run notifications.
+pub async fn project_managers(state: &AppState, project_id: &str) -> Vec<String> {
+    let _guard = state.file_lock.lock().await;
+    let Ok(projects) = read_projects(&state.projects_file).await else {
+        return Vec::new();
+    };
+    projects
+        .iter()
+        .find(|p| p.id == project_id)
+        .map(|p| {
+            p.members
+                .iter()
+                .filter(|m| {
+                    permissions::role_capabilities(p, &m.role).contains(&Capability::ProjectManage)
+                })
+                .map(|m| m.user_id.clone())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// All members of the project.
+pub async fn project_members(state: &AppState, project_id: &str) -> Vec<String> {
+    let _guard = state.file_lock.lock().await;
+    let Ok(projects) = read_projects(&state.projects_file).await else {
+        return Vec::new();
+    };
+    projects
+        .iter()
+        .find(|p| p.id == project_id)
+        .map(|p| p.members.iter().map(|m| m.user_id.clone()).collect())
+        .unwrap_or_default()
+}
+
+/// Whether the user wants notifications of this kind from the project through the channel:
+/// the project's setting, else the user-wide one, else the built-in default.
+pub async fn is_enabled(
+    state: &AppState,
+    user_id: Uuid,
+    project_id: Option<Uuid>,
+    kind: NotificationKind,
+    channel: Channel,
+) -> Result<bool, sqlx::Error> {
+    if let Some(enabled) = configured(state, user_id, project_id, kind, channel).await? {
+        return Ok(enabled);
+    }
+    if channel == Channel::Email {
+        return Ok(load_preferences(state, user_id)
+            .await?
+            .get::<bool, _>(kind.column()));
+    }
+    Ok(kind.default_enabled(channel))
+}
+
+/// The most specific row of `notification_settings`, if any.
+async fn configured(
+    state: &AppState,
+    user_id: Uuid,
+    project_id: Option<Uuid>,
+    kind: NotificationKind,
+    channel: Channel,
+) -> Result<Option<bool>, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT enabled
+        FROM notification_settings
+        WHERE user_id = $1
+          AND event = $2
+          AND channel = $3
+          AND (project_id = $4 OR project_id IS NULL)
+        ORDER BY project_id NULLS LAST
+        LIMIT 1
+        "#,
+        user_id,
+        kind.column(),
+        channel.as_str(),
+        project_id,
+    )
+    .fetch_optional(&state.db)
+    .await
+}
+
+/// The user's preferences row, created with defaults (everything enabled) on first access.
+async fn load_preferences(
+    state: &AppState,
+    user_id: Uuid,
+) -> Result<sqlx::postgres::PgRow, sqlx::Error> {
+    sqlx::query(
+        r#"
+        WITH inserted AS (
+          INSERT INTO notification_preferences (user_id)
+          VALUES ($1)
+          ON CONFLICT (user_id) DO NOTHING
+          RETURNING *
+        )
+        SELECT * FROM inserted
+        UNION ALL
+        SELECT * FROM notification_preferences WHERE user_id = $1
+        "#,
+    )
+    .bind(user_id)
+    .fetch_one(&state.db)
+    .await
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationPreferences {
+    member_added: bool,
+    run_assigned: bool,
+    run_done: bool,
+    required_failed: bool,
+    mentioned: bool,
+    /// Optional in requests for clients that predate it; defaults to `true`.
+    #[serde(default = "enabled")]
+    run_unlocked: bool,
+}
+
+fn enabled() -> bool {
+    true
+}
+
+impl NotificationPreferences {
+    fn from_fn(enabled: impl Fn(NotificationKind) -> bool) -> Self {
+        Self {
+            member_added: enabled(NotificationKind::MemberAdded),
+            run_assigned: enabled(NotificationKind::RunAssigned),
+            run_done: enabled(NotificationKind::RunDone),
+            required_failed: enabled(NotificationKind::RequiredFailed),
+            mentioned: enabled(NotificationKind::Mentioned),
+            run_unlocked: enabled(NotificationKind::RunUnlocked),
+        }
+    }
+
+    fn get(&self, kind: NotificationKind) -> bool {
+        match kind {
+            NotificationKind::MemberAdded => self.member_added,
+            NotificationKind::RunAssigned => self.run_assigned,
+            NotificationKind::RunDone => self.run_done,
+            NotificationKind::RequiredFailed => self.required_failed,
+            NotificationKind::Mentioned => self.mentioned,
+            NotificationKind::RunUnlocked => self.run_unlocked,
+        }
+    }
+
+    fn from_row(row: &sqlx::postgres::PgRow) -> Self {
+        Self {
+            member_added: row.get::<bool, _>("member_added"),
+            run_assigned: row.get::<bool, _>("run_assigned"),
+            run_done: row.get::<bool, _>("run_done"),
+            required_failed: row.get::<bool, _>("required_failed"),
+            mentioned: row.get::<bool, _>("mentioned"),
+            run_unlocked: row.get::<bool, _>("run_unlocked"),
+        }
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct UnsubscribeQuery {
+    token: String,
+    kind: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct UnsubscribeResponse {
+    ok: bool,
+    preferences: NotificationPreferences,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/notifications/preferences",
+    tag = "notifications",
+    responses((status = 200, body = NotificationPreferences))
+)]
+pub async fn get_preferences(
+    State(state): State<AppState>,
+    AuthUser(user_id): AuthUser,
+) -> Result<Json<NotificationPreferences>, ApiError> {
+    ensure_db_user_exists(&state, &user_id).await?;
+    let user_uuid = parse_uuid(&user_id, ApiError::InvalidUserId)?;
+
+    let row = load_preferences(&state, user_uuid)
+        .await
+        .map_err(|_| ApiError::NotificationPrefsLoadFailed)?;
+    Ok(Json(NotificationPreferences::from_row(&row)))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/notifications/preferences",
+    tag = "notifications",
+    request_body = NotificationPreferences,
+    responses((status = 200, body = NotificationPreferences))
+)]
+pub async fn update_preferences(
+    State(state): State<AppState>,
+    AuthUser(user_id): AuthUser,
+    Json(payload): Json<NotificationPreferences>,
+) -> Result<Json<NotificationPreferences>, ApiError> {
+    ensure_db_user_exists(&state, &user_id).await?;
+    let user_uuid = parse_uuid(&user_id, ApiError::InvalidUserId)?;
+
+    let row = store_preferences(&state.db, user_uuid, &payload)
+        .await
+        .map_err(|_| ApiError::NotificationPrefsSaveFailed)?;
+    Ok(Json(NotificationPreferences::from_row(&row)))
+}
+
+/// Saves the user-wide email settings.
+async fn store_preferences<'e, E: PgExecutor<'e>>(
+    executor: E,
+    user_id: Uuid,
+    preferences: &NotificationPreferences,
+) -> Result<sqlx::postgres::PgRow, sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO notification_preferences (
+          user_id, member_added, run_assigned, run_done, required_failed, mentioned,
+          run_unlocked
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        ON CONFLICT (user_id) DO UPDATE SET
+          member_added = EXCLUDED.member_added,
+          run_assigned = EXCLUDED.run_assigned,
+          run_done = EXCLUDED.run_done,
+          required_failed = EXCLUDED.required_failed,
+          mentioned = EXCLUDED.mentioned,
+          run_unlocked = EXCLUDED.run_unlocked
+        RETURNING *
+        "#,
+    )
+    .bind(user_id)
+    .bind(preferences.member_added)
+    .bind(preferences.run_assigned)
+    .bind(preferences.run_done)
+    .bind(preferences.required_failed)
+    .bind(preferences.mentioned)
+    .bind(preferences.run_unlocked)
+    .fetch_one(executor)
+    .await
+}
+
+/// Target of the link in every email; works without a session. Without `kind` it turns off
+/// all notification emails, including those turned on for single projects.
+#[utoipa::path(
+    get,
+    path = "/api/notifications/unsubscribe",
+    tag = "notifications",
+    params(UnsubscribeQuery),
+    responses((status = 200, body = UnsubscribeResponse)),
+    security(())
+)]
+pub async fn unsubscribe(
+    State(state): State<AppState>,
+    Query(query): Query<UnsubscribeQuery>,
+) -> Result<Json<UnsubscribeResponse>, ApiError> {
+    let token = parse_uuid(query.token.trim(), ApiError::InvalidUnsubscribeLink)?;
+    let kinds: Vec<NotificationKind> = match query.kind.as_deref().map(str::trim) {
+        Some(v) if !v.is_empty() => vec![NotificationKind::ALL
+            .into_iter()
+            .find(|k| k.column() == v)
+            .ok_or(ApiError::InvalidNotificationKind)?],
+        _ => NotificationKind::ALL.to_vec(),
+    };
+    let assignments = kinds
+        .iter()
+        .map(|k| format!("{} = FALSE", k.column()))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let sql = format!(
+        r#"
+        UPDATE notification_preferences
+        SET {assignments}
+        WHERE unsubscribe_token = $1
+        RETURNING *
+        "#
+    );
+    let save_failed = |_| ApiError::NotificationPrefsSaveFailed;
+    let mut tx = state.db.begin().await.map_err(save_failed)?;
+    let row = sqlx::query(&sql)
+        .bind(token)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(save_failed)?
+        .ok_or(ApiError::UnsubscribeLinkExpired)?;
+    // Project settings that still turn these emails on would defeat the link.
+    let events: Vec<String> = kinds.iter().map(|k| k.column().to_string()).collect();
+    sqlx::query!(
+        r#"
+        DELETE FROM notification_settings
+        WHERE user_id = $1 AND channel = 'email' AND enabled AND event = ANY($2)
+        "#,
+        row.get::<Uuid, _>("user_id"),
+        &events,
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(save_failed)?;
+    tx.commit().await.map_err(save_failed)?;
+    Ok(Json(UnsubscribeResponse {
+        ok: true,
+        preferences: NotificationPreferences::from_row(&row),
+    }))
+}
+
+/// Channels of one event; `null` means not configured: a project follows the user-wide
+/// setting, a user-wide setting the built-in default.
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct EventChannelSettings {
+    /// `member_added`, `run_assigned`, `run_done`, `required_failed`, `mentioned` or
+    /// `run_unlocked`.
+    event: String,
+    #[serde(default)]
+    email: Option<bool>,
+    /// The linked Telegram chat.
+    #[serde(default)]
+    chat: Option<bool>,
+    #[serde(default)]
+    in_app: Option<bool>,
+}
+
+impl EventChannelSettings {
+    fn channels(&self) -> [(Channel, Option<bool>); 3] {
+        [
+            (Channel::Email, self.email),
+            (Channel::Chat, self.chat),
+            (Channel::InApp, self.in_app),
+        ]
+    }
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectNotificationSettings {
+    project_id: String,
+    /// Only the events with at least one channel configured for the project.
+    events: Vec<EventChannelSettings>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationSettings {
+    /// User-wide settings; responses list every event with effective values.
+    #[serde(default)]
+    defaults: Vec<EventChannelSettings>,
+    /// Projects with settings of their own.
+    #[serde(default)]
+    projects: Vec<ProjectNotificationSettings>,
+}
+
+/// Which events arrive through which channel, user-wide and per project. The user-wide
+/// email column is the same setting as `/api/notifications/preferences`.
+#[utoipa::path(
+    get,
+    path = "/api/auth/me/notifications",
+    tag = "notifications",
+    responses((status = 200, body = NotificationSettings))
+)]
+pub async fn get_settings(
+    State(state): State<AppState>,
+    AuthUser(user_id): AuthUser,
+) -> Result<Json<NotificationSettings>, ApiError> {
+    ensure_db_user_exists(&state, &user_id).await?;
+    let user_uuid = parse_uuid(&user_id, ApiError::InvalidUserId)?;
+    let settings = load_settings(&state, user_uuid)
+        .await
+        .map_err(|_| ApiError::NotificationPrefsLoadFailed)?;
+    Ok(Json(settings))
+}
+
+/// Replaces all settings: events missing from `defaults` and projects missing from
+/// `projects` return to the defaults. Projects must be ones the caller is a member of.
+#[utoipa::path(
+    put,
+    path = "/api/auth/me/notifications",
+    tag = "notifications",
+    request_body = NotificationSettings,
+    responses((status = 200, body = NotificationSettings))
+)]
+pub async fn update_settings(
+    State(state): State<AppState>,
+    AuthUser(user_id): AuthUser,
+    Json(payload): Json<NotificationSettings>,
+) -> Result<Json<NotificationSettings>, ApiError> {
+    ensure_db_user_exists(&state, &user_id).await?;
+    let user_uuid = parse_uuid(&user_id, ApiError::InvalidUserId)?;
+    let defaults = parse_events(&payload.defaults)?;
+    let member_projects = authz::member_project_ids(&state, &user_id).await?;
+    let mut projects = Vec::new();
+    for project in &payload.projects {
+        let project_id = parse_uuid(&project.project_id, ApiError::InvalidProjectId)?;
+        if !member_projects.contains(&project_id) {
+            return Err(ApiError::ProjectNotFound);
+        }
+        if projects.iter().any(|(id, _)| *id == project_id) {
+            return Err(ApiError::DuplicateNotificationSetting);
+        }
+        projects.push((project_id, parse_events(&project.events)?));
+    }
+
+    let mut row_projects: Vec<Option<Uuid>> = Vec::new();
+    let mut row_events: Vec<String> = Vec::new();
+    let mut row_channels: Vec<String> = Vec::new();
+    let mut row_enabled: Vec<bool> = Vec::new();
+    let mut push = |project_id: Option<Uuid>, kind: NotificationKind, channel: Channel, on| {
+        row_projects.push(project_id);
+        row_events.push(kind.column().to_string());
+        row_channels.push(channel.as_str().to_string());
+        row_enabled.push(on);
+    };
+    for (kind, settings) in &defaults {
+        for (channel, enabled) in settings.channels() {
+            // User-wide email settings live in `notification_preferences`.
+            if channel == Channel::Email {
+                continue;
+            }
+            if let Some(enabled) = enabled.filter(|&on| on != kind.default_enabled(channel)) {
+                push(None, *kind, channel, enabled);
+            }
+        }
+    }
+    for (project_id, events) in &projects {
+        for (kind, settings) in events {
+            for (channel, enabled) in settings.channels() {
+                if let Some(enabled) = enabled {
+                    push(Some(*project_id), *kind, channel, enabled);
+                }
+            }
+        }
+    }
+    let email = NotificationPreferences::from_fn(|kind| {
+        defaults
+            .iter()
+            .find(|(k, _)| k.column() == kind.column())
+            .and_then(|(_, settings)| settings.email)
+            .unwrap_or_else(|| kind.default_enabled(Channel::Email))
+    });
+
+    let save_failed = |_| ApiError::NotificationPrefsSaveFailed;
+    let mut tx = state.db.begin().await.map_err(save_failed)?;
+    store_preferences(&mut *tx, user_uuid, &email)
+        .await
+        .map_err(save_failed)?;
+    sqlx::query!(
+        "DELETE FROM notification_settings WHERE user_id = $1",
+        user_uuid
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(save_failed)?;
+    sqlx::query!(
+        r#"
+        INSERT INTO notification_settings (user_id, project_id, event, channel, enabled)
+        SELECT $1, * FROM UNNEST($2::uuid[], $3::text[], $4::text[], $5::bool[])
+        "#,
+        user_uuid,
+        &row_projects as &[Option<Uuid>],
+        &row_events,
+        &row_channels,
+        &row_enabled,
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(save_failed)?;
+    tx.commit().await.map_err(save_failed)?;
+
+    let settings = load_settings(&state, user_uuid)
+        .await
+        .map_err(|_| ApiError::NotificationPrefsLoadFailed)?;
+    Ok(Json(settings))
+}
+
+/// Events of a settings list; unknown and repeated events are rejected.
+fn parse_events(
+    events: &[EventChannelSettings],
+) -> Result<Vec<(NotificationKind, &EventChannelSettings)>, ApiError> {
+    let mut parsed: Vec<(NotificationKind, &EventChannelSettings)> = Vec::new();
+    for settings in events {
+        let kind = NotificationKind::parse(settings.event.trim())
+            .ok_or(ApiError::InvalidNotificationKind)?;
+        if parsed.iter().any(|(k, _)| k.column() == kind.column()) {
+            return Err(ApiError::DuplicateNotificationSetting);
+        }
+        parsed.push((kind, settings));
+    }
+    Ok(parsed)
+}
+
+async fn load_settings(
+    state: &AppState,
+    user_id: Uuid,
+) -> Result<NotificationSettings, sqlx::Error> {
+    let email = NotificationPreferences::from_row(&load_preferences(state, user_id).await?);
+    let rows = sqlx::query!(
+        r#"
+        SELECT project_id, event, channel, enabled
+        FROM notification_settings
+        WHERE user_id = $1
+        ORDER BY project_id NULLS FIRST
+        "#,
+        user_id,
+    )
+    .fetch_all(&state.db)
+    .await?;
+    let find = |project_id: Option<Uuid>, kind: NotificationKind, channel: Channel| {
+        rows.iter()
+            .find(|r| {
+                r.project_id == project_id
+                    && r.event == kind.column()
+                    && r.channel == channel.as_str()
+            })
+            .map(|r| r.enabled)
+    };
+
+    let defaults = NotificationKind::ALL
+        .into_iter()
+        .map(|kind| {
+            let resolved =
+                |channel| Some(find(None, kind, channel).unwrap_or(kind.default_enabled(channel)));
+            EventChannelSettings {
+                event: kind.column().to_string(),
+                email: Some(email.get(kind)),
+                chat: resolved(Channel::Chat),
+                in_app: resolved(Channel::InApp),
+            }
+        })
+        .collect();
+    let mut project_ids: Vec<Uuid> = rows.iter().filter_map(|r| r.project_id).collect();
+    project_ids.dedup();
+    let projects = project_ids
+        .into_iter()
+        .map(|project_id| ProjectNotificationSettings {
+            project_id: project_id.to_string(),
+            events: NotificationKind::ALL
+                .into_iter()
+                .map(|kind| EventChannelSettings {
+                    event: kind.column().to_string(),
+                    email: find(Some(project_id), kind, Channel::Email),
+                    chat: find(Some(project_id), kind, Channel::Chat),
+                    in_app: find(Some(project_id), kind, Channel::InApp),
+                })
+                .filter(|settings| settings.channels().iter().any(|(_, on)| on.is_some()))
+                .collect(),
+        })
+        .collect();
+    Ok(NotificationSettings { defaults, projects })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    /// Keeps every email instead of sending it; fails when asked to.
+    #[derive(Default)]
+    struct RecordingMailer {
+        sent: Mutex<Vec<OutgoingEmail>>,
+        fail: bool,
+    }
+
+    impl Mailer for RecordingMailer {
+        fn send(&self, email: &OutgoingEmail) -> anyhow::Result<()> {
+            if self.fail {
+                anyhow::bail!("smtp unavailable");
+            }
+            self.sent.lock().unwrap().push(OutgoingEmail {
+                to: email.to.clone(),
+                subject: email.subject.clone(),
+                body: email.body.clone(),
+                reply_to: email.reply_to.clone(),
+                attachment: None,
+            });
+            Ok(())
+        }
+    }
+
+    const UNSUBSCRIBE: &str =
+        "https://uran.test/api/notifications/unsubscribe?token=t&kind=run_done";
+
+    #[tokio::test]
+    async fn sends_the_notification_with_an_unsubscribe_link() {
+        let mailer = Arc::new(RecordingMailer::default());
+        let email = notification_email(
+            "qa@example.com".into(),
+            "Прогон «Smoke» завершён",
+            "Прогон «Smoke» переведён в статус done.",
+            UNSUBSCRIBE,
+            None,
+        );
+        send_email(mailer.clone(), email).await.unwrap();
+
+        let sent = mailer.sent.lock().unwrap();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].to, "qa@example.com");
+        assert_eq!(sent[0].subject, "Прогон «Smoke» завершён");
+        assert_eq!(
+            sent[0].body,
+            format!(
+                "Прогон «Smoke» переведён в статус done.\n\n--\nОтписаться от таких писем: \
+                 {UNSUBSCRIBE}\n"
+            )
+        );
+        assert!(sent[0].reply_to.is_none());
+    }
+
+    #[tokio::test]
+    async fn item_notifications_explain_how_to_reply() {
+        let mailer = Arc::new(RecordingMailer::default());
+        let email = notification_email(
+            "qa@example.com".into(),
+            "Вам назначен пункт",
+            "C-12 Вход по паролю",
+            UNSUBSCRIBE,
+            Some("reply+abc@uran.test".into()),
+        );
+        send_email(mailer.clone(), email).await.unwrap();
+
+        let sent = mailer.sent.lock().unwrap();
+        assert_eq!(sent[0].reply_to.as_deref(), Some("reply+abc@uran.test"));
+        assert!(sent[0]
+            .body
+            .starts_with(&format!("C-12 Вход по паролю{}", email_reply::REPLY_HINT)));
+        assert!(sent[0].body.ends_with(&format!("{UNSUBSCRIBE}\n")));
+    }
+
+    #[tokio::test]
+    async fn mailer_errors_fail_the_job() {
+        let mailer = Arc::new(RecordingMailer {
+            fail: true,
+            ..Default::default()
+        });
+        let email = notification_email("qa@example.com".into(), "s", "b", UNSUBSCRIBE, None);
+        assert!(send_email(mailer.clone(), email).await.is_err());
+        assert!(mailer.sent.lock().unwrap().is_empty());
+    }
+}
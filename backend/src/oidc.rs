@@ -0,0 +1,514 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use axum::{
+    extract::{Query, State},
+    http::{
+        header::{COOKIE, LOCATION, SET_COOKIE},
+        HeaderMap, HeaderValue, StatusCode,
+    },
+    response::{IntoResponse, Response},
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use jsonwebtoken::{
+    decode, decode_header, encode, jwk::JwkSet, Algorithm, DecodingKey, EncodingKey, Header,
+    Validation,
+};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use tracing::warn;
+use utoipa::IntoParams;
+use uuid::Uuid;
+
+use crate::{
+    config::OidcConfig, error::ApiError, invitations, issue_auth_response, now_iso, read_projects,
+    read_users, write_projects, write_users, AppState, User,
+};
+
+const LOGIN_COOKIE: &str = "uran_oidc_login";
+const LOGIN_COOKIE_PATH: &str = "/api/auth/oidc";
+/// How long the user may spend at the provider before the login has to be restarted.
+const LOGIN_TTL_SECS: i64 = 10 * 60;
+const LOGIN_AUDIENCE: &str = "uran-oidc-login";
+/// Discovery document and keys are refetched after this, and also whenever an ID token is
+/// signed with a key we have not seen (the provider rotated its keys).
+const PROVIDER_TTL: Duration = Duration::from_secs(60 * 60);
+const HTTP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The provider account a user signs in with. `subject` is stable per issuer, unlike the
+/// email, so a user stays linked after changing the address on either side.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct OidcIdentity {
+    pub issuer: String,
+    pub subject: String,
+}
+
+/// Client of one OpenID Connect provider (authorization code flow with PKCE). Provider
+/// metadata is discovered lazily, so an unreachable provider does not stop the API.
+pub struct OidcClient {
+    config: OidcConfig,
+    http: reqwest::Client,
+    provider: Mutex<Option<Arc<Provider>>>,
+    /// Signs the short-lived login cookie; derived from the JWT secret.
+    encoding: EncodingKey,
+    decoding: DecodingKey,
+    secure_cookie: bool,
+}
+
+struct Provider {
+    authorization_endpoint: String,
+    token_endpoint: String,
+    jwks: JwkSet,
+    fetched_at: Instant,
+}
+
+#[derive(Deserialize)]
+struct DiscoveryDocument {
+    issuer: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+    jwks_uri: String,
+}
+
+/// What the login leaves in the browser to be checked by the callback.
+#[derive(Serialize, Deserialize)]
+struct LoginState {
+    aud: String,
+    exp: i64,
+    state: String,
+    nonce: String,
+    verifier: String,
+    return_to: String,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    id_token: String,
+}
+
+#[derive(Deserialize)]
+struct IdTokenClaims {
+    sub: String,
+    nonce: Option<String>,
+    email: Option<String>,
+    /// Some providers send `"true"` instead of a boolean.
+    email_verified: Option<Value>,
+    name: Option<String>,
+    preferred_username: Option<String>,
+}
+
+impl IdTokenClaims {
+    fn verified_email(&self) -> Option<String> {
+        let verified = match &self.email_verified {
+            Some(Value::Bool(verified)) => *verified,
+            Some(Value::String(verified)) => verified.eq_ignore_ascii_case("true"),
+            _ => false,
+        };
+        self.email
+            .as_deref()
+            .map(|email| email.trim().to_lowercase())
+            .filter(|email| verified && email.contains('@'))
+    }
+
+    fn display_name(&self, email: &str) -> String {
+        [self.name.as_deref(), self.preferred_username.as_deref()]
+            .into_iter()
+            .flatten()
+            .map(str::trim)
+            .find(|name| name.chars().count() >= 2)
+            .unwrap_or(email)
+            .to_string()
+    }
+}
+
+impl OidcClient {
+    pub fn new(config: &OidcConfig, jwt_secret: &str, public_url: &str) -> anyhow::Result<Self> {
+        let secret = Sha256::new()
+            .chain_update(b"oidc-login:")
+            .chain_update(jwt_secret.as_bytes())
+            .finalize();
+        Ok(Self {
+            config: config.clone(),
+            http: reqwest::Client::builder().timeout(HTTP_TIMEOUT).build()?,
+            provider: Mutex::new(None),
+            encoding: EncodingKey::from_secret(&secret),
+            decoding: DecodingKey::from_secret(&secret),
+            secure_cookie: public_url.starts_with("https://"),
+        })
+    }
+
+    async fn provider(&self, refresh: bool) -> Result<Arc<Provider>, ApiError> {
+        if !refresh {
+            let cached = self
+                .provider
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .clone();
+            if let Some(provider) = cached.filter(|p| p.fetched_at.elapsed() < PROVIDER_TTL) {
+                return Ok(provider);
+            }
+        }
+        let provider = Arc::new(self.discover().await.map_err(|err| {
+            warn!(error = %err, issuer = %self.config.issuer_url, "OIDC discovery failed");
+            ApiError::OidcProviderUnavailable
+        })?);
+        *self.provider.lock().unwrap_or_else(|e| e.into_inner()) = Some(provider.clone());
+        Ok(provider)
+    }
+
+    async fn discover(&self) -> anyhow::Result<Provider> {
+        let url = format!(
+            "{}/.well-known/openid-configuration",
+            self.config.issuer_url
+        );
+        let document: DiscoveryDocument = self
+            .http
+            .get(&url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        if document.issuer.trim_end_matches('/') != self.config.issuer_url {
+            anyhow::bail!("discovery document is for issuer {}", document.issuer);
+        }
+        let jwks: JwkSet = self
+            .http
+            .get(&document.jwks_uri)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        Ok(Provider {
+            authorization_endpoint: document.authorization_endpoint,
+            token_endpoint: document.token_endpoint,
+            jwks,
+            fetched_at: Instant::now(),
+        })
+    }
+
+    fn sign_login(&self, login: &LoginState) -> Result<String, ApiError> {
+        encode(&Header::new(Algorithm::HS256), login, &self.encoding)
+            .map_err(|_| ApiError::OidcLoginFailed)
+    }
+
+    fn verify_login(&self, token: &str) -> Option<LoginState> {
+        let mut validation = Validation::new(Algorithm::HS256);
+        validation.leeway = 0;
+        validation.set_audience(&[LOGIN_AUDIENCE]);
+        decode::<LoginState>(token, &self.decoding, &validation)
+            .ok()
+            .map(|data| data.claims)
+    }
+
+    fn login_cookie(&self, value: &str, max_age: i64) -> Result<HeaderValue, ApiError> {
+        let secure = if self.secure_cookie { "; Secure" } else { "" };
+        HeaderValue::from_str(&format!(
+            "{LOGIN_COOKIE}={value}; Path={LOGIN_COOKIE_PATH}; Max-Age={max_age}; HttpOnly; SameSite=Lax{secure}"
+        ))
+        .map_err(|_| ApiError::OidcLoginFailed)
+    }
+
+    /// Exchanges the code and returns the verified claims of the ID token.
+    async fn redeem(&self, code: &str, login: &LoginState) -> Result<IdTokenClaims, ApiError> {
+        let provider = self.provider(false).await?;
+        let mut request = self.http.post(&provider.token_endpoint).form(&[
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", self.config.redirect_url.as_str()),
+            ("client_id", self.config.client_id.as_str()),
+            ("code_verifier", login.verifier.as_str()),
+        ]);
+        if let Some(secret) = &self.config.client_secret {
+            request = request.basic_auth(&self.config.client_id, Some(secret));
+        }
+        let tokens: TokenResponse =
+            async { request.send().await?.error_for_status()?.json().await }
+                .await
+                .map_err(|err| {
+                    warn!(error = %err, "OIDC code exchange failed");
+                    ApiError::OidcProviderUnavailable
+                })?;
+
+        let header = decode_header(&tokens.id_token).map_err(|_| ApiError::OidcTokenInvalid)?;
+        if matches!(
+            header.alg,
+            Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512
+        ) {
+            return Err(ApiError::OidcTokenInvalid);
+        }
+        let mut jwk = find_key(&provider.jwks, header.kid.as_deref()).cloned();
+        if jwk.is_none() {
+            // Unknown key id: the provider may have rotated its keys since discovery.
+            let provider = self.provider(true).await?;
+            jwk = find_key(&provider.jwks, header.kid.as_deref()).cloned();
+        }
+        let jwk = jwk.ok_or(ApiError::OidcTokenInvalid)?;
+        let key = DecodingKey::from_jwk(&jwk).map_err(|_| ApiError::OidcTokenInvalid)?;
+
+        let mut validation = Validation::new(header.alg);
+        validation.set_issuer(&[
+            &self.config.issuer_url,
+            &format!("{}/", self.config.issuer_url),
+        ]);
+        validation.set_audience(&[&self.config.client_id]);
+        let claims = decode::<IdTokenClaims>(&tokens.id_token, &key, &validation)
+            .map_err(|_| ApiError::OidcTokenInvalid)?
+            .claims;
+        if claims.nonce.as_deref() != Some(login.nonce.as_str()) {
+            return Err(ApiError::OidcTokenInvalid);
+        }
+        Ok(claims)
+    }
+}
+
+fn find_key<'a>(jwks: &'a JwkSet, kid: Option<&str>) -> Option<&'a jsonwebtoken::jwk::Jwk> {
+    match kid {
+        Some(kid) => jwks.find(kid),
+        None if jwks.keys.len() == 1 => jwks.keys.first(),
+        None => None,
+    }
+}
+
+fn random_token() -> String {
+    format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple())
+}
+
+/// Only paths of the web UI itself, so the login cannot be used as an open redirect.
+fn safe_return_to(return_to: Option<&str>) -> String {
+    return_to
+        .map(str::trim)
+        .filter(|path| path.starts_with('/') && !path.starts_with("//") && !path.contains('\\'))
+        .unwrap_or("/")
+        .to_string()
+}
+
+fn redirect(location: &str) -> Response {
+    match HeaderValue::from_str(location) {
+        Ok(location) => (StatusCode::FOUND, [(LOCATION, location)]).into_response(),
+        Err(_) => ApiError::OidcLoginFailed.into_response(),
+    }
+}
+
+fn cookie_value<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers
+        .get_all(COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value)
+}
+
+#[derive(Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct OidcLoginQuery {
+    /// Path of the web UI to open after signing in, `/` by default.
+    return_to: Option<String>,
+}
+
+/// Starts single sign-on: redirects the browser to the provider's authorization endpoint.
+/// The state, nonce and PKCE verifier travel in a signed HttpOnly cookie scoped to
+/// `/api/auth/oidc`.
+#[utoipa::path(
+    get,
+    path = "/api/auth/oidc/login",
+    tag = "auth",
+    params(OidcLoginQuery),
+    responses((status = 302, description = "Redirect to the identity provider")),
+    security(())
+)]
+pub async fn oidc_login(
+    State(state): State<AppState>,
+    Query(query): Query<OidcLoginQuery>,
+) -> Result<Response, ApiError> {
+    let client = state.oidc.as_ref().ok_or(ApiError::OidcNotConfigured)?;
+    let provider = client.provider(false).await?;
+
+    let login = LoginState {
+        aud: LOGIN_AUDIENCE.to_string(),
+        exp: chrono::Utc::now().timestamp() + LOGIN_TTL_SECS,
+        state: random_token(),
+        nonce: random_token(),
+        verifier: random_token(),
+        return_to: safe_return_to(query.return_to.as_deref()),
+    };
+    let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(login.verifier.as_bytes()));
+    let url = Url::parse_with_params(
+        &provider.authorization_endpoint,
+        &[
+            ("response_type", "code"),
+            ("client_id", client.config.client_id.as_str()),
+            ("redirect_uri", client.config.redirect_url.as_str()),
+            ("scope", client.config.scopes.as_str()),
+            ("state", login.state.as_str()),
+            ("nonce", login.nonce.as_str()),
+            ("code_challenge", challenge.as_str()),
+            ("code_challenge_method", "S256"),
+        ],
+    )
+    .map_err(|_| ApiError::OidcProviderUnavailable)?;
+
+    let cookie = client.login_cookie(&client.sign_login(&login)?, LOGIN_TTL_SECS)?;
+    let mut response = redirect(url.as_str());
+    response.headers_mut().insert(SET_COOKIE, cookie);
+    Ok(response)
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct OidcCallbackQuery {
+    code: Option<String>,
+    state: Option<String>,
+    /// Set by the provider instead of `code` when the user declined or the request failed.
+    error: Option<String>,
+}
+
+/// Completes single sign-on and redirects to the web UI. On success the fragment carries
+/// `token`, `refreshToken` and `expiresIn`; on failure it carries `oidcError` with the error
+/// code. The user is found by the linked provider account, then by a verified email (and
+/// linked), and is created otherwise; pending project invitations are accepted as on
+/// registration.
+#[utoipa::path(
+    get,
+    path = "/api/auth/oidc/callback",
+    tag = "auth",
+    params(OidcCallbackQuery),
+    responses((status = 302, description = "Redirect to the web UI")),
+    security(())
+)]
+pub async fn oidc_callback(
+    State(state): State<AppState>,
+    Query(query): Query<OidcCallbackQuery>,
+    headers: HeaderMap,
+) -> Response {
+    let Some(client) = state.oidc.clone() else {
+        return ApiError::OidcNotConfigured.into_response();
+    };
+    let login = cookie_value(&headers, LOGIN_COOKIE).and_then(|value| client.verify_login(value));
+    let base = state.public_url.trim_end_matches('/');
+    let return_to = login
+        .as_ref()
+        .map(|login| login.return_to.as_str())
+        .unwrap_or("/");
+    let fragment = match complete_login(&state, &client, &query, login.as_ref()).await {
+        Ok(auth) => format!(
+            "token={}&refreshToken={}&expiresIn={}",
+            auth.token, auth.refresh_token, auth.expires_in
+        ),
+        Err(err) => format!("oidcError={}", err.code()),
+    };
+    let mut response = redirect(&format!("{base}{return_to}#{fragment}"));
+    if let Ok(cookie) = client.login_cookie("", 0) {
+        response.headers_mut().insert(SET_COOKIE, cookie);
+    }
+    response
+}
+
+async fn complete_login(
+    state: &AppState,
+    client: &OidcClient,
+    query: &OidcCallbackQuery,
+    login: Option<&LoginState>,
+) -> Result<crate::AuthResponse, ApiError> {
+    let login = login.ok_or(ApiError::OidcLoginStateInvalid)?;
+    if query.state.as_deref() != Some(login.state.as_str()) {
+        return Err(ApiError::OidcLoginStateInvalid);
+    }
+    if query.error.is_some() {
+        return Err(ApiError::OidcLoginDenied);
+    }
+    let code = query.code.as_deref().ok_or(ApiError::OidcLoginDenied)?;
+    let claims = client.redeem(code, login).await?;
+    let identity = OidcIdentity {
+        issuer: client.config.issuer_url.clone(),
+        subject: claims.sub.clone(),
+    };
+
+    let _guard = state.file_lock.lock().await;
+    let mut users = read_users(&state.users_file)
+        .await
+        .map_err(|_| ApiError::OidcLoginFailed)?;
+
+    let linked = users.iter().position(|u| {
+        u.oidc.as_ref().is_some_and(|linked| {
+            linked.issuer == identity.issuer && linked.subject == identity.subject
+        })
+    });
+    let user = match linked {
+        Some(index) => users[index].clone(),
+        None => {
+            let email = claims
+                .verified_email()
+                .ok_or(ApiError::OidcEmailNotVerified)?;
+            match users.iter_mut().find(|u| u.email == email) {
+                Some(existing) => {
+                    if existing.oidc.is_some() {
+                        return Err(ApiError::OidcAccountLinkedElsewhere);
+                    }
+                    existing.oidc = Some(identity);
+                    // The provider vouched for the address.
+                    existing.email_verified = true;
+                    existing.email_verification = None;
+                    let user = existing.clone();
+                    write_users(&state.users_file, &users)
+                        .await
+                        .map_err(|_| ApiError::OidcLoginFailed)?;
+                    user
+                }
+                None => provision(state, &mut users, &claims, email, identity).await?,
+            }
+        }
+    };
+
+    issue_auth_response(&state.jwt, &user, ApiError::OidcLoginFailed)
+}
+
+/// Creates the account of a first-time single sign-on user. It has no local password.
+async fn provision(
+    state: &AppState,
+    users: &mut Vec<User>,
+    claims: &IdTokenClaims,
+    email: String,
+    identity: OidcIdentity,
+) -> Result<User, ApiError> {
+    let user = User {
+        id: Uuid::new_v4().to_string(),
+        name: claims.display_name(&email),
+        email,
+        password_hash: String::new(),
+        created_at: now_iso(),
+        pending_email: None,
+        avatar: None,
+        oidc: Some(identity),
+        email_verified: true,
+        email_verification: None,
+        is_admin: false,
+        deactivated_at: None,
+        password_reset: None,
+        service_account: None,
+    };
+    let mut projects = read_projects(&state.projects_file)
+        .await
+        .map_err(|_| ApiError::OidcLoginFailed)?;
+    let attached = invitations::attach_pending_invitations(&mut projects, &user);
+    users.push(user.clone());
+    write_users(&state.users_file, users)
+        .await
+        .map_err(|_| ApiError::OidcLoginFailed)?;
+    if !attached.is_empty() {
+        write_projects(state, &projects)
+            .await
+            .map_err(|_| ApiError::OidcLoginFailed)?;
+    }
+    invitations::emit_attached(state, &user, &attached).await;
+    Ok(user)
+}
@@ -0,0 +1,308 @@
+use utoipa::{
+    openapi::{
+        security::{HttpAuthScheme, HttpBuilder, SecurityScheme},
+        RefOr, Response, ResponseBuilder,
+    },
+    Modify, OpenApi,
+};
+
+use crate::{
+    account, admin, analytics, api_keys, assets, assignments, attachments, audit, bundle, cache,
+    chat, ci, comments, custom_fields, custom_reports, dashboard, db_pool, dedup, defects,
+    dependencies, effort, email_reply, error::ErrorResponse, export, fail_reasons, gherkin, health,
+    inbox, ingest, invitations, jira, jobs, live, milestones, notifications, oidc, organizations,
+    overview, permissions, profile, quotas, report, requirements, result_history, result_import,
+    retention, revocation, run_diff, saved_filters, schedules, search, service_accounts, session,
+    suites, tags, telegram, testcase_import, testcases, webhooks,
+};
+
+/// The spec is derived from `#[utoipa::path]` on every handler; a route missing here is
+/// missing from `/api/openapi.json`.
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Uran API", description = "API системы ручного тестирования Uran."),
+    paths(
+        health::health,
+        health::live,
+        health::ready,
+        crate::register,
+        crate::login,
+        crate::refresh,
+        revocation::logout,
+        oidc::oidc_login,
+        oidc::oidc_callback,
+        profile::update_profile,
+        account::delete_account,
+        account::export_account,
+        profile::confirm_email,
+        profile::verify_email,
+        profile::resend_verification,
+        profile::change_password,
+        profile::reset_password,
+        profile::upload_avatar,
+        profile::delete_avatar,
+        profile::get_avatar,
+        crate::me,
+        api_keys::create_api_key,
+        api_keys::list_api_keys,
+        api_keys::revoke_api_key,
+        crate::list_fail_reasons,
+        admin::list_users,
+        db_pool::pool_metrics,
+        cache::cache_metrics,
+        admin::update_user,
+        admin::deactivate_user,
+        admin::reactivate_user,
+        admin::force_password_reset,
+        admin::list_projects,
+        admin::reassign_owner,
+        service_accounts::list_service_accounts,
+        service_accounts::create_service_account,
+        service_accounts::delete_service_account,
+        service_accounts::create_service_account_key,
+        service_accounts::list_service_account_keys,
+        service_accounts::revoke_service_account_key,
+        quotas::set_storage_quota,
+        quotas::reset_storage_quota,
+        organizations::create_organization,
+        organizations::list_organizations,
+        organizations::get_organization,
+        organizations::update_organization,
+        organizations::add_organization_member,
+        organizations::update_organization_member,
+        organizations::remove_organization_member,
+        crate::list_projects,
+        crate::create_project,
+        crate::update_project,
+        invitations::create_invitation,
+        invitations::list_invitations,
+        invitations::revoke_invitation,
+        invitations::get_invitation,
+        notifications::get_preferences,
+        notifications::update_preferences,
+        notifications::unsubscribe,
+        notifications::get_settings,
+        notifications::update_settings,
+        inbox::list_notifications,
+        inbox::mark_read,
+        inbox::mark_all_read,
+        email_reply::receive_inbound_email,
+        telegram::get_link,
+        telegram::create_link_code,
+        telegram::delete_link,
+        crate::add_member,
+        crate::list_members,
+        crate::update_member,
+        crate::remove_member,
+        crate::get_session,
+        crate::save_session,
+        session::patch_session,
+        bundle::export_project,
+        bundle::import_project,
+        permissions::list_roles,
+        permissions::upsert_role,
+        permissions::delete_role,
+        crate::create_run_v2,
+        crate::list_runs_v2,
+        crate::get_run_details_v2,
+        crate::update_run_v2,
+        crate::update_run_status_v2,
+        crate::unlock_run_v2,
+        crate::clone_run_v2,
+        crate::get_run_summary_v2,
+        crate::add_run_item_v2,
+        crate::bulk_add_run_items_v2,
+        crate::reorder_run_items_v2,
+        crate::delete_run_item_v2,
+        crate::update_run_result_v2,
+        crate::bulk_update_run_results_v2,
+        ingest::ingest_results,
+        result_history::get_result_history,
+        result_history::list_testcase_executions,
+        comments::list_comments,
+        comments::create_comment,
+        comments::update_comment,
+        comments::delete_comment,
+        result_import::import_report,
+        search::search,
+        suites::create_suite,
+        suites::get_suite_tree,
+        suites::update_suite,
+        suites::move_suite,
+        suites::assign_testcase_suite,
+        testcases::list_testcases,
+        testcases::list_testcase_versions,
+        testcases::create_testcase_version,
+        testcases::archive_testcase,
+        testcases::restore_testcase,
+        testcases::get_testcase_usage,
+        testcases::delete_testcase,
+        dedup::list_duplicates,
+        dedup::merge_testcase,
+        assets::list_assets,
+        assets::get_asset,
+        assets::create_asset,
+        assets::update_asset,
+        assets::delete_asset,
+        assets::list_asset_versions,
+        quotas::project_usage,
+        retention::get_retention,
+        retention::set_retention,
+        retention::preview_retention,
+        custom_fields::list_custom_fields,
+        custom_fields::create_custom_field,
+        custom_fields::update_custom_field,
+        custom_fields::delete_custom_field,
+        custom_fields::update_testcase_values,
+        custom_fields::update_run_values,
+        saved_filters::list_saved_filters,
+        saved_filters::create_saved_filter,
+        saved_filters::update_saved_filter,
+        saved_filters::delete_saved_filter,
+        custom_reports::list_reports,
+        custom_reports::create_report,
+        custom_reports::update_report,
+        custom_reports::delete_report,
+        custom_reports::run_report,
+        custom_reports::preview_report,
+        tags::get_testcase_tags,
+        tags::replace_testcase_tags,
+        tags::add_testcase_tags,
+        tags::list_project_tags,
+        tags::rename_project_tag,
+        tags::delete_project_tag,
+        testcase_import::import_testcases,
+        testcase_import::download_import_errors,
+        gherkin::import_gherkin,
+        requirements::list_requirements,
+        requirements::create_requirement,
+        requirements::update_requirement,
+        requirements::delete_requirement,
+        requirements::set_requirement_testcases,
+        requirements::get_traceability,
+        fail_reasons::list_project_fail_reasons,
+        fail_reasons::create_project_fail_reason,
+        fail_reasons::update_project_fail_reason,
+        fail_reasons::delete_project_fail_reason,
+        milestones::list_milestones,
+        milestones::create_milestone,
+        milestones::update_milestone,
+        milestones::delete_milestone,
+        milestones::milestone_burndown,
+        webhooks::create_webhook,
+        webhooks::list_webhooks,
+        webhooks::delete_webhook,
+        webhooks::list_deliveries,
+        chat::list_chat_webhooks,
+        chat::create_chat_webhook,
+        chat::update_chat_webhook,
+        chat::delete_chat_webhook,
+        chat::test_chat_webhook,
+        audit::list_project_audit,
+        audit::export_project_audit,
+        analytics::project_analytics,
+        overview::reports_overview,
+        effort::project_effort,
+        export::export_run,
+        export::export_project_runs,
+        run_diff::diff_runs,
+        report::run_report_pdf,
+        report::queue_run_report,
+        jobs::get_job,
+        jobs::download_job_file,
+        assignments::set_run_default_assignee,
+        assignments::set_run_executor,
+        assignments::set_item_assignee,
+        assignments::list_my_assignments,
+        dashboard::get_dashboard,
+        dependencies::set_item_dependencies,
+        live::run_socket,
+        live::project_events,
+        defects::link_defect,
+        defects::unlink_defect,
+        ci::get_ci_connection,
+        ci::put_ci_connection,
+        ci::delete_ci_connection,
+        schedules::list_schedules,
+        schedules::create_schedule,
+        schedules::update_schedule,
+        schedules::delete_schedule,
+        schedules::run_schedule_now,
+        jira::get_jira_connection,
+        jira::put_jira_connection,
+        jira::delete_jira_connection,
+        jira::create_jira_issue,
+        defects::get_issue_tracker,
+        defects::put_issue_tracker,
+        attachments::upload_attachment,
+        attachments::list_item_attachments,
+        attachments::delete_attachment,
+        attachments::download_attachment,
+    ),
+    components(schemas(ErrorResponse)),
+    modifiers(&ApiConventions),
+    security(("bearer" = []))
+)]
+pub struct ApiDoc;
+
+/// Bearer auth scheme plus the `{ "error": { "code", "message" } }` body every handler returns
+/// on failure, so handlers only declare their success response.
+struct ApiConventions;
+
+impl Modify for ApiConventions {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .bearer_format("JWT")
+                    .description(Some(
+                        "Access-токен из /api/auth/login или API-ключ uran_... (только /api/v2/*).",
+                    ))
+                    .build(),
+            ),
+        );
+
+        let error = |description: &str| -> RefOr<Response> {
+            ResponseBuilder::new()
+                .description(description)
+                .content(
+                    "application/json",
+                    utoipa::openapi::ContentBuilder::new()
+                        .schema(Some(utoipa::openapi::Ref::from_schema_name(
+                            "ErrorResponse",
+                        )))
+                        .build(),
+                )
+                .build()
+                .into()
+        };
+        for item in openapi.paths.paths.values_mut() {
+            let operations = [
+                &mut item.get,
+                &mut item.post,
+                &mut item.put,
+                &mut item.patch,
+                &mut item.delete,
+            ];
+            for operation in operations.into_iter().flatten() {
+                let responses = &mut operation.responses.responses;
+                for response in responses.values_mut() {
+                    if let RefOr::T(response) = response {
+                        if response.description.is_empty() {
+                            response.description = "Успешный ответ.".to_string();
+                        }
+                    }
+                }
+                responses
+                    .entry("4XX".to_string())
+                    .or_insert_with(|| error("Ошибка запроса, авторизации или доступа."));
+                responses
+                    .entry("5XX".to_string())
+                    .or_insert_with(|| error("Внутренняя ошибка сервера."));
+            }
+        }
+    }
+}
@@ -0,0 +1,542 @@
+use std::path::{Path as StdPath, PathBuf};
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{
+    authz::AuthUser, ensure_json_file, error::ApiError, now_iso, read_users, AppState, Project,
+};
+
+const ORGANIZATIONS_FILE: &str = "organizations.json";
+
+/// Org roles; `owner` and `admin` manage the organization and act as `org_admin` in all of
+/// its projects, `member` may only create projects in it.
+const ORGANIZATION_ROLES: [&str; 3] = ["owner", "admin", "member"];
+
+/// Organization above projects, stored in `organizations.json` next to `projects.json` and
+/// guarded by the same file lock.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Organization {
+    pub id: String,
+    pub name: String,
+    pub created_at: String,
+    pub updated_at: String,
+    pub members: Vec<OrganizationMember>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct OrganizationMember {
+    pub user_id: String,
+    pub role: String,
+}
+
+impl Organization {
+    pub fn role_of(&self, user_id: &str) -> Option<&str> {
+        self.members
+            .iter()
+            .find(|m| m.user_id == user_id)
+            .map(|m| m.role.as_str())
+    }
+
+    pub fn is_admin(&self, user_id: &str) -> bool {
+        matches!(self.role_of(user_id), Some("owner" | "admin"))
+    }
+
+    pub fn admin_ids(&self) -> Vec<String> {
+        self.members
+            .iter()
+            .filter(|m| m.role == "owner" || m.role == "admin")
+            .map(|m| m.user_id.clone())
+            .collect()
+    }
+
+    pub fn owner_count(&self) -> usize {
+        self.members.iter().filter(|m| m.role == "owner").count()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct OrganizationsFile {
+    organizations: Vec<Organization>,
+}
+
+pub fn file_next_to(projects_file: &StdPath) -> PathBuf {
+    projects_file.with_file_name(ORGANIZATIONS_FILE)
+}
+
+pub async fn read_organizations(path: &StdPath) -> anyhow::Result<Vec<Organization>> {
+    ensure_json_file(path, "{\n  \"organizations\": []\n}\n").await?;
+    let raw = fs::read_to_string(path).await?;
+    Ok(serde_json::from_str::<OrganizationsFile>(&raw)?.organizations)
+}
+
+/// Also drops cached projects, whose `organization_admins` come from this file.
+pub async fn write_organizations(
+    state: &AppState,
+    organizations: &[Organization],
+) -> anyhow::Result<()> {
+    let data = OrganizationsFile {
+        organizations: organizations.to_vec(),
+    };
+    let raw = serde_json::to_string_pretty(&data)?;
+    fs::write(&state.organizations_file, raw).await?;
+    state.cache.projects.invalidate_all();
+    Ok(())
+}
+
+/// Fills `organization_admins` of every project that belongs to an organization, so
+/// membership checks see org admins without loading organizations themselves.
+pub async fn attach_admins(
+    projects_file: &StdPath,
+    projects: &mut [Project],
+) -> anyhow::Result<()> {
+    if projects.iter().all(|p| p.organization_id.is_none()) {
+        return Ok(());
+    }
+    let organizations = read_organizations(&file_next_to(projects_file)).await?;
+    for project in projects.iter_mut() {
+        let Some(organization) = project
+            .organization_id
+            .as_deref()
+            .and_then(|id| organizations.iter().find(|o| o.id == id))
+        else {
+            continue;
+        };
+        project.organization_admins = organization.admin_ids();
+    }
+    Ok(())
+}
+
+/// The organization a project is created in or moved to; `admin` requires an org admin.
+pub fn require_member<'a>(
+    organizations: &'a [Organization],
+    organization_id: &str,
+    user_id: &str,
+    admin: bool,
+) -> Result<&'a Organization, ApiError> {
+    let organization = organizations
+        .iter()
+        .find(|o| o.id == organization_id)
+        .ok_or(ApiError::OrganizationNotFound)?;
+    if organization.role_of(user_id).is_none() {
+        return Err(ApiError::NoOrganizationAccess);
+    }
+    if admin && !organization.is_admin(user_id) {
+        return Err(ApiError::NoOrganizationManageRight);
+    }
+    Ok(organization)
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateOrganizationRequest {
+    name: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct UpdateOrganizationRequest {
+    name: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct AddOrganizationMemberRequest {
+    email: String,
+    role: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct UpdateOrganizationMemberRequest {
+    role: String,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct OrganizationView {
+    id: String,
+    name: String,
+    /// The caller's role in the organization.
+    role: String,
+    created_at: String,
+    updated_at: String,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct OrganizationMemberView {
+    user_id: String,
+    email: String,
+    name: String,
+    role: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct OrganizationResponse {
+    organization: OrganizationView,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ListOrganizationsResponse {
+    organizations: Vec<OrganizationView>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct OrganizationDetailsResponse {
+    organization: OrganizationView,
+    members: Vec<OrganizationMemberView>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct RemoveOrganizationMemberResponse {
+    ok: bool,
+}
+
+fn map_organization(organization: &Organization, user_id: &str) -> Option<OrganizationView> {
+    Some(OrganizationView {
+        id: organization.id.clone(),
+        name: organization.name.clone(),
+        role: organization.role_of(user_id)?.to_string(),
+        created_at: organization.created_at.clone(),
+        updated_at: organization.updated_at.clone(),
+    })
+}
+
+fn validate_name(name: &str) -> Result<String, ApiError> {
+    let name = name.trim();
+    if name.chars().count() < 3 {
+        return Err(ApiError::OrganizationNameTooShort);
+    }
+    Ok(name.to_string())
+}
+
+fn parse_role(role: &str) -> Result<String, ApiError> {
+    let role = role.trim().to_lowercase();
+    if !ORGANIZATION_ROLES.contains(&role.as_str()) {
+        return Err(ApiError::InvalidOrganizationRole);
+    }
+    Ok(role)
+}
+
+/// Owners are granted and revoked only by owners.
+fn ensure_owner_change_allowed(
+    organization: &Organization,
+    actor_id: &str,
+    roles: [&str; 2],
+) -> Result<(), ApiError> {
+    if roles.contains(&"owner") && organization.role_of(actor_id) != Some("owner") {
+        return Err(ApiError::OrganizationOwnerRoleDenied);
+    }
+    Ok(())
+}
+
+async fn details(
+    state: &AppState,
+    organization: &Organization,
+    user_id: &str,
+) -> Result<OrganizationDetailsResponse, ApiError> {
+    let users = read_users(&state.users_file)
+        .await
+        .map_err(|_| ApiError::OrganizationsLoadFailed)?;
+    let members = organization
+        .members
+        .iter()
+        .map(|m| {
+            let user = users.iter().find(|u| u.id == m.user_id);
+            OrganizationMemberView {
+                user_id: m.user_id.clone(),
+                email: user.map(|u| u.email.clone()).unwrap_or_default(),
+                name: user.map(|u| u.name.clone()).unwrap_or_default(),
+                role: m.role.clone(),
+            }
+        })
+        .collect();
+    Ok(OrganizationDetailsResponse {
+        organization: map_organization(organization, user_id)
+            .ok_or(ApiError::NoOrganizationAccess)?,
+        members,
+    })
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/organizations",
+    tag = "organizations",
+    request_body = CreateOrganizationRequest,
+    responses((status = 201, body = OrganizationResponse))
+)]
+pub async fn create_organization(
+    State(state): State<AppState>,
+    AuthUser(user_id): AuthUser,
+    Json(payload): Json<CreateOrganizationRequest>,
+) -> Result<(StatusCode, Json<OrganizationResponse>), ApiError> {
+    let name = validate_name(&payload.name)?;
+
+    let _guard = state.file_lock.lock().await;
+    let mut organizations = read_organizations(&state.organizations_file)
+        .await
+        .map_err(|_| ApiError::OrganizationSaveFailed)?;
+    let now = now_iso();
+    let organization = Organization {
+        id: Uuid::new_v4().to_string(),
+        name,
+        created_at: now.clone(),
+        updated_at: now,
+        members: vec![OrganizationMember {
+            user_id: user_id.clone(),
+            role: "owner".to_string(),
+        }],
+    };
+    let view = map_organization(&organization, &user_id).ok_or(ApiError::OrganizationSaveFailed)?;
+    organizations.push(organization);
+    write_organizations(&state, &organizations)
+        .await
+        .map_err(|_| ApiError::OrganizationSaveFailed)?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(OrganizationResponse { organization: view }),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/organizations",
+    tag = "organizations",
+    responses((status = 200, body = ListOrganizationsResponse))
+)]
+pub async fn list_organizations(
+    State(state): State<AppState>,
+    AuthUser(user_id): AuthUser,
+) -> Result<Json<ListOrganizationsResponse>, ApiError> {
+    let _guard = state.file_lock.lock().await;
+    let organizations = read_organizations(&state.organizations_file)
+        .await
+        .map_err(|_| ApiError::OrganizationsLoadFailed)?;
+    Ok(Json(ListOrganizationsResponse {
+        organizations: organizations
+            .iter()
+            .filter_map(|o| map_organization(o, &user_id))
+            .collect(),
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/organizations/{organization_id}",
+    tag = "organizations",
+    params(("organization_id" = String, Path)),
+    responses((status = 200, body = OrganizationDetailsResponse))
+)]
+pub async fn get_organization(
+    State(state): State<AppState>,
+    Path(organization_id): Path<String>,
+    AuthUser(user_id): AuthUser,
+) -> Result<Json<OrganizationDetailsResponse>, ApiError> {
+    let _guard = state.file_lock.lock().await;
+    let organizations = read_organizations(&state.organizations_file)
+        .await
+        .map_err(|_| ApiError::OrganizationsLoadFailed)?;
+    let organization = require_member(&organizations, &organization_id, &user_id, false)?;
+    Ok(Json(details(&state, organization, &user_id).await?))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/api/organizations/{organization_id}",
+    tag = "organizations",
+    params(("organization_id" = String, Path)),
+    request_body = UpdateOrganizationRequest,
+    responses((status = 200, body = OrganizationResponse))
+)]
+pub async fn update_organization(
+    State(state): State<AppState>,
+    Path(organization_id): Path<String>,
+    AuthUser(user_id): AuthUser,
+    Json(payload): Json<UpdateOrganizationRequest>,
+) -> Result<Json<OrganizationResponse>, ApiError> {
+    let name = validate_name(&payload.name)?;
+
+    let _guard = state.file_lock.lock().await;
+    let mut organizations = read_organizations(&state.organizations_file)
+        .await
+        .map_err(|_| ApiError::OrganizationSaveFailed)?;
+    require_member(&organizations, &organization_id, &user_id, true)?;
+    let organization = organizations
+        .iter_mut()
+        .find(|o| o.id == organization_id)
+        .ok_or(ApiError::OrganizationNotFound)?;
+    organization.name = name;
+    organization.updated_at = now_iso();
+    let view = map_organization(organization, &user_id).ok_or(ApiError::OrganizationSaveFailed)?;
+    write_organizations(&state, &organizations)
+        .await
+        .map_err(|_| ApiError::OrganizationSaveFailed)?;
+
+    Ok(Json(OrganizationResponse { organization: view }))
+}
+
+/// Adds a registered user or changes the role of an existing member.
+#[utoipa::path(
+    post,
+    path = "/api/organizations/{organization_id}/members",
+    tag = "organizations",
+    params(("organization_id" = String, Path)),
+    request_body = AddOrganizationMemberRequest,
+    responses((status = 200, body = OrganizationDetailsResponse))
+)]
+pub async fn add_organization_member(
+    State(state): State<AppState>,
+    Path(organization_id): Path<String>,
+    AuthUser(actor_id): AuthUser,
+    Json(payload): Json<AddOrganizationMemberRequest>,
+) -> Result<Json<OrganizationDetailsResponse>, ApiError> {
+    let email = payload.email.trim().to_lowercase();
+    if !email.contains('@') {
+        return Err(ApiError::InvalidEmail);
+    }
+    let role = parse_role(&payload.role)?;
+
+    let _guard = state.file_lock.lock().await;
+    let users = read_users(&state.users_file)
+        .await
+        .map_err(|_| ApiError::OrganizationSaveFailed)?;
+    let invitee = users
+        .iter()
+        .find(|u| u.email == email)
+        .ok_or(ApiError::MemberEmailNotFound)?;
+    let mut organizations = read_organizations(&state.organizations_file)
+        .await
+        .map_err(|_| ApiError::OrganizationSaveFailed)?;
+    let organization = require_member(&organizations, &organization_id, &actor_id, true)?;
+    let previous = organization.role_of(&invitee.id).unwrap_or("member");
+    ensure_owner_change_allowed(organization, &actor_id, [previous, role.as_str()])?;
+    if previous == "owner" && role != "owner" && organization.owner_count() == 1 {
+        return Err(ApiError::OrganizationLastOwner);
+    }
+
+    let organization = organizations
+        .iter_mut()
+        .find(|o| o.id == organization_id)
+        .ok_or(ApiError::OrganizationNotFound)?;
+    match organization
+        .members
+        .iter_mut()
+        .find(|m| m.user_id == invitee.id)
+    {
+        Some(existing) => existing.role = role,
+        None => organization.members.push(OrganizationMember {
+            user_id: invitee.id.clone(),
+            role,
+        }),
+    }
+    organization.updated_at = now_iso();
+    let organization = organization.clone();
+    write_organizations(&state, &organizations)
+        .await
+        .map_err(|_| ApiError::OrganizationSaveFailed)?;
+
+    Ok(Json(details(&state, &organization, &actor_id).await?))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/api/organizations/{organization_id}/members/{user_id}",
+    tag = "organizations",
+    params(("organization_id" = String, Path), ("user_id" = String, Path)),
+    request_body = UpdateOrganizationMemberRequest,
+    responses((status = 200, body = OrganizationDetailsResponse))
+)]
+pub async fn update_organization_member(
+    State(state): State<AppState>,
+    Path((organization_id, member_id)): Path<(String, String)>,
+    AuthUser(actor_id): AuthUser,
+    Json(payload): Json<UpdateOrganizationMemberRequest>,
+) -> Result<Json<OrganizationDetailsResponse>, ApiError> {
+    let role = parse_role(&payload.role)?;
+
+    let _guard = state.file_lock.lock().await;
+    let mut organizations = read_organizations(&state.organizations_file)
+        .await
+        .map_err(|_| ApiError::OrganizationSaveFailed)?;
+    let organization = require_member(&organizations, &organization_id, &actor_id, true)?;
+    let previous = organization
+        .role_of(&member_id)
+        .ok_or(ApiError::OrganizationMemberNotFound)?;
+    ensure_owner_change_allowed(organization, &actor_id, [previous, role.as_str()])?;
+    if previous == "owner" && role != "owner" && organization.owner_count() == 1 {
+        return Err(ApiError::OrganizationLastOwner);
+    }
+
+    let organization = organizations
+        .iter_mut()
+        .find(|o| o.id == organization_id)
+        .ok_or(ApiError::OrganizationNotFound)?;
+    if let Some(member) = organization
+        .members
+        .iter_mut()
+        .find(|m| m.user_id == member_id)
+    {
+        member.role = role;
+    }
+    organization.updated_at = now_iso();
+    let organization = organization.clone();
+    write_organizations(&state, &organizations)
+        .await
+        .map_err(|_| ApiError::OrganizationSaveFailed)?;
+
+    Ok(Json(details(&state, &organization, &actor_id).await?))
+}
+
+/// Admins remove members; any member may leave. Project memberships are kept, only the
+/// org-wide access goes away.
+#[utoipa::path(
+    delete,
+    path = "/api/organizations/{organization_id}/members/{user_id}",
+    tag = "organizations",
+    params(("organization_id" = String, Path), ("user_id" = String, Path)),
+    responses((status = 200, body = RemoveOrganizationMemberResponse))
+)]
+pub async fn remove_organization_member(
+    State(state): State<AppState>,
+    Path((organization_id, member_id)): Path<(String, String)>,
+    AuthUser(actor_id): AuthUser,
+) -> Result<Json<RemoveOrganizationMemberResponse>, ApiError> {
+    let _guard = state.file_lock.lock().await;
+    let mut organizations = read_organizations(&state.organizations_file)
+        .await
+        .map_err(|_| ApiError::OrganizationSaveFailed)?;
+    let organization = require_member(
+        &organizations,
+        &organization_id,
+        &actor_id,
+        member_id != actor_id,
+    )?;
+    let previous = organization
+        .role_of(&member_id)
+        .ok_or(ApiError::OrganizationMemberNotFound)?;
+    if member_id != actor_id {
+        ensure_owner_change_allowed(organization, &actor_id, [previous, previous])?;
+    }
+    if previous == "owner" && organization.owner_count() == 1 {
+        return Err(ApiError::OrganizationLastOwner);
+    }
+
+    let organization = organizations
+        .iter_mut()
+        .find(|o| o.id == organization_id)
+        .ok_or(ApiError::OrganizationNotFound)?;
+    organization.members.retain(|m| m.user_id != member_id);
+    organization.updated_at = now_iso();
+    write_organizations(&state, &organizations)
+        .await
+        .map_err(|_| ApiError::OrganizationSaveFailed)?;
+
+    Ok(Json(RemoveOrganizationMemberResponse { ok: true }))
+}
@@ -0,0 +1,345 @@
+use std::collections::HashMap;
+
+use axum::{
+    extract::{Query, State},
+    http::header,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{Days, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::{
+    admin, api_keys, authz::AuthUser, error::ApiError, export, now_iso, parse_uuid, read_projects,
+    read_users, AppState, Project,
+};
+
+const DEFAULT_DAYS: u64 = 30;
+const MAX_DAYS: i64 = 365;
+
+const CSV_COLUMNS: [&str; 9] = [
+    "Проект",
+    "ID проекта",
+    "OK",
+    "FAIL",
+    "Pass rate, %",
+    "Открытые FAIL обязательных",
+    "Создано run",
+    "Начато run",
+    "Завершено run",
+];
+
+#[derive(Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct OverviewQuery {
+    /// First day of the period, `YYYY-MM-DD` in UTC (default 29 days before `to`).
+    from: Option<NaiveDate>,
+    /// Last day of the period, inclusive (default today). The period is at most 365 days.
+    to: Option<NaiveDate>,
+    /// Comma-separated project ids; all projects in the caller's scope when omitted.
+    project_id: Option<String>,
+    /// `json` (default) or `csv`.
+    format: Option<String>,
+}
+
+/// Figures of one project or of the whole report.
+#[derive(Serialize, Default, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct OverviewTotals {
+    /// `ok` results recorded in the period.
+    ok: i64,
+    /// `fail` results recorded in the period.
+    fail: i64,
+    /// `ok / (ok + fail)` in percent; `null` without results.
+    pass_rate: Option<f64>,
+    /// Required items currently failed in runs that are still `draft` or `in_progress`,
+    /// regardless of the period.
+    open_failed_required: i64,
+    runs_created: i64,
+    runs_started: i64,
+    runs_finished: i64,
+}
+
+impl OverviewTotals {
+    fn add(&mut self, other: &OverviewTotals) {
+        self.ok += other.ok;
+        self.fail += other.fail;
+        self.open_failed_required += other.open_failed_required;
+        self.runs_created += other.runs_created;
+        self.runs_started += other.runs_started;
+        self.runs_finished += other.runs_finished;
+        self.pass_rate = percent(self.ok, self.ok + self.fail);
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectOverview {
+    project_id: String,
+    name: String,
+    organization_id: Option<String>,
+    #[serde(flatten)]
+    totals: OverviewTotals,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct OverviewResponse {
+    from: NaiveDate,
+    to: NaiveDate,
+    /// Sorted by project name.
+    projects: Vec<ProjectOverview>,
+    totals: OverviewTotals,
+    generated_at: String,
+}
+
+fn percent(ok: i64, total: i64) -> Option<f64> {
+    (total > 0).then(|| (ok as f64 * 1000.0 / total as f64).round() / 10.0)
+}
+
+/// Projects the caller may report on: every project for system admins, otherwise the
+/// projects of organizations the caller owns or administers. API keys never get a scope.
+async fn scope(state: &AppState, user_id: &str) -> Result<Vec<Project>, ApiError> {
+    if api_keys::current().is_some() {
+        return Err(ApiError::ReportScopeRequired);
+    }
+    let _guard = state.file_lock.lock().await;
+    let users = read_users(&state.users_file)
+        .await
+        .map_err(|_| ApiError::AccessCheckFailed)?;
+    let system_admin = users
+        .iter()
+        .find(|u| u.id == user_id)
+        .is_some_and(|u| admin::is_admin(state, u));
+    let projects = read_projects(&state.projects_file)
+        .await
+        .map_err(|_| ApiError::AccessCheckFailed)?;
+    let projects: Vec<Project> = projects
+        .into_iter()
+        .filter(|p| system_admin || p.organization_admins.iter().any(|id| id == user_id))
+        .collect();
+    if !system_admin && projects.is_empty() {
+        return Err(ApiError::ReportScopeRequired);
+    }
+    Ok(projects)
+}
+
+fn period(query: &OverviewQuery) -> Result<(NaiveDate, NaiveDate), ApiError> {
+    let to = query.to.unwrap_or_else(|| Utc::now().date_naive());
+    let from = match query.from {
+        Some(from) => from,
+        None => to
+            .checked_sub_days(Days::new(DEFAULT_DAYS - 1))
+            .ok_or(ApiError::InvalidReportPeriod)?,
+    };
+    let days = (to - from).num_days() + 1;
+    if !(1..=MAX_DAYS).contains(&days) {
+        return Err(ApiError::InvalidReportPeriod);
+    }
+    Ok((from, to))
+}
+
+async fn compute(
+    state: &AppState,
+    projects: Vec<Project>,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<OverviewResponse, ApiError> {
+    let ids: Vec<Uuid> = projects
+        .iter()
+        .filter_map(|p| Uuid::parse_str(&p.id).ok())
+        .collect();
+    let start = from.and_time(Default::default()).and_utc();
+    let end = (to + Days::new(1)).and_time(Default::default()).and_utc();
+
+    let rows = sqlx::query!(
+        r#"
+        WITH results AS (
+          SELECT
+            r.project_id,
+            COUNT(*) FILTER (WHERE rr.status = 'ok') AS ok,
+            COUNT(*) FILTER (WHERE rr.status = 'fail') AS fail
+          FROM run_results rr
+          JOIN run_items ri ON ri.id = rr.run_item_id
+          JOIN runs r ON r.id = ri.run_id
+          WHERE r.project_id = ANY($1) AND rr.updated_at >= $2 AND rr.updated_at < $3
+          GROUP BY r.project_id
+        ),
+        open_failed AS (
+          SELECT r.project_id, COUNT(*) AS open_failed_required
+          FROM run_results rr
+          JOIN run_items ri ON ri.id = rr.run_item_id
+          JOIN runs r ON r.id = ri.run_id
+          WHERE r.project_id = ANY($1)
+            AND r.status IN ('draft', 'in_progress')
+            AND ri.is_required
+            AND rr.status = 'fail'
+          GROUP BY r.project_id
+        ),
+        throughput AS (
+          SELECT
+            project_id,
+            COUNT(*) FILTER (WHERE created_at >= $2 AND created_at < $3) AS created,
+            COUNT(*) FILTER (WHERE started_at >= $2 AND started_at < $3) AS started,
+            COUNT(*) FILTER (WHERE finished_at >= $2 AND finished_at < $3) AS finished
+          FROM runs
+          WHERE project_id = ANY($1)
+          GROUP BY project_id
+        )
+        SELECT
+          p.id AS "project_id!",
+          COALESCE(results.ok, 0) AS "ok!",
+          COALESCE(results.fail, 0) AS "fail!",
+          COALESCE(open_failed.open_failed_required, 0) AS "open_failed_required!",
+          COALESCE(throughput.created, 0) AS "runs_created!",
+          COALESCE(throughput.started, 0) AS "runs_started!",
+          COALESCE(throughput.finished, 0) AS "runs_finished!"
+        FROM UNNEST($1::uuid[]) AS p(id)
+        LEFT JOIN results ON results.project_id = p.id
+        LEFT JOIN open_failed ON open_failed.project_id = p.id
+        LEFT JOIN throughput ON throughput.project_id = p.id
+        "#,
+        &ids,
+        start,
+        end,
+    )
+    .fetch_all(state.read_db())
+    .await
+    .map_err(|_| ApiError::ReportFailed)?;
+    let mut figures: HashMap<Uuid, OverviewTotals> = rows
+        .into_iter()
+        .map(|row| {
+            (
+                row.project_id,
+                OverviewTotals {
+                    ok: row.ok,
+                    fail: row.fail,
+                    pass_rate: percent(row.ok, row.ok + row.fail),
+                    open_failed_required: row.open_failed_required,
+                    runs_created: row.runs_created,
+                    runs_started: row.runs_started,
+                    runs_finished: row.runs_finished,
+                },
+            )
+        })
+        .collect();
+
+    let mut totals = OverviewTotals::default();
+    let mut entries: Vec<ProjectOverview> = projects
+        .into_iter()
+        .map(|project| {
+            let figures = Uuid::parse_str(&project.id)
+                .ok()
+                .and_then(|id| figures.remove(&id))
+                .unwrap_or_default();
+            totals.add(&figures);
+            ProjectOverview {
+                project_id: project.id,
+                name: project.name,
+                organization_id: project.organization_id,
+                totals: figures,
+            }
+        })
+        .collect();
+    entries.sort_by(|a, b| {
+        a.name
+            .cmp(&b.name)
+            .then_with(|| a.project_id.cmp(&b.project_id))
+    });
+
+    Ok(OverviewResponse {
+        from,
+        to,
+        projects: entries,
+        totals,
+        generated_at: now_iso(),
+    })
+}
+
+fn csv_cells(name: &str, project_id: &str, totals: &OverviewTotals) -> [String; 9] {
+    [
+        name.to_string(),
+        project_id.to_string(),
+        totals.ok.to_string(),
+        totals.fail.to_string(),
+        totals.pass_rate.map(|p| p.to_string()).unwrap_or_default(),
+        totals.open_failed_required.to_string(),
+        totals.runs_created.to_string(),
+        totals.runs_started.to_string(),
+        totals.runs_finished.to_string(),
+    ]
+}
+
+fn render_csv(report: &OverviewResponse) -> anyhow::Result<Vec<u8>> {
+    let mut writer = csv::Writer::from_writer(export::UTF8_BOM.to_vec());
+    writer.write_record(CSV_COLUMNS)?;
+    for project in &report.projects {
+        writer.write_record(csv_cells(
+            &project.name,
+            &project.project_id,
+            &project.totals,
+        ))?;
+    }
+    writer.write_record(csv_cells("Итого", "", &report.totals))?;
+    Ok(writer.into_inner()?)
+}
+
+/// Pass rate, open failed required items and run throughput of every project in the
+/// caller's scope: all projects for system admins, the projects of their organizations for
+/// organization owners and admins.
+#[utoipa::path(
+    get,
+    path = "/api/v2/reports/overview",
+    tag = "analytics",
+    params(OverviewQuery),
+    responses(
+        (status = 200, body = OverviewResponse),
+        (status = 200, description = "`format=csv`: the same figures, one row per project and a total row.", content_type = "text/csv")
+    )
+)]
+pub async fn reports_overview(
+    State(state): State<AppState>,
+    AuthUser(user_id): AuthUser,
+    Query(query): Query<OverviewQuery>,
+) -> Result<Response, ApiError> {
+    let csv = match query.format.as_deref().map(str::trim) {
+        None | Some("json") => false,
+        Some("csv") => true,
+        Some(_) => return Err(ApiError::InvalidReportFormat),
+    };
+    let (from, to) = period(&query)?;
+    let mut projects = scope(&state, &user_id).await?;
+    if let Some(filter) = query.project_id.as_deref().filter(|f| !f.trim().is_empty()) {
+        let wanted = filter
+            .split(',')
+            .map(|id| parse_uuid(id.trim(), ApiError::InvalidProjectId))
+            .collect::<Result<Vec<_>, _>>()?;
+        if wanted
+            .iter()
+            .any(|id| !projects.iter().any(|p| p.id == id.to_string()))
+        {
+            return Err(ApiError::NoProjectAccess);
+        }
+        projects.retain(|p| wanted.iter().any(|id| p.id == id.to_string()));
+    }
+
+    let report = compute(&state, projects, from, to).await?;
+    if !csv {
+        return Ok(Json(report).into_response());
+    }
+    let body = render_csv(&report).map_err(|_| ApiError::ExportFailed)?;
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"overview-{from}-{to}.csv\""),
+            ),
+        ],
+        body,
+    )
+        .into_response())
+}
@@ -0,0 +1,84 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::error::ApiError;
+
+const DEFAULT_PAGE_LIMIT: i64 = 50;
+const MAX_PAGE_LIMIT: i64 = 200;
+
+pub fn page_limit(limit: Option<i64>) -> i64 {
+    limit.unwrap_or(DEFAULT_PAGE_LIMIT).clamp(1, MAX_PAGE_LIMIT)
+}
+
+/// Position of the last row of a page: `created_at` (RFC 3339, or as rendered by `::text`)
+/// plus the row id as a tie-breaker. Clients only ever see it as an opaque base64 string.
+pub struct Cursor {
+    pub created_at: String,
+    pub id: String,
+}
+
+impl Cursor {
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(format!("{}|{}", self.created_at, self.id))
+    }
+
+    pub fn decode(input: &str) -> Result<Self, ApiError> {
+        let invalid = || ApiError::InvalidCursor;
+        let raw = URL_SAFE_NO_PAD
+            .decode(input.trim())
+            .ok()
+            .and_then(|bytes| String::from_utf8(bytes).ok())
+            .ok_or_else(invalid)?;
+        let (created_at, id) = raw.rsplit_once('|').ok_or_else(invalid)?;
+        if parse_timestamp(created_at).is_none() {
+            return Err(invalid());
+        }
+        let id = Uuid::parse_str(id).map_err(|_| invalid())?;
+        Ok(Self {
+            created_at: created_at.to_string(),
+            id: id.to_string(),
+        })
+    }
+
+    /// `created_at` for queries that bind it as a timestamp; [`Cursor::decode`] has already
+    /// checked that it parses.
+    pub fn timestamp(&self) -> DateTime<Utc> {
+        parse_timestamp(&self.created_at).unwrap_or_default()
+    }
+
+    pub fn uuid(&self) -> Uuid {
+        Uuid::parse_str(&self.id).unwrap_or_default()
+    }
+}
+
+/// RFC 3339, or the `::text` rendering of a `timestamptz`.
+fn parse_timestamp(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .or_else(|_| DateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S%.f%#z"))
+        .ok()
+        .map(|at| at.with_timezone(&Utc))
+}
+
+/// Parses an optional `cursor` query parameter, treating an empty value as the first page.
+pub fn parse_cursor(input: Option<&str>) -> Result<Option<Cursor>, ApiError> {
+    match input {
+        Some(v) if !v.trim().is_empty() => Cursor::decode(v).map(Some),
+        _ => Ok(None),
+    }
+}
+
+/// Lists fetch `limit + 1` rows; the extra row only signals that another page exists.
+pub fn finish_page<T>(
+    mut rows: Vec<T>,
+    limit: i64,
+    key: impl Fn(&T) -> Cursor,
+) -> (Vec<T>, Option<String>) {
+    let limit = limit as usize;
+    if rows.len() <= limit {
+        return (rows, None);
+    }
+    rows.truncate(limit);
+    let next_cursor = rows.last().map(|row| key(row).encode());
+    (rows, next_cursor)
+}
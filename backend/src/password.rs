@@ -0,0 +1,89 @@
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Argon2,
+};
+
+pub fn hash_password(password: &str) -> anyhow::Result<String> {
+    let salt = SaltString::generate(&mut OsRng);
+    let hash = Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map_err(|e| anyhow::anyhow!("failed to hash password: {e}"))?;
+    Ok(hash.to_string())
+}
+
+/// Stored values written before hashing was introduced are plaintext.
+pub fn is_hashed(stored: &str) -> bool {
+    stored.starts_with("$argon2")
+}
+
+pub fn verify_password(password: &str, stored: &str) -> bool {
+    if !is_hashed(stored) {
+        return !stored.is_empty() && stored == password;
+    }
+    match PasswordHash::new(stored) {
+        Ok(parsed) => Argon2::default()
+            .verify_password(password.as_bytes(), &parsed)
+            .is_ok(),
+        Err(_) => false,
+    }
+}
+
+/// Lazy migration after a successful login: a plaintext entry is replaced with its hash.
+/// Returns whether `stored` changed and has to be written back.
+pub fn rehash_if_plaintext(stored: &mut String, password: &str) -> anyhow::Result<bool> {
+    if is_hashed(stored) {
+        return Ok(false);
+    }
+    *stored = hash_password(password)?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hash_is_salted_argon2() {
+        let first = hash_password("s3cret").unwrap();
+        let second = hash_password("s3cret").unwrap();
+        assert!(first.starts_with("$argon2id$"));
+        assert!(is_hashed(&first));
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn verifies_hashed_passwords() {
+        let stored = hash_password("s3cret").unwrap();
+        assert!(verify_password("s3cret", &stored));
+        assert!(!verify_password("S3cret", &stored));
+        assert!(!verify_password("", &stored));
+    }
+
+    #[test]
+    fn verifies_legacy_plaintext() {
+        assert!(!is_hashed("s3cret"));
+        assert!(verify_password("s3cret", "s3cret"));
+        assert!(!verify_password("other", "s3cret"));
+        // An empty stored value (SSO-only accounts) never matches.
+        assert!(!verify_password("", ""));
+    }
+
+    #[test]
+    fn rejects_damaged_hashes() {
+        assert!(is_hashed("$argon2id$garbage"));
+        assert!(!verify_password("$argon2id$garbage", "$argon2id$garbage"));
+    }
+
+    #[test]
+    fn login_rehashes_plaintext_once() {
+        let mut stored = "s3cret".to_string();
+        assert!(rehash_if_plaintext(&mut stored, "s3cret").unwrap());
+        assert!(is_hashed(&stored));
+        assert!(verify_password("s3cret", &stored));
+        assert!(!verify_password("other", &stored));
+
+        let hashed = stored.clone();
+        assert!(!rehash_if_plaintext(&mut stored, "s3cret").unwrap());
+        assert_eq!(stored, hashed);
+    }
+}
@@ -0,0 +1,330 @@
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::{
+    api_keys, authz::AuthUser, error::ApiError, membership_role, now_iso, read_projects,
+    write_projects, AppState, Project,
+};
+
+/// A single action a project role may be allowed to perform.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Capability {
+    ProjectRead,
+    LibraryEdit,
+    RunCreate,
+    RunCompose,
+    ResultEdit,
+    RunStatus,
+    RunLock,
+    ProjectManage,
+}
+
+impl Capability {
+    pub const ALL: [Capability; 8] = [
+        Capability::ProjectRead,
+        Capability::LibraryEdit,
+        Capability::RunCreate,
+        Capability::RunCompose,
+        Capability::ResultEdit,
+        Capability::RunStatus,
+        Capability::RunLock,
+        Capability::ProjectManage,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Capability::ProjectRead => "project.read",
+            Capability::LibraryEdit => "library.edit",
+            Capability::RunCreate => "run.create",
+            Capability::RunCompose => "run.compose",
+            Capability::ResultEdit => "result.edit",
+            Capability::RunStatus => "run.status",
+            Capability::RunLock => "run.lock",
+            Capability::ProjectManage => "project.manage",
+        }
+    }
+
+    pub fn parse(input: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|c| c.as_str() == input)
+    }
+
+    pub fn denied_error(self) -> ApiError {
+        match self {
+            Capability::ProjectRead => ApiError::NoProjectAccess,
+            Capability::LibraryEdit => ApiError::NoLibraryEditRight,
+            Capability::RunCreate => ApiError::NoRunCreateRight,
+            Capability::RunCompose => ApiError::NoRunComposeRight,
+            Capability::ResultEdit => ApiError::NoResultEditRight,
+            Capability::RunStatus => ApiError::NoRunStatusRight,
+            Capability::RunLock => ApiError::NoRunLockRight,
+            Capability::ProjectManage => ApiError::NoProjectManageRight,
+        }
+    }
+}
+
+pub const BUILTIN_ROLES: [&str; 3] = ["owner", "editor", "viewer"];
+
+/// Implicit role of organization admins in the organization's projects; not assignable.
+pub const ORG_ADMIN_ROLE: &str = "org_admin";
+
+fn builtin_capabilities(role: &str) -> Option<Vec<Capability>> {
+    match role {
+        "owner" | ORG_ADMIN_ROLE => Some(Capability::ALL.to_vec()),
+        "editor" => Some(
+            Capability::ALL
+                .into_iter()
+                .filter(|c| !matches!(c, Capability::RunLock | Capability::ProjectManage))
+                .collect(),
+        ),
+        "viewer" => Some(vec![Capability::ProjectRead]),
+        _ => None,
+    }
+}
+
+/// Custom role defined by the project owner, stored alongside the project in `projects.json`.
+#[derive(Serialize, Deserialize, Clone, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectRole {
+    pub name: String,
+    pub capabilities: Vec<String>,
+}
+
+/// Capabilities granted by `role` in the project. Every member may read the project;
+/// unknown roles and capability names grant nothing beyond that.
+pub fn role_capabilities(project: &Project, role: &str) -> Vec<Capability> {
+    if let Some(capabilities) = builtin_capabilities(role) {
+        return capabilities;
+    }
+    let mut capabilities = vec![Capability::ProjectRead];
+    if let Some(custom) = project.roles.iter().find(|r| r.name == role) {
+        for capability in custom
+            .capabilities
+            .iter()
+            .filter_map(|c| Capability::parse(c))
+        {
+            if !capabilities.contains(&capability) {
+                capabilities.push(capability);
+            }
+        }
+    }
+    capabilities
+}
+
+/// Roles the owner may hand out to members: everything except `owner` itself.
+pub fn is_assignable_role(project: &Project, role: &str) -> bool {
+    role == "editor" || role == "viewer" || project.roles.iter().any(|r| r.name == role)
+}
+
+/// Checks that the user's role in an already loaded project grants `capability`.
+/// Used by [`crate::authz`] and by file-based handlers that already hold the projects lock.
+pub fn check(project: &Project, user_id: &str, capability: Capability) -> Result<String, ApiError> {
+    if let Some(grant) = api_keys::current() {
+        grant.check(&project.id, capability)?;
+    }
+    let role = membership_role(project, user_id).ok_or(ApiError::NoProjectAccess)?;
+    if !role_capabilities(project, &role).contains(&capability) {
+        return Err(capability.denied_error());
+    }
+    Ok(role)
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct UpsertRoleRequest {
+    capabilities: Vec<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RoleView {
+    name: String,
+    builtin: bool,
+    capabilities: Vec<&'static str>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ListRolesResponse {
+    roles: Vec<RoleView>,
+    /// Every capability known to the server, for building the role editor.
+    capabilities: Vec<&'static str>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UpsertRoleResponse {
+    role: RoleView,
+    updated_at: String,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteRoleResponse {
+    ok: bool,
+    updated_at: String,
+}
+
+fn role_view(project: &Project, name: &str) -> RoleView {
+    RoleView {
+        name: name.to_string(),
+        builtin: BUILTIN_ROLES.contains(&name),
+        capabilities: role_capabilities(project, name)
+            .into_iter()
+            .map(Capability::as_str)
+            .collect(),
+    }
+}
+
+fn validate_role_name(name: &str) -> Result<(), ApiError> {
+    let valid_chars = name
+        .chars()
+        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-');
+    if !(2..=32).contains(&name.len()) || !valid_chars {
+        return Err(ApiError::InvalidRoleName);
+    }
+    if BUILTIN_ROLES.contains(&name) || name == ORG_ADMIN_ROLE {
+        return Err(ApiError::BuiltinRoleReadOnly);
+    }
+    Ok(())
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/projects/{project_id}/roles",
+    tag = "roles",
+    params(("project_id" = String, Path)),
+    responses((status = 200, body = ListRolesResponse))
+)]
+pub async fn list_roles(
+    State(state): State<AppState>,
+    Path(project_id): Path<String>,
+    AuthUser(user_id): AuthUser,
+) -> Result<Json<ListRolesResponse>, ApiError> {
+    let _guard = state.file_lock.lock().await;
+    let projects = read_projects(&state.projects_file)
+        .await
+        .map_err(|_| ApiError::RolesLoadFailed)?;
+    let project = projects
+        .iter()
+        .find(|p| p.id == project_id)
+        .ok_or(ApiError::ProjectNotFound)?;
+    check(project, &user_id, Capability::ProjectRead)?;
+
+    let roles = BUILTIN_ROLES
+        .iter()
+        .copied()
+        .chain(project.roles.iter().map(|r| r.name.as_str()))
+        .map(|name| role_view(project, name))
+        .collect();
+    Ok(Json(ListRolesResponse {
+        roles,
+        capabilities: Capability::ALL
+            .into_iter()
+            .map(Capability::as_str)
+            .collect(),
+    }))
+}
+
+/// Creates or replaces a custom role. `project.read` is always implied.
+#[utoipa::path(
+    put,
+    path = "/api/projects/{project_id}/roles/{role_name}",
+    tag = "roles",
+    params(("project_id" = String, Path), ("role_name" = String, Path)),
+    request_body = UpsertRoleRequest,
+    responses((status = 200, body = UpsertRoleResponse))
+)]
+pub async fn upsert_role(
+    State(state): State<AppState>,
+    Path((project_id, role_name)): Path<(String, String)>,
+    AuthUser(actor_id): AuthUser,
+    Json(payload): Json<UpsertRoleRequest>,
+) -> Result<Json<UpsertRoleResponse>, ApiError> {
+    let role_name = role_name.trim().to_lowercase();
+    validate_role_name(&role_name)?;
+    let mut capabilities = vec![Capability::ProjectRead.as_str().to_string()];
+    for raw in payload.capabilities.iter().map(|c| c.trim()) {
+        let capability = Capability::parse(raw).ok_or(ApiError::UnknownCapability)?;
+        if !capabilities.iter().any(|c| c == capability.as_str()) {
+            capabilities.push(capability.as_str().to_string());
+        }
+    }
+
+    let _guard = state.file_lock.lock().await;
+    let mut projects = read_projects(&state.projects_file)
+        .await
+        .map_err(|_| ApiError::RoleSaveFailed)?;
+    let project = projects
+        .iter_mut()
+        .find(|p| p.id == project_id)
+        .ok_or(ApiError::ProjectNotFound)?;
+    check(project, &actor_id, Capability::ProjectManage)?;
+
+    match project.roles.iter_mut().find(|r| r.name == role_name) {
+        Some(existing) => existing.capabilities = capabilities,
+        None => project.roles.push(ProjectRole {
+            name: role_name.clone(),
+            capabilities,
+        }),
+    }
+    project.updated_at = now_iso();
+    let role = role_view(project, &role_name);
+    let updated_at = project.updated_at.clone();
+    write_projects(&state, &projects)
+        .await
+        .map_err(|_| ApiError::RoleSaveFailed)?;
+
+    Ok(Json(UpsertRoleResponse { role, updated_at }))
+}
+
+/// Deletes a custom role; refused while any member or pending invitation still holds it.
+#[utoipa::path(
+    delete,
+    path = "/api/projects/{project_id}/roles/{role_name}",
+    tag = "roles",
+    params(("project_id" = String, Path), ("role_name" = String, Path)),
+    responses((status = 200, body = DeleteRoleResponse))
+)]
+pub async fn delete_role(
+    State(state): State<AppState>,
+    Path((project_id, role_name)): Path<(String, String)>,
+    AuthUser(actor_id): AuthUser,
+) -> Result<Json<DeleteRoleResponse>, ApiError> {
+    let role_name = role_name.trim().to_lowercase();
+
+    let _guard = state.file_lock.lock().await;
+    let mut projects = read_projects(&state.projects_file)
+        .await
+        .map_err(|_| ApiError::RoleDeleteFailed)?;
+    let project = projects
+        .iter_mut()
+        .find(|p| p.id == project_id)
+        .ok_or(ApiError::ProjectNotFound)?;
+    check(project, &actor_id, Capability::ProjectManage)?;
+
+    if BUILTIN_ROLES.contains(&role_name.as_str()) {
+        return Err(ApiError::BuiltinRoleReadOnly);
+    }
+    if !project.roles.iter().any(|r| r.name == role_name) {
+        return Err(ApiError::RoleNotFound);
+    }
+    let in_use = project.members.iter().any(|m| m.role == role_name)
+        || project.invitations.iter().any(|i| i.role == role_name);
+    if in_use {
+        return Err(ApiError::RoleInUse);
+    }
+    project.roles.retain(|r| r.name != role_name);
+    project.updated_at = now_iso();
+    let updated_at = project.updated_at.clone();
+    write_projects(&state, &projects)
+        .await
+        .map_err(|_| ApiError::RoleDeleteFailed)?;
+
+    Ok(Json(DeleteRoleResponse {
+        ok: true,
+        updated_at,
+    }))
+}
@@ -0,0 +1,289 @@
+use std::collections::HashSet;
+
+use axum::body::Bytes;
+use base64::Engine;
+use serde::Deserialize;
+
+use crate::{
+    attachments::AttachmentLimits,
+    error::ApiError,
+    result_import::{
+        case_title, clip, embedded_attachment, ImportedCase, ImportedStep, ParsedReport,
+        ReportParser, ReportSource, MAX_COMMENT_CHARS,
+    },
+};
+
+const MAX_STEP_CHARS: usize = 1000;
+/// Playwright's own separator of title paths.
+const TITLE_SEPARATOR: &str = " › ";
+
+static SOURCE: ReportSource = ReportSource {
+    format: "playwright",
+    name: "Playwright",
+    // Screenshots and traces may be embedded in the report.
+    max_bytes: 50 * 1024 * 1024,
+    failed: ApiError::ReportImportFailed,
+    rejected: ApiError::ReportRunCreateRejected,
+};
+
+#[derive(Deserialize)]
+struct PlaywrightReport {
+    #[serde(default)]
+    suites: Vec<Suite>,
+}
+
+/// A file at the top level, a `describe` block below it.
+#[derive(Deserialize)]
+struct Suite {
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    specs: Vec<Spec>,
+    #[serde(default)]
+    suites: Vec<Suite>,
+}
+
+#[derive(Deserialize)]
+struct Spec {
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    tests: Vec<Test>,
+}
+
+/// A spec run in one project (browser).
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Test {
+    project_name: Option<String>,
+    status: Option<String>,
+    #[serde(default)]
+    results: Vec<TestResult>,
+}
+
+/// One attempt; retries add more.
+#[derive(Deserialize)]
+struct TestResult {
+    status: Option<String>,
+    duration: Option<f64>,
+    #[serde(default)]
+    errors: Vec<TestError>,
+    error: Option<TestError>,
+    #[serde(default)]
+    steps: Vec<TestStep>,
+    #[serde(default)]
+    attachments: Vec<Attachment>,
+}
+
+#[derive(Deserialize)]
+struct TestError {
+    message: Option<String>,
+    stack: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TestStep {
+    #[serde(default)]
+    title: String,
+    error: Option<TestError>,
+    #[serde(default)]
+    steps: Vec<TestStep>,
+}
+
+/// `body` is base64; attachments saved next to the report only have a `path`, which the
+/// server cannot read.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Attachment {
+    #[serde(default)]
+    name: String,
+    content_type: Option<String>,
+    body: Option<String>,
+}
+
+/// Removes the terminal colours Playwright puts into error messages.
+fn strip_ansi(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\u{1b}' {
+            out.push(c);
+            continue;
+        }
+        if chars.next() == Some('[') {
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        }
+    }
+    out
+}
+
+fn error_text(error: &TestError) -> String {
+    // The stack repeats the message in front of the call frames.
+    let text = match (&error.stack, &error.message) {
+        (Some(stack), _) if !stack.trim().is_empty() => stack,
+        (_, Some(message)) => message,
+        _ => return String::new(),
+    };
+    strip_ansi(text.trim())
+}
+
+/// Verdict of the test: the attempt that counts is the last one.
+fn map_status(test: &Test, last: Option<&TestResult>) -> &'static str {
+    match test.status.as_deref() {
+        Some("skipped") => return "skipped",
+        Some("unexpected") => return "fail",
+        Some("expected" | "flaky") => return "ok",
+        _ => {}
+    }
+    match last.and_then(|r| r.status.as_deref()) {
+        Some("passed") => "ok",
+        Some("failed" | "timedOut" | "interrupted") => "fail",
+        Some("skipped") => "skipped",
+        _ => "na",
+    }
+}
+
+fn add_steps(steps: &[TestStep], prefix: &str, out: &mut Vec<ImportedStep>) {
+    for step in steps {
+        let title = step.title.trim();
+        let action = match (prefix.is_empty(), title.is_empty()) {
+            (_, true) => prefix.to_string(),
+            (true, false) => title.to_string(),
+            (false, false) => format!("{prefix}{TITLE_SEPARATOR}{title}"),
+        };
+        if !title.is_empty() {
+            out.push(ImportedStep {
+                action: clip(&action, MAX_STEP_CHARS),
+                expected: String::new(),
+                status: if step.error.is_some() { "fail" } else { "ok" },
+                comment: step
+                    .error
+                    .as_ref()
+                    .map(|e| clip(&error_text(e), MAX_STEP_CHARS))
+                    .unwrap_or_default(),
+            });
+        }
+        add_steps(&step.steps, &action, out);
+    }
+}
+
+struct Walk<'a> {
+    limits: &'a AttachmentLimits,
+    cases: Vec<ImportedCase>,
+    keys: HashSet<String>,
+    skipped_attachments: usize,
+}
+
+impl Walk<'_> {
+    fn suite(&mut self, suite: &Suite, path: &[&str]) {
+        let mut path = path.to_vec();
+        if !suite.title.trim().is_empty() {
+            path.push(suite.title.trim());
+        }
+        for spec in &suite.specs {
+            for test in &spec.tests {
+                self.test(spec, test, &path);
+            }
+        }
+        for child in &suite.suites {
+            self.suite(child, &path);
+        }
+    }
+
+    fn test(&mut self, spec: &Spec, test: &Test, path: &[&str]) {
+        let title = spec.title.trim();
+        if title.is_empty() {
+            return;
+        }
+        let mut title_path = path.to_vec();
+        title_path.push(title);
+        let title_path = title_path.join(TITLE_SEPARATOR);
+        let key = match test.project_name.as_deref().map(str::trim) {
+            Some(project) if !project.is_empty() => {
+                format!("[{project}]{TITLE_SEPARATOR}{title_path}")
+            }
+            _ => title_path,
+        };
+        if !self.keys.insert(key.clone()) {
+            return;
+        }
+
+        let last = test.results.last();
+        let mut steps = Vec::new();
+        let mut attachments = Vec::new();
+        let mut comment = String::new();
+        if let Some(result) = last {
+            let errors: Vec<String> = if result.errors.is_empty() {
+                result.error.iter().map(error_text).collect()
+            } else {
+                result.errors.iter().map(error_text).collect()
+            };
+            comment = clip(&errors.join("\n\n"), MAX_COMMENT_CHARS);
+            add_steps(&result.steps, "", &mut steps);
+            for attachment in &result.attachments {
+                let data = attachment.body.as_deref().and_then(|body| {
+                    base64::engine::general_purpose::STANDARD
+                        .decode(body.trim())
+                        .ok()
+                });
+                let mime_type = attachment.content_type.as_deref().unwrap_or_default();
+                match data.and_then(|data| {
+                    embedded_attachment(self.limits, &attachment.name, mime_type, data)
+                }) {
+                    Some(attachment) => attachments.push(attachment),
+                    None => self.skipped_attachments += 1,
+                }
+            }
+        }
+        let elapsed_seconds = last
+            .and_then(|r| r.duration)
+            .filter(|d| d.is_finite() && *d >= 0.0)
+            .map(|d| (d / 1000.0).round().min(i32::MAX as f64) as i32);
+        self.cases.push(ImportedCase {
+            title: case_title(title, &key),
+            key,
+            source_path: None,
+            status: map_status(test, last),
+            comment,
+            elapsed_seconds,
+            steps,
+            attachments,
+        });
+    }
+}
+
+/// Output of Playwright's JSON reporter. A test is keyed by its project and title path
+/// (`[chromium] › login.spec.ts › Login › signs in`); the last attempt gives its steps,
+/// errors and embedded attachments.
+pub struct PlaywrightParser;
+
+impl ReportParser for PlaywrightParser {
+    fn source(&self) -> &'static ReportSource {
+        &SOURCE
+    }
+
+    fn parse(&self, body: Bytes, limits: &AttachmentLimits) -> Result<ParsedReport, ApiError> {
+        let report: PlaywrightReport =
+            serde_json::from_slice(&body).map_err(|_| ApiError::InvalidPlaywrightReport)?;
+        let mut walk = Walk {
+            limits,
+            cases: Vec::new(),
+            keys: HashSet::new(),
+            skipped_attachments: 0,
+        };
+        for suite in &report.suites {
+            walk.suite(suite, &[]);
+        }
+        if walk.cases.is_empty() {
+            return Err(ApiError::PlaywrightReportEmpty);
+        }
+        Ok(ParsedReport {
+            cases: walk.cases,
+            skipped_attachments: walk.skipped_attachments,
+        })
+    }
+}
@@ -0,0 +1,625 @@
+use axum::{
+    body::Bytes,
+    extract::{Multipart, Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::{
+    attachments::AttachmentUpload, authz::AuthUser, error::ApiError, invitations, map_safe_user,
+    notifications, now_iso, password, read_projects, read_users, write_projects, write_users,
+    AppState, MeResponse, User,
+};
+
+pub const AVATAR_MAX_BYTES: usize = 2 * 1024 * 1024;
+const AVATAR_TYPES: [&str; 4] = ["image/png", "image/jpeg", "image/webp", "image/gif"];
+const EMAIL_CHANGE_TTL_HOURS: i64 = 24;
+const EMAIL_VERIFICATION_TTL_HOURS: i64 = 72;
+
+/// Email change waiting for confirmation from the new address, stored in `users.json`.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingEmailChange {
+    pub email: String,
+    pub token_hash: String,
+    pub expires_at: String,
+}
+
+impl PendingEmailChange {
+    pub fn is_expired(&self) -> bool {
+        chrono::DateTime::parse_from_rfc3339(&self.expires_at)
+            .map(|t| t <= chrono::Utc::now())
+            .unwrap_or(true)
+    }
+}
+
+/// Verification of the address given at registration, stored in `users.json`.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct EmailVerification {
+    pub token_hash: String,
+    pub expires_at: String,
+}
+
+impl EmailVerification {
+    /// A fresh verification and the token for its link.
+    pub fn issue() -> (Self, String) {
+        let token = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+        let verification = Self {
+            token_hash: hash_token(&token),
+            expires_at: (chrono::Utc::now()
+                + chrono::Duration::hours(EMAIL_VERIFICATION_TTL_HOURS))
+            .to_rfc3339(),
+        };
+        (verification, token)
+    }
+
+    pub fn is_expired(&self) -> bool {
+        chrono::DateTime::parse_from_rfc3339(&self.expires_at)
+            .map(|t| t <= chrono::Utc::now())
+            .unwrap_or(true)
+    }
+}
+
+pub fn send_verification_email(state: &AppState, user: &User, token: &str) {
+    let link = format!(
+        "{}/api/auth/verify?token={token}",
+        state.public_url.trim_end_matches('/')
+    );
+    notifications::send_transactional(
+        state,
+        notifications::OutgoingEmail {
+            to: user.email.clone(),
+            subject: "Uran: подтвердите email".to_string(),
+            body: format!(
+                "{}, чтобы подтвердить адрес учётной записи Uran, откройте ссылку \
+                 (действует {EMAIL_VERIFICATION_TTL_HOURS} ч):\n{link}\n\n\
+                 Если вы не регистрировались в Uran, просто проигнорируйте это письмо.",
+                user.name
+            ),
+            reply_to: None,
+            attachment: None,
+        },
+    );
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Avatar {
+    pub storage_key: String,
+    pub mime_type: String,
+    pub updated_at: String,
+}
+
+/// Cache-busting URL of the user's avatar, `None` when it is not set.
+pub fn avatar_url(user_id: &str, avatar: Option<&Avatar>) -> Option<String> {
+    avatar.map(|a| {
+        let version = chrono::DateTime::parse_from_rfc3339(&a.updated_at)
+            .map(|t| t.timestamp())
+            .unwrap_or_default();
+        format!("/api/users/{user_id}/avatar?v={version}")
+    })
+}
+
+pub(crate) fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateProfileRequest {
+    name: Option<String>,
+    /// The new address is applied only after the link sent to it is opened.
+    email: Option<String>,
+    /// Required when `email` changes.
+    current_password: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangePasswordRequest {
+    current_password: String,
+    new_password: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ResetPasswordRequest {
+    /// From the link emailed when an admin forced the reset.
+    token: String,
+    new_password: String,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ConfirmEmailQuery {
+    token: String,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct VerifyEmailQuery {
+    token: String,
+}
+
+#[utoipa::path(
+    patch,
+    path = "/api/auth/me",
+    tag = "auth",
+    request_body = UpdateProfileRequest,
+    responses((status = 200, body = MeResponse))
+)]
+pub async fn update_profile(
+    State(state): State<AppState>,
+    AuthUser(user_id): AuthUser,
+    Json(payload): Json<UpdateProfileRequest>,
+) -> Result<Json<MeResponse>, ApiError> {
+    let name = payload.name.map(|n| n.trim().to_string());
+    let email = payload.email.map(|e| e.trim().to_lowercase());
+    if name.is_none() && email.is_none() {
+        return Err(ApiError::ProfileUpdateEmpty);
+    }
+    if name.as_ref().is_some_and(|n| n.chars().count() < 2) {
+        return Err(ApiError::NameTooShort);
+    }
+    if email.as_ref().is_some_and(|e| !e.contains('@')) {
+        return Err(ApiError::InvalidEmail);
+    }
+
+    let _guard = state.file_lock.lock().await;
+    let mut users = read_users(&state.users_file)
+        .await
+        .map_err(|_| ApiError::ProfileUpdateFailed)?;
+    let email_taken = |email: &str| users.iter().any(|u| u.id != user_id && u.email == email);
+    let mut confirmation = None;
+    if let Some(email) = &email {
+        let user = users
+            .iter()
+            .find(|u| u.id == user_id)
+            .ok_or(ApiError::UserNotFound)?;
+        if *email != user.email {
+            let confirmed = payload
+                .current_password
+                .as_deref()
+                .is_some_and(|p| password::verify_password(p, &user.password_hash));
+            if !confirmed {
+                return Err(ApiError::InvalidCurrentPassword);
+            }
+            if email_taken(email) {
+                return Err(ApiError::EmailTaken);
+            }
+            confirmation = Some(format!(
+                "{}{}",
+                Uuid::new_v4().simple(),
+                Uuid::new_v4().simple()
+            ));
+        }
+    }
+
+    let user = users
+        .iter_mut()
+        .find(|u| u.id == user_id)
+        .ok_or(ApiError::UserNotFound)?;
+    if let Some(name) = name {
+        user.name = name;
+    }
+    if email.is_some() {
+        user.pending_email = match (&email, &confirmation) {
+            (Some(email), Some(token)) => Some(PendingEmailChange {
+                email: email.clone(),
+                token_hash: hash_token(token),
+                expires_at: (chrono::Utc::now() + chrono::Duration::hours(EMAIL_CHANGE_TTL_HOURS))
+                    .to_rfc3339(),
+            }),
+            // Setting the current address again cancels a pending change.
+            _ => None,
+        };
+    }
+    let user = user.clone();
+    write_users(&state.users_file, &users)
+        .await
+        .map_err(|_| ApiError::ProfileUpdateFailed)?;
+
+    if let (Some(email), Some(token)) = (email, confirmation) {
+        let link = format!(
+            "{}/api/auth/me/email/confirm?token={token}",
+            state.public_url.trim_end_matches('/')
+        );
+        notifications::send_transactional(
+            &state,
+            notifications::OutgoingEmail {
+                to: email,
+                subject: "Uran: подтвердите новый email".to_string(),
+                body: format!(
+                    "{}, чтобы сменить email учётной записи Uran на этот адрес, откройте ссылку \
+                     (действует {EMAIL_CHANGE_TTL_HOURS} ч):\n{link}\n\n\
+                     Если вы не запрашивали смену email, просто проигнорируйте это письмо.",
+                    user.name
+                ),
+                reply_to: None,
+                attachment: None,
+            },
+        );
+    }
+
+    Ok(Json(MeResponse {
+        user: map_safe_user(&user),
+    }))
+}
+
+/// Opened from the confirmation email, so it works without a bearer token.
+#[utoipa::path(
+    get,
+    path = "/api/auth/me/email/confirm",
+    tag = "auth",
+    params(ConfirmEmailQuery),
+    responses((status = 200, body = MeResponse)),
+    security(())
+)]
+pub async fn confirm_email(
+    State(state): State<AppState>,
+    Query(query): Query<ConfirmEmailQuery>,
+) -> Result<Json<MeResponse>, ApiError> {
+    let token_hash = hash_token(query.token.trim());
+
+    let _guard = state.file_lock.lock().await;
+    let mut users = read_users(&state.users_file)
+        .await
+        .map_err(|_| ApiError::ProfileUpdateFailed)?;
+    let index = users
+        .iter()
+        .position(|u| {
+            u.pending_email
+                .as_ref()
+                .is_some_and(|p| p.token_hash == token_hash && !p.is_expired())
+        })
+        .ok_or(ApiError::EmailChangeLinkInvalid)?;
+    let new_email = users[index]
+        .pending_email
+        .take()
+        .map(|p| p.email)
+        .unwrap_or_default();
+    let user_id = users[index].id.clone();
+    if users
+        .iter()
+        .any(|u| u.id != user_id && u.email == new_email)
+    {
+        return Err(ApiError::EmailTaken);
+    }
+    users[index].email = new_email;
+    // The link reached the new address, which proves it as well as verification does.
+    users[index].email_verified = true;
+    users[index].email_verification = None;
+    let user = users[index].clone();
+    write_users(&state.users_file, &users)
+        .await
+        .map_err(|_| ApiError::ProfileUpdateFailed)?;
+
+    Ok(Json(MeResponse {
+        user: map_safe_user(&user),
+    }))
+}
+
+/// Opened from the email sent at registration, so it works without a bearer token. With
+/// `INVITES_REQUIRE_VERIFIED_EMAIL`, pending project invitations are accepted here.
+#[utoipa::path(
+    get,
+    path = "/api/auth/verify",
+    tag = "auth",
+    params(VerifyEmailQuery),
+    responses((status = 200, body = MeResponse)),
+    security(())
+)]
+pub async fn verify_email(
+    State(state): State<AppState>,
+    Query(query): Query<VerifyEmailQuery>,
+) -> Result<Json<MeResponse>, ApiError> {
+    let token_hash = hash_token(query.token.trim());
+
+    let (user, attached) = {
+        let _guard = state.file_lock.lock().await;
+        let mut users = read_users(&state.users_file)
+            .await
+            .map_err(|_| ApiError::ProfileUpdateFailed)?;
+        let user = users
+            .iter_mut()
+            .find(|u| {
+                u.email_verification
+                    .as_ref()
+                    .is_some_and(|v| v.token_hash == token_hash && !v.is_expired())
+            })
+            .ok_or(ApiError::EmailVerificationLinkInvalid)?;
+        user.email_verified = true;
+        user.email_verification = None;
+        let user = user.clone();
+        write_users(&state.users_file, &users)
+            .await
+            .map_err(|_| ApiError::ProfileUpdateFailed)?;
+
+        let mut attached = Vec::new();
+        if state.invites_require_verified_email {
+            let mut projects = read_projects(&state.projects_file)
+                .await
+                .map_err(|_| ApiError::ProfileUpdateFailed)?;
+            attached = invitations::attach_pending_invitations(&mut projects, &user);
+            if !attached.is_empty() {
+                write_projects(&state, &projects)
+                    .await
+                    .map_err(|_| ApiError::ProfileUpdateFailed)?;
+            }
+        }
+        (user, attached)
+    };
+    invitations::emit_attached(&state, &user, &attached).await;
+
+    Ok(Json(MeResponse {
+        user: map_safe_user(&user),
+    }))
+}
+
+/// Sends a new verification link; the previous one stops working.
+#[utoipa::path(
+    post,
+    path = "/api/auth/me/verify",
+    tag = "auth",
+    responses((status = 204, description = "Письмо отправлено."))
+)]
+pub async fn resend_verification(
+    State(state): State<AppState>,
+    AuthUser(user_id): AuthUser,
+) -> Result<StatusCode, ApiError> {
+    let (verification, token) = EmailVerification::issue();
+
+    let _guard = state.file_lock.lock().await;
+    let mut users = read_users(&state.users_file)
+        .await
+        .map_err(|_| ApiError::ProfileUpdateFailed)?;
+    let user = users
+        .iter_mut()
+        .find(|u| u.id == user_id)
+        .ok_or(ApiError::UserNotFound)?;
+    if user.email_verified {
+        return Err(ApiError::EmailAlreadyVerified);
+    }
+    user.email_verification = Some(verification);
+    let user = user.clone();
+    write_users(&state.users_file, &users)
+        .await
+        .map_err(|_| ApiError::ProfileUpdateFailed)?;
+    send_verification_email(&state, &user, &token);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/auth/me/password",
+    tag = "auth",
+    request_body = ChangePasswordRequest,
+    responses((status = 204, description = "Пароль изменён."))
+)]
+pub async fn change_password(
+    State(state): State<AppState>,
+    AuthUser(user_id): AuthUser,
+    Json(payload): Json<ChangePasswordRequest>,
+) -> Result<StatusCode, ApiError> {
+    if payload.new_password.chars().count() < 8 {
+        return Err(ApiError::PasswordTooShort);
+    }
+
+    let _guard = state.file_lock.lock().await;
+    let mut users = read_users(&state.users_file)
+        .await
+        .map_err(|_| ApiError::PasswordChangeFailed)?;
+    let user = users
+        .iter_mut()
+        .find(|u| u.id == user_id)
+        .ok_or(ApiError::UserNotFound)?;
+    if !password::verify_password(&payload.current_password, &user.password_hash) {
+        return Err(ApiError::InvalidCurrentPassword);
+    }
+    user.password_hash = password::hash_password(&payload.new_password)
+        .map_err(|_| ApiError::PasswordChangeFailed)?;
+    write_users(&state.users_file, &users)
+        .await
+        .map_err(|_| ApiError::PasswordChangeFailed)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Completes a reset forced by an admin; opened from the emailed link, so it works without a
+/// bearer token. Afterwards the user signs in with the new password.
+#[utoipa::path(
+    post,
+    path = "/api/auth/password-reset",
+    tag = "auth",
+    request_body = ResetPasswordRequest,
+    responses((status = 204, description = "Пароль задан.")),
+    security(())
+)]
+pub async fn reset_password(
+    State(state): State<AppState>,
+    Json(payload): Json<ResetPasswordRequest>,
+) -> Result<StatusCode, ApiError> {
+    if payload.new_password.chars().count() < 8 {
+        return Err(ApiError::PasswordTooShort);
+    }
+    let token_hash = hash_token(payload.token.trim());
+
+    let _guard = state.file_lock.lock().await;
+    let mut users = read_users(&state.users_file)
+        .await
+        .map_err(|_| ApiError::PasswordChangeFailed)?;
+    let user = users
+        .iter_mut()
+        .find(|u| {
+            u.password_reset
+                .as_ref()
+                .is_some_and(|r| r.token_hash == token_hash && !r.is_expired())
+        })
+        .ok_or(ApiError::PasswordResetLinkInvalid)?;
+    user.password_hash = password::hash_password(&payload.new_password)
+        .map_err(|_| ApiError::PasswordChangeFailed)?;
+    user.password_reset = None;
+    write_users(&state.users_file, &users)
+        .await
+        .map_err(|_| ApiError::PasswordChangeFailed)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Replaces the avatar; `file` must be a PNG, JPEG, WebP or GIF image up to 2 MiB.
+#[utoipa::path(
+    put,
+    path = "/api/auth/me/avatar",
+    tag = "auth",
+    request_body(content = AttachmentUpload, content_type = "multipart/form-data"),
+    responses((status = 200, body = MeResponse))
+)]
+pub async fn upload_avatar(
+    State(state): State<AppState>,
+    AuthUser(user_id): AuthUser,
+    mut multipart: Multipart,
+) -> Result<Json<MeResponse>, ApiError> {
+    let mut upload: Option<(String, Bytes)> = None;
+    while let Some(mut field) = multipart
+        .next_field()
+        .await
+        .map_err(|_| ApiError::InvalidMultipart)?
+    {
+        if field.name() != Some("file") {
+            continue;
+        }
+        let mime_type = field
+            .content_type()
+            .unwrap_or_default()
+            .trim()
+            .to_lowercase();
+        if !AVATAR_TYPES.contains(&mime_type.as_str()) {
+            return Err(ApiError::AvatarTypeNotAllowed);
+        }
+        let mut data: Vec<u8> = Vec::new();
+        while let Some(chunk) = field
+            .chunk()
+            .await
+            .map_err(|_| ApiError::InvalidMultipart)?
+        {
+            if data.len() + chunk.len() > AVATAR_MAX_BYTES {
+                return Err(ApiError::AvatarTooLarge);
+            }
+            data.extend_from_slice(&chunk);
+        }
+        upload = Some((mime_type, Bytes::from(data)));
+        break;
+    }
+    let (mime_type, data) = upload.ok_or(ApiError::AttachmentFileRequired)?;
+    if data.is_empty() {
+        return Err(ApiError::AvatarEmpty);
+    }
+
+    let storage_key = format!("avatars/{user_id}/{}", Uuid::new_v4());
+    state
+        .storage
+        .put(&storage_key, data)
+        .await
+        .map_err(|_| ApiError::AvatarSaveFailed)?;
+
+    let (user, previous) = {
+        let _guard = state.file_lock.lock().await;
+        let mut users = read_users(&state.users_file)
+            .await
+            .map_err(|_| ApiError::AvatarSaveFailed)?;
+        let user = users
+            .iter_mut()
+            .find(|u| u.id == user_id)
+            .ok_or(ApiError::UserNotFound)?;
+        let previous = user.avatar.replace(Avatar {
+            storage_key: storage_key.clone(),
+            mime_type,
+            updated_at: now_iso(),
+        });
+        let user = user.clone();
+        if write_users(&state.users_file, &users).await.is_err() {
+            let _ = state.storage.delete(&storage_key).await;
+            return Err(ApiError::AvatarSaveFailed);
+        }
+        (user, previous)
+    };
+    if let Some(previous) = previous {
+        let _ = state.storage.delete(&previous.storage_key).await;
+    }
+
+    Ok(Json(MeResponse {
+        user: map_safe_user(&user),
+    }))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/auth/me/avatar",
+    tag = "auth",
+    responses((status = 204, description = "Аватар удалён."))
+)]
+pub async fn delete_avatar(
+    State(state): State<AppState>,
+    AuthUser(user_id): AuthUser,
+) -> Result<StatusCode, ApiError> {
+    let previous = {
+        let _guard = state.file_lock.lock().await;
+        let mut users = read_users(&state.users_file)
+            .await
+            .map_err(|_| ApiError::AvatarSaveFailed)?;
+        let user = users
+            .iter_mut()
+            .find(|u| u.id == user_id)
+            .ok_or(ApiError::UserNotFound)?;
+        let previous = user.avatar.take().ok_or(ApiError::AvatarNotFound)?;
+        write_users(&state.users_file, &users)
+            .await
+            .map_err(|_| ApiError::AvatarSaveFailed)?;
+        previous
+    };
+    let _ = state.storage.delete(&previous.storage_key).await;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Any signed-in user can see avatars, they are shown next to members and assignees.
+#[utoipa::path(
+    get,
+    path = "/api/users/{user_id}/avatar",
+    tag = "auth",
+    params(("user_id" = String, Path)),
+    responses((status = 200, description = "Изображение аватара.", content_type = "image/*"))
+)]
+pub async fn get_avatar(
+    State(state): State<AppState>,
+    Path(user_id): Path<String>,
+    _user: AuthUser,
+) -> Result<Response, ApiError> {
+    let avatar = {
+        let _guard = state.file_lock.lock().await;
+        let users = read_users(&state.users_file)
+            .await
+            .map_err(|_| ApiError::ProfileLoadFailed)?;
+        users
+            .into_iter()
+            .find(|u| u.id == user_id)
+            .and_then(|u| u.avatar)
+            .ok_or(ApiError::AvatarNotFound)?
+    };
+    let data = state
+        .storage
+        .get(&avatar.storage_key)
+        .await
+        .map_err(|_| ApiError::AvatarNotFound)?;
+    Ok((
+        [
+            (header::CONTENT_TYPE, avatar.mime_type),
+            (header::CACHE_CONTROL, "private, max-age=86400".to_string()),
+        ],
+        data,
+    )
+        .into_response())
+}
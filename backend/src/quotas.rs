@@ -0,0 +1,267 @@
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::{PgConnection, PgExecutor};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{
+    audit,
+    authz::{AdminUser, ProjectRole},
+    ensure_db_user_exists,
+    error::ApiError,
+    parse_uuid, AppState,
+};
+
+/// Attachment bytes of a project against its effective quota.
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectUsage {
+    project_id: String,
+    used_bytes: i64,
+    attachment_count: i64,
+    /// Effective quota; `null` means unlimited.
+    quota_bytes: Option<i64>,
+    /// `true` when an administrator set the quota for this project, `false` when the
+    /// `PROJECT_STORAGE_QUOTA_BYTES` default applies.
+    quota_overridden: bool,
+    /// `null` when unlimited; never negative.
+    remaining_bytes: Option<i64>,
+}
+
+impl ProjectUsage {
+    fn admits(&self, size_bytes: i64) -> bool {
+        self.quota_bytes
+            .is_none_or(|quota| self.used_bytes + size_bytes <= quota)
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SetStorageQuotaRequest {
+    /// `null` makes the project unlimited.
+    quota_bytes: Option<i64>,
+}
+
+async fn load_usage<'e, E: PgExecutor<'e>>(
+    executor: E,
+    project_id: Uuid,
+    default_quota: Option<i64>,
+) -> Result<ProjectUsage, ApiError> {
+    let row = sqlx::query!(
+        r#"
+        SELECT
+          COALESCE(SUM(a.size_bytes), 0)::bigint AS "used_bytes!",
+          COUNT(a.id) AS "attachment_count!",
+          q.project_id IS NOT NULL AS "overridden!",
+          q.quota_bytes AS "quota_bytes?"
+        FROM (SELECT $1::uuid AS project_id) p
+        LEFT JOIN project_storage_quotas q ON q.project_id = p.project_id
+        LEFT JOIN runs r ON r.project_id = p.project_id
+        LEFT JOIN attachments a ON a.run_id = r.id
+        GROUP BY q.project_id, q.quota_bytes
+        "#,
+        project_id,
+    )
+    .fetch_one(executor)
+    .await
+    .map_err(|_| ApiError::StorageUsageReadFailed)?;
+    let used_bytes = row.used_bytes;
+    let quota_overridden = row.overridden;
+    let quota_bytes = if quota_overridden {
+        row.quota_bytes
+    } else {
+        default_quota
+    };
+    Ok(ProjectUsage {
+        project_id: project_id.to_string(),
+        used_bytes,
+        attachment_count: row.attachment_count,
+        quota_bytes,
+        quota_overridden,
+        remaining_bytes: quota_bytes.map(|quota| (quota - used_bytes).max(0)),
+    })
+}
+
+/// Quick check before an upload is stored; [`reserve`] repeats it atomically.
+pub async fn check(state: &AppState, project_id: Uuid, size_bytes: i64) -> Result<(), ApiError> {
+    let usage = load_usage(
+        &state.db,
+        project_id,
+        state.attachment_limits.project_quota_bytes,
+    )
+    .await?;
+    if !usage.admits(size_bytes) {
+        return Err(ApiError::StorageQuotaExceeded);
+    }
+    Ok(())
+}
+
+/// Checks the quota inside the transaction that records the new bytes. The project row
+/// stays locked until it ends, so concurrent uploads to one project cannot both pass.
+pub async fn reserve(
+    conn: &mut PgConnection,
+    state: &AppState,
+    project_id: Uuid,
+    size_bytes: i64,
+) -> Result<(), ApiError> {
+    sqlx::query!(
+        r#"SELECT 1 AS locked FROM projects WHERE id = $1 FOR NO KEY UPDATE"#,
+        project_id,
+    )
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(|_| ApiError::StorageUsageReadFailed)?;
+    let usage = load_usage(
+        conn,
+        project_id,
+        state.attachment_limits.project_quota_bytes,
+    )
+    .await?;
+    if !usage.admits(size_bytes) {
+        return Err(ApiError::StorageQuotaExceeded);
+    }
+    Ok(())
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v2/projects/{project_id}/usage",
+    tag = "attachments",
+    params(("project_id" = String, Path)),
+    responses((status = 200, body = ProjectUsage))
+)]
+pub async fn project_usage(
+    State(state): State<AppState>,
+    access: ProjectRole,
+) -> Result<Json<ProjectUsage>, ApiError> {
+    let usage = load_usage(
+        &state.db,
+        access.project_id,
+        state.attachment_limits.project_quota_bytes,
+    )
+    .await?;
+    Ok(Json(usage))
+}
+
+/// Sets the project's quota, overriding the default; existing attachments are kept even
+/// when they exceed it.
+#[utoipa::path(
+    put,
+    path = "/api/admin/projects/{project_id}/storage-quota",
+    tag = "admin",
+    params(("project_id" = String, Path)),
+    request_body = SetStorageQuotaRequest,
+    responses((status = 200, body = ProjectUsage))
+)]
+pub async fn set_storage_quota(
+    State(state): State<AppState>,
+    AdminUser(actor_id): AdminUser,
+    Path(project_id): Path<String>,
+    Json(payload): Json<SetStorageQuotaRequest>,
+) -> Result<Json<ProjectUsage>, ApiError> {
+    let project_uuid = parse_uuid(&project_id, ApiError::InvalidProjectId)?;
+    if payload.quota_bytes.is_some_and(|q| q < 0) {
+        return Err(ApiError::InvalidStorageQuota);
+    }
+    change_quota(&state, &actor_id, project_uuid, Some(payload.quota_bytes)).await
+}
+
+/// Removes the override; the `PROJECT_STORAGE_QUOTA_BYTES` default applies again.
+#[utoipa::path(
+    delete,
+    path = "/api/admin/projects/{project_id}/storage-quota",
+    tag = "admin",
+    params(("project_id" = String, Path)),
+    responses((status = 200, body = ProjectUsage))
+)]
+pub async fn reset_storage_quota(
+    State(state): State<AppState>,
+    AdminUser(actor_id): AdminUser,
+    Path(project_id): Path<String>,
+) -> Result<Json<ProjectUsage>, ApiError> {
+    let project_uuid = parse_uuid(&project_id, ApiError::InvalidProjectId)?;
+    change_quota(&state, &actor_id, project_uuid, None).await
+}
+
+/// `Some(quota)` stores an override (`Some(None)` = unlimited), `None` removes it.
+async fn change_quota(
+    state: &AppState,
+    actor_id: &str,
+    project_id: Uuid,
+    quota: Option<Option<i64>>,
+) -> Result<Json<ProjectUsage>, ApiError> {
+    ensure_db_user_exists(state, actor_id).await?;
+    let actor_uuid = parse_uuid(actor_id, ApiError::InvalidUserId)?;
+    let default_quota = state.attachment_limits.project_quota_bytes;
+
+    let save_failed = |_| ApiError::StorageQuotaSaveFailed;
+    let mut tx = state.db.begin().await.map_err(save_failed)?;
+    let exists = sqlx::query_scalar!(
+        r#"SELECT EXISTS (SELECT 1 FROM projects WHERE id = $1) AS "exists!""#,
+        project_id,
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(save_failed)?;
+    if !exists {
+        return Err(ApiError::ProjectNotFound);
+    }
+    let before = load_usage(&mut *tx, project_id, default_quota).await?;
+    match quota {
+        Some(quota_bytes) => {
+            sqlx::query!(
+                r#"
+                INSERT INTO project_storage_quotas (project_id, quota_bytes, set_by_user_id)
+                VALUES ($1, $2, $3)
+                ON CONFLICT (project_id) DO UPDATE SET
+                  quota_bytes = EXCLUDED.quota_bytes,
+                  set_by_user_id = EXCLUDED.set_by_user_id,
+                  updated_at = NOW()
+                "#,
+                project_id,
+                quota_bytes,
+                actor_uuid,
+            )
+            .execute(&mut *tx)
+            .await
+            .map_err(save_failed)?;
+        }
+        None => {
+            sqlx::query!(
+                r#"DELETE FROM project_storage_quotas WHERE project_id = $1"#,
+                project_id,
+            )
+            .execute(&mut *tx)
+            .await
+            .map_err(save_failed)?;
+        }
+    }
+    let after = load_usage(&mut *tx, project_id, default_quota).await?;
+    audit::record(
+        &mut *tx,
+        audit::AuditEntry {
+            actor_user_id: Some(actor_uuid),
+            action: "update",
+            entity_type: "project_storage_quota",
+            entity_id: Some(project_id),
+            project_id: Some(project_id),
+            run_id: None,
+            before: Some(json!({
+                "quotaBytes": before.quota_bytes,
+                "quotaOverridden": before.quota_overridden,
+            })),
+            after: Some(json!({
+                "quotaBytes": after.quota_bytes,
+                "quotaOverridden": after.quota_overridden,
+            })),
+        },
+    )
+    .await
+    .map_err(save_failed)?;
+    tx.commit().await.map_err(save_failed)?;
+    Ok(Json(after))
+}
@@ -0,0 +1,207 @@
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{
+        header::{AUTHORIZATION, RETRY_AFTER},
+        HeaderValue, Method,
+    },
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use sha2::{Digest, Sha256};
+use tracing::warn;
+
+use crate::{config::RateLimitConfig, error::ApiError, AppState};
+
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(60);
+/// Sign-in endpoints under the stricter per-IP limit; the SSO redirects are plain GETs.
+const AUTH_ROUTES: [(Method, &str); 4] = [
+    (Method::POST, "/api/auth/login"),
+    (Method::POST, "/api/auth/register"),
+    (Method::GET, "/api/auth/oidc/login"),
+    (Method::GET, "/api/auth/oidc/callback"),
+];
+
+/// Token bucket per client key: holds up to `per_minute` requests and refills continuously.
+struct Limit {
+    name: &'static str,
+    per_minute: u32,
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl Limit {
+    fn new(name: &'static str, per_minute: u32) -> Self {
+        Self {
+            name,
+            per_minute,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn refill_per_sec(&self) -> f64 {
+        f64::from(self.per_minute) / 60.0
+    }
+
+    /// Takes one token for `key`; on exhaustion returns how long until the next one.
+    fn acquire(&self, key: &str, now: Instant) -> Result<(), Duration> {
+        if self.per_minute == 0 {
+            return Ok(());
+        }
+        let capacity = f64::from(self.per_minute);
+        let rate = self.refill_per_sec();
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        let bucket = buckets.entry(key.to_string()).or_insert(Bucket {
+            tokens: capacity,
+            updated_at: now,
+        });
+        let elapsed = now
+            .saturating_duration_since(bucket.updated_at)
+            .as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * rate).min(capacity);
+        bucket.updated_at = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / rate))
+        }
+    }
+
+    /// Drops buckets that have refilled completely, they are equivalent to absent ones.
+    fn prune(&self, now: Instant) {
+        if self.per_minute == 0 {
+            return;
+        }
+        let capacity = f64::from(self.per_minute);
+        let rate = self.refill_per_sec();
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        buckets.retain(|_, bucket| {
+            let elapsed = now
+                .saturating_duration_since(bucket.updated_at)
+                .as_secs_f64();
+            bucket.tokens + elapsed * rate < capacity
+        });
+    }
+}
+
+pub struct RateLimiter {
+    auth: Limit,
+    ip: Limit,
+    token: Limit,
+    trust_proxy: bool,
+}
+
+impl RateLimiter {
+    /// Auth limits login/register per IP, ip and token all of `/api` per IP / per bearer
+    /// token (`0` disables a limit); `trust_proxy` takes the client IP from `X-Forwarded-For`.
+    pub fn new(config: &RateLimitConfig) -> Self {
+        Self {
+            auth: Limit::new("auth", config.auth_per_minute),
+            ip: Limit::new("ip", config.ip_per_minute),
+            token: Limit::new("token", config.token_per_minute),
+            trust_proxy: config.trust_proxy,
+        }
+    }
+
+    fn prune(&self) {
+        let now = Instant::now();
+        self.auth.prune(now);
+        self.ip.prune(now);
+        self.token.prune(now);
+    }
+
+    fn client_ip(&self, request: &Request) -> Option<IpAddr> {
+        // The client can send any `X-Forwarded-For`; only the last hop, appended by the
+        // trusted proxy itself, is the address it saw.
+        if self.trust_proxy {
+            let forwarded = request
+                .headers()
+                .get("x-forwarded-for")
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.rsplit(',').next())
+                .and_then(|v| v.trim().parse().ok());
+            if forwarded.is_some() {
+                return forwarded;
+            }
+        }
+        request
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|info| info.0.ip())
+    }
+}
+
+pub fn spawn_cleanup(limiter: Arc<RateLimiter>) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(CLEANUP_INTERVAL);
+        loop {
+            interval.tick().await;
+            limiter.prune();
+        }
+    });
+}
+
+/// Applies the per-IP and per-token limits to `/api` requests, plus the stricter per-IP
+/// limit on login/register. Runs before authentication so rejected requests cost nothing.
+pub async fn enforce(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let path = request.uri().path();
+    if !path.starts_with("/api/") {
+        return next.run(request).await;
+    }
+    let limiter = &state.rate_limiter;
+    let now = Instant::now();
+    let ip = limiter
+        .client_ip(&request)
+        .map(|ip| ip.to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+
+    let mut checks: Vec<(&Limit, String)> = vec![(&limiter.ip, ip.clone())];
+    if AUTH_ROUTES
+        .iter()
+        .any(|(method, route)| method == request.method() && *route == path)
+    {
+        checks.push((&limiter.auth, ip));
+    }
+    let token = request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(str::trim)
+        .filter(|v| !v.is_empty());
+    if let Some(token) = token {
+        checks.push((
+            &limiter.token,
+            hex::encode(Sha256::digest(token.as_bytes())),
+        ));
+    }
+
+    for (limit, key) in checks {
+        if let Err(wait) = limit.acquire(&key, now) {
+            let retry_after = wait.as_secs() + u64::from(wait.subsec_nanos() > 0);
+            let retry_after = retry_after.max(1);
+            warn!(limit = limit.name, path, "rate limit exceeded");
+            return too_many_requests(retry_after);
+        }
+    }
+    next.run(request).await
+}
+
+fn too_many_requests(retry_after: u64) -> Response {
+    let mut response = ApiError::RateLimited.into_response();
+    response
+        .headers_mut()
+        .insert(RETRY_AFTER, HeaderValue::from(retry_after));
+    response
+}
@@ -0,0 +1,105 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use sqlx::PgPool;
+use tracing::{info, warn};
+
+use crate::{config::DatabasePoolConfig, db_pool};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+const CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Streaming replica of the primary (`DATABASE_REPLICA_URL`) for analytics and large lists.
+/// Only reads that tolerate a few seconds of staleness go here, through
+/// `AppState::read_db`; writes and the reads that follow them stay on the primary. The
+/// replica is used only while the monitor sees it answer with a lag under
+/// `DATABASE_REPLICA_MAX_LAG_SECONDS`, otherwise reads fall back to the primary.
+pub struct ReadReplica {
+    pool: PgPool,
+    max_lag: Duration,
+    healthy: AtomicBool,
+}
+
+impl ReadReplica {
+    /// Connects lazily, so a replica that is down at startup does not stop the server; it
+    /// starts taking reads after the first successful check.
+    pub fn new(url: &str, pool: &DatabasePoolConfig, max_lag: Duration) -> anyhow::Result<Self> {
+        Ok(Self {
+            pool: db_pool::connect_lazy(url, pool)?,
+            max_lag,
+            healthy: AtomicBool::new(false),
+        })
+    }
+
+    /// The replica pool while it is healthy.
+    pub fn pool(&self) -> Option<&PgPool> {
+        self.healthy.load(Ordering::Relaxed).then_some(&self.pool)
+    }
+
+    /// The pool regardless of health, for metrics.
+    pub fn raw_pool(&self) -> &PgPool {
+        &self.pool
+    }
+
+    /// Replay lag in seconds: 0 when everything received has been replayed, so an idle
+    /// primary does not make the replica look stale; also 0 on a server that is not a
+    /// standby.
+    async fn lag(&self) -> Result<f64, sqlx::Error> {
+        sqlx::query_scalar(
+            r#"
+            SELECT COALESCE(
+              CASE WHEN pg_last_wal_receive_lsn() = pg_last_wal_replay_lsn() THEN 0
+                   ELSE EXTRACT(EPOCH FROM NOW() - pg_last_xact_replay_timestamp())::float8
+              END,
+              0
+            )
+            "#,
+        )
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    /// Fails when the replica does not answer or lags more than allowed.
+    pub async fn probe(&self) -> anyhow::Result<()> {
+        let lag = self.lag().await?;
+        if lag > self.max_lag.as_secs_f64() {
+            anyhow::bail!("lags {lag:.1} s behind");
+        }
+        Ok(())
+    }
+
+    /// Logs the first result and then only changes of state, so an outage does not repeat
+    /// a warning every check.
+    async fn check(&self, first: bool) {
+        let problem = match tokio::time::timeout(CHECK_TIMEOUT, self.probe()).await {
+            Ok(Ok(())) => None,
+            Ok(Err(err)) => Some(format!("{err:#}")),
+            Err(_) => Some("did not answer in time".to_string()),
+        };
+        let was_healthy = self.healthy.swap(problem.is_none(), Ordering::Relaxed);
+        match problem {
+            None if !was_healthy => info!("read replica is healthy, routing reads to it"),
+            Some(problem) if was_healthy || first => {
+                warn!("read replica is not usable ({problem}), reading from the primary")
+            }
+            _ => {}
+        }
+    }
+}
+
+pub fn spawn_monitor(replica: Arc<ReadReplica>) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        let mut first = true;
+        loop {
+            interval.tick().await;
+            replica.check(first).await;
+            first = false;
+        }
+    });
+}
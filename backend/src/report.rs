@@ -0,0 +1,411 @@
+use std::{collections::BTreeMap, path::PathBuf};
+
+use axum::{
+    body::Bytes,
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use printpdf::{
+    Color, IndirectFontRef, Mm, PdfDocument, PdfDocumentReference, PdfLayerReference, Rect, Rgb,
+};
+use serde_json::{json, Value};
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::{
+    authz::{self, AuthUser},
+    ensure_db_user_exists,
+    error::ApiError,
+    export, fetch_run_view,
+    jobs::{self, JobContext},
+    parse_uuid,
+    permissions::Capability,
+    read_projects, AppState, RunView,
+};
+
+const PAGE_WIDTH: f32 = 210.0;
+const PAGE_HEIGHT: f32 = 297.0;
+const MARGIN: f32 = 15.0;
+const ROW_HEIGHT: f32 = 6.0;
+
+/// Builtin PDF fonts cannot render Cyrillic, so reports embed a TTF font from disk.
+#[derive(Clone)]
+pub struct ReportSettings {
+    font_path: PathBuf,
+}
+
+impl ReportSettings {
+    pub fn new(font_path: PathBuf) -> Self {
+        Self { font_path }
+    }
+}
+
+struct ReportData {
+    project_name: String,
+    run: RunView,
+    run_title: String,
+    rows: Vec<export::ExportRow>,
+}
+
+fn cache_key(run_id: Uuid) -> String {
+    format!("reports/{run_id}.pdf")
+}
+
+/// Removes the stored report of a run; the next request for it renders a fresh one.
+pub async fn drop_cached(state: &AppState, run_id: Uuid) -> anyhow::Result<()> {
+    state.storage.delete(&cache_key(run_id)).await
+}
+
+fn clip(value: &str, max_chars: usize) -> String {
+    let value = value.replace(['\n', '\r'], " ");
+    if value.chars().count() <= max_chars {
+        return value;
+    }
+    let mut clipped: String = value.chars().take(max_chars.saturating_sub(1)).collect();
+    clipped.push('…');
+    clipped
+}
+
+/// `2026-01-31 10:15:00.123+00` -> `2026-01-31 10:15`.
+fn short_time(value: Option<DateTime<Utc>>) -> String {
+    value
+        .map(|at| at.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|| "—".to_string())
+}
+
+fn rgb(r: f32, g: f32, b: f32) -> Color {
+    Color::Rgb(Rgb::new(r, g, b, None))
+}
+
+fn status_color(status: &str) -> Color {
+    match status {
+        "ok" => rgb(0.20, 0.62, 0.33),
+        "fail" => rgb(0.82, 0.22, 0.20),
+        "na" => rgb(0.55, 0.55, 0.55),
+        "blocked" => rgb(0.90, 0.55, 0.10),
+        "skipped" => rgb(0.40, 0.55, 0.80),
+        "retest" => rgb(0.60, 0.35, 0.75),
+        _ => rgb(0.85, 0.85, 0.85),
+    }
+}
+
+struct ReportWriter {
+    doc: PdfDocumentReference,
+    font: IndirectFontRef,
+    layer: PdfLayerReference,
+    y: f32,
+}
+
+impl ReportWriter {
+    fn new(title: &str, font_bytes: &[u8]) -> anyhow::Result<Self> {
+        let (doc, page, layer) =
+            PdfDocument::new(title, Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "content");
+        let font = doc.add_external_font(font_bytes)?;
+        let layer = doc.get_page(page).get_layer(layer);
+        Ok(Self {
+            doc,
+            font,
+            layer,
+            y: PAGE_HEIGHT - MARGIN,
+        })
+    }
+
+    fn ensure_space(&mut self, height: f32) {
+        if self.y - height >= MARGIN {
+            return;
+        }
+        let (page, layer) = self
+            .doc
+            .add_page(Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "content");
+        self.layer = self.doc.get_page(page).get_layer(layer);
+        self.y = PAGE_HEIGHT - MARGIN;
+    }
+
+    fn text(&self, x: f32, size: f32, value: &str) {
+        self.layer.set_fill_color(rgb(0.0, 0.0, 0.0));
+        self.layer
+            .use_text(value, size, Mm(MARGIN + x), Mm(self.y), &self.font);
+    }
+
+    fn line(&mut self, size: f32, value: &str) {
+        self.ensure_space(size * 0.5);
+        self.y -= size * 0.5;
+        self.text(0.0, size, value);
+        self.y -= 2.0;
+    }
+
+    fn rect(&self, x: f32, width: f32, height: f32, color: Color) {
+        if width <= 0.0 {
+            return;
+        }
+        self.layer.set_fill_color(color);
+        self.layer.add_rect(Rect::new(
+            Mm(MARGIN + x),
+            Mm(self.y),
+            Mm(MARGIN + x + width),
+            Mm(self.y + height),
+        ));
+    }
+}
+
+fn render_report(data: &ReportData, font_bytes: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let mut w = ReportWriter::new(&format!("Отчёт: {}", data.run_title), font_bytes)?;
+    let content_width = PAGE_WIDTH - 2.0 * MARGIN;
+
+    w.line(18.0, "Отчёт о прогоне");
+    w.line(11.0, &format!("Проект: {}", clip(&data.project_name, 80)));
+    w.line(11.0, &format!("Run: {}", clip(&data.run_title, 80)));
+    w.line(
+        9.0,
+        &format!(
+            "Статус: {}   Начат: {}   Завершён: {}   Зафиксирован: {}",
+            data.run.status,
+            short_time(data.run.started_at),
+            short_time(data.run.finished_at),
+            short_time(data.run.locked_at),
+        ),
+    );
+    w.y -= 4.0;
+
+    // Summary: one stacked bar split by result status, followed by a legend with counts.
+    let total = data.rows.len();
+    let count = |status: &str| data.rows.iter().filter(|r| r.status == status).count();
+    let buckets = [
+        ("ok", "OK", count("ok")),
+        ("fail", "FAIL", count("fail")),
+        ("na", "N/A", count("na")),
+        ("blocked", "BLOCKED", count("blocked")),
+        ("skipped", "SKIPPED", count("skipped")),
+        ("retest", "RETEST", count("retest")),
+        ("untested", "Не пройдено", count("untested")),
+    ];
+    w.line(13.0, "Сводка");
+    w.ensure_space(12.0);
+    w.y -= 8.0;
+    let mut x = 0.0;
+    for (status, _, n) in buckets {
+        if total > 0 {
+            let width = content_width * n as f32 / total as f32;
+            w.rect(x, width, 6.0, status_color(status));
+            x += width;
+        }
+    }
+    // Four legend entries per row.
+    for row in buckets.chunks(4) {
+        w.y -= 6.0;
+        let mut x = 0.0;
+        for (status, label, n) in row {
+            w.rect(x, 3.0, 3.0, status_color(status));
+            w.text(x + 4.5, 9.0, &format!("{label}: {n}"));
+            x += 42.0;
+        }
+    }
+    w.y -= 4.0;
+    let required_failing = data
+        .rows
+        .iter()
+        .filter(|r| r.is_required && r.status == "fail")
+        .count();
+    w.line(
+        9.0,
+        &format!("Всего пунктов: {total}   Обязательных с FAIL: {required_failing}"),
+    );
+    w.y -= 4.0;
+
+    // Per-item results.
+    let columns: [(f32, &str); 6] = [
+        (0.0, "#"),
+        (8.0, "Ключ"),
+        (30.0, "Тест-кейс"),
+        (95.0, "Статус"),
+        (112.0, "Причина / комментарий"),
+        (160.0, "Исполнитель"),
+    ];
+    w.line(13.0, "Результаты");
+    w.ensure_space(ROW_HEIGHT);
+    w.y -= ROW_HEIGHT;
+    for (x, title) in columns {
+        w.text(x, 8.0, title);
+    }
+    for row in &data.rows {
+        w.ensure_space(ROW_HEIGHT);
+        w.y -= ROW_HEIGHT;
+        let note = match (row.fail_reason.is_empty(), row.comment.is_empty()) {
+            (false, false) => format!("{}: {}", row.fail_reason, row.comment),
+            (false, true) => row.fail_reason.clone(),
+            _ => row.comment.clone(),
+        };
+        w.rect(93.5, 1.2, 3.0, status_color(&row.status));
+        let cells = [
+            row.position.to_string(),
+            clip(&row.testcase_key, 12),
+            clip(&row.testcase_title, 38),
+            row.status.clone(),
+            clip(&note, 28),
+            clip(&row.executor, 14),
+        ];
+        for ((x, _), value) in columns.iter().zip(cells.iter()) {
+            w.text(*x, 8.0, value);
+        }
+    }
+    w.y -= 6.0;
+
+    // Sign-off: everyone who recorded a result, plus the approver.
+    let mut executors: BTreeMap<&str, usize> = BTreeMap::new();
+    for row in data.rows.iter().filter(|r| !r.executor.is_empty()) {
+        *executors.entry(row.executor.as_str()).or_default() += 1;
+    }
+    w.line(13.0, "Подписи");
+    for (name, n) in executors {
+        w.ensure_space(10.0);
+        w.y -= 8.0;
+        w.text(0.0, 9.0, &format!("{} ({n} рез.)", clip(name, 40)));
+        w.text(95.0, 9.0, "Подпись: ____________________");
+    }
+    w.ensure_space(10.0);
+    w.y -= 8.0;
+    w.text(0.0, 9.0, "Утвердил: ____________________");
+    w.text(95.0, 9.0, "Дата: ______________");
+
+    Ok(w.doc.save_to_bytes()?)
+}
+
+async fn project_name(state: &AppState, project_id: &str) -> String {
+    let _guard = state.file_lock.lock().await;
+    read_projects(&state.projects_file)
+        .await
+        .ok()
+        .and_then(|projects| projects.into_iter().find(|p| p.id == project_id))
+        .map(|p| p.name)
+        .unwrap_or_default()
+}
+
+fn pdf_response(run_id: Uuid, body: Bytes) -> Response {
+    (
+        [
+            (header::CONTENT_TYPE, "application/pdf".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"run-{run_id}.pdf\""),
+            ),
+        ],
+        body,
+    )
+        .into_response()
+}
+
+/// Renders the run's report. Reports of locked runs never change, so the first rendering is
+/// stored under `reports/{run_id}.pdf` in the attachment storage and served from there.
+async fn build_report(state: &AppState, run_uuid: Uuid) -> Result<Bytes, ApiError> {
+    let run = fetch_run_view(state.read_db(), run_uuid)
+        .await?
+        .ok_or(ApiError::RunNotFound)?;
+    let is_locked = run.status == "locked";
+    if is_locked {
+        if let Ok(cached) = state.storage.get(&cache_key(run_uuid)).await {
+            return Ok(cached);
+        }
+    }
+
+    let (run_title, rows) = export::load_export_rows(state, run_uuid).await?;
+    let font_bytes = tokio::fs::read(&state.report_settings.font_path)
+        .await
+        .map_err(|_| ApiError::ReportFontMissing)?;
+    let data = ReportData {
+        project_name: project_name(state, &run.project_id).await,
+        run,
+        run_title,
+        rows,
+    };
+    let pdf = tokio::task::spawn_blocking(move || render_report(&data, &font_bytes))
+        .await
+        .ok()
+        .and_then(|result| result.ok())
+        .map(Bytes::from)
+        .ok_or(ApiError::ReportRenderFailed)?;
+
+    if is_locked {
+        if let Err(err) = state.storage.put(&cache_key(run_uuid), pdf.clone()).await {
+            warn!("failed to cache report for run {run_uuid}: {err:#}");
+        }
+    }
+    Ok(pdf)
+}
+
+/// PDF report for a run, rendered while the request waits; large runs are better served by
+/// `POST /api/v2/runs/{run_id}/report-jobs`.
+#[utoipa::path(
+    get,
+    path = "/api/v2/runs/{run_id}/report.pdf",
+    tag = "export",
+    params(("run_id" = String, Path)),
+    responses((status = 200, description = "PDF-отчёт по run.", content_type = "application/pdf"))
+)]
+pub async fn run_report_pdf(
+    State(state): State<AppState>,
+    Path(run_id): Path<String>,
+    AuthUser(actor_id): AuthUser,
+) -> Result<Response, ApiError> {
+    let run_uuid = parse_uuid(&run_id, ApiError::InvalidRunId)?;
+    authz::require_run_capability(&state, run_uuid, &actor_id, Capability::ProjectRead).await?;
+    let pdf = build_report(&state, run_uuid).await?;
+    Ok(pdf_response(run_uuid, pdf))
+}
+
+/// Renders the PDF report in the background; the finished job links to the file.
+#[utoipa::path(
+    post,
+    path = "/api/v2/runs/{run_id}/report-jobs",
+    tag = "export",
+    params(("run_id" = String, Path)),
+    responses((status = 202, body = jobs::JobAcceptedResponse))
+)]
+pub async fn queue_run_report(
+    State(state): State<AppState>,
+    Path(run_id): Path<String>,
+    AuthUser(actor_id): AuthUser,
+) -> Result<(StatusCode, Json<jobs::JobAcceptedResponse>), ApiError> {
+    let run_uuid = parse_uuid(&run_id, ApiError::InvalidRunId)?;
+    authz::require_run_capability(&state, run_uuid, &actor_id, Capability::ProjectRead).await?;
+    ensure_db_user_exists(&state, &actor_id).await?;
+    let actor_uuid = parse_uuid(&actor_id, ApiError::InvalidUserId)?;
+    let run = fetch_run_view(&state.db, run_uuid)
+        .await?
+        .ok_or(ApiError::RunNotFound)?;
+    let project_id = parse_uuid(&run.project_id, ApiError::InvalidProjectId)?;
+    let job_id = jobs::submit(
+        &state,
+        jobs::NewJob {
+            kind: jobs::JobKind::RunReport,
+            project_id: Some(project_id),
+            created_by_user_id: Some(actor_uuid),
+            payload: json!({ "runId": run_uuid }),
+        },
+    )
+    .await
+    .map_err(|_| ApiError::JobQueueFailed)?;
+    Ok(jobs::accepted(job_id))
+}
+
+/// Job handler of [`queue_run_report`]: the PDF is stored under `reports/jobs/{job_id}.pdf`.
+pub async fn run_report_job(
+    state: &AppState,
+    ctx: &JobContext,
+    payload: Value,
+) -> anyhow::Result<Value> {
+    let run_uuid: Uuid = serde_json::from_value(payload["runId"].clone())?;
+    let pdf = build_report(state, run_uuid)
+        .await
+        .map_err(|err| anyhow::anyhow!("{}", err.code()))?;
+    let key = format!("reports/jobs/{}.pdf", ctx.id);
+    state.storage.put(&key, pdf).await?;
+    Ok(jobs::file_result(
+        ctx.id,
+        &key,
+        "application/pdf",
+        &format!("run-{run_uuid}.pdf"),
+    ))
+}
@@ -0,0 +1,581 @@
+use std::collections::HashMap;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::Row;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{
+    authz::{self, AuthUser, ProjectRole},
+    db_errors, ensure_db_user_exists,
+    error::ApiError,
+    parse_uuid,
+    permissions::Capability,
+    AppState,
+};
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateRequirementRequest {
+    key: String,
+    title: String,
+    description: Option<String>,
+    testcase_ids: Option<Vec<String>>,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateRequirementRequest {
+    title: Option<String>,
+    description: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SetRequirementTestcasesRequest {
+    testcase_ids: Vec<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RequirementView {
+    id: String,
+    project_id: String,
+    key: String,
+    title: String,
+    description: String,
+    testcase_ids: Vec<String>,
+    created_at: String,
+    updated_at: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct RequirementResponse {
+    requirement: RequirementView,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ListRequirementsResponse {
+    requirements: Vec<RequirementView>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct DeleteRequirementResponse {
+    ok: bool,
+}
+
+#[derive(Serialize, Clone, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LatestResultView {
+    run_id: String,
+    run_title: String,
+    status: String,
+    updated_at: String,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceabilityTestcaseView {
+    testcase_id: String,
+    key: String,
+    title: String,
+    latest_result: Option<LatestResultView>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceabilityRowView {
+    requirement_id: String,
+    key: String,
+    title: String,
+    /// `uncovered` | `not_run` | `failed` | `passed` | `partial`
+    coverage: &'static str,
+    testcases: Vec<TraceabilityTestcaseView>,
+}
+
+#[derive(Serialize, Default, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceabilitySummary {
+    total: usize,
+    uncovered: usize,
+    not_run: usize,
+    failed: usize,
+    passed: usize,
+    partial: usize,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct TraceabilityResponse {
+    requirements: Vec<TraceabilityRowView>,
+    summary: TraceabilitySummary,
+}
+
+const REQUIREMENT_SELECT: &str = r#"
+  SELECT
+    r.id::text AS id,
+    r.project_id::text AS project_id,
+    r.key,
+    r.title,
+    r.description,
+    COALESCE(
+      ARRAY_AGG(rt.testcase_id::text ORDER BY rt.created_at) FILTER (WHERE rt.testcase_id IS NOT NULL),
+      ARRAY[]::text[]
+    ) AS testcase_ids,
+    r.created_at::text AS created_at,
+    r.updated_at::text AS updated_at
+  FROM requirements r
+  LEFT JOIN requirement_testcases rt ON rt.requirement_id = r.id
+"#;
+
+fn map_requirement_row(r: &sqlx::postgres::PgRow) -> RequirementView {
+    RequirementView {
+        id: r.get::<String, _>("id"),
+        project_id: r.get::<String, _>("project_id"),
+        key: r.get::<String, _>("key"),
+        title: r.get::<String, _>("title"),
+        description: r.get::<String, _>("description"),
+        testcase_ids: r.get::<Vec<String>, _>("testcase_ids"),
+        created_at: r.get::<String, _>("created_at"),
+        updated_at: r.get::<String, _>("updated_at"),
+    }
+}
+
+fn validate_requirement_title(title: &str) -> Result<(), ApiError> {
+    let len = title.chars().count();
+    if !(2..=240).contains(&len) {
+        return Err(ApiError::InvalidRequirementTitle);
+    }
+    Ok(())
+}
+
+fn parse_testcase_ids(input: &[String]) -> Result<Vec<Uuid>, ApiError> {
+    let mut ids: Vec<Uuid> = Vec::with_capacity(input.len());
+    for raw in input {
+        let id = parse_uuid(raw, ApiError::InvalidTestcaseIdParam)?;
+        if !ids.contains(&id) {
+            ids.push(id);
+        }
+    }
+    Ok(ids)
+}
+
+async fn fetch_requirement(
+    state: &AppState,
+    requirement_id: Uuid,
+) -> Result<Option<RequirementView>, ApiError> {
+    let sql = format!("{REQUIREMENT_SELECT} WHERE r.id = $1 GROUP BY r.id");
+    let row = sqlx::query(&sql)
+        .bind(requirement_id)
+        .fetch_optional(&state.db)
+        .await
+        .map_err(|_| ApiError::RequirementReadFailed)?;
+    Ok(row.as_ref().map(map_requirement_row))
+}
+
+/// Loads a requirement and checks the actor's access to its project.
+async fn load_requirement_for_actor(
+    state: &AppState,
+    requirement_id: &str,
+    actor_id: &str,
+    capability: Capability,
+) -> Result<RequirementView, ApiError> {
+    let requirement_uuid = parse_uuid(requirement_id, ApiError::InvalidRequirementId)?;
+    let requirement = fetch_requirement(state, requirement_uuid)
+        .await?
+        .ok_or(ApiError::RequirementNotFound)?;
+    authz::require_capability(state, &requirement.project_id, actor_id, capability).await?;
+    Ok(requirement)
+}
+
+/// Replaces the linked test cases; every test case must belong to the requirement's project.
+async fn replace_links(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    requirement_id: Uuid,
+    project_id: Uuid,
+    testcase_ids: &[Uuid],
+) -> Result<(), ApiError> {
+    let link_failed = |err| db_errors::map(err, ApiError::RequirementLinkFailed);
+    let in_project: i64 = sqlx::query_scalar(
+        r#"
+        SELECT COUNT(*)
+        FROM testcases tc
+        JOIN test_suites ts ON ts.id = tc.suite_id
+        WHERE tc.id = ANY($1) AND ts.project_id = $2
+        "#,
+    )
+    .bind(testcase_ids)
+    .bind(project_id)
+    .fetch_one(&mut **tx)
+    .await
+    .map_err(link_failed)?;
+    if in_project as usize != testcase_ids.len() {
+        return Err(ApiError::RequirementTestcasesForeign);
+    }
+    sqlx::query(r#"DELETE FROM requirement_testcases WHERE requirement_id = $1"#)
+        .bind(requirement_id)
+        .execute(&mut **tx)
+        .await
+        .map_err(link_failed)?;
+    sqlx::query(
+        r#"
+        INSERT INTO requirement_testcases (requirement_id, testcase_id)
+        SELECT $1, id FROM UNNEST($2::uuid[]) AS t(id)
+        "#,
+    )
+    .bind(requirement_id)
+    .bind(testcase_ids)
+    .execute(&mut **tx)
+    .await
+    .map_err(link_failed)?;
+    Ok(())
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v2/projects/{project_id}/requirements",
+    tag = "requirements",
+    params(("project_id" = String, Path)),
+    responses((status = 200, body = ListRequirementsResponse))
+)]
+pub async fn list_requirements(
+    State(state): State<AppState>,
+    access: ProjectRole,
+) -> Result<Json<ListRequirementsResponse>, ApiError> {
+    let project_uuid = access.project_id;
+    let sql = format!("{REQUIREMENT_SELECT} WHERE r.project_id = $1 GROUP BY r.id ORDER BY r.key");
+    let rows = sqlx::query(&sql)
+        .bind(project_uuid)
+        .fetch_all(&state.db)
+        .await
+        .map_err(|_| ApiError::RequirementsReadFailed)?;
+
+    Ok(Json(ListRequirementsResponse {
+        requirements: rows.iter().map(map_requirement_row).collect(),
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v2/projects/{project_id}/requirements",
+    tag = "requirements",
+    params(("project_id" = String, Path)),
+    request_body = CreateRequirementRequest,
+    responses((status = 201, body = RequirementResponse))
+)]
+pub async fn create_requirement(
+    State(state): State<AppState>,
+    access: ProjectRole,
+    Json(payload): Json<CreateRequirementRequest>,
+) -> Result<(StatusCode, Json<RequirementResponse>), ApiError> {
+    access.require(Capability::LibraryEdit)?;
+    let ProjectRole {
+        user_id: actor_id,
+        project_id: project_uuid,
+        ..
+    } = access;
+    let key = payload.key.trim().to_string();
+    if key.is_empty() || key.chars().count() > 64 {
+        return Err(ApiError::InvalidRequirementKey);
+    }
+    let title = payload.title.trim().to_string();
+    validate_requirement_title(&title)?;
+    let testcase_ids = parse_testcase_ids(payload.testcase_ids.as_deref().unwrap_or_default())?;
+    ensure_db_user_exists(&state, &actor_id).await?;
+    let actor_uuid = parse_uuid(&actor_id, ApiError::InvalidUserId)?;
+
+    let mut tx = state
+        .db
+        .begin()
+        .await
+        .map_err(|_| ApiError::RequirementCreateFailed)?;
+    let requirement_id: Uuid = sqlx::query_scalar(
+        r#"
+        INSERT INTO requirements (
+          project_id, key, title, description, created_by_user_id, updated_by_user_id
+        )
+        VALUES ($1, $2, $3, $4, $5, $5)
+        RETURNING id
+        "#,
+    )
+    .bind(project_uuid)
+    .bind(&key)
+    .bind(&title)
+    .bind(payload.description.unwrap_or_default())
+    .bind(actor_uuid)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|err| db_errors::map(err, ApiError::RequirementRejected))?;
+    replace_links(&mut tx, requirement_id, project_uuid, &testcase_ids).await?;
+    tx.commit()
+        .await
+        .map_err(|_| ApiError::RequirementCreateFailed)?;
+
+    let requirement = fetch_requirement(&state, requirement_id)
+        .await?
+        .ok_or(ApiError::RequirementCreatedNotFound)?;
+    Ok((
+        StatusCode::CREATED,
+        Json(RequirementResponse { requirement }),
+    ))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/api/v2/requirements/{requirement_id}",
+    tag = "requirements",
+    params(("requirement_id" = String, Path)),
+    request_body = UpdateRequirementRequest,
+    responses((status = 200, body = RequirementResponse))
+)]
+pub async fn update_requirement(
+    State(state): State<AppState>,
+    Path(requirement_id): Path<String>,
+    AuthUser(actor_id): AuthUser,
+    Json(payload): Json<UpdateRequirementRequest>,
+) -> Result<Json<RequirementResponse>, ApiError> {
+    let title = payload.title.as_deref().map(str::trim).map(str::to_string);
+    if let Some(title) = title.as_deref() {
+        validate_requirement_title(title)?;
+    }
+    let requirement =
+        load_requirement_for_actor(&state, &requirement_id, &actor_id, Capability::LibraryEdit)
+            .await?;
+    ensure_db_user_exists(&state, &actor_id).await?;
+    let actor_uuid = parse_uuid(&actor_id, ApiError::InvalidUserId)?;
+    let requirement_uuid = parse_uuid(&requirement.id, ApiError::InvalidRequirementId)?;
+
+    sqlx::query(
+        r#"
+        UPDATE requirements
+        SET title = COALESCE($2, title),
+            description = COALESCE($3, description),
+            updated_by_user_id = $4
+        WHERE id = $1
+        "#,
+    )
+    .bind(requirement_uuid)
+    .bind(title)
+    .bind(payload.description)
+    .bind(actor_uuid)
+    .execute(&state.db)
+    .await
+    .map_err(|err| db_errors::map(err, ApiError::RequirementUpdateRejected))?;
+
+    let requirement = fetch_requirement(&state, requirement_uuid)
+        .await?
+        .ok_or(ApiError::RequirementNotFound)?;
+    Ok(Json(RequirementResponse { requirement }))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v2/requirements/{requirement_id}/testcases",
+    tag = "library",
+    params(("requirement_id" = String, Path)),
+    request_body = SetRequirementTestcasesRequest,
+    responses((status = 200, body = RequirementResponse))
+)]
+pub async fn set_requirement_testcases(
+    State(state): State<AppState>,
+    Path(requirement_id): Path<String>,
+    AuthUser(actor_id): AuthUser,
+    Json(payload): Json<SetRequirementTestcasesRequest>,
+) -> Result<Json<RequirementResponse>, ApiError> {
+    let testcase_ids = parse_testcase_ids(&payload.testcase_ids)?;
+    let requirement =
+        load_requirement_for_actor(&state, &requirement_id, &actor_id, Capability::LibraryEdit)
+            .await?;
+    let requirement_uuid = parse_uuid(&requirement.id, ApiError::InvalidRequirementId)?;
+    let project_uuid = parse_uuid(&requirement.project_id, ApiError::InvalidProjectId)?;
+
+    let mut tx = state
+        .db
+        .begin()
+        .await
+        .map_err(|_| ApiError::RequirementLinkFailed)?;
+    replace_links(&mut tx, requirement_uuid, project_uuid, &testcase_ids).await?;
+    tx.commit()
+        .await
+        .map_err(|_| ApiError::RequirementLinkFailed)?;
+
+    let requirement = fetch_requirement(&state, requirement_uuid)
+        .await?
+        .ok_or(ApiError::RequirementNotFound)?;
+    Ok(Json(RequirementResponse { requirement }))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v2/requirements/{requirement_id}",
+    tag = "requirements",
+    params(("requirement_id" = String, Path)),
+    responses((status = 200, body = DeleteRequirementResponse))
+)]
+pub async fn delete_requirement(
+    State(state): State<AppState>,
+    Path(requirement_id): Path<String>,
+    AuthUser(actor_id): AuthUser,
+) -> Result<Json<DeleteRequirementResponse>, ApiError> {
+    let requirement =
+        load_requirement_for_actor(&state, &requirement_id, &actor_id, Capability::LibraryEdit)
+            .await?;
+    let requirement_uuid = parse_uuid(&requirement.id, ApiError::InvalidRequirementId)?;
+
+    sqlx::query(r#"DELETE FROM requirements WHERE id = $1"#)
+        .bind(requirement_uuid)
+        .execute(&state.db)
+        .await
+        .map_err(|_| ApiError::RequirementDeleteFailed)?;
+
+    Ok(Json(DeleteRequirementResponse { ok: true }))
+}
+
+/// Coverage of a requirement from the latest results of its test cases.
+fn coverage_of(testcases: &[TraceabilityTestcaseView]) -> &'static str {
+    if testcases.is_empty() {
+        return "uncovered";
+    }
+    let statuses: Vec<&str> = testcases
+        .iter()
+        .filter_map(|t| t.latest_result.as_ref().map(|r| r.status.as_str()))
+        .collect();
+    if statuses.is_empty() {
+        "not_run"
+    } else if statuses.contains(&"fail") {
+        "failed"
+    } else if statuses.len() == testcases.len() && statuses.iter().all(|s| *s == "ok") {
+        "passed"
+    } else {
+        "partial"
+    }
+}
+
+/// Requirements vs. the latest recorded result of each linked test case in the project's runs.
+/// Placeholder `na` results count as "not run".
+#[utoipa::path(
+    get,
+    path = "/api/v2/projects/{project_id}/traceability",
+    tag = "requirements",
+    params(("project_id" = String, Path)),
+    responses((status = 200, body = TraceabilityResponse))
+)]
+pub async fn get_traceability(
+    State(state): State<AppState>,
+    access: ProjectRole,
+) -> Result<Json<TraceabilityResponse>, ApiError> {
+    let project_uuid = access.project_id;
+    let read_failed = |_| ApiError::TraceabilityFailed;
+
+    let link_rows = sqlx::query(
+        r#"
+        SELECT
+          r.id::text AS requirement_id,
+          r.key AS requirement_key,
+          r.title AS requirement_title,
+          tc.id::text AS testcase_id,
+          tc.key AS testcase_key,
+          tc.title AS testcase_title
+        FROM requirements r
+        LEFT JOIN requirement_testcases rt ON rt.requirement_id = r.id
+        LEFT JOIN testcases tc ON tc.id = rt.testcase_id
+        WHERE r.project_id = $1
+        ORDER BY r.key ASC, tc.key ASC
+        "#,
+    )
+    .bind(project_uuid)
+    .fetch_all(&state.db)
+    .await
+    .map_err(read_failed)?;
+
+    let result_rows = sqlx::query(
+        r#"
+        SELECT DISTINCT ON (tv.testcase_id)
+          tv.testcase_id::text AS testcase_id,
+          r.id::text AS run_id,
+          r.title AS run_title,
+          rr.status::text AS status,
+          rr.updated_at::text AS updated_at
+        FROM run_results rr
+        JOIN run_items ri ON ri.id = rr.run_item_id
+        JOIN runs r ON r.id = ri.run_id
+        JOIN testcase_versions tv ON tv.id = ri.testcase_version_id
+        WHERE r.project_id = $1
+          AND rr.status <> 'na'
+          AND tv.testcase_id IN (
+            SELECT rt.testcase_id
+            FROM requirement_testcases rt
+            JOIN requirements q ON q.id = rt.requirement_id
+            WHERE q.project_id = $1
+          )
+        ORDER BY tv.testcase_id, rr.updated_at DESC
+        "#,
+    )
+    .bind(project_uuid)
+    .fetch_all(&state.db)
+    .await
+    .map_err(read_failed)?;
+    let latest: HashMap<String, LatestResultView> = result_rows
+        .iter()
+        .map(|r| {
+            (
+                r.get::<String, _>("testcase_id"),
+                LatestResultView {
+                    run_id: r.get::<String, _>("run_id"),
+                    run_title: r.get::<String, _>("run_title"),
+                    status: r.get::<String, _>("status"),
+                    updated_at: r.get::<String, _>("updated_at"),
+                },
+            )
+        })
+        .collect();
+
+    let mut requirements: Vec<TraceabilityRowView> = Vec::new();
+    for row in &link_rows {
+        let requirement_id = row.get::<String, _>("requirement_id");
+        if requirements.last().map(|r| &r.requirement_id) != Some(&requirement_id) {
+            requirements.push(TraceabilityRowView {
+                requirement_id,
+                key: row.get::<String, _>("requirement_key"),
+                title: row.get::<String, _>("requirement_title"),
+                coverage: "uncovered",
+                testcases: Vec::new(),
+            });
+        }
+        if let (Some(current), Some(testcase_id)) = (
+            requirements.last_mut(),
+            row.get::<Option<String>, _>("testcase_id"),
+        ) {
+            current.testcases.push(TraceabilityTestcaseView {
+                latest_result: latest.get(&testcase_id).cloned(),
+                testcase_id,
+                key: row.get::<String, _>("testcase_key"),
+                title: row.get::<String, _>("testcase_title"),
+            });
+        }
+    }
+
+    let mut summary = TraceabilitySummary {
+        total: requirements.len(),
+        ..Default::default()
+    };
+    for requirement in &mut requirements {
+        requirement.coverage = coverage_of(&requirement.testcases);
+        match requirement.coverage {
+            "uncovered" => summary.uncovered += 1,
+            "not_run" => summary.not_run += 1,
+            "failed" => summary.failed += 1,
+            "passed" => summary.passed += 1,
+            _ => summary.partial += 1,
+        }
+    }
+
+    Ok(Json(TraceabilityResponse {
+        requirements,
+        summary,
+    }))
+}
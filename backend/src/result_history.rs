@@ -0,0 +1,246 @@
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::Row;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{
+    authz::{self, AuthUser},
+    error::ApiError,
+    pagination, parse_result_status, parse_uuid,
+    permissions::Capability,
+    testcases, AppState,
+};
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ResultChangeView {
+    id: String,
+    status: String,
+    /// `null` for the first result of the item.
+    previous_status: Option<String>,
+    fail_reason_code: Option<String>,
+    comment: String,
+    measured_value: Option<String>,
+    changed_by_user_id: Option<String>,
+    changed_at: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ResultHistoryResponse {
+    history: Vec<ResultChangeView>,
+}
+
+/// Result changes of a run item, oldest first. The history is written by a trigger on
+/// `run_results`, so it covers every path that records a result.
+#[utoipa::path(
+    get,
+    path = "/api/v2/runs/{run_id}/items/{run_item_id}/history",
+    tag = "results",
+    params(("run_id" = String, Path), ("run_item_id" = String, Path)),
+    responses((status = 200, body = ResultHistoryResponse))
+)]
+pub async fn get_result_history(
+    State(state): State<AppState>,
+    Path((run_id, run_item_id)): Path<(String, String)>,
+    AuthUser(actor_id): AuthUser,
+) -> Result<Json<ResultHistoryResponse>, ApiError> {
+    let run_uuid = parse_uuid(&run_id, ApiError::InvalidRunId)?;
+    let run_item_uuid = parse_uuid(&run_item_id, ApiError::InvalidRunItemId)?;
+    authz::require_run_capability(&state, run_uuid, &actor_id, Capability::ProjectRead).await?;
+
+    let exists: bool = sqlx::query_scalar(
+        r#"SELECT EXISTS (SELECT 1 FROM run_items WHERE id = $1 AND run_id = $2)"#,
+    )
+    .bind(run_item_uuid)
+    .bind(run_uuid)
+    .fetch_one(state.read_db())
+    .await
+    .map_err(|_| ApiError::ResultHistoryReadFailed)?;
+    if !exists {
+        return Err(ApiError::RunItemNotFound);
+    }
+
+    let rows = sqlx::query(
+        r#"
+        SELECT
+          id::text AS id,
+          status::text AS status,
+          previous_status::text AS previous_status,
+          fail_reason_code,
+          comment,
+          measured_value,
+          changed_by_user_id::text AS changed_by_user_id,
+          changed_at::text AS changed_at
+        FROM run_result_history
+        WHERE run_item_id = $1
+        ORDER BY changed_at ASC, id ASC
+        "#,
+    )
+    .bind(run_item_uuid)
+    .fetch_all(state.read_db())
+    .await
+    .map_err(|_| ApiError::ResultHistoryReadFailed)?;
+
+    Ok(Json(ResultHistoryResponse {
+        history: rows
+            .iter()
+            .map(|row| ResultChangeView {
+                id: row.get("id"),
+                status: row.get("status"),
+                previous_status: row.get("previous_status"),
+                fail_reason_code: row.get("fail_reason_code"),
+                comment: row.get("comment"),
+                measured_value: row.get("measured_value"),
+                changed_by_user_id: row.get("changed_by_user_id"),
+                changed_at: row.get("changed_at"),
+            })
+            .collect(),
+    }))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct ExecutionsQuery {
+    /// Only results with this status: `ok|fail|blocked|skipped|retest`; `na` items are
+    /// never listed.
+    status: Option<String>,
+    limit: Option<i64>,
+    cursor: Option<String>,
+}
+
+/// A run item of any version of the test case that has a result other than `na`.
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecutionView {
+    run_id: String,
+    run_item_id: String,
+    run_title: String,
+    run_status: String,
+    version_number: i32,
+    /// The current result of the item.
+    status: String,
+    fail_reason_code: Option<String>,
+    /// The user who recorded the current result.
+    executor_user_id: Option<String>,
+    executor_name: Option<String>,
+    /// When the item first got a result other than `na`.
+    executed_at: Option<DateTime<Utc>>,
+    /// When the current result was recorded; the list is ordered by it.
+    updated_at: DateTime<Utc>,
+    /// The environment: the asset the run was executed on and its firmware at that time.
+    asset_id: Option<String>,
+    asset_name: Option<String>,
+    asset_version: Option<String>,
+    commit_sha: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecutionsResponse {
+    executions: Vec<ExecutionView>,
+    /// Latest `ok` result across all runs, whatever the filter.
+    last_passed_at: Option<DateTime<Utc>>,
+    /// Latest `fail` result across all runs, whatever the filter.
+    last_failed_at: Option<DateTime<Utc>>,
+    next_cursor: Option<String>,
+}
+
+/// Every run where a version of the test case was executed, newest result first.
+#[utoipa::path(
+    get,
+    path = "/api/v2/testcases/{testcase_id}/executions",
+    tag = "results",
+    params(("testcase_id" = String, Path), ExecutionsQuery),
+    responses((status = 200, body = ExecutionsResponse))
+)]
+pub async fn list_testcase_executions(
+    State(state): State<AppState>,
+    Path(testcase_id): Path<String>,
+    AuthUser(actor_id): AuthUser,
+    Query(query): Query<ExecutionsQuery>,
+) -> Result<Json<ExecutionsResponse>, ApiError> {
+    let testcase_uuid = parse_uuid(&testcase_id, ApiError::InvalidTestcaseId)?;
+    let status = match query.status.as_deref().map(str::trim) {
+        None | Some("") => None,
+        Some(status) => Some(parse_result_status(status)?),
+    };
+    testcases::authorize_testcase(&state, testcase_uuid, &actor_id, Capability::ProjectRead)
+        .await?;
+    let limit = pagination::page_limit(query.limit);
+    let cursor = pagination::parse_cursor(query.cursor.as_deref())?;
+    let failed = |_| ApiError::ResultHistoryReadFailed;
+
+    let rows = sqlx::query_as!(
+        ExecutionView,
+        r#"
+        SELECT
+          r.id::text AS "run_id!",
+          ri.id::text AS "run_item_id!",
+          r.title AS run_title,
+          r.status::text AS "run_status!",
+          tv.version_number,
+          rr.status::text AS "status!",
+          rr.fail_reason_code,
+          rr.updated_by_user_id::text AS executor_user_id,
+          u.display_name AS "executor_name?",
+          rr.executed_at,
+          rr.updated_at,
+          r.asset_id::text AS asset_id,
+          a.name AS "asset_name?",
+          r.asset_version,
+          r.commit_sha
+        FROM run_results rr
+        JOIN run_items ri ON ri.id = rr.run_item_id
+        JOIN runs r ON r.id = ri.run_id
+        JOIN testcase_versions tv ON tv.id = ri.testcase_version_id
+        LEFT JOIN users u ON u.id = rr.updated_by_user_id
+        LEFT JOIN assets a ON a.id = r.asset_id
+        WHERE tv.testcase_id = $1
+          AND rr.status <> 'na'
+          AND ($2::text IS NULL OR rr.status::text = $2)
+          AND ($3::timestamptz IS NULL OR (rr.updated_at, ri.id) < ($3::timestamptz, $4::uuid))
+        ORDER BY rr.updated_at DESC, ri.id DESC
+        LIMIT $5
+        "#,
+        testcase_uuid,
+        status,
+        cursor.as_ref().map(|c| c.timestamp()),
+        cursor.as_ref().map(|c| c.uuid()),
+        limit + 1,
+    )
+    .fetch_all(state.read_db())
+    .await
+    .map_err(failed)?;
+    let (executions, next_cursor) = pagination::finish_page(rows, limit, |e| pagination::Cursor {
+        created_at: e.updated_at.to_rfc3339(),
+        id: e.run_item_id.clone(),
+    });
+
+    let latest = sqlx::query!(
+        r#"
+        SELECT
+          MAX(rr.updated_at) FILTER (WHERE rr.status = 'ok') AS last_passed_at,
+          MAX(rr.updated_at) FILTER (WHERE rr.status = 'fail') AS last_failed_at
+        FROM run_results rr
+        JOIN run_items ri ON ri.id = rr.run_item_id
+        JOIN testcase_versions tv ON tv.id = ri.testcase_version_id
+        WHERE tv.testcase_id = $1
+        "#,
+        testcase_uuid,
+    )
+    .fetch_one(state.read_db())
+    .await
+    .map_err(failed)?;
+
+    Ok(Json(ExecutionsResponse {
+        executions,
+        last_passed_at: latest.last_passed_at,
+        last_failed_at: latest.last_failed_at,
+        next_cursor,
+    }))
+}
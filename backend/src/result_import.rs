@@ -0,0 +1,646 @@
+use axum::{
+    body::Bytes,
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::{Postgres, Transaction};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::{
+    allure,
+    attachments::{sanitize_file_name, AttachmentLimits},
+    authz::{self, AuthUser},
+    cucumber, db_errors, ensure_db_user_exists,
+    error::ApiError,
+    fetch_run_view, junit, parse_uuid,
+    permissions::Capability,
+    playwright, quotas, suites, webhooks, AppState, RunView,
+};
+
+pub const MAX_COMMENT_CHARS: usize = 4000;
+const MAX_TITLE_CHARS: usize = 240;
+
+#[derive(Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct ImportResultsQuery {
+    project_id: String,
+    title: Option<String>,
+    /// Suite for testcases that do not exist yet; defaults to the project's suite with the
+    /// format as its key (`junit`, `allure`, ...).
+    suite_id: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportResultsResponse {
+    run: RunView,
+    items: usize,
+    created_testcases: usize,
+    passed: usize,
+    failed: usize,
+    skipped: usize,
+    /// Step outcomes recorded against the steps of the testcase version.
+    steps: usize,
+    attachments: usize,
+    /// Attachments left out for their type or size.
+    skipped_attachments: usize,
+}
+
+/// Report format being imported. `format` is its path segment and the key of its import
+/// suite; `name` goes into the default run title, the suite name and the change note of
+/// created versions.
+pub struct ReportSource {
+    pub format: &'static str,
+    pub name: &'static str,
+    pub max_bytes: usize,
+    pub failed: ApiError,
+    pub rejected: ApiError,
+}
+
+pub struct ParsedReport {
+    pub cases: Vec<ImportedCase>,
+    /// Attachments left out for their type or size.
+    pub skipped_attachments: usize,
+}
+
+/// A test report format accepted by `import_report`. Parsing runs on a blocking thread.
+pub trait ReportParser: Send + Sync {
+    fn source(&self) -> &'static ReportSource;
+
+    fn parse(&self, body: Bytes, limits: &AttachmentLimits) -> Result<ParsedReport, ApiError>;
+}
+
+const PARSERS: &[&dyn ReportParser] = &[
+    &junit::JunitParser,
+    &allure::AllureParser,
+    &playwright::PlaywrightParser,
+    &cucumber::CucumberParser,
+];
+
+/// Body limit of the import route: the largest any format accepts.
+pub fn max_report_bytes() -> usize {
+    PARSERS
+        .iter()
+        .map(|parser| parser.source().max_bytes)
+        .max()
+        .unwrap_or_default()
+}
+
+pub struct ImportedCase {
+    pub key: String,
+    pub title: String,
+    /// Feature file of a Gherkin scenario; such cases are found by path and title first,
+    /// like `gherkin::import_gherkin` links them.
+    pub source_path: Option<String>,
+    pub status: &'static str,
+    pub comment: String,
+    pub elapsed_seconds: Option<i32>,
+    pub steps: Vec<ImportedStep>,
+    pub attachments: Vec<ImportedAttachment>,
+}
+
+pub struct ImportedStep {
+    pub action: String,
+    pub expected: String,
+    pub status: &'static str,
+    pub comment: String,
+}
+
+pub struct ImportedAttachment {
+    pub file_name: String,
+    pub mime_type: String,
+    pub data: Bytes,
+}
+
+pub struct ImportTarget {
+    project_id: Uuid,
+    suite_id: Option<Uuid>,
+    title: Option<String>,
+}
+
+pub fn clip(value: &str, max_chars: usize) -> String {
+    value.trim().chars().take(max_chars).collect()
+}
+
+/// Title of a testcase created from a report: the test name, or the key when the name is
+/// too short to be a title.
+pub fn case_title(name: &str, key: &str) -> String {
+    let title = if name.trim().chars().count() >= 2 {
+        name
+    } else {
+        key
+    };
+    clip(title, MAX_TITLE_CHARS)
+}
+
+/// Attachment embedded in a report, when uploads would accept its type and size. Names
+/// without an extension get one from the MIME type.
+pub fn embedded_attachment(
+    limits: &AttachmentLimits,
+    name: &str,
+    mime_type: &str,
+    data: Vec<u8>,
+) -> Option<ImportedAttachment> {
+    let mime_type = match mime_type.trim() {
+        "" => "application/octet-stream",
+        mime_type => mime_type,
+    };
+    if data.is_empty() || data.len() > limits.max_bytes || !limits.allows(mime_type) {
+        return None;
+    }
+    let name = match name.trim() {
+        "" => "attachment",
+        name => name,
+    };
+    let extension = match mime_type.split('/').nth(1).unwrap_or_default() {
+        "plain" => "txt",
+        "jpeg" => "jpg",
+        "octet-stream" => "",
+        subtype if subtype.chars().all(|c| c.is_ascii_alphanumeric()) => subtype,
+        _ => "",
+    };
+    let file_name = if name.contains('.') || extension.is_empty() {
+        name.to_string()
+    } else {
+        format!("{name}.{extension}")
+    };
+    Some(ImportedAttachment {
+        file_name: sanitize_file_name(&file_name),
+        mime_type: mime_type.to_string(),
+        data: Bytes::from(data),
+    })
+}
+
+/// Status of two outcomes merged into one: a failure outweighs everything, a passed
+/// outcome nothing.
+pub fn worse(a: &'static str, b: &'static str) -> &'static str {
+    let rank = |status: &str| match status {
+        "fail" => 5,
+        "blocked" => 4,
+        "retest" => 3,
+        "na" => 2,
+        "skipped" => 1,
+        _ => 0,
+    };
+    if rank(b) > rank(a) {
+        b
+    } else {
+        a
+    }
+}
+
+/// Checks access before the report is read. Import creates the run and, when needed, the
+/// testcases it references.
+async fn authorize(
+    state: &AppState,
+    actor_id: &str,
+    query: ImportResultsQuery,
+) -> Result<ImportTarget, ApiError> {
+    let project_id = parse_uuid(&query.project_id, ApiError::InvalidProjectId)?;
+    let suite_id = match query.suite_id.as_deref() {
+        Some(v) if !v.trim().is_empty() => Some(parse_uuid(v, ApiError::InvalidSuiteId)?),
+        _ => None,
+    };
+    for capability in [Capability::RunCreate, Capability::LibraryEdit] {
+        authz::require_capability(state, &project_id.to_string(), actor_id, capability).await?;
+    }
+    if let Some(suite_id) = suite_id {
+        suites::ensure_suite_in_project(state, suite_id, project_id).await?;
+    }
+    let title = query
+        .title
+        .as_deref()
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .map(str::to_string);
+    Ok(ImportTarget {
+        project_id,
+        suite_id,
+        title,
+    })
+}
+
+/// Existing testcase with this source or key anywhere in the project, or a new one in
+/// `suite_id` whose first version takes the steps of the report.
+/// Returns the latest version id and whether the testcase was created.
+async fn resolve_testcase_version(
+    tx: &mut Transaction<'_, Postgres>,
+    project_id: Uuid,
+    suite_id: Uuid,
+    case: &ImportedCase,
+    change_note: &str,
+    actor_id: Uuid,
+) -> Result<(Uuid, bool), sqlx::Error> {
+    let existing = sqlx::query_scalar!(
+        r#"
+        SELECT tc.id
+        FROM testcases tc
+        JOIN test_suites s ON s.id = tc.suite_id
+        WHERE s.project_id = $1 AND tc.is_archived = FALSE
+          AND (tc.key = $2 OR (tc.source_path = $3 AND tc.source_name = $4))
+        ORDER BY (tc.source_path = $3 AND tc.source_name = $4) IS TRUE DESC, tc.created_at ASC
+        LIMIT 1
+        "#,
+        project_id,
+        &case.key,
+        case.source_path.as_deref(),
+        &case.title,
+    )
+    .fetch_optional(&mut **tx)
+    .await?;
+    let (testcase_id, created) = match existing {
+        Some(id) => (id, false),
+        None => {
+            let id = sqlx::query_scalar!(
+                r#"
+                INSERT INTO testcases (
+                  suite_id, key, title, source_path, source_name, created_by_user_id,
+                  updated_by_user_id
+                )
+                VALUES ($1, $2, $3, $4, CASE WHEN $4::text IS NOT NULL THEN $3 END, $5, $5)
+                RETURNING id
+                "#,
+                suite_id,
+                &case.key,
+                &case.title,
+                case.source_path.as_deref(),
+                actor_id,
+            )
+            .fetch_one(&mut **tx)
+            .await?;
+            (id, true)
+        }
+    };
+
+    let version = sqlx::query_scalar!(
+        r#"
+        SELECT id FROM testcase_versions
+        WHERE testcase_id = $1
+        ORDER BY version_number DESC
+        LIMIT 1
+        "#,
+        testcase_id,
+    )
+    .fetch_optional(&mut **tx)
+    .await?;
+    let version_id = match version {
+        Some(id) => id,
+        None => {
+            let steps: Vec<&str> = case.steps.iter().map(|s| s.action.as_str()).collect();
+            let expected: Vec<&str> = case.steps.iter().map(|s| s.expected.as_str()).collect();
+            sqlx::query_scalar!(
+                r#"
+                INSERT INTO testcase_versions (
+                  testcase_id, version_number, summary, steps_json, expected_json, change_note,
+                  created_by_user_id
+                )
+                VALUES ($1, 1, $2, $3, $4, $5, $6)
+                RETURNING id
+                "#,
+                testcase_id,
+                &case.title,
+                json!(steps),
+                json!(expected),
+                change_note,
+                actor_id,
+            )
+            .fetch_one(&mut **tx)
+            .await?
+        }
+    };
+    Ok((version_id, created))
+}
+
+/// Pairs report steps with the version's steps by action text, in order. Steps the
+/// version does not have are returned as lines for the result comment.
+fn match_steps<'a>(
+    version_steps: &[(Uuid, String)],
+    steps: &'a [ImportedStep],
+) -> (Vec<(Uuid, &'a ImportedStep)>, Vec<String>) {
+    let mut used = vec![false; version_steps.len()];
+    let mut matched = Vec::new();
+    let mut unmatched = Vec::new();
+    for step in steps {
+        let found = version_steps
+            .iter()
+            .enumerate()
+            .position(|(idx, (_, action))| !used[idx] && action.trim() == step.action.trim());
+        match found {
+            Some(idx) => {
+                used[idx] = true;
+                matched.push((version_steps[idx].0, step));
+            }
+            None if step.comment.is_empty() => {
+                unmatched.push(format!("[{}] {}", step.status, step.action))
+            }
+            None => unmatched.push(format!(
+                "[{}] {}\n{}",
+                step.status, step.action, step.comment
+            )),
+        }
+    }
+    (matched, unmatched)
+}
+
+struct StoredAttachment {
+    case_idx: usize,
+    id: Uuid,
+    storage_key: String,
+    file_name: String,
+    mime_type: String,
+    size_bytes: i64,
+}
+
+/// Creates an `in_progress` run with one item and result per case, their step outcomes
+/// and attachments. Attachments are stored first and removed again when the transaction
+/// fails; the project quota covers them as a whole.
+async fn create_run(
+    state: &AppState,
+    actor_id: &str,
+    target: ImportTarget,
+    source: &ReportSource,
+    cases: Vec<ImportedCase>,
+    skipped_attachments: usize,
+) -> Result<ImportResultsResponse, ApiError> {
+    ensure_db_user_exists(state, actor_id).await?;
+    let actor_uuid = parse_uuid(actor_id, ApiError::InvalidUserId)?;
+    let ImportTarget {
+        project_id,
+        suite_id,
+        title,
+    } = target;
+    let title = title.unwrap_or_else(|| {
+        format!(
+            "{} import {}",
+            source.name,
+            chrono::Utc::now().format("%Y-%m-%d %H:%M")
+        )
+    });
+
+    let run_id = Uuid::new_v4();
+    let item_ids: Vec<Uuid> = cases.iter().map(|_| Uuid::new_v4()).collect();
+    let total_bytes: i64 = cases
+        .iter()
+        .flat_map(|c| &c.attachments)
+        .map(|a| a.data.len() as i64)
+        .sum();
+    if total_bytes > 0 {
+        quotas::check(state, project_id, total_bytes).await?;
+    }
+    let mut stored: Vec<StoredAttachment> = Vec::new();
+    for (case_idx, case) in cases.iter().enumerate() {
+        for attachment in &case.attachments {
+            let id = Uuid::new_v4();
+            let storage_key = format!("runs/{run_id}/{}/{id}", item_ids[case_idx]);
+            if state
+                .storage
+                .put(&storage_key, attachment.data.clone())
+                .await
+                .is_err()
+            {
+                for done in &stored {
+                    let _ = state.storage.delete(&done.storage_key).await;
+                }
+                return Err(ApiError::AttachmentStoreFailed);
+            }
+            stored.push(StoredAttachment {
+                case_idx,
+                id,
+                storage_key,
+                file_name: attachment.file_name.clone(),
+                mime_type: attachment.mime_type.clone(),
+                size_bytes: attachment.data.len() as i64,
+            });
+        }
+    }
+
+    let persisted: Result<(usize, usize), ApiError> = async {
+        let import_failed = |err| db_errors::map(err, source.failed);
+        let mut tx = state.db.begin().await.map_err(import_failed)?;
+        let suite_id = match suite_id {
+            Some(id) => id,
+            None => sqlx::query_scalar!(
+                r#"
+                INSERT INTO test_suites (project_id, key, name, created_by_user_id, updated_by_user_id)
+                VALUES ($1, $2, $3, $4, $4)
+                ON CONFLICT (project_id, key) DO UPDATE SET key = EXCLUDED.key
+                RETURNING id
+                "#,
+                project_id,
+                source.format,
+                format!("{} import", source.name),
+                actor_uuid,
+            )
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(import_failed)?,
+        };
+        sqlx::query!(
+            r#"
+            INSERT INTO runs (id, project_id, title, status, executed_by_user_id, started_at)
+            VALUES ($1, $2, $3, 'in_progress', $4, NOW())
+            "#,
+            run_id,
+            project_id,
+            &title,
+            actor_uuid,
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|err| db_errors::map(err, source.rejected))?;
+        if total_bytes > 0 {
+            quotas::reserve(&mut tx, state, project_id, total_bytes).await?;
+        }
+
+        let change_note = format!("Imported from {}", source.name);
+        let mut created_testcases = 0;
+        let mut step_results = 0;
+        for (idx, case) in cases.iter().enumerate() {
+            let (version_id, created) = resolve_testcase_version(
+                &mut tx,
+                project_id,
+                suite_id,
+                case,
+                &change_note,
+                actor_uuid,
+            )
+            .await
+            .map_err(import_failed)?;
+            if created {
+                created_testcases += 1;
+            }
+            let version_steps: Vec<(Uuid, String)> = if case.steps.is_empty() {
+                Vec::new()
+            } else {
+                sqlx::query!(
+                    r#"
+                    SELECT id, action FROM testcase_steps
+                    WHERE testcase_version_id = $1
+                    ORDER BY position ASC
+                    "#,
+                    version_id,
+                )
+                .fetch_all(&mut *tx)
+                .await
+                .map_err(import_failed)?
+                .into_iter()
+                .map(|r| (r.id, r.action))
+                .collect()
+            };
+            let (matched, unmatched) = match_steps(&version_steps, &case.steps);
+            let comment = if unmatched.is_empty() {
+                case.comment.clone()
+            } else {
+                let mut parts = vec![case.comment.as_str()];
+                parts.retain(|c| !c.is_empty());
+                parts.extend(unmatched.iter().map(String::as_str));
+                clip(&parts.join("\n\n"), MAX_COMMENT_CHARS)
+            };
+            let result_id = sqlx::query_scalar!(
+                r#"
+                WITH item AS (
+                  INSERT INTO run_items (id, run_id, testcase_version_id, position, is_required)
+                  VALUES ($1, $2, $3, $4, TRUE)
+                  RETURNING id
+                )
+                INSERT INTO run_results (run_item_id, status, comment, elapsed_seconds, updated_by_user_id)
+                SELECT id, $5::text::result_status, $6, $7, $8 FROM item
+                RETURNING id
+                "#,
+                item_ids[idx],
+                run_id,
+                version_id,
+                idx as i32 + 1,
+                case.status,
+                &comment,
+                case.elapsed_seconds,
+                actor_uuid,
+            )
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(import_failed)?;
+            for (step_id, step) in &matched {
+                sqlx::query!(
+                    r#"
+                    INSERT INTO run_result_steps (run_item_id, step_id, status, comment, updated_by_user_id)
+                    VALUES ($1, $2, $3::text::result_status, $4, $5)
+                    "#,
+                    item_ids[idx],
+                    step_id,
+                    step.status,
+                    &step.comment,
+                    actor_uuid,
+                )
+                .execute(&mut *tx)
+                .await
+                .map_err(import_failed)?;
+            }
+            step_results += matched.len();
+            for attachment in stored.iter().filter(|a| a.case_idx == idx) {
+                sqlx::query!(
+                    r#"
+                    INSERT INTO attachments (
+                      id, run_id, run_result_id, storage_provider, storage_key,
+                      file_name, mime_type, size_bytes, uploaded_by_user_id
+                    )
+                    VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+                    "#,
+                    attachment.id,
+                    run_id,
+                    result_id,
+                    state.storage.provider(),
+                    &attachment.storage_key,
+                    &attachment.file_name,
+                    &attachment.mime_type,
+                    attachment.size_bytes,
+                    actor_uuid,
+                )
+                .execute(&mut *tx)
+                .await
+                .map_err(import_failed)?;
+            }
+        }
+        tx.commit().await.map_err(import_failed)?;
+        Ok((created_testcases, step_results))
+    }
+    .await;
+    let (created_testcases, steps) = match persisted {
+        Ok(counts) => counts,
+        Err(err) => {
+            for attachment in &stored {
+                let _ = state.storage.delete(&attachment.storage_key).await;
+            }
+            return Err(err);
+        }
+    };
+
+    let run = fetch_run_view(&state.db, run_id)
+        .await?
+        .ok_or(ApiError::RunCreatedNotFound)?;
+    webhooks::emit(state, project_id, "run.created", json!({ "run": &run })).await;
+
+    let count = |status: &str| cases.iter().filter(|c| c.status == status).count();
+    Ok(ImportResultsResponse {
+        run,
+        items: cases.len(),
+        created_testcases,
+        passed: count("ok"),
+        failed: count("fail"),
+        skipped: count("skipped"),
+        steps,
+        attachments: stored.len(),
+        skipped_attachments,
+    })
+}
+
+/// Imports a test report as a new run. `format` is `junit` (JUnit XML), `allure` (zipped
+/// `allure-results`), `playwright` (JSON reporter output) or `cucumber` (NDJSON messages).
+#[utoipa::path(
+    post,
+    path = "/api/v2/runs/import/{format}",
+    tag = "runs",
+    params(("format" = String, Path), ImportResultsQuery),
+    request_body(content(
+        (String = "application/xml"),
+        (Vec<u8> = "application/zip"),
+        (String = "application/json"),
+        (String = "application/x-ndjson")
+    )),
+    responses((status = 201, body = ImportResultsResponse))
+)]
+pub async fn import_report(
+    State(state): State<AppState>,
+    AuthUser(actor_id): AuthUser,
+    Path(format): Path<String>,
+    Query(query): Query<ImportResultsQuery>,
+    body: Bytes,
+) -> Result<(StatusCode, Json<ImportResultsResponse>), ApiError> {
+    let parser = *PARSERS
+        .iter()
+        .find(|parser| parser.source().format == format)
+        .ok_or(ApiError::UnsupportedReportFormat)?;
+    let source = parser.source();
+    if body.len() > source.max_bytes {
+        return Err(ApiError::PayloadTooLarge);
+    }
+    let target = authorize(&state, &actor_id, query).await?;
+    let limits = state.attachment_limits.clone();
+    let parsed = tokio::task::spawn_blocking(move || parser.parse(body, &limits))
+        .await
+        .map_err(|_| source.failed)??;
+    let response = create_run(
+        &state,
+        &actor_id,
+        target,
+        source,
+        parsed.cases,
+        parsed.skipped_attachments,
+    )
+    .await?;
+    Ok((StatusCode::CREATED, Json(response)))
+}
@@ -0,0 +1,589 @@
+use std::time::Duration;
+
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use chrono::{DateTime, Months, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sqlx::Row;
+use tracing::warn;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::{
+    audit,
+    authz::ProjectRole,
+    ensure_db_user_exists,
+    error::ApiError,
+    jobs::{self, JobContext},
+    parse_uuid,
+    permissions::Capability,
+    report, AppState,
+};
+
+/// How often projects due for a purge are looked for.
+const POLL_INTERVAL: Duration = Duration::from_secs(60 * 60);
+/// A project is purged at most once in this many hours.
+const PURGE_INTERVAL_HOURS: i32 = 24;
+/// Runs or attachments deleted in one transaction.
+const PURGE_BATCH: i64 = 100;
+/// Runs and attachments listed by the preview; the totals always cover everything.
+const PREVIEW_LIMIT: i64 = 500;
+const MAX_RUN_MONTHS: i32 = 1200;
+const MAX_ATTACHMENT_DAYS: i32 = 36500;
+
+/// Retention settings of a project; `null` keeps the data forever.
+#[derive(Clone, Copy, Default)]
+struct Policy {
+    run_months: Option<i32>,
+    attachment_days: Option<i32>,
+}
+
+impl Policy {
+    fn validate(self) -> Result<Self, ApiError> {
+        let months_ok = self
+            .run_months
+            .is_none_or(|m| (1..=MAX_RUN_MONTHS).contains(&m));
+        let days_ok = self
+            .attachment_days
+            .is_none_or(|d| (1..=MAX_ATTACHMENT_DAYS).contains(&d));
+        if !months_ok || !days_ok {
+            return Err(ApiError::InvalidRetentionPolicy);
+        }
+        Ok(self)
+    }
+
+    /// Runs finished before the first cutoff and attachments uploaded before the second
+    /// one are purged.
+    fn cutoffs(self, now: DateTime<Utc>) -> (Option<DateTime<Utc>>, Option<DateTime<Utc>>) {
+        let runs = self
+            .run_months
+            .and_then(|m| now.checked_sub_months(Months::new(m as u32)));
+        let attachments = self
+            .attachment_days
+            .and_then(|d| now.checked_sub_signed(chrono::Duration::days(d.into())));
+        (runs, attachments)
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RetentionPolicyView {
+    project_id: String,
+    /// Finished runs older than this many months are deleted; `null` keeps them.
+    run_retention_months: Option<i32>,
+    /// Attachments older than this many days are deleted; `null` keeps them.
+    attachment_retention_days: Option<i32>,
+    /// `null` until the policy is set for the first time.
+    updated_at: Option<DateTime<Utc>>,
+    last_purged_at: Option<DateTime<Utc>>,
+    /// The last `retention_purge` job; its result holds the deleted counts.
+    last_job_id: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SetRetentionPolicyRequest {
+    /// 1 to 1200, or `null` to keep runs forever.
+    run_retention_months: Option<i32>,
+    /// 1 to 36500, or `null` to keep attachments forever.
+    attachment_retention_days: Option<i32>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct RetentionPreviewQuery {
+    /// Tries another value instead of the saved one.
+    run_retention_months: Option<i32>,
+    /// Tries another value instead of the saved one.
+    attachment_retention_days: Option<i32>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PurgedRunView {
+    id: String,
+    title: String,
+    finished_at: DateTime<Utc>,
+    /// Attachments deleted together with the run.
+    attachment_count: i64,
+    attachment_bytes: i64,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PurgedAttachmentView {
+    id: String,
+    run_id: String,
+    run_status: String,
+    file_name: String,
+    size_bytes: i64,
+    created_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RetentionPreview {
+    run_retention_months: Option<i32>,
+    attachment_retention_days: Option<i32>,
+    /// Finished runs before this moment would be deleted.
+    run_cutoff: Option<DateTime<Utc>>,
+    /// Attachments uploaded before this moment would be deleted.
+    attachment_cutoff: Option<DateTime<Utc>>,
+    /// Oldest first, at most 500.
+    runs: Vec<PurgedRunView>,
+    /// Attachments of runs that stay, oldest first, at most 500.
+    attachments: Vec<PurgedAttachmentView>,
+    /// `true` when either list was cut; the counts below are complete.
+    truncated: bool,
+    run_count: i64,
+    /// Every attachment that would go, including those of deleted runs.
+    attachment_count: i64,
+    bytes_freed: i64,
+}
+
+async fn load_view(state: &AppState, project_id: Uuid) -> Result<RetentionPolicyView, ApiError> {
+    let row = sqlx::query!(
+        r#"
+        SELECT run_retention_months, attachment_retention_days, updated_at, last_purged_at,
+               last_job_id::text AS last_job_id
+        FROM project_retention_policies
+        WHERE project_id = $1
+        "#,
+        project_id,
+    )
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|_| ApiError::RetentionReadFailed)?;
+    Ok(match row {
+        Some(row) => RetentionPolicyView {
+            project_id: project_id.to_string(),
+            run_retention_months: row.run_retention_months,
+            attachment_retention_days: row.attachment_retention_days,
+            updated_at: Some(row.updated_at),
+            last_purged_at: row.last_purged_at,
+            last_job_id: row.last_job_id,
+        },
+        None => RetentionPolicyView {
+            project_id: project_id.to_string(),
+            run_retention_months: None,
+            attachment_retention_days: None,
+            updated_at: None,
+            last_purged_at: None,
+            last_job_id: None,
+        },
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v2/projects/{project_id}/retention",
+    tag = "projects",
+    params(("project_id" = String, Path)),
+    responses((status = 200, body = RetentionPolicyView))
+)]
+pub async fn get_retention(
+    State(state): State<AppState>,
+    access: ProjectRole,
+) -> Result<Json<RetentionPolicyView>, ApiError> {
+    access.require(Capability::ProjectManage)?;
+    Ok(Json(load_view(&state, access.project_id).await?))
+}
+
+/// Replaces the project's retention policy. The purge runs in the background about once a
+/// day; locked runs and their attachments are always kept.
+#[utoipa::path(
+    put,
+    path = "/api/v2/projects/{project_id}/retention",
+    tag = "projects",
+    params(("project_id" = String, Path)),
+    request_body = SetRetentionPolicyRequest,
+    responses((status = 200, body = RetentionPolicyView))
+)]
+pub async fn set_retention(
+    State(state): State<AppState>,
+    access: ProjectRole,
+    Json(payload): Json<SetRetentionPolicyRequest>,
+) -> Result<Json<RetentionPolicyView>, ApiError> {
+    access.require(Capability::ProjectManage)?;
+    let policy = Policy {
+        run_months: payload.run_retention_months,
+        attachment_days: payload.attachment_retention_days,
+    }
+    .validate()?;
+    ensure_db_user_exists(&state, &access.user_id).await?;
+    let actor_uuid = parse_uuid(&access.user_id, ApiError::InvalidUserId)?;
+    let project_id = access.project_id;
+    let before = load_view(&state, project_id).await?;
+
+    let save_failed = |_| ApiError::RetentionSaveFailed;
+    let mut tx = state.db.begin().await.map_err(save_failed)?;
+    sqlx::query!(
+        r#"
+        INSERT INTO project_retention_policies
+          (project_id, run_retention_months, attachment_retention_days, set_by_user_id)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (project_id) DO UPDATE SET
+          run_retention_months = EXCLUDED.run_retention_months,
+          attachment_retention_days = EXCLUDED.attachment_retention_days,
+          set_by_user_id = EXCLUDED.set_by_user_id,
+          updated_at = NOW()
+        "#,
+        project_id,
+        policy.run_months,
+        policy.attachment_days,
+        actor_uuid,
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(save_failed)?;
+    audit::record(
+        &mut *tx,
+        audit::AuditEntry {
+            actor_user_id: Some(actor_uuid),
+            action: "update",
+            entity_type: "retention_policy",
+            entity_id: Some(project_id),
+            project_id: Some(project_id),
+            run_id: None,
+            before: Some(json!({
+                "runRetentionMonths": before.run_retention_months,
+                "attachmentRetentionDays": before.attachment_retention_days,
+            })),
+            after: Some(json!({
+                "runRetentionMonths": policy.run_months,
+                "attachmentRetentionDays": policy.attachment_days,
+            })),
+        },
+    )
+    .await
+    .map_err(save_failed)?;
+    tx.commit().await.map_err(save_failed)?;
+    Ok(Json(load_view(&state, project_id).await?))
+}
+
+/// Dry run of the purge: what the saved policy, or the values given in the query, would
+/// delete now.
+#[utoipa::path(
+    get,
+    path = "/api/v2/projects/{project_id}/retention/preview",
+    tag = "projects",
+    params(("project_id" = String, Path), RetentionPreviewQuery),
+    responses((status = 200, body = RetentionPreview))
+)]
+pub async fn preview_retention(
+    State(state): State<AppState>,
+    access: ProjectRole,
+    Query(query): Query<RetentionPreviewQuery>,
+) -> Result<Json<RetentionPreview>, ApiError> {
+    access.require(Capability::ProjectManage)?;
+    let project_id = access.project_id;
+    let saved = load_view(&state, project_id).await?;
+    let policy = Policy {
+        run_months: query.run_retention_months.or(saved.run_retention_months),
+        attachment_days: query
+            .attachment_retention_days
+            .or(saved.attachment_retention_days),
+    }
+    .validate()?;
+    let (run_cutoff, attachment_cutoff) = policy.cutoffs(Utc::now());
+    let read_failed = |_| ApiError::RetentionReadFailed;
+
+    let mut runs = sqlx::query_as!(
+        PurgedRunView,
+        r#"
+        SELECT
+          r.id::text AS "id!",
+          r.title,
+          r.finished_at AS "finished_at!",
+          COUNT(a.id) AS "attachment_count!",
+          COALESCE(SUM(a.size_bytes), 0)::bigint AS "attachment_bytes!"
+        FROM runs r
+        LEFT JOIN attachments a ON a.run_id = r.id
+        WHERE r.project_id = $1 AND r.status = 'done' AND r.finished_at < $2
+        GROUP BY r.id
+        ORDER BY r.finished_at ASC, r.id ASC
+        LIMIT $3
+        "#,
+        project_id,
+        run_cutoff,
+        PREVIEW_LIMIT + 1,
+    )
+    .fetch_all(&state.db)
+    .await
+    .map_err(read_failed)?;
+    let mut attachments = sqlx::query_as!(
+        PurgedAttachmentView,
+        r#"
+        SELECT
+          a.id::text AS "id!",
+          a.run_id::text AS "run_id!",
+          r.status::text AS "run_status!",
+          a.file_name,
+          a.size_bytes,
+          a.created_at
+        FROM attachments a
+        JOIN runs r ON r.id = a.run_id
+        WHERE r.project_id = $1
+          AND r.status <> 'locked'
+          AND a.created_at < $2
+          AND (r.status = 'done' AND r.finished_at < $3) IS NOT TRUE
+        ORDER BY a.created_at ASC, a.id ASC
+        LIMIT $4
+        "#,
+        project_id,
+        attachment_cutoff,
+        run_cutoff,
+        PREVIEW_LIMIT + 1,
+    )
+    .fetch_all(&state.db)
+    .await
+    .map_err(read_failed)?;
+    let totals = sqlx::query!(
+        r#"
+        SELECT
+          COUNT(DISTINCT r.id) FILTER (WHERE r.status = 'done' AND r.finished_at < $2)
+            AS "run_count!",
+          COUNT(a.id) FILTER (
+            WHERE (r.status = 'done' AND r.finished_at < $2)
+               OR (r.status <> 'locked' AND a.created_at < $3)
+          ) AS "attachment_count!",
+          COALESCE(SUM(a.size_bytes) FILTER (
+            WHERE (r.status = 'done' AND r.finished_at < $2)
+               OR (r.status <> 'locked' AND a.created_at < $3)
+          ), 0)::bigint AS "bytes_freed!"
+        FROM runs r
+        LEFT JOIN attachments a ON a.run_id = r.id
+        WHERE r.project_id = $1
+        "#,
+        project_id,
+        run_cutoff,
+        attachment_cutoff,
+    )
+    .fetch_one(&state.db)
+    .await
+    .map_err(read_failed)?;
+
+    let truncated = runs.len() as i64 > PREVIEW_LIMIT || attachments.len() as i64 > PREVIEW_LIMIT;
+    runs.truncate(PREVIEW_LIMIT as usize);
+    attachments.truncate(PREVIEW_LIMIT as usize);
+    Ok(Json(RetentionPreview {
+        run_retention_months: policy.run_months,
+        attachment_retention_days: policy.attachment_days,
+        run_cutoff,
+        attachment_cutoff,
+        runs,
+        attachments,
+        truncated,
+        run_count: totals.run_count,
+        attachment_count: totals.attachment_count,
+        bytes_freed: totals.bytes_freed,
+    }))
+}
+
+/// Queues a `retention_purge` job for every project with a policy that has not been
+/// purged for [`PURGE_INTERVAL_HOURS`]. Every API instance runs it; `SKIP LOCKED` keeps
+/// them from queuing the same project twice.
+pub fn spawn_scheduler(state: AppState) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(err) = queue_due(&state).await {
+                warn!(error = %err, "retention purge pass failed");
+            }
+        }
+    });
+}
+
+async fn queue_due(state: &AppState) -> Result<(), sqlx::Error> {
+    loop {
+        let mut tx = state.db.begin().await?;
+        let Some(project_id) = sqlx::query_scalar!(
+            r#"
+            SELECT project_id
+            FROM project_retention_policies
+            WHERE (run_retention_months IS NOT NULL OR attachment_retention_days IS NOT NULL)
+              AND (last_purged_at IS NULL
+                   OR last_purged_at < NOW() - make_interval(hours => $1))
+            ORDER BY last_purged_at ASC NULLS FIRST
+            LIMIT 1
+            FOR UPDATE SKIP LOCKED
+            "#,
+            PURGE_INTERVAL_HOURS,
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        else {
+            return Ok(());
+        };
+        let job_id = jobs::enqueue(
+            &mut *tx,
+            jobs::NewJob {
+                kind: jobs::JobKind::RetentionPurge,
+                project_id: Some(project_id),
+                created_by_user_id: None,
+                payload: json!({ "projectId": project_id }),
+            },
+        )
+        .await?;
+        sqlx::query!(
+            r#"
+            UPDATE project_retention_policies
+            SET last_purged_at = NOW(), last_job_id = $2
+            WHERE project_id = $1
+            "#,
+            project_id,
+            job_id,
+        )
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        state.jobs.wake();
+    }
+}
+
+/// Job handler of the purge: deletes expired finished runs, then expired attachments of
+/// the runs that stay, in batches. Locked runs are skipped, also when one is locked while
+/// the purge waits for it. Stored files (attachments and cached reports of deleted runs) are
+/// deleted once their rows are gone.
+pub async fn run_purge_job(
+    state: &AppState,
+    _ctx: &JobContext,
+    payload: Value,
+) -> anyhow::Result<Value> {
+    let project_id: Uuid = serde_json::from_value(payload["projectId"].clone())?;
+    let Some(row) = sqlx::query!(
+        r#"
+        SELECT run_retention_months, attachment_retention_days
+        FROM project_retention_policies
+        WHERE project_id = $1
+        "#,
+        project_id,
+    )
+    .fetch_optional(&state.db)
+    .await?
+    else {
+        // The project was deleted after the purge was queued.
+        return Ok(Value::Null);
+    };
+    let policy = Policy {
+        run_months: row.run_retention_months,
+        attachment_days: row.attachment_retention_days,
+    };
+    let (run_cutoff, attachment_cutoff) = policy.cutoffs(Utc::now());
+
+    let mut runs_deleted = 0_i64;
+    let mut attachments_deleted = 0_i64;
+    let mut bytes_freed = 0_i64;
+    while run_cutoff.is_some() {
+        let mut tx = state.db.begin().await?;
+        // `FOR UPDATE` waits for a concurrent lock and then re-checks the status.
+        let run_ids = sqlx::query_scalar!(
+            r#"
+            SELECT id
+            FROM runs
+            WHERE project_id = $1 AND status = 'done' AND finished_at < $2
+            ORDER BY finished_at ASC
+            LIMIT $3
+            FOR UPDATE
+            "#,
+            project_id,
+            run_cutoff,
+            PURGE_BATCH,
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        if run_ids.is_empty() {
+            break;
+        }
+        let files =
+            sqlx::query("SELECT storage_key, size_bytes FROM attachments WHERE run_id = ANY($1)")
+                .bind(&run_ids)
+                .fetch_all(&mut *tx)
+                .await?;
+        sqlx::query!("DELETE FROM runs WHERE id = ANY($1)", &run_ids)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        runs_deleted += run_ids.len() as i64;
+        attachments_deleted += files.len() as i64;
+        bytes_freed += delete_files(state, &files).await;
+        for run_id in &run_ids {
+            if let Err(err) = report::drop_cached(state, *run_id).await {
+                warn!("cached report of purged run {run_id} not removed from storage: {err:#}");
+            }
+        }
+    }
+    while attachment_cutoff.is_some() {
+        let mut tx = state.db.begin().await?;
+        let files = sqlx::query(
+            r#"
+            SELECT a.id, a.storage_key, a.size_bytes
+            FROM attachments a
+            JOIN runs r ON r.id = a.run_id
+            WHERE r.project_id = $1 AND r.status <> 'locked' AND a.created_at < $2
+            ORDER BY a.created_at ASC
+            LIMIT $3
+            FOR UPDATE OF a FOR SHARE OF r
+            "#,
+        )
+        .bind(project_id)
+        .bind(attachment_cutoff)
+        .bind(PURGE_BATCH)
+        .fetch_all(&mut *tx)
+        .await?;
+        if files.is_empty() {
+            break;
+        }
+        let ids: Vec<Uuid> = files.iter().map(|f| f.get("id")).collect();
+        sqlx::query!("DELETE FROM attachments WHERE id = ANY($1)", &ids)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        attachments_deleted += files.len() as i64;
+        bytes_freed += delete_files(state, &files).await;
+    }
+
+    let summary = json!({
+        "runsDeleted": runs_deleted,
+        "attachmentsDeleted": attachments_deleted,
+        "bytesFreed": bytes_freed,
+    });
+    if runs_deleted > 0 || attachments_deleted > 0 {
+        audit::record(
+            &state.db,
+            audit::AuditEntry {
+                actor_user_id: None,
+                action: "delete",
+                entity_type: "retention_purge",
+                entity_id: Some(project_id),
+                project_id: Some(project_id),
+                run_id: None,
+                before: Some(json!({
+                    "runRetentionMonths": policy.run_months,
+                    "attachmentRetentionDays": policy.attachment_days,
+                })),
+                after: Some(summary.clone()),
+            },
+        )
+        .await?;
+    }
+    Ok(summary)
+}
+
+/// Deletes stored files whose rows are already gone; returns the bytes they took.
+async fn delete_files(state: &AppState, files: &[sqlx::postgres::PgRow]) -> i64 {
+    let mut bytes = 0;
+    for file in files {
+        let key: String = file.get("storage_key");
+        if let Err(err) = state.storage.delete(&key).await {
+            warn!("purged attachment {key} removed from DB but not from storage: {err:#}");
+        }
+        bytes += file.get::<i64, _>("size_bytes");
+    }
+    bytes
+}
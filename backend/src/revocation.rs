@@ -0,0 +1,172 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use serde::Deserialize;
+use sqlx::{PgPool, Row};
+use tracing::warn;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{
+    ensure_db_user_exists,
+    error::ApiError,
+    jwt::{Claims, JwtKeys, TokenKind, REFRESH_TOKEN_TTL_SECS},
+    AppState,
+};
+
+const SYNC_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Persists the revocation and applies it to this process immediately. Returns `false` when
+/// the token had already been revoked.
+pub async fn revoke(db: &PgPool, keys: &JwtKeys, claims: &Claims) -> Result<bool, sqlx::Error> {
+    let jti = Uuid::parse_str(&claims.jti).unwrap_or_default();
+    let user_id = Uuid::parse_str(&claims.sub).unwrap_or_default();
+    let kind = match claims.kind {
+        TokenKind::Access => "access",
+        TokenKind::Refresh => "refresh",
+    };
+    let inserted = sqlx::query(
+        r#"
+        INSERT INTO revoked_tokens (jti, user_id, token_kind, expires_at)
+        VALUES ($1, $2, $3, to_timestamp($4))
+        ON CONFLICT (jti) DO NOTHING
+        RETURNING jti
+        "#,
+    )
+    .bind(jti)
+    .bind(user_id)
+    .bind(kind)
+    .bind(claims.exp as f64)
+    .fetch_optional(db)
+    .await?;
+    keys.mark_revoked(&claims.jti, claims.exp);
+    Ok(inserted.is_some())
+}
+
+/// Invalidates every token issued to the user so far (deactivation, forced password reset).
+pub async fn end_sessions(db: &PgPool, keys: &JwtKeys, user_id: Uuid) -> Result<(), sqlx::Error> {
+    let ended_at: i64 = sqlx::query_scalar(
+        r#"
+        UPDATE users
+        SET tokens_valid_after = NOW()
+        WHERE id = $1
+        RETURNING EXTRACT(EPOCH FROM tokens_valid_after)::bigint
+        "#,
+    )
+    .bind(user_id)
+    .fetch_one(db)
+    .await?;
+    keys.end_sessions(&user_id.to_string(), ended_at);
+    Ok(())
+}
+
+/// Loads the revocations that are still relevant into `keys`.
+pub async fn load(db: &PgPool, keys: &JwtKeys) -> Result<(), sqlx::Error> {
+    let rows = sqlx::query(
+        r#"
+        SELECT jti::text AS jti, EXTRACT(EPOCH FROM expires_at)::bigint AS exp
+        FROM revoked_tokens
+        WHERE expires_at > NOW()
+        "#,
+    )
+    .fetch_all(db)
+    .await?;
+    let entries: HashMap<String, i64> = rows
+        .iter()
+        .map(|row| (row.get::<String, _>("jti"), row.get::<i64, _>("exp")))
+        .collect();
+    keys.reset_revoked(entries);
+
+    // Older cutoffs only cover tokens that have expired anyway.
+    let rows = sqlx::query(
+        r#"
+        SELECT id::text AS id, EXTRACT(EPOCH FROM tokens_valid_after)::bigint AS ended_at
+        FROM users
+        WHERE tokens_valid_after > NOW() - make_interval(secs => $1)
+        "#,
+    )
+    .bind(REFRESH_TOKEN_TTL_SECS as f64)
+    .fetch_all(db)
+    .await?;
+    keys.reset_ended_sessions(
+        rows.iter()
+            .map(|row| (row.get::<String, _>("id"), row.get::<i64, _>("ended_at")))
+            .collect(),
+    );
+    Ok(())
+}
+
+/// Periodically drops expired rows and reloads the set, which also picks up tokens revoked
+/// and sessions ended by other API instances.
+pub fn spawn_sync(db: PgPool, keys: Arc<JwtKeys>) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(SYNC_INTERVAL);
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            if let Err(err) = sqlx::query("DELETE FROM revoked_tokens WHERE expires_at <= NOW()")
+                .execute(&db)
+                .await
+            {
+                warn!(error = %err, "failed to prune revoked tokens");
+            }
+            if let Err(err) = load(&db, &keys).await {
+                warn!(error = %err, "failed to reload revoked tokens");
+            }
+        }
+    });
+}
+
+#[derive(Deserialize, ToSchema, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct LogoutRequest {
+    /// Refresh token of the same session; revoked together with the access token.
+    refresh_token: Option<String>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/auth/logout",
+    tag = "auth",
+    request_body = LogoutRequest,
+    responses((status = 204, description = "Токены отозваны."))
+)]
+pub async fn logout(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    payload: Option<Json<LogoutRequest>>,
+) -> Result<StatusCode, ApiError> {
+    let token = headers
+        .get("authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(str::trim)
+        .ok_or(ApiError::Unauthorized)?;
+    let access = state
+        .jwt
+        .verify(token, TokenKind::Access)
+        .ok_or(ApiError::InvalidToken)?;
+    let refresh = match payload.and_then(|Json(p)| p.refresh_token) {
+        Some(refresh_token) => {
+            let claims = state
+                .jwt
+                .verify(refresh_token.trim(), TokenKind::Refresh)
+                .filter(|claims| claims.sub == access.sub)
+                .ok_or(ApiError::InvalidRefreshToken)?;
+            Some(claims)
+        }
+        None => None,
+    };
+
+    ensure_db_user_exists(&state, &access.sub).await?;
+    for claims in std::iter::once(&access).chain(refresh.as_ref()) {
+        revoke(&state.db, &state.jwt, claims)
+            .await
+            .map_err(|_| ApiError::LogoutFailed)?;
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
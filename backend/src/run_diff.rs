@@ -0,0 +1,186 @@
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{
+    authz::{self, AuthUser},
+    error::ApiError,
+    parse_uuid,
+    permissions::Capability,
+    AppState,
+};
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct RunDiffQuery {
+    /// The baseline run, usually the previous release; it must be in the same project.
+    against: Option<String>,
+}
+
+/// One test case in the comparison. When a run holds several versions of the case, the
+/// newest one is compared. Items without a result count as `na`.
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RunDiffEntry {
+    testcase_id: String,
+    key: String,
+    title: String,
+    /// Whether the item is required in the run, or in the baseline for removed cases.
+    is_required: bool,
+    run_item_id: Option<String>,
+    version_number: Option<i32>,
+    status: Option<String>,
+    against_run_item_id: Option<String>,
+    against_version_number: Option<i32>,
+    against_status: Option<String>,
+}
+
+#[derive(Serialize, Default, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RunDiffSummary {
+    regressions: i64,
+    /// Regressions of required items; any of them usually blocks a release.
+    required_regressions: i64,
+    fixes: i64,
+    changed: i64,
+    added: i64,
+    removed: i64,
+    unchanged: i64,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RunDiffResponse {
+    run_id: String,
+    against_run_id: String,
+    summary: RunDiffSummary,
+    /// `ok` in the baseline, `fail` now.
+    regressions: Vec<RunDiffEntry>,
+    /// `fail` in the baseline, `ok` now.
+    fixes: Vec<RunDiffEntry>,
+    /// Any other status change, e.g. `ok` to `blocked`.
+    changed: Vec<RunDiffEntry>,
+    /// Cases only in this run.
+    added: Vec<RunDiffEntry>,
+    /// Cases only in the baseline.
+    removed: Vec<RunDiffEntry>,
+}
+
+/// Compares the run with a baseline run item by item, matching items by test case.
+#[utoipa::path(
+    get,
+    path = "/api/v2/runs/{run_id}/diff",
+    tag = "runs",
+    params(("run_id" = String, Path), RunDiffQuery),
+    responses((status = 200, body = RunDiffResponse))
+)]
+pub async fn diff_runs(
+    State(state): State<AppState>,
+    Path(run_id): Path<String>,
+    AuthUser(actor_id): AuthUser,
+    Query(query): Query<RunDiffQuery>,
+) -> Result<Json<RunDiffResponse>, ApiError> {
+    let run_uuid = parse_uuid(&run_id, ApiError::InvalidRunId)?;
+    let against_uuid = parse_uuid(
+        query.against.as_deref().unwrap_or_default(),
+        ApiError::InvalidRunId,
+    )?;
+    let project_id =
+        authz::require_run_capability(&state, run_uuid, &actor_id, Capability::ProjectRead).await?;
+    let against_project_id =
+        authz::require_run_capability(&state, against_uuid, &actor_id, Capability::ProjectRead)
+            .await?;
+    if project_id != against_project_id {
+        return Err(ApiError::RunDiffProjectMismatch);
+    }
+
+    let rows = sqlx::query_as!(
+        RunDiffEntry,
+        r#"
+        WITH current AS (
+          SELECT DISTINCT ON (tv.testcase_id)
+            tv.testcase_id, ri.id, ri.is_required, tv.version_number,
+            COALESCE(rr.status::text, 'na') AS status
+          FROM run_items ri
+          JOIN testcase_versions tv ON tv.id = ri.testcase_version_id
+          LEFT JOIN run_results rr ON rr.run_item_id = ri.id
+          WHERE ri.run_id = $1
+          ORDER BY tv.testcase_id, tv.version_number DESC
+        ),
+        baseline AS (
+          SELECT DISTINCT ON (tv.testcase_id)
+            tv.testcase_id, ri.id, ri.is_required, tv.version_number,
+            COALESCE(rr.status::text, 'na') AS status
+          FROM run_items ri
+          JOIN testcase_versions tv ON tv.id = ri.testcase_version_id
+          LEFT JOIN run_results rr ON rr.run_item_id = ri.id
+          WHERE ri.run_id = $2
+          ORDER BY tv.testcase_id, tv.version_number DESC
+        )
+        SELECT
+          tc.id::text AS "testcase_id!",
+          tc.key,
+          tc.title,
+          COALESCE(c.is_required, b.is_required) AS "is_required!",
+          c.id::text AS "run_item_id?",
+          c.version_number AS "version_number?",
+          c.status AS "status?",
+          b.id::text AS "against_run_item_id?",
+          b.version_number AS "against_version_number?",
+          b.status AS "against_status?"
+        FROM current c
+        FULL JOIN baseline b ON b.testcase_id = c.testcase_id
+        JOIN testcases tc ON tc.id = COALESCE(c.testcase_id, b.testcase_id)
+        ORDER BY tc.key ASC
+        "#,
+        run_uuid,
+        against_uuid,
+    )
+    .fetch_all(state.read_db())
+    .await
+    .map_err(|_| ApiError::RunDiffFailed)?;
+
+    let mut response = RunDiffResponse {
+        run_id: run_uuid.to_string(),
+        against_run_id: against_uuid.to_string(),
+        summary: RunDiffSummary::default(),
+        regressions: Vec::new(),
+        fixes: Vec::new(),
+        changed: Vec::new(),
+        added: Vec::new(),
+        removed: Vec::new(),
+    };
+    for entry in rows {
+        let summary = &mut response.summary;
+        match (entry.status.as_deref(), entry.against_status.as_deref()) {
+            (Some(_), None) => {
+                summary.added += 1;
+                response.added.push(entry);
+            }
+            (None, _) => {
+                summary.removed += 1;
+                response.removed.push(entry);
+            }
+            (Some("fail"), Some("ok")) => {
+                summary.regressions += 1;
+                if entry.is_required {
+                    summary.required_regressions += 1;
+                }
+                response.regressions.push(entry);
+            }
+            (Some("ok"), Some("fail")) => {
+                summary.fixes += 1;
+                response.fixes.push(entry);
+            }
+            (Some(now), Some(before)) if now != before => {
+                summary.changed += 1;
+                response.changed.push(entry);
+            }
+            _ => summary.unchanged += 1,
+        }
+    }
+    Ok(Json(response))
+}
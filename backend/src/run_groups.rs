@@ -0,0 +1,188 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+use sqlx::PgPool;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{error::ApiError, RunItemView};
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GroupBy {
+    Suite,
+    Tag,
+    Assignee,
+}
+
+impl GroupBy {
+    pub fn parse(raw: Option<&str>) -> Result<Option<Self>, ApiError> {
+        match raw.map(str::trim) {
+            None | Some("") => Ok(None),
+            Some("suite") => Ok(Some(Self::Suite)),
+            Some("tag") => Ok(Some(Self::Tag)),
+            Some("assignee") => Ok(Some(Self::Assignee)),
+            Some(_) => Err(ApiError::InvalidGroupBy),
+        }
+    }
+}
+
+/// A section of the run checklist; items keep their run order inside it.
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RunItemGroupView {
+    /// Suite id, tag name or assignee user id; `null` for the items without one.
+    key: Option<String>,
+    /// Suite name, tag name or assignee display name.
+    title: Option<String>,
+    /// Suite names from the root down to this suite; empty unless `groupBy=suite`.
+    path: Vec<String>,
+    item_ids: Vec<String>,
+    item_count: i64,
+    /// Items with a result other than `na`.
+    completed_count: i64,
+}
+
+/// Names and positions of a suite and its ancestors, root first.
+pub struct SuitePath {
+    pub names: Vec<String>,
+    positions: Vec<i32>,
+}
+
+/// Paths of the suites the run's items belong to, by suite id.
+pub async fn fetch_suite_paths(
+    db: &PgPool,
+    run_id: Uuid,
+) -> Result<HashMap<String, SuitePath>, ApiError> {
+    let rows = sqlx::query!(
+        r#"
+        WITH RECURSIVE chain AS (
+          SELECT s.id AS suite_id, s.parent_id, s.name, s.position, 0 AS depth
+          FROM test_suites s
+          WHERE s.id IN (
+            SELECT tc.suite_id
+            FROM run_items ri
+            JOIN testcase_versions tv ON tv.id = ri.testcase_version_id
+            JOIN testcases tc ON tc.id = tv.testcase_id
+            WHERE ri.run_id = $1
+          )
+          UNION ALL
+          SELECT c.suite_id, p.parent_id, p.name, p.position, c.depth + 1
+          FROM chain c
+          JOIN test_suites p ON p.id = c.parent_id
+        )
+        SELECT
+          suite_id::text AS "suite_id!",
+          array_agg(name ORDER BY depth DESC) AS "names!",
+          array_agg(position ORDER BY depth DESC) AS "positions!"
+        FROM chain
+        GROUP BY suite_id
+        "#,
+        run_id,
+    )
+    .fetch_all(db)
+    .await
+    .map_err(|_| ApiError::RunItemsReadFailed)?;
+    Ok(rows
+        .into_iter()
+        .map(|r| {
+            (
+                r.suite_id,
+                SuitePath {
+                    names: r.names,
+                    positions: r.positions,
+                },
+            )
+        })
+        .collect())
+}
+
+struct Group {
+    key: Option<String>,
+    title: Option<String>,
+    path: Vec<String>,
+    items: Vec<usize>,
+}
+
+/// Splits the items (already in run order) into sections. Suites follow the library tree;
+/// tags and assignees are sorted by name, the section without a key goes last. With
+/// `groupBy=tag` an item is listed under each of its tags.
+pub fn group(
+    by: GroupBy,
+    items: &[RunItemView],
+    suites: &HashMap<String, SuitePath>,
+) -> Vec<RunItemGroupView> {
+    let mut groups: Vec<Group> = Vec::new();
+    let mut index: HashMap<Option<String>, usize> = HashMap::new();
+    let mut add = |key: Option<String>, title: Option<String>, path: Vec<String>, item: usize| {
+        let slot = *index.entry(key.clone()).or_insert_with(|| {
+            groups.push(Group {
+                key,
+                title,
+                path,
+                items: Vec::new(),
+            });
+            groups.len() - 1
+        });
+        groups[slot].items.push(item);
+    };
+    for (i, item) in items.iter().enumerate() {
+        match by {
+            GroupBy::Suite => {
+                let path = suites
+                    .get(&item.suite_id)
+                    .map(|p| p.names.clone())
+                    .unwrap_or_default();
+                add(Some(item.suite_id.clone()), path.last().cloned(), path, i);
+            }
+            GroupBy::Tag if item.tags.is_empty() => add(None, None, Vec::new(), i),
+            GroupBy::Tag => {
+                for tag in &item.tags {
+                    add(Some(tag.to_lowercase()), Some(tag.clone()), Vec::new(), i);
+                }
+            }
+            GroupBy::Assignee => add(
+                item.assignee_user_id.clone(),
+                item.assignee_display_name.clone(),
+                Vec::new(),
+                i,
+            ),
+        }
+    }
+
+    match by {
+        GroupBy::Suite => groups.sort_by(|a, b| {
+            let sort_key = |g: &Group| {
+                g.key
+                    .as_ref()
+                    .and_then(|id| suites.get(id))
+                    .map(|p| (p.positions.clone(), p.names.clone()))
+            };
+            sort_key(a).cmp(&sort_key(b))
+        }),
+        GroupBy::Tag | GroupBy::Assignee => groups.sort_by(|a, b| {
+            let sort_key = |g: &Group| {
+                (
+                    g.key.is_none(),
+                    g.title.as_deref().map(str::to_lowercase),
+                    g.key.clone(),
+                )
+            };
+            sort_key(a).cmp(&sort_key(b))
+        }),
+    }
+
+    groups
+        .into_iter()
+        .map(|g| {
+            let completed = g.items.iter().filter(|&&i| items[i].status != "na").count();
+            RunItemGroupView {
+                item_count: g.items.len() as i64,
+                completed_count: completed as i64,
+                item_ids: g.items.iter().map(|&i| items[i].id.clone()).collect(),
+                key: g.key,
+                title: g.title,
+                path: g.path,
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,567 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use sqlx::{Executor, PgConnection, PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+use crate::{db_errors, error::ApiError, RunItemPositionView, RunItemStepView};
+
+/// How a writer holds the run row for the rest of its transaction.
+#[derive(Clone, Copy)]
+pub enum RunLock {
+    /// `FOR UPDATE`: composition and status changes, one at a time per run.
+    Exclusive,
+    /// `FOR SHARE`: result edits run side by side but wait for, and block, an exclusive
+    /// holder, so no result lands in a run that is being locked.
+    Shared,
+}
+
+struct RunRow {
+    status: String,
+    project_id: Uuid,
+}
+
+/// A transaction that holds a row lock on one run. Every multi-statement write to a run
+/// goes through it, so the statements commit together and see the status they checked.
+pub struct LockedRun {
+    tx: Transaction<'static, Postgres>,
+    pub id: Uuid,
+    pub status: String,
+    pub project_id: Uuid,
+}
+
+impl LockedRun {
+    /// `failed` is reported when the transaction cannot be started; a missing run is
+    /// `RunNotFound`.
+    pub async fn begin(
+        db: &PgPool,
+        run_id: Uuid,
+        lock: RunLock,
+        failed: ApiError,
+    ) -> Result<Self, ApiError> {
+        let mut tx = db.begin().await.map_err(|_| failed)?;
+        let row = match lock {
+            RunLock::Exclusive => sqlx::query_as!(
+                RunRow,
+                r#"SELECT status::text AS "status!", project_id FROM runs WHERE id = $1 FOR UPDATE"#,
+                run_id,
+            )
+            .fetch_optional(&mut *tx)
+            .await,
+            RunLock::Shared => sqlx::query_as!(
+                RunRow,
+                r#"SELECT status::text AS "status!", project_id FROM runs WHERE id = $1 FOR SHARE"#,
+                run_id,
+            )
+            .fetch_optional(&mut *tx)
+            .await,
+        }
+        .map_err(|_| ApiError::RunReadFailed)?
+        .ok_or(ApiError::RunNotFound)?;
+        Ok(Self {
+            tx,
+            id: run_id,
+            status: row.status,
+            project_id: row.project_id,
+        })
+    }
+
+    pub fn ensure_unlocked(&self, err: ApiError) -> Result<(), ApiError> {
+        if self.status == "locked" {
+            return Err(err);
+        }
+        Ok(())
+    }
+
+    pub fn conn(&mut self) -> &mut PgConnection {
+        &mut self.tx
+    }
+
+    /// Starts a part of the transaction that can be undone alone with
+    /// [`rollback_to_savepoint`](Self::rollback_to_savepoint), e.g. one entry of a batch.
+    pub async fn savepoint(&mut self, failed: ApiError) -> Result<(), ApiError> {
+        self.execute_raw("SAVEPOINT batch_entry", failed).await
+    }
+
+    pub async fn release_savepoint(&mut self, failed: ApiError) -> Result<(), ApiError> {
+        self.execute_raw("RELEASE SAVEPOINT batch_entry", failed)
+            .await
+    }
+
+    /// Undoes everything since [`savepoint`](Self::savepoint) and clears a failed statement,
+    /// so the transaction stays usable.
+    pub async fn rollback_to_savepoint(&mut self, failed: ApiError) -> Result<(), ApiError> {
+        self.execute_raw("ROLLBACK TO SAVEPOINT batch_entry", failed)
+            .await
+    }
+
+    async fn execute_raw(&mut self, sql: &'static str, failed: ApiError) -> Result<(), ApiError> {
+        self.tx.execute(sql).await.map(|_| ()).map_err(|_| failed)
+    }
+
+    pub async fn commit(self, failed: ApiError) -> Result<(), ApiError> {
+        self.tx.commit().await.map_err(|_| failed)
+    }
+}
+
+pub struct InsertedItem {
+    pub id: Uuid,
+    pub testcase_version_id: Uuid,
+    pub position: i32,
+}
+
+/// Adds items with their placeholder `na` results. `position` places a single item
+/// explicitly; without it the items are appended after the current last one. Versions of
+/// archived test cases are refused. `rejected` is reported when the insert fails, e.g. on
+/// an unknown or duplicate version.
+pub async fn insert_items(
+    run: &mut LockedRun,
+    version_ids: &[Uuid],
+    position: Option<i32>,
+    is_required: bool,
+    actor_uuid: Uuid,
+    rejected: ApiError,
+) -> Result<Vec<InsertedItem>, ApiError> {
+    let run_id = run.id;
+    let archived = sqlx::query_scalar!(
+        r#"
+        SELECT EXISTS (
+          SELECT 1
+          FROM testcase_versions tv
+          JOIN testcases tc ON tc.id = tv.testcase_id
+          WHERE tv.id = ANY($1) AND tc.is_archived
+        ) AS "archived!"
+        "#,
+        version_ids,
+    )
+    .fetch_one(run.conn())
+    .await
+    .map_err(|err| db_errors::map(err, rejected))?;
+    if archived {
+        return Err(ApiError::TestcaseArchived);
+    }
+    let items = sqlx::query_as!(
+        InsertedItem,
+        r#"
+        INSERT INTO run_items (run_id, testcase_version_id, position, is_required)
+        SELECT
+          $1,
+          v.id,
+          COALESCE(
+            $3::int,
+            (SELECT COALESCE(MAX(position), -1) FROM run_items WHERE run_id = $1) + v.ord::int
+          ),
+          $4
+        FROM UNNEST($2::uuid[]) WITH ORDINALITY AS v(id, ord)
+        RETURNING id, testcase_version_id, position
+        "#,
+        run_id,
+        version_ids,
+        position,
+        is_required,
+    )
+    .fetch_all(run.conn())
+    .await
+    .map_err(|err| db_errors::map(err, rejected))?;
+
+    let item_ids: Vec<Uuid> = items.iter().map(|item| item.id).collect();
+    sqlx::query!(
+        r#"
+        INSERT INTO run_results (run_item_id, status, comment, updated_by_user_id)
+        SELECT id, 'na', '', $2 FROM UNNEST($1::uuid[]) AS t(id)
+        ON CONFLICT (run_item_id) DO NOTHING
+        "#,
+        &item_ids,
+        actor_uuid,
+    )
+    .execute(run.conn())
+    .await
+    .map_err(|err| db_errors::map(err, ApiError::ResultCreateFailed))?;
+
+    Ok(items)
+}
+
+pub async fn item_positions(run: &mut LockedRun) -> Result<Vec<RunItemPositionView>, ApiError> {
+    let run_id = run.id;
+    sqlx::query_as!(
+        RunItemPositionView,
+        r#"
+        SELECT id::text AS "id!", position
+        FROM run_items
+        WHERE run_id = $1
+        ORDER BY position ASC, created_at ASC
+        "#,
+        run_id,
+    )
+    .fetch_all(run.conn())
+    .await
+    .map_err(|_| ApiError::RunItemsReadFailed)
+}
+
+/// Renumbers positions to `0..n` keeping the order.
+pub async fn compact_positions(run: &mut LockedRun) -> Result<(), ApiError> {
+    let run_id = run.id;
+    sqlx::query!(
+        r#"
+        UPDATE run_items ri
+        SET position = ordered.new_position
+        FROM (
+          SELECT id, (ROW_NUMBER() OVER (ORDER BY position ASC, created_at ASC))::int - 1 AS new_position
+          FROM run_items
+          WHERE run_id = $1
+        ) ordered
+        WHERE ri.id = ordered.id AND ri.position <> ordered.new_position
+        "#,
+        run_id,
+    )
+    .execute(run.conn())
+    .await
+    .map_err(|_| ApiError::RunItemsRenumberFailed)?;
+    Ok(())
+}
+
+pub struct ResultChange<'a> {
+    pub run_item_id: Uuid,
+    pub status: &'a str,
+    pub fail_reason_code: Option<&'a str>,
+    pub comment: &'a str,
+    /// `None` keeps the stored value.
+    pub elapsed_seconds: Option<i32>,
+    pub actor_uuid: Uuid,
+}
+
+/// Writes the result of an item, bumping its version; returns the new `updated_at` and
+/// version.
+pub async fn upsert_result(
+    run: &mut LockedRun,
+    change: ResultChange<'_>,
+) -> Result<(DateTime<Utc>, i64), ApiError> {
+    let row = sqlx::query!(
+        r#"
+        INSERT INTO run_results (
+          run_item_id, status, fail_reason_code, comment, elapsed_seconds, updated_by_user_id,
+          updated_at
+        )
+        VALUES ($1, $2::text::result_status, $3, $4, $6, $5, NOW())
+        ON CONFLICT (run_item_id)
+        DO UPDATE SET
+          status = EXCLUDED.status,
+          fail_reason_code = EXCLUDED.fail_reason_code,
+          comment = EXCLUDED.comment,
+          elapsed_seconds = COALESCE(EXCLUDED.elapsed_seconds, run_results.elapsed_seconds),
+          updated_by_user_id = EXCLUDED.updated_by_user_id,
+          updated_at = NOW(),
+          version = run_results.version + 1
+        RETURNING updated_at, version::bigint AS "version!"
+        "#,
+        change.run_item_id,
+        change.status,
+        change.fail_reason_code,
+        change.comment,
+        change.actor_uuid,
+        change.elapsed_seconds,
+    )
+    .fetch_one(run.conn())
+    .await
+    .map_err(|err| db_errors::map(err, ApiError::ResultRejected))?;
+    Ok((row.updated_at, row.version))
+}
+
+pub struct StepResultChange {
+    pub step_id: Uuid,
+    pub status: &'static str,
+    pub comment: String,
+}
+
+/// Records step outcomes of an item. Every step must belong to the item's testcase version.
+pub async fn upsert_step_results(
+    run: &mut LockedRun,
+    run_item_id: Uuid,
+    changes: &[StepResultChange],
+    actor_uuid: Uuid,
+) -> Result<(), ApiError> {
+    if changes.is_empty() {
+        return Ok(());
+    }
+    let step_ids: Vec<Uuid> = changes.iter().map(|c| c.step_id).collect();
+    let statuses: Vec<&str> = changes.iter().map(|c| c.status).collect();
+    let comments: Vec<&str> = changes.iter().map(|c| c.comment.as_str()).collect();
+    let written = sqlx::query!(
+        r#"
+        INSERT INTO run_result_steps (
+          run_item_id, step_id, status, comment, updated_by_user_id, updated_at
+        )
+        SELECT ri.id, ts.id, v.status::result_status, v.comment, $5, NOW()
+        FROM UNNEST($2::uuid[], $3::text[], $4::text[]) AS v(step_id, status, comment)
+        JOIN run_items ri ON ri.id = $1
+        JOIN testcase_steps ts
+          ON ts.id = v.step_id AND ts.testcase_version_id = ri.testcase_version_id
+        ON CONFLICT (run_item_id, step_id)
+        DO UPDATE SET
+          status = EXCLUDED.status,
+          comment = EXCLUDED.comment,
+          updated_by_user_id = EXCLUDED.updated_by_user_id,
+          updated_at = NOW()
+        "#,
+        run_item_id,
+        &step_ids,
+        &statuses as &[&str],
+        &comments as &[&str],
+        actor_uuid,
+    )
+    .execute(run.conn())
+    .await
+    .map_err(|err| db_errors::map(err, ApiError::ResultRejected))?;
+    if written.rows_affected() != changes.len() as u64 {
+        return Err(ApiError::StepNotInRunItem);
+    }
+    Ok(())
+}
+
+/// Steps of every item of the run with their outcomes, keyed by run item id.
+pub async fn fetch_run_steps(
+    db: &PgPool,
+    run_id: Uuid,
+) -> Result<HashMap<String, Vec<RunItemStepView>>, ApiError> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT
+          ri.id::text AS "run_item_id!",
+          ts.id::text AS "step_id!",
+          ts.position,
+          ts.action,
+          ts.expected_result,
+          rs.status::text AS status,
+          COALESCE(rs.comment, '') AS "comment!",
+          rs.updated_at AS "updated_at?"
+        FROM run_items ri
+        JOIN testcase_steps ts ON ts.testcase_version_id = ri.testcase_version_id
+        LEFT JOIN run_result_steps rs ON rs.run_item_id = ri.id AND rs.step_id = ts.id
+        WHERE ri.run_id = $1
+        ORDER BY ts.position ASC
+        "#,
+        run_id,
+    )
+    .fetch_all(db)
+    .await
+    .map_err(|_| ApiError::RunItemsReadFailed)?;
+    let mut grouped: HashMap<String, Vec<RunItemStepView>> = HashMap::new();
+    for row in rows {
+        grouped
+            .entry(row.run_item_id)
+            .or_default()
+            .push(RunItemStepView {
+                step_id: row.step_id,
+                position: row.position,
+                action: row.action,
+                expected_result: row.expected_result,
+                status: row.status,
+                comment: row.comment,
+                updated_at: row.updated_at,
+            });
+    }
+    Ok(grouped)
+}
+
+/// Steps of one item with their outcomes, read inside the run transaction.
+pub async fn item_steps(
+    run: &mut LockedRun,
+    run_item_id: Uuid,
+) -> Result<Vec<RunItemStepView>, ApiError> {
+    sqlx::query_as!(
+        RunItemStepView,
+        r#"
+        SELECT
+          ts.id::text AS "step_id!",
+          ts.position,
+          ts.action,
+          ts.expected_result,
+          rs.status::text AS status,
+          COALESCE(rs.comment, '') AS "comment!",
+          rs.updated_at AS "updated_at?"
+        FROM run_items ri
+        JOIN testcase_steps ts ON ts.testcase_version_id = ri.testcase_version_id
+        LEFT JOIN run_result_steps rs ON rs.run_item_id = ri.id AND rs.step_id = ts.id
+        WHERE ri.id = $1
+        ORDER BY ts.position ASC
+        "#,
+        run_item_id,
+    )
+    .fetch_all(run.conn())
+    .await
+    .map_err(|_| ApiError::RunItemsReadFailed)
+}
+
+/// Prerequisites of the item that have not passed yet, in run order. Their item rows are
+/// share-locked, so a prerequisite cannot change its result until this transaction ends.
+pub async fn unmet_dependencies(
+    run: &mut LockedRun,
+    run_item_id: Uuid,
+) -> Result<Vec<String>, ApiError> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT ri.id::text AS "id!", rr.status::text AS status
+        FROM run_item_dependencies d
+        JOIN run_items ri ON ri.id = d.depends_on_run_item_id
+        LEFT JOIN run_results rr ON rr.run_item_id = ri.id
+        WHERE d.run_item_id = $1
+        ORDER BY ri.position ASC, ri.created_at ASC
+        FOR SHARE OF ri
+        "#,
+        run_item_id,
+    )
+    .fetch_all(run.conn())
+    .await
+    .map_err(|_| ApiError::RunItemDependenciesReadFailed)?;
+    Ok(rows
+        .into_iter()
+        .filter(|r| r.status.as_deref() != Some("ok"))
+        .map(|r| r.id)
+        .collect())
+}
+
+/// Definition of done for `done`/`locked`: the run has L0 tests and each has a result.
+pub async fn validate_dod_for_close(run: &mut LockedRun) -> Result<(), ApiError> {
+    let run_id = run.id;
+    let l0_count = sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*) AS "count!"
+        FROM run_items ri
+        JOIN testcase_versions tv ON tv.id = ri.testcase_version_id
+        JOIN testcases tc ON tc.id = tv.testcase_id
+        JOIN testcase_tags tt ON tt.testcase_id = tc.id
+        JOIN tags t ON t.id = tt.tag_id
+        WHERE ri.run_id = $1
+          AND lower(t.name::text) = 'l0'
+        "#,
+        run_id,
+    )
+    .fetch_one(run.conn())
+    .await
+    .map_err(|_| ApiError::L0CoverageCheckFailed)?;
+
+    if l0_count == 0 {
+        return Err(ApiError::RunMissingL0);
+    }
+
+    let unresolved_l0_count = sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*) AS "count!"
+        FROM run_items ri
+        JOIN testcase_versions tv ON tv.id = ri.testcase_version_id
+        JOIN testcases tc ON tc.id = tv.testcase_id
+        JOIN testcase_tags tt ON tt.testcase_id = tc.id
+        JOIN tags t ON t.id = tt.tag_id
+        LEFT JOIN run_results rr ON rr.run_item_id = ri.id
+        WHERE ri.run_id = $1
+          AND lower(t.name::text) = 'l0'
+          AND rr.run_item_id IS NULL
+        "#,
+        run_id,
+    )
+    .fetch_one(run.conn())
+    .await
+    .map_err(|_| ApiError::L0ResultsCheckFailed)?;
+
+    if unresolved_l0_count > 0 {
+        return Err(ApiError::RunL0Incomplete);
+    }
+
+    Ok(())
+}
+
+/// Moves the run to `next`, stamping `started_at`/`finished_at`/`locked_at` on the way.
+pub async fn set_status(run: &mut LockedRun, next: &str) -> Result<(), ApiError> {
+    let run_id = run.id;
+    let updated = match next {
+        "draft" => {
+            sqlx::query!(
+                r#"UPDATE runs SET status = 'draft', updated_at = NOW() WHERE id = $1"#,
+                run_id,
+            )
+            .execute(run.conn())
+            .await
+        }
+        "in_progress" => {
+            sqlx::query!(
+                r#"
+                UPDATE runs
+                SET status = 'in_progress',
+                    started_at = COALESCE(started_at, NOW()),
+                    updated_at = NOW()
+                WHERE id = $1
+                "#,
+                run_id,
+            )
+            .execute(run.conn())
+            .await
+        }
+        "done" => {
+            sqlx::query!(
+                r#"
+                UPDATE runs
+                SET status = 'done',
+                    started_at = COALESCE(started_at, NOW()),
+                    finished_at = COALESCE(finished_at, NOW()),
+                    updated_at = NOW()
+                WHERE id = $1
+                "#,
+                run_id,
+            )
+            .execute(run.conn())
+            .await
+        }
+        "locked" => {
+            sqlx::query!(
+                r#"
+                UPDATE runs
+                SET status = 'locked',
+                    started_at = COALESCE(started_at, NOW()),
+                    finished_at = COALESCE(finished_at, NOW()),
+                    locked_at = NOW(),
+                    updated_at = NOW()
+                WHERE id = $1
+                "#,
+                run_id,
+            )
+            .execute(run.conn())
+            .await
+        }
+        _ => return Err(ApiError::CorruptRunStatus),
+    };
+    updated.map_err(|_| ApiError::RunStatusUpdateFailed)?;
+    run.status = next.to_string();
+    Ok(())
+}
+
+/// When and by whom a run was locked.
+pub struct RunLockInfo {
+    pub locked_at: Option<DateTime<Utc>>,
+    pub locked_by_user_id: Option<String>,
+}
+
+/// Returns a `locked` run to `done`, clearing the lock. Returns the lock that was cleared.
+pub async fn unlock(run: &mut LockedRun) -> Result<RunLockInfo, ApiError> {
+    let run_id = run.id;
+    let lock = sqlx::query_as!(
+        RunLockInfo,
+        r#"
+        UPDATE runs AS r
+        SET status = 'done',
+            locked_at = NULL,
+            locked_by_user_id = NULL,
+            updated_at = NOW()
+        FROM runs AS previous
+        WHERE r.id = $1 AND previous.id = r.id
+        RETURNING
+          previous.locked_at,
+          previous.locked_by_user_id::text AS locked_by_user_id
+        "#,
+        run_id,
+    )
+    .fetch_one(run.conn())
+    .await
+    .map_err(|_| ApiError::RunStatusUpdateFailed)?;
+    run.status = "done".to_string();
+    Ok(lock)
+}
@@ -0,0 +1,529 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use sqlx::Row;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::{
+    assets, audit,
+    authz::{self, ProjectRole},
+    custom_fields, ensure_db_user_exists,
+    error::ApiError,
+    parse_run_status, parse_uuid,
+    permissions::Capability,
+    suites, AppState,
+};
+
+/// The list endpoint a filter belongs to.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Target {
+    Runs,
+    Testcases,
+}
+
+impl Target {
+    fn parse(input: &str) -> Result<Self, ApiError> {
+        match input.trim() {
+            "runs" => Ok(Self::Runs),
+            "testcases" => Ok(Self::Testcases),
+            _ => Err(ApiError::InvalidSavedFilterTarget),
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Runs => "runs",
+            Self::Testcases => "testcases",
+        }
+    }
+
+    /// Query parameters a filter may store; paging (`limit`, `cursor`) is never saved.
+    fn params(self) -> &'static [&'static str] {
+        match self {
+            Self::Runs => &[
+                "status",
+                "assetId",
+                "assetVersion",
+                "executedByUserId",
+                "customFields",
+            ],
+            Self::Testcases => &["suiteId", "customFields"],
+        }
+    }
+}
+
+/// A saved filter resolved for a list request.
+pub struct SavedFilter {
+    pub project_id: Uuid,
+    params: Map<String, Value>,
+}
+
+impl SavedFilter {
+    pub fn param(&self, key: &str) -> Option<String> {
+        self.params
+            .get(key)
+            .and_then(Value::as_str)
+            .map(str::to_string)
+    }
+}
+
+/// Loads a filter for `?filterId=`: it must target the list, be the actor's own or shared,
+/// and its project must be readable by the actor.
+pub async fn load(
+    state: &AppState,
+    filter_id: &str,
+    actor_id: &str,
+    target: Target,
+) -> Result<SavedFilter, ApiError> {
+    let filter_uuid = parse_uuid(filter_id, ApiError::InvalidSavedFilterId)?;
+    let actor_uuid = parse_uuid(actor_id, ApiError::InvalidUserId)?;
+    let row = sqlx::query(
+        r#"
+        SELECT project_id, params
+        FROM saved_filters
+        WHERE id = $1 AND target = $2 AND (owner_user_id = $3 OR is_shared)
+        "#,
+    )
+    .bind(filter_uuid)
+    .bind(target.as_str())
+    .bind(actor_uuid)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|_| ApiError::SavedFiltersReadFailed)?
+    .ok_or(ApiError::SavedFilterNotFound)?;
+    let project_id: Uuid = row.get("project_id");
+    authz::require_capability(
+        state,
+        &project_id.to_string(),
+        actor_id,
+        Capability::ProjectRead,
+    )
+    .await?;
+    Ok(SavedFilter {
+        project_id,
+        params: row
+            .get::<Value, _>("params")
+            .as_object()
+            .cloned()
+            .unwrap_or_default(),
+    })
+}
+
+/// Keeps the non-empty parameters and checks them the way the list endpoint would, so a
+/// filter that saves fine also applies fine (until the project's fields or suites change).
+async fn validate_params(
+    state: &AppState,
+    project_id: Uuid,
+    target: Target,
+    params: Map<String, Value>,
+) -> Result<Map<String, Value>, ApiError> {
+    let mut valid = Map::new();
+    for (key, value) in params {
+        if !target.params().contains(&key.as_str()) {
+            return Err(ApiError::InvalidSavedFilterParams);
+        }
+        let value = match value {
+            Value::String(s) => s.trim().to_string(),
+            Value::Null => continue,
+            _ => return Err(ApiError::InvalidSavedFilterParams),
+        };
+        if value.is_empty() {
+            continue;
+        }
+        match key.as_str() {
+            "status" => {
+                parse_run_status(&value)?;
+            }
+            "assetId" => {
+                let asset_id = parse_uuid(&value, ApiError::InvalidAssetId)?;
+                assets::ensure_asset_in_project(state, asset_id, project_id).await?;
+            }
+            "executedByUserId" => {
+                parse_uuid(&value, ApiError::InvalidExecutorUserId)?;
+            }
+            "suiteId" => {
+                let suite_id = parse_uuid(&value, ApiError::InvalidSuiteIdParam)?;
+                suites::ensure_suite_in_project(state, suite_id, project_id).await?;
+            }
+            "customFields" => {
+                let entity = match target {
+                    Target::Runs => custom_fields::Entity::Run,
+                    Target::Testcases => custom_fields::Entity::Testcase,
+                };
+                let definitions = custom_fields::definitions(&state.db, project_id, entity).await?;
+                custom_fields::parse_filter(&definitions, &value)?;
+            }
+            _ => {}
+        }
+        valid.insert(key, Value::String(value));
+    }
+    Ok(valid)
+}
+
+fn normalize_name(name: &str) -> Result<String, ApiError> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().count() > 200 {
+        return Err(ApiError::InvalidSavedFilterName);
+    }
+    Ok(name.to_string())
+}
+
+#[derive(Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct ListSavedFiltersQuery {
+    /// `runs` or `testcases`; both when omitted.
+    target: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateSavedFilterRequest {
+    /// `runs` or `testcases`.
+    target: String,
+    name: String,
+    /// List query parameters as strings, e.g. `{"status":"in_progress"}`.
+    #[schema(value_type = Object)]
+    params: Map<String, Value>,
+    /// Visible to every project member; private to the author by default.
+    is_shared: Option<bool>,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateSavedFilterRequest {
+    name: Option<String>,
+    /// Replaces all stored parameters.
+    #[schema(value_type = Option<Object>)]
+    params: Option<Map<String, Value>>,
+    is_shared: Option<bool>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SavedFilterView {
+    id: String,
+    target: String,
+    name: String,
+    #[schema(value_type = Object)]
+    params: Value,
+    is_shared: bool,
+    owner_user_id: String,
+    created_at: String,
+    updated_at: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ListSavedFiltersResponse {
+    filters: Vec<SavedFilterView>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct DeleteSavedFilterResponse {
+    ok: bool,
+}
+
+const FILTER_SELECT: &str = r#"
+    SELECT id::text AS id, target, name, params, is_shared, owner_user_id::text AS owner_user_id,
+           created_at::text AS created_at, updated_at::text AS updated_at
+    FROM saved_filters
+"#;
+
+fn map_filter_row(r: &sqlx::postgres::PgRow) -> SavedFilterView {
+    SavedFilterView {
+        id: r.get("id"),
+        target: r.get("target"),
+        name: r.get("name"),
+        params: r.get("params"),
+        is_shared: r.get("is_shared"),
+        owner_user_id: r.get("owner_user_id"),
+        created_at: r.get("created_at"),
+        updated_at: r.get("updated_at"),
+    }
+}
+
+/// A filter of the project visible to the actor.
+async fn fetch_filter(
+    state: &AppState,
+    project_id: Uuid,
+    filter_id: Uuid,
+    actor_id: Uuid,
+) -> Result<Option<SavedFilterView>, ApiError> {
+    let sql = format!(
+        "{FILTER_SELECT} WHERE project_id = $1 AND id = $2 AND (owner_user_id = $3 OR is_shared)"
+    );
+    let row = sqlx::query(&sql)
+        .bind(project_id)
+        .bind(filter_id)
+        .bind(actor_id)
+        .fetch_optional(&state.db)
+        .await
+        .map_err(|_| ApiError::SavedFiltersReadFailed)?;
+    Ok(row.as_ref().map(map_filter_row))
+}
+
+/// Loads a filter the actor may change: their own, or a shared one for project managers.
+async fn fetch_editable(
+    state: &AppState,
+    access: &ProjectRole,
+    filter_id: Uuid,
+    actor_id: Uuid,
+) -> Result<SavedFilterView, ApiError> {
+    let filter = fetch_filter(state, access.project_id, filter_id, actor_id)
+        .await?
+        .ok_or(ApiError::SavedFilterNotFound)?;
+    if filter.owner_user_id != actor_id.to_string() {
+        access
+            .require(Capability::ProjectManage)
+            .map_err(|_| ApiError::SavedFilterForbidden)?;
+    }
+    Ok(filter)
+}
+
+/// The actor's own filters and the ones shared in the project.
+#[utoipa::path(
+    get,
+    path = "/api/v2/projects/{project_id}/saved-filters",
+    tag = "saved-filters",
+    params(("project_id" = String, Path), ListSavedFiltersQuery),
+    responses((status = 200, body = ListSavedFiltersResponse))
+)]
+pub async fn list_saved_filters(
+    State(state): State<AppState>,
+    access: ProjectRole,
+    Query(query): Query<ListSavedFiltersQuery>,
+) -> Result<Json<ListSavedFiltersResponse>, ApiError> {
+    let target = query.target.as_deref().map(Target::parse).transpose()?;
+    let actor_uuid = parse_uuid(&access.user_id, ApiError::InvalidUserId)?;
+    let sql = format!(
+        "{FILTER_SELECT} WHERE project_id = $1 AND (owner_user_id = $2 OR is_shared)
+           AND ($3::text IS NULL OR target = $3)
+         ORDER BY target ASC, name ASC, id ASC"
+    );
+    let rows = sqlx::query(&sql)
+        .bind(access.project_id)
+        .bind(actor_uuid)
+        .bind(target.map(Target::as_str))
+        .fetch_all(&state.db)
+        .await
+        .map_err(|_| ApiError::SavedFiltersReadFailed)?;
+    Ok(Json(ListSavedFiltersResponse {
+        filters: rows.iter().map(map_filter_row).collect(),
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v2/projects/{project_id}/saved-filters",
+    tag = "saved-filters",
+    params(("project_id" = String, Path)),
+    request_body = CreateSavedFilterRequest,
+    responses((status = 201, body = SavedFilterView))
+)]
+pub async fn create_saved_filter(
+    State(state): State<AppState>,
+    access: ProjectRole,
+    Json(payload): Json<CreateSavedFilterRequest>,
+) -> Result<(StatusCode, Json<SavedFilterView>), ApiError> {
+    let ProjectRole {
+        user_id: actor_id,
+        project_id,
+        ..
+    } = access;
+    let target = Target::parse(&payload.target)?;
+    let name = normalize_name(&payload.name)?;
+    let params = validate_params(&state, project_id, target, payload.params).await?;
+    let is_shared = payload.is_shared.unwrap_or(false);
+    ensure_db_user_exists(&state, &actor_id).await?;
+    let actor_uuid = parse_uuid(&actor_id, ApiError::InvalidUserId)?;
+
+    let create_failed = |_| ApiError::SavedFilterCreateFailed;
+    let mut tx = state.db.begin().await.map_err(create_failed)?;
+    let filter_id: Option<Uuid> = sqlx::query_scalar(
+        r#"
+        INSERT INTO saved_filters (project_id, owner_user_id, target, name, params, is_shared)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        ON CONFLICT (project_id, owner_user_id, target, name) DO NOTHING
+        RETURNING id
+        "#,
+    )
+    .bind(project_id)
+    .bind(actor_uuid)
+    .bind(target.as_str())
+    .bind(&name)
+    .bind(Value::Object(params.clone()))
+    .bind(is_shared)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(create_failed)?;
+    let filter_id = filter_id.ok_or(ApiError::SavedFilterExists)?;
+    audit::record(
+        &mut *tx,
+        audit::AuditEntry {
+            actor_user_id: Some(actor_uuid),
+            action: "create",
+            entity_type: "saved_filter",
+            entity_id: Some(filter_id),
+            project_id: Some(project_id),
+            run_id: None,
+            before: None,
+            after: Some(json!({
+                "target": target.as_str(),
+                "name": &name,
+                "params": &params,
+                "isShared": is_shared,
+            })),
+        },
+    )
+    .await
+    .map_err(create_failed)?;
+    tx.commit().await.map_err(create_failed)?;
+
+    let filter = fetch_filter(&state, project_id, filter_id, actor_uuid)
+        .await?
+        .ok_or(ApiError::SavedFilterNotFound)?;
+    Ok((StatusCode::CREATED, Json(filter)))
+}
+
+/// Partial update by the author; project managers may also change shared filters.
+#[utoipa::path(
+    patch,
+    path = "/api/v2/projects/{project_id}/saved-filters/{filter_id}",
+    tag = "saved-filters",
+    params(("project_id" = String, Path), ("filter_id" = String, Path)),
+    request_body = UpdateSavedFilterRequest,
+    responses((status = 200, body = SavedFilterView))
+)]
+pub async fn update_saved_filter(
+    State(state): State<AppState>,
+    Path((_project_id, filter_id)): Path<(String, String)>,
+    access: ProjectRole,
+    Json(payload): Json<UpdateSavedFilterRequest>,
+) -> Result<Json<SavedFilterView>, ApiError> {
+    let filter_uuid = parse_uuid(&filter_id, ApiError::InvalidSavedFilterId)?;
+    let name = payload.name.as_deref().map(normalize_name).transpose()?;
+    ensure_db_user_exists(&state, &access.user_id).await?;
+    let actor_uuid = parse_uuid(&access.user_id, ApiError::InvalidUserId)?;
+    let project_id = access.project_id;
+    let before = fetch_editable(&state, &access, filter_uuid, actor_uuid).await?;
+    let params = match payload.params {
+        Some(params) => {
+            Some(validate_params(&state, project_id, Target::parse(&before.target)?, params).await?)
+        }
+        None => None,
+    };
+
+    let update_failed = |_| ApiError::SavedFilterUpdateFailed;
+    let mut tx = state.db.begin().await.map_err(update_failed)?;
+    let updated = sqlx::query(
+        r#"
+        UPDATE saved_filters
+        SET name = COALESCE($3, name),
+            params = COALESCE($4, params),
+            is_shared = COALESCE($5, is_shared),
+            updated_at = NOW()
+        WHERE project_id = $1 AND id = $2
+          AND NOT EXISTS (
+            SELECT 1 FROM saved_filters other
+            WHERE other.project_id = $1 AND other.owner_user_id = saved_filters.owner_user_id
+              AND other.target = saved_filters.target AND other.name = $3 AND other.id <> $2
+          )
+        "#,
+    )
+    .bind(project_id)
+    .bind(filter_uuid)
+    .bind(&name)
+    .bind(params.clone().map(Value::Object))
+    .bind(payload.is_shared)
+    .execute(&mut *tx)
+    .await
+    .map_err(update_failed)?;
+    if updated.rows_affected() == 0 {
+        return Err(ApiError::SavedFilterExists);
+    }
+    audit::record(
+        &mut *tx,
+        audit::AuditEntry {
+            actor_user_id: Some(actor_uuid),
+            action: "update",
+            entity_type: "saved_filter",
+            entity_id: Some(filter_uuid),
+            project_id: Some(project_id),
+            run_id: None,
+            before: Some(json!({
+                "name": &before.name,
+                "params": &before.params,
+                "isShared": before.is_shared,
+            })),
+            after: Some(json!({
+                "name": name.as_ref().unwrap_or(&before.name),
+                "params": params.map(Value::Object).unwrap_or_else(|| before.params.clone()),
+                "isShared": payload.is_shared.unwrap_or(before.is_shared),
+            })),
+        },
+    )
+    .await
+    .map_err(update_failed)?;
+    tx.commit().await.map_err(update_failed)?;
+
+    let filter = fetch_filter(&state, project_id, filter_uuid, actor_uuid)
+        .await?
+        .ok_or(ApiError::SavedFilterNotFound)?;
+    Ok(Json(filter))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v2/projects/{project_id}/saved-filters/{filter_id}",
+    tag = "saved-filters",
+    params(("project_id" = String, Path), ("filter_id" = String, Path)),
+    responses((status = 200, body = DeleteSavedFilterResponse))
+)]
+pub async fn delete_saved_filter(
+    State(state): State<AppState>,
+    Path((_project_id, filter_id)): Path<(String, String)>,
+    access: ProjectRole,
+) -> Result<Json<DeleteSavedFilterResponse>, ApiError> {
+    let filter_uuid = parse_uuid(&filter_id, ApiError::InvalidSavedFilterId)?;
+    ensure_db_user_exists(&state, &access.user_id).await?;
+    let actor_uuid = parse_uuid(&access.user_id, ApiError::InvalidUserId)?;
+    let project_id = access.project_id;
+    let filter = fetch_editable(&state, &access, filter_uuid, actor_uuid).await?;
+
+    let delete_failed = |_| ApiError::SavedFilterDeleteFailed;
+    let mut tx = state.db.begin().await.map_err(delete_failed)?;
+    sqlx::query("DELETE FROM saved_filters WHERE project_id = $1 AND id = $2")
+        .bind(project_id)
+        .bind(filter_uuid)
+        .execute(&mut *tx)
+        .await
+        .map_err(delete_failed)?;
+    audit::record(
+        &mut *tx,
+        audit::AuditEntry {
+            actor_user_id: Some(actor_uuid),
+            action: "delete",
+            entity_type: "saved_filter",
+            entity_id: Some(filter_uuid),
+            project_id: Some(project_id),
+            run_id: None,
+            before: Some(json!({
+                "target": &filter.target,
+                "name": &filter.name,
+                "params": &filter.params,
+                "isShared": filter.is_shared,
+            })),
+            after: None,
+        },
+    )
+    .await
+    .map_err(delete_failed)?;
+    tx.commit().await.map_err(delete_failed)?;
+
+    Ok(Json(DeleteSavedFilterResponse { ok: true }))
+}
@@ -0,0 +1,768 @@
+use std::time::Duration;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sqlx::{Connection, PgPool, Row};
+use tracing::warn;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{
+    assignments, audit,
+    authz::{self, ProjectRole},
+    chat,
+    cron::CronExpr,
+    custom_fields, ensure_db_user_exists,
+    error::ApiError,
+    fetch_run_view, load_project_settings, notifications, parse_uuid,
+    permissions::Capability,
+    webhooks, AppState, CreateRunResponse,
+};
+
+/// How often the scheduler looks for due schedules; runs start at most this late.
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduleRequest {
+    name: String,
+    /// Five fields (minute hour day month weekday) or `@hourly|@daily|@weekly|@monthly`,
+    /// e.g. `0 3 * * 1-5` for 03:00 on weekdays.
+    cron: String,
+    /// IANA time zone the expression is evaluated in; the project's zone by default.
+    timezone: Option<String>,
+    /// Run template whose items make up every created run.
+    template_id: String,
+    /// Title of created runs, followed by the local date and time; the schedule name by default.
+    run_title: Option<String>,
+    /// Default executor of created runs; notified when a run is created.
+    assignee_user_id: Option<String>,
+    is_active: Option<bool>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduleView {
+    id: String,
+    name: String,
+    cron: String,
+    timezone: String,
+    template_id: String,
+    run_title: String,
+    assignee_user_id: Option<String>,
+    is_active: bool,
+    next_run_at: Option<String>,
+    last_run_at: Option<String>,
+    last_run_id: Option<String>,
+    /// Error code of the last attempt when it did not create a run.
+    last_error: Option<String>,
+    /// Runs are created on behalf of this user while they may still create runs.
+    created_by_user_id: Option<String>,
+    created_at: String,
+    updated_at: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ListSchedulesResponse {
+    schedules: Vec<ScheduleView>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct DeleteScheduleResponse {
+    ok: bool,
+}
+
+const SCHEDULE_SELECT: &str = r#"
+    SELECT id::text AS id, project_id, name, cron, timezone,
+           template_id::text AS template_id, run_title,
+           assignee_user_id::text AS assignee_user_id, is_active,
+           next_run_at::text AS next_run_at, last_run_at::text AS last_run_at,
+           last_run_id::text AS last_run_id, last_error,
+           created_by_user_id::text AS created_by_user_id,
+           created_at::text AS created_at, updated_at::text AS updated_at
+    FROM schedules
+"#;
+
+fn map_schedule_row(r: &sqlx::postgres::PgRow) -> ScheduleView {
+    ScheduleView {
+        id: r.get("id"),
+        name: r.get("name"),
+        cron: r.get("cron"),
+        timezone: r.get("timezone"),
+        template_id: r.get("template_id"),
+        run_title: r.get("run_title"),
+        assignee_user_id: r.get("assignee_user_id"),
+        is_active: r.get("is_active"),
+        next_run_at: r.get("next_run_at"),
+        last_run_at: r.get("last_run_at"),
+        last_run_id: r.get("last_run_id"),
+        last_error: r.get("last_error"),
+        created_by_user_id: r.get("created_by_user_id"),
+        created_at: r.get("created_at"),
+        updated_at: r.get("updated_at"),
+    }
+}
+
+async fn fetch_schedule(
+    state: &AppState,
+    project_id: Uuid,
+    schedule_id: Uuid,
+) -> Result<ScheduleView, ApiError> {
+    let sql = format!("{SCHEDULE_SELECT} WHERE project_id = $1 AND id = $2");
+    let row = sqlx::query(&sql)
+        .bind(project_id)
+        .bind(schedule_id)
+        .fetch_optional(&state.db)
+        .await
+        .map_err(|_| ApiError::SchedulesReadFailed)?
+        .ok_or(ApiError::ScheduleNotFound)?;
+    Ok(map_schedule_row(&row))
+}
+
+/// Next match of `cron` after now, evaluated on the wall clock of `timezone`; Postgres does
+/// the zone conversions so DST gaps and overlaps follow its tz database.
+pub async fn next_run_at(
+    db: &PgPool,
+    cron: &CronExpr,
+    timezone: &str,
+) -> Result<Option<DateTime<Utc>>, sqlx::Error> {
+    let local_now: NaiveDateTime = sqlx::query_scalar("SELECT NOW() AT TIME ZONE $1")
+        .bind(timezone)
+        .fetch_one(db)
+        .await?;
+    let Some(next) = cron.next_after(local_now) else {
+        return Ok(None);
+    };
+    sqlx::query_scalar("SELECT $1::timestamp AT TIME ZONE $2")
+        .bind(next)
+        .bind(timezone)
+        .fetch_one(db)
+        .await
+        .map(Some)
+}
+
+/// The requested IANA time zone, or the project's zone when none is given; fails unless
+/// Postgres knows the zone.
+pub async fn resolve_timezone(
+    state: &AppState,
+    project_id: Uuid,
+    timezone: Option<&str>,
+) -> Result<String, ApiError> {
+    let timezone = match timezone.map(str::trim) {
+        Some(tz) if !tz.is_empty() => tz.to_string(),
+        _ => {
+            load_project_settings(state, &project_id.to_string())
+                .await?
+                .timezone
+        }
+    };
+    let timezone_known: bool =
+        sqlx::query_scalar(r#"SELECT EXISTS (SELECT 1 FROM pg_timezone_names WHERE name = $1)"#)
+            .bind(&timezone)
+            .fetch_one(&state.db)
+            .await
+            .map_err(|_| ApiError::SchedulesReadFailed)?;
+    if !timezone_known {
+        return Err(ApiError::InvalidTimezone);
+    }
+    Ok(timezone)
+}
+
+/// A validated request, ready to be stored.
+struct ScheduleInput {
+    name: String,
+    cron: String,
+    timezone: String,
+    template_id: Uuid,
+    run_title: String,
+    assignee: Option<Uuid>,
+    is_active: bool,
+    next_run_at: DateTime<Utc>,
+}
+
+async fn validate_request(
+    state: &AppState,
+    project_id: Uuid,
+    payload: ScheduleRequest,
+) -> Result<ScheduleInput, ApiError> {
+    let name = payload.name.trim().to_string();
+    if name.is_empty() || name.chars().count() > 200 {
+        return Err(ApiError::InvalidScheduleName);
+    }
+    let run_title = payload.run_title.unwrap_or_default().trim().to_string();
+    if run_title.chars().count() > 200 {
+        return Err(ApiError::InvalidScheduleName);
+    }
+    let cron_text = payload
+        .cron
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    let cron = CronExpr::parse(&cron_text)?;
+    let template_id = parse_uuid(&payload.template_id, ApiError::InvalidTemplateId)?;
+    let check_failed = |_| ApiError::SchedulesReadFailed;
+
+    let timezone = resolve_timezone(state, project_id, payload.timezone.as_deref()).await?;
+
+    let template_ok: bool = sqlx::query_scalar(
+        r#"
+        SELECT EXISTS (
+          SELECT 1 FROM run_templates
+          WHERE id = $1 AND is_active = TRUE AND (project_id IS NULL OR project_id = $2)
+        )
+        "#,
+    )
+    .bind(template_id)
+    .bind(project_id)
+    .fetch_one(&state.db)
+    .await
+    .map_err(check_failed)?;
+    if !template_ok {
+        return Err(ApiError::RunTemplateNotInProject);
+    }
+
+    let assignee = assignments::resolve_assignee(
+        state,
+        &project_id.to_string(),
+        payload.assignee_user_id.as_deref(),
+    )
+    .await?;
+    let next_run_at = next_run_at(&state.db, &cron, &timezone)
+        .await
+        .map_err(check_failed)?
+        .ok_or(ApiError::InvalidCronExpression)?;
+
+    Ok(ScheduleInput {
+        name,
+        cron: cron_text,
+        timezone,
+        template_id,
+        run_title,
+        assignee,
+        is_active: payload.is_active.unwrap_or(true),
+        next_run_at,
+    })
+}
+
+fn audit_snapshot(input: &ScheduleInput) -> Value {
+    json!({
+        "name": &input.name,
+        "cron": &input.cron,
+        "timezone": &input.timezone,
+        "templateId": input.template_id,
+        "runTitle": &input.run_title,
+        "assigneeUserId": input.assignee,
+        "isActive": input.is_active,
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v2/projects/{project_id}/schedules",
+    tag = "runs",
+    params(("project_id" = String, Path)),
+    responses((status = 200, body = ListSchedulesResponse))
+)]
+pub async fn list_schedules(
+    State(state): State<AppState>,
+    access: ProjectRole,
+) -> Result<Json<ListSchedulesResponse>, ApiError> {
+    access.require(Capability::ProjectRead)?;
+    let sql = format!("{SCHEDULE_SELECT} WHERE project_id = $1 ORDER BY name ASC, id ASC");
+    let rows = sqlx::query(&sql)
+        .bind(access.project_id)
+        .fetch_all(&state.db)
+        .await
+        .map_err(|_| ApiError::SchedulesReadFailed)?;
+    Ok(Json(ListSchedulesResponse {
+        schedules: rows.iter().map(map_schedule_row).collect(),
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v2/projects/{project_id}/schedules",
+    tag = "runs",
+    params(("project_id" = String, Path)),
+    request_body = ScheduleRequest,
+    responses((status = 201, body = ScheduleView))
+)]
+pub async fn create_schedule(
+    State(state): State<AppState>,
+    access: ProjectRole,
+    Json(payload): Json<ScheduleRequest>,
+) -> Result<(StatusCode, Json<ScheduleView>), ApiError> {
+    access.require(Capability::ProjectManage)?;
+    access.require(Capability::RunCreate)?;
+    let project_id = access.project_id;
+    let input = validate_request(&state, project_id, payload).await?;
+    ensure_db_user_exists(&state, &access.user_id).await?;
+    let actor_uuid = parse_uuid(&access.user_id, ApiError::InvalidUserId)?;
+
+    let create_failed = |_| ApiError::ScheduleSaveFailed;
+    let mut tx = state.db.begin().await.map_err(create_failed)?;
+    let schedule_id: Uuid = sqlx::query_scalar(
+        r#"
+        INSERT INTO schedules (
+          project_id, name, cron, timezone, template_id, run_title, assignee_user_id,
+          is_active, next_run_at, created_by_user_id
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+        RETURNING id
+        "#,
+    )
+    .bind(project_id)
+    .bind(&input.name)
+    .bind(&input.cron)
+    .bind(&input.timezone)
+    .bind(input.template_id)
+    .bind(&input.run_title)
+    .bind(input.assignee)
+    .bind(input.is_active)
+    .bind(input.next_run_at)
+    .bind(actor_uuid)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(create_failed)?;
+    audit::record(
+        &mut *tx,
+        audit::AuditEntry {
+            actor_user_id: Some(actor_uuid),
+            action: "create",
+            entity_type: "schedule",
+            entity_id: Some(schedule_id),
+            project_id: Some(project_id),
+            run_id: None,
+            before: None,
+            after: Some(audit_snapshot(&input)),
+        },
+    )
+    .await
+    .map_err(create_failed)?;
+    tx.commit().await.map_err(create_failed)?;
+
+    let schedule = fetch_schedule(&state, project_id, schedule_id).await?;
+    Ok((StatusCode::CREATED, Json(schedule)))
+}
+
+/// Replaces the schedule; the next run time is recalculated from now, so re-activating a
+/// paused schedule does not fire the runs it missed.
+#[utoipa::path(
+    put,
+    path = "/api/v2/projects/{project_id}/schedules/{schedule_id}",
+    tag = "runs",
+    params(("project_id" = String, Path), ("schedule_id" = String, Path)),
+    request_body = ScheduleRequest,
+    responses((status = 200, body = ScheduleView))
+)]
+pub async fn update_schedule(
+    State(state): State<AppState>,
+    Path((_project_id, schedule_id)): Path<(String, String)>,
+    access: ProjectRole,
+    Json(payload): Json<ScheduleRequest>,
+) -> Result<Json<ScheduleView>, ApiError> {
+    access.require(Capability::ProjectManage)?;
+    let schedule_uuid = parse_uuid(&schedule_id, ApiError::InvalidScheduleId)?;
+    let project_id = access.project_id;
+    let before = fetch_schedule(&state, project_id, schedule_uuid).await?;
+    let input = validate_request(&state, project_id, payload).await?;
+    ensure_db_user_exists(&state, &access.user_id).await?;
+    let actor_uuid = parse_uuid(&access.user_id, ApiError::InvalidUserId)?;
+
+    let update_failed = |_| ApiError::ScheduleSaveFailed;
+    let mut tx = state.db.begin().await.map_err(update_failed)?;
+    sqlx::query(
+        r#"
+        UPDATE schedules
+        SET name = $3, cron = $4, timezone = $5, template_id = $6, run_title = $7,
+            assignee_user_id = $8, is_active = $9, next_run_at = $10
+        WHERE project_id = $1 AND id = $2
+        "#,
+    )
+    .bind(project_id)
+    .bind(schedule_uuid)
+    .bind(&input.name)
+    .bind(&input.cron)
+    .bind(&input.timezone)
+    .bind(input.template_id)
+    .bind(&input.run_title)
+    .bind(input.assignee)
+    .bind(input.is_active)
+    .bind(input.next_run_at)
+    .execute(&mut *tx)
+    .await
+    .map_err(update_failed)?;
+    audit::record(
+        &mut *tx,
+        audit::AuditEntry {
+            actor_user_id: Some(actor_uuid),
+            action: "update",
+            entity_type: "schedule",
+            entity_id: Some(schedule_uuid),
+            project_id: Some(project_id),
+            run_id: None,
+            before: Some(json!({
+                "name": &before.name,
+                "cron": &before.cron,
+                "timezone": &before.timezone,
+                "templateId": &before.template_id,
+                "runTitle": &before.run_title,
+                "assigneeUserId": &before.assignee_user_id,
+                "isActive": before.is_active,
+            })),
+            after: Some(audit_snapshot(&input)),
+        },
+    )
+    .await
+    .map_err(update_failed)?;
+    tx.commit().await.map_err(update_failed)?;
+
+    Ok(Json(
+        fetch_schedule(&state, project_id, schedule_uuid).await?,
+    ))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v2/projects/{project_id}/schedules/{schedule_id}",
+    tag = "runs",
+    params(("project_id" = String, Path), ("schedule_id" = String, Path)),
+    responses((status = 200, body = DeleteScheduleResponse))
+)]
+pub async fn delete_schedule(
+    State(state): State<AppState>,
+    Path((_project_id, schedule_id)): Path<(String, String)>,
+    access: ProjectRole,
+) -> Result<Json<DeleteScheduleResponse>, ApiError> {
+    access.require(Capability::ProjectManage)?;
+    let schedule_uuid = parse_uuid(&schedule_id, ApiError::InvalidScheduleId)?;
+    ensure_db_user_exists(&state, &access.user_id).await?;
+    let actor_uuid = parse_uuid(&access.user_id, ApiError::InvalidUserId)?;
+    let project_id = access.project_id;
+    let before = fetch_schedule(&state, project_id, schedule_uuid).await?;
+
+    let delete_failed = |_| ApiError::ScheduleSaveFailed;
+    let mut tx = state.db.begin().await.map_err(delete_failed)?;
+    sqlx::query("DELETE FROM schedules WHERE project_id = $1 AND id = $2")
+        .bind(project_id)
+        .bind(schedule_uuid)
+        .execute(&mut *tx)
+        .await
+        .map_err(delete_failed)?;
+    audit::record(
+        &mut *tx,
+        audit::AuditEntry {
+            actor_user_id: Some(actor_uuid),
+            action: "delete",
+            entity_type: "schedule",
+            entity_id: Some(schedule_uuid),
+            project_id: Some(project_id),
+            run_id: None,
+            before: Some(json!({
+                "name": &before.name,
+                "cron": &before.cron,
+                "templateId": &before.template_id,
+            })),
+            after: None,
+        },
+    )
+    .await
+    .map_err(delete_failed)?;
+    tx.commit().await.map_err(delete_failed)?;
+
+    Ok(Json(DeleteScheduleResponse { ok: true }))
+}
+
+/// Creates the schedule's run right away on behalf of the caller, also for a paused
+/// schedule; the next planned run is not affected.
+#[utoipa::path(
+    post,
+    path = "/api/v2/projects/{project_id}/schedules/{schedule_id}/run",
+    tag = "runs",
+    params(("project_id" = String, Path), ("schedule_id" = String, Path)),
+    responses((status = 201, body = CreateRunResponse))
+)]
+pub async fn run_schedule_now(
+    State(state): State<AppState>,
+    Path((_project_id, schedule_id)): Path<(String, String)>,
+    access: ProjectRole,
+) -> Result<(StatusCode, Json<CreateRunResponse>), ApiError> {
+    access.require(Capability::RunCreate)?;
+    let schedule_uuid = parse_uuid(&schedule_id, ApiError::InvalidScheduleId)?;
+    ensure_db_user_exists(&state, &access.user_id).await?;
+    let actor_uuid = parse_uuid(&access.user_id, ApiError::InvalidUserId)?;
+    let project_id = access.project_id;
+    let sql = format!("{SCHEDULE_SELECT} WHERE project_id = $1 AND id = $2");
+    let schedule = sqlx::query(&sql)
+        .bind(project_id)
+        .bind(schedule_uuid)
+        .fetch_optional(&state.db)
+        .await
+        .map_err(|_| ApiError::SchedulesReadFailed)?
+        .ok_or(ApiError::ScheduleNotFound)?;
+
+    let run_failed = |_| ApiError::ScheduleRunFailed;
+    let mut tx = state.db.begin().await.map_err(run_failed)?;
+    let run_id = create_run(&state, &mut tx, project_id, &schedule, actor_uuid).await?;
+    sqlx::query(
+        "UPDATE schedules SET last_run_at = NOW(), last_run_id = $2, last_error = NULL WHERE id = $1",
+    )
+    .bind(schedule_uuid)
+    .bind(run_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(run_failed)?;
+    tx.commit().await.map_err(run_failed)?;
+
+    let run = fetch_run_view(&state.db, run_id)
+        .await?
+        .ok_or(ApiError::RunCreatedNotFound)?;
+    announce(&state, project_id, schedule_uuid, &run).await;
+    Ok((StatusCode::CREATED, Json(CreateRunResponse { run })))
+}
+
+/// Creates a draft run with the template's items, titled with the schedule's local time.
+/// `schedule` is a row of [`SCHEDULE_SELECT`].
+async fn create_run(
+    state: &AppState,
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    project_id: Uuid,
+    schedule: &sqlx::postgres::PgRow,
+    executor: Uuid,
+) -> Result<Uuid, ApiError> {
+    let run_failed = |_| ApiError::ScheduleRunFailed;
+    let schedule_id = parse_uuid(schedule.get("id"), ApiError::InvalidScheduleId)?;
+    let template_id = parse_uuid(schedule.get("template_id"), ApiError::InvalidTemplateId)?;
+    let assignee = schedule
+        .get::<Option<&str>, _>("assignee_user_id")
+        .and_then(|id| Uuid::parse_str(id).ok());
+    let field_definitions =
+        custom_fields::definitions(&state.db, project_id, custom_fields::Entity::Run).await?;
+    let field_values = custom_fields::apply_values(
+        &field_definitions,
+        serde_json::Map::new(),
+        serde_json::Map::new(),
+    )?;
+
+    let local_time: String =
+        sqlx::query_scalar("SELECT to_char(NOW() AT TIME ZONE $1, 'YYYY-MM-DD HH24:MI')")
+            .bind(schedule.get::<&str, _>("timezone"))
+            .fetch_one(&mut **tx)
+            .await
+            .map_err(run_failed)?;
+    let run_title = schedule.get::<&str, _>("run_title");
+    let title = format!(
+        "{} — {local_time}",
+        if run_title.is_empty() {
+            schedule.get::<&str, _>("name")
+        } else {
+            run_title
+        }
+    );
+
+    let run_id: Uuid = sqlx::query_scalar(
+        r#"
+        INSERT INTO runs (
+          project_id, template_id, title, status, executed_by_user_id, default_assignee_user_id,
+          custom_fields
+        )
+        SELECT $1, rt.id, $3, 'draft', $4, $5, $6
+        FROM run_templates rt
+        WHERE rt.id = $2 AND rt.is_active = TRUE AND (rt.project_id IS NULL OR rt.project_id = $1)
+        RETURNING id
+        "#,
+    )
+    .bind(project_id)
+    .bind(template_id)
+    .bind(&title)
+    .bind(executor)
+    .bind(assignee)
+    .bind(Value::Object(field_values))
+    .fetch_optional(&mut **tx)
+    .await
+    .map_err(run_failed)?
+    .ok_or(ApiError::RunTemplateNotInProject)?;
+    let added = sqlx::query(
+        r#"
+        WITH inserted AS (
+          INSERT INTO run_items (run_id, testcase_version_id, position, is_required)
+          SELECT
+            $1,
+            rti.testcase_version_id,
+            (ROW_NUMBER() OVER (ORDER BY rti.position ASC, rti.created_at ASC))::int - 1,
+            rti.is_required
+          FROM run_template_items rti
+          JOIN testcase_versions tv ON tv.id = rti.testcase_version_id
+          JOIN testcases tc ON tc.id = tv.testcase_id
+          WHERE rti.template_id = $2 AND tc.is_archived = FALSE
+          RETURNING id
+        )
+        INSERT INTO run_results (run_item_id, status, comment, updated_by_user_id)
+        SELECT id, 'na', '', $3 FROM inserted
+        "#,
+    )
+    .bind(run_id)
+    .bind(template_id)
+    .bind(executor)
+    .execute(&mut **tx)
+    .await
+    .map_err(run_failed)?
+    .rows_affected();
+    if added == 0 {
+        return Err(ApiError::RunTemplateEmpty);
+    }
+    audit::record(
+        &mut **tx,
+        audit::AuditEntry {
+            actor_user_id: Some(executor),
+            action: "create",
+            entity_type: "run",
+            entity_id: Some(run_id),
+            project_id: Some(project_id),
+            run_id: Some(run_id),
+            before: None,
+            after: Some(json!({
+                "title": &title,
+                "templateId": template_id,
+                "scheduleId": schedule_id,
+            })),
+        },
+    )
+    .await
+    .map_err(run_failed)?;
+    Ok(run_id)
+}
+
+/// Tells integrations and the assignee about a run the schedule created.
+async fn announce(state: &AppState, project_id: Uuid, schedule_id: Uuid, run: &crate::RunView) {
+    webhooks::emit(
+        state,
+        project_id,
+        "run.created",
+        json!({ "run": run, "scheduleId": schedule_id }),
+    )
+    .await;
+    let Some(assignee_id) = run.default_assignee_user_id.clone() else {
+        return;
+    };
+    let Ok(run_uuid) = Uuid::parse_str(&run.id) else {
+        return;
+    };
+    chat::run_assigned(
+        state,
+        project_id,
+        run_uuid,
+        run.title.clone(),
+        None,
+        assignee_id.clone(),
+    );
+    notifications::notify(
+        state,
+        notifications::NotificationKind::RunAssigned,
+        Some(project_id),
+        Some(run_uuid),
+        vec![assignee_id],
+        format!("Вам назначен прогон «{}»", run.title),
+        format!(
+            "Прогон «{}» создан по расписанию, вы назначены исполнителем по умолчанию.",
+            run.title
+        ),
+    );
+}
+
+/// Creates the runs of due schedules in the background. Each schedule is claimed with
+/// `SKIP LOCKED`, so several API instances never create the same run twice; a schedule
+/// missed while the API was down fires once and then continues from now.
+pub fn spawn_scheduler(state: AppState) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(err) = run_due(&state).await {
+                warn!(error = %err, "scheduler pass failed");
+            }
+        }
+    });
+}
+
+async fn run_due(state: &AppState) -> Result<(), sqlx::Error> {
+    loop {
+        let mut tx = state.db.begin().await?;
+        let sql = format!(
+            "{SCHEDULE_SELECT}
+            WHERE is_active = TRUE AND next_run_at <= NOW()
+            ORDER BY next_run_at
+            LIMIT 1
+            FOR UPDATE SKIP LOCKED"
+        );
+        let Some(schedule) = sqlx::query(&sql).fetch_optional(&mut *tx).await? else {
+            return Ok(());
+        };
+        let schedule_id: Uuid = schedule.get::<&str, _>("id").parse().unwrap_or_default();
+        let project_id: Uuid = schedule.get("project_id");
+
+        // The run is created in a savepoint so a failure is recorded on the schedule instead.
+        let mut attempt = Connection::begin(&mut *tx).await?;
+        let outcome = match fire(state, &mut attempt, project_id, &schedule).await {
+            Ok(run_id) => attempt.commit().await.map(|_| Ok(run_id))?,
+            Err(err) => {
+                attempt.rollback().await?;
+                warn!(%schedule_id, code = err.code(), "scheduled run was not created");
+                Err(err)
+            }
+        };
+
+        let next = match CronExpr::parse(schedule.get("cron")) {
+            Ok(cron) => next_run_at(&state.db, &cron, schedule.get("timezone")).await?,
+            Err(_) => None,
+        };
+        sqlx::query(
+            r#"
+            UPDATE schedules
+            SET next_run_at = $2, last_run_at = NOW(), last_run_id = COALESCE($3, last_run_id),
+                last_error = $4
+            WHERE id = $1
+            "#,
+        )
+        .bind(schedule_id)
+        .bind(next)
+        .bind(outcome.as_ref().ok())
+        .bind(outcome.as_ref().err().map(|err| err.code()))
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        if let Ok(run_id) = outcome {
+            if let Ok(Some(run)) = fetch_run_view(&state.db, run_id).await {
+                announce(state, project_id, schedule_id, &run).await;
+            }
+        }
+    }
+}
+
+/// A planned run is created on behalf of the schedule's author while they may create runs.
+async fn fire(
+    state: &AppState,
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    project_id: Uuid,
+    schedule: &sqlx::postgres::PgRow,
+) -> Result<Uuid, ApiError> {
+    let author_id = schedule
+        .get::<Option<String>, _>("created_by_user_id")
+        .ok_or(ApiError::ScheduleAuthorRemoved)?;
+    authz::require_capability(
+        state,
+        &project_id.to_string(),
+        &author_id,
+        Capability::RunCreate,
+    )
+    .await?;
+    let author_uuid = parse_uuid(&author_id, ApiError::InvalidUserId)?;
+    create_run(state, tx, project_id, schedule, author_uuid).await
+}
@@ -0,0 +1,175 @@
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::Row;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{
+    authz::{self, AuthUser},
+    error::ApiError,
+    parse_uuid,
+    permissions::Capability,
+    AppState,
+};
+
+const HEADLINE_OPTIONS: &str =
+    "StartSel=<mark>, StopSel=</mark>, MaxFragments=2, MaxWords=20, MinWords=5";
+
+#[derive(Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct SearchQuery {
+    q: String,
+    project_id: Option<String>,
+    limit: Option<i64>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchHit {
+    /// `testcase`, `run` or `run_result`.
+    #[serde(rename = "type")]
+    kind: String,
+    id: String,
+    project_id: String,
+    run_id: Option<String>,
+    title: String,
+    /// Matched fragment with terms wrapped in `<mark>`.
+    highlight: String,
+    rank: f32,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct SearchResponse {
+    hits: Vec<SearchHit>,
+}
+
+/// Full-text search over testcases (title + latest version body), run titles and result
+/// comments in the projects the actor is a member of.
+#[utoipa::path(
+    get,
+    path = "/api/v2/search",
+    tag = "search",
+    params(SearchQuery),
+    responses((status = 200, body = SearchResponse))
+)]
+pub async fn search(
+    State(state): State<AppState>,
+    AuthUser(actor_id): AuthUser,
+    Query(query): Query<SearchQuery>,
+) -> Result<Json<SearchResponse>, ApiError> {
+    let q = query.q.trim();
+    if q.chars().count() < 2 || q.chars().count() > 200 {
+        return Err(ApiError::InvalidSearchQuery);
+    }
+    let project_ids = match query.project_id.as_deref() {
+        Some(v) if !v.trim().is_empty() => {
+            let project_id = parse_uuid(v, ApiError::InvalidProjectId)?;
+            authz::require_capability(
+                &state,
+                &project_id.to_string(),
+                &actor_id,
+                Capability::ProjectRead,
+            )
+            .await?;
+            vec![project_id]
+        }
+        _ => authz::member_project_ids(&state, &actor_id).await?,
+    };
+    let limit = query.limit.unwrap_or(20).clamp(1, 50);
+
+    let rows = sqlx::query(
+        r#"
+        WITH q AS (SELECT websearch_to_tsquery('simple', $2) AS query)
+        SELECT * FROM (
+          SELECT
+            'testcase' AS kind,
+            tc.id::text AS id,
+            s.project_id::text AS project_id,
+            NULL::text AS run_id,
+            tc.title AS title,
+            ts_headline(
+              'simple',
+              tc.title || ' ' || COALESCE(tv.summary, '') || ' ' || COALESCE(tv.preconditions, '') || ' ' || COALESCE((
+                SELECT string_agg(v #>> '{}', ' ')
+                FROM jsonb_path_query(tv.steps_json || tv.expected_json, 'strict $.**') AS v
+                WHERE jsonb_typeof(v) = 'string'
+              ), ''),
+              q.query,
+              $3
+            ) AS highlight,
+            ts_rank(tc.search_vector || COALESCE(tv.search_vector, ''::tsvector), q.query) AS rank
+          FROM testcases tc
+          JOIN test_suites s ON s.id = tc.suite_id
+          LEFT JOIN LATERAL (
+            SELECT * FROM testcase_versions v
+            WHERE v.testcase_id = tc.id
+            ORDER BY v.version_number DESC
+            LIMIT 1
+          ) tv ON TRUE
+          CROSS JOIN q
+          WHERE s.project_id = ANY($1)
+            AND tc.is_archived = FALSE
+            AND (tc.search_vector || COALESCE(tv.search_vector, ''::tsvector)) @@ q.query
+
+          UNION ALL
+
+          SELECT
+            'run',
+            r.id::text,
+            r.project_id::text,
+            r.id::text,
+            r.title,
+            ts_headline('simple', r.title || ' ' || r.fail_summary, q.query, $3),
+            ts_rank(r.search_vector, q.query)
+          FROM runs r
+          CROSS JOIN q
+          WHERE r.project_id = ANY($1)
+            AND r.search_vector @@ q.query
+
+          UNION ALL
+
+          SELECT
+            'run_result',
+            ri.id::text,
+            r.project_id::text,
+            r.id::text,
+            r.title,
+            ts_headline('simple', rr.comment, q.query, $3),
+            ts_rank(rr.search_vector, q.query)
+          FROM run_results rr
+          JOIN run_items ri ON ri.id = rr.run_item_id
+          JOIN runs r ON r.id = ri.run_id
+          CROSS JOIN q
+          WHERE r.project_id = ANY($1)
+            AND rr.search_vector @@ q.query
+        ) hits
+        ORDER BY rank DESC, id
+        LIMIT $4
+        "#,
+    )
+    .bind(project_ids)
+    .bind(q)
+    .bind(HEADLINE_OPTIONS)
+    .bind(limit)
+    .fetch_all(state.read_db())
+    .await
+    .map_err(|_| ApiError::SearchFailed)?;
+
+    let hits = rows
+        .into_iter()
+        .map(|r| SearchHit {
+            kind: r.get::<String, _>("kind"),
+            id: r.get::<String, _>("id"),
+            project_id: r.get::<String, _>("project_id"),
+            run_id: r.get::<Option<String>, _>("run_id"),
+            title: r.get::<String, _>("title"),
+            highlight: r.get::<String, _>("highlight"),
+            rank: r.get::<f32, _>("rank"),
+        })
+        .collect();
+
+    Ok(Json(SearchResponse { hits }))
+}
@@ -0,0 +1,259 @@
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    Aes256Gcm, Key, Nonce,
+};
+use sqlx::PgPool;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::{error::ApiError, AppState};
+
+const NONCE_LEN: usize = 12;
+
+/// Encrypts integration credentials at rest with AES-256-GCM. A sealed value is the random
+/// nonce followed by the ciphertext. New values are always sealed with `SECRETS_KEY`; keys in
+/// `SECRETS_PREVIOUS_KEYS` only open values sealed before a rotation, until
+/// `uran-api --reencrypt-secrets` moves them to the current key.
+#[derive(Clone)]
+pub struct SecretBox {
+    current: Aes256Gcm,
+    previous: Vec<Aes256Gcm>,
+}
+
+impl SecretBox {
+    pub fn new(key: &[u8; 32], previous: &[[u8; 32]]) -> Self {
+        let cipher = |key: &[u8; 32]| Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+        Self {
+            current: cipher(key),
+            previous: previous.iter().map(cipher).collect(),
+        }
+    }
+
+    pub fn seal(&self, plaintext: &str) -> Result<Vec<u8>, ApiError> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .current
+            .encrypt(&nonce, plaintext.as_bytes())
+            .map_err(|_| ApiError::SecretSealFailed)?;
+        let mut sealed = nonce.to_vec();
+        sealed.extend_from_slice(&ciphertext);
+        Ok(sealed)
+    }
+
+    /// `None` when the value was sealed with an unknown key or is corrupt.
+    pub fn open(&self, sealed: &[u8]) -> Option<String> {
+        if sealed.len() <= NONCE_LEN {
+            return None;
+        }
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+        let plaintext = std::iter::once(&self.current)
+            .chain(&self.previous)
+            .find_map(|cipher| cipher.decrypt(Nonce::from_slice(nonce), ciphertext).ok())?;
+        String::from_utf8(plaintext).ok()
+    }
+}
+
+/// The key ring, or `503 secrets_key_missing` when the server has no `SECRETS_KEY`.
+pub fn require(state: &AppState) -> Result<&SecretBox, ApiError> {
+    state.secrets.as_deref().ok_or(ApiError::SecretsKeyMissing)
+}
+
+/// Reads a credential kept as a plaintext/sealed column pair. Rows written before migration
+/// 0044 still have the plaintext until `--reencrypt-secrets` runs; `None` when the sealed
+/// value cannot be opened, `Err` when there is no key to try.
+pub fn reveal(
+    state: &AppState,
+    plaintext: Option<String>,
+    sealed: Option<&[u8]>,
+) -> Result<Option<String>, ApiError> {
+    match (plaintext, sealed) {
+        (Some(plaintext), _) => Ok(Some(plaintext)),
+        (None, Some(sealed)) => Ok(require(state)?.open(sealed)),
+        (None, None) => Ok(None),
+    }
+}
+
+/// A stored credential: the plaintext left from before migration 0044 and the sealed value.
+struct StoredSecret {
+    key: Uuid,
+    plaintext: Option<String>,
+    sealed: Option<Vec<u8>>,
+}
+
+/// What `--reencrypt-secrets` did to the values of one column.
+#[derive(Default)]
+struct Tally {
+    resealed: usize,
+    encrypted: usize,
+    unreadable: usize,
+}
+
+impl Tally {
+    /// The value to seal with the current key; `None` when there is none or no key opens it.
+    fn reveal(
+        &mut self,
+        secrets: &SecretBox,
+        column: &str,
+        stored: &StoredSecret,
+    ) -> Option<String> {
+        match (&stored.plaintext, &stored.sealed) {
+            (Some(value), _) => {
+                self.encrypted += 1;
+                Some(value.clone())
+            }
+            (None, Some(bytes)) => {
+                let value = secrets.open(bytes);
+                match value {
+                    Some(_) => self.resealed += 1,
+                    None => {
+                        warn!(
+                            "{column} of {} cannot be decrypted with any key",
+                            stored.key
+                        );
+                        self.unreadable += 1;
+                    }
+                }
+                value
+            }
+            (None, None) => None,
+        }
+    }
+
+    fn report(&self, table: &str) -> usize {
+        info!(
+            "{table}: re-encrypted {}, encrypted {} plaintext values",
+            self.resealed, self.encrypted
+        );
+        self.unreadable
+    }
+}
+
+fn seal_failed(err: ApiError) -> anyhow::Error {
+    anyhow::anyhow!("{}", err.code())
+}
+
+/// `uran-api --reencrypt-secrets`: seals every stored credential again with the current key
+/// and encrypts the plaintext left from before migration 0044, one transaction per table.
+/// Values no key opens are left untouched and reported; the command then fails so that the
+/// old key is not dropped from `SECRETS_PREVIOUS_KEYS` by mistake.
+pub async fn reencrypt(db: &PgPool, secrets: &SecretBox) -> anyhow::Result<()> {
+    let mut unreadable = 0usize;
+
+    let mut tx = db.begin().await?;
+    let rows = sqlx::query_as!(
+        StoredSecret,
+        r#"
+        SELECT project_id AS key, NULL::text AS plaintext, api_token_encrypted AS sealed
+        FROM project_jira_connections
+        FOR UPDATE
+        "#
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+    let mut tally = Tally::default();
+    for row in &rows {
+        let column = "project_jira_connections.api_token_encrypted";
+        let Some(value) = tally.reveal(secrets, column, row) else {
+            continue;
+        };
+        sqlx::query!(
+            "UPDATE project_jira_connections SET api_token_encrypted = $2 WHERE project_id = $1",
+            row.key,
+            secrets.seal(&value).map_err(seal_failed)?
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+    unreadable += tally.report("project_jira_connections");
+
+    let mut tx = db.begin().await?;
+    let rows = sqlx::query_as!(
+        StoredSecret,
+        r#"
+        SELECT project_id AS key, NULL::text AS plaintext, token_encrypted AS sealed
+        FROM project_ci_connections
+        FOR UPDATE
+        "#
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+    let mut tally = Tally::default();
+    for row in &rows {
+        let column = "project_ci_connections.token_encrypted";
+        let Some(value) = tally.reveal(secrets, column, row) else {
+            continue;
+        };
+        sqlx::query!(
+            "UPDATE project_ci_connections SET token_encrypted = $2 WHERE project_id = $1",
+            row.key,
+            secrets.seal(&value).map_err(seal_failed)?
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+    unreadable += tally.report("project_ci_connections");
+
+    let mut tx = db.begin().await?;
+    let rows = sqlx::query_as!(
+        StoredSecret,
+        r#"
+        SELECT id AS key, secret AS plaintext, secret_encrypted AS sealed
+        FROM webhooks
+        FOR UPDATE
+        "#
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+    let mut tally = Tally::default();
+    for row in &rows {
+        let Some(value) = tally.reveal(secrets, "webhooks.secret_encrypted", row) else {
+            continue;
+        };
+        sqlx::query!(
+            "UPDATE webhooks SET secret_encrypted = $2, secret = NULL WHERE id = $1",
+            row.key,
+            secrets.seal(&value).map_err(seal_failed)?
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+    unreadable += tally.report("webhooks");
+
+    let mut tx = db.begin().await?;
+    let rows = sqlx::query_as!(
+        StoredSecret,
+        r#"
+        SELECT id AS key, url AS plaintext, url_encrypted AS sealed
+        FROM chat_webhooks
+        FOR UPDATE
+        "#
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+    let mut tally = Tally::default();
+    for row in &rows {
+        let Some(value) = tally.reveal(secrets, "chat_webhooks.url_encrypted", row) else {
+            continue;
+        };
+        sqlx::query!(
+            "UPDATE chat_webhooks SET url_encrypted = $2, url = NULL WHERE id = $1",
+            row.key,
+            secrets.seal(&value).map_err(seal_failed)?
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+    unreadable += tally.report("chat_webhooks");
+
+    if unreadable > 0 {
+        anyhow::bail!(
+            "{unreadable} stored credentials cannot be decrypted; add the key they were \
+             sealed with to SECRETS_PREVIOUS_KEYS or enter them again"
+        );
+    }
+    Ok(())
+}
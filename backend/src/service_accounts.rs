@@ -0,0 +1,467 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::{
+    api_keys::{self, ApiKeyView, CreateApiKeyResponse, KeySpec, ListApiKeysResponse},
+    audit,
+    authz::AdminUser,
+    db_errors, ensure_db_user_exists,
+    error::ApiError,
+    now_iso, parse_uuid, permissions, read_projects, read_users, write_projects, write_users,
+    AppState, Project, ProjectMember, User,
+};
+
+/// Role given to a service account when the request names none: enough to create runs and
+/// record results, not to lock runs or manage the project.
+const DEFAULT_ROLE: &str = "editor";
+
+/// Domain of the generated addresses; `.invalid` never receives mail.
+const EMAIL_DOMAIN: &str = "service-accounts.invalid";
+
+/// Marks a user in `users.json` as a service account: it cannot sign in and acts only
+/// through API keys minted by admins, in its one project.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ServiceAccount {
+    pub project_id: String,
+    pub created_by_user_id: String,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct ListServiceAccountsQuery {
+    project_id: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateServiceAccountRequest {
+    project_id: String,
+    name: String,
+    /// Project role of the account, `editor` by default; `owner` is not allowed.
+    role: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateServiceAccountKeyRequest {
+    name: String,
+    scopes: Vec<String>,
+    expires_at: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ServiceAccountView {
+    id: String,
+    project_id: String,
+    name: String,
+    /// Role in the project; `null` once the account was removed from it.
+    role: Option<String>,
+    /// `active` or `deactivated`.
+    status: &'static str,
+    created_by_user_id: String,
+    created_at: String,
+    deactivated_at: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ListServiceAccountsResponse {
+    service_accounts: Vec<ServiceAccountView>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ServiceAccountResponse {
+    service_account: ServiceAccountView,
+}
+
+fn map_account(user: &User, account: &ServiceAccount, projects: &[Project]) -> ServiceAccountView {
+    ServiceAccountView {
+        id: user.id.clone(),
+        project_id: account.project_id.clone(),
+        name: user.name.clone(),
+        role: projects
+            .iter()
+            .find(|p| p.id == account.project_id)
+            .and_then(|p| p.members.iter().find(|m| m.user_id == user.id))
+            .map(|m| m.role.clone()),
+        status: if user.deactivated_at.is_some() {
+            "deactivated"
+        } else {
+            "active"
+        },
+        created_by_user_id: account.created_by_user_id.clone(),
+        created_at: user.created_at.clone(),
+        deactivated_at: user.deactivated_at.clone(),
+    }
+}
+
+/// The service account with this id, from a freshly read `users.json`.
+async fn load_account(state: &AppState, user_id: &str) -> Result<(User, ServiceAccount), ApiError> {
+    parse_uuid(user_id, ApiError::InvalidUserId)?;
+    let _guard = state.file_lock.lock().await;
+    let users = read_users(&state.users_file)
+        .await
+        .map_err(|_| ApiError::ServiceAccountsReadFailed)?;
+    users
+        .into_iter()
+        .find(|u| u.id == user_id)
+        .and_then(|u| u.service_account.clone().map(|a| (u, a)))
+        .ok_or(ApiError::ServiceAccountNotFound)
+}
+
+/// Service accounts of all projects, or of `projectId`, oldest first.
+#[utoipa::path(
+    get,
+    path = "/api/admin/service-accounts",
+    tag = "admin",
+    params(ListServiceAccountsQuery),
+    responses((status = 200, body = ListServiceAccountsResponse))
+)]
+pub async fn list_service_accounts(
+    State(state): State<AppState>,
+    AdminUser(_admin_id): AdminUser,
+    Query(query): Query<ListServiceAccountsQuery>,
+) -> Result<Json<ListServiceAccountsResponse>, ApiError> {
+    let project_id = query
+        .project_id
+        .as_deref()
+        .map(str::trim)
+        .filter(|v| !v.is_empty());
+    if let Some(project_id) = project_id {
+        parse_uuid(project_id, ApiError::InvalidProjectIdParam)?;
+    }
+
+    let _guard = state.file_lock.lock().await;
+    let users = read_users(&state.users_file)
+        .await
+        .map_err(|_| ApiError::ServiceAccountsReadFailed)?;
+    let projects = read_projects(&state.projects_file)
+        .await
+        .map_err(|_| ApiError::ServiceAccountsReadFailed)?;
+    let mut accounts: Vec<(&User, &ServiceAccount)> = users
+        .iter()
+        .filter_map(|u| u.service_account.as_ref().map(|a| (u, a)))
+        .filter(|(_, a)| project_id.is_none_or(|id| a.project_id == id))
+        .collect();
+    accounts.sort_by(|a, b| (&a.0.created_at, &a.0.id).cmp(&(&b.0.created_at, &b.0.id)));
+    Ok(Json(ListServiceAccountsResponse {
+        service_accounts: accounts
+            .into_iter()
+            .map(|(u, a)| map_account(u, a, &projects))
+            .collect(),
+    }))
+}
+
+/// Creates a service account and adds it to the project with `role`. Runs created and
+/// results recorded with its API keys name the account, not a person, as the executor.
+#[utoipa::path(
+    post,
+    path = "/api/admin/service-accounts",
+    tag = "admin",
+    request_body = CreateServiceAccountRequest,
+    responses((status = 201, body = ServiceAccountResponse))
+)]
+pub async fn create_service_account(
+    State(state): State<AppState>,
+    AdminUser(actor_id): AdminUser,
+    Json(payload): Json<CreateServiceAccountRequest>,
+) -> Result<(StatusCode, Json<ServiceAccountResponse>), ApiError> {
+    let project_uuid = parse_uuid(payload.project_id.trim(), ApiError::InvalidProjectIdParam)?;
+    let project_id = project_uuid.to_string();
+    let name = payload.name.trim().to_string();
+    if name.is_empty() || name.chars().count() > 200 {
+        return Err(ApiError::InvalidServiceAccountName);
+    }
+    let role = payload
+        .role
+        .as_deref()
+        .map(str::trim)
+        .filter(|r| !r.is_empty())
+        .unwrap_or(DEFAULT_ROLE)
+        .to_string();
+
+    let id = Uuid::new_v4();
+    let account = ServiceAccount {
+        project_id: project_id.clone(),
+        created_by_user_id: actor_id.clone(),
+    };
+    let user = User {
+        id: id.to_string(),
+        name: name.clone(),
+        email: format!("{}@{EMAIL_DOMAIN}", id.simple()),
+        password_hash: String::new(),
+        created_at: now_iso(),
+        pending_email: None,
+        avatar: None,
+        oidc: None,
+        email_verified: true,
+        email_verification: None,
+        is_admin: false,
+        deactivated_at: None,
+        password_reset: None,
+        service_account: Some(account.clone()),
+    };
+    let view = {
+        let _guard = state.file_lock.lock().await;
+        let mut users = read_users(&state.users_file)
+            .await
+            .map_err(|_| ApiError::ServiceAccountCreateFailed)?;
+        let mut projects = read_projects(&state.projects_file)
+            .await
+            .map_err(|_| ApiError::ServiceAccountCreateFailed)?;
+        let project = projects
+            .iter_mut()
+            .find(|p| p.id == project_id)
+            .ok_or(ApiError::ProjectNotFound)?;
+        if !permissions::is_assignable_role(project, &role) {
+            return Err(ApiError::InvalidMemberRole);
+        }
+        project.members.push(ProjectMember {
+            user_id: user.id.clone(),
+            role: role.clone(),
+        });
+        project.updated_at = now_iso();
+        users.push(user.clone());
+        write_users(&state.users_file, &users)
+            .await
+            .map_err(|_| ApiError::ServiceAccountCreateFailed)?;
+        write_projects(&state, &projects)
+            .await
+            .map_err(|_| ApiError::ServiceAccountCreateFailed)?;
+        map_account(&user, &account, &projects)
+    };
+
+    // The row carries the real name, so exports and reports show the account by name.
+    sqlx::query!(
+        r#"
+        INSERT INTO users (id, email, display_name, password_hash, is_active)
+        VALUES ($1, $2, $3, 'service-account', TRUE)
+        ON CONFLICT (id) DO NOTHING
+        "#,
+        id,
+        &user.email,
+        &name,
+    )
+    .execute(&state.db)
+    .await
+    .map_err(|err| db_errors::map(err, ApiError::UserSyncFailed))?;
+    ensure_db_user_exists(&state, &actor_id).await?;
+    let recorded = audit::record(
+        &state.db,
+        audit::AuditEntry {
+            actor_user_id: Uuid::parse_str(&actor_id).ok(),
+            action: "create",
+            entity_type: "service_account",
+            entity_id: Some(id),
+            project_id: Some(project_uuid),
+            run_id: None,
+            before: None,
+            after: serde_json::to_value(&view).ok(),
+        },
+    )
+    .await;
+    if let Err(err) = recorded {
+        warn!("failed to audit the creation of service account {id}: {err}");
+    }
+    Ok((
+        StatusCode::CREATED,
+        Json(ServiceAccountResponse {
+            service_account: view,
+        }),
+    ))
+}
+
+/// Retires the account: it is deactivated, removed from its project and its keys are
+/// revoked. Runs and results it recorded keep naming it.
+#[utoipa::path(
+    delete,
+    path = "/api/admin/service-accounts/{user_id}",
+    tag = "admin",
+    params(("user_id" = String, Path)),
+    responses((status = 200, body = ServiceAccountResponse))
+)]
+pub async fn delete_service_account(
+    State(state): State<AppState>,
+    AdminUser(actor_id): AdminUser,
+    Path(user_id): Path<String>,
+) -> Result<Json<ServiceAccountResponse>, ApiError> {
+    let user_uuid = parse_uuid(&user_id, ApiError::InvalidUserId)?;
+    let (before, after, project_id) = {
+        let _guard = state.file_lock.lock().await;
+        let mut users = read_users(&state.users_file)
+            .await
+            .map_err(|_| ApiError::ServiceAccountDeleteFailed)?;
+        let mut projects = read_projects(&state.projects_file)
+            .await
+            .map_err(|_| ApiError::ServiceAccountDeleteFailed)?;
+        let (user, account) = users
+            .iter_mut()
+            .filter(|u| u.id == user_id)
+            .find_map(|u| {
+                let account = u.service_account.clone()?;
+                Some((u, account))
+            })
+            .ok_or(ApiError::ServiceAccountNotFound)?;
+        let before = map_account(user, &account, &projects);
+        if user.deactivated_at.is_none() {
+            user.deactivated_at = Some(now_iso());
+        }
+        let mut removed = false;
+        if let Some(project) = projects.iter_mut().find(|p| p.id == account.project_id) {
+            let count = project.members.len();
+            project.members.retain(|m| m.user_id != user_id);
+            removed = project.members.len() != count;
+            if removed {
+                project.updated_at = now_iso();
+            }
+        }
+        let after = map_account(user, &account, &projects);
+        write_users(&state.users_file, &users)
+            .await
+            .map_err(|_| ApiError::ServiceAccountDeleteFailed)?;
+        if removed {
+            write_projects(&state, &projects)
+                .await
+                .map_err(|_| ApiError::ServiceAccountDeleteFailed)?;
+        }
+        (before, after, account.project_id)
+    };
+
+    ensure_db_user_exists(&state, &user_id).await?;
+    sqlx::query!(
+        "UPDATE users SET is_active = FALSE WHERE id = $1",
+        user_uuid
+    )
+    .execute(&state.db)
+    .await
+    .map_err(|err| db_errors::map(err, ApiError::ServiceAccountDeleteFailed))?;
+    sqlx::query!(
+        "UPDATE api_keys SET revoked_at = NOW() WHERE user_id = $1 AND revoked_at IS NULL",
+        user_uuid,
+    )
+    .execute(&state.db)
+    .await
+    .map_err(|err| db_errors::map(err, ApiError::ServiceAccountDeleteFailed))?;
+
+    ensure_db_user_exists(&state, &actor_id).await?;
+    let recorded = audit::record(
+        &state.db,
+        audit::AuditEntry {
+            actor_user_id: Uuid::parse_str(&actor_id).ok(),
+            action: "delete",
+            entity_type: "service_account",
+            entity_id: Some(user_uuid),
+            project_id: Uuid::parse_str(&project_id).ok(),
+            run_id: None,
+            before: serde_json::to_value(&before).ok(),
+            after: serde_json::to_value(&after).ok(),
+        },
+    )
+    .await;
+    if let Err(err) = recorded {
+        warn!("failed to audit the removal of service account {user_id}: {err}");
+    }
+    Ok(Json(ServiceAccountResponse {
+        service_account: after,
+    }))
+}
+
+/// Mints a key acting as the service account, limited to its project. Requests made with it
+/// are checked against the account's role like any member's.
+#[utoipa::path(
+    post,
+    path = "/api/admin/service-accounts/{user_id}/api-keys",
+    tag = "admin",
+    params(("user_id" = String, Path)),
+    request_body = CreateServiceAccountKeyRequest,
+    responses((status = 201, body = CreateApiKeyResponse))
+)]
+pub async fn create_service_account_key(
+    State(state): State<AppState>,
+    AdminUser(actor_id): AdminUser,
+    Path(user_id): Path<String>,
+    Json(payload): Json<CreateServiceAccountKeyRequest>,
+) -> Result<(StatusCode, Json<CreateApiKeyResponse>), ApiError> {
+    let spec = KeySpec::parse(
+        &payload.name,
+        &payload.scopes,
+        payload.expires_at.as_deref(),
+    )?;
+    let (user, account) = load_account(&state, &user_id).await?;
+    if user.deactivated_at.is_some() {
+        return Err(ApiError::ServiceAccountDeactivated);
+    }
+    let project_uuid = parse_uuid(&account.project_id, ApiError::InvalidProjectId)?;
+    ensure_db_user_exists(&state, &actor_id).await?;
+    ensure_db_user_exists(&state, &user_id).await?;
+    let created = api_keys::insert_key(
+        &state,
+        parse_uuid(&actor_id, ApiError::InvalidUserId)?,
+        parse_uuid(&user_id, ApiError::InvalidUserId)?,
+        project_uuid,
+        spec,
+    )
+    .await?;
+    Ok((StatusCode::CREATED, Json(created)))
+}
+
+/// Keys of the service account, including revoked and expired ones.
+#[utoipa::path(
+    get,
+    path = "/api/admin/service-accounts/{user_id}/api-keys",
+    tag = "admin",
+    params(("user_id" = String, Path)),
+    responses((status = 200, body = ListApiKeysResponse))
+)]
+pub async fn list_service_account_keys(
+    State(state): State<AppState>,
+    AdminUser(_admin_id): AdminUser,
+    Path(user_id): Path<String>,
+) -> Result<Json<ListApiKeysResponse>, ApiError> {
+    load_account(&state, &user_id).await?;
+    let user_uuid = parse_uuid(&user_id, ApiError::InvalidUserId)?;
+    api_keys::list_keys(&state, user_uuid).await.map(Json)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/admin/service-accounts/{user_id}/api-keys/{key_id}",
+    tag = "admin",
+    params(("user_id" = String, Path), ("key_id" = String, Path)),
+    responses((status = 200, body = ApiKeyView))
+)]
+pub async fn revoke_service_account_key(
+    State(state): State<AppState>,
+    AdminUser(actor_id): AdminUser,
+    Path((user_id, key_id)): Path<(String, String)>,
+) -> Result<Json<ApiKeyView>, ApiError> {
+    load_account(&state, &user_id).await?;
+    let key_uuid = parse_uuid(&key_id, ApiError::InvalidApiKeyId)?;
+    ensure_db_user_exists(&state, &actor_id).await?;
+    api_keys::revoke_key(
+        &state,
+        parse_uuid(&actor_id, ApiError::InvalidUserId)?,
+        parse_uuid(&user_id, ApiError::InvalidUserId)?,
+        key_uuid,
+    )
+    .await
+    .map(Json)
+}
+
+/// Service accounts have no mailbox, so notifications skip them.
+pub fn is_service_account(users: &[User], user_id: &str) -> bool {
+    users
+        .iter()
+        .any(|u| u.id == user_id && u.service_account.is_some())
+}
@@ -0,0 +1,121 @@
+use axum::{
+    body::Bytes,
+    extract::{Path, State},
+    http::{
+        header::{CONTENT_TYPE, ETAG},
+        HeaderMap,
+    },
+    response::IntoResponse,
+    Json,
+};
+use serde::Serialize;
+use serde_json::Value;
+use utoipa::ToSchema;
+
+use crate::{
+    authz::AuthUser,
+    error::ApiError,
+    etag::{self, Precondition, VersionError},
+    now_iso, permissions,
+    permissions::Capability,
+    read_projects, write_projects, AppState,
+};
+
+const MERGE_PATCH: &str = "application/merge-patch+json";
+const JSON_PATCH: &str = "application/json-patch+json";
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PatchSessionResponse {
+    ok: bool,
+    updated_at: String,
+    version: i64,
+    /// The session after the patch, so the client can continue from the merged state.
+    session: Value,
+}
+
+/// Autosave of a part of the session: the patch is applied to the stored session under the
+/// projects file lock, so concurrent editors of different keys do not overwrite each other.
+/// `Content-Type` picks the format: `application/merge-patch+json` (RFC 7386) or
+/// `application/json-patch+json` (RFC 6902, applied atomically). A session that was never
+/// saved starts as `{}`. `If-Match` works as for `PUT`.
+#[utoipa::path(
+    patch,
+    path = "/api/projects/{project_id}/session",
+    tag = "projects",
+    params(("project_id" = String, Path)),
+    request_body(content(
+        (Value = "application/merge-patch+json"),
+        (json_patch::Patch = "application/json-patch+json")
+    )),
+    responses((status = 200, body = PatchSessionResponse))
+)]
+pub async fn patch_session(
+    State(state): State<AppState>,
+    Path(project_id): Path<String>,
+    AuthUser(user_id): AuthUser,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<impl IntoResponse, VersionError> {
+    let precondition = Precondition::from_headers(&headers)?;
+    let content_type = headers
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.split(';').next())
+        .map(|v| v.trim().to_ascii_lowercase())
+        .unwrap_or_default();
+    let apply: fn(&mut Value, &[u8]) -> Result<(), ApiError> = match content_type.as_str() {
+        MERGE_PATCH => |session, body| {
+            let patch: Value =
+                serde_json::from_slice(body).map_err(|_| ApiError::InvalidSessionPatch)?;
+            json_patch::merge(session, &patch);
+            Ok(())
+        },
+        JSON_PATCH => |session, body| {
+            let patch: json_patch::Patch =
+                serde_json::from_slice(body).map_err(|_| ApiError::InvalidSessionPatch)?;
+            json_patch::patch(session, &patch).map_err(|_| ApiError::SessionPatchNotApplicable)
+        },
+        _ => return Err(ApiError::UnsupportedSessionPatchType.into()),
+    };
+
+    let _guard = state.file_lock.lock().await;
+    let mut projects = read_projects(&state.projects_file)
+        .await
+        .map_err(|_| ApiError::SessionSaveFailed)?;
+    let project = projects
+        .iter_mut()
+        .find(|p| p.id == project_id)
+        .ok_or(ApiError::ProjectNotFound)?;
+
+    permissions::check(project, &user_id, Capability::ResultEdit)?;
+    precondition.check(
+        Some(project.session_version),
+        ApiError::SessionVersionConflict,
+    )?;
+
+    let mut session = project
+        .session
+        .clone()
+        .unwrap_or_else(|| Value::Object(Default::default()));
+    apply(&mut session, &body)?;
+
+    project.session = Some(session.clone());
+    project.session_version += 1;
+    project.updated_at = now_iso();
+    let updated_at = project.updated_at.clone();
+    let version = project.session_version;
+    write_projects(&state, &projects)
+        .await
+        .map_err(|_| ApiError::SessionSaveFailed)?;
+
+    Ok((
+        [(ETAG, etag::etag(version)?)],
+        Json(PatchSessionResponse {
+            ok: true,
+            updated_at,
+            version,
+            session,
+        }),
+    ))
+}
@@ -0,0 +1,72 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use axum::body::Bytes;
+use object_store::{aws::AmazonS3Builder, local::LocalFileSystem, path::Path, ObjectStore};
+
+use crate::config::StorageConfig;
+
+/// Binary artifacts (attachments, avatars) live here; the DB only keeps the key.
+pub struct Storage {
+    provider: &'static str,
+    store: Arc<dyn ObjectStore>,
+}
+
+impl Storage {
+    /// Local storage writes under its directory; S3 takes the bucket from the config plus
+    /// the standard `AWS_*` variables, so any S3-compatible endpoint (MinIO etc.) works via
+    /// `AWS_ENDPOINT`.
+    pub fn new(config: &StorageConfig) -> anyhow::Result<Self> {
+        match config {
+            StorageConfig::Local { dir } => {
+                std::fs::create_dir_all(dir)
+                    .with_context(|| format!("failed to create storage dir {}", dir.display()))?;
+                let store = LocalFileSystem::new_with_prefix(dir)
+                    .context("failed to open local storage")?;
+                Ok(Self {
+                    provider: "local",
+                    store: Arc::new(store),
+                })
+            }
+            StorageConfig::S3 { bucket } => {
+                let store = AmazonS3Builder::from_env()
+                    .with_bucket_name(bucket)
+                    .build()
+                    .context("failed to configure S3 storage")?;
+                Ok(Self {
+                    provider: "s3",
+                    store: Arc::new(store),
+                })
+            }
+        }
+    }
+
+    pub fn provider(&self) -> &'static str {
+        self.provider
+    }
+
+    pub async fn put(&self, key: &str, data: Bytes) -> anyhow::Result<()> {
+        self.store.put(&Path::from(key), data.into()).await?;
+        Ok(())
+    }
+
+    pub async fn get(&self, key: &str) -> anyhow::Result<Bytes> {
+        let result = self.store.get(&Path::from(key)).await?;
+        Ok(result.bytes().await?)
+    }
+
+    /// Cheap round trip to the backend for readiness checks; a missing key is an answer too.
+    pub async fn probe(&self) -> anyhow::Result<()> {
+        match self.store.head(&Path::from("health/probe")).await {
+            Ok(_) | Err(object_store::Error::NotFound { .. }) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub async fn delete(&self, key: &str) -> anyhow::Result<()> {
+        match self.store.delete(&Path::from(key)).await {
+            Ok(()) | Err(object_store::Error::NotFound { .. }) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
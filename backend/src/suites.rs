@@ -0,0 +1,527 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::Row;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{
+    authz::{self, AuthUser, ProjectRole},
+    db_errors, ensure_db_user_exists,
+    error::ApiError,
+    parse_uuid,
+    permissions::Capability,
+    AppState,
+};
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateSuiteRequest {
+    name: String,
+    key: Option<String>,
+    description: Option<String>,
+    parent_id: Option<String>,
+    position: Option<i32>,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateSuiteRequest {
+    name: Option<String>,
+    description: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct MoveSuiteRequest {
+    parent_id: Option<String>,
+    position: Option<i32>,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AssignTestcaseSuiteRequest {
+    suite_id: String,
+}
+
+#[derive(Serialize, Clone, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SuiteView {
+    id: String,
+    project_id: String,
+    parent_id: Option<String>,
+    key: String,
+    name: String,
+    description: String,
+    position: i32,
+    created_at: String,
+    updated_at: String,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SuiteNode {
+    #[serde(flatten)]
+    suite: SuiteView,
+    testcase_count: i64,
+    #[schema(no_recursion)]
+    children: Vec<SuiteNode>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct SuiteResponse {
+    suite: SuiteView,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct SuiteTreeResponse {
+    suites: Vec<SuiteNode>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AssignTestcaseSuiteResponse {
+    ok: bool,
+    testcase_id: String,
+    suite_id: String,
+}
+
+const SUITE_COLUMNS: &str = r#"
+  id::text AS id,
+  project_id::text AS project_id,
+  parent_id::text AS parent_id,
+  key,
+  name,
+  description,
+  position,
+  created_at::text AS created_at,
+  updated_at::text AS updated_at
+"#;
+
+fn map_suite_row(r: &sqlx::postgres::PgRow) -> SuiteView {
+    SuiteView {
+        id: r.get::<String, _>("id"),
+        project_id: r.get::<String, _>("project_id"),
+        parent_id: r.get::<Option<String>, _>("parent_id"),
+        key: r.get::<String, _>("key"),
+        name: r.get::<String, _>("name"),
+        description: r.get::<String, _>("description"),
+        position: r.get::<i32, _>("position"),
+        created_at: r.get::<String, _>("created_at"),
+        updated_at: r.get::<String, _>("updated_at"),
+    }
+}
+
+fn validate_suite_name(name: &str) -> Result<(), ApiError> {
+    let len = name.chars().count();
+    if !(2..=200).contains(&len) {
+        return Err(ApiError::InvalidSuiteTitle);
+    }
+    Ok(())
+}
+
+async fn fetch_suite(state: &AppState, suite_id: Uuid) -> Result<Option<SuiteView>, ApiError> {
+    let sql =
+        format!("SELECT {SUITE_COLUMNS} FROM test_suites WHERE id = $1 AND is_archived = FALSE");
+    let row = sqlx::query(&sql)
+        .bind(suite_id)
+        .fetch_optional(&state.db)
+        .await
+        .map_err(|_| ApiError::SuiteReadFailed)?;
+    Ok(row.as_ref().map(map_suite_row))
+}
+
+/// Loads a suite and checks the actor's access to its project.
+async fn load_suite_for_actor(
+    state: &AppState,
+    suite_id: &str,
+    actor_id: &str,
+    capability: Capability,
+) -> Result<SuiteView, ApiError> {
+    let suite_uuid = parse_uuid(suite_id, ApiError::InvalidSuiteId)?;
+    let suite = fetch_suite(state, suite_uuid)
+        .await?
+        .ok_or(ApiError::SuiteNotFound)?;
+    authz::require_capability(state, &suite.project_id, actor_id, capability).await?;
+    Ok(suite)
+}
+
+/// Checks that `suite_id` exists in `project_id`; used before expanding a suite into a run.
+pub async fn ensure_suite_in_project(
+    state: &AppState,
+    suite_id: Uuid,
+    project_id: Uuid,
+) -> Result<(), ApiError> {
+    let exists: bool = sqlx::query_scalar(
+        r#"
+        SELECT EXISTS (
+          SELECT 1 FROM test_suites
+          WHERE id = $1 AND project_id = $2 AND is_archived = FALSE
+        )
+        "#,
+    )
+    .bind(suite_id)
+    .bind(project_id)
+    .fetch_one(&state.db)
+    .await
+    .map_err(|_| ApiError::SuiteReadFailed)?;
+    if !exists {
+        return Err(ApiError::SuiteNotInProject);
+    }
+    Ok(())
+}
+
+/// Adds the latest version of every active testcase in the suite subtree to the run.
+pub async fn expand_suite_into_run(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    run_id: Uuid,
+    suite_id: Uuid,
+    actor_id: Uuid,
+) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query(
+        r#"
+        WITH RECURSIVE subtree AS (
+          SELECT id, ARRAY[position] AS sort_path
+          FROM test_suites
+          WHERE id = $2
+          UNION ALL
+          SELECT s.id, st.sort_path || s.position
+          FROM test_suites s
+          JOIN subtree st ON s.parent_id = st.id
+          WHERE s.is_archived = FALSE
+        ),
+        latest AS (
+          SELECT DISTINCT ON (tc.id)
+            tv.id AS testcase_version_id,
+            tc.is_required,
+            st.sort_path,
+            tc.key
+          FROM testcases tc
+          JOIN subtree st ON st.id = tc.suite_id
+          JOIN testcase_versions tv ON tv.testcase_id = tc.id
+          WHERE tc.is_archived = FALSE
+          ORDER BY tc.id, tv.version_number DESC
+        ),
+        inserted AS (
+          INSERT INTO run_items (run_id, testcase_version_id, position, is_required)
+          SELECT
+            $1,
+            testcase_version_id,
+            (ROW_NUMBER() OVER (ORDER BY sort_path, key))::int - 1,
+            is_required
+          FROM latest
+          ON CONFLICT (run_id, testcase_version_id) DO NOTHING
+          RETURNING id
+        )
+        INSERT INTO run_results (run_item_id, status, comment, updated_by_user_id)
+        SELECT id, 'na', '', $3 FROM inserted
+        "#,
+    )
+    .bind(run_id)
+    .bind(suite_id)
+    .bind(actor_id)
+    .execute(&mut **tx)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v2/projects/{project_id}/suites",
+    tag = "library",
+    params(("project_id" = String, Path)),
+    request_body = CreateSuiteRequest,
+    responses((status = 201, body = SuiteResponse))
+)]
+pub async fn create_suite(
+    State(state): State<AppState>,
+    access: ProjectRole,
+    Json(payload): Json<CreateSuiteRequest>,
+) -> Result<(StatusCode, Json<SuiteResponse>), ApiError> {
+    access.require(Capability::LibraryEdit)?;
+    let ProjectRole {
+        user_id: actor_id,
+        project_id: project_uuid,
+        ..
+    } = access;
+    let name = payload.name.trim().to_string();
+    validate_suite_name(&name)?;
+    let parent_id = match payload.parent_id.as_deref() {
+        Some(v) if !v.trim().is_empty() => Some(parse_uuid(v, ApiError::InvalidParentId)?),
+        _ => None,
+    };
+    ensure_db_user_exists(&state, &actor_id).await?;
+    let actor_uuid = parse_uuid(&actor_id, ApiError::InvalidUserId)?;
+
+    if let Some(parent_id) = parent_id {
+        ensure_suite_in_project(&state, parent_id, project_uuid).await?;
+    }
+
+    let key = payload
+        .key
+        .as_deref()
+        .map(str::trim)
+        .filter(|k| !k.is_empty())
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("suite-{}", &Uuid::new_v4().simple().to_string()[..8]));
+
+    let suite_id: Uuid = sqlx::query_scalar(
+        r#"
+        INSERT INTO test_suites (
+          project_id, parent_id, key, name, description, position,
+          created_by_user_id, updated_by_user_id
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $7)
+        RETURNING id
+        "#,
+    )
+    .bind(project_uuid)
+    .bind(parent_id)
+    .bind(key)
+    .bind(name)
+    .bind(payload.description.unwrap_or_default())
+    .bind(payload.position.unwrap_or(0))
+    .bind(actor_uuid)
+    .fetch_one(&state.db)
+    .await
+    .map_err(|err| db_errors::map(err, ApiError::SuiteRejected))?;
+
+    let suite = fetch_suite(&state, suite_id)
+        .await?
+        .ok_or(ApiError::SuiteCreatedNotFound)?;
+    Ok((StatusCode::CREATED, Json(SuiteResponse { suite })))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v2/projects/{project_id}/suites",
+    tag = "library",
+    params(("project_id" = String, Path)),
+    responses((status = 200, body = SuiteTreeResponse))
+)]
+pub async fn get_suite_tree(
+    State(state): State<AppState>,
+    access: ProjectRole,
+) -> Result<Json<SuiteTreeResponse>, ApiError> {
+    let project_uuid = access.project_id;
+    let sql = format!(
+        r#"
+        SELECT {SUITE_COLUMNS},
+          (
+            SELECT COUNT(*) FROM testcases tc
+            WHERE tc.suite_id = test_suites.id AND tc.is_archived = FALSE
+          ) AS testcase_count
+        FROM test_suites
+        WHERE project_id = $1 AND is_archived = FALSE
+        ORDER BY position ASC, name ASC
+        "#
+    );
+    let rows = sqlx::query(&sql)
+        .bind(project_uuid)
+        .fetch_all(&state.db)
+        .await
+        .map_err(|_| ApiError::SuitesReadFailed)?;
+
+    let flat: Vec<(SuiteView, i64)> = rows
+        .iter()
+        .map(|r| (map_suite_row(r), r.get::<i64, _>("testcase_count")))
+        .collect();
+
+    fn build(parent: Option<&str>, flat: &[(SuiteView, i64)]) -> Vec<SuiteNode> {
+        flat.iter()
+            .filter(|(s, _)| s.parent_id.as_deref() == parent)
+            .map(|(s, count)| SuiteNode {
+                suite: s.clone(),
+                testcase_count: *count,
+                children: build(Some(&s.id), flat),
+            })
+            .collect()
+    }
+
+    Ok(Json(SuiteTreeResponse {
+        suites: build(None, &flat),
+    }))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/api/v2/suites/{suite_id}",
+    tag = "library",
+    params(("suite_id" = String, Path)),
+    request_body = UpdateSuiteRequest,
+    responses((status = 200, body = SuiteResponse))
+)]
+pub async fn update_suite(
+    State(state): State<AppState>,
+    Path(suite_id): Path<String>,
+    AuthUser(actor_id): AuthUser,
+    Json(payload): Json<UpdateSuiteRequest>,
+) -> Result<Json<SuiteResponse>, ApiError> {
+    let name = payload.name.as_deref().map(str::trim).map(str::to_string);
+    if let Some(name) = name.as_deref() {
+        validate_suite_name(name)?;
+    }
+    let suite = load_suite_for_actor(&state, &suite_id, &actor_id, Capability::LibraryEdit).await?;
+    ensure_db_user_exists(&state, &actor_id).await?;
+    let actor_uuid = parse_uuid(&actor_id, ApiError::InvalidUserId)?;
+    let suite_uuid = parse_uuid(&suite.id, ApiError::InvalidSuiteId)?;
+
+    sqlx::query(
+        r#"
+        UPDATE test_suites
+        SET name = COALESCE($2, name),
+            description = COALESCE($3, description),
+            updated_by_user_id = $4
+        WHERE id = $1
+        "#,
+    )
+    .bind(suite_uuid)
+    .bind(name)
+    .bind(payload.description)
+    .bind(actor_uuid)
+    .execute(&state.db)
+    .await
+    .map_err(|err| db_errors::map(err, ApiError::SuiteUpdateRejected))?;
+
+    let suite = fetch_suite(&state, suite_uuid)
+        .await?
+        .ok_or(ApiError::SuiteNotFound)?;
+    Ok(Json(SuiteResponse { suite }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v2/suites/{suite_id}/move",
+    tag = "library",
+    params(("suite_id" = String, Path)),
+    request_body = MoveSuiteRequest,
+    responses((status = 200, body = SuiteResponse))
+)]
+pub async fn move_suite(
+    State(state): State<AppState>,
+    Path(suite_id): Path<String>,
+    AuthUser(actor_id): AuthUser,
+    Json(payload): Json<MoveSuiteRequest>,
+) -> Result<Json<SuiteResponse>, ApiError> {
+    let parent_id = match payload.parent_id.as_deref() {
+        Some(v) if !v.trim().is_empty() => Some(parse_uuid(v, ApiError::InvalidParentId)?),
+        _ => None,
+    };
+    let suite = load_suite_for_actor(&state, &suite_id, &actor_id, Capability::LibraryEdit).await?;
+    ensure_db_user_exists(&state, &actor_id).await?;
+    let actor_uuid = parse_uuid(&actor_id, ApiError::InvalidUserId)?;
+    let suite_uuid = parse_uuid(&suite.id, ApiError::InvalidSuiteId)?;
+    let project_uuid = parse_uuid(&suite.project_id, ApiError::InvalidProjectId)?;
+
+    if let Some(parent_id) = parent_id {
+        ensure_suite_in_project(&state, parent_id, project_uuid).await?;
+        let creates_cycle: bool = sqlx::query_scalar(
+            r#"
+            WITH RECURSIVE subtree AS (
+              SELECT id FROM test_suites WHERE id = $1
+              UNION ALL
+              SELECT s.id FROM test_suites s JOIN subtree st ON s.parent_id = st.id
+            )
+            SELECT EXISTS (SELECT 1 FROM subtree WHERE id = $2)
+            "#,
+        )
+        .bind(suite_uuid)
+        .bind(parent_id)
+        .fetch_one(&state.db)
+        .await
+        .map_err(|_| ApiError::SuiteHierarchyCheckFailed)?;
+        if creates_cycle {
+            return Err(ApiError::SuiteCycle);
+        }
+    }
+
+    sqlx::query(
+        r#"
+        UPDATE test_suites
+        SET parent_id = $2,
+            position = COALESCE($3, position),
+            updated_by_user_id = $4
+        WHERE id = $1
+        "#,
+    )
+    .bind(suite_uuid)
+    .bind(parent_id)
+    .bind(payload.position)
+    .bind(actor_uuid)
+    .execute(&state.db)
+    .await
+    .map_err(|err| db_errors::map(err, ApiError::SuiteMoveRejected))?;
+
+    let suite = fetch_suite(&state, suite_uuid)
+        .await?
+        .ok_or(ApiError::SuiteNotFound)?;
+    Ok(Json(SuiteResponse { suite }))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/api/v2/testcases/{testcase_id}/suite",
+    tag = "library",
+    params(("testcase_id" = String, Path)),
+    request_body = AssignTestcaseSuiteRequest,
+    responses((status = 200, body = AssignTestcaseSuiteResponse))
+)]
+pub async fn assign_testcase_suite(
+    State(state): State<AppState>,
+    Path(testcase_id): Path<String>,
+    AuthUser(actor_id): AuthUser,
+    Json(payload): Json<AssignTestcaseSuiteRequest>,
+) -> Result<Json<AssignTestcaseSuiteResponse>, ApiError> {
+    let testcase_uuid = parse_uuid(&testcase_id, ApiError::InvalidTestcaseId)?;
+    let target = load_suite_for_actor(
+        &state,
+        &payload.suite_id,
+        &actor_id,
+        Capability::LibraryEdit,
+    )
+    .await?;
+    ensure_db_user_exists(&state, &actor_id).await?;
+    let actor_uuid = parse_uuid(&actor_id, ApiError::InvalidUserId)?;
+    let target_uuid = parse_uuid(&target.id, ApiError::InvalidSuiteId)?;
+
+    let current_project: Option<String> = sqlx::query_scalar(
+        r#"
+        SELECT s.project_id::text
+        FROM testcases tc
+        JOIN test_suites s ON s.id = tc.suite_id
+        WHERE tc.id = $1
+        "#,
+    )
+    .bind(testcase_uuid)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|_| ApiError::TestcaseReadFailed)?;
+    let current_project = current_project.ok_or(ApiError::TestcaseNotFound)?;
+    if current_project != target.project_id {
+        return Err(ApiError::TestcaseSuiteProjectMismatch);
+    }
+
+    sqlx::query(
+        r#"
+        UPDATE testcases
+        SET suite_id = $2,
+            updated_by_user_id = $3
+        WHERE id = $1
+        "#,
+    )
+    .bind(testcase_uuid)
+    .bind(target_uuid)
+    .bind(actor_uuid)
+    .execute(&state.db)
+    .await
+    .map_err(|err| db_errors::map(err, ApiError::TestcaseKeyConflict))?;
+
+    Ok(Json(AssignTestcaseSuiteResponse {
+        ok: true,
+        testcase_id: testcase_uuid.to_string(),
+        suite_id: target.id,
+    }))
+}
@@ -0,0 +1,712 @@
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::Row;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{
+    audit,
+    authz::{AuthUser, ProjectRole},
+    ensure_db_user_exists,
+    error::ApiError,
+    parse_uuid,
+    permissions::Capability,
+    testcases, AppState,
+};
+
+const MAX_TAGS_PER_TESTCASE: usize = 50;
+const MAX_QUERY_CHARS: usize = 500;
+const MAX_QUERY_TAGS: usize = 32;
+
+/// Tag names: 1–100 characters without quotes, parentheses and commas, which are used by
+/// tag queries and CSV import.
+fn normalize_tag(tag: &str) -> Result<String, ApiError> {
+    let tag = tag.trim();
+    let valid = (1..=100).contains(&tag.chars().count())
+        && !tag
+            .chars()
+            .any(|c| c.is_control() || matches!(c, '"' | '(' | ')' | ','));
+    if !valid {
+        return Err(ApiError::InvalidTagName);
+    }
+    Ok(tag.to_string())
+}
+
+/// Normalized, case-insensitively unique tags in the given order.
+fn normalize_tags(tags: &[String]) -> Result<Vec<String>, ApiError> {
+    let mut normalized: Vec<String> = Vec::with_capacity(tags.len());
+    for tag in tags {
+        let tag = normalize_tag(tag)?;
+        if !normalized.iter().any(|t| t.eq_ignore_ascii_case(&tag)) {
+            normalized.push(tag);
+        }
+    }
+    Ok(normalized)
+}
+
+/// Boolean expression over tags, e.g. `smoke AND (payments OR "new ui") AND NOT slow`.
+/// `NOT` binds tighter than `AND`, `AND` tighter than `OR`; keywords are case-insensitive
+/// and names are matched case-insensitively.
+pub enum TagQuery {
+    Tag(String),
+    Not(Box<TagQuery>),
+    And(Box<TagQuery>, Box<TagQuery>),
+    Or(Box<TagQuery>, Box<TagQuery>),
+}
+
+#[derive(PartialEq)]
+enum Token {
+    Open,
+    Close,
+    And,
+    Or,
+    Not,
+    Name(String),
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, ApiError> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::Open);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::Close);
+            }
+            '"' => {
+                chars.next();
+                let mut name = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some(c) => name.push(c),
+                        None => return Err(ApiError::InvalidTagQuery),
+                    }
+                }
+                tokens.push(Token::Name(name));
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || matches!(c, '(' | ')' | '"') {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                tokens.push(match word.to_ascii_uppercase().as_str() {
+                    "AND" => Token::And,
+                    "OR" => Token::Or,
+                    "NOT" => Token::Not,
+                    _ => Token::Name(word),
+                });
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    tags: usize,
+}
+
+impl Parser {
+    fn eat(&mut self, token: Token) -> bool {
+        if self.tokens.get(self.pos) == Some(&token) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn or(&mut self) -> Result<TagQuery, ApiError> {
+        let mut left = self.and()?;
+        while self.eat(Token::Or) {
+            left = TagQuery::Or(Box::new(left), Box::new(self.and()?));
+        }
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<TagQuery, ApiError> {
+        let mut left = self.not()?;
+        while self.eat(Token::And) {
+            left = TagQuery::And(Box::new(left), Box::new(self.not()?));
+        }
+        Ok(left)
+    }
+
+    fn not(&mut self) -> Result<TagQuery, ApiError> {
+        if self.eat(Token::Not) {
+            return Ok(TagQuery::Not(Box::new(self.not()?)));
+        }
+        if self.eat(Token::Open) {
+            let inner = self.or()?;
+            if !self.eat(Token::Close) {
+                return Err(ApiError::InvalidTagQuery);
+            }
+            return Ok(inner);
+        }
+        match self.tokens.get(self.pos) {
+            Some(Token::Name(name)) => {
+                let tag = normalize_tag(name).map_err(|_| ApiError::InvalidTagQuery)?;
+                self.pos += 1;
+                self.tags += 1;
+                if self.tags > MAX_QUERY_TAGS {
+                    return Err(ApiError::InvalidTagQuery);
+                }
+                Ok(TagQuery::Tag(tag))
+            }
+            _ => Err(ApiError::InvalidTagQuery),
+        }
+    }
+}
+
+impl TagQuery {
+    pub fn parse(input: &str) -> Result<Self, ApiError> {
+        if input.chars().count() > MAX_QUERY_CHARS {
+            return Err(ApiError::InvalidTagQuery);
+        }
+        let mut parser = Parser {
+            tokens: tokenize(input)?,
+            pos: 0,
+            tags: 0,
+        };
+        let query = parser.or()?;
+        if parser.pos != parser.tokens.len() {
+            return Err(ApiError::InvalidTagQuery);
+        }
+        Ok(query)
+    }
+
+    /// SQL predicate over `tc` (a `testcases` row); tag names are pushed to `params` and
+    /// referenced as `$first_param + i`.
+    fn to_sql(&self, first_param: usize, params: &mut Vec<String>) -> String {
+        match self {
+            TagQuery::Tag(name) => {
+                params.push(name.clone());
+                format!(
+                    "EXISTS (SELECT 1 FROM testcase_tags tt JOIN tags t ON t.id = tt.tag_id \
+                     WHERE tt.testcase_id = tc.id AND t.name = ${}::citext)",
+                    first_param + params.len() - 1
+                )
+            }
+            TagQuery::Not(inner) => format!("NOT {}", inner.to_sql(first_param, params)),
+            TagQuery::And(left, right) => format!(
+                "({} AND {})",
+                left.to_sql(first_param, params),
+                right.to_sql(first_param, params)
+            ),
+            TagQuery::Or(left, right) => format!(
+                "({} OR {})",
+                left.to_sql(first_param, params),
+                right.to_sql(first_param, params)
+            ),
+        }
+    }
+}
+
+/// Adds the latest version of every active testcase of the project (or of the suite subtree
+/// when `suite_id` is set) that matches `query` to the run, in suite tree order.
+pub async fn expand_tag_query_into_run(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    run_id: Uuid,
+    project_id: Uuid,
+    suite_id: Option<Uuid>,
+    query: &TagQuery,
+    actor_id: Uuid,
+) -> Result<u64, sqlx::Error> {
+    let mut names = Vec::new();
+    let predicate = query.to_sql(5, &mut names);
+    let sql = format!(
+        r#"
+        WITH RECURSIVE subtree AS (
+          SELECT id, ARRAY[position] AS sort_path
+          FROM test_suites
+          WHERE project_id = $2
+            AND CASE WHEN $3::uuid IS NULL THEN parent_id IS NULL AND is_archived = FALSE
+                     ELSE id = $3 END
+          UNION ALL
+          SELECT s.id, st.sort_path || s.position
+          FROM test_suites s
+          JOIN subtree st ON s.parent_id = st.id
+          WHERE s.is_archived = FALSE
+        ),
+        latest AS (
+          SELECT DISTINCT ON (tc.id)
+            tv.id AS testcase_version_id,
+            tc.is_required,
+            st.sort_path,
+            tc.key
+          FROM testcases tc
+          JOIN subtree st ON st.id = tc.suite_id
+          JOIN testcase_versions tv ON tv.testcase_id = tc.id
+          WHERE tc.is_archived = FALSE AND {predicate}
+          ORDER BY tc.id, tv.version_number DESC
+        ),
+        inserted AS (
+          INSERT INTO run_items (run_id, testcase_version_id, position, is_required)
+          SELECT
+            $1,
+            testcase_version_id,
+            (ROW_NUMBER() OVER (ORDER BY sort_path, key))::int - 1,
+            is_required
+          FROM latest
+          ON CONFLICT (run_id, testcase_version_id) DO NOTHING
+          RETURNING id
+        )
+        INSERT INTO run_results (run_item_id, status, comment, updated_by_user_id)
+        SELECT id, 'na', '', $4 FROM inserted
+        "#
+    );
+    let mut statement = sqlx::query(&sql)
+        .bind(run_id)
+        .bind(project_id)
+        .bind(suite_id)
+        .bind(actor_id);
+    for name in &names {
+        statement = statement.bind(name);
+    }
+    let result = statement.execute(&mut **tx).await?;
+    Ok(result.rows_affected())
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct TestcaseTagsRequest {
+    tags: Vec<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct TestcaseTagsResponse {
+    tags: Vec<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectTagView {
+    name: String,
+    /// Active testcases of the project with the tag.
+    testcase_count: i64,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ListProjectTagsResponse {
+    tags: Vec<ProjectTagView>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct RenameTagRequest {
+    /// New name; when the project already uses it, the tags are merged.
+    name: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct DeleteTagResponse {
+    ok: bool,
+    /// Testcases the tag was removed from.
+    removed: u64,
+}
+
+async fn testcase_tags(
+    executor: impl sqlx::PgExecutor<'_>,
+    testcase_id: Uuid,
+) -> Result<Vec<String>, ApiError> {
+    sqlx::query_scalar(
+        r#"
+        SELECT t.name::text
+        FROM testcase_tags tt
+        JOIN tags t ON t.id = tt.tag_id
+        WHERE tt.testcase_id = $1
+        ORDER BY lower(t.name::text)
+        "#,
+    )
+    .bind(testcase_id)
+    .fetch_all(executor)
+    .await
+    .map_err(|_| ApiError::TagsReadFailed)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v2/testcases/{testcase_id}/tags",
+    tag = "library",
+    params(("testcase_id" = String, Path)),
+    responses((status = 200, body = TestcaseTagsResponse))
+)]
+pub async fn get_testcase_tags(
+    State(state): State<AppState>,
+    Path(testcase_id): Path<String>,
+    AuthUser(actor_id): AuthUser,
+) -> Result<Json<TestcaseTagsResponse>, ApiError> {
+    let testcase_uuid = parse_uuid(&testcase_id, ApiError::InvalidTestcaseId)?;
+    testcases::authorize_testcase(&state, testcase_uuid, &actor_id, Capability::ProjectRead)
+        .await?;
+    let tags = testcase_tags(&state.db, testcase_uuid).await?;
+    Ok(Json(TestcaseTagsResponse { tags }))
+}
+
+/// Replaces the testcase tags.
+#[utoipa::path(
+    put,
+    path = "/api/v2/testcases/{testcase_id}/tags",
+    tag = "library",
+    params(("testcase_id" = String, Path)),
+    request_body = TestcaseTagsRequest,
+    responses((status = 200, body = TestcaseTagsResponse))
+)]
+pub async fn replace_testcase_tags(
+    State(state): State<AppState>,
+    Path(testcase_id): Path<String>,
+    AuthUser(actor_id): AuthUser,
+    Json(payload): Json<TestcaseTagsRequest>,
+) -> Result<Json<TestcaseTagsResponse>, ApiError> {
+    write_testcase_tags(state, testcase_id, actor_id, payload.tags, true).await
+}
+
+/// Adds tags to the testcase, keeping the ones it has.
+#[utoipa::path(
+    post,
+    path = "/api/v2/testcases/{testcase_id}/tags",
+    tag = "library",
+    params(("testcase_id" = String, Path)),
+    request_body = TestcaseTagsRequest,
+    responses((status = 200, body = TestcaseTagsResponse))
+)]
+pub async fn add_testcase_tags(
+    State(state): State<AppState>,
+    Path(testcase_id): Path<String>,
+    AuthUser(actor_id): AuthUser,
+    Json(payload): Json<TestcaseTagsRequest>,
+) -> Result<Json<TestcaseTagsResponse>, ApiError> {
+    write_testcase_tags(state, testcase_id, actor_id, payload.tags, false).await
+}
+
+async fn write_testcase_tags(
+    state: AppState,
+    testcase_id: String,
+    actor_id: String,
+    tags: Vec<String>,
+    replace: bool,
+) -> Result<Json<TestcaseTagsResponse>, ApiError> {
+    let testcase_uuid = parse_uuid(&testcase_id, ApiError::InvalidTestcaseId)?;
+    let tags = normalize_tags(&tags)?;
+    let project_id =
+        testcases::authorize_testcase(&state, testcase_uuid, &actor_id, Capability::LibraryEdit)
+            .await?;
+    ensure_db_user_exists(&state, &actor_id).await?;
+    let actor_uuid = parse_uuid(&actor_id, ApiError::InvalidUserId)?;
+
+    let update_failed = |_| ApiError::TagsUpdateFailed;
+    let mut tx = state.db.begin().await.map_err(update_failed)?;
+    sqlx::query("SELECT id FROM testcases WHERE id = $1 FOR UPDATE")
+        .bind(testcase_uuid)
+        .execute(&mut *tx)
+        .await
+        .map_err(update_failed)?;
+    let before = testcase_tags(&mut *tx, testcase_uuid).await?;
+    if replace {
+        sqlx::query("DELETE FROM testcase_tags WHERE testcase_id = $1")
+            .bind(testcase_uuid)
+            .execute(&mut *tx)
+            .await
+            .map_err(update_failed)?;
+    }
+    sqlx::query(
+        r#"
+        WITH upserted AS (
+          INSERT INTO tags (name)
+          SELECT t FROM UNNEST($2::text[]) AS t
+          ON CONFLICT (name) DO UPDATE SET name = tags.name
+          RETURNING id
+        )
+        INSERT INTO testcase_tags (testcase_id, tag_id)
+        SELECT $1, id FROM upserted
+        ON CONFLICT DO NOTHING
+        "#,
+    )
+    .bind(testcase_uuid)
+    .bind(&tags)
+    .execute(&mut *tx)
+    .await
+    .map_err(update_failed)?;
+    let after = testcase_tags(&mut *tx, testcase_uuid).await?;
+    if after.len() > MAX_TAGS_PER_TESTCASE {
+        return Err(ApiError::TooManyTags);
+    }
+    sqlx::query(
+        r#"
+        UPDATE testcases
+        SET updated_at = NOW(), updated_by_user_id = $2
+        WHERE id = $1
+        "#,
+    )
+    .bind(testcase_uuid)
+    .bind(actor_uuid)
+    .execute(&mut *tx)
+    .await
+    .map_err(update_failed)?;
+    audit::record(
+        &mut *tx,
+        audit::AuditEntry {
+            actor_user_id: Some(actor_uuid),
+            action: "update",
+            entity_type: "testcase",
+            entity_id: Some(testcase_uuid),
+            project_id: Some(project_id),
+            run_id: None,
+            before: Some(json!({ "tags": before })),
+            after: Some(json!({ "tags": &after })),
+        },
+    )
+    .await
+    .map_err(update_failed)?;
+    tx.commit().await.map_err(update_failed)?;
+
+    Ok(Json(TestcaseTagsResponse { tags: after }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v2/projects/{project_id}/tags",
+    tag = "library",
+    params(("project_id" = String, Path)),
+    responses((status = 200, body = ListProjectTagsResponse))
+)]
+pub async fn list_project_tags(
+    State(state): State<AppState>,
+    access: ProjectRole,
+) -> Result<Json<ListProjectTagsResponse>, ApiError> {
+    let rows = sqlx::query(
+        r#"
+        SELECT t.name::text AS name, COUNT(*) AS testcase_count
+        FROM tags t
+        JOIN testcase_tags tt ON tt.tag_id = t.id
+        JOIN testcases tc ON tc.id = tt.testcase_id
+        JOIN test_suites s ON s.id = tc.suite_id
+        WHERE s.project_id = $1 AND tc.is_archived = FALSE
+        GROUP BY t.id
+        ORDER BY lower(t.name::text)
+        "#,
+    )
+    .bind(access.project_id)
+    .fetch_all(&state.db)
+    .await
+    .map_err(|_| ApiError::TagsReadFailed)?;
+    Ok(Json(ListProjectTagsResponse {
+        tags: rows
+            .into_iter()
+            .map(|r| ProjectTagView {
+                name: r.get("name"),
+                testcase_count: r.get("testcase_count"),
+            })
+            .collect(),
+    }))
+}
+
+/// Renames a tag on the project's testcases; other projects keep the old name.
+#[utoipa::path(
+    patch,
+    path = "/api/v2/projects/{project_id}/tags/{tag}",
+    tag = "library",
+    params(("project_id" = String, Path), ("tag" = String, Path)),
+    request_body = RenameTagRequest,
+    responses((status = 200, body = ProjectTagView))
+)]
+pub async fn rename_project_tag(
+    State(state): State<AppState>,
+    Path((_project_id, tag)): Path<(String, String)>,
+    access: ProjectRole,
+    Json(payload): Json<RenameTagRequest>,
+) -> Result<Json<ProjectTagView>, ApiError> {
+    access.require(Capability::LibraryEdit)?;
+    let ProjectRole {
+        user_id: actor_id,
+        project_id,
+        ..
+    } = access;
+    let name = normalize_tag(&payload.name)?;
+    ensure_db_user_exists(&state, &actor_id).await?;
+    let actor_uuid = parse_uuid(&actor_id, ApiError::InvalidUserId)?;
+
+    let update_failed = |_| ApiError::TagsUpdateFailed;
+    let mut tx = state.db.begin().await.map_err(update_failed)?;
+    let target: Uuid = sqlx::query_scalar(
+        r#"
+        INSERT INTO tags (name) VALUES ($1)
+        ON CONFLICT (name) DO UPDATE SET name = tags.name
+        RETURNING id
+        "#,
+    )
+    .bind(&name)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(update_failed)?;
+    // Relinks the project's testcases; a testcase that already has the target tag just
+    // loses the old one.
+    let moved: Vec<Uuid> = sqlx::query_scalar(
+        r#"
+        DELETE FROM testcase_tags tt
+        USING tags t, testcases tc, test_suites s
+        WHERE t.id = tt.tag_id AND tc.id = tt.testcase_id AND s.id = tc.suite_id
+          AND s.project_id = $1 AND t.name = $2::citext AND tt.tag_id <> $3
+        RETURNING tt.testcase_id
+        "#,
+    )
+    .bind(project_id)
+    .bind(&tag)
+    .bind(target)
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(update_failed)?;
+    sqlx::query(
+        r#"
+        INSERT INTO testcase_tags (testcase_id, tag_id)
+        SELECT UNNEST($1::uuid[]), $2
+        ON CONFLICT DO NOTHING
+        "#,
+    )
+    .bind(&moved)
+    .bind(target)
+    .execute(&mut *tx)
+    .await
+    .map_err(update_failed)?;
+    let linked: i64 = sqlx::query_scalar(
+        r#"
+        SELECT COUNT(*)
+        FROM testcase_tags tt
+        JOIN testcases tc ON tc.id = tt.testcase_id
+        JOIN test_suites s ON s.id = tc.suite_id
+        WHERE s.project_id = $1 AND tt.tag_id = $2 AND tc.is_archived = FALSE
+        "#,
+    )
+    .bind(project_id)
+    .bind(target)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(update_failed)?;
+    // A case-only rename (`Smoke` → `smoke`) keeps the same tag row, so nothing moves.
+    let same_tag = tag.trim().eq_ignore_ascii_case(&name);
+    if moved.is_empty() && !(same_tag && linked > 0) {
+        return Err(ApiError::TagNotFound);
+    }
+    if same_tag {
+        sqlx::query("UPDATE tags SET name = $2 WHERE id = $1")
+            .bind(target)
+            .bind(&name)
+            .execute(&mut *tx)
+            .await
+            .map_err(update_failed)?;
+    }
+    delete_orphan_tags(&mut tx).await.map_err(update_failed)?;
+    audit::record(
+        &mut *tx,
+        audit::AuditEntry {
+            actor_user_id: Some(actor_uuid),
+            action: "update",
+            entity_type: "tag",
+            entity_id: Some(target),
+            project_id: Some(project_id),
+            run_id: None,
+            before: Some(json!({ "name": tag })),
+            after: Some(json!({ "name": &name })),
+        },
+    )
+    .await
+    .map_err(update_failed)?;
+    tx.commit().await.map_err(update_failed)?;
+
+    Ok(Json(ProjectTagView {
+        name,
+        testcase_count: linked,
+    }))
+}
+
+/// Removes a tag from all testcases of the project.
+#[utoipa::path(
+    delete,
+    path = "/api/v2/projects/{project_id}/tags/{tag}",
+    tag = "library",
+    params(("project_id" = String, Path), ("tag" = String, Path)),
+    responses((status = 200, body = DeleteTagResponse))
+)]
+pub async fn delete_project_tag(
+    State(state): State<AppState>,
+    Path((_project_id, tag)): Path<(String, String)>,
+    access: ProjectRole,
+) -> Result<Json<DeleteTagResponse>, ApiError> {
+    access.require(Capability::LibraryEdit)?;
+    let ProjectRole {
+        user_id: actor_id,
+        project_id,
+        ..
+    } = access;
+    ensure_db_user_exists(&state, &actor_id).await?;
+    let actor_uuid = parse_uuid(&actor_id, ApiError::InvalidUserId)?;
+
+    let delete_failed = |_| ApiError::TagsUpdateFailed;
+    let mut tx = state.db.begin().await.map_err(delete_failed)?;
+    let removed = sqlx::query(
+        r#"
+        DELETE FROM testcase_tags tt
+        USING tags t, testcases tc, test_suites s
+        WHERE t.id = tt.tag_id AND tc.id = tt.testcase_id AND s.id = tc.suite_id
+          AND s.project_id = $1 AND t.name = $2::citext
+        "#,
+    )
+    .bind(project_id)
+    .bind(&tag)
+    .execute(&mut *tx)
+    .await
+    .map_err(delete_failed)?
+    .rows_affected();
+    if removed == 0 {
+        return Err(ApiError::TagNotFound);
+    }
+    delete_orphan_tags(&mut tx).await.map_err(delete_failed)?;
+    audit::record(
+        &mut *tx,
+        audit::AuditEntry {
+            actor_user_id: Some(actor_uuid),
+            action: "delete",
+            entity_type: "tag",
+            entity_id: None,
+            project_id: Some(project_id),
+            run_id: None,
+            before: Some(json!({ "name": tag, "testcases": removed })),
+            after: None,
+        },
+    )
+    .await
+    .map_err(delete_failed)?;
+    tx.commit().await.map_err(delete_failed)?;
+
+    Ok(Json(DeleteTagResponse { ok: true, removed }))
+}
+
+/// Tags are shared between projects by name; a name no testcase uses any more is dropped.
+async fn delete_orphan_tags(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        DELETE FROM tags t
+        WHERE NOT EXISTS (SELECT 1 FROM testcase_tags tt WHERE tt.tag_id = t.id)
+        "#,
+    )
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,650 @@
+use std::time::Duration;
+
+use axum::{extract::State, http::StatusCode, Json};
+use chrono::{DateTime, Utc};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::sync::OnceCell;
+use tracing::warn;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{
+    audit::{self, AuditEntry},
+    authz::{self, AuthUser},
+    config::TelegramConfig,
+    ensure_db_user_exists,
+    error::{ApiError, Lang},
+    etag::{Precondition, VersionError},
+    jobs,
+    notifications::{self, Channel, NotificationKind},
+    parse_uuid, record_run_result, AppState, UpdateRunResultRequest,
+};
+
+/// Long-polling wait of `getUpdates`; the HTTP timeout leaves room on top of it.
+const POLL_TIMEOUT_SECS: u64 = 30;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(POLL_TIMEOUT_SECS + 15);
+/// Pause after a failed poll, and between attempts of an instance that is not the poller.
+const RETRY_DELAY: Duration = Duration::from_secs(15);
+/// Advisory lock held by the instance that polls; Telegram allows one consumer per bot.
+const POLLER_LOCK_KEY: i64 = 0x7572_616e_7467_6d00;
+const LINK_CODE_TTL_MINUTES: i32 = 15;
+/// Buttons sent with assignment messages, `(label, status)`.
+const RESULT_BUTTONS: [(&str, &str); 2] = [("✅ OK", "ok"), ("❌ FAIL", "fail")];
+
+const HELP_TEXT: &str = "Это бот системы тестирования Uran. Чтобы получать сюда назначения и \
+FAIL обязательных тестов, откройте ссылку привязки из профиля Uran. Результат назначенного \
+теста можно записать кнопками под сообщением.\n\n/stop — отвязать этот чат.";
+
+/// Bot API client; the bot's username is fetched once, for link URLs.
+pub struct TelegramBot {
+    http: reqwest::Client,
+    base_url: String,
+    username: OnceCell<String>,
+}
+
+#[derive(Deserialize)]
+struct ApiResponse<T> {
+    ok: bool,
+    result: Option<T>,
+    description: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct BotUser {
+    id: i64,
+    username: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Chat {
+    id: i64,
+    #[serde(rename = "type")]
+    kind: String,
+}
+
+#[derive(Deserialize)]
+struct Message {
+    chat: Chat,
+    from: Option<BotUser>,
+    text: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct CallbackQuery {
+    id: String,
+    from: BotUser,
+    data: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Update {
+    message: Option<Message>,
+    callback_query: Option<CallbackQuery>,
+}
+
+impl TelegramBot {
+    pub fn new(config: &TelegramConfig) -> anyhow::Result<Self> {
+        Ok(Self {
+            http: reqwest::Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .build()?,
+            base_url: format!("{}/bot{}", config.api_url, config.token),
+            username: OnceCell::new(),
+        })
+    }
+
+    async fn call<T: DeserializeOwned>(&self, method: &str, params: Value) -> anyhow::Result<T> {
+        // The URL contains the token, so it is kept out of errors that end up in logs.
+        let response: ApiResponse<T> = self
+            .http
+            .post(format!("{}/{method}", self.base_url))
+            .json(&params)
+            .send()
+            .await
+            .map_err(reqwest::Error::without_url)?
+            .json()
+            .await
+            .map_err(reqwest::Error::without_url)?;
+        match response.result {
+            Some(result) if response.ok => Ok(result),
+            _ => anyhow::bail!(
+                "{method} failed: {}",
+                response.description.unwrap_or_default()
+            ),
+        }
+    }
+
+    async fn username(&self) -> anyhow::Result<&str> {
+        let username = self
+            .username
+            .get_or_try_init(|| async {
+                let me: BotUser = self.call("getMe", json!({})).await?;
+                me.username
+                    .ok_or_else(|| anyhow::anyhow!("the bot has no username"))
+            })
+            .await?;
+        Ok(username)
+    }
+
+    async fn send_message(
+        &self,
+        chat_id: i64,
+        text: &str,
+        reply_markup: Option<Value>,
+    ) -> anyhow::Result<()> {
+        let mut params = json!({ "chat_id": chat_id, "text": text });
+        if let Some(markup) = reply_markup {
+            params["reply_markup"] = markup;
+        }
+        self.call::<Value>("sendMessage", params).await?;
+        Ok(())
+    }
+
+    async fn answer_callback(&self, callback_id: &str, text: &str, alert: bool) {
+        let answered = self
+            .call::<Value>(
+                "answerCallbackQuery",
+                json!({ "callback_query_id": callback_id, "text": text, "show_alert": alert }),
+            )
+            .await;
+        if let Err(err) = answered {
+            warn!("failed to answer a telegram callback: {err:#}");
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TelegramLinkView {
+    linked: bool,
+    /// Telegram username of the linked account, if it has one.
+    username: Option<String>,
+    linked_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TelegramLinkCodeResponse {
+    /// Opens the bot with `/start <code>`, which links the chat to the caller.
+    url: String,
+    code: String,
+    expires_at: DateTime<Utc>,
+}
+
+/// Whether the caller's account is linked to a Telegram chat.
+#[utoipa::path(
+    get,
+    path = "/api/v2/me/telegram",
+    tag = "notifications",
+    responses((status = 200, body = TelegramLinkView))
+)]
+pub async fn get_link(
+    State(state): State<AppState>,
+    AuthUser(user_id): AuthUser,
+) -> Result<Json<TelegramLinkView>, ApiError> {
+    state.telegram.as_ref().ok_or(ApiError::TelegramDisabled)?;
+    let user_uuid = parse_uuid(&user_id, ApiError::InvalidUserId)?;
+    let link = sqlx::query!(
+        "SELECT username, linked_at FROM telegram_links WHERE user_id = $1",
+        user_uuid,
+    )
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|_| ApiError::TelegramLinkReadFailed)?;
+    Ok(Json(match link {
+        Some(link) => TelegramLinkView {
+            linked: true,
+            username: link.username,
+            linked_at: Some(link.linked_at),
+        },
+        None => TelegramLinkView {
+            linked: false,
+            username: None,
+            linked_at: None,
+        },
+    }))
+}
+
+/// Issues a one-time link code, replacing the caller's previous one. The chat in which the
+/// code is sent to the bot replaces any chat linked before.
+#[utoipa::path(
+    post,
+    path = "/api/v2/me/telegram/link",
+    tag = "notifications",
+    responses((status = 200, body = TelegramLinkCodeResponse))
+)]
+pub async fn create_link_code(
+    State(state): State<AppState>,
+    AuthUser(user_id): AuthUser,
+) -> Result<Json<TelegramLinkCodeResponse>, ApiError> {
+    let bot = state.telegram.as_ref().ok_or(ApiError::TelegramDisabled)?;
+    ensure_db_user_exists(&state, &user_id).await?;
+    let user_uuid = parse_uuid(&user_id, ApiError::InvalidUserId)?;
+    let username = bot.username().await.map_err(|err| {
+        warn!("failed to read the telegram bot username: {err:#}");
+        ApiError::TelegramUnavailable
+    })?;
+
+    let code = Uuid::new_v4().simple().to_string();
+    let expires_at = sqlx::query_scalar!(
+        r#"
+        INSERT INTO telegram_link_codes (code, user_id, expires_at)
+        VALUES ($1, $2, NOW() + make_interval(mins => $3))
+        ON CONFLICT (user_id) DO UPDATE
+          SET code = EXCLUDED.code, expires_at = EXCLUDED.expires_at
+        RETURNING expires_at
+        "#,
+        code,
+        user_uuid,
+        LINK_CODE_TTL_MINUTES,
+    )
+    .fetch_one(&state.db)
+    .await
+    .map_err(|_| ApiError::TelegramLinkFailed)?;
+
+    Ok(Json(TelegramLinkCodeResponse {
+        url: format!("https://t.me/{username}?start={code}"),
+        code,
+        expires_at,
+    }))
+}
+
+/// Unlinks the caller's Telegram chat; the same as `/stop` in the bot.
+#[utoipa::path(
+    delete,
+    path = "/api/v2/me/telegram",
+    tag = "notifications",
+    responses((status = 204))
+)]
+pub async fn delete_link(
+    State(state): State<AppState>,
+    AuthUser(user_id): AuthUser,
+) -> Result<StatusCode, ApiError> {
+    state.telegram.as_ref().ok_or(ApiError::TelegramDisabled)?;
+    let user_uuid = parse_uuid(&user_id, ApiError::InvalidUserId)?;
+    let chat_id = sqlx::query_scalar!(
+        "DELETE FROM telegram_links WHERE user_id = $1 RETURNING chat_id",
+        user_uuid,
+    )
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|_| ApiError::TelegramLinkFailed)?;
+    if let Some(chat_id) = chat_id {
+        audit_unlink(&state, user_uuid, chat_id).await;
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Queues a `telegram_message` job for a notification; buttons for recording the result are
+/// added when `run_item_id` is set. Does nothing without a bot.
+pub fn submit(
+    state: &AppState,
+    kind: NotificationKind,
+    project_id: Option<Uuid>,
+    user_id: &str,
+    subject: &str,
+    body: &str,
+    run_item_id: Option<Uuid>,
+) {
+    if state.telegram.is_none() {
+        return;
+    }
+    jobs::submit_in_background(
+        state,
+        jobs::NewJob {
+            kind: jobs::JobKind::TelegramMessage,
+            project_id: None,
+            created_by_user_id: None,
+            payload: json!({
+                "kind": kind.column(),
+                "projectId": project_id,
+                "userId": user_id,
+                "text": format!("{subject}\n\n{body}"),
+                "runItemId": run_item_id,
+            }),
+        },
+    );
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MessageJob {
+    kind: String,
+    #[serde(default)]
+    project_id: Option<Uuid>,
+    user_id: Uuid,
+    text: String,
+    #[serde(default)]
+    run_item_id: Option<Uuid>,
+}
+
+/// Job handler of [`submit`]: sends the message if the user has a linked chat and wants this
+/// kind of notification from the project in the chat channel.
+pub async fn run_message_job(state: &AppState, payload: Value) -> anyhow::Result<Value> {
+    let Some(bot) = state.telegram.as_deref() else {
+        return Ok(Value::Null);
+    };
+    let job: MessageJob = serde_json::from_value(payload)?;
+    let kind = NotificationKind::parse(&job.kind)
+        .ok_or_else(|| anyhow::anyhow!("unknown notification kind {}", job.kind))?;
+    let chat_id = sqlx::query_scalar!(
+        "SELECT chat_id FROM telegram_links WHERE user_id = $1",
+        job.user_id,
+    )
+    .fetch_optional(&state.db)
+    .await?;
+    let Some(chat_id) = chat_id else {
+        return Ok(json!({ "sent": false }));
+    };
+    if !notifications::is_enabled(state, job.user_id, job.project_id, kind, Channel::Chat).await? {
+        return Ok(json!({ "sent": false }));
+    }
+    let buttons = job.run_item_id.map(|run_item_id| {
+        let row: Vec<Value> = RESULT_BUTTONS
+            .iter()
+            .map(|(label, status)| {
+                json!({ "text": label, "callback_data": format!("r:{run_item_id}:{status}") })
+            })
+            .collect();
+        json!({ "inline_keyboard": [row] })
+    });
+    bot.send_message(chat_id, &job.text, buttons).await?;
+    Ok(json!({ "sent": true }))
+}
+
+/// Starts the long-polling loop. Every instance tries, but only the holder of an advisory
+/// lock polls; the others take over when its database session ends.
+pub fn spawn_poller(state: AppState) {
+    if state.telegram.is_none() {
+        return;
+    }
+    tokio::spawn(async move {
+        loop {
+            if let Err(err) = poll(&state).await {
+                warn!("telegram polling failed: {err:#}");
+            }
+            tokio::time::sleep(RETRY_DELAY).await;
+        }
+    });
+}
+
+async fn poll(state: &AppState) -> anyhow::Result<()> {
+    let Some(bot) = state.telegram.as_deref() else {
+        return Ok(());
+    };
+    // Detached so the session, and the lock with it, ends when polling stops.
+    let mut lock_conn = state.db.acquire().await?.detach();
+    let locked = sqlx::query_scalar!(
+        r#"SELECT pg_try_advisory_lock($1) AS "locked!""#,
+        POLLER_LOCK_KEY,
+    )
+    .fetch_one(&mut lock_conn)
+    .await?;
+    if !locked {
+        return Ok(());
+    }
+
+    let mut offset = 0_i64;
+    loop {
+        // Parsed one by one, so an update of an unexpected shape is skipped instead of being
+        // fetched again forever.
+        let updates: Vec<Value> = bot
+            .call(
+                "getUpdates",
+                json!({
+                    "offset": offset,
+                    "timeout": POLL_TIMEOUT_SECS,
+                    "allowed_updates": ["message", "callback_query"],
+                }),
+            )
+            .await?;
+        for update in updates {
+            let update_id = update["update_id"].as_i64().unwrap_or_default();
+            offset = offset.max(update_id + 1);
+            let handled = match serde_json::from_value::<Update>(update) {
+                Ok(update) => handle_update(state, bot, update).await,
+                Err(err) => Err(err.into()),
+            };
+            if let Err(err) = handled {
+                warn!(update_id, "failed to handle a telegram update: {err:#}");
+            }
+        }
+    }
+}
+
+async fn handle_update(state: &AppState, bot: &TelegramBot, update: Update) -> anyhow::Result<()> {
+    match (update.message, update.callback_query) {
+        (Some(message), _) => handle_message(state, bot, message).await,
+        (_, Some(callback)) => handle_callback(state, bot, callback).await,
+        _ => Ok(()),
+    }
+}
+
+async fn handle_message(
+    state: &AppState,
+    bot: &TelegramBot,
+    message: Message,
+) -> anyhow::Result<()> {
+    // Notifications are personal, so group chats are never linked.
+    if message.chat.kind != "private" {
+        return Ok(());
+    }
+    let chat_id = message.chat.id;
+    let text = message.text.unwrap_or_default();
+    let mut words = text.split_whitespace();
+    let command = words.next().unwrap_or_default();
+    // Commands may be addressed as `/start@bot_name`.
+    let command = command.split('@').next().unwrap_or_default();
+    let reply = match (command, words.next()) {
+        ("/start", Some(code)) => {
+            let username = message.from.and_then(|from| from.username);
+            link_chat(state, chat_id, code, username).await?
+        }
+        ("/stop", _) => unlink_chat(state, chat_id).await?,
+        _ => HELP_TEXT,
+    };
+    bot.send_message(chat_id, reply, None).await
+}
+
+async fn link_chat(
+    state: &AppState,
+    chat_id: i64,
+    code: &str,
+    username: Option<String>,
+) -> anyhow::Result<&'static str> {
+    let mut tx = state.db.begin().await?;
+    let issued = sqlx::query!(
+        r#"
+        DELETE FROM telegram_link_codes
+        WHERE code = $1
+        RETURNING user_id, expires_at > NOW() AS "valid!"
+        "#,
+        code,
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+    let Some(user_id) = issued.filter(|code| code.valid).map(|code| code.user_id) else {
+        tx.commit().await?;
+        return Ok("Ссылка привязки недействительна или устарела. Получите новую в профиле Uran.");
+    };
+    // The chat may have been linked to another account before.
+    let replaced = sqlx::query_scalar!(
+        "DELETE FROM telegram_links WHERE chat_id = $1 AND user_id <> $2 RETURNING user_id",
+        chat_id,
+        user_id,
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+    sqlx::query!(
+        r#"
+        INSERT INTO telegram_links (user_id, chat_id, username)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (user_id) DO UPDATE
+          SET chat_id = EXCLUDED.chat_id, username = EXCLUDED.username, linked_at = NOW()
+        "#,
+        user_id,
+        chat_id,
+        username,
+    )
+    .execute(&mut *tx)
+    .await?;
+    audit::record(
+        &mut *tx,
+        AuditEntry {
+            actor_user_id: Some(user_id),
+            action: "create",
+            entity_type: "telegram_link",
+            entity_id: Some(user_id),
+            project_id: None,
+            run_id: None,
+            before: None,
+            after: Some(json!({ "chatId": chat_id, "username": username })),
+        },
+    )
+    .await?;
+    tx.commit().await?;
+    if let Some(previous_user_id) = replaced {
+        audit_unlink(state, previous_user_id, chat_id).await;
+    }
+    Ok("Чат привязан к Uran. Сюда будут приходить назначения и FAIL обязательных тестов.")
+}
+
+async fn handle_callback(
+    state: &AppState,
+    bot: &TelegramBot,
+    callback: CallbackQuery,
+) -> anyhow::Result<()> {
+    let Some((run_item_id, status)) = callback.data.as_deref().and_then(parse_callback) else {
+        bot.answer_callback(&callback.id, "Кнопка устарела.", false)
+            .await;
+        return Ok(());
+    };
+    // Buttons only reach private chats, where the chat id is the user's Telegram id.
+    let recorded = record_from_button(state, callback.from.id, run_item_id, status).await;
+    let (text, alert) = match recorded {
+        Ok(()) => (format!("Записано: {}", status.to_uppercase()), false),
+        Err(error) => (error.message(Lang::Ru).to_string(), true),
+    };
+    bot.answer_callback(&callback.id, &text, alert).await;
+    Ok(())
+}
+
+/// `r:<run item id>:<status>` of [`RESULT_BUTTONS`].
+fn parse_callback(data: &str) -> Option<(Uuid, &'static str)> {
+    let (run_item_id, status) = data.strip_prefix("r:")?.rsplit_once(':')?;
+    let (_, status) = RESULT_BUTTONS.iter().find(|(_, s)| *s == status)?;
+    Some((Uuid::parse_str(run_item_id).ok()?, status))
+}
+
+/// Records the result as the chat's user with the same checks as `PATCH .../result`. The
+/// comment and fail reason already entered are kept, since buttons cannot supply them.
+async fn record_from_button(
+    state: &AppState,
+    chat_id: i64,
+    run_item_id: Uuid,
+    status: &str,
+) -> Result<(), ApiError> {
+    let user_id = sqlx::query_scalar!(
+        "SELECT user_id FROM telegram_links WHERE chat_id = $1",
+        chat_id,
+    )
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|_| ApiError::TelegramLinkReadFailed)?
+    .ok_or(ApiError::TelegramChatNotLinked)?;
+    if !authz::is_active_person(&state.db, user_id)
+        .await
+        .map_err(|_| ApiError::TelegramLinkReadFailed)?
+    {
+        return Err(ApiError::UserDeactivated);
+    }
+    let item = sqlx::query!(
+        r#"
+        SELECT
+          ri.run_id,
+          r.project_id AS "project_id?",
+          rr.comment AS "comment?",
+          rr.fail_reason_code
+        FROM run_items ri
+        JOIN runs r ON r.id = ri.run_id
+        LEFT JOIN run_results rr ON rr.run_item_id = ri.id
+        WHERE ri.id = $1
+        "#,
+        run_item_id,
+    )
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|_| ApiError::RunItemsReadFailed)?
+    .ok_or(ApiError::RunItemNotFound)?;
+
+    record_run_result(
+        state,
+        &user_id.to_string(),
+        &item.run_id.to_string(),
+        &run_item_id.to_string(),
+        Precondition::None,
+        UpdateRunResultRequest {
+            status: status.to_string(),
+            fail_reason_code: item.fail_reason_code,
+            comment: item.comment,
+            elapsed_seconds: None,
+            steps: None,
+        },
+    )
+    .await
+    .map_err(|(VersionError::Api(error) | VersionError::Conflict { error, .. })| error)?;
+
+    let audited = audit::record(
+        &state.db,
+        AuditEntry {
+            actor_user_id: Some(user_id),
+            action: "update",
+            entity_type: "run_item",
+            entity_id: Some(run_item_id),
+            project_id: item.project_id,
+            run_id: Some(item.run_id),
+            before: None,
+            after: Some(json!({ "status": status, "source": "telegram" })),
+        },
+    )
+    .await;
+    if let Err(err) = audited {
+        warn!("failed to audit a telegram result: {err}");
+    }
+    Ok(())
+}
+
+async fn unlink_chat(state: &AppState, chat_id: i64) -> anyhow::Result<&'static str> {
+    let user_id = sqlx::query_scalar!(
+        "DELETE FROM telegram_links WHERE chat_id = $1 RETURNING user_id",
+        chat_id,
+    )
+    .fetch_optional(&state.db)
+    .await?;
+    let Some(user_id) = user_id else {
+        return Ok("Этот чат не привязан к Uran.");
+    };
+    audit_unlink(state, user_id, chat_id).await;
+    Ok("Чат отвязан, уведомления сюда больше не придут.")
+}
+
+async fn audit_unlink(state: &AppState, user_id: Uuid, chat_id: i64) {
+    let audited = audit::record(
+        &state.db,
+        AuditEntry {
+            actor_user_id: Some(user_id),
+            action: "delete",
+            entity_type: "telegram_link",
+            entity_id: Some(user_id),
+            project_id: None,
+            run_id: None,
+            before: Some(json!({ "chatId": chat_id })),
+            after: None,
+        },
+    )
+    .await;
+    if let Err(err) = audited {
+        warn!("failed to audit a telegram unlink: {err}");
+    }
+}
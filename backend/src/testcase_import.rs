@@ -0,0 +1,1002 @@
+use std::collections::{HashMap, HashSet};
+
+use axum::{
+    body::Bytes,
+    extract::{Multipart, Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sqlx::Row;
+use tracing::warn;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::{
+    audit::{self, AuditEntry},
+    authz::ProjectRole,
+    ensure_db_user_exists,
+    error::{current_lang, ApiError},
+    export,
+    jobs::{self, JobContext},
+    parse_uuid,
+    permissions::Capability,
+    suites, AppState,
+};
+
+/// Spreadsheets exported from other tools are much larger than regular JSON payloads.
+pub const MAX_IMPORT_BYTES: usize = 10 * 1024 * 1024;
+
+const MAX_IMPORT_ROWS: usize = 10_000;
+/// Separates levels of the `section` column, as in TestRail's "Section Hierarchy".
+const SECTION_SEPARATOR: &str = ">";
+
+#[derive(Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct ImportTestcasesQuery {
+    /// Suite that receives the testcases; sections become child suites under it.
+    suite_id: String,
+    /// `csv` or `testrail` (TestRail XML export); by default `.xml` files are TestRail.
+    format: Option<String>,
+    /// Validate and report what would be created without writing anything.
+    dry_run: Option<bool>,
+    /// Import in a background job: answers `202` with the job, whose result is the usual
+    /// response once it succeeds.
+    background: Option<bool>,
+}
+
+/// Column of the file for each testcase field. An unmapped field is read from a column named
+/// like the field (case-insensitive) or its TestRail CSV name, and left empty when there is
+/// none. Only `title` is required.
+#[derive(Serialize, Deserialize, Default, ToSchema)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct ColumnMapping {
+    title: Option<String>,
+    key: Option<String>,
+    summary: Option<String>,
+    preconditions: Option<String>,
+    /// One step per line; `1.` / `1)` numbering is stripped.
+    steps: Option<String>,
+    /// One expected result per line.
+    expected: Option<String>,
+    /// Comma- or semicolon-separated.
+    tags: Option<String>,
+    /// Suite path under the target suite, levels separated by `>`.
+    section: Option<String>,
+    is_required: Option<String>,
+    /// Minutes, or a TestRail estimate such as `1h 30m`.
+    estimated_minutes: Option<String>,
+    complexity: Option<String>,
+}
+
+/// Multipart form of `import_testcases`; only describes the request in the OpenAPI spec.
+#[derive(ToSchema)]
+#[allow(dead_code)]
+pub struct TestcaseImportUpload {
+    #[schema(value_type = String, format = Binary)]
+    file: Vec<u8>,
+    /// JSON; ignored for TestRail XML.
+    mapping: Option<ColumnMapping>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportedTestcaseView {
+    row: usize,
+    /// `None` in a dry run.
+    id: Option<String>,
+    key: String,
+    title: String,
+    /// Suite path under the target suite; empty for the target suite itself.
+    section: String,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SkippedRowView {
+    row: usize,
+    title: String,
+    /// Set when a testcase with this title already exists in the project.
+    existing_testcase_id: Option<String>,
+    /// Set when an earlier row of the file has the same title.
+    duplicate_of_row: Option<usize>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RejectedRowView {
+    row: usize,
+    code: String,
+    message: String,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportTestcasesResponse {
+    import_id: String,
+    dry_run: bool,
+    format: String,
+    total_rows: usize,
+    testcases: Vec<ImportedTestcaseView>,
+    /// Paths of the suites created (or to be created) for sections.
+    created_suites: Vec<String>,
+    skipped: Vec<SkippedRowView>,
+    rejected: Vec<RejectedRowView>,
+    /// CSV of the rejected rows with the reason; `None` when nothing was rejected.
+    error_report_url: Option<String>,
+}
+
+#[derive(Default)]
+struct RawCase {
+    key: String,
+    title: String,
+    summary: String,
+    preconditions: String,
+    steps: Vec<String>,
+    expected: Vec<String>,
+    tags: Vec<String>,
+    section: Vec<String>,
+    is_required: String,
+    estimated_minutes: String,
+    complexity: String,
+}
+
+/// One row (CSV) or case (TestRail) of the file; `values` are echoed in the error report.
+struct SourceRow {
+    row: usize,
+    values: Vec<String>,
+    parsed: Result<RawCase, ApiError>,
+}
+
+struct SourceFile {
+    /// Header of the error report after `row`, `code` and `error`.
+    columns: Vec<String>,
+    rows: Vec<SourceRow>,
+}
+
+struct Case {
+    key: Option<String>,
+    title: String,
+    summary: String,
+    preconditions: String,
+    steps: Vec<String>,
+    expected: Vec<String>,
+    tags: Vec<String>,
+    section: Vec<String>,
+    is_required: bool,
+    estimated_minutes: Option<i32>,
+    complexity: Option<i16>,
+}
+
+struct PlannedSuite {
+    id: Uuid,
+    parent_id: Uuid,
+    name: String,
+}
+
+struct PlannedTestcase {
+    row: usize,
+    id: Uuid,
+    suite_id: Uuid,
+    key: String,
+    section: String,
+    case: Case,
+}
+
+fn lines(value: &str) -> Vec<String> {
+    value
+        .lines()
+        .map(|line| {
+            let line = line.trim();
+            let digits = line.len() - line.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+            match line[digits..].strip_prefix(['.', ')']) {
+                Some(rest) if digits > 0 && rest.starts_with(char::is_whitespace) => {
+                    rest.trim().to_string()
+                }
+                _ => line.to_string(),
+            }
+        })
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+fn section_path(value: &str) -> Vec<String> {
+    value
+        .split(SECTION_SEPARATOR)
+        .map(|level| level.trim().to_string())
+        .filter(|level| !level.is_empty())
+        .collect()
+}
+
+fn parse_required(value: &str) -> Result<bool, ApiError> {
+    match value.trim().to_lowercase().as_str() {
+        "" | "1" | "true" | "yes" | "y" | "да" => Ok(true),
+        "0" | "false" | "no" | "n" | "нет" => Ok(false),
+        _ => Err(ApiError::ImportRowInvalidRequired),
+    }
+}
+
+/// `30`, `30m`, `1h 30m` or `1m 30s` (rounded up to a minute).
+fn parse_minutes(value: &str) -> Result<Option<i32>, ApiError> {
+    let value = value.trim().to_lowercase();
+    if value.is_empty() {
+        return Ok(None);
+    }
+    let mut seconds: i64 = 0;
+    for token in value.split_whitespace() {
+        let unit_at = token
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(token.len());
+        let amount: i64 = token[..unit_at]
+            .parse()
+            .map_err(|_| ApiError::ImportRowInvalidEstimate)?;
+        let scale = match &token[unit_at..] {
+            "" | "m" | "min" => 60,
+            "h" => 3600,
+            "s" => 1,
+            _ => return Err(ApiError::ImportRowInvalidEstimate),
+        };
+        seconds = seconds.saturating_add(amount.saturating_mul(scale));
+    }
+    let minutes = seconds.saturating_add(59) / 60;
+    if minutes <= 0 || minutes > i32::MAX as i64 {
+        return Err(ApiError::ImportRowInvalidEstimate);
+    }
+    Ok(Some(minutes as i32))
+}
+
+fn validate(raw: &RawCase) -> Result<Case, ApiError> {
+    let title = raw.title.trim().to_string();
+    if !(2..=240).contains(&title.chars().count()) {
+        return Err(ApiError::ImportRowTitleInvalid);
+    }
+    if raw
+        .section
+        .iter()
+        .any(|level| !(2..=200).contains(&level.chars().count()))
+    {
+        return Err(ApiError::ImportRowSectionInvalid);
+    }
+    let complexity = match raw.complexity.trim() {
+        "" => None,
+        value => Some(
+            value
+                .parse::<i16>()
+                .ok()
+                .filter(|c| (1..=5).contains(c))
+                .ok_or(ApiError::ImportRowInvalidComplexity)?,
+        ),
+    };
+    let mut tags: Vec<String> = Vec::new();
+    for tag in &raw.tags {
+        if !tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+            tags.push(tag.clone());
+        }
+    }
+    Ok(Case {
+        key: Some(raw.key.trim().to_string()).filter(|k| !k.is_empty()),
+        title,
+        summary: raw.summary.trim().to_string(),
+        preconditions: raw.preconditions.trim().to_string(),
+        steps: raw.steps.clone(),
+        expected: raw.expected.clone(),
+        tags,
+        section: raw.section.clone(),
+        is_required: parse_required(&raw.is_required)?,
+        estimated_minutes: parse_minutes(&raw.estimated_minutes)?,
+        complexity,
+    })
+}
+
+fn parse_csv(data: &[u8], mapping: &ColumnMapping) -> Result<SourceFile, ApiError> {
+    let data = data.strip_prefix(export::UTF8_BOM).unwrap_or(data);
+    // Spreadsheet exports in some locales use `;` or tabs; the header line tells which.
+    let header_line = data.split(|b| *b == b'\n').next().unwrap_or_default();
+    let delimiter = [b',', b';', b'\t']
+        .into_iter()
+        .max_by_key(|d| header_line.iter().filter(|b| *b == d).count())
+        .unwrap_or(b',');
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true)
+        .from_reader(data);
+    let headers: Vec<String> = reader
+        .headers()
+        .map_err(|_| ApiError::InvalidImportCsv)?
+        .iter()
+        .map(|h| h.trim().to_string())
+        .collect();
+
+    let column = |mapped: &Option<String>, aliases: &[&str]| -> Result<Option<usize>, ApiError> {
+        let find = |name: &str| headers.iter().position(|h| h.eq_ignore_ascii_case(name));
+        match mapped.as_deref().map(str::trim) {
+            Some(name) => find(name).map(Some).ok_or(ApiError::InvalidImportMapping),
+            None => Ok(aliases.iter().find_map(|alias| find(alias))),
+        }
+    };
+    let title = column(&mapping.title, &["title"])?.ok_or(ApiError::InvalidImportMapping)?;
+    let key = column(&mapping.key, &["key", "id"])?;
+    let summary = column(&mapping.summary, &["summary"])?;
+    let preconditions = column(&mapping.preconditions, &["preconditions"])?;
+    let steps = column(&mapping.steps, &["steps"])?;
+    let expected = column(&mapping.expected, &["expected", "expected result"])?;
+    let tags = column(&mapping.tags, &["tags"])?;
+    let section = column(&mapping.section, &["section", "section hierarchy"])?;
+    let is_required = column(&mapping.is_required, &["isRequired", "required"])?;
+    let estimated_minutes = column(
+        &mapping.estimated_minutes,
+        &["estimatedMinutes", "estimate"],
+    )?;
+    let complexity = column(&mapping.complexity, &["complexity"])?;
+
+    let mut rows = Vec::new();
+    for (idx, record) in reader.records().enumerate() {
+        if rows.len() >= MAX_IMPORT_ROWS {
+            return Err(ApiError::ImportTooManyRows);
+        }
+        // Numbered as in a spreadsheet: the header is row 1, whatever line breaks the cells hold.
+        let row = idx + 2;
+        let record = match record {
+            Ok(record) => record,
+            Err(_) => {
+                rows.push(SourceRow {
+                    row,
+                    values: Vec::new(),
+                    parsed: Err(ApiError::ImportRowMalformed),
+                });
+                continue;
+            }
+        };
+        if record.iter().all(|cell| cell.trim().is_empty()) {
+            continue;
+        }
+        let cell = |index: Option<usize>| {
+            index
+                .and_then(|i| record.get(i))
+                .unwrap_or_default()
+                .to_string()
+        };
+        let parsed = Ok(RawCase {
+            key: cell(key),
+            title: cell(Some(title)),
+            summary: cell(summary),
+            preconditions: cell(preconditions),
+            steps: lines(&cell(steps)),
+            expected: lines(&cell(expected)),
+            tags: cell(tags)
+                .split([',', ';'])
+                .map(|t| t.trim().to_string())
+                .filter(|t| !t.is_empty())
+                .collect(),
+            section: section_path(&cell(section)),
+            is_required: cell(is_required),
+            estimated_minutes: cell(estimated_minutes),
+            complexity: cell(complexity),
+        });
+        rows.push(SourceRow {
+            row,
+            values: record.iter().map(str::to_string).collect(),
+            parsed,
+        });
+    }
+    Ok(SourceFile {
+        columns: headers,
+        rows,
+    })
+}
+
+fn child_text(node: roxmltree::Node, name: &str) -> String {
+    node.children()
+        .find(|c| c.has_tag_name(name))
+        .and_then(|c| c.text())
+        .unwrap_or_default()
+        .trim()
+        .to_string()
+}
+
+/// TestRail "Export to XML": `suite > sections > section > cases > case`, sections nest.
+fn parse_testrail(data: &[u8]) -> Result<SourceFile, ApiError> {
+    let xml = std::str::from_utf8(data).map_err(|_| ApiError::InvalidTestrailXml)?;
+    let doc = roxmltree::Document::parse(xml).map_err(|_| ApiError::InvalidTestrailXml)?;
+    let mut rows = Vec::new();
+    for (idx, case) in doc
+        .descendants()
+        .filter(|n| n.has_tag_name("case"))
+        .enumerate()
+    {
+        if rows.len() >= MAX_IMPORT_ROWS {
+            return Err(ApiError::ImportTooManyRows);
+        }
+        let mut section: Vec<String> = case
+            .ancestors()
+            .filter(|n| n.has_tag_name("section"))
+            .map(|n| child_text(n, "name"))
+            .filter(|name| !name.is_empty())
+            .collect();
+        section.reverse();
+
+        let custom = case.children().find(|c| c.has_tag_name("custom"));
+        let custom_text = |name: &str| custom.map(|c| child_text(c, name)).unwrap_or_default();
+        let separated: Vec<roxmltree::Node> = custom
+            .and_then(|c| c.children().find(|n| n.has_tag_name("steps_separated")))
+            .map(|s| s.children().filter(|n| n.has_tag_name("step")).collect())
+            .unwrap_or_default();
+        let (steps, expected) = if separated.is_empty() {
+            (
+                lines(&custom_text("steps")),
+                lines(&custom_text("expected")),
+            )
+        } else {
+            let collect = |name: &str| {
+                separated
+                    .iter()
+                    .map(|step| child_text(*step, name))
+                    .filter(|text| !text.is_empty())
+                    .collect()
+            };
+            (collect("content"), collect("expected"))
+        };
+
+        let key = child_text(case, "id");
+        let title = child_text(case, "title");
+        rows.push(SourceRow {
+            row: idx + 1,
+            values: vec![key.clone(), title.clone()],
+            parsed: Ok(RawCase {
+                key,
+                title,
+                preconditions: custom_text("preconds"),
+                steps,
+                expected,
+                section,
+                estimated_minutes: child_text(case, "estimate"),
+                ..RawCase::default()
+            }),
+        });
+    }
+    Ok(SourceFile {
+        columns: vec!["id".to_string(), "title".to_string()],
+        rows,
+    })
+}
+
+fn render_error_report(
+    columns: &[String],
+    rejected: &[(&SourceRow, ApiError)],
+) -> anyhow::Result<Vec<u8>> {
+    let lang = current_lang();
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    let mut header = vec!["row".to_string(), "code".to_string(), "error".to_string()];
+    header.extend(columns.iter().cloned());
+    writer.write_record(&header)?;
+    for (source, error) in rejected {
+        let mut record = vec![
+            source.row.to_string(),
+            error.code().to_string(),
+            error.message(lang).to_string(),
+        ];
+        record.extend(source.values.iter().cloned());
+        writer.write_record(&record)?;
+    }
+    Ok(writer.into_inner()?)
+}
+
+fn error_report_key(project_id: Uuid, import_id: Uuid) -> String {
+    format!("testcase-imports/{project_id}/{import_id}/errors.csv")
+}
+
+/// Resolves (or plans) the suite for a section path under `root`. Existing suites are
+/// matched by name among the children of the previous level.
+fn resolve_suite(
+    root: Uuid,
+    section: &[String],
+    children: &mut HashMap<(Uuid, String), Uuid>,
+    planned: &mut Vec<PlannedSuite>,
+    created_paths: &mut Vec<String>,
+) -> Uuid {
+    let mut parent = root;
+    for (depth, name) in section.iter().enumerate() {
+        let lookup = (parent, name.to_lowercase());
+        parent = match children.get(&lookup) {
+            Some(id) => *id,
+            None => {
+                let id = Uuid::new_v4();
+                planned.push(PlannedSuite {
+                    id,
+                    parent_id: parent,
+                    name: name.clone(),
+                });
+                created_paths.push(section[..=depth].join(" > "));
+                children.insert(lookup, id);
+                id
+            }
+        };
+    }
+    parent
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v2/projects/{project_id}/testcases/import",
+    tag = "library",
+    params(("project_id" = String, Path), ImportTestcasesQuery),
+    request_body(content = TestcaseImportUpload, content_type = "multipart/form-data"),
+    responses(
+        (status = 201, body = ImportTestcasesResponse),
+        (status = 200, description = "Dry run.", body = ImportTestcasesResponse),
+        (status = 202, description = "background=true.", body = jobs::JobAcceptedResponse)
+    )
+)]
+pub async fn import_testcases(
+    State(state): State<AppState>,
+    access: ProjectRole,
+    Query(query): Query<ImportTestcasesQuery>,
+    mut multipart: Multipart,
+) -> Result<Response, ApiError> {
+    access.require(Capability::LibraryEdit)?;
+    let ProjectRole {
+        user_id: actor_id,
+        project_id,
+        ..
+    } = access;
+    let suite_id = parse_uuid(&query.suite_id, ApiError::InvalidSuiteId)?;
+
+    let mut file: Option<(String, String, Bytes)> = None;
+    let mut mapping = ColumnMapping::default();
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|_| ApiError::InvalidMultipart)?
+    {
+        match field.name() {
+            Some("file") => {
+                let file_name = field.file_name().unwrap_or_default().to_lowercase();
+                let content_type = field.content_type().unwrap_or_default().to_lowercase();
+                let data = field
+                    .bytes()
+                    .await
+                    .map_err(|_| ApiError::InvalidMultipart)?;
+                file = Some((file_name, content_type, data));
+            }
+            Some("mapping") => {
+                let data = field
+                    .bytes()
+                    .await
+                    .map_err(|_| ApiError::InvalidMultipart)?;
+                mapping =
+                    serde_json::from_slice(&data).map_err(|_| ApiError::InvalidImportMapping)?;
+            }
+            _ => {}
+        }
+    }
+    let (file_name, content_type, data) = file.ok_or(ApiError::ImportFileRequired)?;
+    let options = ImportOptions {
+        project_id,
+        actor_id,
+        suite_id,
+        dry_run: query.dry_run.unwrap_or(false),
+        format: query.format,
+        file_name,
+        content_type,
+        mapping,
+    };
+
+    if query.background.unwrap_or(false) {
+        suites::ensure_suite_in_project(&state, suite_id, project_id).await?;
+        ensure_db_user_exists(&state, &options.actor_id).await?;
+        let actor_uuid = parse_uuid(&options.actor_id, ApiError::InvalidUserId)?;
+        let source_key = format!("imports/{project_id}/uploads/{}", Uuid::new_v4());
+        state
+            .storage
+            .put(&source_key, data)
+            .await
+            .map_err(|_| ApiError::JobQueueFailed)?;
+        let job_id = jobs::submit(
+            &state,
+            jobs::NewJob {
+                kind: jobs::JobKind::TestcaseImport,
+                project_id: Some(project_id),
+                created_by_user_id: Some(actor_uuid),
+                payload: json!({ "options": options, "sourceKey": source_key }),
+            },
+        )
+        .await
+        .map_err(|_| ApiError::JobQueueFailed)?;
+        return Ok(jobs::accepted(job_id).into_response());
+    }
+
+    let (status, response) = import_file(&state, &options, &data).await?;
+    Ok((status, Json(response)).into_response())
+}
+
+/// Everything an import needs besides the file itself; kept in the job payload of a
+/// background import.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ImportOptions {
+    project_id: Uuid,
+    actor_id: String,
+    suite_id: Uuid,
+    dry_run: bool,
+    format: Option<String>,
+    file_name: String,
+    content_type: String,
+    mapping: ColumnMapping,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ImportJob {
+    options: ImportOptions,
+    source_key: String,
+}
+
+/// Job handler of `import_testcases?background=true`; the uploaded file is deleted once the
+/// import has run, whatever its outcome.
+pub async fn run_import_job(
+    state: &AppState,
+    ctx: &JobContext,
+    payload: Value,
+) -> anyhow::Result<Value> {
+    let job: ImportJob = serde_json::from_value(payload)?;
+    let data = state.storage.get(&job.source_key).await?;
+    let outcome = import_file(state, &job.options, &data).await;
+    let finished = outcome.is_ok() || ctx.is_last_attempt();
+    if finished {
+        if let Err(err) = state.storage.delete(&job.source_key).await {
+            warn!("failed to delete import upload {}: {err:#}", job.source_key);
+        }
+    }
+    let (_, response) = outcome.map_err(|err| anyhow::anyhow!("{}", err.code()))?;
+    Ok(json!(response))
+}
+
+async fn import_file(
+    state: &AppState,
+    options: &ImportOptions,
+    data: &Bytes,
+) -> Result<(StatusCode, ImportTestcasesResponse), ApiError> {
+    let project_id = options.project_id;
+    let target_suite = options.suite_id;
+    let dry_run = options.dry_run;
+    let file_name = &options.file_name;
+    let format = match options.format.as_deref().map(str::trim) {
+        Some("csv") => "csv",
+        Some("testrail") => "testrail",
+        Some(_) => return Err(ApiError::InvalidImportFormat),
+        None if file_name.ends_with(".xml") || options.content_type.contains("xml") => "testrail",
+        None => "csv",
+    };
+    let source = match format {
+        "testrail" => parse_testrail(data)?,
+        _ => parse_csv(data, &options.mapping)?,
+    };
+    if source.rows.is_empty() {
+        return Err(ApiError::ImportFileEmpty);
+    }
+
+    suites::ensure_suite_in_project(state, target_suite, project_id).await?;
+    ensure_db_user_exists(state, &options.actor_id).await?;
+    let actor_uuid = parse_uuid(&options.actor_id, ApiError::InvalidUserId)?;
+
+    let read_failed = |_| ApiError::TestcasesReadFailed;
+    let mut existing_titles: HashMap<String, String> = HashMap::new();
+    let mut existing_keys: HashSet<(Uuid, String)> = HashSet::new();
+    for r in sqlx::query(
+        r#"
+        SELECT tc.id, tc.suite_id, tc.key, tc.title, tc.is_archived
+        FROM testcases tc
+        JOIN test_suites s ON s.id = tc.suite_id
+        WHERE s.project_id = $1
+        ORDER BY tc.created_at ASC
+        "#,
+    )
+    .bind(project_id)
+    .fetch_all(&state.db)
+    .await
+    .map_err(read_failed)?
+    {
+        existing_keys.insert((r.get("suite_id"), r.get("key")));
+        if !r.get::<bool, _>("is_archived") {
+            existing_titles
+                .entry(r.get::<String, _>("title").trim().to_lowercase())
+                .or_insert_with(|| r.get::<Uuid, _>("id").to_string());
+        }
+    }
+    let mut children: HashMap<(Uuid, String), Uuid> = HashMap::new();
+    for r in sqlx::query(
+        r#"
+        SELECT id, parent_id, name
+        FROM test_suites
+        WHERE project_id = $1 AND parent_id IS NOT NULL AND is_archived = FALSE
+        ORDER BY position ASC, created_at ASC
+        "#,
+    )
+    .bind(project_id)
+    .fetch_all(&state.db)
+    .await
+    .map_err(|_| ApiError::SuitesReadFailed)?
+    {
+        children
+            .entry((
+                r.get("parent_id"),
+                r.get::<String, _>("name").to_lowercase(),
+            ))
+            .or_insert(r.get("id"));
+    }
+
+    let mut planned_suites: Vec<PlannedSuite> = Vec::new();
+    let mut created_suites: Vec<String> = Vec::new();
+    let mut planned: Vec<PlannedTestcase> = Vec::new();
+    let mut skipped: Vec<SkippedRowView> = Vec::new();
+    let mut rejected: Vec<(&SourceRow, ApiError)> = Vec::new();
+    let mut seen_titles: HashMap<String, usize> = HashMap::new();
+    for source_row in &source.rows {
+        let case = match &source_row.parsed {
+            Ok(raw) => validate(raw),
+            Err(err) => Err(*err),
+        };
+        let case = match case {
+            Ok(case) => case,
+            Err(err) => {
+                rejected.push((source_row, err));
+                continue;
+            }
+        };
+        let title_key = case.title.to_lowercase();
+        if let Some(existing) = existing_titles.get(&title_key) {
+            skipped.push(SkippedRowView {
+                row: source_row.row,
+                title: case.title,
+                existing_testcase_id: Some(existing.clone()),
+                duplicate_of_row: None,
+            });
+            continue;
+        }
+        if let Some(first_row) = seen_titles.get(&title_key) {
+            skipped.push(SkippedRowView {
+                row: source_row.row,
+                title: case.title,
+                existing_testcase_id: None,
+                duplicate_of_row: Some(*first_row),
+            });
+            continue;
+        }
+
+        // A key can only clash in a suite that exists or was planned by an earlier row, so
+        // a rejected row never leaves an empty planned suite behind.
+        let suite_id = resolve_suite(
+            target_suite,
+            &case.section,
+            &mut children,
+            &mut planned_suites,
+            &mut created_suites,
+        );
+        let key = case
+            .key
+            .clone()
+            .unwrap_or_else(|| format!("TC-{}", &Uuid::new_v4().simple().to_string()[..8]));
+        if !existing_keys.insert((suite_id, key.clone())) {
+            rejected.push((source_row, ApiError::ImportRowKeyTaken));
+            continue;
+        }
+        seen_titles.insert(title_key, source_row.row);
+        planned.push(PlannedTestcase {
+            row: source_row.row,
+            id: Uuid::new_v4(),
+            suite_id,
+            key,
+            section: case.section.join(" > "),
+            case,
+        });
+    }
+
+    let import_id = Uuid::new_v4();
+    let error_report_url = if rejected.is_empty() {
+        None
+    } else {
+        let report = render_error_report(&source.columns, &rejected)
+            .map_err(|_| ApiError::ImportReportStoreFailed)?;
+        state
+            .storage
+            .put(
+                &error_report_key(project_id, import_id),
+                Bytes::from(report),
+            )
+            .await
+            .map_err(|_| ApiError::ImportReportStoreFailed)?;
+        Some(format!(
+            "/api/v2/projects/{project_id}/testcases/imports/{import_id}/errors"
+        ))
+    };
+
+    if !dry_run && !planned.is_empty() {
+        let import_failed = |_| ApiError::TestcaseImportFailed;
+        let change_note = match format {
+            "testrail" => "Imported from TestRail",
+            _ => "Imported from CSV",
+        };
+        let mut tx = state.db.begin().await.map_err(import_failed)?;
+        for suite in &planned_suites {
+            sqlx::query(
+                r#"
+                INSERT INTO test_suites (
+                  id, project_id, parent_id, key, name, created_by_user_id, updated_by_user_id
+                )
+                VALUES ($1, $2, $3, $4, $5, $6, $6)
+                "#,
+            )
+            .bind(suite.id)
+            .bind(project_id)
+            .bind(suite.parent_id)
+            .bind(format!("suite-{}", &suite.id.simple().to_string()[..8]))
+            .bind(&suite.name)
+            .bind(actor_uuid)
+            .execute(&mut *tx)
+            .await
+            .map_err(import_failed)?;
+        }
+        for testcase in &planned {
+            let case = &testcase.case;
+            sqlx::query(
+                r#"
+                INSERT INTO testcases (
+                  id, suite_id, key, title, is_required, estimated_minutes, complexity,
+                  created_by_user_id, updated_by_user_id
+                )
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $8)
+                "#,
+            )
+            .bind(testcase.id)
+            .bind(testcase.suite_id)
+            .bind(&testcase.key)
+            .bind(&case.title)
+            .bind(case.is_required)
+            .bind(case.estimated_minutes)
+            .bind(case.complexity)
+            .bind(actor_uuid)
+            .execute(&mut *tx)
+            .await
+            .map_err(import_failed)?;
+            sqlx::query(
+                r#"
+                INSERT INTO testcase_versions (
+                  testcase_id, version_number, summary, preconditions, steps_json, expected_json,
+                  is_mandatory, estimated_minutes, complexity, change_note, created_by_user_id
+                )
+                VALUES ($1, 1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+                "#,
+            )
+            .bind(testcase.id)
+            .bind(&case.summary)
+            .bind(&case.preconditions)
+            .bind(json!(case.steps))
+            .bind(json!(case.expected))
+            .bind(case.is_required)
+            .bind(case.estimated_minutes)
+            .bind(case.complexity)
+            .bind(change_note)
+            .bind(actor_uuid)
+            .execute(&mut *tx)
+            .await
+            .map_err(import_failed)?;
+            if !case.tags.is_empty() {
+                sqlx::query(
+                    r#"
+                    WITH upserted AS (
+                      INSERT INTO tags (name)
+                      SELECT DISTINCT t FROM UNNEST($2::text[]) AS t
+                      ON CONFLICT (name) DO UPDATE SET name = tags.name
+                      RETURNING id
+                    )
+                    INSERT INTO testcase_tags (testcase_id, tag_id)
+                    SELECT $1, id FROM upserted
+                    ON CONFLICT DO NOTHING
+                    "#,
+                )
+                .bind(testcase.id)
+                .bind(&case.tags)
+                .execute(&mut *tx)
+                .await
+                .map_err(import_failed)?;
+            }
+        }
+        audit::record(
+            &mut *tx,
+            AuditEntry {
+                actor_user_id: Some(actor_uuid),
+                action: "create",
+                entity_type: "testcase_import",
+                entity_id: Some(import_id),
+                project_id: Some(project_id),
+                run_id: None,
+                before: None,
+                after: Some(json!({
+                    "format": format,
+                    "suiteId": target_suite,
+                    "testcases": planned.len(),
+                    "suites": planned_suites.len(),
+                    "skipped": skipped.len(),
+                    "rejected": rejected.len(),
+                })),
+            },
+        )
+        .await
+        .map_err(import_failed)?;
+        tx.commit().await.map_err(import_failed)?;
+    }
+
+    let rejected = rejected
+        .iter()
+        .map(|(source_row, err)| RejectedRowView {
+            row: source_row.row,
+            code: err.code().to_string(),
+            message: err.message(current_lang()).to_string(),
+        })
+        .collect();
+    let testcases = planned
+        .into_iter()
+        .map(|t| ImportedTestcaseView {
+            row: t.row,
+            id: (!dry_run).then(|| t.id.to_string()),
+            key: t.key,
+            title: t.case.title,
+            section: t.section,
+        })
+        .collect();
+    let status = if dry_run {
+        StatusCode::OK
+    } else {
+        StatusCode::CREATED
+    };
+    Ok((
+        status,
+        ImportTestcasesResponse {
+            import_id: import_id.to_string(),
+            dry_run,
+            format: format.to_string(),
+            total_rows: source.rows.len(),
+            testcases,
+            created_suites,
+            skipped,
+            rejected,
+            error_report_url,
+        },
+    ))
+}
+
+/// CSV of the rows rejected by an import (or a dry run): `row`, `code`, `error` and the
+/// original cells.
+#[utoipa::path(
+    get,
+    path = "/api/v2/projects/{project_id}/testcases/imports/{import_id}/errors",
+    tag = "library",
+    params(("project_id" = String, Path), ("import_id" = String, Path)),
+    responses((status = 200, description = "Отчёт об отклонённых строках.", content_type = "text/csv"))
+)]
+pub async fn download_import_errors(
+    State(state): State<AppState>,
+    access: ProjectRole,
+    Path((_project_id, import_id)): Path<(String, String)>,
+) -> Result<Response, ApiError> {
+    let import_id = parse_uuid(&import_id, ApiError::ImportReportNotFound)?;
+    let data = state
+        .storage
+        .get(&error_report_key(access.project_id, import_id))
+        .await
+        .map_err(|_| ApiError::ImportReportNotFound)?;
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"import-{import_id}-errors.csv\""),
+            ),
+        ],
+        data,
+    )
+        .into_response())
+}
@@ -0,0 +1,834 @@
+use std::collections::HashMap;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sqlx::{PgPool, Row};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::{
+    audit::{self, AuditEntry},
+    authz::{self, AuthUser, ProjectRole},
+    custom_fields, db_errors, ensure_db_user_exists,
+    error::ApiError,
+    pagination, parse_uuid,
+    permissions::Capability,
+    saved_filters, AppState,
+};
+
+const MAX_STEPS: usize = 200;
+const MAX_STEP_CHARS: usize = 4000;
+const MAX_VERSION_TEXT_CHARS: usize = 20000;
+const MAX_CHANGE_NOTE_CHARS: usize = 500;
+
+#[derive(Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct ListTestcasesQuery {
+    suite_id: Option<String>,
+    /// JSON object of field key to value, e.g. `{"priority":"high"}`.
+    custom_fields: Option<String>,
+    /// Saved filter of the project; explicit parameters override the saved ones.
+    filter_id: Option<String>,
+    /// Also list archived test cases; they are hidden by default.
+    include_archived: Option<bool>,
+    limit: Option<i64>,
+    cursor: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TestcaseView {
+    id: String,
+    suite_id: String,
+    key: String,
+    title: String,
+    is_required: bool,
+    is_archived: bool,
+    latest_version_number: Option<i32>,
+    /// Feature file the testcase was imported from, if any.
+    source_path: Option<String>,
+    #[schema(value_type = Object)]
+    custom_fields: Value,
+    created_at: String,
+    updated_at: String,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ListTestcasesResponse {
+    testcases: Vec<TestcaseView>,
+    next_cursor: Option<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v2/projects/{project_id}/testcases",
+    tag = "library",
+    params(("project_id" = String, Path), ListTestcasesQuery),
+    responses((status = 200, body = ListTestcasesResponse))
+)]
+pub async fn list_testcases(
+    State(state): State<AppState>,
+    access: ProjectRole,
+    Query(query): Query<ListTestcasesQuery>,
+) -> Result<Json<ListTestcasesResponse>, ApiError> {
+    let project_uuid = access.project_id;
+    let query = match query.filter_id.as_deref() {
+        Some(v) if !v.trim().is_empty() => {
+            let saved =
+                saved_filters::load(&state, v, &access.user_id, saved_filters::Target::Testcases)
+                    .await?;
+            if saved.project_id != project_uuid {
+                return Err(ApiError::SavedFilterProjectMismatch);
+            }
+            ListTestcasesQuery {
+                suite_id: query.suite_id.or_else(|| saved.param("suiteId")),
+                custom_fields: query.custom_fields.or_else(|| saved.param("customFields")),
+                ..query
+            }
+        }
+        _ => query,
+    };
+    let suite_uuid = match query.suite_id.as_deref() {
+        Some(v) if !v.trim().is_empty() => Some(parse_uuid(v, ApiError::InvalidSuiteIdParam)?),
+        _ => None,
+    };
+    let field_filter = match query.custom_fields.as_deref() {
+        Some(raw) if !raw.trim().is_empty() => {
+            let definitions = custom_fields::definitions(
+                &state.db,
+                project_uuid,
+                custom_fields::Entity::Testcase,
+            )
+            .await?;
+            Some(custom_fields::parse_filter(&definitions, raw)?)
+        }
+        _ => None,
+    };
+    let limit = pagination::page_limit(query.limit);
+    let cursor = pagination::parse_cursor(query.cursor.as_deref())?;
+
+    let rows = sqlx::query(
+        r#"
+        SELECT
+          tc.id::text AS id,
+          tc.suite_id::text AS suite_id,
+          tc.key,
+          tc.title,
+          tc.is_required,
+          tc.is_archived,
+          (
+            SELECT MAX(tv.version_number) FROM testcase_versions tv WHERE tv.testcase_id = tc.id
+          ) AS latest_version_number,
+          tc.source_path,
+          tc.custom_fields,
+          tc.created_at::text AS created_at,
+          tc.updated_at::text AS updated_at
+        FROM testcases tc
+        JOIN test_suites s ON s.id = tc.suite_id
+        WHERE s.project_id = $1
+          AND ($7 OR tc.is_archived = FALSE)
+          AND ($2::uuid IS NULL OR tc.suite_id = $2)
+          AND ($3::timestamptz IS NULL OR (tc.created_at, tc.id) < ($3::timestamptz, $4::uuid))
+          AND ($6::jsonb IS NULL OR tc.custom_fields @> $6)
+        ORDER BY tc.created_at DESC, tc.id DESC
+        LIMIT $5
+        "#,
+    )
+    .bind(project_uuid)
+    .bind(suite_uuid)
+    .bind(cursor.as_ref().map(|c| c.created_at.clone()))
+    .bind(cursor.as_ref().map(|c| c.id.clone()))
+    .bind(limit + 1)
+    .bind(field_filter)
+    .bind(query.include_archived.unwrap_or(false))
+    .fetch_all(state.read_db())
+    .await
+    .map_err(|_| ApiError::TestcasesReadFailed)?;
+
+    let testcases: Vec<TestcaseView> = rows
+        .into_iter()
+        .map(|r| TestcaseView {
+            id: r.get::<String, _>("id"),
+            suite_id: r.get::<String, _>("suite_id"),
+            key: r.get::<String, _>("key"),
+            title: r.get::<String, _>("title"),
+            is_required: r.get::<bool, _>("is_required"),
+            is_archived: r.get::<bool, _>("is_archived"),
+            latest_version_number: r.get::<Option<i32>, _>("latest_version_number"),
+            source_path: r.get::<Option<String>, _>("source_path"),
+            custom_fields: r.get::<Value, _>("custom_fields"),
+            created_at: r.get::<String, _>("created_at"),
+            updated_at: r.get::<String, _>("updated_at"),
+        })
+        .collect();
+    let (testcases, next_cursor) =
+        pagination::finish_page(testcases, limit, |t| pagination::Cursor {
+            created_at: t.created_at.clone(),
+            id: t.id.clone(),
+        });
+
+    Ok(Json(ListTestcasesResponse {
+        testcases,
+        next_cursor,
+    }))
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TestcaseStepView {
+    id: String,
+    position: i32,
+    action: String,
+    expected_result: String,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TestcaseVersionView {
+    id: String,
+    version_number: i32,
+    summary: String,
+    preconditions: String,
+    steps: Vec<TestcaseStepView>,
+    change_note: String,
+    created_by_user_id: Option<String>,
+    created_at: String,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ListTestcaseVersionsResponse {
+    /// Newest first.
+    versions: Vec<TestcaseVersionView>,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TestcaseStepRequest {
+    action: String,
+    expected_result: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateTestcaseVersionRequest {
+    summary: Option<String>,
+    preconditions: Option<String>,
+    steps: Vec<TestcaseStepRequest>,
+    change_note: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TestcaseVersionResponse {
+    version: TestcaseVersionView,
+}
+
+/// Resolves the testcase's project and checks the actor's access to it.
+pub async fn authorize_testcase(
+    state: &AppState,
+    testcase_id: Uuid,
+    actor_id: &str,
+    capability: Capability,
+) -> Result<Uuid, ApiError> {
+    let project_id: Option<String> = sqlx::query_scalar(
+        r#"
+        SELECT s.project_id::text
+        FROM testcases tc
+        JOIN test_suites s ON s.id = tc.suite_id
+        WHERE tc.id = $1
+        "#,
+    )
+    .bind(testcase_id)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|_| ApiError::TestcaseReadFailed)?;
+    let project_id = project_id.ok_or(ApiError::TestcaseNotFound)?;
+    authz::require_capability(state, &project_id, actor_id, capability).await?;
+    parse_uuid(&project_id, ApiError::InvalidProjectId)
+}
+
+/// Versions of a testcase with their steps; one version when `version_id` is given.
+async fn fetch_versions(
+    db: &PgPool,
+    testcase_id: Uuid,
+    version_id: Option<Uuid>,
+) -> Result<Vec<TestcaseVersionView>, ApiError> {
+    let rows = sqlx::query(
+        r#"
+        SELECT
+          id::text AS id,
+          version_number,
+          summary,
+          preconditions,
+          change_note,
+          created_by_user_id::text AS created_by_user_id,
+          created_at::text AS created_at
+        FROM testcase_versions
+        WHERE testcase_id = $1 AND ($2::uuid IS NULL OR id = $2)
+        ORDER BY version_number DESC
+        "#,
+    )
+    .bind(testcase_id)
+    .bind(version_id)
+    .fetch_all(db)
+    .await
+    .map_err(|_| ApiError::TestcaseVersionsReadFailed)?;
+    let step_rows = sqlx::query(
+        r#"
+        SELECT
+          ts.testcase_version_id::text AS version_id,
+          ts.id::text AS id,
+          ts.position,
+          ts.action,
+          ts.expected_result
+        FROM testcase_steps ts
+        JOIN testcase_versions tv ON tv.id = ts.testcase_version_id
+        WHERE tv.testcase_id = $1 AND ($2::uuid IS NULL OR tv.id = $2)
+        ORDER BY ts.position ASC
+        "#,
+    )
+    .bind(testcase_id)
+    .bind(version_id)
+    .fetch_all(db)
+    .await
+    .map_err(|_| ApiError::TestcaseVersionsReadFailed)?;
+
+    let mut steps: HashMap<String, Vec<TestcaseStepView>> = HashMap::new();
+    for r in step_rows {
+        steps
+            .entry(r.get::<String, _>("version_id"))
+            .or_default()
+            .push(TestcaseStepView {
+                id: r.get("id"),
+                position: r.get("position"),
+                action: r.get("action"),
+                expected_result: r.get("expected_result"),
+            });
+    }
+    Ok(rows
+        .into_iter()
+        .map(|r| {
+            let id = r.get::<String, _>("id");
+            TestcaseVersionView {
+                steps: steps.remove(&id).unwrap_or_default(),
+                id,
+                version_number: r.get("version_number"),
+                summary: r.get("summary"),
+                preconditions: r.get("preconditions"),
+                change_note: r.get("change_note"),
+                created_by_user_id: r.get("created_by_user_id"),
+                created_at: r.get("created_at"),
+            }
+        })
+        .collect())
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v2/testcases/{testcase_id}/versions",
+    tag = "library",
+    params(("testcase_id" = String, Path)),
+    responses((status = 200, body = ListTestcaseVersionsResponse))
+)]
+pub async fn list_testcase_versions(
+    State(state): State<AppState>,
+    Path(testcase_id): Path<String>,
+    AuthUser(actor_id): AuthUser,
+) -> Result<Json<ListTestcaseVersionsResponse>, ApiError> {
+    let testcase_uuid = parse_uuid(&testcase_id, ApiError::InvalidTestcaseId)?;
+    authorize_testcase(&state, testcase_uuid, &actor_id, Capability::ProjectRead).await?;
+    let versions = fetch_versions(&state.db, testcase_uuid, None).await?;
+    Ok(Json(ListTestcaseVersionsResponse { versions }))
+}
+
+/// Adds a version with the given steps. Fields not edited here (estimate, complexity,
+/// artifacts) are carried over from the previous version; runs keep the version they were
+/// built from.
+#[utoipa::path(
+    post,
+    path = "/api/v2/testcases/{testcase_id}/versions",
+    tag = "library",
+    params(("testcase_id" = String, Path)),
+    request_body = CreateTestcaseVersionRequest,
+    responses((status = 201, body = TestcaseVersionResponse))
+)]
+pub async fn create_testcase_version(
+    State(state): State<AppState>,
+    Path(testcase_id): Path<String>,
+    AuthUser(actor_id): AuthUser,
+    Json(payload): Json<CreateTestcaseVersionRequest>,
+) -> Result<(StatusCode, Json<TestcaseVersionResponse>), ApiError> {
+    let testcase_uuid = parse_uuid(&testcase_id, ApiError::InvalidTestcaseId)?;
+    let summary = payload.summary.unwrap_or_default().trim().to_string();
+    let preconditions = payload.preconditions.unwrap_or_default().trim().to_string();
+    let change_note = payload.change_note.unwrap_or_default().trim().to_string();
+    if summary.chars().count() > MAX_VERSION_TEXT_CHARS
+        || preconditions.chars().count() > MAX_VERSION_TEXT_CHARS
+        || change_note.chars().count() > MAX_CHANGE_NOTE_CHARS
+    {
+        return Err(ApiError::InvalidTestcaseVersionText);
+    }
+    let steps: Vec<(String, String)> = payload
+        .steps
+        .into_iter()
+        .map(|step| {
+            (
+                step.action.trim().to_string(),
+                step.expected_result.unwrap_or_default().trim().to_string(),
+            )
+        })
+        .collect();
+    if steps.is_empty()
+        || steps.len() > MAX_STEPS
+        || steps.iter().any(|(action, expected)| {
+            action.is_empty()
+                || action.chars().count() > MAX_STEP_CHARS
+                || expected.chars().count() > MAX_STEP_CHARS
+        })
+    {
+        return Err(ApiError::InvalidTestcaseSteps);
+    }
+    let project_id =
+        authorize_testcase(&state, testcase_uuid, &actor_id, Capability::LibraryEdit).await?;
+    ensure_db_user_exists(&state, &actor_id).await?;
+    let actor_uuid = parse_uuid(&actor_id, ApiError::InvalidUserId)?;
+
+    // Steps are stored as the paired steps_json/expected_json arrays; the testcase_steps
+    // rows are derived from them by a trigger.
+    let actions: Vec<&str> = steps.iter().map(|(action, _)| action.as_str()).collect();
+    let expected: Vec<&str> = steps
+        .iter()
+        .map(|(_, expected)| expected.as_str())
+        .collect();
+    let failed = |_| ApiError::TestcaseVersionCreateFailed;
+    let mut tx = state.db.begin().await.map_err(failed)?;
+    sqlx::query(
+        r#"
+        UPDATE testcases
+        SET updated_at = NOW(), updated_by_user_id = $2
+        WHERE id = $1
+        "#,
+    )
+    .bind(testcase_uuid)
+    .bind(actor_uuid)
+    .execute(&mut *tx)
+    .await
+    .map_err(failed)?;
+    let row = sqlx::query(
+        r#"
+        INSERT INTO testcase_versions (
+          testcase_id, version_number, summary, preconditions, steps_json, expected_json,
+          typical_artifacts_json, common_mistakes_json, is_mandatory, estimated_minutes,
+          complexity, change_note, created_by_user_id
+        )
+        SELECT
+          $1, COALESCE(prev.version_number, 0) + 1, $2, $3, $4, $5,
+          COALESCE(prev.typical_artifacts_json, '[]'::jsonb),
+          COALESCE(prev.common_mistakes_json, '[]'::jsonb),
+          COALESCE(prev.is_mandatory, TRUE), prev.estimated_minutes, prev.complexity, $6, $7
+        FROM (SELECT 1) AS one
+        LEFT JOIN LATERAL (
+          SELECT * FROM testcase_versions
+          WHERE testcase_id = $1
+          ORDER BY version_number DESC
+          LIMIT 1
+        ) prev ON TRUE
+        RETURNING id, version_number
+        "#,
+    )
+    .bind(testcase_uuid)
+    .bind(&summary)
+    .bind(&preconditions)
+    .bind(json!(actions))
+    .bind(json!(expected))
+    .bind(&change_note)
+    .bind(actor_uuid)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(failed)?;
+    let version_id: Uuid = row.get("id");
+    audit::record(
+        &mut *tx,
+        AuditEntry {
+            actor_user_id: Some(actor_uuid),
+            action: "create",
+            entity_type: "testcase_version",
+            entity_id: Some(version_id),
+            project_id: Some(project_id),
+            run_id: None,
+            before: None,
+            after: Some(json!({
+                "testcaseId": testcase_uuid,
+                "versionNumber": row.get::<i32, _>("version_number"),
+                "steps": steps.len(),
+                "changeNote": change_note,
+            })),
+        },
+    )
+    .await
+    .map_err(failed)?;
+    tx.commit().await.map_err(failed)?;
+
+    let version = fetch_versions(&state.db, testcase_uuid, Some(version_id))
+        .await?
+        .pop()
+        .ok_or(ApiError::TestcaseVersionCreateFailed)?;
+    Ok((
+        StatusCode::CREATED,
+        Json(TestcaseVersionResponse { version }),
+    ))
+}
+
+/// Runs listed in the usage report; `runCount` counts all of them.
+const USAGE_RUN_LIMIT: i64 = 100;
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TestcaseUsageRunView {
+    run_id: String,
+    run_item_id: String,
+    title: String,
+    status: String,
+    version_number: i32,
+    created_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TestcaseUsageTemplateView {
+    template_id: String,
+    key: String,
+    name: String,
+    is_active: bool,
+    version_number: i32,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TestcaseUsageRequirementView {
+    requirement_id: String,
+    key: String,
+    title: String,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TestcaseUsageResponse {
+    testcase_id: String,
+    is_archived: bool,
+    run_count: i64,
+    /// Runs in `draft` or `in_progress`.
+    open_run_count: i64,
+    /// Open runs first, then newest; at most 100.
+    runs: Vec<TestcaseUsageRunView>,
+    templates: Vec<TestcaseUsageTemplateView>,
+    requirements: Vec<TestcaseUsageRequirementView>,
+}
+
+async fn fetch_usage(
+    db: &PgPool,
+    testcase_id: Uuid,
+    is_archived: bool,
+) -> Result<TestcaseUsageResponse, ApiError> {
+    let read_failed = |_| ApiError::TestcaseUsageReadFailed;
+    let counts = sqlx::query!(
+        r#"
+        SELECT
+          COUNT(DISTINCT ri.run_id) AS "run_count!",
+          COUNT(DISTINCT ri.run_id) FILTER (
+            WHERE r.status IN ('draft', 'in_progress')
+          ) AS "open_run_count!"
+        FROM run_items ri
+        JOIN runs r ON r.id = ri.run_id
+        JOIN testcase_versions tv ON tv.id = ri.testcase_version_id
+        WHERE tv.testcase_id = $1
+        "#,
+        testcase_id,
+    )
+    .fetch_one(db)
+    .await
+    .map_err(read_failed)?;
+    let runs = sqlx::query_as!(
+        TestcaseUsageRunView,
+        r#"
+        SELECT
+          r.id::text AS "run_id!",
+          ri.id::text AS "run_item_id!",
+          r.title,
+          r.status::text AS "status!",
+          tv.version_number,
+          r.created_at
+        FROM run_items ri
+        JOIN runs r ON r.id = ri.run_id
+        JOIN testcase_versions tv ON tv.id = ri.testcase_version_id
+        WHERE tv.testcase_id = $1
+        ORDER BY r.status IN ('draft', 'in_progress') DESC, r.created_at DESC, ri.id
+        LIMIT $2
+        "#,
+        testcase_id,
+        USAGE_RUN_LIMIT,
+    )
+    .fetch_all(db)
+    .await
+    .map_err(read_failed)?;
+    let templates = sqlx::query_as!(
+        TestcaseUsageTemplateView,
+        r#"
+        SELECT
+          rt.id::text AS "template_id!",
+          rt.key,
+          rt.name,
+          rt.is_active,
+          tv.version_number
+        FROM run_template_items rti
+        JOIN run_templates rt ON rt.id = rti.template_id
+        JOIN testcase_versions tv ON tv.id = rti.testcase_version_id
+        WHERE tv.testcase_id = $1
+        ORDER BY rt.key, tv.version_number
+        "#,
+        testcase_id,
+    )
+    .fetch_all(db)
+    .await
+    .map_err(read_failed)?;
+    let requirements = sqlx::query_as!(
+        TestcaseUsageRequirementView,
+        r#"
+        SELECT rq.id::text AS "requirement_id!", rq.key, rq.title
+        FROM requirement_testcases rt
+        JOIN requirements rq ON rq.id = rt.requirement_id
+        WHERE rt.testcase_id = $1
+        ORDER BY rq.key
+        "#,
+        testcase_id,
+    )
+    .fetch_all(db)
+    .await
+    .map_err(read_failed)?;
+
+    Ok(TestcaseUsageResponse {
+        testcase_id: testcase_id.to_string(),
+        is_archived,
+        run_count: counts.run_count,
+        open_run_count: counts.open_run_count,
+        runs,
+        templates,
+        requirements,
+    })
+}
+
+/// Where the test case is still used: runs and run templates holding any of its versions
+/// and linked requirements. Meant for archived cases before they are deleted.
+#[utoipa::path(
+    get,
+    path = "/api/v2/testcases/{testcase_id}/usage",
+    tag = "library",
+    params(("testcase_id" = String, Path)),
+    responses((status = 200, body = TestcaseUsageResponse))
+)]
+pub async fn get_testcase_usage(
+    State(state): State<AppState>,
+    Path(testcase_id): Path<String>,
+    AuthUser(actor_id): AuthUser,
+) -> Result<Json<TestcaseUsageResponse>, ApiError> {
+    let testcase_uuid = parse_uuid(&testcase_id, ApiError::InvalidTestcaseId)?;
+    authorize_testcase(&state, testcase_uuid, &actor_id, Capability::ProjectRead).await?;
+    let is_archived = sqlx::query_scalar!(
+        "SELECT is_archived FROM testcases WHERE id = $1",
+        testcase_uuid,
+    )
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|_| ApiError::TestcaseReadFailed)?
+    .ok_or(ApiError::TestcaseNotFound)?;
+    Ok(Json(
+        fetch_usage(&state.db, testcase_uuid, is_archived).await?,
+    ))
+}
+
+/// Archives the test case: it is hidden from the library and cannot be added to new runs,
+/// while runs that already hold its versions are not touched. Returns where it is still
+/// used.
+#[utoipa::path(
+    post,
+    path = "/api/v2/testcases/{testcase_id}/archive",
+    tag = "library",
+    params(("testcase_id" = String, Path)),
+    responses((status = 200, body = TestcaseUsageResponse))
+)]
+pub async fn archive_testcase(
+    State(state): State<AppState>,
+    Path(testcase_id): Path<String>,
+    AuthUser(actor_id): AuthUser,
+) -> Result<Json<TestcaseUsageResponse>, ApiError> {
+    set_archived(state, testcase_id, actor_id, true).await
+}
+
+/// Returns an archived test case to the library.
+#[utoipa::path(
+    post,
+    path = "/api/v2/testcases/{testcase_id}/restore",
+    tag = "library",
+    params(("testcase_id" = String, Path)),
+    responses((status = 200, body = TestcaseUsageResponse))
+)]
+pub async fn restore_testcase(
+    State(state): State<AppState>,
+    Path(testcase_id): Path<String>,
+    AuthUser(actor_id): AuthUser,
+) -> Result<Json<TestcaseUsageResponse>, ApiError> {
+    set_archived(state, testcase_id, actor_id, false).await
+}
+
+async fn set_archived(
+    state: AppState,
+    testcase_id: String,
+    actor_id: String,
+    archived: bool,
+) -> Result<Json<TestcaseUsageResponse>, ApiError> {
+    let testcase_uuid = parse_uuid(&testcase_id, ApiError::InvalidTestcaseId)?;
+    let project_id =
+        authorize_testcase(&state, testcase_uuid, &actor_id, Capability::LibraryEdit).await?;
+    ensure_db_user_exists(&state, &actor_id).await?;
+    let actor_uuid = parse_uuid(&actor_id, ApiError::InvalidUserId)?;
+
+    let failed = |_| ApiError::TestcaseArchiveFailed;
+    let mut tx = state.db.begin().await.map_err(failed)?;
+    let updated = sqlx::query!(
+        r#"
+        UPDATE testcases
+        SET is_archived = $2, updated_by_user_id = $3
+        WHERE id = $1 AND is_archived <> $2
+        "#,
+        testcase_uuid,
+        archived,
+        actor_uuid,
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(failed)?;
+    if updated.rows_affected() == 0 {
+        return Err(if archived {
+            ApiError::TestcaseAlreadyArchived
+        } else {
+            ApiError::TestcaseNotArchived
+        });
+    }
+    audit::record(
+        &mut *tx,
+        AuditEntry {
+            actor_user_id: Some(actor_uuid),
+            action: "update",
+            entity_type: "testcase",
+            entity_id: Some(testcase_uuid),
+            project_id: Some(project_id),
+            run_id: None,
+            before: Some(json!({ "isArchived": !archived })),
+            after: Some(json!({ "isArchived": archived })),
+        },
+    )
+    .await
+    .map_err(failed)?;
+    tx.commit().await.map_err(failed)?;
+
+    Ok(Json(fetch_usage(&state.db, testcase_uuid, archived).await?))
+}
+
+/// Deletes the test case with all its versions. Only possible while no run or run
+/// template holds any of its versions; otherwise archive it. Requires `project.manage`.
+#[utoipa::path(
+    delete,
+    path = "/api/v2/testcases/{testcase_id}",
+    tag = "library",
+    params(("testcase_id" = String, Path)),
+    responses((status = 204, description = "Тест-кейс удалён."))
+)]
+pub async fn delete_testcase(
+    State(state): State<AppState>,
+    Path(testcase_id): Path<String>,
+    AuthUser(actor_id): AuthUser,
+) -> Result<StatusCode, ApiError> {
+    let testcase_uuid = parse_uuid(&testcase_id, ApiError::InvalidTestcaseId)?;
+    let project_id =
+        authorize_testcase(&state, testcase_uuid, &actor_id, Capability::ProjectManage).await?;
+    ensure_db_user_exists(&state, &actor_id).await?;
+    let actor_uuid = parse_uuid(&actor_id, ApiError::InvalidUserId)?;
+
+    let failed = |_| ApiError::TestcaseDeleteFailed;
+    let mut tx = state.db.begin().await.map_err(failed)?;
+    let testcase = sqlx::query!(
+        r#"
+        SELECT suite_id, key, title, is_archived
+        FROM testcases
+        WHERE id = $1
+        FOR UPDATE
+        "#,
+        testcase_uuid,
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(failed)?
+    .ok_or(ApiError::TestcaseNotFound)?;
+    // Locking the versions makes a concurrent insert into run_items wait for this
+    // transaction, so the checks below cannot go stale before the delete.
+    let usage = sqlx::query!(
+        r#"
+        SELECT
+          EXISTS (SELECT 1 FROM run_items WHERE testcase_version_id = ANY(v.ids)) AS "in_runs!",
+          EXISTS (
+            SELECT 1 FROM run_template_items WHERE testcase_version_id = ANY(v.ids)
+          ) AS "in_templates!"
+        FROM (
+          SELECT COALESCE(array_agg(id), '{}') AS ids
+          FROM (SELECT id FROM testcase_versions WHERE testcase_id = $1 FOR UPDATE) locked
+        ) v
+        "#,
+        testcase_uuid,
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(failed)?;
+    if usage.in_runs {
+        return Err(ApiError::TestcaseUsedInRuns);
+    }
+    if usage.in_templates {
+        return Err(ApiError::TestcaseUsedInTemplates);
+    }
+
+    sqlx::query!("DELETE FROM testcases WHERE id = $1", testcase_uuid)
+        .execute(&mut *tx)
+        .await
+        .map_err(|err| db_errors::map(err, ApiError::TestcaseDeleteFailed))?;
+    audit::record(
+        &mut *tx,
+        AuditEntry {
+            actor_user_id: Some(actor_uuid),
+            action: "delete",
+            entity_type: "testcase",
+            entity_id: Some(testcase_uuid),
+            project_id: Some(project_id),
+            run_id: None,
+            before: Some(json!({
+                "suiteId": testcase.suite_id,
+                "key": testcase.key,
+                "title": testcase.title,
+                "isArchived": testcase.is_archived,
+            })),
+            after: None,
+        },
+    )
+    .await
+    .map_err(failed)?;
+    tx.commit().await.map_err(failed)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,489 @@
+use std::{collections::HashMap, time::Duration};
+
+use anyhow::{bail, Context};
+use chrono::{DateTime, Utc};
+use reqwest::{header::RETRY_AFTER, StatusCode};
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::Value;
+use tracing::warn;
+
+use crate::migration_import::{
+    parse_timespan, SourceCase, SourceProject, SourceResult, SourceRun, SourceSuite, SourceTest,
+};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+/// Attempts of a request TestRail answers with `429`.
+const MAX_ATTEMPTS: u32 = 5;
+
+#[derive(Deserialize)]
+struct TrProject {
+    id: i64,
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct TrSuite {
+    id: i64,
+    name: String,
+    description: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TrSection {
+    id: i64,
+    parent_id: Option<i64>,
+    name: String,
+    description: Option<String>,
+    #[serde(default)]
+    depth: i64,
+    #[serde(default)]
+    display_order: i64,
+}
+
+#[derive(Deserialize)]
+struct TrStep {
+    content: Option<String>,
+    expected: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TrCase {
+    id: i64,
+    title: String,
+    section_id: Option<i64>,
+    estimate: Option<String>,
+    custom_preconds: Option<String>,
+    custom_steps: Option<String>,
+    custom_expected: Option<String>,
+    custom_steps_separated: Option<Vec<TrStep>>,
+}
+
+#[derive(Deserialize)]
+struct TrMilestone {
+    id: i64,
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct TrRun {
+    id: i64,
+    name: String,
+    milestone_id: Option<i64>,
+    #[serde(default)]
+    is_completed: bool,
+    created_on: Option<i64>,
+    completed_on: Option<i64>,
+    created_by: Option<i64>,
+}
+
+#[derive(Deserialize)]
+struct TrPlan {
+    id: i64,
+}
+
+#[derive(Deserialize)]
+struct TrPlanEntry {
+    #[serde(default)]
+    runs: Vec<TrRun>,
+}
+
+#[derive(Deserialize)]
+struct TrPlanDetails {
+    name: String,
+    milestone_id: Option<i64>,
+    #[serde(default)]
+    entries: Vec<TrPlanEntry>,
+}
+
+#[derive(Deserialize)]
+struct TrTest {
+    id: i64,
+    case_id: Option<i64>,
+}
+
+#[derive(Deserialize)]
+struct TrStepResult {
+    status_id: Option<i64>,
+    actual: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TrResult {
+    test_id: i64,
+    status_id: Option<i64>,
+    comment: Option<String>,
+    created_by: Option<i64>,
+    created_on: Option<i64>,
+    elapsed: Option<String>,
+    custom_step_results: Option<Vec<TrStepResult>>,
+}
+
+#[derive(Deserialize)]
+struct TrStatus {
+    id: i64,
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct TrUser {
+    id: i64,
+    email: String,
+}
+
+fn timestamp(seconds: Option<i64>) -> Option<DateTime<Utc>> {
+    DateTime::from_timestamp(seconds?, 0)
+}
+
+fn text(value: Option<&String>) -> String {
+    value.map(|v| v.trim().to_string()).unwrap_or_default()
+}
+
+/// Result status of a TestRail status by its system name; `untested` has none. Custom
+/// statuses become `na`.
+fn map_status(name: &str) -> Option<&'static str> {
+    match name {
+        "passed" => Some("ok"),
+        "failed" => Some("fail"),
+        "blocked" => Some("blocked"),
+        "retest" => Some("retest"),
+        "untested" => None,
+        _ => Some("na"),
+    }
+}
+
+/// TestRail API v2 with basic auth of the user email and an API key. Statuses and users are
+/// read once on connect.
+pub struct Client {
+    http: reqwest::Client,
+    base_url: String,
+    user: String,
+    api_key: String,
+    statuses: HashMap<i64, Option<&'static str>>,
+    emails: HashMap<i64, String>,
+}
+
+impl Client {
+    pub async fn connect(url: &str, token: &str) -> anyhow::Result<Self> {
+        let Some((user, api_key)) = token.split_once(':') else {
+            bail!("the TestRail token is `user@example.com:api_key`");
+        };
+        let mut client = Self {
+            http: reqwest::Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .build()?,
+            base_url: url.to_string(),
+            user: user.to_string(),
+            api_key: api_key.to_string(),
+            statuses: HashMap::new(),
+            emails: HashMap::new(),
+        };
+        let statuses: Vec<TrStatus> = client.get("get_statuses").await?;
+        client.statuses = statuses
+            .into_iter()
+            .map(|s| (s.id, map_status(&s.name)))
+            .collect();
+        // Listing users needs administrator rights on some instances; results then fall back
+        // to the owner.
+        match client.list::<TrUser>("get_users", "users").await {
+            Ok(users) => {
+                client.emails = users.into_iter().map(|u| (u.id, u.email)).collect();
+            }
+            Err(err) => warn!("TestRail users are not readable, results keep the owner: {err:#}"),
+        }
+        Ok(client)
+    }
+
+    async fn get<T: DeserializeOwned>(&self, endpoint: &str) -> anyhow::Result<T> {
+        let url = format!("{}/index.php?/api/v2/{endpoint}", self.base_url);
+        let mut attempt = 1;
+        loop {
+            let response = self
+                .http
+                .get(&url)
+                .basic_auth(&self.user, Some(&self.api_key))
+                .send()
+                .await
+                .with_context(|| format!("TestRail {endpoint}"))?;
+            let status = response.status();
+            if status == StatusCode::TOO_MANY_REQUESTS && attempt < MAX_ATTEMPTS {
+                let wait = response
+                    .headers()
+                    .get(RETRY_AFTER)
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(|v| v.trim().parse().ok())
+                    .unwrap_or(10);
+                warn!("TestRail rate limit hit, retrying {endpoint} in {wait}s");
+                tokio::time::sleep(Duration::from_secs(wait)).await;
+                attempt += 1;
+                continue;
+            }
+            if !status.is_success() {
+                let body: Value = response.json().await.unwrap_or_default();
+                let message = body
+                    .get("error")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default();
+                bail!("TestRail {endpoint}: {status} {message}");
+            }
+            return response
+                .json()
+                .await
+                .with_context(|| format!("TestRail {endpoint}: unexpected response"));
+        }
+    }
+
+    /// Bulk endpoints return a page with a `next` link since TestRail 6.7 and a plain array
+    /// before it.
+    async fn list<T: DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        field: &str,
+    ) -> anyhow::Result<Vec<T>> {
+        let mut items = Vec::new();
+        let mut next = Some(endpoint.to_string());
+        while let Some(endpoint) = next.take() {
+            let batch = match self.get::<Value>(&endpoint).await? {
+                Value::Array(batch) => batch,
+                Value::Object(mut page) => {
+                    next = page
+                        .get("_links")
+                        .and_then(|links| links.get("next"))
+                        .and_then(Value::as_str)
+                        .map(|link| link.trim_start_matches("/api/v2/").to_string());
+                    match page.remove(field) {
+                        Some(Value::Array(batch)) => batch,
+                        _ => bail!("TestRail {endpoint}: no `{field}` in the response"),
+                    }
+                }
+                _ => bail!("TestRail {endpoint}: unexpected response"),
+            };
+            if batch.is_empty() {
+                break;
+            }
+            for item in batch {
+                items.push(
+                    serde_json::from_value(item)
+                        .with_context(|| format!("TestRail {endpoint}: unexpected {field}"))?,
+                );
+            }
+        }
+        Ok(items)
+    }
+
+    /// Projects by id or name; all of them without a selection.
+    pub async fn projects(&self, selected: &[String]) -> anyhow::Result<Vec<SourceProject>> {
+        let projects: Vec<TrProject> = self.list("get_projects", "projects").await?;
+        let mut chosen = Vec::new();
+        for wanted in selected {
+            let project = projects
+                .iter()
+                .find(|p| p.id.to_string() == *wanted || p.name.eq_ignore_ascii_case(wanted))
+                .with_context(|| format!("TestRail has no project {wanted}"))?;
+            chosen.push(project);
+        }
+        if selected.is_empty() {
+            chosen = projects.iter().collect();
+        }
+        Ok(chosen
+            .into_iter()
+            .map(|p| SourceProject {
+                id: p.id.to_string(),
+                name: p.name.clone(),
+            })
+            .collect())
+    }
+
+    /// Each suite (`S1`) becomes a top-level suite and its sections (`SEC10`) suites below it.
+    pub async fn suites(&self, project: &SourceProject) -> anyhow::Result<Vec<SourceSuite>> {
+        let suites: Vec<TrSuite> = self.get(&format!("get_suites/{}", project.id)).await?;
+        let mut out = Vec::new();
+        for suite in suites {
+            let suite_key = format!("S{}", suite.id);
+            out.push(SourceSuite {
+                id: suite_key.clone(),
+                parent_id: None,
+                name: suite.name,
+                description: text(suite.description.as_ref()),
+            });
+            let mut sections: Vec<TrSection> = self
+                .list(
+                    &format!("get_sections/{}&suite_id={}", project.id, suite.id),
+                    "sections",
+                )
+                .await?;
+            sections.sort_by_key(|s| (s.depth, s.display_order));
+            out.extend(sections.into_iter().map(|section| {
+                SourceSuite {
+                    id: format!("SEC{}", section.id),
+                    parent_id: Some(
+                        section
+                            .parent_id
+                            .map_or(suite_key.clone(), |parent| format!("SEC{parent}")),
+                    ),
+                    name: section.name,
+                    description: text(section.description.as_ref()),
+                }
+            }));
+        }
+        Ok(out)
+    }
+
+    /// Steps come from the separated-steps template when a case uses it, otherwise the
+    /// text template gives one step.
+    pub async fn cases(&self, project: &SourceProject) -> anyhow::Result<Vec<SourceCase>> {
+        let suites: Vec<TrSuite> = self.get(&format!("get_suites/{}", project.id)).await?;
+        let mut out = Vec::new();
+        for suite in suites {
+            let cases: Vec<TrCase> = self
+                .list(
+                    &format!("get_cases/{}&suite_id={}", project.id, suite.id),
+                    "cases",
+                )
+                .await?;
+            for case in cases {
+                let (steps, expected) = match &case.custom_steps_separated {
+                    Some(separated) if !separated.is_empty() => separated
+                        .iter()
+                        .map(|s| (text(s.content.as_ref()), text(s.expected.as_ref())))
+                        .unzip(),
+                    _ => {
+                        let (step, expected) = (
+                            text(case.custom_steps.as_ref()),
+                            text(case.custom_expected.as_ref()),
+                        );
+                        if step.is_empty() && expected.is_empty() {
+                            (Vec::new(), Vec::new())
+                        } else {
+                            (vec![step], vec![expected])
+                        }
+                    }
+                };
+                out.push(SourceCase {
+                    id: case.id.to_string(),
+                    suite_id: case
+                        .section_id
+                        .map_or(format!("S{}", suite.id), |s| format!("SEC{s}")),
+                    key: format!("C{}", case.id),
+                    title: case.title,
+                    preconditions: text(case.custom_preconds.as_ref()),
+                    steps,
+                    expected,
+                    estimated_minutes: case
+                        .estimate
+                        .as_deref()
+                        .and_then(parse_timespan)
+                        .map(|s| ((s + 59) / 60).min(i32::MAX as i64) as i32),
+                });
+            }
+        }
+        Ok(out)
+    }
+
+    fn run(&self, run: TrRun, title: String, milestone: Option<String>) -> SourceRun {
+        SourceRun {
+            id: run.id.to_string(),
+            title,
+            milestone,
+            author_email: run.created_by.and_then(|id| self.emails.get(&id).cloned()),
+            created_at: timestamp(run.created_on),
+            completed_at: if run.is_completed {
+                timestamp(run.completed_on)
+            } else {
+                None
+            },
+        }
+    }
+
+    /// Standalone runs and the runs of test plans, titled `Plan › Run`.
+    pub async fn runs(&self, project: &SourceProject) -> anyhow::Result<Vec<SourceRun>> {
+        let milestones: HashMap<i64, String> = self
+            .list::<TrMilestone>(&format!("get_milestones/{}", project.id), "milestones")
+            .await?
+            .into_iter()
+            .map(|m| (m.id, m.name))
+            .collect();
+        let milestone = |id: Option<i64>| id.and_then(|id| milestones.get(&id).cloned());
+        let mut out = Vec::new();
+        let runs: Vec<TrRun> = self
+            .list(&format!("get_runs/{}", project.id), "runs")
+            .await?;
+        for run in runs {
+            let milestone = milestone(run.milestone_id);
+            let title = run.name.clone();
+            out.push(self.run(run, title, milestone));
+        }
+        let plans: Vec<TrPlan> = self
+            .list(&format!("get_plans/{}", project.id), "plans")
+            .await?;
+        for plan in plans {
+            let plan: TrPlanDetails = self.get(&format!("get_plan/{}", plan.id)).await?;
+            for run in plan.entries.into_iter().flat_map(|e| e.runs) {
+                let milestone = milestone(run.milestone_id.or(plan.milestone_id));
+                let title = format!("{} › {}", plan.name, run.name);
+                out.push(self.run(run, title, milestone));
+            }
+        }
+        out.sort_by_key(|run| run.created_at);
+        Ok(out)
+    }
+
+    /// Tests of the run with the latest result that set a status; comment-only results
+    /// are skipped.
+    pub async fn tests(&self, run: &SourceRun) -> anyhow::Result<Vec<SourceTest>> {
+        let tests: Vec<TrTest> = self.list(&format!("get_tests/{}", run.id), "tests").await?;
+        let results: Vec<TrResult> = self
+            .list(&format!("get_results_for_run/{}", run.id), "results")
+            .await?;
+        let mut latest: HashMap<i64, TrResult> = HashMap::new();
+        for result in results {
+            if result.status_id.and_then(|id| self.status(id)).is_none() {
+                continue;
+            }
+            match latest.get(&result.test_id) {
+                Some(kept) if kept.created_on >= result.created_on => {}
+                _ => {
+                    latest.insert(result.test_id, result);
+                }
+            }
+        }
+        Ok(tests
+            .into_iter()
+            .filter_map(|test| {
+                let case_id = test.case_id?;
+                let result = latest.remove(&test.id).map(|result| SourceResult {
+                    status: result
+                        .status_id
+                        .and_then(|id| self.status(id))
+                        .unwrap_or("na"),
+                    comment: text(result.comment.as_ref()),
+                    elapsed_seconds: result
+                        .elapsed
+                        .as_deref()
+                        .and_then(parse_timespan)
+                        .map(|s| s.min(i32::MAX as i64) as i32),
+                    author_email: result
+                        .created_by
+                        .and_then(|id| self.emails.get(&id).cloned()),
+                    recorded_at: timestamp(result.created_on),
+                    steps: result
+                        .custom_step_results
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(|step| {
+                            let status = step.status_id.and_then(|id| self.status(id))?;
+                            Some((status, text(step.actual.as_ref())))
+                        })
+                        .collect(),
+                });
+                Some(SourceTest {
+                    case_id: case_id.to_string(),
+                    result,
+                })
+            })
+            .collect())
+    }
+
+    fn status(&self, id: i64) -> Option<&'static str> {
+        self.statuses.get(&id).copied().unwrap_or(Some("na"))
+    }
+}
@@ -0,0 +1,495 @@
+use std::time::Duration;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::Sha256;
+use sqlx::Row;
+use tracing::warn;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::{
+    authz::{self, AuthUser, ProjectRole},
+    ensure_db_user_exists,
+    error::ApiError,
+    jobs::{self, JobContext, JobKind},
+    pagination, parse_uuid,
+    permissions::Capability,
+    secrets, AppState,
+};
+
+pub const EVENTS: [&str; 5] = [
+    "run.created",
+    "run.done",
+    "result.updated",
+    "result.failed",
+    "member.added",
+];
+
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// HTTP client of the `webhook_delivery` jobs.
+pub struct WebhookDispatcher {
+    client: reqwest::Client,
+}
+
+impl WebhookDispatcher {
+    pub fn new() -> anyhow::Result<Self> {
+        Ok(Self {
+            client: reqwest::Client::builder()
+                .timeout(DELIVERY_TIMEOUT)
+                .build()?,
+        })
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateWebhookRequest {
+    url: String,
+    events: Vec<String>,
+    secret: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookView {
+    id: String,
+    project_id: String,
+    url: String,
+    events: Vec<String>,
+    is_active: bool,
+    created_at: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct CreateWebhookResponse {
+    webhook: WebhookView,
+    /// Returned only once; used by receivers to verify `X-Uran-Signature`.
+    secret: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ListWebhooksResponse {
+    webhooks: Vec<WebhookView>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct DeleteWebhookResponse {
+    ok: bool,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListDeliveriesQuery {
+    limit: Option<i64>,
+    cursor: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DeliveryView {
+    id: String,
+    event: String,
+    status: String,
+    attempts: i32,
+    next_attempt_at: String,
+    last_status_code: Option<i32>,
+    last_error: Option<String>,
+    delivered_at: Option<String>,
+    payload: Value,
+    created_at: String,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ListDeliveriesResponse {
+    deliveries: Vec<DeliveryView>,
+    next_cursor: Option<String>,
+}
+
+const WEBHOOK_COLUMNS: &str = r#"
+  id::text AS id,
+  project_id::text AS project_id,
+  url,
+  events,
+  is_active,
+  created_at::text AS created_at
+"#;
+
+fn map_webhook_row(r: &sqlx::postgres::PgRow) -> WebhookView {
+    WebhookView {
+        id: r.get::<String, _>("id"),
+        project_id: r.get::<String, _>("project_id"),
+        url: r.get::<String, _>("url"),
+        events: r.get::<Vec<String>, _>("events"),
+        is_active: r.get::<bool, _>("is_active"),
+        created_at: r.get::<String, _>("created_at"),
+    }
+}
+
+/// Queues `event` for every active webhook of the project subscribed to it and appends it to
+/// the project activity feed (`live::project_events`). Errors are only logged: a broken
+/// webhook must never fail the request that produced the event.
+pub async fn emit(state: &AppState, project_id: Uuid, event: &str, data: Value) {
+    let payload = json!({
+        "event": event,
+        "projectId": project_id,
+        "occurredAt": chrono::Utc::now().to_rfc3339(),
+        "data": data,
+    });
+    // Every delivery gets its own job, so one slow endpoint does not hold up the others.
+    let queued = sqlx::query(
+        r#"
+        WITH logged AS (
+          INSERT INTO project_events (project_id, event, payload)
+          VALUES ($1, $2, $3)
+        ),
+        queued AS (
+          INSERT INTO webhook_deliveries (webhook_id, event, payload)
+          SELECT id, $2, $3
+          FROM webhooks
+          WHERE project_id = $1 AND is_active = TRUE AND $2 = ANY(events)
+          RETURNING id
+        )
+        INSERT INTO jobs (kind, project_id, payload, max_attempts)
+        SELECT $4, $1, jsonb_build_object('deliveryId', id), $5
+        FROM queued
+        "#,
+    )
+    .bind(project_id)
+    .bind(event)
+    .bind(payload)
+    .bind(JobKind::WebhookDelivery.as_str())
+    .bind(JobKind::WebhookDelivery.max_attempts())
+    .execute(&state.db)
+    .await;
+    match queued {
+        Ok(result) if result.rows_affected() > 0 => state.jobs.wake(),
+        Ok(_) => {}
+        Err(err) => warn!("failed to queue webhook event {event}: {err}"),
+    }
+}
+
+fn sign(secret: &str, body: &[u8]) -> anyhow::Result<String> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())?;
+    mac.update(body);
+    Ok(format!(
+        "sha256={}",
+        hex::encode(mac.finalize().into_bytes())
+    ))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DeliveryJob {
+    delivery_id: Uuid,
+}
+
+/// Job handler of [`emit`]: one attempt of one delivery. The delivery row mirrors the job
+/// for `GET /api/v2/webhooks/{webhook_id}/deliveries`; a failed attempt fails the job, which
+/// is retried with backoff.
+pub async fn run_delivery_job(
+    state: &AppState,
+    ctx: &JobContext,
+    payload: Value,
+) -> anyhow::Result<Value> {
+    let job: DeliveryJob = serde_json::from_value(payload)?;
+    let Some(row) = sqlx::query(
+        r#"
+        SELECT d.event, d.payload, w.url, w.secret, w.secret_encrypted
+        FROM webhook_deliveries d
+        JOIN webhooks w ON w.id = d.webhook_id
+        WHERE d.id = $1 AND d.status = 'pending'
+        "#,
+    )
+    .bind(job.delivery_id)
+    .fetch_optional(&state.db)
+    .await?
+    else {
+        // The webhook was deleted or the delivery already finished.
+        return Ok(Value::Null);
+    };
+
+    let secret = secrets::reveal(
+        state,
+        row.get("secret"),
+        row.get::<Option<&[u8]>, _>("secret_encrypted"),
+    )
+    .map_err(|_| anyhow::anyhow!("SECRETS_KEY is not set"))?
+    .ok_or_else(|| anyhow::anyhow!("the webhook secret cannot be decrypted"))?;
+    let event = row.get::<String, _>("event");
+    let body = row.get::<Value, _>("payload").to_string();
+    // A payload that cannot be signed never will be: the delivery fails without retries.
+    let signature = sign(&secret, body.as_bytes());
+    let (status_code, error) = match &signature {
+        Err(err) => (None, Some(format!("the payload cannot be signed: {err}"))),
+        Ok(signature) => {
+            let response = state
+                .webhooks
+                .client
+                .post(row.get::<String, _>("url"))
+                .header("content-type", "application/json")
+                .header("x-uran-event", &event)
+                .header("x-uran-delivery", job.delivery_id.to_string())
+                .header("x-uran-signature", signature.as_str())
+                .body(body)
+                .send()
+                .await;
+            match response {
+                Ok(res) if res.status().is_success() => (Some(res.status().as_u16() as i32), None),
+                Ok(res) => (
+                    Some(res.status().as_u16() as i32),
+                    Some(format!("HTTP {}", res.status())),
+                ),
+                Err(err) => (None, Some(err.to_string())),
+            }
+        }
+    };
+
+    let next_status = match (&error, signature.is_err() || ctx.is_last_attempt()) {
+        (None, _) => "delivered",
+        (Some(_), true) => "failed",
+        (Some(_), false) => "pending",
+    };
+    sqlx::query(
+        r#"
+        UPDATE webhook_deliveries
+        SET status = $2,
+            attempts = $3,
+            last_status_code = $4,
+            last_error = $5,
+            delivered_at = CASE WHEN $2 = 'delivered' THEN NOW() ELSE NULL END,
+            next_attempt_at = NOW() + make_interval(secs => $6)
+        WHERE id = $1
+        "#,
+    )
+    .bind(job.delivery_id)
+    .bind(next_status)
+    .bind(ctx.attempt)
+    .bind(status_code)
+    .bind(&error)
+    .bind(jobs::retry_delay_secs(ctx.attempt) as f64)
+    .execute(&state.db)
+    .await?;
+    match error {
+        Some(error) => anyhow::bail!("{event}: {error}"),
+        None => Ok(Value::Null),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v2/projects/{project_id}/webhooks",
+    tag = "webhooks",
+    params(("project_id" = String, Path)),
+    request_body = CreateWebhookRequest,
+    responses((status = 201, body = CreateWebhookResponse))
+)]
+pub async fn create_webhook(
+    State(state): State<AppState>,
+    access: ProjectRole,
+    Json(payload): Json<CreateWebhookRequest>,
+) -> Result<(StatusCode, Json<CreateWebhookResponse>), ApiError> {
+    access.require(Capability::ProjectManage)?;
+    let ProjectRole {
+        user_id: actor_id,
+        project_id: project_uuid,
+        ..
+    } = access;
+    let url = payload.url.trim().to_string();
+    if !(url.starts_with("https://") || url.starts_with("http://")) {
+        return Err(ApiError::InvalidWebhookUrl);
+    }
+    let mut events: Vec<String> = Vec::new();
+    for event in payload.events.iter().map(|e| e.trim()) {
+        if !EVENTS.contains(&event) {
+            return Err(ApiError::InvalidWebhookEvent);
+        }
+        if !events.iter().any(|e| e == event) {
+            events.push(event.to_string());
+        }
+    }
+    if events.is_empty() {
+        return Err(ApiError::WebhookEventsEmpty);
+    }
+    let secret = match payload.secret.as_deref().map(str::trim) {
+        Some(s) if s.chars().count() >= 16 => s.to_string(),
+        Some(s) if !s.is_empty() => return Err(ApiError::WebhookSecretTooShort),
+        _ => format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple()),
+    };
+    let sealed_secret = secrets::require(&state)?.seal(&secret)?;
+    ensure_db_user_exists(&state, &actor_id).await?;
+    let actor_uuid = parse_uuid(&actor_id, ApiError::InvalidUserId)?;
+
+    let sql = format!(
+        r#"
+        INSERT INTO webhooks (project_id, url, secret_encrypted, events, created_by_user_id)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING {WEBHOOK_COLUMNS}
+        "#
+    );
+    let row = sqlx::query(&sql)
+        .bind(project_uuid)
+        .bind(&url)
+        .bind(&sealed_secret)
+        .bind(&events)
+        .bind(actor_uuid)
+        .fetch_one(&state.db)
+        .await
+        .map_err(|_| ApiError::WebhookRejected)?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(CreateWebhookResponse {
+            webhook: map_webhook_row(&row),
+            secret,
+        }),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v2/projects/{project_id}/webhooks",
+    tag = "webhooks",
+    params(("project_id" = String, Path)),
+    responses((status = 200, body = ListWebhooksResponse))
+)]
+pub async fn list_webhooks(
+    State(state): State<AppState>,
+    access: ProjectRole,
+) -> Result<Json<ListWebhooksResponse>, ApiError> {
+    access.require(Capability::ProjectManage)?;
+    let project_uuid = access.project_id;
+    let sql = format!(
+        "SELECT {WEBHOOK_COLUMNS} FROM webhooks WHERE project_id = $1 ORDER BY created_at ASC"
+    );
+    let rows = sqlx::query(&sql)
+        .bind(project_uuid)
+        .fetch_all(&state.db)
+        .await
+        .map_err(|_| ApiError::WebhooksReadFailed)?;
+
+    Ok(Json(ListWebhooksResponse {
+        webhooks: rows.iter().map(map_webhook_row).collect(),
+    }))
+}
+
+/// Owner check for endpoints addressed by webhook id.
+async fn require_webhook_owner(
+    state: &AppState,
+    webhook_id: Uuid,
+    actor_id: &str,
+) -> Result<(), ApiError> {
+    let project_id: Option<String> =
+        sqlx::query_scalar(r#"SELECT project_id::text FROM webhooks WHERE id = $1"#)
+            .bind(webhook_id)
+            .fetch_optional(&state.db)
+            .await
+            .map_err(|_| ApiError::WebhookReadFailed)?;
+    let project_id = project_id.ok_or(ApiError::WebhookNotFound)?;
+    authz::require_capability(state, &project_id, actor_id, Capability::ProjectManage).await?;
+    Ok(())
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v2/webhooks/{webhook_id}",
+    tag = "webhooks",
+    params(("webhook_id" = String, Path)),
+    responses((status = 200, body = DeleteWebhookResponse))
+)]
+pub async fn delete_webhook(
+    State(state): State<AppState>,
+    Path(webhook_id): Path<String>,
+    AuthUser(actor_id): AuthUser,
+) -> Result<Json<DeleteWebhookResponse>, ApiError> {
+    let webhook_uuid = parse_uuid(&webhook_id, ApiError::InvalidWebhookId)?;
+    require_webhook_owner(&state, webhook_uuid, &actor_id).await?;
+
+    sqlx::query(r#"DELETE FROM webhooks WHERE id = $1"#)
+        .bind(webhook_uuid)
+        .execute(&state.db)
+        .await
+        .map_err(|_| ApiError::WebhookDeleteFailed)?;
+
+    Ok(Json(DeleteWebhookResponse { ok: true }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v2/webhooks/{webhook_id}/deliveries",
+    tag = "webhooks",
+    params(("webhook_id" = String, Path), ListDeliveriesQuery),
+    responses((status = 200, body = ListDeliveriesResponse))
+)]
+pub async fn list_deliveries(
+    State(state): State<AppState>,
+    Path(webhook_id): Path<String>,
+    AuthUser(actor_id): AuthUser,
+    Query(query): Query<ListDeliveriesQuery>,
+) -> Result<Json<ListDeliveriesResponse>, ApiError> {
+    let webhook_uuid = parse_uuid(&webhook_id, ApiError::InvalidWebhookId)?;
+    let limit = pagination::page_limit(query.limit);
+    let cursor = pagination::parse_cursor(query.cursor.as_deref())?;
+    require_webhook_owner(&state, webhook_uuid, &actor_id).await?;
+
+    let rows = sqlx::query(
+        r#"
+        SELECT
+          id::text AS id,
+          event,
+          status,
+          attempts,
+          next_attempt_at::text AS next_attempt_at,
+          last_status_code,
+          last_error,
+          delivered_at::text AS delivered_at,
+          payload,
+          created_at::text AS created_at
+        FROM webhook_deliveries
+        WHERE webhook_id = $1
+          AND ($2::timestamptz IS NULL OR (created_at, id) < ($2::timestamptz, $3::uuid))
+        ORDER BY created_at DESC, id DESC
+        LIMIT $4
+        "#,
+    )
+    .bind(webhook_uuid)
+    .bind(cursor.as_ref().map(|c| c.created_at.clone()))
+    .bind(cursor.as_ref().map(|c| c.id.clone()))
+    .bind(limit + 1)
+    .fetch_all(state.read_db())
+    .await
+    .map_err(|_| ApiError::DeliveriesReadFailed)?;
+
+    let deliveries: Vec<DeliveryView> = rows
+        .into_iter()
+        .map(|r| DeliveryView {
+            id: r.get::<String, _>("id"),
+            event: r.get::<String, _>("event"),
+            status: r.get::<String, _>("status"),
+            attempts: r.get::<i32, _>("attempts"),
+            next_attempt_at: r.get::<String, _>("next_attempt_at"),
+            last_status_code: r.get::<Option<i32>, _>("last_status_code"),
+            last_error: r.get::<Option<String>, _>("last_error"),
+            delivered_at: r.get::<Option<String>, _>("delivered_at"),
+            payload: r.get::<Value, _>("payload"),
+            created_at: r.get::<String, _>("created_at"),
+        })
+        .collect();
+    let (deliveries, next_cursor) =
+        pagination::finish_page(deliveries, limit, |d| pagination::Cursor {
+            created_at: d.created_at.clone(),
+            id: d.id.clone(),
+        });
+
+    Ok(Json(ListDeliveriesResponse {
+        deliveries,
+        next_cursor,
+    }))
+}
@@ -0,0 +1,473 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use anyhow::{bail, Context};
+use chrono::{DateTime, Utc};
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::{json, Value};
+
+use crate::migration_import::{
+    SourceCase, SourceProject, SourceResult, SourceRun, SourceSuite, SourceTest,
+};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+/// The largest page the GraphQL API serves.
+const PAGE_SIZE: usize = 100;
+
+const TESTS_QUERY: &str = r#"
+query($jql: String, $limit: Int!, $start: Int) {
+  getTests(jql: $jql, limit: $limit, start: $start) {
+    total
+    results {
+      issueId
+      folder { path }
+      steps { action data result }
+      gherkin
+      unstructured
+      preconditions(limit: 10) { results { definition } }
+      jira(fields: ["key", "summary"])
+    }
+  }
+}"#;
+
+const EXECUTIONS_QUERY: &str = r#"
+query($jql: String, $limit: Int!, $start: Int) {
+  getTestExecutions(jql: $jql, limit: $limit, start: $start) {
+    total
+    results {
+      issueId
+      jira(fields: ["key", "summary", "created", "updated", "resolutiondate", "status", "fixVersions"])
+    }
+  }
+}"#;
+
+const TEST_RUNS_QUERY: &str = r#"
+query($ids: [String], $limit: Int!, $start: Int) {
+  getTestRuns(testExecIssueIds: $ids, limit: $limit, start: $start) {
+    total
+    results {
+      test { issueId }
+      status { name }
+      comment
+      startedOn
+      finishedOn
+      steps { status { name } comment actualResult }
+    }
+  }
+}"#;
+
+#[derive(Deserialize)]
+struct Page<T> {
+    total: usize,
+    results: Vec<T>,
+}
+
+#[derive(Deserialize)]
+struct Folder {
+    path: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Step {
+    action: Option<String>,
+    data: Option<String>,
+    result: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Precondition {
+    definition: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct XrayTest {
+    issue_id: String,
+    folder: Option<Folder>,
+    #[serde(default)]
+    steps: Vec<Step>,
+    gherkin: Option<String>,
+    unstructured: Option<String>,
+    preconditions: Option<Page<Precondition>>,
+    #[serde(default)]
+    jira: Value,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TestExecution {
+    issue_id: String,
+    #[serde(default)]
+    jira: Value,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TestRef {
+    issue_id: String,
+}
+
+#[derive(Deserialize)]
+struct Status {
+    name: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StepResult {
+    status: Option<Status>,
+    comment: Option<String>,
+    actual_result: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TestRun {
+    test: Option<TestRef>,
+    status: Option<Status>,
+    comment: Option<String>,
+    started_on: Option<String>,
+    finished_on: Option<String>,
+    #[serde(default)]
+    steps: Vec<StepResult>,
+}
+
+/// Result status of an Xray status; `TODO` and `EXECUTING` have none, custom ones are `na`.
+fn map_status(status: Option<&Status>) -> Option<&'static str> {
+    match status.and_then(|s| s.name.as_deref()).unwrap_or("TODO") {
+        "PASSED" => Some("ok"),
+        "FAILED" => Some("fail"),
+        "ABORTED" | "BLOCKED" => Some("blocked"),
+        "TODO" | "EXECUTING" => None,
+        _ => Some("na"),
+    }
+}
+
+/// Xray timestamps are RFC 3339, Jira's lack the colon in the offset.
+fn parse_time(value: Option<&str>) -> Option<DateTime<Utc>> {
+    let value = value?.trim();
+    DateTime::parse_from_rfc3339(value)
+        .or_else(|_| DateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f%z"))
+        .ok()
+        .map(|t| t.with_timezone(&Utc))
+}
+
+fn jira_str<'a>(jira: &'a Value, field: &str) -> &'a str {
+    jira.get(field)
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .trim()
+}
+
+fn text(value: Option<&String>) -> String {
+    value.map(|v| v.trim().to_string()).unwrap_or_default()
+}
+
+/// Source id of a test repository folder; folder paths repeat across projects.
+fn folder_id(project: &SourceProject, path: &str) -> String {
+    format!("{}:{path}", project.id)
+}
+
+/// Folder path of a test without empty segments, `/` for the root.
+fn folder_path(test: &XrayTest) -> String {
+    let path = test
+        .folder
+        .as_ref()
+        .and_then(|f| f.path.as_deref())
+        .unwrap_or("/");
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.trim().is_empty()).collect();
+    format!("/{}", segments.join("/"))
+}
+
+/// Xray Cloud GraphQL API, authenticated with an API key's client id and secret.
+pub struct Client {
+    http: reqwest::Client,
+    base_url: String,
+    token: String,
+    /// Tests by project; both the suites and the cases are made of them.
+    tests: Mutex<HashMap<String, Arc<Vec<XrayTest>>>>,
+}
+
+impl Client {
+    pub async fn connect(url: &str, token: &str) -> anyhow::Result<Self> {
+        let Some((client_id, client_secret)) = token.split_once(':') else {
+            bail!("the Xray token is `client_id:client_secret`");
+        };
+        let http = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()?;
+        let response = http
+            .post(format!("{url}/api/v2/authenticate"))
+            .json(&json!({ "client_id": client_id, "client_secret": client_secret }))
+            .send()
+            .await
+            .context("Xray authentication")?;
+        if !response.status().is_success() {
+            bail!("Xray authentication: {}", response.status());
+        }
+        let token: String = response
+            .json()
+            .await
+            .context("Xray authentication: unexpected response")?;
+        Ok(Self {
+            http,
+            base_url: url.to_string(),
+            token,
+            tests: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Every page of a paged query whose result is `data.{field}`.
+    async fn list<T: DeserializeOwned>(
+        &self,
+        query: &str,
+        field: &str,
+        mut variables: Value,
+    ) -> anyhow::Result<Vec<T>> {
+        let mut items = Vec::new();
+        loop {
+            variables["limit"] = json!(PAGE_SIZE);
+            variables["start"] = json!(items.len());
+            let response = self
+                .http
+                .post(format!("{}/api/v2/graphql", self.base_url))
+                .bearer_auth(&self.token)
+                .json(&json!({ "query": query, "variables": &variables }))
+                .send()
+                .await
+                .with_context(|| format!("Xray {field}"))?;
+            if !response.status().is_success() {
+                bail!("Xray {field}: {}", response.status());
+            }
+            let mut body: Value = response
+                .json()
+                .await
+                .with_context(|| format!("Xray {field}: unexpected response"))?;
+            if let Some(error) = body
+                .get("errors")
+                .and_then(|errors| errors.get(0))
+                .and_then(|error| error.get("message"))
+            {
+                bail!("Xray {field}: {error}");
+            }
+            let page: Page<T> = serde_json::from_value(body["data"][field].take())
+                .with_context(|| format!("Xray {field}: unexpected response"))?;
+            let received = page.results.len();
+            items.extend(page.results);
+            if received == 0 || items.len() >= page.total {
+                return Ok(items);
+            }
+        }
+    }
+
+    async fn project_tests(&self, project: &SourceProject) -> anyhow::Result<Arc<Vec<XrayTest>>> {
+        if let Some(tests) = self
+            .tests
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&project.id)
+        {
+            return Ok(tests.clone());
+        }
+        let tests: Vec<XrayTest> = self
+            .list(
+                TESTS_QUERY,
+                "getTests",
+                json!({ "jql": format!("project = \"{}\"", project.id) }),
+            )
+            .await?;
+        let tests = Arc::new(tests);
+        self.tests
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(project.id.clone(), tests.clone());
+        Ok(tests)
+    }
+
+    /// Xray cannot list Jira projects; each one is given by key and named after it.
+    pub async fn projects(&self, selected: &[String]) -> anyhow::Result<Vec<SourceProject>> {
+        if selected.is_empty() {
+            bail!("pass the Jira project keys to import with --project");
+        }
+        Ok(selected
+            .iter()
+            .map(|key| SourceProject {
+                id: key.to_uppercase(),
+                name: key.to_uppercase(),
+            })
+            .collect())
+    }
+
+    /// The test repository: a suite named after the project for its root folder and one
+    /// per folder below it.
+    pub async fn suites(&self, project: &SourceProject) -> anyhow::Result<Vec<SourceSuite>> {
+        let mut paths = BTreeSet::new();
+        for test in self.project_tests(project).await?.iter() {
+            let mut prefix = String::new();
+            for segment in folder_path(test).split('/').filter(|s| !s.is_empty()) {
+                prefix = format!("{prefix}/{segment}");
+                paths.insert(prefix.clone());
+            }
+        }
+        let mut suites = vec![SourceSuite {
+            id: folder_id(project, "/"),
+            parent_id: None,
+            name: project.name.clone(),
+            description: String::new(),
+        }];
+        // Sorted, so a folder comes after its parent.
+        for path in paths {
+            let (parent, name) = path.rsplit_once('/').unwrap_or(("", &path));
+            suites.push(SourceSuite {
+                id: folder_id(project, &path),
+                parent_id: Some(folder_id(
+                    project,
+                    if parent.is_empty() { "/" } else { parent },
+                )),
+                name: name.to_string(),
+                description: String::new(),
+            });
+        }
+        Ok(suites)
+    }
+
+    /// Manual tests keep their steps, with the step data after the action; generic and
+    /// Cucumber tests become one step of their definition.
+    pub async fn cases(&self, project: &SourceProject) -> anyhow::Result<Vec<SourceCase>> {
+        let tests = self.project_tests(project).await?;
+        Ok(tests
+            .iter()
+            .map(|test| {
+                let (mut steps, mut expected): (Vec<String>, Vec<String>) = test
+                    .steps
+                    .iter()
+                    .map(|step| {
+                        let action = text(step.action.as_ref());
+                        let data = text(step.data.as_ref());
+                        let action = if data.is_empty() {
+                            action
+                        } else {
+                            format!("{action}\n{data}")
+                        };
+                        (action, text(step.result.as_ref()))
+                    })
+                    .unzip();
+                if steps.is_empty() {
+                    let definition = text(test.unstructured.as_ref().or(test.gherkin.as_ref()));
+                    if !definition.is_empty() {
+                        steps.push(definition);
+                        expected.push(String::new());
+                    }
+                }
+                let preconditions: Vec<String> = test
+                    .preconditions
+                    .iter()
+                    .flat_map(|page| &page.results)
+                    .map(|p| text(p.definition.as_ref()))
+                    .filter(|p| !p.is_empty())
+                    .collect();
+                let key = jira_str(&test.jira, "key");
+                SourceCase {
+                    id: test.issue_id.clone(),
+                    suite_id: folder_id(project, &folder_path(test)),
+                    key: if key.is_empty() {
+                        test.issue_id.clone()
+                    } else {
+                        key.to_string()
+                    },
+                    title: jira_str(&test.jira, "summary").to_string(),
+                    preconditions: preconditions.join("\n\n"),
+                    steps,
+                    expected,
+                    estimated_minutes: None,
+                }
+            })
+            .collect())
+    }
+
+    /// Test executions; one in a done status category counts as closed, and its first fix
+    /// version is the milestone.
+    pub async fn runs(&self, project: &SourceProject) -> anyhow::Result<Vec<SourceRun>> {
+        let executions: Vec<TestExecution> = self
+            .list(
+                EXECUTIONS_QUERY,
+                "getTestExecutions",
+                json!({ "jql": format!("project = \"{}\"", project.id) }),
+            )
+            .await?;
+        let mut runs: Vec<SourceRun> = executions
+            .into_iter()
+            .map(|execution| {
+                let jira = &execution.jira;
+                let key = jira_str(jira, "key");
+                let done = jira["status"]["statusCategory"]["key"].as_str() == Some("done");
+                SourceRun {
+                    title: format!("{key} {}", jira_str(jira, "summary"))
+                        .trim()
+                        .to_string(),
+                    milestone: jira["fixVersions"][0]["name"].as_str().map(str::to_string),
+                    author_email: None,
+                    created_at: parse_time(jira["created"].as_str()),
+                    completed_at: if done {
+                        parse_time(jira["resolutiondate"].as_str())
+                            .or_else(|| parse_time(jira["updated"].as_str()))
+                    } else {
+                        None
+                    },
+                    id: execution.issue_id,
+                }
+            })
+            .collect();
+        runs.sort_by_key(|run| run.created_at);
+        Ok(runs)
+    }
+
+    /// Xray keeps no result history here: a test run's status is its result. Executors are
+    /// Jira accounts without an email, so results keep the owner.
+    pub async fn tests(&self, run: &SourceRun) -> anyhow::Result<Vec<SourceTest>> {
+        let test_runs: Vec<TestRun> = self
+            .list(TEST_RUNS_QUERY, "getTestRuns", json!({ "ids": [&run.id] }))
+            .await?;
+        Ok(test_runs
+            .into_iter()
+            .filter_map(|test_run| {
+                let case_id = test_run.test?.issue_id;
+                let result = map_status(test_run.status.as_ref()).map(|status| {
+                    let started = parse_time(test_run.started_on.as_deref());
+                    let finished = parse_time(test_run.finished_on.as_deref());
+                    SourceResult {
+                        status,
+                        comment: text(test_run.comment.as_ref()),
+                        elapsed_seconds: match (started, finished) {
+                            (Some(started), Some(finished)) if finished >= started => {
+                                Some((finished - started).num_seconds().min(i32::MAX as i64) as i32)
+                            }
+                            _ => None,
+                        },
+                        author_email: None,
+                        recorded_at: finished.or(started),
+                        steps: test_run
+                            .steps
+                            .iter()
+                            .map(|step| {
+                                let status = map_status(step.status.as_ref())?;
+                                let actual = text(step.actual_result.as_ref());
+                                let comment = text(step.comment.as_ref());
+                                let comment = match (actual.is_empty(), comment.is_empty()) {
+                                    (false, false) => format!("{actual}\n\n{comment}"),
+                                    (false, true) => actual,
+                                    _ => comment,
+                                };
+                                Some((status, comment))
+                            })
+                            .collect(),
+                    }
+                });
+                Some(SourceTest { case_id, result })
+            })
+            .collect())
+    }
+}
@@ -38,6 +38,11 @@ interface ApiRunDetails {
   items: ApiRunItem[]
 }
 
+interface ApiError {
+  code: string
+  message: string
+}
+
 const theme = createTheme({
   palette: {
     mode: 'light',
@@ -112,8 +117,8 @@ export default function App() {
         },
       })
 
-      const payload = (await response.json()) as { runs?: ApiRun[]; error?: string }
-      if (!response.ok) throw new Error(payload.error || 'Не удалось загрузить runs.')
+      const payload = (await response.json()) as { runs?: ApiRun[]; error?: ApiError }
+      if (!response.ok) throw new Error(payload.error?.message || 'Не удалось загрузить runs.')
 
       const items = payload.runs ?? []
       setRuns(items)
@@ -139,8 +144,8 @@ export default function App() {
           Authorization: `Bearer ${token}`,
         },
       })
-      const payload = (await response.json()) as ApiRunDetails & { error?: string }
-      if (!response.ok) throw new Error(payload.error || 'Не удалось загрузить run details.')
+      const payload = (await response.json()) as ApiRunDetails & { error?: ApiError }
+      if (!response.ok) throw new Error(payload.error?.message || 'Не удалось загрузить run details.')
 
       const mapped = (payload.items || []).map<ChecklistItem>((item, idx) => ({
         id: item.id,